@@ -0,0 +1,48 @@
+// bot/src/accuracy.rs
+//! Simulation accuracy self-check.
+//!
+//! After each confirmed arb the predicted net profit (from simulation) is compared with the realized one
+//! (executor WETH gain across the arb's block minus the actual gas cost). Errors are kept as basis points
+//! of the prediction over a rolling window; a window mean beyond `config.profit_bias_warn_bps` means the
+//! fee/gas assumptions are systematically off and is logged as a warning.
+
+use crate::rolling_window::RollingWindow;
+use ethers::types::I256;
+
+/// Confirmed arbs averaged into the bias.
+pub const PROFIT_BIAS_WINDOW: usize = 20;
+/// Samples needed before a bias is reported, so a single outlier does not warn.
+pub const PROFIT_BIAS_MIN_SAMPLES: usize = 5;
+
+/// `(predicted - realized) / predicted` in basis points; positive when simulation overestimated.
+/// None when nothing positive was predicted (there is no meaningful ratio).
+pub fn prediction_error_bps(predicted_wei: I256, realized_wei: I256) -> Option<i64> {
+    if predicted_wei <= I256::zero() {
+        return None;
+    }
+    let error = predicted_wei.saturating_sub(realized_wei);
+    let bps = error.saturating_mul(I256::from(10_000)) / predicted_wei;
+    Some(i64::try_from(bps).unwrap_or(if bps.is_negative() { i64::MIN } else { i64::MAX }))
+}
+
+/// Rolling window of prediction errors (bps).
+#[derive(Debug, Default)]
+pub struct ProfitAccuracy {
+    errors_bps: RollingWindow<i64, PROFIT_BIAS_WINDOW>,
+}
+
+impl ProfitAccuracy {
+    /// Adds one sample and returns the window mean and sample count.
+    pub fn record(&self, error_bps: i64) -> (i64, usize) {
+        self.errors_bps.record_then(error_bps, |errors| {
+            let sum: i128 = errors.iter().map(|&e| e as i128).sum();
+            ((sum / errors.len() as i128) as i64, errors.len())
+        })
+    }
+
+    /// The window mean when it is based on enough samples and exceeds `threshold_bps` in either direction.
+    pub fn persistent_bias(mean_bps: i64, samples: usize, threshold_bps: u64) -> Option<i64> {
+        (threshold_bps > 0 && samples >= PROFIT_BIAS_MIN_SAMPLES && mean_bps.unsigned_abs() > threshold_bps).then_some(mean_bps)
+    }
+}
+// END OF FILE: bot/src/accuracy.rs
@@ -0,0 +1,163 @@
+// bot/src/admin.rs
+//! Line-based admin command interface for inspecting a running bot.
+//!
+//! Bound to `config.admin_addr` (e.g. `127.0.0.1:9090`). Each line received is one command and
+//! each reply is one line of JSON, so `nc 127.0.0.1 9090` is enough to use it (a line over
+//! `MAX_COMMAND_BYTES` closes the connection):
+//! * `pools` – monitored pools with DEX type, tokens and fee/stable flag
+//! * `snapshot <addr>` – hot-cache snapshot of one pool
+//! * `pause` / `resume` – trip or reset the admin pause (automatic pauses clear on their own)
+//! * `stats` – cache sizes, circuit breaker state and pause reasons, and metrics
+//! * `dump [path] [format]` – write the snapshot cache to a file (see `dump`; default `config.snapshot_dump_path`, csv)
+//!
+//! State is only read, except for the pause flag (and the dump file). There is no authentication: bind to loopback.
+
+use crate::dump::{dump_snapshots, SnapshotDumpFormat};
+use crate::state::{AppState, PauseReason};
+use ethers::types::Address;
+use eyre::{Result, WrapErr};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+use tracing::{debug, info, warn};
+
+/// Longest command line accepted. A longer line is answered with an error and the connection is closed,
+/// so a client that never sends a newline cannot grow the read buffer without bound.
+pub const MAX_COMMAND_BYTES: usize = 4096;
+
+/// Executes a single admin command and returns the JSON reply.
+pub fn handle_command(line: &str, app_state: &AppState) -> Value {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().unwrap_or_default().to_lowercase();
+    match command.as_str() {
+        "pools" => {
+            let pools: Vec<Value> = app_state.pool_states.iter().map(|entry| {
+                let ps = entry.value();
+                json!({
+                    "address": format!("{:?}", ps.pool_address),
+                    "dex": ps.dex_type.to_string(),
+                    "token0": format!("{:?}", ps.token0),
+                    "token1": format!("{:?}", ps.token1),
+                    "fee": ps.uni_fee,
+                    "stable": ps.velo_stable,
+                })
+            }).collect();
+            json!({ "ok": true, "count": pools.len(), "pools": pools })
+        }
+        "snapshot" => {
+            let addr = match parts.next().map(|a| a.parse::<Address>()) {
+                Some(Ok(a)) => a,
+                Some(Err(e)) => return error_reply(format!("Invalid address: {}", e)),
+                None => return error_reply("Usage: snapshot <pool address>"),
+            };
+            match app_state.pool_snapshots.get(&addr) {
+                Some(sn) => json!({
+                    "ok": true,
+                    "address": format!("{:?}", sn.pool_address),
+                    "dex": sn.dex_type.to_string(),
+                    "token0": format!("{:?}", sn.token0),
+                    "token1": format!("{:?}", sn.token1),
+                    "reserve0": sn.reserve0.map(|v| v.to_string()),
+                    "reserve1": sn.reserve1.map(|v| v.to_string()),
+                    "sqrt_price_x96": sn.sqrt_price_x96.map(|v| v.to_string()),
+                    "tick": sn.tick,
+                    "liquidity": sn.liquidity.map(|v| v.to_string()),
+                    "last_update_block": sn.last_update_block.map(|b| b.as_u64()),
+                    "last_update_timestamp": sn.last_update_timestamp,
+                }),
+                None => error_reply(format!("No snapshot for {:?}", addr)),
+            }
+        }
+        "pause" => {
+            app_state.set_paused(PauseReason::Admin, true);
+            json!({ "ok": true, "paused": true })
+        }
+        "resume" => {
+            // Only the admin pause is lifted; automatic pauses clear when their condition does
+            app_state.set_paused(PauseReason::Admin, false);
+            json!({ "ok": true, "paused": app_state.is_paused(), "pause_reasons": pause_reasons(app_state) })
+        }
+        "stats" => {
+            let metrics: serde_json::Map<String, Value> = app_state.metrics.values().into_iter()
+                .map(|(name, value)| (name.to_string(), json!(value)))
+                .collect();
+            json!({
+                "ok": true,
+                "paused": app_state.is_paused(),
+                "pause_reasons": pause_reasons(app_state),
+                "pool_states": app_state.pool_states.len(),
+                "pool_snapshots": app_state.pool_snapshots.len(),
+                "latest_block": app_state.block_timestamps.iter().map(|e| e.key().as_u64()).max(),
+                "fee_on_transfer_tokens": app_state.fee_on_transfer_tokens.iter().filter(|e| *e.value()).count(),
+                "metrics": metrics,
+            })
+        }
+        "dump" => {
+            let path = match parts.next().map(str::to_string).or_else(|| app_state.config.snapshot_dump_path.clone()) {
+                Some(path) => path,
+                None => return error_reply("Usage: dump <path> [csv] (or set SNAPSHOT_DUMP_PATH)"),
+            };
+            let format = match parts.next().map(SnapshotDumpFormat::from_str).transpose() {
+                Ok(format) => format.unwrap_or_default(),
+                Err(e) => return error_reply(e.to_string()),
+            };
+            match dump_snapshots(app_state, &path, format) {
+                Ok(rows) => json!({ "ok": true, "path": path, "rows": rows }),
+                Err(e) => error_reply(format!("{:#}", e)),
+            }
+        }
+        "" => error_reply("Empty command"),
+        other => error_reply(format!("Unknown command '{}'. Commands: pools, snapshot <addr>, pause, resume, stats, dump [path] [format]", other)),
+    }
+}
+
+fn pause_reasons(app_state: &AppState) -> Vec<String> {
+    app_state.pause_reasons().into_iter().map(|reason| format!("{:?}", reason)).collect()
+}
+
+fn error_reply(message: impl Into<String>) -> Value {
+    json!({ "ok": false, "error": message.into() })
+}
+
+/// Serves admin commands on `addr` until the process exits. Each connection runs in its own task.
+pub async fn run_admin_server(addr: &str, app_state: Arc<AppState>) -> Result<()> {
+    let listener = TcpListener::bind(addr).await.wrap_err_with(|| format!("Failed to bind admin interface on {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    if !local_addr.ip().is_loopback() {
+        warn!(%local_addr, "Admin interface is not bound to loopback and has no authentication.");
+    }
+    info!(%local_addr, "🛠️ Admin interface listening.");
+    loop {
+        let (stream, peer) = listener.accept().await.wrap_err("Admin accept failed")?;
+        let state = app_state.clone();
+        tokio::spawn(async move {
+            debug!(%peer, "Admin connection opened.");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(MAX_COMMAND_BYTES));
+            while let Some(line) = lines.next().await {
+                // The codec ends the stream after an error, so an oversized line gets its reply and closes the connection
+                let reply = match line {
+                    Ok(line) => {
+                        info!(%peer, command = %line.trim(), "Admin command received.");
+                        handle_command(&line, &state)
+                    }
+                    Err(LinesCodecError::MaxLineLengthExceeded) => {
+                        warn!(%peer, max_bytes = MAX_COMMAND_BYTES, "Oversized admin command rejected.");
+                        error_reply(format!("Command longer than {} bytes", MAX_COMMAND_BYTES))
+                    }
+                    Err(LinesCodecError::Io(_)) => break,
+                };
+                let mut reply = reply.to_string();
+                reply.push('\n');
+                if writer.write_all(reply.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+            debug!(%peer, "Admin connection closed.");
+        });
+    }
+}
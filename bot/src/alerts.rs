@@ -0,0 +1,69 @@
+// bot/src/alerts.rs
+//! Fire-and-forget operator alerts via a Telegram bot or Discord webhook.
+//!
+//! The destination is taken from `config.alert_webhook_url`:
+//! * Discord: `https://discord.com/api/webhooks/<id>/<token>`
+//! * Telegram: `https://api.telegram.org/bot<token>/sendMessage?chat_id=<chat_id>`
+//!
+//! Alerts never block the caller and never fail it; delivery problems are only logged.
+
+use crate::config::Config;
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+const ALERT_HTTP_TIMEOUT_SECS: u64 = 10;
+
+lazy_static! {
+    // One client (and connection pool) shared by all alerts
+    static ref ALERT_HTTP: reqwest::Client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(ALERT_HTTP_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_default();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertLevel {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AlertLevel {
+    fn prefix(&self) -> &'static str {
+        match self {
+            AlertLevel::Info => "ℹ️ INFO",
+            AlertLevel::Warning => "⚠️ WARNING",
+            AlertLevel::Critical => "🚨 CRITICAL",
+        }
+    }
+}
+
+/// Sends an alert to the configured webhook in a background task.
+/// Returns the task handle so callers that are about to exit (e.g. shutdown paths)
+/// can give delivery a moment to complete. Returns `None` when no webhook is configured.
+pub fn send_alert(config: &Config, level: AlertLevel, message: impl Into<String>) -> Option<JoinHandle<()>> {
+    let url = match config.alert_webhook_url.as_deref() {
+        Some(u) if !u.is_empty() => u.to_string(),
+        _ => return None,
+    };
+    let text = format!("[ULP 1.5] {}: {}", level.prefix(), message.into());
+    Some(tokio::spawn(async move {
+        if let Err(e) = post_alert(&url, &text).await {
+            warn!(error = ?e, "Failed to deliver alert to webhook (alert dropped).");
+        }
+    }))
+}
+
+async fn post_alert(url: &str, text: &str) -> eyre::Result<()> {
+    // Discord expects `content`, Telegram's sendMessage expects `text` (chat_id is carried in the URL).
+    let body = if url.contains("api.telegram.org") { json!({ "text": text }) } else { json!({ "content": text }) };
+    let response = ALERT_HTTP.post(url).json(&body).send().await?;
+    if !response.status().is_success() {
+        return Err(eyre::eyre!("Webhook responded with status {}", response.status()));
+    }
+    debug!("Alert delivered.");
+    Ok(())
+}
@@ -0,0 +1,175 @@
+// bot/src/audit.rs
+//! Append-only opportunity audit trail for post-hoc PnL accounting.
+//!
+//! When `config.audit_log_path` is set every decision point (route detected, simulated unprofitable,
+//! submitted, confirmed, reverted, failed, profit shared, WETH unwrapped) appends one JSON object per line to that file. Records are
+//! handed to a background writer over a channel, so the hot path never waits on disk I/O. Without a
+//! path the log is disabled and `record` is a no-op. Confirmed records also carry the realized net profit
+//! and the simulation error against the predicted one. With `config.log_failed_calldata` failed and
+//! reverted submissions also carry the exact transaction (`FailedSubmission`) so they can be replayed.
+//! Confirmed records list each leg's quoted and realized output (`LegSlippageRecord`) when the receipt has them.
+
+use crate::path_optimizer::RouteCandidate;
+use crate::slippage::LegSlippage;
+use ethers::types::{Address, Bytes, TxHash, I256, U256};
+use eyre::{Result, WrapErr};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Detected,
+    Unprofitable,
+    SimulationFailed,
+    Expired,
+    Submitted,
+    SubmissionFailed,
+    Confirmed,
+    Reverted,
+    ProfitShared,
+    Unwrapped,
+}
+
+/// One audit line. Amounts are decimal wei strings so they survive JSON number precision.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    pub event: AuditEvent,
+    pub route_id: String,
+    pub buy_pool: Address,
+    pub sell_pool: Address,
+    pub loan_amount_wei: Option<String>,
+    pub net_profit_wei: Option<String>,
+    pub realized_profit_wei: Option<String>,
+    pub profit_error_wei: Option<String>,
+    pub gas_limit: Option<String>,
+    pub gas_cost_wei: Option<String>,
+    pub tx_hash: Option<TxHash>,
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_submission: Option<FailedSubmission>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub leg_slippage: Vec<LegSlippageRecord>,
+}
+
+/// Quoted vs realized output of one swap leg of a confirmed arb (see `slippage`).
+#[derive(Debug, Clone, Serialize)]
+pub struct LegSlippageRecord {
+    pub pool: Address,
+    pub dex: String,
+    pub quoted_out: String,
+    pub realized_out: String,
+    pub error_bps: Option<i64>,
+}
+
+/// The transaction of a failed submission, enough to replay it with `eth_call`. Never holds key material.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedSubmission {
+    /// Where it failed: `gas_estimation`, `submission` or `on_chain`.
+    pub stage: String,
+    pub to: Address,
+    pub calldata: Bytes,
+    pub salt: String,
+    /// None before the final gas limit is known (gas estimation failures).
+    pub gas_limit: Option<String>,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    pub nonce: Option<String>,
+    /// Debug rendering of the `RouteCandidate`.
+    pub route: String,
+}
+
+impl AuditRecord {
+    pub fn new(event: AuditEvent, route: &RouteCandidate) -> Self {
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default();
+        Self {
+            timestamp_ms, event, route_id: route.id(), buy_pool: route.buy_pool_addr, sell_pool: route.sell_pool_addr,
+            loan_amount_wei: None, net_profit_wei: None, realized_profit_wei: None, profit_error_wei: None, gas_limit: None, gas_cost_wei: None, tx_hash: None, reason: None, failed_submission: None, leg_slippage: Vec::new(),
+        }
+    }
+
+    pub fn loan_amount(mut self, wei: U256) -> Self { self.loan_amount_wei = Some(wei.to_string()); self }
+    pub fn net_profit(mut self, wei: I256) -> Self { self.net_profit_wei = Some(wei.to_string()); self }
+    /// Realized net profit and the simulation error (`net_profit - realized`).
+    pub fn realized_profit(mut self, wei: I256) -> Self {
+        self.profit_error_wei = self.net_profit_wei.as_deref().and_then(|p| I256::from_dec_str(p).ok()).map(|p| p.saturating_sub(wei).to_string());
+        self.realized_profit_wei = Some(wei.to_string());
+        self
+    }
+    pub fn gas_limit(mut self, gas: U256) -> Self { self.gas_limit = Some(gas.to_string()); self }
+    pub fn gas_cost(mut self, wei: U256) -> Self { self.gas_cost_wei = Some(wei.to_string()); self }
+    pub fn tx_hash(mut self, hash: TxHash) -> Self { self.tx_hash = Some(hash); self }
+    pub fn reason(mut self, reason: impl Into<String>) -> Self { self.reason = Some(reason.into()); self }
+    pub fn failed_submission(mut self, dump: Option<FailedSubmission>) -> Self { self.failed_submission = dump; self }
+    pub fn leg_slippage(mut self, legs: &[LegSlippage]) -> Self {
+        self.leg_slippage = legs.iter().map(|leg| LegSlippageRecord {
+            pool: leg.pool, dex: format!("{:?}", leg.dex_type), quoted_out: leg.quoted_out.to_string(), realized_out: leg.realized_out.to_string(), error_bps: leg.error_bps(),
+        }).collect();
+        self
+    }
+}
+
+/// Cheap-to-clone handle to the audit writer (disabled by default).
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    sender: Option<UnboundedSender<AuditRecord>>,
+    closed: Arc<Notify>,
+}
+
+impl AuditLog {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Opens `path` for appending and spawns the writer task. The task ends once every handle is dropped or
+    /// `close` is called; the returned `JoinHandle` resolves after the remaining records are written.
+    pub async fn open(path: &str) -> Result<(Self, JoinHandle<()>)> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path).await
+            .wrap_err_with(|| format!("Failed to open audit log {}", path))?;
+        let (sender, mut receiver) = unbounded_channel::<AuditRecord>();
+        let closed = Arc::new(Notify::new());
+        let close_requested = closed.clone();
+        let path = path.to_string();
+        let handle = tokio::spawn(async move {
+            loop {
+                let record = tokio::select! {
+                    record = receiver.recv() => match record { Some(record) => record, None => break },
+                    // Stop accepting records; the ones already queued are still written
+                    _ = close_requested.notified() => { receiver.close(); continue; }
+                };
+                let mut line = match serde_json::to_string(&record) {
+                    Ok(l) => l,
+                    Err(e) => { warn!(error = %e, "Failed to serialize audit record."); continue; }
+                };
+                line.push('\n');
+                // Flush per record so a crash loses at most the line being written
+                if let Err(e) = async { file.write_all(line.as_bytes()).await?; file.flush().await }.await {
+                    warn!(%path, error = %e, "Failed to write audit record (record dropped).");
+                }
+            }
+        });
+        info!("📒 Audit log enabled.");
+        Ok((Self { sender: Some(sender), closed }, handle))
+    }
+
+    /// Stops the writer without waiting for every handle to drop (shutdown, where spawned tasks still hold
+    /// clones). Records queued so far are written; later ones are dropped.
+    pub fn close(&self) {
+        self.closed.notify_one();
+    }
+
+    /// Queues the record built by `build`; never blocks. When disabled the record is not even built.
+    pub fn record(&self, build: impl FnOnce() -> AuditRecord) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(build());
+        }
+    }
+}
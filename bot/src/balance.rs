@@ -0,0 +1,58 @@
+// bot/src/balance.rs
+//! Signer gas balance monitor.
+//!
+//! Every submission pays gas from the signer, so once its native balance runs dry every attempt fails.
+//! A periodic check reports the balance as the `signer_balance_gwei` metric and, while it is below
+//! `config.min_gas_balance_wei`, holds the circuit breaker tripped and alerts. The check only sets and
+//! clears its own `PauseReason::LowGasBalance`, so a manual `pause` from the admin interface (or any other
+//! pause) survives a refill.
+
+use crate::alerts::{send_alert, AlertLevel};
+use crate::metrics::Metrics;
+use crate::state::{AppState, PauseReason};
+use ethers::prelude::*;
+use ethers::utils::format_units;
+use eyre::{Result, WrapErr};
+use tracing::{info, warn};
+
+/// Change of the low-balance condition produced by one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasBalanceTransition {
+    /// Balance fell below the minimum; the breaker was tripped.
+    Low,
+    /// Balance is back at or above the minimum; the low-balance pause was cleared.
+    Refilled,
+}
+
+/// Applies a fresh balance reading: updates the gauge and trips or resets the breaker on a threshold crossing.
+/// Returns `None` while the condition is unchanged (or the check is disabled by a zero minimum).
+pub fn apply_gas_balance(app_state: &AppState, balance_wei: U256) -> Option<GasBalanceTransition> {
+    let gwei = balance_wei / U256::exp10(9);
+    Metrics::set(&app_state.metrics.signer_balance_gwei, if gwei > U256::from(u64::MAX) { u64::MAX } else { gwei.as_u64() });
+    let min = app_state.config.min_gas_balance_wei;
+    if min.is_zero() { return None; }
+    let low = balance_wei < min;
+    if app_state.set_paused(PauseReason::LowGasBalance, low) == low { return None; }
+    Some(if low { GasBalanceTransition::Low } else { GasBalanceTransition::Refilled })
+}
+
+/// Fetches the signer balance, applies it and alerts on a transition. Returns the balance in wei.
+pub async fn check_gas_balance<M: Middleware>(client: &M, app_state: &AppState, signer: Address) -> Result<U256> where M::Error: 'static + Send + Sync {
+    let balance = client.get_balance(signer, None).await.wrap_err("Failed to fetch signer balance")?;
+    let eth = |wei: U256| format_units(wei, "ether").unwrap_or_else(|_| wei.to_string());
+    let min = app_state.config.min_gas_balance_wei;
+    match apply_gas_balance(app_state, balance) {
+        Some(GasBalanceTransition::Low) => {
+            warn!(balance = %eth(balance), min = %eth(min), "ALERT: Signer gas balance below minimum. Circuit breaker tripped.");
+            send_alert(&app_state.config, AlertLevel::Critical, format!("Signer {:?} gas balance {} ETH below minimum {} ETH. Bot paused until refilled.", signer, eth(balance), eth(min)));
+        }
+        Some(GasBalanceTransition::Refilled) => {
+            info!(balance = %eth(balance), still_paused = app_state.is_paused(), "Signer gas balance refilled. Low-balance pause cleared.");
+            let status = if app_state.is_paused() { format!("Bot still paused ({:?}).", app_state.pause_reasons()) } else { "Bot resumed.".to_string() };
+            send_alert(&app_state.config, AlertLevel::Info, format!("Signer {:?} gas balance refilled to {} ETH. {}", signer, eth(balance), status));
+        }
+        None => {}
+    }
+    Ok(balance)
+}
+// END OF FILE: bot/src/balance.rs
@@ -60,9 +60,10 @@ abigen!(
     event_derives(serde::Deserialize, serde::Serialize)
 );
 
+// Same PoolCreated event as Velodrome V2 (`stable` indexed); a separate file for Aerodrome's factory functions
 abigen!(
     IAerodromeFactory, // New Rust struct name for Aerodrome Factory interface
-    "./abis/IVelodromeFactory.json", // Reusing the Velo Factory ABI
+    "./abis/IAerodromeFactory.json",
     event_derives(serde::Deserialize, serde::Serialize)
 );
 // --- TODO: Add Bindings for Ramses (Arbitrum) when implementing ---
@@ -75,6 +76,23 @@ abigen!(
     event_derives(serde::Deserialize, serde::Serialize)
 );
 
+// Balancer Vault views missing from BalancerVault.json (startup contract preflight, flash loan fee)
+abigen!(
+    IBalancerVaultViews,
+    r#"[
+        function WETH() external view returns (address)
+        function getProtocolFeesCollector() external view returns (address)
+    ]"#
+);
+
+// Balancer ProtocolFeesCollector: the flash loan fee is an 18-decimal fraction of the loan
+abigen!(
+    IBalancerProtocolFeesCollector,
+    r#"[
+        function getFlashLoanFeePercentage() external view returns (uint256)
+    ]"#
+);
+
 // Generic ERC20 interface with common functions
 abigen!(
     IERC20,
@@ -94,10 +112,32 @@ abigen!(
     event_derives(serde::Deserialize, serde::Serialize)
 );
 
+// Wrapped ether (WETH9), used to unwrap realized profit to native ETH
+abigen!(
+    IWETH9,
+    r#"[
+        event Deposit(address indexed dst, uint256 wad)
+        event Withdrawal(address indexed src, uint256 wad)
+        function deposit() external payable
+        function withdraw(uint256 wad) external
+        function balanceOf(address account) external view returns (uint256)
+    ]"#,
+    event_derives(serde::Deserialize, serde::Serialize)
+);
+
+// Chainlink price feed (AggregatorV3Interface), read-only subset
+abigen!(
+    ChainlinkAggregator,
+    r#"[
+        function decimals() external view returns (uint8)
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
+
 // Minimal interface for the Huff executor (e.g., for owner functions if needed)
 abigen!(
     ArbitrageExecutor,
-    "./abis/ArbitrageExecutor.json", // Contains only withdrawToken for now
+    "./abis/ArbitrageExecutor.json", // executeFlashLoan, withdrawToken and the ProfitRealized event
     event_derives(serde::Deserialize, serde::Serialize)
 );
 
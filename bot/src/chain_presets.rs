@@ -0,0 +1,111 @@
+// bot/src/chain_presets.rs
+//! Canonical per-chain contract addresses, selected with `CHAIN_PRESET`.
+//!
+//! A preset is a partially-populated `Config` holding the chain id, token decimals and the
+//! well-known addresses of that chain. `load_config` uses it as the default for every address
+//! variable, so a preset only needs the RPC URLs, key and executor on top; any variable that is
+//! set still wins. DEXes a chain does not have (Velodrome outside Optimism) stay unset.
+
+use crate::config::Config;
+use ethers::types::Address;
+use ethers::utils::to_checksum;
+use eyre::{eyre, Result};
+use std::str::FromStr;
+
+/// Chains with built-in address presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainPreset {
+    Mainnet,
+    Optimism,
+    Base,
+}
+
+impl FromStr for ChainPreset {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mainnet" | "ethereum" => Ok(ChainPreset::Mainnet),
+            "optimism" | "op" => Ok(ChainPreset::Optimism),
+            "base" => Ok(ChainPreset::Base),
+            other => Err(eyre!("Unknown chain preset: {} (supported: mainnet, optimism, base)", other)),
+        }
+    }
+}
+
+// Shared across chains
+const UNISWAP_V3_FACTORY: &str = "0x1F98431c8aD98523631AE4a59f267346ea31F984"; // Mainnet, Optimism
+const QUOTER_V2: &str = "0x61fFE014bA17989E743c5F6cB21bF9697530B21e"; // Mainnet, Optimism
+const BALANCER_VAULT: &str = "0xBA12222222228d8Ba445958a75a0704d566BF2C8";
+const OP_STACK_WETH: &str = "0x4200000000000000000000000000000000000006";
+
+impl ChainPreset {
+    pub const ALL: [ChainPreset; 3] = [ChainPreset::Mainnet, ChainPreset::Optimism, ChainPreset::Base];
+
+    /// The preset of the live chain `chain_id`, if it has one.
+    pub fn from_chain_id(chain_id: u64) -> Option<ChainPreset> {
+        Self::ALL.into_iter().find(|preset| preset.config().chain_id == Some(chain_id))
+    }
+
+    pub fn config(self) -> Config {
+        match self {
+            ChainPreset::Mainnet => Self::mainnet(),
+            ChainPreset::Optimism => Self::optimism(),
+            ChainPreset::Base => Self::base(),
+        }
+    }
+
+    /// Ethereum mainnet: Uniswap V3 and Balancer.
+    pub fn mainnet() -> Config {
+        Config {
+            chain_id: Some(1),
+            uniswap_v3_factory_addr: addr(UNISWAP_V3_FACTORY),
+            quoter_v2_address: addr(QUOTER_V2),
+            balancer_vault_address: addr(BALANCER_VAULT),
+            weth_address: addr("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+            usdc_address: addr("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+            ..preset_base("mainnet")
+        }
+    }
+
+    /// Optimism: Uniswap V3, Velodrome V2 and native USDC.
+    pub fn optimism() -> Config {
+        Config {
+            chain_id: Some(10),
+            uniswap_v3_factory_addr: addr(UNISWAP_V3_FACTORY),
+            quoter_v2_address: addr(QUOTER_V2),
+            velodrome_v2_factory_addr: addr("0xF1046053aa5682b4F9a81b5481394DA16BE5FF5a"),
+            velo_router_addr: addr("0xa062aE8A9c5e11aaA026fc2670B0D65cCc8B2858"),
+            balancer_vault_address: addr(BALANCER_VAULT),
+            weth_address: addr(OP_STACK_WETH),
+            usdc_address: addr("0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+            ..preset_base("optimism")
+        }
+    }
+
+    /// Base: Uniswap V3, Aerodrome and native USDC.
+    pub fn base() -> Config {
+        Config {
+            chain_id: Some(8453),
+            uniswap_v3_factory_addr: addr("0x33128a8fC17869897dcE68Ed026d694621f6FDfD"),
+            quoter_v2_address: addr("0x3d4e44Eb1374240CE5F1B871ab261CD16335B76a"),
+            aerodrome_factory_addr: Some(addr("0x420DD381b31aEf6683db6B902084cB0FFECe40Da")),
+            aerodrome_router_addr: Some(addr("0xcF77a3Ba9A5CA399B7c97c74d54e5b1Beb874E43")),
+            balancer_vault_address: addr(BALANCER_VAULT),
+            weth_address: addr(OP_STACK_WETH),
+            usdc_address: addr("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"),
+            ..preset_base("base")
+        }
+    }
+}
+
+fn preset_base(name: &str) -> Config {
+    Config { chain_preset: Some(name.to_string()), weth_decimals: 18, usdc_decimals: 6, ..Config::default() }
+}
+
+/// Parses a preset literal, which must be EIP-55 checksummed so a mistyped digit cannot slip in.
+fn addr(literal: &str) -> Address {
+    let address: Address = literal.parse().expect("preset address literal is valid hex");
+    assert_eq!(to_checksum(&address, None), literal, "preset address literal is not checksummed");
+    address
+}
+// END OF FILE: bot/src/chain_presets.rs
@@ -1,18 +1,129 @@
 // bot/src/config.rs
 
-use ethers::types::Address;
+use crate::chain_presets::ChainPreset;
+use crate::gas_learning::RouteShape;
+use crate::relay::{RelayAuthScheme, RelayConfig};
+use crate::state::DexType;
+use ethers::types::{Address, U256};
+use ethers::utils::parse_units;
 use eyre::{Result, WrapErr, eyre};
+use std::collections::HashMap;
 use std::env;
+use std::path::{Path, PathBuf};
 use dotenv::dotenv;
+use std::str::FromStr;
 use tracing::{debug, info, warn};
 
-#[derive(Debug, Clone)]
+/// How the submission priority fee (tip) is chosen. Always capped by `max_priority_fee_per_gas_gwei`; only a
+/// competitive bid (`competition_priority_fee_bps_of_profit`) raises the submitted tip past it.
+/// Parsed from `PRIORITY_FEE_STRATEGY`: `fixed:<gwei>`, `multiplier:<f64>` or `percentile:<0-100>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriorityFeeStrategy {
+    /// Constant tip in gwei.
+    Fixed(f64),
+    /// Tip = current base fee * multiplier.
+    BaseFeeMultiplier(f64),
+    /// Tip = median of the given reward percentile over recent blocks (`eth_feeHistory`).
+    RecentBlocksPercentile(u8),
+}
+impl FromStr for PriorityFeeStrategy {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(':').ok_or_else(|| eyre!("Expected <strategy>:<value>, got '{}'", s))?;
+        match kind.trim().to_lowercase().as_str() {
+            "fixed" => Ok(PriorityFeeStrategy::Fixed(value.trim().parse()?)),
+            "multiplier" | "base_fee_multiplier" => Ok(PriorityFeeStrategy::BaseFeeMultiplier(value.trim().parse()?)),
+            "percentile" | "recent_blocks_percentile" => {
+                let p: u8 = value.trim().parse()?;
+                if p > 100 { return Err(eyre!("Percentile must be 0-100, got {}", p)); }
+                Ok(PriorityFeeStrategy::RecentBlocksPercentile(p))
+            }
+            _ => Err(eyre!("Unknown priority fee strategy: {}", kind)),
+        }
+    }
+}
+
+/// `userData` layout expected by the deployed Huff executor (`EXECUTOR_ABI_VERSION`, e.g. `2.4`).
+/// Must match the version header of the `ArbitrageExecutor.huff` the executor was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutorAbiVersion {
+    /// 9 words, salt at 0x100. Not accepted from `EXECUTOR_ABI_VERSION`: a 2.3.0 executor reads the Velodrome
+    /// router from the 0xC0 word, which the encoder writes as zero, and cannot complete a flash loan anyway.
+    V2_3,
+    /// V2_3 plus per-swap minimum outputs at 0x120/0x140; loans are started through `executeFlashLoan`.
+    #[default]
+    V2_4,
+}
+impl FromStr for ExecutorAbiVersion {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().trim_start_matches(['v', 'V']) {
+            "2.4" | "2.4.3" => Ok(ExecutorAbiVersion::V2_4),
+            old @ ("2.3" | "2.3.0" | "2.4.0" | "2.4.1" | "2.4.2") => Err(eyre!(
+                "Executor ABI version {} is no longer supported: redeploy the executor from contracts/ArbitrageExecutor.huff (2.4.3)", old
+            )),
+            other => Err(eyre!("Unknown executor ABI version: {} (supported: 2.4)", other)),
+        }
+    }
+}
+
+/// Log event families the live subscription listens for (`MONITORED_EVENTS`, comma-separated).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitoredEvent {
+    /// UniV3 and Velodrome/Aerodrome `Swap`.
+    Swap,
+    /// Factory `PoolCreated`.
+    PoolCreated,
+    /// UniV3 `Mint`: adds to the active liquidity when the position spans the current tick.
+    Mint,
+    /// UniV3 `Burn`: removes from the active liquidity when the position spans the current tick.
+    Burn,
+}
+impl FromStr for MonitoredEvent {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "swap" => Ok(MonitoredEvent::Swap),
+            "pool_created" | "poolcreated" => Ok(MonitoredEvent::PoolCreated),
+            "mint" => Ok(MonitoredEvent::Mint),
+            "burn" => Ok(MonitoredEvent::Burn),
+            other => Err(eyre!("Unknown monitored event: {} (supported: swap, pool_created, mint, burn)", other)),
+        }
+    }
+}
+
+/// How startup finds Velodrome/Aerodrome pools (`POOL_DISCOVERY`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolDiscovery {
+    /// `allPools(i)` + `tokens()` per pool index.
+    #[default]
+    Index,
+    /// `PoolCreated` logs of the last `initial_block_history_to_scan` blocks, in chunked `eth_getLogs` calls
+    /// (see `discovery`). Also covers the UniV3 factory.
+    Logs,
+}
+impl FromStr for PoolDiscovery {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "index" => Ok(PoolDiscovery::Index),
+            "logs" => Ok(PoolDiscovery::Logs),
+            other => Err(eyre!("Unknown pool discovery method: {} (supported: index, logs)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Config {
     // Network & Keys
     pub ws_rpc_url: String,
     pub http_rpc_url: String,
+    pub read_rpc_url: Option<String>, // Pool state reads and simulation (e.g., a fast archive node); defaults to http_rpc_url
+    pub write_rpc_url: Option<String>, // Arb submission (e.g., a low-latency sender); defaults to http_rpc_url
     pub local_private_key: String,
     pub chain_id: Option<u64>, // Optional: Chain ID if needed for logic
+    pub chain_preset: Option<String>, // Built-in address defaults (mainnet/optimism/base); env vars override
+    pub production: bool, // Production deployment: test-only overrides such as allow_submission_zero_profit are ignored
 
     // Contract Addresses (Core - Optimism/Base)
     pub arb_executor_address: Option<Address>,
@@ -32,42 +143,239 @@ pub struct Config {
     // Token Information (Required for initial WETH/USDC pair)
     pub weth_address: Address,
     pub usdc_address: Address,
+    pub usdc_addresses: Vec<Address>, // Further accepted quote tokens (e.g. native USDC beside USDC.e); never routed against each other, usdc_decimals until their decimals() is read
     pub weth_decimals: u8,
     pub usdc_decimals: u8,
 
     // Deployment Options
     pub deploy_executor: bool,
-    pub executor_bytecode_path: String,
+    pub executor_bytecode_path: String, // Defaults to `ArbitrageExecutor.bin` under `build_dir`
+    pub build_dir: String, // Directory compiled contract bytecode is read from (`./build` when empty)
+    pub executor_abi_version: ExecutorAbiVersion, // userData layout of the deployed executor
 
     // Optimization Options
     pub min_loan_amount_weth: f64,
     pub max_loan_amount_weth: f64,
     pub optimal_loan_search_iterations: u32,
-    pub fetch_timeout_secs: Option<u64>, // Timeout for individual pool state fetches
+    pub sim_probe_concurrency: usize, // Optimal-loan search probes simulated at once
+    pub fetch_timeout_secs: Option<u64>, // Timeout for individual pool state fetches (until latency samples exist)
+    pub min_fetch_timeout_secs: u64, // Lower bound of the adaptive fetch timeout
+    pub max_fetch_timeout_secs: u64, // Upper bound of the adaptive fetch timeout (0 disables adaptation)
+    pub fetch_max_retries: u32, // Retries of a failed/timed-out pool state fetch (jittered exponential backoff)
+    pub fetch_base_delay_ms: u64, // Backoff before the first retry; doubles per retry
+    pub enumeration_concurrency: usize, // Concurrent allPools/tokens lookups during startup pool enumeration
+    pub enumeration_timeout_secs: u64, // Overall budget per factory enumeration; pools not reached in time are skipped
+    pub pool_discovery: PoolDiscovery,
+    pub initial_block_history_to_scan: u64, // Blocks of PoolCreated logs backfilled by `PoolDiscovery::Logs` (0 = from genesis)
+    pub max_block_range_per_query: u64, // Largest eth_getLogs block range (providers cap it, commonly at 10k)
+    pub univ3_fee_tiers: Vec<u32>, // UniV3 fee tiers probed at startup and accepted from PoolCreated logs (empty = DEFAULT_UNIV3_FEE_TIERS)
+    pub monitored_events: Vec<MonitoredEvent>, // Log events the live filter subscribes to (empty = DEFAULT_MONITORED_EVENTS)
     pub enable_univ3_dynamic_sizing: bool, // Defaults to false
+    pub max_price_impact_bps: u64, // Loans moving either pool's price further than this are not considered (0 disables)
+    pub max_price_jump_bps: u64, // Velo/Aero reserve updates moving the reserve price further than this are held back until a second reading confirms them (0 disables)
+    pub univ3_tick_window_words: u32, // Tick bitmap words read on each side of a UniV3 pool's current word, whose initialized ticks local quotes may cross (0 = never cross ticks)
+    pub twap_window_secs: u32, // Window of the UniV3 observe() TWAP read with each pool fetch/refresh and, with the deviation check on, each swap (0 disables)
+    pub default_token_decimals: u8, // Decimals assumed for tokens whose decimals() is missing or reverts
+    pub skip_unknown_decimals: bool, // Skip pools holding a token whose decimals had to be assumed
+    pub max_spot_twap_deviation_bps: u64, // UniV3 pools whose slot0 price is further than this from their TWAP are not routed (0 disables)
+    pub competition_priority_fee_bps_of_profit: u64, // Share of swap profit bid as priority fee to win a contested block, in profit estimates and on the submitted tx (0 disables)
+    pub executor_callstatic_check: bool, // Confirm profit by eth_call-ing the exact submission transaction before sending it
+    pub allow_submission_zero_profit: bool, // Forks/tests only: submit routes without a positive profit (ignored in production)
+    pub sim_divergence_tolerance_bps: u64, // Largest gap between the eth_call's and the quoters' final output before aborting (default 50; 0 disables; needs executor_callstatic_check)
+    pub pricing_shadow_mode: bool, // Price UniV3 legs with QuoterV2 only, comparing (not using) the local tick math
+    pub opportunity_ttl_ms: u64, // Latency budget from swap log receipt to submission
+    pub salt_expiry_blocks: u64, // Blocks a submitted salt is remembered to block duplicate submissions
+    pub confirmations_required: u64, // Blocks (including the inclusion block) before a receipt counts as final; must fit the 90s confirmation timeout
+    pub enable_mempool: bool, // Report routes predicted from pending swaps, without submitting them (needs a WS endpoint exposing newPendingTransactions)
+    pub max_blocks_late: u64, // Blocks the head may pass an opportunity's target block (source + 1) before it is dropped
+    pub pool_blocklist: Vec<Address>, // Pools never cached or routed through (honeypots, fee-on-transfer, chronic reverts)
+    pub pool_allowlist: Option<Vec<Address>>, // When set, only these pools are considered
+    pub flash_loan_fee_refresh_secs: u64, // Period of the Balancer flash loan fee re-read (0 = read at startup only)
+    pub full_refresh_interval_secs: u64, // Period of the full snapshot re-fetch safety net (0 disables)
+    pub pool_failure_cooldown_secs: u64, // Base cooldown of a pool after a reverted submission through it (0 disables)
+    pub max_routes_to_evaluate: usize, // Top-K route candidates simulated per swap event (0 = unlimited)
+    pub route_score_profit_weight: f64, // Route ranking: weight of the price spread (%)
+    pub route_score_liquidity_weight: f64, // Route ranking: weight of log10 of the shallower pool's depth (whole WETH)
+    pub route_score_age_weight: f64, // Route ranking: weight of the younger pool's maturity (0..1)
+    pub route_score_mature_blocks: u64, // Pool age in blocks at which it counts as fully established
+    pub max_slippage_bps: u64, // Per-swap output floor below the simulated amount, enforced by the executor
 
     // Gas Pricing Options
     pub max_priority_fee_per_gas_gwei: f64,
     pub fallback_gas_price_gwei: Option<f64>, // Fallback if fetch fails
+    pub simulation_gas_price_multiplier: f64, // Safety margin on the live gas price simulations are charged at (below 1 counts as 1)
+    pub gas_token_price_in_loan_token: Option<f64>, // Native gas token price in loan tokens; unset = 1:1 (ETH-native chains)
     pub gas_limit_buffer_percentage: u64,
+    pub learned_gas_min_samples: usize, // Receipts of a route shape before its learned p95 gas replaces eth_estimateGas (0 disables)
     pub min_flashloan_gas_limit: u64,
+    pub submission_gas_limit_default: u64, // Used only when eth_estimateGas reverts
+    pub max_gas_limit: u64, // Upper clamp for the submitted gas limit
+    pub gas_profiles: HashMap<RouteShape, u64>, // Gas limit per (buy DEX, sell DEX), used instead of estimation (GAS_PROFILES; empty unless set)
+    pub priority_fee_strategy: Option<PriorityFeeStrategy>, // None = node's eth_maxPriorityFeePerGas estimate
 
     // Transaction Submission Options
     pub private_rpc_url: Option<String>, // Primary private relay (e.g., Flashbots Protect, MEV-Share)
     pub secondary_private_rpc_url: Option<String>, // Secondary/fallback private relay
+    pub private_rpc_auth: RelayAuthScheme, // Request signing for the primary relay (none/flashbots)
+    pub secondary_private_rpc_auth: RelayAuthScheme, // Request signing for the secondary relay
+    pub concurrent_relay_submission: bool, // Send to every private relay at once instead of one after another
+    pub auto_unwrap_profit: bool, // Sweep executor WETH to the wallet and unwrap it to ETH after each confirmed arb
+    pub auto_wrap_eth: bool, // Let the rebalance check wrap native ETH when selling tokens leaves wallet WETH below MIN_WETH_WORKING_BALANCE
 
     // Profitability & Slippage Control
     pub min_profit_buffer_bps: u64, // Buffer in basis points (100ths of a percent)
     pub min_profit_abs_buffer_wei_str: String, // Buffer in absolute wei (as string to handle large numbers)
+    pub chainlink_eth_usd_feed: Option<Address>, // Chainlink ETH/USD aggregator; pool-derived price when unset
+    pub profit_sharing_bps_for_devs: u64, // Share of realized net profit sent to profit_share_recipient (0 disables)
+    pub profit_share_recipient: Option<Address>, // Required when profit_sharing_bps_for_devs > 0
+    pub profit_bias_warn_bps: u64, // Warn when the rolling predicted-vs-realized profit error exceeds this (0 disables)
+    pub velo_slippage_correction_bps: u64, // Velo/Aero getAmountsOut quotes are reduced by this (tune from `velo_output_shortfall_bps`)
 
     // Health Check & Monitoring
     pub critical_block_lag_seconds: u64, // Added field
     pub critical_log_lag_seconds: u64,   // Added field
+    pub min_gas_balance_wei: U256, // Signer native balance below this trips the circuit breaker (0 disables)
+    pub gas_balance_check_interval_secs: u64, // Period of the signer balance check (0 disables)
+    pub min_weth_working_balance: U256, // Wallet WETH below this is restored by selling USDC (0 disables)
+    pub rebalance_interval_secs: u64, // Period of the WETH rebalance check (0 disables)
+    pub nonce_divergence_blocks: u64, // Blocks the on-chain tx count may stay behind the local nonce before the breaker trips (0 disables)
+    pub nonce_check_interval_secs: u64, // Period of the nonce divergence check
+    pub alert_webhook_url: Option<String>, // Telegram sendMessage or Discord webhook URL for critical alerts
+    pub alert_profit_threshold_wei: U256, // Successful trades netting at least this (loan-token wei) trigger an alert
+    pub admin_addr: Option<String>, // host:port for the admin command interface (disabled when unset)
+    pub audit_log_path: Option<String>, // JSONL opportunity audit trail (disabled when unset)
+    pub log_snapshot_diffs: bool, // Debug-log per-update snapshot deltas (sqrtPrice/tick/reserves)
+    pub log_failed_calldata: bool, // Error-log (and audit) the exact calldata of failed/reverted submissions
+    pub skip_contract_preflight: bool, // Start without checking that configured contracts answer view calls
+    pub nonce_resync_retries: u32, // Re-sync the nonce and resubmit this many times after "nonce too low"
+    pub max_monitored_pools: usize, // Cap on cached pools; past it the least liquid pool is evicted for a deeper one (0 = unlimited)
+    pub min_submission_interval_ms: u64, // Minimum time between submission attempts; sooner opportunities are dropped (0 disables)
+    pub submission_warmup_secs: u64, // After startup, profitable opportunities are only logged for this long (0 disables)
+    pub min_pools_before_trading: usize, // Submissions also wait until this many pools are cached (0 disables)
+    pub min_pool_submission_interval_ms: u64, // Same, per pool traded through (0 disables)
+    pub max_total_inflight_loan_wei: U256, // Cap on the summed loans of unresolved submissions (0 disables)
+    pub seen_log_cache_size: usize, // Recently processed logs remembered to skip re-deliveries after a reconnect (0 disables)
+    pub self_test_rpc_url: Option<String>, // Running Anvil fork for `self-test` (a local anvil fork of http_rpc_url is started when unset)
+    pub snapshot_dump_path: Option<String>, // CSV dump of the pool cache written on shutdown and by the admin `dump` command
+    pub pool_cache_path: Option<String>, // Versioned pool cache written on shutdown; its pools are re-fetched first on the next start
+    pub lock_file_path: Option<String>, // Single-instance lock file (a per-signer file in the temp dir when unset)
+    pub executor_residual_check: bool, // After each confirmed arb, warn/alert when the executor still holds the intermediate token
+    pub executor_dust_threshold: U256, // Residual intermediate-token balance (raw units) tolerated by the residual check
+    pub sweep_recipient: Option<Address>, // Residuals found by the check are swept here via withdrawToken (disabled when unset)
+
+}
+
+pub const DEFAULT_BUILD_DIR: &str = "./build";
+pub const EXECUTOR_BYTECODE_FILE: &str = "ArbitrageExecutor.bin";
+/// Huff source `EXECUTOR_BYTECODE_FILE` is compiled from (`huffc ./contracts/ArbitrageExecutor.huff -b`).
+pub const EXECUTOR_HUFF_SOURCE: &str = "./contracts/ArbitrageExecutor.huff";
+/// UniV3 fee tiers (hundredths of a bip) monitored when `UNIV3_FEE_TIERS` is unset.
+pub const DEFAULT_UNIV3_FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+/// Events monitored when `MONITORED_EVENTS` is unset: price-moving swaps and new pools.
+pub const DEFAULT_MONITORED_EVENTS: [MonitoredEvent; 2] = [MonitoredEvent::Swap, MonitoredEvent::PoolCreated];
+/// Local test chain ids `ALLOW_SUBMISSION_ZERO_PROFIT` is honored on: Anvil/Hardhat and Ganache/geth --dev.
+pub const TEST_CHAIN_IDS: [u64; 2] = [31337, 1337];
+
+/// Seed gas limits per `(buy DEX, sell DEX)` for Balancer loans, opted into with `GAS_PROFILES=default`.
+/// Velodrome-style legs quote, transfer and swap (three pool calls), so they cost more than a UniV3 swap
+/// paid from its callback. Profiled pairs skip `eth_estimateGas` and with it the pre-submission revert check.
+pub const DEFAULT_GAS_PROFILES: [((DexType, DexType), u64); 9] = [
+    ((DexType::UniswapV3, DexType::UniswapV3), 450_000),
+    ((DexType::UniswapV3, DexType::VelodromeV2), 550_000),
+    ((DexType::UniswapV3, DexType::Aerodrome), 550_000),
+    ((DexType::VelodromeV2, DexType::UniswapV3), 550_000),
+    ((DexType::Aerodrome, DexType::UniswapV3), 550_000),
+    ((DexType::VelodromeV2, DexType::VelodromeV2), 650_000),
+    ((DexType::VelodromeV2, DexType::Aerodrome), 650_000),
+    ((DexType::Aerodrome, DexType::VelodromeV2), 650_000),
+    ((DexType::Aerodrome, DexType::Aerodrome), 650_000),
+];
+
+/// Parses `GAS_PROFILES`: comma-separated `<buy_dex>:<sell_dex>=<gas_limit>` entries (e.g.
+/// `univ3:velov2=520000`). `default` loads `DEFAULT_GAS_PROFILES`. A limit of 0 leaves that shape to
+/// estimation.
+pub fn parse_gas_profiles(s: &str) -> Result<HashMap<RouteShape, u64>> {
+    if s.trim().eq_ignore_ascii_case("default") {
+        return Ok(DEFAULT_GAS_PROFILES.into_iter().collect());
+    }
+    s.split(',').map(str::trim).filter(|entry| !entry.is_empty()).map(|entry| {
+        let (shape, limit) = entry.split_once('=').ok_or_else(|| eyre!("Expected <buy_dex>:<sell_dex>=<gas_limit>, got {}", entry))?;
+        let mut parts = shape.split(':').map(str::trim);
+        let (Some(buy), Some(sell), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(eyre!("Expected <buy_dex>:<sell_dex>, got {}", shape));
+        };
+        let limit = limit.trim().parse::<u64>().map_err(|e| eyre!("Invalid gas limit in {}: {}", entry, e))?;
+        Ok(((buy.parse()?, sell.parse()?), limit))
+    }).collect()
+}
+
+/// Directory compiled contract bytecode is read from: `BUILD_DIR`, or `DEFAULT_BUILD_DIR` when unset or empty.
+pub fn build_dir() -> String {
+    env::var("BUILD_DIR").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| DEFAULT_BUILD_DIR.to_string())
+}
+
+/// `file` under `dir`, or under `default_dir` when `dir` is empty.
+pub fn resolve_artifact_path(dir: &str, default_dir: &str, file: impl AsRef<Path>) -> PathBuf {
+    Path::new(if dir.is_empty() { default_dir } else { dir }).join(file)
+}
+
+impl Config {
+    /// Bytecode file `file` under `build_dir`.
+    pub fn build_path(&self, file: impl AsRef<Path>) -> PathBuf {
+        resolve_artifact_path(&self.build_dir, DEFAULT_BUILD_DIR, file)
+    }
+
+    /// Whether a pool passes the operator blocklist/allowlist.
+    pub fn is_pool_allowed(&self, pool: Address) -> bool {
+        if self.pool_blocklist.contains(&pool) { return false; }
+        self.pool_allowlist.as_ref().is_none_or(|allow| allow.contains(&pool))
+    }
+
+    /// UniV3 fee tiers to monitor: `univ3_fee_tiers`, or `DEFAULT_UNIV3_FEE_TIERS` when empty.
+    pub fn enabled_univ3_fee_tiers(&self) -> &[u32] {
+        if self.univ3_fee_tiers.is_empty() { &DEFAULT_UNIV3_FEE_TIERS } else { &self.univ3_fee_tiers }
+    }
+
+    /// Log events to subscribe to: `monitored_events`, or `DEFAULT_MONITORED_EVENTS` when empty.
+    pub fn enabled_monitored_events(&self) -> &[MonitoredEvent] {
+        if self.monitored_events.is_empty() { &DEFAULT_MONITORED_EVENTS } else { &self.monitored_events }
+    }
+
+    /// Whether `allow_submission_zero_profit` applies on `chain_id`. Never in production: not with
+    /// `production` set, and only on the local test chain ids in `TEST_CHAIN_IDS`.
+    pub fn zero_profit_submission_allowed(&self, chain_id: u64) -> bool {
+        self.allow_submission_zero_profit && !self.production && TEST_CHAIN_IDS.contains(&chain_id)
+    }
 
+    /// Endpoint for pool state reads and simulation: `read_rpc_url`, or `http_rpc_url` when unset.
+    pub fn read_endpoint(&self) -> &str {
+        self.read_rpc_url.as_deref().unwrap_or(&self.http_rpc_url)
+    }
+
+    /// Endpoint for arb submission: `write_rpc_url`, or `http_rpc_url` when unset.
+    pub fn write_endpoint(&self) -> &str {
+        self.write_rpc_url.as_deref().unwrap_or(&self.http_rpc_url)
+    }
+
+    /// Configured private relays in submission order (primary, then secondary); empty URLs are skipped.
+    pub fn private_relays(&self) -> Vec<RelayConfig> {
+        [(&self.private_rpc_url, self.private_rpc_auth), (&self.secondary_private_rpc_url, self.secondary_private_rpc_auth)]
+            .into_iter()
+            .filter_map(|(url, auth_scheme)| url.as_ref().filter(|u| !u.is_empty()).map(|url| RelayConfig { url: url.clone(), auth_scheme }))
+            .collect()
+    }
 }
 
 // --- Parsing helpers ---
 fn parse_address_env(var_name: &str) -> Result<Address> { let s = env::var(var_name)?; s.parse().map_err(|e| eyre!("Invalid address format for {}: {}", var_name, e)).wrap_err_with(|| format!("Failed to parse env var {}", var_name)) }
+// Falls back to the chain preset's address when the var is unset or empty
+fn parse_address_env_or(var_name: &str, preset: Address) -> Result<Address> {
+    match env::var(var_name) {
+        Ok(s) if !s.is_empty() => parse_address_env(var_name),
+        _ if !preset.is_zero() => Ok(preset),
+        _ => Err(eyre!("Need {} (not provided by the chain preset)", var_name)),
+    }
+}
 fn parse_optional_address_env(var_name: &str) -> Result<Option<Address>> {
     match env::var(var_name) {
         Ok(s) if s.is_empty() => Ok(None),
@@ -77,6 +385,13 @@ fn parse_optional_address_env(var_name: &str) -> Result<Option<Address>> {
     }
 }
 fn parse_u8_env(var_name: &str) -> Result<u8> { let s = env::var(var_name)?; s.parse().map_err(|e| eyre!("Invalid u8 format for {}: {}", var_name, e)).wrap_err_with(|| format!("Failed to parse env var {}", var_name)) }
+fn parse_u8_env_or(var_name: &str, preset: Option<u8>) -> Result<u8> {
+    match (env::var(var_name), preset) {
+        (Ok(s), _) if !s.is_empty() => parse_u8_env(var_name),
+        (_, Some(decimals)) => Ok(decimals),
+        _ => parse_u8_env(var_name),
+    }
+}
 fn parse_f64_env(var_name: &str, default: f64) -> f64 { env::var(var_name).ok().and_then(|s| s.parse().ok()).unwrap_or_else(|| { warn!("Using default f64 for {}: {}", var_name, default); default }) }
 fn parse_optional_f64_env(var_name: &str) -> Result<Option<f64>> {
     match env::var(var_name) {
@@ -96,6 +411,15 @@ fn parse_optional_u64_env(var_name: &str) -> Result<Option<u64>> {
         Err(e) => Err(eyre!(e).wrap_err(format!("Error checking env var {}", var_name))),
     }
 }
+// Comma-separated address list; None when the var is unset or empty
+fn parse_optional_address_list_env(var_name: &str) -> Result<Option<Vec<Address>>> {
+    match env::var(var_name) {
+        Ok(s) if s.trim().is_empty() => Ok(None),
+        Ok(s) => s.split(',').map(|a| a.trim().parse::<Address>().map_err(|e| eyre!("Invalid address '{}' in {}: {}", a.trim(), var_name, e))).collect::<Result<Vec<_>>>().map(Some),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(eyre!(e).wrap_err(format!("Error checking env var {}", var_name))),
+    }
+}
 // Updated parse_bool_env to explicitly default to false if var not present or invalid
 fn parse_bool_env(var_name: &str) -> bool {
     env::var(var_name)
@@ -115,55 +439,188 @@ pub fn load_config() -> Result<Config> {
     info!("Loading configuration..."); dotenv().ok();
     // --- Load Required Vars ---
     let ws_rpc_url = env::var("WS_RPC_URL")?; let http_rpc_url = env::var("HTTP_RPC_URL")?; let local_private_key = env::var("LOCAL_PRIVATE_KEY")?;
-    let uniswap_v3_factory_addr = parse_address_env("UNISWAP_V3_FACTORY_ADDR")?; let velodrome_v2_factory_addr = parse_address_env("VELODROME_V2_FACTORY_ADDR")?;
-    let weth_address = parse_address_env("WETH_ADDRESS")?; let usdc_address = parse_address_env("USDC_ADDRESS")?;
-    let velo_router_addr = parse_address_env("VELO_V2_ROUTER_ADDR")?; let balancer_vault_address = parse_address_env("BALANCER_VAULT_ADDRESS")?;
-    let quoter_v2_address = parse_address_env("QUOTER_V2_ADDRESS")?;
-    let weth_decimals = parse_u8_env("WETH_DECIMALS")?; let usdc_decimals = parse_u8_env("USDC_DECIMALS")?;
+    let read_rpc_url = env::var("READ_RPC_URL").ok().filter(|s| !s.is_empty()); let write_rpc_url = env::var("WRITE_RPC_URL").ok().filter(|s| !s.is_empty());
+    let chain_preset = env::var("CHAIN_PRESET").ok().filter(|s| !s.is_empty());
+    let preset = match &chain_preset {
+        Some(s) => s.parse::<ChainPreset>().wrap_err("Failed to parse CHAIN_PRESET")?.config(),
+        None => Config::default(),
+    };
+    let uniswap_v3_factory_addr = parse_address_env_or("UNISWAP_V3_FACTORY_ADDR", preset.uniswap_v3_factory_addr)?; let velodrome_v2_factory_addr = parse_address_env_or("VELODROME_V2_FACTORY_ADDR", preset.velodrome_v2_factory_addr)?;
+    let weth_address = parse_address_env_or("WETH_ADDRESS", preset.weth_address)?; let usdc_address = parse_address_env_or("USDC_ADDRESS", preset.usdc_address)?;
+    let usdc_addresses = parse_optional_address_list_env("USDC_ADDRESSES")?.unwrap_or_default();
+    let velo_router_addr = parse_address_env_or("VELO_V2_ROUTER_ADDR", preset.velo_router_addr)?; let balancer_vault_address = parse_address_env_or("BALANCER_VAULT_ADDRESS", preset.balancer_vault_address)?;
+    let quoter_v2_address = parse_address_env_or("QUOTER_V2_ADDRESS", preset.quoter_v2_address)?;
+    let weth_decimals = parse_u8_env_or("WETH_DECIMALS", chain_preset.is_some().then_some(preset.weth_decimals))?; let usdc_decimals = parse_u8_env_or("USDC_DECIMALS", chain_preset.is_some().then_some(preset.usdc_decimals))?;
 
     // --- Load Optional DEX Expansion ---
-    let aerodrome_factory_addr = parse_optional_address_env("AERODROME_FACTORY_ADDR")?; let aerodrome_router_addr = parse_optional_address_env("AERODROME_ROUTER_ADDR")?;
+    let aerodrome_factory_addr = parse_optional_address_env("AERODROME_FACTORY_ADDR")?.or(preset.aerodrome_factory_addr); let aerodrome_router_addr = parse_optional_address_env("AERODROME_ROUTER_ADDR")?.or(preset.aerodrome_router_addr);
 
     // --- Deployment Options ---
     let deploy_executor = parse_bool_env("DEPLOY_EXECUTOR"); let mut executor_bytecode_path = String::new(); let arb_executor_address = parse_optional_address_env("ARBITRAGE_EXECUTOR_ADDRESS")?;
-    if deploy_executor { executor_bytecode_path = env::var("EXECUTOR_BYTECODE_PATH")?; } else if arb_executor_address.is_none() { return Err(eyre!("Need ARBITRAGE_EXECUTOR_ADDRESS")); }
+    let build_dir = build_dir();
+    if deploy_executor { executor_bytecode_path = env::var("EXECUTOR_BYTECODE_PATH").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| resolve_artifact_path(&build_dir, DEFAULT_BUILD_DIR, EXECUTOR_BYTECODE_FILE).display().to_string()); } else if arb_executor_address.is_none() { return Err(eyre!("Need ARBITRAGE_EXECUTOR_ADDRESS")); }
+    let executor_abi_version = match env::var("EXECUTOR_ABI_VERSION") {
+        Ok(s) if !s.is_empty() => s.parse::<ExecutorAbiVersion>().wrap_err("Failed to parse EXECUTOR_ABI_VERSION")?,
+        _ => ExecutorAbiVersion::default(),
+    };
 
     // --- Load Optimization & Numeric Vars ---
     let min_loan_amount_weth = parse_f64_env("MIN_LOAN_AMOUNT_WETH", 0.1); let max_loan_amount_weth = parse_f64_env("MAX_LOAN_AMOUNT_WETH", 100.0);
     let optimal_loan_search_iterations = parse_u32_env("OPTIMAL_LOAN_SEARCH_ITERATIONS", 10);
+    let sim_probe_concurrency = (parse_u64_env("SIM_PROBE_CONCURRENCY", 8) as usize).max(1);
     let fetch_timeout_secs = parse_optional_u64_env("FETCH_TIMEOUT_SECS")?;
+    let min_fetch_timeout_secs = parse_u64_env("MIN_FETCH_TIMEOUT_SECS", 2);
+    let max_fetch_timeout_secs = parse_u64_env("MAX_FETCH_TIMEOUT_SECS", 30);
+    let fetch_max_retries = parse_u32_env("FETCH_MAX_RETRIES", 3);
+    let fetch_base_delay_ms = parse_u64_env("FETCH_BASE_DELAY_MS", 200);
+    if max_fetch_timeout_secs > 0 && min_fetch_timeout_secs > max_fetch_timeout_secs { return Err(eyre!("MIN_FETCH_TIMEOUT_SECS ({}) exceeds MAX_FETCH_TIMEOUT_SECS ({})", min_fetch_timeout_secs, max_fetch_timeout_secs)); }
+    let enumeration_concurrency = parse_u64_env("ENUMERATION_CONCURRENCY", 16).max(1) as usize;
+    let enumeration_timeout_secs = parse_u64_env("ENUMERATION_TIMEOUT_SECS", 600);
+    let pool_discovery = match env::var("POOL_DISCOVERY") {
+        Ok(s) if !s.is_empty() => s.parse::<PoolDiscovery>().wrap_err("Failed to parse POOL_DISCOVERY")?,
+        _ => PoolDiscovery::default(),
+    };
+    let initial_block_history_to_scan = parse_u64_env("INITIAL_BLOCK_HISTORY_TO_SCAN", 5_000_000);
+    let max_block_range_per_query = parse_u64_env("MAX_BLOCK_RANGE_PER_QUERY", 10_000).max(1);
+    let univ3_fee_tiers = match env::var("UNIV3_FEE_TIERS") {
+        Ok(s) if !s.trim().is_empty() => s.split(',').map(|f| f.trim().parse::<u32>().map_err(|e| eyre!("Invalid fee tier '{}' in UNIV3_FEE_TIERS: {}", f.trim(), e))).collect::<Result<Vec<_>>>()?,
+        _ => DEFAULT_UNIV3_FEE_TIERS.to_vec(),
+    };
+    let monitored_events = match env::var("MONITORED_EVENTS") {
+        Ok(s) if !s.trim().is_empty() => s.split(',').map(|e| e.parse::<MonitoredEvent>()).collect::<Result<Vec<_>>>().wrap_err("Failed to parse MONITORED_EVENTS")?,
+        _ => DEFAULT_MONITORED_EVENTS.to_vec(),
+    };
     let enable_univ3_dynamic_sizing = parse_bool_env("ENABLE_UNIV3_DYNAMIC_SIZING");
+    let pricing_shadow_mode = parse_bool_env("PRICING_SHADOW_MODE");
+    let executor_callstatic_check = parse_bool_env("EXECUTOR_CALLSTATIC_CHECK");
+    let allow_submission_zero_profit = parse_bool_env("ALLOW_SUBMISSION_ZERO_PROFIT");
+    let production = parse_bool_env("PRODUCTION");
+    let sim_divergence_tolerance_bps = parse_u64_env("SIM_DIVERGENCE_TOLERANCE_BPS", 50); // Default 0.5% of the quoted output
+    if sim_divergence_tolerance_bps == 0 {
+        warn!("SIM_DIVERGENCE_TOLERANCE_BPS=0: quoted leg outputs are never compared with the executor eth_call.");
+    } else if !executor_callstatic_check {
+        warn!("SIM_DIVERGENCE_TOLERANCE_BPS only applies with EXECUTOR_CALLSTATIC_CHECK; quoted leg outputs are not compared with the executor.");
+    }
+    let max_price_impact_bps = parse_u64_env("MAX_PRICE_IMPACT_BPS", 0);
+    let max_price_jump_bps = parse_u64_env("MAX_PRICE_JUMP_BPS", 0);
+    let univ3_tick_window_words = parse_u32_env("UNIV3_TICK_WINDOW_WORDS", 1);
+    let twap_window_secs = parse_u32_env("TWAP_WINDOW_SECS", 0);
+    let max_spot_twap_deviation_bps = parse_u64_env("MAX_SPOT_TWAP_DEVIATION_BPS", 0);
+    let default_token_decimals = parse_u8_env_or("DEFAULT_TOKEN_DECIMALS", Some(18))?;
+    let skip_unknown_decimals = parse_bool_env("SKIP_UNKNOWN_DECIMALS");
+    let competition_priority_fee_bps_of_profit = parse_u64_env("COMPETITION_PRIORITY_FEE_BPS_OF_PROFIT", 0).min(10_000);
+    let opportunity_ttl_ms = parse_u64_env("OPPORTUNITY_TTL_MS", 1500); // Default: under one 2s L2 block
+    let salt_expiry_blocks = parse_u64_env("SALT_EXPIRY_BLOCKS", 50);
+    let confirmations_required = parse_u64_env("CONFIRMATIONS_REQUIRED", 1).max(1);
+    let enable_mempool = parse_bool_env("ENABLE_MEMPOOL");
+    let max_blocks_late = parse_u64_env("MAX_BLOCKS_LATE", 0); // Default: only submit for the block right after detection
+    let pool_blocklist = parse_optional_address_list_env("POOL_BLOCKLIST")?.unwrap_or_default();
+    let pool_allowlist = parse_optional_address_list_env("POOL_ALLOWLIST")?;
+    let full_refresh_interval_secs = parse_u64_env("FULL_REFRESH_INTERVAL_SECS", 300);
+    let flash_loan_fee_refresh_secs = parse_u64_env("FLASH_LOAN_FEE_REFRESH_SECS", 3600);
+    let max_routes_to_evaluate = parse_u64_env("MAX_ROUTES_TO_EVALUATE", 5) as usize;
+    let pool_failure_cooldown_secs = parse_u64_env("POOL_FAILURE_COOLDOWN_SECS", 300);
+    let route_score_profit_weight = parse_f64_env("ROUTE_SCORE_PROFIT_WEIGHT", 1.0);
+    let route_score_liquidity_weight = parse_f64_env("ROUTE_SCORE_LIQUIDITY_WEIGHT", 0.1);
+    let route_score_age_weight = parse_f64_env("ROUTE_SCORE_AGE_WEIGHT", 0.1);
+    let route_score_mature_blocks = parse_u64_env("ROUTE_SCORE_MATURE_BLOCKS", 43_200); // Default: one day of 2s blocks
+    let max_slippage_bps = parse_u64_env("MAX_SLIPPAGE_BPS", 50);
+    if max_slippage_bps > 10_000 { return Err(eyre!("MAX_SLIPPAGE_BPS must be at most 10000, got {}", max_slippage_bps)); }
 
     // --- Load Gas Vars ---
     let max_priority_fee_per_gas_gwei = parse_f64_env("MAX_PRIORITY_FEE_PER_GAS_GWEI", 0.01);
     let fallback_gas_price_gwei = parse_optional_f64_env("FALLBACK_GAS_PRICE_GWEI")?;
+    let simulation_gas_price_multiplier = parse_f64_env("SIMULATION_GAS_PRICE_MULTIPLIER", 1.0);
+    let gas_token_price_in_loan_token = parse_optional_f64_env("GAS_TOKEN_PRICE_IN_LOAN_TOKEN")?;
+    if gas_token_price_in_loan_token.is_some_and(|p| !p.is_finite() || p <= 0.0) { return Err(eyre!("GAS_TOKEN_PRICE_IN_LOAN_TOKEN must be a positive number")); }
     let gas_limit_buffer_percentage = parse_u64_env("GAS_LIMIT_BUFFER_PERCENTAGE", 25); let min_flashloan_gas_limit = parse_u64_env("MIN_FLASHLOAN_GAS_LIMIT", 400_000);
-    let chain_id = parse_optional_u64_env("CHAIN_ID")?;
+    let learned_gas_min_samples = parse_u64_env("LEARNED_GAS_MIN_SAMPLES", 0) as usize;
+    let submission_gas_limit_default = parse_u64_env("SUBMISSION_GAS_LIMIT_DEFAULT", 1_500_000);
+    let max_gas_limit = parse_u64_env("MAX_GAS_LIMIT", 3_000_000);
+    let gas_profiles = match env::var("GAS_PROFILES") {
+        Ok(s) => parse_gas_profiles(&s).wrap_err("Failed to parse GAS_PROFILES")?,
+        Err(_) => HashMap::new(),
+    };
+    let chain_id = parse_optional_u64_env("CHAIN_ID")?.or(preset.chain_id);
+    let priority_fee_strategy = match env::var("PRIORITY_FEE_STRATEGY") {
+        Ok(s) if !s.is_empty() => Some(s.parse::<PriorityFeeStrategy>().wrap_err("Failed to parse PRIORITY_FEE_STRATEGY")?),
+        _ => None,
+    };
 
     // --- Load Profitability Vars ---
     let min_profit_buffer_bps = parse_u64_env("MIN_PROFIT_BUFFER_BPS", 10); // Default 10 BPS (0.10%)
     let min_profit_abs_buffer_wei_str = parse_string_env("MIN_PROFIT_ABS_BUFFER_WEI", "5000000000000"); // Default 0.000005 WETH equivalent (adjust based on typical gas costs)
+    let chainlink_eth_usd_feed = parse_optional_address_env("CHAINLINK_ETH_USD_FEED")?;
+    let profit_sharing_bps_for_devs = parse_u64_env("PROFIT_SHARING_BPS_FOR_DEVS", 0);
+    let profit_share_recipient = parse_optional_address_env("PROFIT_SHARE_RECIPIENT")?;
+    if profit_sharing_bps_for_devs > 10_000 { return Err(eyre!("PROFIT_SHARING_BPS_FOR_DEVS must be at most 10000, got {}", profit_sharing_bps_for_devs)); }
+    let profit_bias_warn_bps = parse_u64_env("PROFIT_BIAS_WARN_BPS", 2000); // Default 20% of predicted profit
+    let velo_slippage_correction_bps = parse_u64_env("VELO_SLIPPAGE_CORRECTION_BPS", 0).min(10_000);
+    if profit_sharing_bps_for_devs > 0 && profit_share_recipient.is_none() { return Err(eyre!("Need PROFIT_SHARE_RECIPIENT when PROFIT_SHARING_BPS_FOR_DEVS is set")); }
+    if env::var("PROFIT_TOKEN").is_ok_and(|s| !s.trim().is_empty()) { return Err(eyre!("PROFIT_TOKEN is not supported: the executor always realizes profit in the loan token")); }
 
     // --- Load Optional String Vars ---
     let private_rpc_url = env::var("PRIVATE_RPC_URL").ok(); let secondary_private_rpc_url = env::var("SECONDARY_PRIVATE_RPC_URL").ok();
+    let private_rpc_auth = env::var("PRIVATE_RPC_AUTH").unwrap_or_default().parse::<RelayAuthScheme>().wrap_err("Failed to parse PRIVATE_RPC_AUTH")?;
+    let secondary_private_rpc_auth = env::var("SECONDARY_PRIVATE_RPC_AUTH").unwrap_or_default().parse::<RelayAuthScheme>().wrap_err("Failed to parse SECONDARY_PRIVATE_RPC_AUTH")?;
+    let concurrent_relay_submission = parse_bool_env("CONCURRENT_RELAY_SUBMISSION");
+    let auto_unwrap_profit = parse_bool_env("AUTO_UNWRAP_PROFIT");
+    let auto_wrap_eth = parse_bool_env("AUTO_WRAP_ETH");
 
     // --- Load Health Check Vars --- Added
     let critical_block_lag_seconds = parse_u64_env("CRITICAL_BLOCK_LAG_SECONDS", 300); // Default 300s
     let critical_log_lag_seconds = parse_u64_env("CRITICAL_LOG_LAG_SECONDS", 300); // Default 300s
+    let min_gas_balance_wei_str = parse_string_env("MIN_GAS_BALANCE_WEI", "2000000000000000"); // Default 0.002 ETH
+    let min_gas_balance_wei = U256::from_dec_str(&min_gas_balance_wei_str).map_err(|e| eyre!("Invalid MIN_GAS_BALANCE_WEI {}: {}", min_gas_balance_wei_str, e))?;
+    let gas_balance_check_interval_secs = parse_u64_env("GAS_BALANCE_CHECK_INTERVAL_SECS", 60);
+    let min_weth_working_balance_str = parse_string_env("MIN_WETH_WORKING_BALANCE", "0");
+    let min_weth_working_balance = U256::from_dec_str(&min_weth_working_balance_str).map_err(|e| eyre!("Invalid MIN_WETH_WORKING_BALANCE {}: {}", min_weth_working_balance_str, e))?;
+    let rebalance_interval_secs = parse_u64_env("REBALANCE_INTERVAL_SECS", 300);
+    let nonce_divergence_blocks = parse_u64_env("NONCE_DIVERGENCE_BLOCKS", 0);
+    let nonce_check_interval_secs = parse_u64_env("NONCE_CHECK_INTERVAL_SECS", 30).max(1);
+    let alert_webhook_url = env::var("ALERT_WEBHOOK_URL").ok().filter(|s| !s.is_empty());
+    let alert_profit_threshold_weth = parse_string_env("ALERT_PROFIT_THRESHOLD_WETH", "0.05");
+    let alert_profit_threshold_wei: U256 = parse_units(&alert_profit_threshold_weth, weth_decimals as u32)
+        .map_err(|e| eyre!("Invalid ALERT_PROFIT_THRESHOLD_WETH {}: {}", alert_profit_threshold_weth, e))?.into();
+    let admin_addr = env::var("ADMIN_ADDR").ok().filter(|s| !s.is_empty());
+    let audit_log_path = env::var("AUDIT_LOG_PATH").ok().filter(|s| !s.is_empty());
+    let log_snapshot_diffs = parse_bool_env("LOG_SNAPSHOT_DIFFS");
+    let log_failed_calldata = parse_bool_env("LOG_FAILED_CALLDATA");
+    let skip_contract_preflight = parse_bool_env("SKIP_CONTRACT_PREFLIGHT");
+    let nonce_resync_retries = parse_u32_env("NONCE_RESYNC_RETRIES", 1);
+    let max_monitored_pools = parse_u64_env("MAX_MONITORED_POOLS", 0) as usize;
+    let min_submission_interval_ms = parse_u64_env("MIN_SUBMISSION_INTERVAL_MS", 0);
+    let submission_warmup_secs = parse_u64_env("SUBMISSION_WARMUP_SECS", 0);
+    let min_pools_before_trading = parse_u64_env("MIN_POOLS_BEFORE_TRADING", 0) as usize;
+    let min_pool_submission_interval_ms = parse_u64_env("MIN_POOL_SUBMISSION_INTERVAL_MS", 0);
+    let max_total_inflight_loan_wei_str = parse_string_env("MAX_TOTAL_INFLIGHT_LOAN_WEI", "0");
+    let max_total_inflight_loan_wei = U256::from_dec_str(&max_total_inflight_loan_wei_str).map_err(|e| eyre!("Invalid MAX_TOTAL_INFLIGHT_LOAN_WEI {}: {}", max_total_inflight_loan_wei_str, e))?;
+    let seen_log_cache_size = parse_u64_env("SEEN_LOG_CACHE_SIZE", 4096) as usize;
+    let self_test_rpc_url = env::var("SELF_TEST_RPC_URL").ok().filter(|s| !s.is_empty());
+    let snapshot_dump_path = env::var("SNAPSHOT_DUMP_PATH").ok().filter(|s| !s.is_empty());
+    let pool_cache_path = env::var("POOL_CACHE_PATH").ok().filter(|s| !s.is_empty());
+    let lock_file_path = env::var("LOCK_FILE_PATH").ok().filter(|s| !s.is_empty());
+    let executor_residual_check = parse_bool_env("EXECUTOR_RESIDUAL_CHECK");
+    let executor_dust_threshold_str = parse_string_env("EXECUTOR_DUST_THRESHOLD", "1000");
+    let executor_dust_threshold = U256::from_dec_str(&executor_dust_threshold_str).map_err(|e| eyre!("Invalid EXECUTOR_DUST_THRESHOLD {}: {}", executor_dust_threshold_str, e))?;
+    let sweep_recipient = parse_optional_address_env("SWEEP_RECIPIENT")?;
 
 
     // --- Construct Config ---
     let config = Config {
-        ws_rpc_url, http_rpc_url, local_private_key, chain_id, arb_executor_address,
+        ws_rpc_url, http_rpc_url, read_rpc_url, write_rpc_url, local_private_key, chain_id, chain_preset, arb_executor_address,
         uniswap_v3_factory_addr, velodrome_v2_factory_addr, balancer_vault_address, quoter_v2_address,
-        velo_router_addr, aerodrome_factory_addr, aerodrome_router_addr, weth_address, usdc_address,
-        weth_decimals, usdc_decimals, deploy_executor, executor_bytecode_path, min_loan_amount_weth,
-        max_loan_amount_weth, optimal_loan_search_iterations, fetch_timeout_secs,
-        enable_univ3_dynamic_sizing,
-        max_priority_fee_per_gas_gwei, fallback_gas_price_gwei,
-        gas_limit_buffer_percentage, min_flashloan_gas_limit, private_rpc_url, secondary_private_rpc_url,
-        min_profit_buffer_bps, min_profit_abs_buffer_wei_str,
-        critical_block_lag_seconds, critical_log_lag_seconds, // Added fields
+        velo_router_addr, aerodrome_factory_addr, aerodrome_router_addr, weth_address, usdc_address, usdc_addresses,
+        weth_decimals, usdc_decimals, deploy_executor, executor_bytecode_path, build_dir, executor_abi_version, min_loan_amount_weth,
+        max_loan_amount_weth, optimal_loan_search_iterations, fetch_timeout_secs, min_fetch_timeout_secs, max_fetch_timeout_secs, fetch_max_retries, fetch_base_delay_ms, enumeration_concurrency, enumeration_timeout_secs, pool_discovery, initial_block_history_to_scan, max_block_range_per_query, univ3_fee_tiers, monitored_events,
+        enable_univ3_dynamic_sizing, opportunity_ttl_ms, salt_expiry_blocks, confirmations_required, enable_mempool, max_blocks_late, pool_blocklist, pool_allowlist, full_refresh_interval_secs, pool_failure_cooldown_secs, max_routes_to_evaluate,
+        route_score_profit_weight, route_score_liquidity_weight, route_score_age_weight, route_score_mature_blocks, max_slippage_bps,
+        max_priority_fee_per_gas_gwei, fallback_gas_price_gwei, simulation_gas_price_multiplier, gas_token_price_in_loan_token,
+        gas_limit_buffer_percentage, learned_gas_min_samples, min_flashloan_gas_limit, submission_gas_limit_default, max_gas_limit, gas_profiles, priority_fee_strategy, private_rpc_url, secondary_private_rpc_url, private_rpc_auth, secondary_private_rpc_auth, concurrent_relay_submission, auto_unwrap_profit, auto_wrap_eth,
+        min_profit_buffer_bps, min_profit_abs_buffer_wei_str, chainlink_eth_usd_feed, profit_sharing_bps_for_devs, profit_share_recipient, profit_bias_warn_bps, velo_slippage_correction_bps,
+        critical_block_lag_seconds, critical_log_lag_seconds, min_gas_balance_wei, gas_balance_check_interval_secs, min_weth_working_balance, rebalance_interval_secs, nonce_divergence_blocks, nonce_check_interval_secs, // Added fields
+        alert_webhook_url, alert_profit_threshold_wei, admin_addr, audit_log_path, log_snapshot_diffs, log_failed_calldata, skip_contract_preflight, nonce_resync_retries, max_monitored_pools, snapshot_dump_path, pool_cache_path, min_submission_interval_ms, submission_warmup_secs, min_pools_before_trading, min_pool_submission_interval_ms, max_total_inflight_loan_wei, seen_log_cache_size, self_test_rpc_url, pricing_shadow_mode, max_price_impact_bps, max_price_jump_bps, univ3_tick_window_words, twap_window_secs, max_spot_twap_deviation_bps, default_token_decimals, skip_unknown_decimals, executor_callstatic_check, allow_submission_zero_profit, production, sim_divergence_tolerance_bps, flash_loan_fee_refresh_secs,
+        executor_residual_check, executor_dust_threshold, sweep_recipient, competition_priority_fee_bps_of_profit, sim_probe_concurrency, lock_file_path,
     };
     info!("✅ Config loaded."); debug!(?config); Ok(config)
 }
\ No newline at end of file
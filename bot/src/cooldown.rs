@@ -0,0 +1,77 @@
+// bot/src/cooldown.rs
+//! Per-pool cooldown after reverted submissions (`config.pool_failure_cooldown_secs`).
+//!
+//! Every on-chain revert of a route puts each of its pools on cooldown for the base period times the
+//! pool's consecutive strike count, so a pool that keeps reverting (stale state, unusual token) is
+//! retried less and less often. A confirmed success through a pool removes one strike. Routes touching
+//! a pool on cooldown are not evaluated.
+
+use crate::path_optimizer::RouteCandidate;
+use dashmap::DashMap;
+use ethers::types::Address;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Cap on the strike multiplier of the base cooldown.
+const MAX_STRIKES: u32 = 8;
+
+#[derive(Debug, Clone, Copy)]
+struct Cooldown {
+    strikes: u32,
+    until: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct PoolCooldowns {
+    entries: DashMap<Address, Cooldown>,
+}
+
+/// Pools a route trades through (buy and sell).
+pub(crate) fn route_pools(route: &RouteCandidate) -> impl Iterator<Item = Address> {
+    [route.buy_pool_addr, route.sell_pool_addr].into_iter()
+}
+
+impl PoolCooldowns {
+    /// Adds a strike to every pool of a reverted `route` and (re)starts its cooldown. A zero `base` disables cooldowns.
+    pub fn record_failure(&self, route: &RouteCandidate, base: Duration, now: Instant) {
+        if base.is_zero() {
+            return;
+        }
+        for pool in route_pools(route) {
+            let mut entry = self.entries.entry(pool).or_insert(Cooldown { strikes: 0, until: now });
+            entry.strikes = (entry.strikes + 1).min(MAX_STRIKES);
+            entry.until = now + base * entry.strikes;
+            warn!(%pool, strikes = entry.strikes, cooldown_secs = (base * entry.strikes).as_secs(), "Pool on cooldown after reverted submission.");
+        }
+    }
+
+    /// Removes one strike from every pool of a confirmed `route`; a pool without strikes leaves the map.
+    pub fn record_success(&self, route: &RouteCandidate) {
+        for pool in route_pools(route) {
+            let cleared = match self.entries.get_mut(&pool) {
+                Some(mut entry) => { entry.strikes = entry.strikes.saturating_sub(1); entry.strikes == 0 }
+                None => false,
+            };
+            if cleared {
+                self.entries.remove(&pool);
+                info!(%pool, "Pool cooldown cleared after successful arbitrage.");
+            }
+        }
+    }
+
+    /// Remaining cooldown of `pool`, None when it may be traded.
+    pub fn remaining(&self, pool: Address, now: Instant) -> Option<Duration> {
+        self.entries.get(&pool).and_then(|entry| entry.until.checked_duration_since(now)).filter(|d| !d.is_zero())
+    }
+
+    /// Whether any pool of `route` is cooling down.
+    pub fn route_cooling_down(&self, route: &RouteCandidate, now: Instant) -> bool {
+        route_pools(route).any(|pool| self.remaining(pool, now).is_some())
+    }
+
+    /// Number of pools currently cooling down. Strikes are kept after the cooldown ends.
+    pub fn active(&self, now: Instant) -> usize {
+        self.entries.iter().filter(|entry| entry.until > now).count()
+    }
+}
+// END OF FILE: bot/src/cooldown.rs
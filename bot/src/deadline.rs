@@ -0,0 +1,79 @@
+// bot/src/deadline.rs
+//! Latency budget for a single opportunity, from swap log receipt to transaction broadcast.
+//! Each pipeline stage checks the deadline and drops the work once it has expired, so a slow
+//! simulation never leads to a submission in a block where the opportunity is long gone.
+//!
+//! Opportunities are also pinned to a block: one detected off a swap log in block N targets N+1,
+//! and is dropped once the head has moved more than `config.max_blocks_late` blocks past that.
+
+use ethers::types::U64;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    started_at: Instant,
+    expires_at: Instant,
+}
+
+/// Returned when a pipeline stage is reached after the deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineExpired {
+    pub stage: &'static str,
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for DeadlineExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Opportunity deadline expired at stage '{}' ({}ms after detection)", self.stage, self.elapsed.as_millis())
+    }
+}
+
+impl std::error::Error for DeadlineExpired {}
+
+impl Deadline {
+    /// Starts the clock now with the given time-to-live.
+    pub fn after(ttl: Duration) -> Self {
+        let started_at = Instant::now();
+        Self { started_at, expires_at: started_at + ttl }
+    }
+
+    pub fn from_ttl_ms(ttl_ms: u64) -> Self {
+        Self::after(Duration::from_millis(ttl_ms))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+
+    /// Time left before expiry (zero once expired). Useful as a `tokio::time::timeout` budget.
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Fails with the stage name if the deadline has passed.
+    pub fn check(&self, stage: &'static str) -> Result<(), DeadlineExpired> {
+        if self.is_expired() {
+            return Err(DeadlineExpired { stage, elapsed: self.elapsed() });
+        }
+        Ok(())
+    }
+}
+
+/// Block an opportunity detected in `source_block` is submitted for.
+pub fn target_block(source_block: U64) -> U64 {
+    source_block.saturating_add(U64::one())
+}
+
+/// Blocks `head` is past the target of an opportunity from `source_block`; 0 when on time or when the
+/// source block is unknown (zero).
+pub fn blocks_late(source_block: U64, head: U64) -> u64 {
+    if source_block.is_zero() {
+        return 0;
+    }
+    head.saturating_sub(target_block(source_block)).as_u64()
+}
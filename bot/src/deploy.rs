@@ -1,13 +1,13 @@
 // src/deploy.rs
 
+use crate::config::{Config, ExecutorAbiVersion};
 use ethers::{
     abi::Abi,
-    // Removed unused Middleware
-    prelude::{ContractFactory, SignerMiddleware, Provider, Http, LocalWallet},
-    types::{Address, Bytes},
-    utils::hex,
+    prelude::{ContractFactory, Middleware, SignerMiddleware, Provider, Http, LocalWallet},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest},
+    utils::{hex, id},
 };
-use eyre::{Result, WrapErr};
+use eyre::{eyre, Result, WrapErr};
 // Removed unused future::Future
 use std::{path::Path, fs, sync::Arc};
 
@@ -27,8 +27,14 @@ pub async fn deploy_contract_from_bytecode(
     // 2. Decode hex bytecode
     let bytecode = hex::decode(cleaned_bytecode_hex)
         .wrap_err("Failed to decode hex bytecode")?;
-    let deploy_bytes = Bytes::from(bytecode);
+    deploy_bytecode(client, Bytes::from(bytecode)).await
+}
 
+/// Deploys contract creation code.
+pub async fn deploy_bytecode(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    deploy_bytes: Bytes,
+) -> Result<Address> {
     // 3. Construct ContractFactory
     let factory = ContractFactory::new(
         Abi::default(),
@@ -51,4 +57,80 @@ pub async fn deploy_contract_from_bytecode(
     // TODO: Optionally wait for receipt here if needed
 
     Ok(contract_address)
-}
\ No newline at end of file
+}
+
+/// Executor entry points the bot relies on for `version`: Balancer's flash loan callback, the UniV3 swap
+/// callback and the profit sweep, plus the owner's `executeFlashLoan` that 2.4 loans are started through.
+pub fn required_executor_selectors(version: ExecutorAbiVersion) -> Vec<(&'static str, [u8; 4])> {
+    let mut signatures = vec![
+        "receiveFlashLoan(address[],uint256[],uint256[],bytes)",
+        "uniswapV3SwapCallback(int256,int256,bytes)",
+        "withdrawToken(address,address)",
+    ];
+    if version == ExecutorAbiVersion::V2_4 {
+        signatures.push("executeFlashLoan(address,uint256,bytes)");
+    }
+    signatures.into_iter().map(|sig| (sig, id(sig))).collect()
+}
+
+/// The `expected` selectors that no PUSH1-PUSH4 in `code` pushes, i.e. that the dispatcher cannot match.
+/// Push data is skipped while walking, so selector-like bytes inside other immediates do not count.
+pub fn missing_selectors(code: &[u8], expected: &[(&'static str, [u8; 4])]) -> Vec<&'static str> {
+    const PUSH1: u8 = 0x60;
+    const PUSH32: u8 = 0x7f;
+    let mut pushed = std::collections::HashSet::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let op = code[pc];
+        if (PUSH1..=PUSH32).contains(&op) {
+            let len = (op - PUSH1 + 1) as usize;
+            let data = &code[(pc + 1).min(code.len())..(pc + 1 + len).min(code.len())];
+            if len <= 4 {
+                // Compilers may drop leading zero bytes of a selector constant
+                let mut word = [0u8; 4];
+                word[4 - data.len()..].copy_from_slice(data);
+                pushed.insert(word);
+            }
+            pc += len;
+        }
+        pc += 1;
+    }
+    expected.iter().filter(|(_, selector)| !pushed.contains(selector)).map(|(sig, _)| *sig).collect()
+}
+
+/// Fails unless the code deployed at `executor` dispatches every selector `required_executor_selectors`
+/// lists for the configured executor ABI version.
+/// Catches an executor built from a different ABI version before its reverts show up on-chain.
+pub async fn verify_executor_selectors<M: Middleware>(client: &M, executor: Address, config: &Config) -> Result<()>
+where
+    M::Error: 'static + Send + Sync,
+{
+    let code = client.get_code(executor, None).await.wrap_err("Failed to read executor code")?;
+    if code.is_empty() {
+        return Err(eyre!("No contract code at executor address {:?}", executor));
+    }
+    let missing = missing_selectors(&code, &required_executor_selectors(config.executor_abi_version));
+    if !missing.is_empty() {
+        return Err(eyre!(
+            "Executor {:?} does not implement {} (configured executor ABI {:?}); redeploy an executor built for this bot version",
+            executor, missing.join(", "), config.executor_abi_version,
+        ));
+    }
+    Ok(())
+}
+
+/// Init code `PUSH1 0 TLOAD POP STOP`: executes only where EIP-1153 transient storage (Cancun) is active.
+pub const TRANSIENT_STORAGE_PROBE: [u8; 5] = [0x60, 0x00, 0x5c, 0x50, 0x00];
+
+/// Fails unless the chain behind `client` runs TLOAD, checked with an `eth_call` of `TRANSIENT_STORAGE_PROBE`
+/// as contract creation. The executor keeps its flash loan and swap callback guards in transient storage,
+/// so on a pre-Cancun chain (or fork) every arbitrage would revert.
+pub async fn verify_transient_storage<M: Middleware>(client: &M) -> Result<()>
+where
+    M::Error: 'static + Send + Sync,
+{
+    let probe: TypedTransaction = TransactionRequest::new().data(Bytes::from(TRANSIENT_STORAGE_PROBE.to_vec())).into();
+    client.call(&probe, None).await.map(drop).map_err(|e| eyre!(
+        "Chain does not execute TLOAD ({}); the executor needs EIP-1153 transient storage (Cancun or later)", e,
+    ))
+}
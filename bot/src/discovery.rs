@@ -0,0 +1,129 @@
+// bot/src/discovery.rs
+//! Startup pool discovery from factory `PoolCreated` logs (`POOL_DISCOVERY=logs`).
+//!
+//! Backfills the `PoolCreated` logs of the UniV3, Velodrome and Aerodrome factories over the last
+//! `config.initial_block_history_to_scan` blocks, `config.max_block_range_per_query` blocks per
+//! `eth_getLogs` and `config.enumeration_concurrency` queries at a time. The events carry the tokens, the
+//! UniV3 fee and the Velo/Aero stable flag, so target pairs are selected without per-pool calls. Pools
+//! created before the scanned range are not found; the startup UniV3 `getPool` lookup still covers the
+//! target pair's fee tiers. Only the fee tiers in `config.enabled_univ3_fee_tiers()` are kept.
+
+use crate::bindings::{
+    i_uniswap_v3_factory::PoolCreatedFilter as UniV3PoolCreatedFilter,
+    i_velodrome_factory::PoolCreatedFilter as VeloPoolCreatedFilter,
+};
+use crate::config::Config;
+use crate::retry::with_retries;
+use crate::state::DexType;
+use crate::{UNI_V3_POOL_CREATED_TOPIC, VELO_AERO_POOL_CREATED_TOPIC};
+use ethers::contract::EthLogDecode;
+use ethers::prelude::Middleware;
+use ethers::types::{Address, Filter, Log, H256, U64};
+use eyre::{eyre, Result};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use tracing::{debug, info, trace};
+
+/// A pool found in a factory's `PoolCreated` log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPool {
+    pub pool: Address,
+    pub dex_type: DexType,
+    pub factory: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub uni_fee: Option<u32>,
+    pub velo_stable: Option<bool>,
+    pub created_block: Option<U64>,
+}
+
+/// Splits `from..=to` into consecutive inclusive ranges of at most `max_range` blocks.
+pub fn block_ranges(from: u64, to: u64, max_range: u64) -> Vec<(u64, u64)> {
+    let max_range = max_range.max(1);
+    let mut ranges = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(max_range - 1).min(to);
+        ranges.push((start, end));
+        if end == u64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+    ranges
+}
+
+/// Decodes a `PoolCreated` log emitted by one of `config`'s factories. None for other logs. Velodrome V2
+/// and Aerodrome (its fork) emit the same event, with `stable` indexed; the emitting factory names the DEX.
+pub fn decode_pool_created(log: &Log, config: &Config) -> Option<DiscoveredPool> {
+    let topic0 = *log.topics.first()?;
+    let raw = log.clone().into();
+    if topic0 == *UNI_V3_POOL_CREATED_TOPIC && log.address == config.uniswap_v3_factory_addr {
+        let event = <UniV3PoolCreatedFilter as EthLogDecode>::decode_log(&raw).ok()?;
+        return Some(DiscoveredPool {
+            pool: event.pool, dex_type: DexType::UniswapV3, factory: log.address, token0: event.token_0, token1: event.token_1,
+            uni_fee: Some(event.fee), velo_stable: None, created_block: log.block_number,
+        });
+    }
+    if topic0 != *VELO_AERO_POOL_CREATED_TOPIC {
+        return None;
+    }
+    let dex_type = if log.address == config.velodrome_v2_factory_addr {
+        DexType::VelodromeV2
+    } else if Some(log.address) == config.aerodrome_factory_addr {
+        DexType::Aerodrome
+    } else {
+        return None;
+    };
+    let event = <VeloPoolCreatedFilter as EthLogDecode>::decode_log(&raw).ok()?;
+    Some(DiscoveredPool {
+        pool: event.pool, dex_type, factory: log.address, token0: event.token_0, token1: event.token_1,
+        uni_fee: None, velo_stable: Some(event.stable), created_block: log.block_number,
+    })
+}
+
+/// `(token0, token1, fee)` of every UniV3 pool the startup `getPool` lookup probes: `base` against each
+/// quote token (tokens sorted as the factory expects) in each of `fee_tiers`.
+pub fn univ3_pool_keys(base: Address, quote_tokens: &[Address], fee_tiers: &[u32]) -> Vec<(Address, Address, u32)> {
+    quote_tokens.iter()
+        .flat_map(|&quote| fee_tiers.iter().map(move |&fee| if base < quote { (base, quote, fee) } else { (quote, base, fee) }))
+        .collect()
+}
+
+/// Backfills the factories' `PoolCreated` logs up to block `head` and returns the decoded pools, oldest
+/// first. Each chunk is retried like a pool fetch; a chunk that still fails fails the backfill.
+pub async fn backfill_pool_created<M>(client: &M, config: &Config, head: u64) -> Result<Vec<DiscoveredPool>>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    let mut factories = vec![config.uniswap_v3_factory_addr, config.velodrome_v2_factory_addr];
+    factories.extend(config.aerodrome_factory_addr);
+    let topics: Vec<H256> = vec![*UNI_V3_POOL_CREATED_TOPIC, *VELO_AERO_POOL_CREATED_TOPIC];
+    let from = if config.initial_block_history_to_scan == 0 { 0 } else { head.saturating_sub(config.initial_block_history_to_scan - 1) };
+    let ranges = block_ranges(from, head, config.max_block_range_per_query);
+    info!(from, to = head, queries = ranges.len(), "Backfilling PoolCreated logs...");
+
+    let chunks: Vec<Vec<Log>> = stream::iter(ranges)
+        .map(|(start, end)| {
+            let filter = Filter::new().address(factories.clone()).topic0(topics.clone()).from_block(start).to_block(end);
+            async move {
+                let logs = with_retries(config.fetch_max_retries, config.fetch_base_delay_ms, "PoolCreated backfill", || async {
+                    client.get_logs(&filter).await.map_err(|e| eyre!("eth_getLogs {}-{} failed: {}", start, end, e))
+                }).await?;
+                trace!(start, end, logs = logs.len(), "PoolCreated chunk fetched.");
+                Ok::<_, eyre::Report>(logs)
+            }
+        })
+        .buffered(config.enumeration_concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    let fee_tiers = config.enabled_univ3_fee_tiers();
+    let pools: Vec<DiscoveredPool> = chunks.iter().flatten()
+        .filter_map(|log| decode_pool_created(log, config))
+        .filter(|p| p.uni_fee.is_none_or(|fee| fee_tiers.contains(&fee)))
+        .collect();
+    debug!(pools = pools.len(), "PoolCreated backfill complete.");
+    Ok(pools)
+}
+// END OF FILE: bot/src/discovery.rs
@@ -1,72 +1,184 @@
 // src/encoding.rs
 // Module for encoding data specific to the arbitrage strategy,
 // particularly the userData for the Huff contract's flash loan callback.
+//
+// The layout is versioned with the Huff contract (see `ExecutorAbiVersion`); each supported
+// version has its own `encode_user_data_v*` function and `encode_user_data` dispatches on
+// `config.executor_abi_version`.
+//
+// The executor holds no token allowances: it pays UniV3 pools from their swap callback, transfers the
+// amount in to Velo/Aero pools before swapping them directly, and transfers the repayment to the
+// Balancer vault, so no route type needs approvals managed from the bot.
 
+use crate::config::ExecutorAbiVersion;
+use crate::path_optimizer::RouteCandidate;
+use crate::state::{AppState, DexType};
 use ethers::{
-    abi::{encode_packed, Token}, // Functions for ABI encoding
+    abi::{encode, Token}, // Functions for ABI encoding
     types::{Address, Bytes, U256}, // Core Ethereum types
 };
-use eyre::Result; // Error handling
+use eyre::{eyre, Result}; // Error handling
+use tracing::warn;
+
+/// One swap of the two-hop route: the pool, its DEX and the swap direction in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapLeg {
+    pub pool: Address,
+    pub dex_type: DexType,
+    pub zero_for_one: bool, // true when the leg sells the pool's token0
+}
+
+impl SwapLeg {
+    /// The executor's `isVelo` flag: Velodrome and Aerodrome pools are both quoted with `getAmountOut`,
+    /// paid up front and swapped directly, rather than paid from a UniV3 swap callback.
+    pub fn is_velo_flag(&self) -> Result<bool> {
+        if self.dex_type == DexType::Unknown {
+            return Err(eyre!("Pool {:?} has an unknown DEX type; the executor cannot swap through it", self.pool));
+        }
+        Ok(self.dex_type.is_velo_style())
+    }
+}
+
+/// Both legs of `route`: Swap A sells `token_in` into the buy pool, Swap B sells `token_out` into the sell
+/// pool, each direction taken from that pool's cached token order.
+pub fn route_swap_legs(app_state: &AppState, route: &RouteCandidate) -> Result<(SwapLeg, SwapLeg)> {
+    let sell_token0 = app_state.pool_states.get(&route.sell_pool_addr)
+        .map(|state| state.token0)
+        .ok_or_else(|| eyre!("No pool state for sell pool {:?}", route.sell_pool_addr))?;
+    Ok((
+        SwapLeg { pool: route.buy_pool_addr, dex_type: route.buy_dex_type, zero_for_one: route.zero_for_one_a },
+        SwapLeg { pool: route.sell_pool_addr, dex_type: route.sell_dex_type, zero_for_one: sell_token0 == route.token_out },
+    ))
+}
 
 /// Encodes the parameters required by the ArbitrageExecutor Huff contract's
-/// `receiveFlashLoan` function into a tightly packed `Bytes` object.
+/// `receiveFlashLoan` function into a `Bytes` object of consecutive 32-byte words,
+/// using the layout of executor `version`.
 ///
 /// The Huff contract expects parameters loaded via `calldataload` at specific offsets,
 /// assuming standard 32-byte packing.
 ///
+/// The executor takes one direction word (Swap A's) and runs Swap B the opposite way, which is only right
+/// when both pools order the pair alike. Both legs are therefore passed explicitly and a Swap B that is
+/// not the inverse of Swap A is rejected instead of being encoded.
+///
 /// # Arguments
-/// * `pool_a_addr`: Address of the first pool in the swap path (where the buy happens).
-/// * `pool_b_addr`: Address of the second pool in the swap path (where the sell happens).
+/// * `version`: userData layout of the deployed executor.
+/// * `leg_a`: The first swap (buy pool), selling the loan token for `token1_addr`.
+/// * `leg_b`: The second swap (sell pool), selling `token1_addr` back for the loan token.
 /// * `token1_addr`: Address of the intermediate token (e.g., USDC if loan is WETH).
-/// * `zero_for_one_a`: Boolean indicating direction of Swap A (true if token0->token1).
-/// * `is_a_velo`: Boolean indicating if Pool A is a Velodrome pool.
-/// * `is_b_velo`: Boolean indicating if Pool B is a Velodrome pool.
-/// * `velo_router_addr`: Address of the Velodrome Router (needed by Huff for Velo swaps).
 /// * `min_profit_wei`: Minimum required profit in loan token (T0) wei for tx to succeed.
 /// * `salt`: A unique nonce/salt (uint256) for this specific transaction attempt.
+/// * `min_out_a`: Minimum intermediate token received from Swap A (0 disables the check; v2.4+).
+/// * `min_out_b`: Minimum loan token received from Swap B (0 disables the check; v2.4+).
 ///
 /// # Returns
 /// * `Result<Bytes>`: The ABI-encoded `userData` or an error.
+#[allow(clippy::too_many_arguments)]
 pub fn encode_user_data(
+    version: ExecutorAbiVersion,
+    leg_a: SwapLeg,
+    leg_b: SwapLeg,
+    token1_addr: Address, // Intermediate token
+    min_profit_wei: U256, // Minimum profit threshold in loan token wei
+    salt: U256,           // Unique salt for replay protection
+    min_out_a: U256,      // Slippage floor for Swap A output
+    min_out_b: U256,      // Slippage floor for Swap B output
+) -> Result<Bytes> {
+    if leg_b.zero_for_one == leg_a.zero_for_one {
+        return Err(eyre!(
+            "Swap B in {:?} (zeroForOne {}) is not the inverse of Swap A in {:?}; the executor derives it from Swap A",
+            leg_b.pool, leg_b.zero_for_one, leg_a.pool
+        ));
+    }
+    let (is_a_velo, is_b_velo) = (leg_a.is_velo_flag()?, leg_b.is_velo_flag()?);
+    match version {
+        ExecutorAbiVersion::V2_3 => {
+            if !min_out_a.is_zero() || !min_out_b.is_zero() {
+                warn!("Executor ABI v2.3 has no per-swap minimum outputs; slippage floors not enforced.");
+            }
+            encode_user_data_v2_3(leg_a.pool, leg_b.pool, token1_addr, leg_a.zero_for_one, is_a_velo, is_b_velo, min_profit_wei, salt)
+        }
+        ExecutorAbiVersion::V2_4 => encode_user_data_v2_4(leg_a.pool, leg_b.pool, token1_addr, leg_a.zero_for_one, is_a_velo, is_b_velo, min_profit_wei, salt, min_out_a, min_out_b),
+    }
+}
+
+/// Huff contract v2.3.0 layout: 9 words, salt at 0x100. A 2.3.0 executor reads the Velodrome router from
+/// the word at 0xC0, but it is written as zero here: from 2.4.2 on, Velo pools are swapped directly and the
+/// word is ignored. This is why `EXECUTOR_ABI_VERSION` no longer accepts 2.3.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_user_data_v2_3(
     pool_a_addr: Address,
     pool_b_addr: Address,
-    token1_addr: Address, // Intermediate token
+    token1_addr: Address,
     zero_for_one_a: bool,
     is_a_velo: bool,
     is_b_velo: bool,
-    velo_router_addr: Address,
-    min_profit_wei: U256, // Minimum profit threshold in loan token wei
-    salt: U256,           // Unique salt for replay protection
+    min_profit_wei: U256,
+    salt: U256,
 ) -> Result<Bytes> {
+    Ok(Bytes::from(encode(&v2_3_tokens(pool_a_addr, pool_b_addr, token1_addr, zero_for_one_a, is_a_velo, is_b_velo, min_profit_wei, salt))))
+}
+
+/// Huff contract v2.4.0 layout: the v2.3.0 words followed by the per-swap minimum outputs.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_user_data_v2_4(
+    pool_a_addr: Address,
+    pool_b_addr: Address,
+    token1_addr: Address,
+    zero_for_one_a: bool,
+    is_a_velo: bool,
+    is_b_velo: bool,
+    min_profit_wei: U256,
+    salt: U256,
+    min_out_a: U256,
+    min_out_b: U256,
+) -> Result<Bytes> {
+    let mut tokens = v2_3_tokens(pool_a_addr, pool_b_addr, token1_addr, zero_for_one_a, is_a_velo, is_b_velo, min_profit_wei, salt);
+    tokens.extend([
+        Token::Uint(min_out_a),             // [0x120 - 0x13F]
+        Token::Uint(min_out_b),             // [0x140 - 0x15F]
+    ]);
+    Ok(Bytes::from(encode(&tokens)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn v2_3_tokens(
+    pool_a_addr: Address,
+    pool_b_addr: Address,
+    token1_addr: Address,
+    zero_for_one_a: bool,
+    is_a_velo: bool,
+    is_b_velo: bool,
+    min_profit_wei: U256,
+    salt: U256,
+) -> Vec<Token> {
     // Convert boolean flags to U256 values (1 or 0)
     let zero_for_one_a_u256 = U256::from(u8::from(zero_for_one_a));
     let is_a_velo_u256 = U256::from(u8::from(is_a_velo));
     let is_b_velo_u256 = U256::from(u8::from(is_b_velo));
 
-    // Use ethers::abi::encode_packed to concatenate the token representations
-    // Offsets based on Huff contract v2.3.0:
-    // 0x00: pool_A_addr
-    // 0x20: pool_B_addr
-    // 0x40: token1_addr
-    // 0x60: zeroForOne_A (as uint)
-    // 0x80: is_A_Velo (as uint)
-    // 0xA0: is_B_Velo (as uint)
-    // 0xC0: velo_router_addr
-    // 0xE0: minProfitWei (as uint)
-    // 0x100: salt (as uint) ** NEW **
-    encode_packed(&[
+    // Standard ABI encoding: every static token, addresses included, occupies a full 32-byte word.
+    // (encode_packed would emit 20-byte addresses and shift every offset below.)
+    vec![
         Token::Address(pool_a_addr),        // [0x00 - 0x1F]
         Token::Address(pool_b_addr),        // [0x20 - 0x3F]
         Token::Address(token1_addr),        // [0x40 - 0x5F]
         Token::Uint(zero_for_one_a_u256),   // [0x60 - 0x7F]
         Token::Uint(is_a_velo_u256),        // [0x80 - 0x9F]
         Token::Uint(is_b_velo_u256),        // [0xA0 - 0xBF]
-        Token::Address(velo_router_addr),   // [0xC0 - 0xDF]
+        Token::Address(Address::zero()),    // [0xC0 - 0xDF] unused router word
         Token::Uint(min_profit_wei),        // [0xE0 - 0xFF]
-        Token::Uint(salt),                  // [0x100 - 0x11F] ** NEW **
-    ])
-    .map_err(|e| eyre::eyre!("Failed to encode user data: {}", e))
-    .map(Bytes::from)
+        Token::Uint(salt),                  // [0x100 - 0x11F]
+    ]
+}
+
+/// Slippage floor for a swap: `simulated_out * (1 - slippage_bps / 10000)`, rounded down.
+/// `slippage_bps` above 10000 is treated as 10000 (no floor).
+pub fn min_amount_out(simulated_out: U256, slippage_bps: u64) -> U256 {
+    let keep_bps = 10_000 - slippage_bps.min(10_000);
+    let floor = simulated_out.full_mul(U256::from(keep_bps)) / U256::from(10_000u64);
+    U256::try_from(floor).unwrap_or(simulated_out)
 }
 
-// END OF FILE: bot/src/encoding.rs
\ No newline at end of file
+// END OF FILE: bot/src/encoding.rs
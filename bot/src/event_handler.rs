@@ -4,19 +4,28 @@ use crate::bindings::{
     AerodromePool,
     VelodromeV2Pool,
     uniswap_v3_pool::SwapFilter as UniV3SwapFilter, // Alias for clarity
+    uniswap_v3_pool::{BurnFilter as UniV3BurnFilter, MintFilter as UniV3MintFilter},
     velodrome_v2_pool::SwapFilter as VeloSwapFilter, // Alias for clarity
     i_uniswap_v3_factory::PoolCreatedFilter as UniV3PoolCreatedFilter, // Alias
-    i_velodrome_factory::PoolCreatedFilter as VeloPoolCreatedFilter, // Alias
 };
-use crate::state::{self, AppState, DexType};
+use crate::audit::{AuditEvent, AuditRecord};
+use crate::config::MonitoredEvent;
+use crate::deadline::{Deadline, DeadlineExpired};
+use crate::discovery::decode_pool_created;
+use crate::mempool::{decode_pending_swap, predict_snapshot};
+use crate::metrics::Metrics;
+use crate::oracle::{cached_weth_price_usd, net_profit_usd, weth_price_usd};
+use crate::state::{self, AppState, DexType, PoolSnapshot};
 use crate::path_optimizer::{find_top_routes, RouteCandidate};
+use crate::rpc::RpcClients;
+use crate::sim_cache::SimulationKey;
 use crate::simulation::find_optimal_loan_amount;
 use crate::{
-    UNI_V3_POOL_CREATED_TOPIC, UNI_V3_SWAP_TOPIC, VELO_AERO_POOL_CREATED_TOPIC,
+    UNI_V3_BURN_TOPIC, UNI_V3_MINT_TOPIC, UNI_V3_POOL_CREATED_TOPIC, UNI_V3_SWAP_TOPIC, VELO_AERO_POOL_CREATED_TOPIC,
     VELO_AERO_SWAP_TOPIC,
 };
 use crate::transaction::{submit_arbitrage_transaction, NonceManager};
-use crate::utils::ToF64Lossy;
+use crate::twap::refresh_twap_tick;
 
 use ethers::{
     abi::RawLog,
@@ -25,15 +34,21 @@ use ethers::{
     types::{Log, U64, I256, U256, Address},
 };
 use eyre::{Result};
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::{Duration, Instant}};
 use tokio::time::timeout;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 
 // --- Event Handlers ---
 
-pub async fn handle_new_block(block_number: U64, _state: Arc<AppState>) -> Result<()> {
+pub async fn handle_new_block(block_number: U64, block_timestamp: u64, state: Arc<AppState>) -> Result<()> {
     info!("🧱 New Block Received: #{}", block_number);
+    // Cache the header timestamp so snapshot updates in this block don't need their own get_block
+    state.record_block_timestamp(block_number, block_timestamp);
+    state.prune_submitted_salts(block_number);
+    state.simulation_cache.prune(block_number);
+    state.leg_quotes.prune(block_number);
+    Metrics::set(&state.metrics.pools_in_cooldown, state.pool_cooldowns.active(Instant::now()) as u64);
     // TODO: Potentially trigger periodic checks or updates based on block number
     Ok(())
 }
@@ -43,11 +58,19 @@ pub async fn handle_new_block(block_number: U64, _state: Arc<AppState>) -> Resul
 pub async fn handle_log_event(
     log: Log,
     state: Arc<AppState>,
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    clients: RpcClients,
     nonce_manager: Arc<NonceManager>,
 ) -> Result<()> {
+    let client = &clients.read;
+    // The opportunity latency budget starts when the log is received
+    let deadline = Deadline::from_ttl_ms(state.config.opportunity_ttl_ms);
+    if state.seen_logs.check_and_insert(&log) {
+        Metrics::inc(&state.metrics.duplicate_logs_skipped);
+        debug!(log_index = ?log.log_index, "Log already processed, skipping.");
+        return Ok(());
+    }
     // Ensure topic0 exists
-    let event_sig = match log.topics.get(0) {
+    let event_sig = match log.topics.first() {
         Some(t) => *t,
         None => {
             warn!("Log missing topic0, cannot identify event.");
@@ -74,15 +97,19 @@ pub async fn handle_log_event(
         match <UniV3PoolCreatedFilter as EthLogDecode>::decode_log(&raw_log) {
             Ok(event) => {
                  // Check if the created pool involves the target pair (WETH/USDC)
-                if state::is_target_pair_option(event.token_0, event.token_1, state.target_pair()) {
+                if !state.config.enabled_univ3_fee_tiers().contains(&event.fee) {
+                    debug!(pool=%event.pool, fee=%event.fee, "Ignoring UniV3 pool creation in an unmonitored fee tier.");
+                } else if state::is_target_pair_option(event.token_0, event.token_1, state.target_tokens()) {
                     info!(pool=%event.pool, fee=%event.fee, "✨ Target UniV3 pool created! Fetching state...");
                     let s = state.clone();
                     let c = client.clone();
+                    let created_block = log.block_number;
                     // Spawn task to fetch state for the newly created pool
                     tokio::spawn(async move {
-                         let fetch_result = state::fetch_and_cache_pool_state(event.pool, DexType::UniswapV3, contract_address, c, s).await;
-                         if let Err(e) = fetch_result {
-                              error!(pool=%event.pool, factory=%contract_address, error=?e, "Fetch state failed for new UniV3 pool");
+                         let fetch_result = state::fetch_and_cache_pool_state(event.pool, DexType::UniswapV3, contract_address, Some(event.fee), c, s.clone()).await;
+                         match fetch_result {
+                              Ok(()) => state::record_pool_creation(&s, event.pool, created_block),
+                              Err(e) => error!(pool=%event.pool, factory=%contract_address, error=?e, "Fetch state failed for new UniV3 pool"),
                           }
                     });
                 } else {
@@ -92,40 +119,40 @@ pub async fn handle_log_event(
             Err(e) => error!(address=%contract_address, error=?e, "Failed to decode UniV3 PoolCreated event"),
         }
     } else if event_sig == velo_aero_pool_created_topic {
-         // Determine which DEX factory emitted the event
-        let dex_type = if contract_address == state.config.velodrome_v2_factory_addr {
-            DexType::VelodromeV2
-        } else if Some(contract_address) == state.config.aerodrome_factory_addr {
-            DexType::Aerodrome
-        } else {
-            trace!("Ignoring Velo/Aero PoolCreated log from non-factory address: {}", contract_address);
+        // The emitting factory tells Velodrome and Aerodrome pools apart
+        let Some(created) = decode_pool_created(&log, &state.config) else {
+            if contract_address == state.config.velodrome_v2_factory_addr || Some(contract_address) == state.config.aerodrome_factory_addr {
+                error!(address=%contract_address, "Failed to decode Velo/Aero PoolCreated event");
+            } else {
+                trace!("Ignoring Velo/Aero PoolCreated log from non-factory address: {}", contract_address);
+            }
             return Ok(());
         };
-
-        let raw_log: RawLog = log.clone().into();
-        match <VeloPoolCreatedFilter as EthLogDecode>::decode_log(&raw_log) {
-             Ok(event) => {
-                 // Check if the created pool involves the target pair
-                 if state::is_target_pair_option(event.token_0, event.token_1, state.target_pair()) {
-                    info!(pool=%event.pool, dex=?dex_type, stable=%event.stable, "✨ Target {:?} pool created! Fetching state...", dex_type);
-                     let s=state.clone();
-                     let c=client.clone();
-                     // Spawn task to fetch state
-                     tokio::spawn(async move {
-                         let fetch_result = state::fetch_and_cache_pool_state(event.pool, dex_type, contract_address, c, s).await;
-                         if let Err(e) = fetch_result {
-                              error!(pool=%event.pool, factory=%contract_address, dex=?dex_type, error=?e, "Fetch state failed for new Velo/Aero pool");
-                          }
-                     });
-                 } else {
-                    trace!(pool=%event.pool, "Ignoring non-target pair Velo/Aero pool creation.");
+        let (dex_type, pool) = (created.dex_type, created.pool);
+        // Check if the created pool involves the target pair
+        if state::is_target_pair_option(created.token0, created.token1, state.target_tokens()) {
+            info!(pool=%pool, dex=?dex_type, stable=?created.velo_stable, "✨ Target {:?} pool created! Fetching state...", dex_type);
+            let s=state.clone();
+            let c=client.clone();
+            // Spawn task to fetch state
+            tokio::spawn(async move {
+                let fetch_result = state::fetch_and_cache_pool_state(pool, dex_type, contract_address, None, c, s.clone()).await;
+                match fetch_result {
+                     Ok(()) => state::record_pool_creation(&s, pool, created.created_block),
+                     Err(e) => error!(pool=%pool, factory=%contract_address, dex=?dex_type, error=?e, "Fetch state failed for new Velo/Aero pool"),
                  }
-             }
-             Err(e) => error!(address=%contract_address, error=?e, "Failed to decode Velo/Aero PoolCreated event"),
-         }
+            });
+        } else {
+           trace!(pool=%pool, "Ignoring non-target pair Velo/Aero pool creation.");
+        }
 
     // --- Swap Events ---
     } else if event_sig == uni_v3_swap_topic {
+        // Resolve the block timestamp before taking the snapshot lock (cached per block)
+        let block_timestamp = match log.block_number {
+            Some(n) => state.block_timestamp(n, client.as_ref()).await,
+            None => None,
+        };
         // Only process swaps for pools we are actively tracking (in snapshots map)
         if let Some(mut snapshot_entry) = state.pool_snapshots.get_mut(&contract_address) {
             trace!(pool=%contract_address, "Handling UniV3 Swap");
@@ -133,19 +160,26 @@ pub async fn handle_log_event(
             match <UniV3SwapFilter as EthLogDecode>::decode_log(&raw_log) {
                 Ok(swap) => {
                     let block_number = log.block_number; // Get block number from the log metadata
+                    let before = state.config.log_snapshot_diffs.then(|| snapshot_entry.clone());
                     // Update the snapshot cache with new price/tick info from the event
-                    snapshot_entry.sqrt_price_x96 = Some(U256::from(swap.sqrt_price_x96)); // Convert u160
+                    snapshot_entry.sqrt_price_x96 = Some(swap.sqrt_price_x96);
                     snapshot_entry.tick = Some(swap.tick);
+                    snapshot_entry.liquidity = Some(swap.liquidity);
                     snapshot_entry.last_update_block = block_number;
+                    snapshot_entry.last_update_timestamp = block_timestamp;
                     debug!(pool=%contract_address, tick=%swap.tick, "UniV3 Snapshot Updated from Swap event");
+                    if let Some(before) = before {
+                        state::log_snapshot_diff(&before, &snapshot_entry);
+                    }
 
                     // Clone necessary Arcs for the spawned task
                     let s = state.clone();
-                    let c = client.clone();
+                    let c = clients.clone();
                     let nm = nonce_manager.clone();
                     // Spawn task to check for arbitrage opportunities involving this pool
                     tokio::spawn(async move {
-                        if let Err(e) = check_for_arbitrage(contract_address, s, c, nm).await {
+                        refresh_twap_tick(c.read.clone(), &s, contract_address, block_number).await;
+                        if let Err(e) = check_for_arbitrage(contract_address, None, s, c, nm, deadline).await {
                             error!(pool=%contract_address, error=?e, "Check arbitrage task failed after UniV3 swap");
                         }
                     });
@@ -170,21 +204,20 @@ pub async fn handle_log_event(
                      let block_number = log.block_number;
                      // Clone necessary Arcs
                      let s = state.clone();
-                     let c = client.clone();
+                     let c = clients.clone();
                      let nm = nonce_manager.clone();
                      // Spawn task to fetch updated reserves and check for arbitrage
                      tokio::spawn(async move {
                          debug!(pool=%pool_address, dex=?dex_type, "Fetching reserves after swap...");
-                         let timeout_duration = Duration::from_secs(s.config.fetch_timeout_secs.unwrap_or(10));
 
                          // FIX E0716: Create the ContractCall binding first
                          // Define the type explicitly for clarity if needed
                          type ReservesCall = ContractCall<SignerMiddleware<Provider<Http>, LocalWallet>, (U256, U256, U256)>;
                          let pool_call_binding: ReservesCall = if dex_type == DexType::VelodromeV2 {
-                             let pool = VelodromeV2Pool::new(pool_address, c.clone());
+                             let pool = VelodromeV2Pool::new(pool_address, c.read.clone());
                              pool.get_reserves() // Bind the call object
                          } else { // Assumes Aerodrome uses the same get_reserves signature
-                             let pool = AerodromePool::new(pool_address, c.clone());
+                             let pool = AerodromePool::new(pool_address, c.read.clone());
                              pool.get_reserves() // Bind the call object
                          };
 
@@ -192,50 +225,167 @@ pub async fn handle_log_event(
                          let pool_call_future = pool_call_binding.call();
 
                          // Fetch reserves with timeout
-                         match timeout(timeout_duration, pool_call_future).await {
+                         match s.timed_fetch(pool_call_future).await {
                             Ok(Ok(reserves)) => {
                                 let (reserve0, reserve1, _ts): (U256, U256, U256) = reserves;
-                                // Get a mutable reference to update the snapshot
-                                if let Some(mut snapshot) = s.pool_snapshots.get_mut(&pool_address) {
-                                    snapshot.reserve0 = Some(reserve0);
-                                    snapshot.reserve1 = Some(reserve1);
-                                    snapshot.last_update_block = block_number;
+                                let block_timestamp = match block_number {
+                                    Some(n) => s.block_timestamp(n, c.read.as_ref()).await,
+                                    None => None,
+                                };
+                                if s.update_pool_snapshot(pool_address, reserve0, reserve1, block_number, block_timestamp) {
                                     debug!(pool=%pool_address, dex=?dex_type, r0=%reserve0, r1=%reserve1, "Velo/Aero Snapshot Updated after Swap");
 
                                     // Now check for arbitrage
-                                    if let Err(e) = check_for_arbitrage(pool_address, s.clone(), c.clone(), nm.clone()).await {
+                                    if let Err(e) = check_for_arbitrage(pool_address, None, s.clone(), c.clone(), nm.clone(), deadline).await {
                                         error!(pool=%pool_address, error=?e, "Check arbitrage task failed after Velo/Aero swap");
                                     }
                                 } else {
-                                     warn!(pool = %pool_address, "Snapshot disappeared before update after Velo/Aero swap");
+                                     debug!(pool = %pool_address, "Snapshot not updated after Velo/Aero swap (pool dropped or reserves rejected)");
                                 }
                             },
                             Ok(Err(e)) => { error!(pool=%pool_address, dex=?dex_type, error=?e, "Fetch reserves RPC failed after swap"); },
-                            Err(_) => { error!(pool=%pool_address, dex=?dex_type, timeout_secs = timeout_duration.as_secs(), "Timeout fetching reserves after swap"); }
+                            Err(_) => { error!(pool=%pool_address, dex=?dex_type, timeout_ms = s.fetch_timeout().as_millis() as u64, "Timeout fetching reserves after swap"); }
                         }
                     }); // End spawned task
                 }
                 Err(e) => error!(pool=%contract_address, error=?e, "Failed to decode Velo/Aero Swap event"),
             }
         } // Ignore swaps from untracked pools
+
+    // --- Liquidity Events ---
+    } else if event_sig == *UNI_V3_MINT_TOPIC || event_sig == *UNI_V3_BURN_TOPIC {
+        let raw_log: RawLog = log.clone().into();
+        let (tick_lower, tick_upper, liquidity_delta) = if event_sig == *UNI_V3_MINT_TOPIC {
+            match <UniV3MintFilter as EthLogDecode>::decode_log(&raw_log) {
+                Ok(mint) => (mint.tick_lower, mint.tick_upper, i128::try_from(mint.amount).unwrap_or(i128::MAX)),
+                Err(e) => { error!(pool=%contract_address, error=?e, "Failed to decode UniV3 Mint event"); return Ok(()); }
+            }
+        } else {
+            match <UniV3BurnFilter as EthLogDecode>::decode_log(&raw_log) {
+                Ok(burn) => (burn.tick_lower, burn.tick_upper, -i128::try_from(burn.amount).unwrap_or(i128::MAX)),
+                Err(e) => { error!(pool=%contract_address, error=?e, "Failed to decode UniV3 Burn event"); return Ok(()); }
+            }
+        };
+        // Out-of-range positions still change the ticks a local quote may cross
+        if let Some(mut window) = state.univ3_ticks.get_mut(&contract_address) {
+            window.apply_position_change(tick_lower, tick_upper, liquidity_delta);
+        }
+        let Some(mut snapshot_entry) = state.pool_snapshots.get_mut(&contract_address).filter(|s| s.dex_type == DexType::UniswapV3) else {
+            return Ok(()); // Untracked pool
+        };
+        let before = state.config.log_snapshot_diffs.then(|| snapshot_entry.clone());
+        if !apply_liquidity_change(&mut snapshot_entry, tick_lower, tick_upper, liquidity_delta) {
+            trace!(pool=%contract_address, tick_lower, tick_upper, "UniV3 position change outside the current tick. Active liquidity unchanged.");
+            return Ok(());
+        }
+        snapshot_entry.last_update_block = log.block_number;
+        debug!(pool=%contract_address, liquidity=?snapshot_entry.liquidity, delta=liquidity_delta, "UniV3 Snapshot liquidity updated from Mint/Burn event");
+        if let Some(before) = before {
+            state::log_snapshot_diff(&before, &snapshot_entry);
+        }
+        drop(snapshot_entry);
+
+        let s = state.clone();
+        let c = clients.clone();
+        let nm = nonce_manager.clone();
+        tokio::spawn(async move {
+            if let Err(e) = check_for_arbitrage(contract_address, None, s, c, nm, deadline).await {
+                error!(pool=%contract_address, error=?e, "Check arbitrage task failed after UniV3 liquidity change");
+            }
+        });
     }
     Ok(())
 }
 
+/// Applies a UniV3 `Mint` (positive delta) or `Burn` (negative) to the snapshot's active liquidity. Like the
+/// pool itself, only a position spanning the current tick (`tick_lower <= tick < tick_upper`) counts.
+/// Returns false when the snapshot has no tick/liquidity or the position is out of range.
+pub fn apply_liquidity_change(snapshot: &mut PoolSnapshot, tick_lower: i32, tick_upper: i32, delta: i128) -> bool {
+    let (Some(tick), Some(liquidity)) = (snapshot.tick, snapshot.liquidity) else { return false };
+    if tick < tick_lower || tick >= tick_upper {
+        return false;
+    }
+    let updated = if delta >= 0 { liquidity.saturating_add(delta.unsigned_abs()) } else { liquidity.saturating_sub(delta.unsigned_abs()) };
+    snapshot.liquidity = Some(updated);
+    true
+}
+
+/// topic0 values of the live log filter for `events`.
+pub fn monitored_topics(events: &[MonitoredEvent]) -> Vec<H256> {
+    let mut topics = Vec::new();
+    for event in events {
+        let event_topics = match event {
+            MonitoredEvent::Swap => vec![*UNI_V3_SWAP_TOPIC, *VELO_AERO_SWAP_TOPIC],
+            MonitoredEvent::PoolCreated => vec![*UNI_V3_POOL_CREATED_TOPIC, *VELO_AERO_POOL_CREATED_TOPIC],
+            MonitoredEvent::Mint => vec![*UNI_V3_MINT_TOPIC],
+            MonitoredEvent::Burn => vec![*UNI_V3_BURN_TOPIC],
+        };
+        for topic in event_topics {
+            if !topics.contains(&topic) {
+                topics.push(topic);
+            }
+        }
+    }
+    topics
+}
+
+
+/// Counts and logs an opportunity dropped for exceeding its latency budget.
+fn record_expired(state: &AppState, expired: DeadlineExpired) {
+    Metrics::inc(&state.metrics.deadline_expired_opportunities);
+    debug!(stage = expired.stage, elapsed_ms = expired.elapsed.as_millis() as u64, "Opportunity deadline expired. Dropping stale work.");
+}
+
+/// Handles a pending transaction from the mempool subscription: when it swaps through a monitored pool,
+/// the pool's post-swap snapshot is predicted and searched for arbitrage without touching the cache.
+/// Predicted routes are reported only; see `check_for_arbitrage`.
+pub async fn handle_pending_tx(
+    tx: Transaction,
+    state: Arc<AppState>,
+    clients: RpcClients,
+    nonce_manager: Arc<NonceManager>,
+) -> Result<()> {
+    let deadline = Deadline::from_ttl_ms(state.config.opportunity_ttl_ms);
+    let Some(swap) = decode_pending_swap(&tx, &state) else { return Ok(()) };
+    Metrics::inc(&state.metrics.pending_swaps_decoded);
+    let Some(predicted) = predict_snapshot(&swap, &state) else {
+        debug!(tx = ?tx.hash, pool = %swap.pool, "Pending swap cannot be modelled locally. Waiting for confirmation.");
+        return Ok(());
+    };
+    debug!(tx = ?tx.hash, pool = %swap.pool, amount_in = %swap.amount_in, zero_for_one = swap.zero_for_one, "Predicted post-swap snapshot from pending tx.");
+    check_for_arbitrage(swap.pool, Some(predicted), state, clients, nonce_manager, deadline).await
+}
 
 /// Checks for arbitrage opportunities involving the pool that was just updated.
-#[instrument(skip(state, client, nonce_manager), fields(updated_pool=%updated_pool_address), level = "debug")]
+/// `predicted_snapshot` replaces the cached snapshot of that pool when the update is a pending swap. Such
+/// routes are audited and logged but not simulated or submitted: the back-run only exists once the pending
+/// swap lands, which would take a bundle placed behind it, and submissions are single transactions.
+/// Every stage respects `deadline`, which started when the triggering log was received.
+#[instrument(skip(predicted_snapshot, state, clients, nonce_manager, deadline), fields(updated_pool=%updated_pool_address, predicted = predicted_snapshot.is_some()), level = "debug")]
 async fn check_for_arbitrage(
     updated_pool_address: Address,
+    predicted_snapshot: Option<PoolSnapshot>,
     state: Arc<AppState>,
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    clients: RpcClients,
     nonce_manager: Arc<NonceManager>,
+    deadline: Deadline,
 ) -> Result<()> {
     debug!("Checking for arbitrage opportunities triggered by pool update...");
+    Metrics::inc(&state.metrics.arbitrage_checks);
+    if state.is_paused() {
+        trace!("Bot paused (circuit breaker). Skipping arbitrage check.");
+        return Ok(());
+    }
+    if let Err(expired) = deadline.check("route search") {
+        record_expired(&state, expired);
+        return Ok(());
+    }
 
     // 1. Get Snapshot of the updated pool
-    let updated_pool_snapshot = match state.pool_snapshots.get(&updated_pool_address) {
-        Some(entry) => entry.value().clone(), // Clone the snapshot data
+    let predicted = predicted_snapshot.is_some();
+    let cached_snapshot = || state.pool_snapshots.get(&updated_pool_address).map(|entry| entry.value().clone());
+    let updated_pool_snapshot = match predicted_snapshot.or_else(cached_snapshot) {
+        Some(snapshot) => snapshot,
         None => {
             warn!("Snapshot missing for updated pool {} during arbitrage check.", updated_pool_address);
             return Ok(()); // Cannot proceed without snapshot
@@ -246,7 +396,7 @@ async fn check_for_arbitrage(
     if !state::is_target_pair_option(
         updated_pool_snapshot.token0,
         updated_pool_snapshot.token1,
-        state.target_pair(),
+        state.target_tokens(),
     ) {
         trace!("Updated pool {} is not the target pair. Skipping arbitrage check.", updated_pool_address);
         return Ok(());
@@ -259,13 +409,20 @@ async fn check_for_arbitrage(
         &updated_pool_snapshot,
         &state.pool_states,      // Pass reference to detailed states map
         &state.pool_snapshots,   // Pass reference to snapshot map (hot cache)
+        &state.fee_on_transfer_tokens, // Tokens flagged by the transfer probe
+        &state.token_decimals,   // Tokens whose decimals() had to be assumed
         &state.config,           // Pass reference to config
-        state.weth_address,      // Pass WETH address
-        state.usdc_address,      // Pass USDC address
-        state.weth_decimals,     // Pass WETH decimals
-        state.usdc_decimals,     // Pass USDC decimals
+        &state.trading_pairs(),  // WETH against each quote token
     );
 
+    // Pools that recently reverted are left alone until their cooldown ends
+    let now = Instant::now();
+    let top_routes: Vec<RouteCandidate> = top_routes.into_iter().filter(|route| {
+        let cooling_down = state.pool_cooldowns.route_cooling_down(route, now);
+        if cooling_down { debug!(route = %route.id(), "Skipping route through a pool on cooldown."); }
+        !cooling_down
+    }).collect();
+
     if top_routes.is_empty() {
         trace!("No potential arbitrage routes found involving pool {}.", updated_pool_address);
         return Ok(());
@@ -279,13 +436,20 @@ async fn check_for_arbitrage(
         info!(
             buy_pool = ?route_candidate.buy_pool_addr, buy_dex = ?route_candidate.buy_dex_type,
             sell_pool = ?route_candidate.sell_pool_addr, sell_dex = ?route_candidate.sell_dex_type,
-            est_profit_pct = route_candidate.estimated_profit_usd, // Using placeholder field name
+            price_diff_pct = route_candidate.price_diff_pct,
             "Evaluating Route Candidate..."
         );
 
+        state.audit.record(|| AuditRecord::new(AuditEvent::Detected, &route_candidate).reason(format!("price diff {:.4}%", route_candidate.price_diff_pct)));
+        if predicted {
+            info!(route = %route_candidate.id(), "Route predicted from a pending swap. Not submitting without a back-run bundle.");
+            continue;
+        }
+
         // Clone Arcs for the simulation task
         let sim_state = state.clone();
-        let sim_client = client.clone();
+        let sim_client = clients.read.clone();
+        let write_client = clients.write.clone();
         let sim_nonce_manager = nonce_manager.clone();
         let route = route_candidate.clone(); // Clone route for the spawn
 
@@ -302,46 +466,74 @@ async fn check_for_arbitrage(
             let sell_snapshot_option = sim_state.pool_snapshots.get(&route_sell_addr).map(|r| r.value().clone());
 
             // Fetch current gas price before simulation
-            let gas_info = match crate::transaction::fetch_gas_price(sim_client.clone(), &sim_state.config).await {
-                 Ok(g) => g,
-                 Err(e) => {
-                     // Use captured fields for logging
-                     error!(buy_pool = ?route_buy_addr, sell_pool = ?route_sell_addr, error=?e, "Gas price fetch failed in simulation task. Aborting route evaluation.");
-                     return; // Cannot simulate without gas price
-                 }
-            };
-            let current_gas_price_gwei = gas_info.max_priority_fee_per_gas.to_f64_lossy() / 1e9;
+            let current_gas_price_gwei = crate::transaction::fetch_simulation_gas_price_gwei(sim_client.as_ref(), &sim_state.config).await;
+            if let Err(expired) = deadline.check("gas price fetch") {
+                sim_state.audit.record(|| AuditRecord::new(AuditEvent::Expired, &route).reason("gas price fetch"));
+                record_expired(&sim_state, expired);
+                return;
+            }
             debug!(gas_price_gwei = current_gas_price_gwei, "Fetched gas price for simulation.");
 
-            // Find the optimal loan amount and corresponding profit
-             let optimal_loan_result = find_optimal_loan_amount(
+            // Find the optimal loan amount and corresponding profit, bounded by the remaining budget
+             // Repeated checks of this route on unchanged snapshots in the same block reuse the first search
+             let search = || find_optimal_loan_amount(
                  sim_client.clone(),
                  sim_state.clone(),
                  &route, // Pass reference to the cloned route
                  buy_snapshot_option.as_ref(),
                  sell_snapshot_option.as_ref(),
                  current_gas_price_gwei,
-             ).await;
+             );
+             let cached_search = async {
+                 match SimulationKey::new(&route, buy_snapshot_option.as_ref(), sell_snapshot_option.as_ref()) {
+                     Some(key) => sim_state.simulation_cache.get_or_search(key, &sim_state.metrics, search).await,
+                     None => search().await,
+                 }
+             };
+             let optimal_loan_result = match timeout(deadline.remaining(), cached_search).await {
+                 Ok(result) => result,
+                 Err(_) => {
+                     sim_state.audit.record(|| AuditRecord::new(AuditEvent::Expired, &route).reason("loan optimization"));
+                     record_expired(&sim_state, DeadlineExpired { stage: "loan optimization", elapsed: deadline.elapsed() });
+                     return;
+                 }
+             };
 
             match optimal_loan_result {
-                Ok(Some((optimal_loan_amount_wei, max_net_profit_wei))) => {
+                Ok(Some((optimal_loan_amount_wei, max_net_profit_wei, price_impact_bps))) => {
                     // Check if the maximum possible profit is positive
                     if max_net_profit_wei > I256::zero() {
+                        if sim_state.is_paused() {
+                            sim_state.audit.record(|| AuditRecord::new(AuditEvent::Unprofitable, &route).loan_amount(optimal_loan_amount_wei).net_profit(max_net_profit_wei).reason("paused"));
+                            info!(buy_pool = ?route_buy_addr, sell_pool = ?route_sell_addr, "Bot paused during simulation. Dropping profitable route.");
+                            return;
+                        }
+                        let config = &sim_state.config;
+                        if let Err(pending) = sim_state.submission_warmup.check(Instant::now(), sim_state.pool_snapshots.len(), Duration::from_secs(config.submission_warmup_secs), config.min_pools_before_trading) {
+                            Metrics::inc(&sim_state.metrics.warmup_suppressed_submissions);
+                            sim_state.audit.record(|| AuditRecord::new(AuditEvent::Expired, &route).loan_amount(optimal_loan_amount_wei).net_profit(max_net_profit_wei).reason(pending.to_string()));
+                            info!(buy_pool = ?route_buy_addr, sell_pool = ?route_sell_addr, max_profit = %max_net_profit_wei, %pending, "Profitable route found during startup warmup. Not submitting.");
+                            return;
+                        }
+                        let weth_usd = cached_weth_price_usd(&sim_state, route.source_block);
                         info!(
                             // Use captured fields/cloned route for logging
                             buy_pool = ?route_buy_addr, sell_pool = ?route_sell_addr,
                             optimal_loan = %optimal_loan_amount_wei,
                             max_profit = %max_net_profit_wei,
+                            profit_usd = ?net_profit_usd(&sim_state, &route, max_net_profit_wei, weth_usd),
+                            ?price_impact_bps,
                             "🎉 PROFITABLE OPPORTUNITY IDENTIFIED! Attempting execution."
                         );
                         // Attempt to submit the transaction
                          let execute_result = submit_arbitrage_transaction(
-                             sim_client,
+                             write_client,
                              sim_state,
                              route, // Pass the owned route
                              optimal_loan_amount_wei,
                              max_net_profit_wei,
                              sim_nonce_manager,
+                             deadline,
                         ).await;
 
                          if let Err(e) = execute_result {
@@ -352,15 +544,18 @@ async fn check_for_arbitrage(
                          // If successful, submit_arbitrage_transaction logs success internally
                     } else {
                         // Use cloned route for logging
+                        sim_state.audit.record(|| AuditRecord::new(AuditEvent::Unprofitable, &route).loan_amount(optimal_loan_amount_wei).net_profit(max_net_profit_wei));
                         debug!(route = ?route, max_profit = %max_net_profit_wei, "Route evaluated, but max profit is not positive.");
                     }
                  }
                  Ok(None) => {
                     // Use cloned route for logging
+                    sim_state.audit.record(|| AuditRecord::new(AuditEvent::Unprofitable, &route).reason("no profitable loan amount"));
                     debug!(route = ?route, "No profitable loan amount found for this route during optimization.");
                  }
                  Err(e) => {
                     // Use cloned route for logging
+                    sim_state.audit.record(|| AuditRecord::new(AuditEvent::SimulationFailed, &route).reason(format!("{:#}", e)));
                     error!(route = ?route, error = ?e, "Optimal loan search failed for route");
                  }
             }
@@ -368,4 +563,50 @@ async fn check_for_arbitrage(
     } // End loop through routes
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Single detection pass over every cached target-pair snapshot (the `--once` mode). Routes found through
+/// each pool are simulated but never submitted. Returns the profitable routes with their optimal loan amount and
+/// net profit, best first by USD profit (`net_profit_usd`) so routes of different pairs compare; routes
+/// without a USD value rank last, by native profit.
+pub async fn run_detection_pass(
+    state: Arc<AppState>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+) -> Result<Vec<(RouteCandidate, U256, I256)>> {
+    let gas_price_gwei = crate::transaction::fetch_simulation_gas_price_gwei(client.as_ref(), &state.config).await;
+    let snapshots: Vec<state::PoolSnapshot> = state.pool_snapshots.iter()
+        .filter(|e| state::is_target_pair_option(e.token0, e.token1, state.target_tokens()))
+        .map(|e| e.value().clone())
+        .collect();
+    info!(pools = snapshots.len(), "Running single detection pass...");
+
+    let block_number = client.get_block_number().await.unwrap_or_default();
+    let weth_usd = weth_price_usd(client.clone(), &state, block_number).await;
+    let mut evaluated = HashSet::new();
+    let mut profitable = Vec::new();
+    let pairs = state.trading_pairs();
+    for snapshot in &snapshots {
+        let routes = find_top_routes(
+            snapshot, &state.pool_states, &state.pool_snapshots, &state.fee_on_transfer_tokens, &state.token_decimals, &state.config, &pairs,
+        );
+        for route in routes {
+            // The same pool pair is found again from its other pool
+            if !evaluated.insert(route.id()) { continue; }
+            let buy_snapshot = state.pool_snapshots.get(&route.buy_pool_addr).map(|r| r.value().clone());
+            let sell_snapshot = state.pool_snapshots.get(&route.sell_pool_addr).map(|r| r.value().clone());
+            match find_optimal_loan_amount(client.clone(), state.clone(), &route, buy_snapshot.as_ref(), sell_snapshot.as_ref(), gas_price_gwei).await {
+                Ok(Some((loan_amount, net_profit, price_impact_bps))) if net_profit > I256::zero() => {
+                    let profit_usd = net_profit_usd(&state, &route, net_profit, weth_usd);
+                    info!(route = %route.id(), %loan_amount, %net_profit, ?profit_usd, ?price_impact_bps, "Profitable route.");
+                    profitable.push((route, loan_amount, net_profit, profit_usd));
+                }
+                Ok(result) => debug!(route = %route.id(), ?result, "Route not profitable."),
+                Err(e) => warn!(route = %route.id(), error = ?e, "Route simulation failed."),
+            }
+        }
+    }
+    profitable.sort_by(|a, b| {
+        b.3.unwrap_or(f64::NEG_INFINITY).total_cmp(&a.3.unwrap_or(f64::NEG_INFINITY)).then_with(|| b.2.cmp(&a.2))
+    });
+    info!(evaluated = evaluated.len(), profitable = profitable.len(), "Detection pass complete.");
+    Ok(profitable.into_iter().map(|(route, loan_amount, net_profit, _)| (route, loan_amount, net_profit)).collect())
+}
@@ -0,0 +1,50 @@
+// bot/src/exposure.rs
+//! Cap on the flash loan amount of submissions still in flight (`config.max_total_inflight_loan_wei`).
+//!
+//! Concurrent opportunities can each size a loan against the whole vault; together they may exceed its
+//! liquidity or the operator's risk appetite. A submission reserves its loan before the transaction is
+//! built and releases it when the submission resolves (receipt, timeout or failure) through the dropped
+//! `InflightLoan`. A submission whose loan would push the total over the cap is dropped, not queued. A
+//! zero cap disables the check; reservations are still tracked.
+
+use ethers::types::U256;
+use std::sync::{Arc, Mutex};
+
+/// Sum of the loans of unresolved submissions.
+#[derive(Debug, Default)]
+pub struct InflightExposure {
+    total_wei: Mutex<U256>,
+}
+
+impl InflightExposure {
+    /// Reserves `loan_wei` unless it would take the total over `cap_wei`. Returns the current total instead
+    /// when it would. A zero cap always reserves.
+    pub fn try_reserve(self: &Arc<Self>, loan_wei: U256, cap_wei: U256) -> Result<InflightLoan, U256> {
+        let mut total = self.total_wei.lock().unwrap_or_else(|e| e.into_inner());
+        let new_total = total.saturating_add(loan_wei);
+        if !cap_wei.is_zero() && new_total > cap_wei {
+            return Err(*total);
+        }
+        *total = new_total;
+        Ok(InflightLoan { exposure: self.clone(), loan_wei })
+    }
+
+    pub fn total(&self) -> U256 {
+        *self.total_wei.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A reserved loan; releases its share of the in-flight total when dropped.
+#[derive(Debug)]
+pub struct InflightLoan {
+    exposure: Arc<InflightExposure>,
+    loan_wei: U256,
+}
+
+impl Drop for InflightLoan {
+    fn drop(&mut self) {
+        let mut total = self.exposure.total_wei.lock().unwrap_or_else(|e| e.into_inner());
+        *total = total.saturating_sub(self.loan_wei);
+    }
+}
+// END OF FILE: bot/src/exposure.rs
@@ -0,0 +1,70 @@
+// bot/src/flash_loan.rs
+//! Flash loan calldata and fees.
+//!
+//! Loans come from the Balancer V2 Vault, which lends any token it holds and calls back
+//! `receiveFlashLoan` on the `recipient` argument, the only flash loan hook the Huff executor implements.
+//! The executor only accepts that callback during its owner-only `executeFlashLoan`, so the flash loan
+//! transaction is sent to the executor, which calls the Vault's `flashLoan` itself.
+//!
+//! Balancer's protocol flash loan fee (0% on most deployments) is read from the Vault's
+//! ProtocolFeesCollector into `AppState::balancer_flash_loan_fee` and charged in the profit math. The
+//! executor repays the loan plus the `feeAmounts[0]` the Vault passes to `receiveFlashLoan`, so a fee
+//! change only moves the profit threshold.
+
+use crate::bindings::{arbitrage_executor, IBalancerProtocolFeesCollector, IBalancerVaultViews};
+use crate::state::AppState;
+use ethers::abi::AbiEncode;
+use ethers::prelude::Middleware;
+use ethers::types::{Address, Bytes, U256};
+use eyre::{eyre, Result, WrapErr};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// 100% in Balancer's 18-decimal fee fractions.
+pub const BALANCER_FEE_ONE: u64 = 1_000_000_000_000_000_000;
+
+/// Calldata of the executor's `executeFlashLoan`, which borrows `amount` of `token` from the Vault and hands
+/// `user_data` to `receiveFlashLoan`. The transaction is sent to the executor.
+pub fn execute_flash_loan_calldata(token: Address, amount: U256, user_data: Bytes) -> Bytes {
+    arbitrage_executor::ExecuteFlashLoanCall { token, amount, user_data }.encode().into()
+}
+
+/// Fee the Balancer Vault charges on a flash loan of `amount` at `fee_percentage` (18-decimal fraction),
+/// rounded up as the Vault does.
+pub fn balancer_flash_loan_fee(amount: U256, fee_percentage: U256) -> Result<U256> {
+    if fee_percentage.is_zero() {
+        return Ok(U256::zero());
+    }
+    let product = amount.checked_mul(fee_percentage).ok_or_else(|| eyre!("Flash loan fee overflow for amount {}", amount))?;
+    Ok((product + U256::from(BALANCER_FEE_ONE - 1)) / U256::from(BALANCER_FEE_ONE))
+}
+
+/// Fee owed on top of a flash loan of `amount` from the Vault.
+pub fn flash_loan_fee_wei(app_state: &AppState, amount: U256) -> Result<U256> {
+    balancer_flash_loan_fee(amount, U256::from(app_state.balancer_flash_loan_fee.load(Ordering::Relaxed)))
+}
+
+/// Reads the Vault's current flash loan fee (through its ProtocolFeesCollector) into
+/// `app_state.balancer_flash_loan_fee` and returns it. Changes are logged.
+pub async fn refresh_balancer_flash_loan_fee<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState) -> Result<U256> {
+    let vault = app_state.config.balancer_vault_address;
+    let collector = IBalancerVaultViews::new(vault, client.clone()).get_protocol_fees_collector().call().await
+        .wrap_err("Balancer Vault getProtocolFeesCollector failed")?;
+    let fee = IBalancerProtocolFeesCollector::new(collector, client).get_flash_loan_fee_percentage().call().await
+        .wrap_err("Balancer getFlashLoanFeePercentage failed")?;
+    if fee > U256::from(BALANCER_FEE_ONE) {
+        return Err(eyre!("Balancer flash loan fee {} exceeds 100%", fee));
+    }
+    let previous = app_state.balancer_flash_loan_fee.swap(fee.as_u64(), Ordering::Relaxed);
+    if previous != fee.as_u64() {
+        let fee_pct = fee.as_u64() as f64 / BALANCER_FEE_ONE as f64 * 100.0;
+        if fee.is_zero() {
+            info!(%vault, "Balancer flash loan fee is 0%.");
+        } else {
+            warn!(%vault, fee_pct, "Balancer charges a flash loan fee. The executor repays it; it is deducted from simulated profit.");
+        }
+    }
+    Ok(fee)
+}
+// END OF FILE: bot/src/flash_loan.rs
@@ -4,61 +4,148 @@
 use ethers::{
     prelude::{Middleware, SignerMiddleware, Provider, Http, LocalWallet}, // Core types
     // Contract bindings are imported via crate root in this version
-    types::{Address, Bytes, Eip1559TransactionRequest, U256}, // Tx types & Bytes
+    types::{Address, BlockNumber, Bytes, Eip1559TransactionRequest, FeeHistory, U256, U64}, // Tx types & Bytes
 };
-use eyre::{Result, WrapErr}; // Error handling
+use eyre::{eyre, Result, WrapErr}; // Error handling
 use std::sync::Arc; // Arc for client
 use tracing::{debug, instrument}; // Import tracing macros
 
-// Re-import BalancerVault binding from crate root
-use crate::bindings::BalancerVault;
+use crate::config::{Config, PriorityFeeStrategy};
+use crate::flash_loan::execute_flash_loan_calldata;
+use crate::gas_learning::RouteShape;
+use crate::utils::{checked_mul_div, f64_to_wei, ArithmeticOverflow, ToF64Lossy};
 
-/// Estimates the gas required for the Balancer flash loan transaction.
-/// This involves sending an `eth_estimateGas` RPC call.
+/// Number of recent blocks sampled via `eth_feeHistory` for the percentile strategy.
+pub const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Transaction envelope used for submissions, detected once at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxType {
+    Legacy,
+    #[default]
+    Eip1559,
+}
+
+/// Detects the chain's transaction type: EIP-1559 if the latest block carries `baseFeePerGas`, legacy otherwise.
+pub async fn detect_tx_type<M: Middleware>(client: &M) -> Result<TxType> {
+    let latest = client.get_block(BlockNumber::Latest).await
+        .map_err(|e| eyre!("Failed to fetch latest block for tx type detection: {}", e))?
+        .ok_or_else(|| eyre!("Latest block not found"))?;
+    let tx_type = if latest.base_fee_per_gas.is_some() { TxType::Eip1559 } else { TxType::Legacy };
+    debug!(?tx_type, block = ?latest.number, "Detected chain transaction type.");
+    Ok(tx_type)
+}
+
+/// Computes the priority fee (wei) for a strategy from the current base fee and, for the
+/// percentile strategy, a fee history requested with that single reward percentile.
+/// The caller applies the configured max-priority-fee cap.
+pub fn compute_priority_fee(
+    strategy: &PriorityFeeStrategy,
+    base_fee_wei: U256,
+    fee_history: Option<&FeeHistory>,
+) -> Result<U256> {
+    match strategy {
+        PriorityFeeStrategy::Fixed(gwei) => f64_to_wei(*gwei, 9),
+        PriorityFeeStrategy::BaseFeeMultiplier(multiplier) => {
+            f64_to_wei(base_fee_wei.to_f64_lossy() * multiplier, 0)
+        }
+        PriorityFeeStrategy::RecentBlocksPercentile(_) => {
+            let history = fee_history.ok_or_else(|| eyre!("Fee history required for percentile priority fee strategy"))?;
+            // One reward column per block (single percentile requested); empty blocks report zero and are ignored.
+            let mut rewards: Vec<U256> = history.reward.iter()
+                .filter_map(|block_rewards| block_rewards.first().copied())
+                .filter(|r| !r.is_zero())
+                .collect();
+            if rewards.is_empty() {
+                return Err(eyre!("Fee history contains no non-zero rewards"));
+            }
+            rewards.sort();
+            Ok(rewards[rewards.len() / 2])
+        }
+    }
+}
+
+/// Applies the percentage buffer to a gas estimate and enforces the minimum flash loan gas limit.
+pub fn buffered_gas_limit(gas_estimate: U256, buffer_percentage: u64, min_gas_limit: u64) -> std::result::Result<U256, ArithmeticOverflow> {
+    let multiplier = 100u64.checked_add(buffer_percentage).ok_or(ArithmeticOverflow("gas limit buffer percentage"))?;
+    let buffered = checked_mul_div(gas_estimate, U256::from(multiplier), U256::from(100), "buffered gas limit")?;
+    Ok(buffered.max(U256::from(min_gas_limit)))
+}
+
+/// Gas limit for submission: the buffered estimate, or `fallback_gas_limit` when estimation reverted (`None`),
+/// clamped to `[min_gas_limit, max_gas_limit]` (the maximum wins if the bounds are inverted).
+pub fn submission_gas_limit(
+    gas_estimate: Option<U256>,
+    buffer_percentage: u64,
+    fallback_gas_limit: u64,
+    min_gas_limit: u64,
+    max_gas_limit: u64,
+) -> std::result::Result<U256, ArithmeticOverflow> {
+    let limit = match gas_estimate {
+        Some(estimate) => buffered_gas_limit(estimate, buffer_percentage, min_gas_limit)?,
+        None => U256::from(fallback_gas_limit).max(U256::from(min_gas_limit)),
+    };
+    Ok(limit.min(U256::from(max_gas_limit)))
+}
+
+/// Gas limit to submit with without estimating: `learned` (from past receipts) when there is one, else the
+/// `config.gas_profiles` entry for `shape`, clamped to `[min_flashloan_gas_limit, max_gas_limit]`.
+/// None when neither applies (unmapped shape or a 0 entry), meaning `eth_estimateGas`.
+pub fn preset_gas_limit(config: &Config, learned: Option<U256>, shape: &RouteShape) -> Option<U256> {
+    let preset = learned.or_else(|| config.gas_profiles.get(shape).filter(|limit| **limit > 0).map(|limit| U256::from(*limit)))?;
+    Some(preset.max(U256::from(config.min_flashloan_gas_limit)).min(U256::from(config.max_gas_limit)))
+}
+
+/// Total gas cost in wei for a gas limit at the given gas price.
+pub fn gas_cost_wei(gas_limit: U256, gas_price_wei: U256) -> std::result::Result<U256, ArithmeticOverflow> {
+    gas_limit.checked_mul(gas_price_wei).ok_or(ArithmeticOverflow("gas cost"))
+}
+
+/// Converts a gas cost in native-token wei (18 decimals) into loan-token wei so it can be subtracted from
+/// profit. `price_in_loan_token` is one native token's price in whole loan tokens; `None` means the gas
+/// token is the loan token (ETH vs WETH) and the cost is returned unchanged.
+pub fn gas_cost_in_loan_token(gas_cost_wei: U256, price_in_loan_token: Option<f64>, loan_token_decimals: u8) -> Result<U256> {
+    let Some(price) = price_in_loan_token else { return Ok(gas_cost_wei) };
+    let loan_wei_per_native_token = f64_to_wei(price, loan_token_decimals as u32).wrap_err("Invalid gas token price")?;
+    Ok(checked_mul_div(gas_cost_wei, loan_wei_per_native_token, U256::exp10(18), "gas cost in loan token")?)
+}
+
+/// Estimates the gas required for the executor's `executeFlashLoan` transaction.
+/// This involves sending an `eth_estimateGas` RPC call, at `block` when given (latest otherwise).
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip(client, user_data), level = "debug", fields(
-    vault = %balancer_vault_address,
-    receiver = %receiver,
+    executor = %executor,
     token = %token_in,
     amount = %amount_in_wei,
 ))]
 pub async fn estimate_flash_loan_gas(
     client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    balancer_vault_address: Address,
-    receiver: Address, // The address that will receive the flash loan (our ArbitrageExecutor)
+    executor: Address, // Our ArbitrageExecutor, which borrows and receives the flash loan
     token_in: Address, // The token being loaned
     amount_in_wei: U256, // The amount of the token being loaned
     user_data: Bytes,   // Encoded data passed to the receiver's callback
+    block: Option<U64>, // Historical block to estimate against (`replay`)
 ) -> Result<U256> {
     debug!("Estimating gas for flash loan transaction...");
 
-    // Prepare parameters for flash loan calldata generation
-    let tokens = vec![token_in];
-    let amounts = vec![amount_in_wei];
-
-    // Create a BalancerVault instance to generate calldata easily
-    let vault_contract = BalancerVault::new(balancer_vault_address, client.clone());
-
-    // Generate the calldata for the flashLoan function call
-    let flash_loan_calldata = vault_contract
-        .flash_loan(receiver, tokens, amounts, user_data)
-        .calldata() // Get the Bytes representation of the call
-        .ok_or_else(|| eyre::eyre!("Failed to generate flashLoan calldata"))?; // Handle potential Option::None
+    // Generate the executor's executeFlashLoan call; `from` is the signer, the executor's owner
+    let flash_loan_calldata = execute_flash_loan_calldata(token_in, amount_in_wei, user_data);
 
     // Create the transaction request for estimation
     // We only need `to` and `data` for gas estimation. `from` will be filled by the middleware.
     let tx_request = Eip1559TransactionRequest::new()
-        .to(balancer_vault_address)
+        .to(executor)
         .data(flash_loan_calldata);
 
     // Estimate gas using the client middleware
     // The `estimate_gas` function takes a `&TypedTransaction` and optional block number.
     // We convert our Eip1559 request into a generic `TypedTransaction`.
     let estimated_gas_units = client
-        .estimate_gas(&tx_request.clone().into(), None) // Use .into() for conversion, clone tx_request if needed later
+        .estimate_gas(&tx_request.clone().into(), block.map(Into::into)) // Use .into() for conversion, clone tx_request if needed later
         .await
         .wrap_err_with(|| format!( // Add context to the error
-            "Gas estimation failed for flashLoan to vault {} for receiver {}",
-            balancer_vault_address, receiver
+            "Gas estimation failed for flash loan through executor {}",
+            executor
         ))?;
 
     debug!(estimated_gas = %estimated_gas_units, "Gas estimation successful");
@@ -0,0 +1,53 @@
+// bot/src/gas_learning.rs
+//! Gas limits learned from the receipts of confirmed arbs, per route shape.
+//!
+//! Every successful arb records the gas its execution needed under `(buy DEX, sell DEX)`.
+//! A receipt's `gas_used` is net of storage refunds, which the transaction still needs as gas limit while
+//! it runs, so it is scaled back up by the largest refund EIP-3529 allows (a fifth of execution gas).
+//! Once a shape has `config.learned_gas_min_samples` samples, its submissions use
+//! `p95 × (100 + LEARNED_GAS_BUFFER_PERCENT)%` as the gas limit and skip `eth_estimateGas`; unseen or
+//! sparsely seen shapes fall back to `config.gas_profiles`, then estimation. A minimum of 0 disables learning.
+
+use crate::rolling_window::RollingWindow;
+use crate::state::DexType;
+use dashmap::DashMap;
+use ethers::types::U256;
+
+/// Receipts kept per route shape.
+pub const GAS_USAGE_WINDOW: usize = 100;
+/// Headroom added to the observed p95.
+pub const LEARNED_GAS_BUFFER_PERCENT: u64 = 10;
+/// EIP-3529 caps refunds at `execution gas / MAX_REFUND_QUOTIENT`.
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+/// `(buy DEX, sell DEX)`: routes of one shape run the same executor code path.
+pub type RouteShape = (DexType, DexType);
+
+/// Rolling window of execution gas for one route shape.
+pub type GasUsageWindow = RollingWindow<u64, GAS_USAGE_WINDOW>;
+
+/// Gas usage windows keyed by route shape.
+#[derive(Debug, Default)]
+pub struct GasUsageStats {
+    shapes: DashMap<RouteShape, GasUsageWindow>,
+}
+
+/// Upper bound on the execution gas of a transaction whose receipt reports `gas_used` after refunds.
+pub fn execution_gas_upper_bound(gas_used: u64) -> u64 {
+    gas_used.saturating_add(gas_used / (MAX_REFUND_QUOTIENT - 1))
+}
+
+impl GasUsageStats {
+    /// Records a receipt's `gas_used` as its execution gas upper bound.
+    pub fn record(&self, shape: RouteShape, gas_used: U256) {
+        let gas_used = gas_used.min(U256::from(u64::MAX)).as_u64();
+        self.shapes.entry(shape).or_default().record(execution_gas_upper_bound(gas_used));
+    }
+
+    /// The learned gas limit of `shape`, or None until it has `min_samples` receipts.
+    pub fn learned_limit(&self, shape: &RouteShape, min_samples: usize) -> Option<U256> {
+        let p95 = self.shapes.get(shape)?.p95(min_samples)?;
+        Some(U256::from(p95) * U256::from(100 + LEARNED_GAS_BUFFER_PERCENT) / U256::from(100u64))
+    }
+}
+// END OF FILE: bot/src/gas_learning.rs
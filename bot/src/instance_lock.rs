@@ -0,0 +1,88 @@
+// bot/src/instance_lock.rs
+//! Single-instance guard: a lock file naming the process and signer that hold it.
+//!
+//! Two bots signing with the same key race for nonces and submit the same opportunities twice, so
+//! startup takes the lock at `config.lock_file_path` (by default a per-signer file in the temp dir)
+//! and refuses to start while another live process holds it. The lock is removed when the guard is
+//! dropped on shutdown. A lock left behind by a crashed process is taken over when its PID is no
+//! longer running (checked through `/proc` on Linux; elsewhere the file must be removed by hand).
+
+use crate::config::Config;
+use ethers::types::Address;
+use eyre::{eyre, Result, WrapErr};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Held for as long as this instance runs; removes its lock file when dropped.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Lock file for `signer`: `config.lock_file_path`, or `ulp1_5-<signer>.lock` in the temp dir.
+    pub fn path_for(config: &Config, signer: Address) -> PathBuf {
+        match config.lock_file_path.as_deref() {
+            Some(path) => PathBuf::from(path),
+            None => std::env::temp_dir().join(format!("ulp1_5-{:?}.lock", signer)),
+        }
+    }
+
+    /// Takes the lock at `path` for `signer`. Fails while another live process holds it.
+    pub fn acquire(path: impl AsRef<Path>, signer: Address) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let contents = format!("pid={}\nsigner={:?}\n", std::process::id(), signer);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(contents.as_bytes()).wrap_err_with(|| format!("Failed to write lock file {}", path.display()))?;
+                    info!(path = %path.display(), ?signer, "🔒 Instance lock acquired.");
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).unwrap_or_default();
+                    let holder_pid = lock_field(&holder, "pid").and_then(|pid| pid.parse::<u32>().ok());
+                    if holder_pid.is_some_and(process_is_running) {
+                        return Err(eyre!(
+                            "Another instance (pid {}, signer {}) holds {}; refusing to start a second bot on the same signer",
+                            holder_pid.unwrap_or_default(), lock_field(&holder, "signer").unwrap_or("unknown"), path.display()
+                        ));
+                    }
+                    warn!(path = %path.display(), ?holder_pid, "Removing stale instance lock left by a process that is no longer running.");
+                    fs::remove_file(&path).wrap_err_with(|| format!("Failed to remove stale lock file {}", path.display()))?;
+                }
+                Err(e) => return Err(e).wrap_err_with(|| format!("Failed to create lock file {}", path.display())),
+            }
+        }
+        Err(eyre!("Lock file {} was re-created while taking over a stale lock", path.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.path) {
+            Ok(()) => info!(path = %self.path.display(), "🔓 Instance lock released."),
+            Err(e) => warn!(path = %self.path.display(), error = %e, "Failed to remove instance lock file."),
+        }
+    }
+}
+
+fn lock_field<'a>(contents: &'a str, key: &str) -> Option<&'a str> {
+    contents.lines().find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Whether `pid` names a running process. Without `/proc` every holder is assumed to be running.
+fn process_is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else {
+        true
+    }
+}
+// END OF FILE: bot/src/instance_lock.rs
@@ -0,0 +1,49 @@
+// bot/src/latency.rs
+//! Rolling RPC latency per endpoint and the adaptive fetch timeout derived from it.
+//!
+//! Every pool-state fetch records how long the endpoint took (a timed-out call records the timeout, so a
+//! slowing endpoint pushes its own timeout up). Once enough samples exist the per-call timeout is
+//! `TIMEOUT_P95_MULTIPLIER × p95`, bounded by `[config.min_fetch_timeout_secs, config.max_fetch_timeout_secs]`;
+//! before that, and when `max_fetch_timeout_secs` is 0, the fixed `fetch_timeout_secs` applies.
+
+use crate::rolling_window::RollingWindow;
+use dashmap::DashMap;
+use std::time::Duration;
+
+/// Samples kept per endpoint.
+pub const LATENCY_WINDOW: usize = 200;
+/// Samples needed before the p95 is trusted.
+pub const MIN_LATENCY_SAMPLES: usize = 20;
+/// Timeout = this × p95 latency.
+pub const TIMEOUT_P95_MULTIPLIER: u32 = 3;
+
+/// Rolling window of call latencies for one endpoint.
+pub type LatencyTracker = RollingWindow<Duration, LATENCY_WINDOW>;
+
+/// Latency trackers keyed by endpoint URL.
+#[derive(Debug, Default)]
+pub struct EndpointLatencies {
+    endpoints: DashMap<String, LatencyTracker>,
+}
+
+impl EndpointLatencies {
+    pub fn record(&self, endpoint: &str, elapsed: Duration) {
+        match self.endpoints.get(endpoint) {
+            Some(tracker) => tracker.record(elapsed),
+            None => self.endpoints.entry(endpoint.to_string()).or_default().record(elapsed),
+        }
+    }
+
+    pub fn p95(&self, endpoint: &str) -> Option<Duration> {
+        self.endpoints.get(endpoint).and_then(|tracker| tracker.p95(MIN_LATENCY_SAMPLES))
+    }
+}
+
+/// `TIMEOUT_P95_MULTIPLIER × p95` clamped to `[min, max]`; `fallback` without a p95 or when `max` is zero.
+pub fn adaptive_timeout(p95: Option<Duration>, fallback: Duration, min: Duration, max: Duration) -> Duration {
+    match p95 {
+        Some(p95) if !max.is_zero() => (p95 * TIMEOUT_P95_MULTIPLIER).clamp(min.min(max), max),
+        _ => fallback,
+    }
+}
+// END OF FILE: bot/src/latency.rs
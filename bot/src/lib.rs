@@ -7,19 +7,58 @@ use ethers::types::H256;
 use lazy_static::lazy_static;
 
 // Re-export modules needed by integration tests and potentially the binary
+pub mod accuracy;
+pub mod admin;
+pub mod alerts;
+pub mod audit;
+pub mod balance;
 pub mod bindings;
+pub mod chain_presets;
 pub mod config;
+pub mod cooldown;
+pub mod deadline;
 pub mod deploy;
+pub mod discovery;
+pub mod dump;
 pub mod encoding;
 pub mod event_handler;
+pub mod exposure;
+pub mod flash_loan;
 pub mod gas;
+pub mod gas_learning;
+pub mod instance_lock;
+pub mod latency;
 #[cfg(feature = "local_simulation")] // Conditionally compile local_simulator
 pub mod local_simulator;
+pub mod mempool;
+pub mod metrics;
+pub mod nonce_watch;
+pub mod oracle;
 pub mod path_optimizer;
+pub mod pool_cache;
+pub mod preflight;
+pub mod price_impact;
+pub mod rebalance;
+pub mod relay;
+pub mod replay;
+pub mod retry;
+pub mod revert;
+pub mod rolling_window;
+pub mod rpc;
+pub mod seen_logs;
+pub mod self_test;
+pub mod sim_cache;
 pub mod simulation;
+pub mod slippage;
 pub mod state;
+pub mod throttle;
+pub mod token_safety;
 pub mod transaction;
+pub mod twap;
+pub mod univ3_math;
 pub mod utils;
+pub mod velo_math;
+pub mod warmup;
 
 // Public types/constants re-exported for convenience
 pub use state::{AppState, DexType, PoolSnapshot, PoolState}; // Re-export key types
@@ -32,6 +71,9 @@ pub use transaction::NonceManager; // Re-export NonceManager
 lazy_static! {
     pub static ref UNI_V3_SWAP_TOPIC: H256 = bindings::uniswap_v3_pool::SwapFilter::signature();
     pub static ref UNI_V3_POOL_CREATED_TOPIC: H256 = bindings::i_uniswap_v3_factory::PoolCreatedFilter::signature();
+    pub static ref UNI_V3_MINT_TOPIC: H256 = bindings::uniswap_v3_pool::MintFilter::signature();
+    pub static ref UNI_V3_BURN_TOPIC: H256 = bindings::uniswap_v3_pool::BurnFilter::signature();
     pub static ref VELO_AERO_SWAP_TOPIC: H256 = bindings::velodrome_v2_pool::SwapFilter::signature();
     pub static ref VELO_AERO_POOL_CREATED_TOPIC: H256 = bindings::i_velodrome_factory::PoolCreatedFilter::signature();
+    pub static ref PROFIT_REALIZED_TOPIC: H256 = bindings::arbitrage_executor::ProfitRealizedFilter::signature();
 }
\ No newline at end of file
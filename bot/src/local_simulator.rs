@@ -3,6 +3,7 @@
 #![allow(unexpected_cfgs)] // Keep this allow
 
 use crate::bindings::{UniswapV3Pool, VelodromeV2Pool}; // Use crate::
+use crate::config::{build_dir, EXECUTOR_BYTECODE_FILE};
 use ethers::{
     abi::Abi,
     prelude::{
@@ -13,7 +14,7 @@ use ethers::{
     utils::{hex},
 };
 use eyre::{Result, WrapErr, eyre}; // Keep Result and eyre
-use std::{fs, sync::Arc, time::Duration};
+use std::{fs, path::Path, sync::Arc, time::Duration};
 use tracing::{debug, error, info, instrument, warn};
 use tokio::time::error::Elapsed;
 
@@ -31,7 +32,7 @@ pub struct SimulationConfig { // Changed to pub struct
     pub target_uniswap_v3_pool_address: &'static str,
     pub target_velodrome_v2_pool_address: &'static str,
     pub deploy_executor_in_sim: bool,
-    pub executor_bytecode_path: &'static str,
+    pub executor_bytecode_file: &'static str, // Under `config::build_dir()`
     pub emulated_send_latency_ms: u64,
     pub emulated_read_latency_ms: u64,
 }
@@ -48,7 +49,7 @@ pub const SIMULATION_CONFIG: SimulationConfig = SimulationConfig {
     target_uniswap_v3_pool_address: "0x851492574065EDE975391E141377067943aA08eF", // OP WETH/USDC 0.05%
     target_velodrome_v2_pool_address: "0x207addb05c548f262219f6b50eadff8640ed6488", // OP WETH/USDC Stable
     deploy_executor_in_sim: true,
-    executor_bytecode_path: "./build/ArbitrageExecutor.bin",
+    executor_bytecode_file: EXECUTOR_BYTECODE_FILE,
     emulated_send_latency_ms: 10,
     emulated_read_latency_ms: 5,
 };
@@ -94,8 +95,10 @@ pub async fn setup_simulation_environment() -> Result<SimEnv> {
 
     let executor_address = if SIMULATION_CONFIG.deploy_executor_in_sim {
         info!("Deploying Executor contract via Anvil...");
-        let bytecode_hex = fs::read_to_string(SIMULATION_CONFIG.executor_bytecode_path)
-            .wrap_err("Failed to read executor bytecode")?;
+        // The simulator does not load a Config; build_dir() is the same lookup load_config uses
+        let bytecode_path = Path::new(&build_dir()).join(SIMULATION_CONFIG.executor_bytecode_file);
+        let bytecode_hex = fs::read_to_string(&bytecode_path)
+            .wrap_err_with(|| format!("Failed to read executor bytecode from {}", bytecode_path.display()))?;
         let cleaned = bytecode_hex.trim().trim_start_matches("0x");
         let bytecode = hex::decode(cleaned)
             .wrap_err("Failed to decode executor bytecode")?;
@@ -167,6 +170,7 @@ pub async fn trigger_v2_swap(
 }
 
 
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip(sim_env, pool_binding), fields(pool_addr=%pool_addr))]
 pub async fn trigger_v3_swap(
     sim_env: &SimEnv, // Use borrow
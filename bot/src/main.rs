@@ -1,24 +1,33 @@
 // bot/src/main.rs
 
 // Use the library crate name 'ulp1_5' to access modules
+use ulp1_5::admin::run_admin_server;
+use ulp1_5::alerts::{send_alert, AlertLevel};
+use ulp1_5::audit::AuditLog;
+use ulp1_5::balance::check_gas_balance;
 use ulp1_5::bindings::{AerodromePool, IUniswapV3Factory, IVelodromeFactory, IAerodromeFactory, VelodromeV2Pool}; // Removed unused bindings::self
-use ulp1_5::config::load_config; // Removed unused config::self
-use ulp1_5::deploy::deploy_contract_from_bytecode; // Removed unused deploy::self
+use ulp1_5::config::{load_config, PoolDiscovery}; // Removed unused config::self
+use ulp1_5::discovery::{backfill_pool_created, univ3_pool_keys, DiscoveredPool};
+use ulp1_5::deploy::{deploy_contract_from_bytecode, verify_executor_selectors, verify_transient_storage};
+use ulp1_5::dump::{dump_snapshots, SnapshotDumpFormat};
+use ulp1_5::flash_loan::refresh_balancer_flash_loan_fee;
+use ulp1_5::gas::detect_tx_type;
+use ulp1_5::instance_lock::InstanceLock;
+use ulp1_5::metrics::Metrics;
+use ulp1_5::nonce_watch::{check_nonce_divergence, NonceWatch};
+use ulp1_5::pool_cache::{load_pool_cache, write_pool_cache, CachedPool, PoolCacheLoad};
 // encoding might not be needed directly in main
-use ulp1_5::event_handler::{handle_log_event, handle_new_block}; // Removed unused event_handler::self
+use ulp1_5::event_handler::{handle_log_event, handle_new_block, handle_pending_tx, monitored_topics, run_detection_pass}; // Removed unused event_handler::self
 // gas might not be needed directly in main
 // local_simulator only used when feature enabled, not directly in main runtime
 // path_optimizer not needed directly in main
-// simulation not needed directly in main
+use ulp1_5::rebalance::rebalance_weth;
+use ulp1_5::replay::run_replay;
+use ulp1_5::rpc::RpcClients;
+use ulp1_5::self_test::run_self_test;
+use ulp1_5::simulation::find_break_even_loan;
 use ulp1_5::state::{self, AppState, DexType}; // Use state module and specific types
-use ulp1_5::transaction::NonceManager; // Removed unused transaction::self
-// utils might not be needed directly in main
-
-// Import re-exported topics from lib.rs
-use ulp1_5::{
-    UNI_V3_POOL_CREATED_TOPIC, UNI_V3_SWAP_TOPIC, VELO_AERO_POOL_CREATED_TOPIC,
-    VELO_AERO_SWAP_TOPIC,
-};
+use ulp1_5::transaction::{fetch_simulation_gas_price_gwei, NonceManager};
 
 
 use ethers::prelude::*;
@@ -28,10 +37,10 @@ use ethers::types::{
 };
 use eyre::{eyre, Result, WrapErr};
 use std::{collections::HashSet, sync::Arc};
-use tokio::time::{interval, timeout, Duration};
+use tokio::time::{interval, timeout, timeout_at, Duration, Instant};
 use tokio::task::JoinHandle;
 use chrono::Utc;
-use futures_util::{future::join_all, FutureExt};
+use futures_util::{future::join_all, stream, FutureExt, Stream};
 // Removed lazy_static import, topics now come from lib
 use tracing::{debug, error, info, warn, Level, trace};
 use tracing_subscriber::{fmt, EnvFilter};
@@ -39,56 +48,111 @@ use tracing_subscriber::{fmt, EnvFilter};
 // --- Constants ---
 const INITIAL_STATE_FETCH_TIMEOUT_SECS: u64 = 120;
 const EVENT_STREAM_HEALTH_CHECK_INTERVAL_SECS: u64 = 60;
+const SHUTDOWN_ALERT_GRACE_SECS: u64 = 5;
+const MAX_PENDING_TX_FETCHES: usize = 64; // In-flight `eth_getTransactionByHash` lookups; further hashes are dropped
 
 // --- Main Execution ---
 #[tokio::main]
 async fn main() -> Result<()> {
     fmt().with_env_filter(EnvFilter::from_default_env().add_directive(Level::INFO.into())).with_target(true).with_line_number(true).init();
     info!("🚀 Starting Arbitrage Bot ULP 1.5 (Scalable Core)...");
+    // --once: initial fetch, one detection pass, then exit (nonzero when nothing is profitable)
+    let run_once = std::env::args().skip(1).any(|arg| arg == "--once");
+    let report_break_even = std::env::args().skip(1).any(|arg| arg == "--break-even"); // With --once: also print each route's break-even loan
     // Use imported load_config directly
     let config = load_config().wrap_err("Config load failed")?; debug!(?config, "Config loaded");
+    // self-test: deploy, create a discrepancy and arbitrage it on an Anvil fork, then exit (see `self_test`)
+    if std::env::args().nth(1).as_deref() == Some("self-test") {
+        return match run_self_test(&config).await {
+            Ok(report) => {
+                println!("SELF-TEST PASSED: executor={:?} route={} loan_wei={} sim_profit_wei={} tx={:?}", report.executor, report.route_id, report.loan_amount_wei, report.simulated_net_profit_wei, report.tx_hash);
+                Ok(())
+            }
+            Err(e) => { println!("SELF-TEST FAILED: {:#}", e); Err(e) }
+        };
+    }
+    // replay <block> <buy_pool>:<dex> <sell_pool>:<dex>: the route's loan search as of a past block (archive node)
+    if std::env::args().nth(1).as_deref() == Some("replay") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        let report = run_replay(&config, &args).await?;
+        match report.best {
+            Some((loan_wei, net_profit_wei)) => println!("REPLAY block={} route={} gas_price_gwei={:.4} loan_wei={} net_profit_wei={}", report.block, report.route_id, report.gas_price_gwei, loan_wei, net_profit_wei),
+            None => println!("REPLAY block={} route={} gas_price_gwei={:.4}: no profitable loan", report.block, report.route_id, report.gas_price_gwei),
+        }
+        return Ok(());
+    }
 
     info!("Setting up providers & client...");
     let provider_ws = Provider::<Ws>::connect(&config.ws_rpc_url).await.wrap_err("WS connection failed")?;
     let provider_ws_arc: Arc<Provider<Ws>> = Arc::new(provider_ws); info!("✅ WS Connected.");
-    let http_provider = Provider::<Http>::try_from(config.http_rpc_url.clone()).wrap_err("HTTP provider creation failed")?;
+    let http_provider = Provider::<Http>::try_from(config.read_endpoint()).wrap_err("HTTP provider creation failed")?;
     let chain_id = config.chain_id.unwrap_or(http_provider.get_chainid().await?.as_u64()); info!(%chain_id, "Using Chain ID.");
     let wallet = config.local_private_key.parse::<LocalWallet>()?.with_chain_id(chain_id); let wallet_address = wallet.address();
-    let client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>> = Arc::new(SignerMiddleware::new(http_provider.clone(), wallet)); info!(address = ?wallet_address, "✅ Signer Client OK.");
+    // Reads (state, simulation) and submissions may use different endpoints; `client` is the read side
+    let clients = RpcClients::new(&config, wallet).wrap_err("RPC client creation failed")?;
+    let client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>> = clients.read.clone(); info!(address = ?wallet_address, split_endpoints = clients.is_split(), "✅ Signer Client OK.");
+    // Held until main returns; a second instance on the same signer refuses to start
+    let _instance_lock = InstanceLock::acquire(InstanceLock::path_for(&config, wallet_address), wallet_address).wrap_err("Instance lock")?;
+    if config.allow_submission_zero_profit {
+        if config.zero_profit_submission_allowed(chain_id) { warn!(%chain_id, "⚠️ ALLOW_SUBMISSION_ZERO_PROFIT: unprofitable routes will be submitted (test environments only)."); }
+        else { warn!(%chain_id, production = config.production, "ALLOW_SUBMISSION_ZERO_PROFIT ignored on a production chain."); }
+    }
 
-    info!(vault = %config.balancer_vault_address, "ASSUMPTION: Balancer V2 Vault fee is 0%.");
+    if config.skip_contract_preflight { warn!("Contract preflight skipped (SKIP_CONTRACT_PREFLIGHT)."); } else { config.preflight_contracts(client.clone()).await.wrap_err("Contract preflight failed")?; info!("✅ Configured contracts answer preflight calls."); }
 
+    verify_transient_storage(client.as_ref()).await.wrap_err("Transient storage check failed")?; info!("✅ Chain supports transient storage (EIP-1153).");
     // Use imported deploy function directly
     let arb_executor_address = if config.deploy_executor { info!("Deploying Executor..."); deploy_contract_from_bytecode(client.clone(), &config.executor_bytecode_path).await? } else { info!("Using existing executor..."); config.arb_executor_address.ok_or_else(|| eyre!("Executor address required when not deploying"))? }; info!(address = ?arb_executor_address, "Using Executor.");
+    verify_executor_selectors(client.as_ref(), arb_executor_address, &config).await.wrap_err("Executor ABI check failed")?; info!("✅ Executor implements the expected entry points.");
 
     // Use imported AppState directly
-    let app_state = Arc::new(AppState::new(config.clone())); info!("🧠 State initialized."); let target_pair_filter = app_state.target_pair(); info!(?target_pair_filter, "Target pair set.");
+    let mut app_state = AppState::new(config.clone());
+    app_state.tx_type = detect_tx_type(client.as_ref()).await.wrap_err("Transaction type detection failed")?; info!(tx_type = ?app_state.tx_type, "Detected transaction type.");
+    let mut audit_writer = None;
+    if let Some(path) = config.audit_log_path.as_deref() {
+        let (audit, writer) = AuditLog::open(path).await?;
+        app_state.audit = audit;
+        audit_writer = Some(writer);
+    }
+    if let Err(e) = refresh_balancer_flash_loan_fee(client.clone(), &app_state).await { warn!(error = ?e, "Could not read the Balancer flash loan fee. Assuming 0%."); }
+    let app_state = Arc::new(app_state); info!("🧠 State initialized."); let target_pair_filter = app_state.target_tokens(); info!(?target_pair_filter, "Target pair set.");
     // Use imported NonceManager directly
     let nonce_manager = Arc::new(NonceManager::new(wallet_address)); info!("🔑 Nonce Manager initialized.");
 
-    info!("🔍 Fetching initial states..."); let mut tasks: Vec<JoinHandle<()>> = Vec::new(); let mut monitored = HashSet::new(); let fetch_timeout = Duration::from_secs(config.fetch_timeout_secs.unwrap_or(15));
+    if let Some(admin_addr) = config.admin_addr.clone() {
+        let admin_state = app_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_admin_server(&admin_addr, admin_state).await { error!(error = ?e, "Admin interface stopped."); }
+        });
+    }
+
+    info!("🔍 Fetching initial states..."); let mut tasks: Vec<JoinHandle<()>> = Vec::new(); let mut monitored = HashSet::new(); let fetch_timeout = app_state.fetch_timeout();
     let mut factory_addresses_for_filter = vec![config.uniswap_v3_factory_addr, config.velodrome_v2_factory_addr]; if let Some(a) = config.aerodrome_factory_addr { factory_addresses_for_filter.push(a); }
 
+    // --- Re-fetch Pools From the Previous Run's Cache ---
+    if let Some(path) = config.pool_cache_path.as_deref() {
+        if let PoolCacheLoad::Loaded(pools) = load_pool_cache(path) {
+            spawn_discovered_pools(pools.iter().map(CachedPool::discovered).collect(), "pool cache", target_pair_filter, &mut monitored, &mut tasks, client.clone(), app_state.clone());
+        }
+    }
+
     // --- Fetch Initial UniV3 Pools ---
-    if let Some((token_a, token_b)) = target_pair_filter {
+    if let Some((weth, quote_tokens)) = target_pair_filter {
         let factory_addr = config.uniswap_v3_factory_addr;
         // Use imported binding directly
         let f = IUniswapV3Factory::new(factory_addr, client.clone());
-        let fees = [100, 500, 3000, 10000];
-        let (q0, q1) = if token_a < token_b { (token_a, token_b) } else { (token_b, token_a) };
-        for fee in fees {
+        // One WETH pair per quote token (e.g. USDC.e and native USDC) and configured fee tier
+        for (q0, q1, fee) in univ3_pool_keys(weth, quote_tokens, config.enabled_univ3_fee_tiers()) {
             match timeout(fetch_timeout, f.get_pool(q0, q1, fee).call()).await {
-                Ok(Ok(pool_addr)) if pool_addr != Address::zero() => {
-                    if monitored.insert(pool_addr) {
-                        let client_c = client.clone();
-                        let app_state_c = app_state.clone();
-                        tasks.push(tokio::spawn(
-                            // Use imported state function directly
-                            state::fetch_and_cache_pool_state(pool_addr, DexType::UniswapV3, factory_addr, client_c, app_state_c).map(move |res| {
-                                if let Err(e) = res { error!(pool=%pool_addr, dex=?DexType::UniswapV3, error=?e, "Spawned initial fetch state failed"); }
-                            })
-                        ));
-                    }
+                Ok(Ok(pool_addr)) if pool_addr != Address::zero() && monitored.insert(pool_addr) => {
+                    let client_c = client.clone();
+                    let app_state_c = app_state.clone();
+                    tasks.push(tokio::spawn(
+                        // Use imported state function directly
+                        state::fetch_and_cache_pool_state(pool_addr, DexType::UniswapV3, factory_addr, None, client_c, app_state_c).map(move |res| {
+                            if let Err(e) = res { error!(pool=%pool_addr, dex=?DexType::UniswapV3, error=?e, "Spawned initial fetch state failed"); }
+                        })
+                    ));
                 }
                 Ok(Err(e)) => warn!(token0=%q0, token1=%q1, fee=fee, error=?e, "UniV3 getPool RPC failed"),
                 Err(_) => warn!(token0=%q0, token1=%q1, fee=fee, "UniV3 getPool timeout"),
@@ -99,24 +163,42 @@ async fn main() -> Result<()> {
         warn!("Target pair not configured, skipping initial UniV3 pool fetch.");
     }
 
-    // --- Fetch Initial VelodromeV2 Pools ---
-    let velo_factory_addr = config.velodrome_v2_factory_addr;
-    // Use imported binding directly
-    let vf = IVelodromeFactory::new(velo_factory_addr, client.clone());
-    match timeout(fetch_timeout * 2, vf.all_pools_length().call()).await {
-        Ok(Ok(len)) => fetch_velo_style_pools(DexType::VelodromeV2, &vf, velo_factory_addr, len, &mut monitored, &mut tasks, client.clone(), app_state.clone()).await,
-        Ok(Err(e)) => error!(dex = "VeloV2", error = ?e, "allPoolsLength RPC failed"),
-        Err(_) => error!(dex = "VeloV2", "Timeout getting allPoolsLength"),
-    }
+    // --- Discover Velo/Aero (and new UniV3) Pools from PoolCreated Logs ---
+    let backfilled = if config.pool_discovery == PoolDiscovery::Logs {
+        let backfill = async {
+            let head = client.get_block_number().await.wrap_err("get_block_number failed")?.as_u64();
+            backfill_pool_created(client.as_ref(), &config, head).await
+        };
+        match backfill.await {
+            Ok(pools) => Some(pools),
+            Err(e) => { warn!(error = ?e, "PoolCreated backfill failed; falling back to factory index enumeration."); None }
+        }
+    } else {
+        None
+    };
 
-    // --- Fetch Initial Aerodrome Pools ---
-    if let Some(aero_factory_addr) = config.aerodrome_factory_addr {
+    if let Some(pools) = backfilled {
+        spawn_discovered_pools(pools, "PoolCreated backfill", target_pair_filter, &mut monitored, &mut tasks, client.clone(), app_state.clone());
+    } else {
+        // --- Fetch Initial VelodromeV2 Pools ---
+        let velo_factory_addr = config.velodrome_v2_factory_addr;
         // Use imported binding directly
-        let af = IAerodromeFactory::new(aero_factory_addr, client.clone());
-        match timeout(fetch_timeout * 2, af.all_pools_length().call()).await {
-            Ok(Ok(len)) => fetch_aero_style_pools(&af, aero_factory_addr, len, &mut monitored, &mut tasks, client.clone(), app_state.clone()).await,
-            Ok(Err(e)) => error!(dex = "Aero", error = ?e, "allPoolsLength RPC failed"),
-            Err(_) => error!(dex = "Aero", "Timeout getting allPoolsLength"),
+        let vf = IVelodromeFactory::new(velo_factory_addr, client.clone());
+        match timeout(fetch_timeout * 2, vf.all_pools_length().call()).await {
+            Ok(Ok(len)) => fetch_velo_style_pools(DexType::VelodromeV2, &vf, velo_factory_addr, len, &mut monitored, &mut tasks, client.clone(), app_state.clone()).await,
+            Ok(Err(e)) => error!(dex = "VeloV2", error = ?e, "allPoolsLength RPC failed"),
+            Err(_) => error!(dex = "VeloV2", "Timeout getting allPoolsLength"),
+        }
+
+        // --- Fetch Initial Aerodrome Pools ---
+        if let Some(aero_factory_addr) = config.aerodrome_factory_addr {
+            // Use imported binding directly
+            let af = IAerodromeFactory::new(aero_factory_addr, client.clone());
+            match timeout(fetch_timeout * 2, af.all_pools_length().call()).await {
+                Ok(Ok(len)) => fetch_aero_style_pools(&af, aero_factory_addr, len, &mut monitored, &mut tasks, client.clone(), app_state.clone()).await,
+                Ok(Err(e)) => error!(dex = "Aero", error = ?e, "allPoolsLength RPC failed"),
+                Err(_) => error!(dex = "Aero", "Timeout getting allPoolsLength"),
+            }
         }
     }
 
@@ -133,21 +215,121 @@ async fn main() -> Result<()> {
     }
     info!("✅ Initial fetch process complete. Pools loaded: {}", app_state.pool_states.len());
 
+    if run_once {
+        let profitable = run_detection_pass(app_state.clone(), client.clone()).await?;
+        let gas_price_gwei = if report_break_even { fetch_simulation_gas_price_gwei(client.as_ref(), &config).await } else { 0.0 };
+        for (route, loan_amount, net_profit) in &profitable {
+            if report_break_even {
+                let break_even = find_break_even_loan(client.clone(), app_state.clone(), route, *loan_amount, gas_price_gwei).await?;
+                println!("{} loan_wei={} net_profit_wei={} break_even_wei={}", route.id(), loan_amount, net_profit, break_even.map(|b| b.to_string()).unwrap_or_default());
+            } else {
+                println!("{} loan_wei={} net_profit_wei={}", route.id(), loan_amount, net_profit);
+            }
+        }
+        if profitable.is_empty() { return Err(eyre!("No profitable route found")); }
+        info!("🛑 Single pass done."); return Ok(());
+    }
+
+    // --- Periodic Full Snapshot Refresh ---
+    if config.full_refresh_interval_secs > 0 {
+        let refresh_client = client.clone();
+        let refresh_state = app_state.clone();
+        let refresh_interval = Duration::from_secs(config.full_refresh_interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = interval(refresh_interval);
+            ticker.tick().await; // First tick is immediate; the initial fetch is still fresh
+            loop {
+                ticker.tick().await;
+                if let Err(e) = state::refresh_all_snapshots(refresh_client.clone(), refresh_state.clone()).await {
+                    warn!(error = ?e, "Full snapshot refresh sweep failed.");
+                }
+            }
+        });
+        info!(interval_secs = config.full_refresh_interval_secs, "🔄 Full snapshot refresh scheduled.");
+    }
+
+    // --- Periodic Balancer Flash Loan Fee Refresh ---
+    if config.flash_loan_fee_refresh_secs > 0 {
+        let fee_client = client.clone();
+        let fee_state = app_state.clone();
+        let fee_interval = Duration::from_secs(config.flash_loan_fee_refresh_secs);
+        tokio::spawn(async move {
+            let mut ticker = interval(fee_interval);
+            ticker.tick().await; // First tick is immediate; the fee was just read at startup
+            loop {
+                ticker.tick().await;
+                if let Err(e) = refresh_balancer_flash_loan_fee(fee_client.clone(), &fee_state).await {
+                    warn!(error = ?e, "Balancer flash loan fee refresh failed. Keeping the last value.");
+                }
+            }
+        });
+    }
+
+    // --- Periodic Signer Gas Balance Check ---
+    if config.gas_balance_check_interval_secs > 0 {
+        let balance_client = client.clone();
+        let balance_state = app_state.clone();
+        let balance_interval = Duration::from_secs(config.gas_balance_check_interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = interval(balance_interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = check_gas_balance(balance_client.as_ref(), &balance_state, wallet_address).await {
+                    warn!(error = ?e, "Signer gas balance check failed.");
+                }
+            }
+        });
+        info!(interval_secs = config.gas_balance_check_interval_secs, min_wei = %config.min_gas_balance_wei, "⛽ Gas balance check scheduled.");
+    }
+
+    // --- Periodic WETH Working Balance Rebalance ---
+    if config.rebalance_interval_secs > 0 && !config.min_weth_working_balance.is_zero() {
+        let rebalance_client = clients.write.clone();
+        let rebalance_state = app_state.clone();
+        let rebalance_nonce_manager = nonce_manager.clone();
+        let rebalance_interval = Duration::from_secs(config.rebalance_interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = interval(rebalance_interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = rebalance_weth(rebalance_client.clone(), &rebalance_state, &rebalance_nonce_manager, wallet_address).await {
+                    warn!(error = ?e, "WETH rebalance failed.");
+                }
+            }
+        });
+        info!(interval_secs = config.rebalance_interval_secs, min_weth = %config.min_weth_working_balance, "⚖️ WETH rebalance scheduled.");
+    }
+
+    // --- Periodic Nonce Divergence Check (dead man's switch) ---
+    if config.nonce_divergence_blocks > 0 {
+        let nonce_client = client.clone();
+        let nonce_state = app_state.clone();
+        let nonce_watch_manager = nonce_manager.clone();
+        let nonce_interval = Duration::from_secs(config.nonce_check_interval_secs);
+        tokio::spawn(async move {
+            let watch = NonceWatch::default();
+            let mut ticker = interval(nonce_interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = check_nonce_divergence(nonce_client.as_ref(), &nonce_state, &nonce_watch_manager, &watch, wallet_address).await {
+                    warn!(error = ?e, "Nonce divergence check failed.");
+                }
+            }
+        });
+        info!(interval_secs = config.nonce_check_interval_secs, max_blocks = config.nonce_divergence_blocks, "🪦 Nonce divergence check scheduled.");
+    }
+
     // --- Setup Event Filters ---
     let current_monitored_addrs: Vec<Address> = app_state.pool_states.iter().map(|e| *e.key()).collect();
     if current_monitored_addrs.is_empty() { warn!("No target pools found or fetched successfully during initial load. Swap monitoring might be ineffective."); }
     else { info!("Monitoring swaps for {} pools.", current_monitored_addrs.len()); }
 
-    // Use imported topics directly
-    let swap_topics = vec![*UNI_V3_SWAP_TOPIC, *VELO_AERO_SWAP_TOPIC];
-    let factory_topics = vec![*UNI_V3_POOL_CREATED_TOPIC, *VELO_AERO_POOL_CREATED_TOPIC];
-
+    // topic0 set from MONITORED_EVENTS (swaps and pool creations by default)
     let combined_addresses: Vec<H160> = current_monitored_addrs.into_iter()
         .chain(factory_addresses_for_filter.into_iter())
         .collect();
-    let combined_topics: Vec<H256> = swap_topics.into_iter()
-        .chain(factory_topics.into_iter())
-        .collect();
+    let combined_topics: Vec<H256> = monitored_topics(config.enabled_monitored_events());
+    info!(events = ?config.enabled_monitored_events(), "Monitored log events.");
 
     let combined_filter = Filter::new()
         .address(combined_addresses)
@@ -160,6 +342,14 @@ async fn main() -> Result<()> {
     let mut log_stream = match provider_ws_arc.subscribe_logs(&combined_filter).await {
          Ok(stream) => stream, Err(e) => return Err(eyre!(e).wrap_err("Failed to subscribe to log stream")),
     };
+    // Optional: pending transactions, for predicting arbs before the triggering swap confirms
+    let mut pending_tx_stream = if config.enable_mempool {
+        match provider_ws_arc.subscribe_pending_txs().await {
+            Ok(stream) => { info!("Subscribed to pending transactions (mempool)."); Some(stream) }
+            Err(e) => { warn!(error = ?e, "Pending transaction subscription unavailable on this endpoint. Continuing without mempool."); None }
+        }
+    } else { None };
+    let pending_tx_fetches = Arc::new(tokio::sync::Semaphore::new(MAX_PENDING_TX_FETCHES));
     info!("✅ Subscribed.");
 
     // --- Main Event Loop ---
@@ -167,6 +357,7 @@ async fn main() -> Result<()> {
     let mut health_check = interval(Duration::from_secs(EVENT_STREAM_HEALTH_CHECK_INTERVAL_SECS));
     let mut last_block_time = Utc::now();
     let mut last_log_time = Utc::now();
+    let mut shutdown_alert = None;
 
     loop { tokio::select! { biased;
         // --- Handle Log Events ---
@@ -176,14 +367,49 @@ async fn main() -> Result<()> {
                     last_log_time = Utc::now();
                     trace!(tx_hash = ?log.transaction_hash, block = ?log.block_number, address = %log.address, topics=?log.topics, "Received log");
                     let s = app_state.clone();
-                    let c = client.clone();
+                    let c = clients.clone();
                     let nm = nonce_manager.clone();
                     tokio::spawn(async move {
                         // Use imported handle_log_event directly
                         if let Err(e) = handle_log_event(log, s, c, nm).await { error!(error = ?e, "handle_log_event failed"); }
                     });
                 }
-                None => { error!("ALERT: Log stream subscription ended unexpectedly. WS connection may be lost. Shutting down."); break; }
+                None => {
+                    error!("ALERT: Log stream subscription ended unexpectedly. WS connection may be lost. Shutting down.");
+                    shutdown_alert = send_alert(&config, AlertLevel::Critical, "Log stream subscription ended unexpectedly. Bot shutting down.");
+                    break;
+                }
+            }
+        },
+        // --- Handle Pending Transactions (mempool) ---
+        maybe_tx_hash = async { match pending_tx_stream.as_mut() { Some(stream) => stream.next().await, None => std::future::pending().await } } => {
+            match maybe_tx_hash {
+                Some(tx_hash) => {
+                    let Ok(permit) = pending_tx_fetches.clone().try_acquire_owned() else {
+                        Metrics::inc(&app_state.metrics.pending_tx_fetches_dropped);
+                        trace!(tx = ?tx_hash, "Pending tx lookups saturated. Dropping hash.");
+                        continue;
+                    };
+                    let s = app_state.clone();
+                    let c = clients.clone();
+                    let nm = nonce_manager.clone();
+                    let ws = provider_ws_arc.clone();
+                    tokio::spawn(async move {
+                        let fetched = ws.get_transaction(tx_hash).await;
+                        drop(permit);
+                        match fetched {
+                            Ok(Some(tx)) => {
+                                if let Err(e) = handle_pending_tx(tx, s, c, nm).await { error!(tx = ?tx_hash, error = ?e, "handle_pending_tx failed"); }
+                            }
+                            Ok(None) => trace!(tx = ?tx_hash, "Pending tx no longer available."),
+                            Err(e) => trace!(tx = ?tx_hash, error = ?e, "Failed to fetch pending tx."),
+                        }
+                    });
+                }
+                None => {
+                    warn!("Pending transaction stream ended. Continuing on confirmed logs only.");
+                    pending_tx_stream = None;
+                }
             }
         },
         // --- Handle Block Events ---
@@ -194,13 +420,18 @@ async fn main() -> Result<()> {
                     if let Some(n) = block.number {
                         trace!("Received block #{}", n.as_u64());
                         let s = app_state.clone();
+                        let block_timestamp = block.timestamp.as_u64();
                         tokio::spawn(async move {
                              // Use imported handle_new_block directly
-                            if let Err(e) = handle_new_block(n, s).await { error!(block = n.as_u64(), error = ?e, "handle_new_block failed"); }
+                            if let Err(e) = handle_new_block(n, block_timestamp, s).await { error!(block = n.as_u64(), error = ?e, "handle_new_block failed"); }
                         });
                     } else { warn!("Block received without number: {:?}", block.hash); }
                  }
-                 None => { error!("ALERT: Block stream subscription ended unexpectedly. WS connection may be lost. Shutting down."); break; }
+                 None => {
+                     error!("ALERT: Block stream subscription ended unexpectedly. WS connection may be lost. Shutting down.");
+                     shutdown_alert = send_alert(&config, AlertLevel::Critical, "Block stream subscription ended unexpectedly. Bot shutting down.");
+                     break;
+                 }
              }
         },
         // --- Health Check Timer ---
@@ -208,7 +439,9 @@ async fn main() -> Result<()> {
             let now = Utc::now();
             let block_lag = (now - last_block_time).num_seconds();
             let log_lag = (now - last_log_time).num_seconds();
-            info!(block_lag = block_lag, log_lag = log_lag, pools = app_state.pool_states.len(), snapshots = app_state.pool_snapshots.len(), "🩺 Health");
+            let max_snapshot_age = app_state.max_snapshot_age_secs(now.timestamp().max(0) as u64);
+            Metrics::set(&app_state.metrics.max_snapshot_age_secs, max_snapshot_age.unwrap_or(0));
+            info!(block_lag = block_lag, log_lag = log_lag, pools = app_state.pool_states.len(), snapshots = app_state.pool_snapshots.len(), max_snapshot_age_secs = ?max_snapshot_age, metrics = ?app_state.metrics.values(), "🩺 Health");
              // Access config values via app_state.config
             // Fix E0308: Cast u64 config values to i64 for comparison with Duration::num_seconds() result
             let critical_block_lag = app_state.config.critical_block_lag_seconds as i64;
@@ -218,18 +451,38 @@ async fn main() -> Result<()> {
                     "ALERT: High event stream lag detected (Block: {}s > {}s, Log: {}s > {}s). Streams might be stalled. SHUTTING DOWN.",
                     block_lag, critical_block_lag, log_lag, critical_log_lag
                 );
+                 shutdown_alert = send_alert(&config, AlertLevel::Critical, format!(
+                    "High event stream lag (Block: {}s > {}s, Log: {}s > {}s). Bot shutting down.",
+                    block_lag, critical_block_lag, log_lag, critical_log_lag
+                 ));
                  break;
             }
         },
         // --- Handle Ctrl+C ---
         _ = tokio::signal::ctrl_c() => { info!("🔌 Shutdown signal received..."); break; },
     }}
+    // Give an in-flight shutdown alert a chance to be delivered before the runtime exits.
+    if let Some(handle) = shutdown_alert { let _ = timeout(Duration::from_secs(SHUTDOWN_ALERT_GRACE_SECS), handle).await; }
+    // Spawned tasks still hold audit handles, so the writer is closed explicitly and its queue drained
+    app_state.audit.close();
+    if let Some(writer) = audit_writer {
+        if timeout(Duration::from_secs(SHUTDOWN_ALERT_GRACE_SECS), writer).await.is_err() { warn!("Audit log writer did not finish in time. Last records may be lost."); }
+    }
+    if let Some(path) = config.snapshot_dump_path.as_deref() {
+        if let Err(e) = dump_snapshots(&app_state, path, SnapshotDumpFormat::Csv) { error!(error = ?e, "Snapshot dump on shutdown failed."); }
+    }
+    if let Some(path) = config.pool_cache_path.as_deref() {
+        if let Err(e) = write_pool_cache(&app_state, path) { error!(error = ?e, "Pool cache write on shutdown failed."); }
+    }
     info!("🛑 Bot stopped."); Ok(())
 }
 
 
 /// Helper function to fetch initial pools for Velo-style factories.
-async fn fetch_velo_style_pools<M: Middleware + 'static>(
+/// `allPools(i)` + `tokens()` lookups run `config.enumeration_concurrency` at a time; pools still pending when
+/// `config.enumeration_timeout_secs` runs out are skipped with a warning.
+#[allow(clippy::too_many_arguments)]
+async fn fetch_velo_style_pools<M>(
     dex_type: DexType,
     factory_binding: &IVelodromeFactory<M>,
     factory_addr: Address,
@@ -238,50 +491,35 @@ async fn fetch_velo_style_pools<M: Middleware + 'static>(
     tasks: &mut Vec<JoinHandle<()>>,
     client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
     app_state: Arc<AppState>,
-) where M: Middleware + Sync + Send, M::Error: Send + Sync + 'static {
+) where M: Middleware + Sync + Send + 'static, M::Error: Send + Sync + 'static {
      // Access config via app_state.config
-     let fetch_timeout = Duration::from_secs(app_state.config.fetch_timeout_secs.unwrap_or(15));
-     let target_pair_opt = app_state.target_pair();
+     let target_pair_opt = app_state.target_tokens();
 
-     for i in 0..pool_len.as_usize() {
-          let index = U256::from(i);
-          match timeout(fetch_timeout, factory_binding.all_pools(index).call()).await {
-               Ok(Ok(pool_addr)) if pool_addr != Address::zero() => {
-                    let client_c = client.clone();
-                    // Use imported binding
-                    let pool_binding = VelodromeV2Pool::new(pool_addr, client_c);
-                    match timeout(fetch_timeout, pool_binding.tokens().call()).await {
-                         Ok(Ok((t0, t1))) => {
-                              // Use imported state function
-                              if state::is_target_pair_option(t0, t1, target_pair_opt) {
-                                   if monitored.insert(pool_addr) {
-                                        let client_clone = client.clone();
-                                        let app_state_clone = app_state.clone();
-                                        tasks.push(tokio::spawn(async move {
-                                             // Use imported state function
-                                             if let Err(e) = state::fetch_and_cache_pool_state(pool_addr, dex_type, factory_addr, client_clone, app_state_clone).await {
-                                                 error!(pool=%pool_addr, dex=?dex_type, error=?e,"Spawned fetch state failed for Velo-style pool");
-                                             }
-                                        }));
-                                   } else {
-                                       trace!(pool=%pool_addr, "Already monitoring pool.");
-                                   }
-                              } else {
-                                  trace!(pool=%pool_addr, "Skipping non-target pair: {:?}/{:?}", t0, t1);
-                              }
+     let lookups = stream::iter(0..pool_len.as_usize()).map(|i| {
+          let client_c = client.clone();
+          let app_state_c = app_state.clone();
+          async move {
+               let index = U256::from(i);
+               match app_state_c.timed_fetch(factory_binding.all_pools(index).call()).await {
+                    Ok(Ok(pool_addr)) if pool_addr != Address::zero() => {
+                         // Use imported binding
+                         let pool_binding = VelodromeV2Pool::new(pool_addr, client_c);
+                         match app_state_c.timed_fetch(pool_binding.tokens().call()).await {
+                              Ok(Ok((t0, t1))) => Some((pool_addr, t0, t1)),
+                              Ok(Err(e)) => { warn!(pool=%pool_addr, error=?e, dex=?dex_type, "Failed tokens() RPC"); None }
+                              Err(_) => { warn!(pool=%pool_addr, dex=?dex_type, "Timeout tokens()"); None }
                          }
-                         Ok(Err(e)) => warn!(pool=%pool_addr, error=?e, dex=?dex_type, "Failed tokens() RPC"),
-                         Err(_) => warn!(pool=%pool_addr, dex=?dex_type, "Timeout tokens()"),
                     }
+                    Ok(Ok(_)) => None,
+                    Ok(Err(e)) => { warn!(idx=i, error=?e, dex=?dex_type, "allPools RPC failed"); None }
+                    Err(_) => { warn!(idx=i, dex=?dex_type, "Timeout allPools"); None }
                }
-               Ok(Ok(_)) => {}
-               Ok(Err(e)) => warn!(idx=i, error=?e, dex=?dex_type, "allPools RPC failed"),
-               Err(_) => warn!(idx=i, dex=?dex_type, "Timeout allPools"),
           }
-     }
+     }).buffer_unordered(app_state.config.enumeration_concurrency.max(1));
+     collect_enumerated_pools(lookups, dex_type, factory_addr, target_pair_opt, monitored, tasks, client.clone(), app_state.clone()).await;
 }
 
-/// Helper function specifically for Aerodrome factory type.
+/// Helper function specifically for Aerodrome factory type (same batching as `fetch_velo_style_pools`).
 async fn fetch_aero_style_pools(
     factory_binding: &IAerodromeFactory<SignerMiddleware<Provider<Http>, LocalWallet>>,
     factory_addr: Address,
@@ -293,45 +531,105 @@ async fn fetch_aero_style_pools(
 ) {
     let dex_type = DexType::Aerodrome;
     // Access config via app_state.config
-    let fetch_timeout = Duration::from_secs(app_state.config.fetch_timeout_secs.unwrap_or(15));
-    let target_pair_opt = app_state.target_pair();
+    let target_pair_opt = app_state.target_tokens();
 
-     for i in 0..pool_len.as_usize() {
-          let index = U256::from(i);
-          match timeout(fetch_timeout, factory_binding.all_pools(index).call()).await {
-               Ok(Ok(pool_addr)) if pool_addr != Address::zero() => {
-                    let client_c = client.clone();
-                    // Use imported binding
-                    let pool_binding = AerodromePool::new(pool_addr, client_c);
-                    match timeout(fetch_timeout, pool_binding.tokens().call()).await {
-                         Ok(Ok((t0, t1))) => {
-                             // Use imported state function
-                              if state::is_target_pair_option(t0, t1, target_pair_opt) {
-                                   if monitored.insert(pool_addr) {
-                                        let client_clone = client.clone();
-                                        let app_state_clone = app_state.clone();
-                                        tasks.push(tokio::spawn(async move {
-                                             // Use imported state function
-                                             if let Err(e) = state::fetch_and_cache_pool_state(pool_addr, dex_type, factory_addr, client_clone, app_state_clone).await {
-                                                 error!(pool=%pool_addr, dex=?dex_type, error=?e,"Spawned fetch state failed for Aero pool");
-                                             }
-                                        }));
-                                   } else {
-                                        trace!(pool=%pool_addr, "Already monitoring pool.");
-                                   }
-                              } else {
-                                  trace!(pool=%pool_addr, "Skipping non-target pair: {:?}/{:?}", t0, t1);
-                              }
+     let lookups = stream::iter(0..pool_len.as_usize()).map(|i| {
+          let client_c = client.clone();
+          let app_state_c = app_state.clone();
+          async move {
+               let index = U256::from(i);
+               match app_state_c.timed_fetch(factory_binding.all_pools(index).call()).await {
+                    Ok(Ok(pool_addr)) if pool_addr != Address::zero() => {
+                         // Use imported binding
+                         let pool_binding = AerodromePool::new(pool_addr, client_c);
+                         match app_state_c.timed_fetch(pool_binding.tokens().call()).await {
+                              Ok(Ok((t0, t1))) => Some((pool_addr, t0, t1)),
+                              Ok(Err(e)) => { warn!(pool=%pool_addr, error=?e, dex=?dex_type, "Failed tokens() RPC"); None }
+                              Err(_) => { warn!(pool=%pool_addr, dex=?dex_type, "Timeout tokens()"); None }
                          }
-                         Ok(Err(e)) => warn!(pool=%pool_addr, error=?e, dex=?dex_type, "Failed tokens() RPC"),
-                         Err(_) => warn!(pool=%pool_addr, dex=?dex_type, "Timeout tokens()"),
                     }
+                    Ok(Ok(_)) => None,
+                    Ok(Err(e)) => { warn!(idx=i, error=?e, dex=?dex_type, "allPools RPC failed"); None }
+                    Err(_) => { warn!(idx=i, dex=?dex_type, "Timeout allPools"); None }
                }
-               Ok(Ok(_)) => {}
-               Ok(Err(e)) => warn!(idx=i, error=?e, dex=?dex_type, "allPools RPC failed"),
-               Err(_) => warn!(idx=i, dex=?dex_type, "Timeout allPools"),
           }
+     }).buffer_unordered(app_state.config.enumeration_concurrency.max(1));
+     collect_enumerated_pools(lookups, dex_type, factory_addr, target_pair_opt, monitored, tasks, client.clone(), app_state.clone()).await;
+}
+
+/// Drains enumeration results until the overall `config.enumeration_timeout_secs` deadline, spawning an
+/// initial state fetch for every new target-pair pool.
+#[allow(clippy::too_many_arguments)]
+async fn collect_enumerated_pools<S>(
+    lookups: S,
+    dex_type: DexType,
+    factory_addr: Address,
+    target_pair_opt: Option<(Address, &[Address])>,
+    monitored: &mut HashSet<Address>,
+    tasks: &mut Vec<JoinHandle<()>>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: Arc<AppState>,
+) where S: Stream<Item = Option<(Address, Address, Address)>> {
+     let deadline = Instant::now() + Duration::from_secs(app_state.config.enumeration_timeout_secs);
+     let mut lookups = std::pin::pin!(lookups);
+     loop {
+          let (pool_addr, t0, t1) = match timeout_at(deadline, lookups.next()).await {
+               Ok(Some(Some(found))) => found,
+               Ok(Some(None)) => continue,
+               Ok(None) => break,
+               Err(_) => { warn!(dex=?dex_type, timeout_secs = app_state.config.enumeration_timeout_secs, "Pool enumeration timed out; remaining pools skipped."); break; }
+          };
+          // Use imported state function
+          if !state::is_target_pair_option(t0, t1, target_pair_opt) {
+               trace!(pool=%pool_addr, "Skipping non-target pair: {:?}/{:?}", t0, t1);
+               continue;
+          }
+          if !monitored.insert(pool_addr) {
+               trace!(pool=%pool_addr, "Already monitoring pool.");
+               continue;
+          }
+          let client_clone = client.clone();
+          let app_state_clone = app_state.clone();
+          tasks.push(tokio::spawn(async move {
+               // Use imported state function
+               if let Err(e) = state::fetch_and_cache_pool_state(pool_addr, dex_type, factory_addr, None, client_clone, app_state_clone).await {
+                    error!(pool=%pool_addr, dex=?dex_type, error=?e,"Spawned fetch state failed for enumerated pool");
+               }
+          }));
+     }
+}
+
+/// Spawns an initial state fetch for every new target-pair pool found by the `PoolCreated` backfill (or
+/// read from the pool cache), passing the known fee and recording the pool's creation block.
+fn spawn_discovered_pools(
+    pools: Vec<DiscoveredPool>,
+    source: &'static str,
+    target_pair_opt: Option<(Address, &[Address])>,
+    monitored: &mut HashSet<Address>,
+    tasks: &mut Vec<JoinHandle<()>>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: Arc<AppState>,
+) {
+     let found = pools.len();
+     for p in pools {
+          if !state::is_target_pair_option(p.token0, p.token1, target_pair_opt) {
+               trace!(pool=%p.pool, "Skipping non-target pair: {:?}/{:?}", p.token0, p.token1);
+               continue;
+          }
+          if !monitored.insert(p.pool) {
+               trace!(pool=%p.pool, "Already monitoring pool.");
+               continue;
+          }
+          let client_clone = client.clone();
+          let app_state_clone = app_state.clone();
+          tasks.push(tokio::spawn(async move {
+               match state::fetch_and_cache_pool_state(p.pool, p.dex_type, p.factory, p.uni_fee, client_clone, app_state_clone.clone()).await {
+                    Ok(()) => state::record_pool_creation(&app_state_clone, p.pool, p.created_block),
+                    Err(e) => error!(pool=%p.pool, dex=?p.dex_type, error=?e, "Spawned fetch state failed for discovered pool"),
+               }
+          }));
      }
+     info!(source, found, monitored = monitored.len(), "Discovered pools processed.");
 }
 
 // Removed unused helper function parse_u64_env_direct
\ No newline at end of file
@@ -0,0 +1,112 @@
+// bot/src/mempool.rs
+//! Pending-transaction (mempool) swap prediction, enabled by `config.enable_mempool`.
+//!
+//! Pending transactions are decoded when they swap through a monitored pool, either by calling a
+//! UniV3 pool's `swap` directly (exact input) or through the Velo/Aero router's
+//! `swapExactTokensForTokens` (first hop only). The swap is applied to the pool's cached snapshot
+//! with the local pool math to get the snapshot the pool will have once the swap confirms, which is
+//! then searched for routes like a confirmed update. Routes found this way are only reported: acting on
+//! them needs a bundle that places the arb right behind the pending swap, which is not built here.
+//! Lookups of pending hashes are capped in `main.rs` (`MAX_PENDING_TX_FETCHES`).
+//!
+//! Velo/Aero fees are a factory setting not held in the snapshot; the factory defaults are assumed.
+//! Stable pools are only predicted for tokens with known decimals (WETH, USDC), and UniV3 swaps only
+//! when they stay within the current tick-spacing range.
+
+use crate::bindings::{uniswap_v3_pool, velodrome_router};
+use crate::state::{AppState, DexType, PoolSnapshot};
+use crate::univ3_math::swap_within_tick_range;
+use crate::utils::checked_mul_div;
+use crate::velo_math::{get_amount_out, VeloPoolReserves};
+use ethers::abi::AbiDecode;
+use ethers::types::{Address, Transaction, I256, U256};
+use tracing::trace;
+
+/// Velodrome V2 / Aerodrome factory default fee for volatile pools (bps).
+pub const VELO_DEFAULT_VOLATILE_FEE_BPS: u32 = 30;
+/// Velodrome V2 / Aerodrome factory default fee for stable pools (bps).
+pub const VELO_DEFAULT_STABLE_FEE_BPS: u32 = 5;
+
+/// An exact-input swap through a monitored pool, decoded from a pending transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingSwap {
+    pub pool: Address,
+    pub zero_for_one: bool,
+    pub amount_in: U256,
+}
+
+/// Decodes `tx` into a swap through a monitored pool, if it is one.
+pub fn decode_pending_swap(tx: &Transaction, state: &AppState) -> Option<PendingSwap> {
+    let to = tx.to?;
+    if let Some(pool_state) = state.pool_states.get(&to) {
+        if pool_state.dex_type != DexType::UniswapV3 {
+            return None; // Velo pool swaps take amounts out; the input is transferred beforehand
+        }
+        let call = uniswap_v3_pool::SwapCall::decode(&tx.input).ok()?;
+        if call.amount_specified <= I256::zero() {
+            return None; // Exact output
+        }
+        return Some(PendingSwap { pool: to, zero_for_one: call.zero_for_one, amount_in: call.amount_specified.into_raw() });
+    }
+
+    let is_router = to == state.config.velo_router_addr || Some(to) == state.config.aerodrome_router_addr;
+    if !is_router {
+        return None;
+    }
+    let call = velodrome_router::SwapExactTokensForTokensCall::decode(&tx.input).ok()?;
+    let hop = call.routes.first()?;
+    let pool = state.pool_states.iter().find(|e| {
+        e.dex_type.is_velo_style()
+            && e.velo_stable == Some(hop.stable)
+            && (hop.factory.is_zero() || e.factory == hop.factory) // Zero selects the router's default factory
+            && ((e.token0 == hop.from && e.token1 == hop.to) || (e.token0 == hop.to && e.token1 == hop.from))
+    })?;
+    trace!(tx = ?tx.hash, pool = %pool.pool_address, "Decoded pending router swap.");
+    Some(PendingSwap { pool: pool.pool_address, zero_for_one: pool.token0 == hop.from, amount_in: call.amount_in })
+}
+
+/// Reserves and default fee of a Velo/Aero pool from its cached `snapshot`.
+/// None when the snapshot has no reserves, or a stable pool's token decimals are unknown.
+pub fn velo_pool_curve(state: &AppState, snapshot: &PoolSnapshot, stable: bool) -> Option<(VeloPoolReserves, u32)> {
+    let (reserve0, reserve1) = (snapshot.reserve0?, snapshot.reserve1?);
+    let reserves = if stable {
+        VeloPoolReserves::new(reserve0, reserve1, state.decimals_of(snapshot.token0)?, state.decimals_of(snapshot.token1)?, true)
+    } else {
+        VeloPoolReserves::new(reserve0, reserve1, 18, 18, false) // Decimals unused by the volatile curve
+    };
+    let fee_bps = if stable { VELO_DEFAULT_STABLE_FEE_BPS } else { VELO_DEFAULT_VOLATILE_FEE_BPS };
+    Some((reserves, fee_bps))
+}
+
+/// The snapshot `swap.pool` will have after `swap` executes, from its cached snapshot.
+/// None when the pool is not cached or the swap cannot be modelled locally.
+pub fn predict_snapshot(swap: &PendingSwap, state: &AppState) -> Option<PoolSnapshot> {
+    let mut snapshot = state.pool_snapshots.get(&swap.pool)?.value().clone();
+    let pool_state = state.pool_states.get(&swap.pool)?.value().clone();
+    match snapshot.dex_type {
+        DexType::UniswapV3 => {
+            let step = swap_within_tick_range(
+                snapshot.sqrt_price_x96?, snapshot.tick?, snapshot.liquidity?, pool_state.uni_fee?, pool_state.tick_spacing, swap.zero_for_one, swap.amount_in,
+            ).ok()??;
+            // The swap stays inside the tick-spacing range, so the cached tick still locates it
+            snapshot.sqrt_price_x96 = Some(step.sqrt_price_next_x96);
+        }
+        DexType::VelodromeV2 | DexType::Aerodrome => {
+            let (reserves, fee_bps) = velo_pool_curve(state, &snapshot, pool_state.velo_stable?)?;
+            let (reserve0, reserve1) = (reserves.reserve0, reserves.reserve1);
+            let amount_out = get_amount_out(&reserves, swap.amount_in, swap.zero_for_one, fee_bps).ok()?;
+            // Velo V2 pools forward the fee to their PoolFees contract, so only the net input stays in reserves
+            let net_in = swap.amount_in - checked_mul_div(swap.amount_in, U256::from(fee_bps), U256::from(10_000u64), "velo fee").ok()?;
+            let (new0, new1) = if swap.zero_for_one {
+                (reserve0.checked_add(net_in)?, reserve1.checked_sub(amount_out)?)
+            } else {
+                (reserve0.checked_sub(amount_out)?, reserve1.checked_add(net_in)?)
+            };
+            snapshot.reserve0 = Some(new0);
+            snapshot.reserve1 = Some(new1);
+        }
+        DexType::Unknown => return None,
+    }
+    Some(snapshot)
+}
+// END OF FILE: bot/src/mempool.rs
@@ -0,0 +1,141 @@
+// bot/src/metrics.rs
+//! Lightweight in-process metrics (atomic counters and gauges) shared through `AppState`.
+//! Values are reported in the periodic health log.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Age in seconds of the stalest snapshot that has a block timestamp.
+    pub max_snapshot_age_secs: AtomicU64,
+    /// Opportunities dropped because their latency budget (`OPPORTUNITY_TTL_MS`) ran out.
+    pub deadline_expired_opportunities: AtomicU64,
+    /// Opportunities dropped because the head moved past their target block (`MAX_BLOCKS_LATE`).
+    pub late_block_opportunities: AtomicU64,
+    /// Pending transactions decoded as swaps through a monitored pool (`ENABLE_MEMPOOL`).
+    pub pending_swaps_decoded: AtomicU64,
+    /// Pending transaction hashes dropped because `MAX_PENDING_TX_FETCHES` lookups were already in flight.
+    pub pending_tx_fetches_dropped: AtomicU64,
+    /// Pools currently suppressed after reverted submissions (`POOL_FAILURE_COOLDOWN_SECS`).
+    pub pools_in_cooldown: AtomicU64,
+    /// Loan searches answered from the per-block simulation cache instead of re-running.
+    pub simulation_cache_hits: AtomicU64,
+    /// Snapshots corrected by the last full refresh sweep (events the log stream missed).
+    pub last_refresh_changed_snapshots: AtomicU64,
+    /// Signer native balance in gwei at the last balance check.
+    pub signer_balance_gwei: AtomicU64,
+    /// Nonces handed out but not yet reflected in the signer's on-chain transaction count, see `nonce_watch`.
+    pub unmined_nonces: AtomicU64,
+    /// Rolling mean of simulated-vs-realized profit error (bps of prediction) when simulation overestimates.
+    pub profit_overestimate_bps: AtomicU64,
+    /// Rolling mean of simulated-vs-realized profit error (bps of prediction) when simulation underestimates.
+    pub profit_underestimate_bps: AtomicU64,
+    /// Rolling mean shortfall of realized UniV3 leg outputs below their quotes (bps), see `slippage`.
+    pub uni_v3_output_shortfall_bps: AtomicU64,
+    /// Rolling mean shortfall of realized Velodrome leg outputs below their quotes (bps).
+    pub velo_output_shortfall_bps: AtomicU64,
+    /// Rolling mean shortfall of realized Aerodrome leg outputs below their quotes (bps).
+    pub aero_output_shortfall_bps: AtomicU64,
+    /// Current adaptive per-call timeout for pool-state fetches on the HTTP endpoint (ms).
+    pub adaptive_fetch_timeout_ms: AtomicU64,
+    /// Profit simulations run by optimal-loan searches.
+    pub loan_search_simulations: AtomicU64,
+    /// Optimal-loan searches whose best amount was the range minimum (`MIN_LOAN_AMOUNT_WETH`).
+    pub loan_searches_at_min: AtomicU64,
+    /// Optimal-loan searches whose best amount was the range maximum (config or pool-depth limit).
+    pub loan_searches_at_max: AtomicU64,
+    /// Optimal-loan searches whose sampled profit curve had more than one peak.
+    pub loan_searches_irregular: AtomicU64,
+    /// Cached pools evicted for a deeper pool while at `MAX_MONITORED_POOLS`.
+    pub pools_evicted_at_cap: AtomicU64,
+    /// New pools not cached because they were shallower than every cached pool at `MAX_MONITORED_POOLS`.
+    pub pools_refused_at_cap: AtomicU64,
+    /// Velo/Aero reserve updates discarded (zero reserve or a price jump beyond `MAX_PRICE_JUMP_BPS`), keeping the prior snapshot.
+    pub snapshot_updates_rejected: AtomicU64,
+    /// Opportunities dropped because they came sooner than `MIN_SUBMISSION_INTERVAL_MS` (or the per-pool interval) after the last submission.
+    pub throttled_submissions: AtomicU64,
+    /// Opportunities dropped because their loan would take the in-flight loan total over `MAX_TOTAL_INFLIGHT_LOAN_WEI`.
+    pub inflight_exposure_rejections: AtomicU64,
+    /// Profitable opportunities dropped during the startup warmup (`SUBMISSION_WARMUP_SECS`, `MIN_POOLS_BEFORE_TRADING`).
+    pub warmup_suppressed_submissions: AtomicU64,
+    /// Logs skipped because they were already processed (re-delivered after a stream reconnect).
+    pub duplicate_logs_skipped: AtomicU64,
+    /// Arbitrage checks run for pool updates (confirmed logs and predicted pending swaps).
+    pub arbitrage_checks: AtomicU64,
+    /// Local UniV3 quotes compared against QuoterV2 in pricing shadow mode (`PRICING_SHADOW_MODE`).
+    pub shadow_quotes_compared: AtomicU64,
+    /// Shadow-mode local quotes more than 10 bps off the QuoterV2 quote.
+    pub shadow_quotes_divergent: AtomicU64,
+    /// Largest shadow-mode divergence seen since startup (bps of the QuoterV2 quote).
+    pub shadow_quote_max_divergence_bps: AtomicU64,
+    /// Submissions dropped because the `EXECUTOR_CALLSTATIC_CHECK` eth_call reverted or netted no profit.
+    pub callstatic_rejections: AtomicU64,
+    /// Submissions dropped because the quoted leg outputs were zero or diverged from the executor eth_call beyond `SIM_DIVERGENCE_TOLERANCE_BPS`.
+    pub simulation_divergence_rejections: AtomicU64,
+    /// Confirmed arbs that left the executor holding more than `EXECUTOR_DUST_THRESHOLD` of the intermediate token.
+    pub executor_residual_detections: AtomicU64,
+    /// Swaps made to restore the wallet's WETH working balance above `MIN_WETH_WORKING_BALANCE`.
+    pub weth_rebalance_swaps: AtomicU64,
+    /// Confirmed arbs whose realized profit was read from the executor's `ProfitRealized` event rather than balance deltas.
+    pub profit_realized_events: AtomicU64,
+}
+
+impl Metrics {
+    pub fn set(gauge: &AtomicU64, value: u64) {
+        gauge.store(value, Ordering::Relaxed);
+    }
+
+    pub fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn max(gauge: &AtomicU64, value: u64) {
+        gauge.fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Returns all metrics as `(name, value)` pairs for logging/export.
+    pub fn values(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("max_snapshot_age_secs", self.max_snapshot_age_secs.load(Ordering::Relaxed)),
+            ("deadline_expired_opportunities", self.deadline_expired_opportunities.load(Ordering::Relaxed)),
+            ("late_block_opportunities", self.late_block_opportunities.load(Ordering::Relaxed)),
+            ("pending_swaps_decoded", self.pending_swaps_decoded.load(Ordering::Relaxed)),
+            ("pending_tx_fetches_dropped", self.pending_tx_fetches_dropped.load(Ordering::Relaxed)),
+            ("pools_in_cooldown", self.pools_in_cooldown.load(Ordering::Relaxed)),
+            ("simulation_cache_hits", self.simulation_cache_hits.load(Ordering::Relaxed)),
+            ("last_refresh_changed_snapshots", self.last_refresh_changed_snapshots.load(Ordering::Relaxed)),
+            ("signer_balance_gwei", self.signer_balance_gwei.load(Ordering::Relaxed)),
+            ("unmined_nonces", self.unmined_nonces.load(Ordering::Relaxed)),
+            ("profit_overestimate_bps", self.profit_overestimate_bps.load(Ordering::Relaxed)),
+            ("profit_underestimate_bps", self.profit_underestimate_bps.load(Ordering::Relaxed)),
+            ("uni_v3_output_shortfall_bps", self.uni_v3_output_shortfall_bps.load(Ordering::Relaxed)),
+            ("velo_output_shortfall_bps", self.velo_output_shortfall_bps.load(Ordering::Relaxed)),
+            ("aero_output_shortfall_bps", self.aero_output_shortfall_bps.load(Ordering::Relaxed)),
+            ("adaptive_fetch_timeout_ms", self.adaptive_fetch_timeout_ms.load(Ordering::Relaxed)),
+            ("loan_search_simulations", self.loan_search_simulations.load(Ordering::Relaxed)),
+            ("loan_searches_at_min", self.loan_searches_at_min.load(Ordering::Relaxed)),
+            ("loan_searches_at_max", self.loan_searches_at_max.load(Ordering::Relaxed)),
+            ("loan_searches_irregular", self.loan_searches_irregular.load(Ordering::Relaxed)),
+            ("pools_evicted_at_cap", self.pools_evicted_at_cap.load(Ordering::Relaxed)),
+            ("pools_refused_at_cap", self.pools_refused_at_cap.load(Ordering::Relaxed)),
+            ("snapshot_updates_rejected", self.snapshot_updates_rejected.load(Ordering::Relaxed)),
+            ("throttled_submissions", self.throttled_submissions.load(Ordering::Relaxed)),
+            ("inflight_exposure_rejections", self.inflight_exposure_rejections.load(Ordering::Relaxed)),
+            ("warmup_suppressed_submissions", self.warmup_suppressed_submissions.load(Ordering::Relaxed)),
+            ("duplicate_logs_skipped", self.duplicate_logs_skipped.load(Ordering::Relaxed)),
+            ("arbitrage_checks", self.arbitrage_checks.load(Ordering::Relaxed)),
+            ("shadow_quotes_compared", self.shadow_quotes_compared.load(Ordering::Relaxed)),
+            ("shadow_quotes_divergent", self.shadow_quotes_divergent.load(Ordering::Relaxed)),
+            ("shadow_quote_max_divergence_bps", self.shadow_quote_max_divergence_bps.load(Ordering::Relaxed)),
+            ("callstatic_rejections", self.callstatic_rejections.load(Ordering::Relaxed)),
+            ("simulation_divergence_rejections", self.simulation_divergence_rejections.load(Ordering::Relaxed)),
+            ("executor_residual_detections", self.executor_residual_detections.load(Ordering::Relaxed)),
+            ("weth_rebalance_swaps", self.weth_rebalance_swaps.load(Ordering::Relaxed)),
+            ("profit_realized_events", self.profit_realized_events.load(Ordering::Relaxed)),
+        ]
+    }
+}
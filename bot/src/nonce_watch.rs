@@ -0,0 +1,110 @@
+// bot/src/nonce_watch.rs
+//! Dead man's switch on the signer's nonce.
+//!
+//! Every submission takes a nonce from the `NonceManager`; once mined, the on-chain transaction count
+//! (latest block) moves past it. A relay that accepts transactions but never lands them leaves the count
+//! flat while the local nonce keeps advancing. A periodic check compares the two and, when the count has
+//! stayed behind without moving for `config.nonce_divergence_blocks` blocks, trips the circuit breaker
+//! for `PauseReason::NonceDivergence` and alerts. Once the count catches up only that reason is cleared,
+//! so admin or low-balance pauses stay in force. A zero block budget disables the check.
+
+use crate::alerts::{send_alert, AlertLevel};
+use crate::metrics::Metrics;
+use crate::state::{AppState, PauseReason};
+use crate::transaction::NonceManager;
+use ethers::prelude::*;
+use eyre::{Result, WrapErr};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Change of the divergence condition produced by one observation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceDivergenceTransition {
+    /// The on-chain count stayed behind for the configured number of blocks; the breaker was tripped.
+    Diverged { local_next: U256, onchain: U256, blocks: u64 },
+    /// The on-chain count caught up; the breaker was reset.
+    Recovered,
+}
+
+#[derive(Debug, Default)]
+struct WatchState {
+    behind_since: Option<(U64, U256)>, // Block the count was first seen behind (or last advanced), and the count then
+    tripped: bool,
+}
+
+/// Divergence tracking across checks.
+#[derive(Debug, Default)]
+pub struct NonceWatch {
+    state: Mutex<WatchState>,
+}
+
+impl NonceWatch {
+    /// Applies one reading at `block`: `last_assigned` is the nonce manager's last nonce (None before the
+    /// first submission), `onchain` the signer's transaction count at the latest block. Trips or resets
+    /// the breaker on a crossing; None while the condition is unchanged or the check is disabled.
+    pub fn observe(&self, app_state: &AppState, block: U64, last_assigned: Option<U256>, onchain: U256) -> Option<NonceDivergenceTransition> {
+        let max_blocks = app_state.config.nonce_divergence_blocks;
+        if max_blocks == 0 {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let local_next = last_assigned.map_or(onchain, |nonce| nonce + U256::one());
+        let unmined = local_next.saturating_sub(onchain);
+        Metrics::set(&app_state.metrics.unmined_nonces, unmined.min(U256::from(u64::MAX)).as_u64());
+        if unmined.is_zero() {
+            state.behind_since = None;
+            if !state.tripped {
+                return None;
+            }
+            state.tripped = false;
+            app_state.set_paused(PauseReason::NonceDivergence, false);
+            return Some(NonceDivergenceTransition::Recovered);
+        }
+        let since = match state.behind_since {
+            Some((since, seen)) if onchain <= seen => since,
+            // First seen behind, or transactions still landing: the clock (re)starts
+            _ => {
+                state.behind_since = Some((block, onchain));
+                block
+            }
+        };
+        let blocks = block.saturating_sub(since).as_u64();
+        debug!(%local_next, %onchain, blocks, "Signer transaction count behind the local nonce.");
+        if state.tripped || blocks < max_blocks {
+            return None;
+        }
+        state.tripped = true;
+        app_state.set_paused(PauseReason::NonceDivergence, true);
+        Some(NonceDivergenceTransition::Diverged { local_next, onchain, blocks })
+    }
+}
+
+/// Reads the latest block and the signer's transaction count, applies them and alerts on a transition.
+pub async fn check_nonce_divergence<M: Middleware>(
+    client: &M,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    watch: &NonceWatch,
+    signer: Address,
+) -> Result<Option<NonceDivergenceTransition>> where M::Error: 'static + Send + Sync {
+    let block = client.get_block_number().await.wrap_err("Failed to fetch block number")?;
+    let onchain = client.get_transaction_count(signer, Some(BlockNumber::Number(block).into())).await.wrap_err("Failed to fetch transaction count")?;
+    let transition = watch.observe(app_state, block, nonce_manager.last_assigned_nonce().await, onchain);
+    match transition {
+        Some(NonceDivergenceTransition::Diverged { local_next, onchain, blocks }) => {
+            warn!(%local_next, %onchain, blocks, "ALERT: Submitted transactions are not landing. Circuit breaker tripped.");
+            send_alert(&app_state.config, AlertLevel::Critical, format!(
+                "Signer {:?} transaction count stuck at {} for {} blocks while the local nonce is at {}. Relays may be dropping submissions. Bot paused.",
+                signer, onchain, blocks, local_next
+            ));
+        }
+        Some(NonceDivergenceTransition::Recovered) => {
+            info!(%onchain, still_paused = app_state.is_paused(), "Signer transaction count caught up with the local nonce. Nonce divergence pause cleared.");
+            let status = if app_state.is_paused() { format!("Bot still paused ({:?}).", app_state.pause_reasons()) } else { "Bot resumed.".to_string() };
+            send_alert(&app_state.config, AlertLevel::Info, format!("Signer {:?} transaction count caught up ({}). {}", signer, onchain, status));
+        }
+        None => {}
+    }
+    Ok(transition)
+}
+// END OF FILE: bot/src/nonce_watch.rs
@@ -0,0 +1,147 @@
+// bot/src/oracle.rs
+//! WETH/USD price source.
+//!
+//! When `config.chainlink_eth_usd_feed` is set the Chainlink ETH/USD aggregator's `latestRoundData`
+//! is read at most once per block and cached in `AppState::weth_usd_prices`. Without a feed, or when
+//! the feed read fails or is stale, the price is derived from the cached WETH/USDC pool snapshots
+//! (USDC taken as $1).
+//!
+//! `net_profit_usd` puts route profits in different loan tokens on one scale for ranking across pairs.
+
+use crate::bindings::ChainlinkAggregator;
+use crate::path_optimizer::RouteCandidate;
+use crate::price_impact::marginal_price;
+use crate::state::AppState;
+use crate::utils::{v2_price_from_reserves, v3_price_from_sqrt, ToF64Lossy};
+use ethers::prelude::*;
+use ethers::types::{I256, U64};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Answers older than this (relative to the block timestamp) are ignored. The ETH/USD heartbeat is 20 min on OP/Base.
+pub const MAX_FEED_AGE_SECS: u64 = 3600;
+
+/// Converts a raw aggregator answer into a float price. Non-positive answers are rejected.
+pub fn chainlink_answer_to_usd(answer: I256, decimals: u8) -> Option<f64> {
+    if answer <= I256::zero() {
+        return None;
+    }
+    let price = answer.into_raw().to_f64_lossy() / 10f64.powi(decimals as i32);
+    (price.is_finite() && price > 0.0).then_some(price)
+}
+
+/// Median WETH price in USDC across cached WETH/USDC snapshots, or None when no snapshot has a usable price.
+pub fn pool_derived_weth_price_usd(app_state: &AppState) -> Option<f64> {
+    let (weth, usdc) = (app_state.weth_address, app_state.usdc_address);
+    let mut prices: Vec<f64> = app_state.pool_snapshots.iter().filter_map(|sn| {
+        let weth_is_t0 = match (sn.token0, sn.token1) {
+            (t0, t1) if t0 == weth && t1 == usdc => true,
+            (t0, t1) if t0 == usdc && t1 == weth => false,
+            _ => return None,
+        };
+        let (dec0, dec1) = (app_state.decimals_of(sn.token0)?, app_state.decimals_of(sn.token1)?);
+        // token1 per token0
+        let price = if let Some(sqrt_price) = sn.sqrt_price_x96 {
+            v3_price_from_sqrt(sqrt_price, dec0, dec1).ok()?
+        } else {
+            v2_price_from_reserves(sn.reserve0?, sn.reserve1?, dec0, dec1).ok()?
+        };
+        let weth_price = if weth_is_t0 { price } else { 1.0 / price };
+        (weth_price.is_finite() && weth_price > 0.0).then_some(weth_price)
+    }).collect();
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_by(f64::total_cmp);
+    Some(prices[prices.len() / 2])
+}
+
+/// Returns the WETH price in USD as of `block_number`: the Chainlink feed if configured (cached per block),
+/// otherwise the pool-derived price.
+pub async fn weth_price_usd<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, block_number: U64) -> Option<f64> {
+    if let Some(feed) = app_state.config.chainlink_eth_usd_feed {
+        if let Some(price) = app_state.weth_usd_prices.get(&block_number) {
+            return Some(*price);
+        }
+        match read_feed(client, app_state, feed, block_number).await {
+            Some(price) => {
+                app_state.weth_usd_prices.retain(|n, _| *n >= block_number);
+                app_state.weth_usd_prices.insert(block_number, price);
+                return Some(price);
+            }
+            None => debug!(%feed, "Falling back to pool-derived WETH price."),
+        }
+    }
+    pool_derived_weth_price_usd(app_state)
+}
+
+/// The WETH price without RPC calls: the feed answer already cached for `block_number`, else the
+/// pool-derived price. For logging on latency-sensitive paths.
+pub fn cached_weth_price_usd(app_state: &AppState, block_number: U64) -> Option<f64> {
+    app_state.weth_usd_prices.get(&block_number).map(|price| *price).or_else(|| pool_derived_weth_price_usd(app_state))
+}
+
+/// `amount` of a pool's token0 (or token1 when `from_token0` is false) in the other token at
+/// `price_token1_per_token0` (raw units of each), rounded down. None for a non-positive or unusable price.
+pub fn convert_at_price(amount: U256, price_token1_per_token0: f64, from_token0: bool) -> Option<U256> {
+    if !price_token1_per_token0.is_finite() || price_token1_per_token0 <= 0.0 {
+        return None;
+    }
+    let converted = if from_token0 { amount.to_f64_lossy() * price_token1_per_token0 } else { amount.to_f64_lossy() / price_token1_per_token0 };
+    if !converted.is_finite() || converted >= U256::MAX.to_f64_lossy() {
+        return None;
+    }
+    U256::from_dec_str(&format!("{:.0}", converted.floor())).ok()
+}
+
+/// `amount` of `from` in `to` at the marginal price of `pool`, which must pair the two tokens.
+pub fn convert_via_pool(state: &AppState, pool: Address, from: Address, to: Address, amount: U256) -> Option<U256> {
+    let snapshot = state.pool_snapshots.get(&pool)?.value().clone();
+    let from_token0 = match (snapshot.token0, snapshot.token1) {
+        (t0, t1) if t0 == from && t1 == to => true,
+        (t0, t1) if t1 == from && t0 == to => false,
+        _ => return None,
+    };
+    convert_at_price(amount, marginal_price(state, &snapshot)?, from_token0)
+}
+
+/// `profit_wei` (the route's loan token) in USD: WETH at `weth_usd`, quote tokens (USDC variants) at $1,
+/// and any other loan token converted into the route's quote token at the buy pool's price. None when
+/// no price applies. Losses stay negative.
+pub fn net_profit_usd(app_state: &AppState, route: &RouteCandidate, profit_wei: I256, weth_usd: Option<f64>) -> Option<f64> {
+    let amount = profit_wei.unsigned_abs();
+    let (amount, decimals, unit_price) = if route.token_in == app_state.weth_address {
+        (amount, app_state.decimals_of(route.token_in)?, weth_usd?)
+    } else if app_state.quote_tokens.contains(&route.token_in) {
+        (amount, app_state.decimals_of(route.token_in)?, 1.0)
+    } else if app_state.quote_tokens.contains(&route.token_out) {
+        (convert_via_pool(app_state, route.buy_pool_addr, route.token_in, route.token_out, amount)?, app_state.decimals_of(route.token_out)?, 1.0)
+    } else {
+        return None;
+    };
+    let usd = amount.to_f64_lossy() / 10f64.powi(decimals as i32) * unit_price;
+    usd.is_finite().then_some(if profit_wei < I256::zero() { -usd } else { usd })
+}
+
+async fn read_feed<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, feed: Address, block_number: U64) -> Option<f64> {
+    let aggregator = ChainlinkAggregator::new(feed, client);
+    let decimals_call = aggregator.decimals();
+    let round_call = aggregator.latest_round_data();
+    let (decimals, (_round_id, answer, _started_at, updated_at, _answered_in_round)) =
+        match tokio::try_join!(decimals_call.call(), round_call.call()) {
+            Ok(res) => res,
+            Err(e) => { warn!(%feed, error = %e, "Chainlink feed read failed."); return None; }
+        };
+    if let Some(block_ts) = app_state.block_timestamps.get(&block_number).map(|ts| *ts) {
+        let age = block_ts.saturating_sub(updated_at.low_u64());
+        if age > MAX_FEED_AGE_SECS {
+            warn!(%feed, age_secs = age, "Chainlink answer is stale.");
+            return None;
+        }
+    }
+    let price = chainlink_answer_to_usd(answer, decimals);
+    if price.is_none() {
+        warn!(%feed, %answer, "Chainlink feed returned a non-positive answer.");
+    }
+    price
+}
@@ -1,22 +1,25 @@
 // bot/src/path_optimizer.rs
 
 use crate::config::Config;
-use crate::state::{DexType, PoolSnapshot, PoolState};
-use ethers::types::Address;
+use crate::state::{pool_matches_pair, DexType, PoolSnapshot, PoolState};
+use crate::token_safety::TokenDecimals;
+use crate::twap::{spot_deviates_from_twap, spot_twap_deviation_bps};
+use crate::utils::ToF64Lossy;
+use ethers::types::{Address, U64};
 use eyre::{eyre, Result, WrapErr};
 use dashmap::DashMap;
 use std::sync::Arc;
 use tracing::{debug, info, instrument, trace, warn};
 
 // Represents a potential arbitrage opportunity (route) found.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RouteCandidate {
     pub buy_pool_addr: Address,
     pub sell_pool_addr: Address,
     pub buy_dex_type: DexType,
     pub sell_dex_type: DexType,
-    pub token_in: Address,  // The token being borrowed (e.g., WETH)
-    pub token_out: Address, // The intermediate token (e.g., USDC)
+    pub token_in: Address,  // The token being borrowed (the pair's base, e.g., WETH)
+    pub token_out: Address, // The intermediate token (the pair's quote, e.g., USDC)
     // Pool-specific details needed for simulation/execution
     pub buy_pool_fee: Option<u32>,     // UniV3 fee
     pub sell_pool_fee: Option<u32>,    // UniV3 fee
@@ -27,7 +30,67 @@ pub struct RouteCandidate {
     // Execution parameters
     pub zero_for_one_a: bool, // Direction for the first swap (buy pool)
     // Metadata
-    pub estimated_profit_usd: f64, // Placeholder metric (e.g., price diff %)
+    pub price_diff_pct: f64, // Spot price spread between the buy and sell pools (%), not a profit estimate
+    pub source_block: U64, // Block of the swap log that triggered detection (zero if unknown)
+}
+
+impl RouteCandidate {
+    /// Stable identifier of the route (buy pool > sell pool) for logs and the audit trail.
+    pub fn id(&self) -> String {
+        format!("{:?}>{:?}", self.buy_pool_addr, self.sell_pool_addr)
+    }
+
+    /// Recomputes the swap directions from the pools' actual token ordering and errors on any mismatch.
+    /// Swap A sells `token_in` into the buy pool; Swap B sells `token_out` back into the sell pool, so both
+    /// pools must hold exactly the (`token_in`, `token_out`) pair. Must pass before userData is encoded.
+    pub fn validate_directions(&self, buy_pool: &PoolState, sell_pool: &PoolState) -> Result<()> {
+        for (label, expected_addr, pool) in [("buy", self.buy_pool_addr, buy_pool), ("sell", self.sell_pool_addr, sell_pool)] {
+            if pool.pool_address != expected_addr {
+                return Err(eyre!("{} pool state is for {:?}, route expects {:?}", label, pool.pool_address, expected_addr));
+            }
+            let holds_pair = (pool.token0 == self.token_in && pool.token1 == self.token_out)
+                || (pool.token0 == self.token_out && pool.token1 == self.token_in);
+            if !holds_pair {
+                return Err(eyre!(
+                    "{} pool {:?} holds ({:?}, {:?}), not the route pair ({:?}, {:?})",
+                    label, pool.pool_address, pool.token0, pool.token1, self.token_in, self.token_out
+                ));
+            }
+        }
+        let expected_zero_for_one_a = determine_swap_direction(buy_pool, self.token_in);
+        if self.zero_for_one_a != expected_zero_for_one_a {
+            return Err(eyre!(
+                "zero_for_one_a is {} but buy pool {:?} has token0 {:?} (loan token {:?}), expected {}",
+                self.zero_for_one_a, buy_pool.pool_address, buy_pool.token0, self.token_in, expected_zero_for_one_a
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A base/quote pair routes are searched within: the base token is borrowed, sold for the quote token on
+/// the cheaper pool and bought back on the dearer one. Prices are quote per base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingPair {
+    pub base: Address,
+    pub quote: Address,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+}
+
+impl TradingPair {
+    /// `base` paired with each of `quotes` (quote tokens sharing `quote_decimals`, e.g. USDC variants),
+    /// in order, deduplicated; zero addresses are skipped.
+    pub fn with_quotes(base: Address, quotes: impl IntoIterator<Item = Address>, base_decimals: u8, quote_decimals: u8) -> Vec<Self> {
+        let mut pairs: Vec<Self> = Vec::new();
+        for quote in quotes {
+            if quote.is_zero() || quote == base || pairs.iter().any(|p| p.quote == quote) {
+                continue;
+            }
+            pairs.push(Self { base, quote, base_decimals, quote_decimals });
+        }
+        pairs
+    }
 }
 
 // Define the threshold here for now, could be moved to config later
@@ -35,23 +98,50 @@ const ARBITRAGE_THRESHOLD_PERCENTAGE: f64 = 0.1; // Example: 0.1% difference nee
 
 /// Identifies potential 2-way arbitrage routes involving the updated pool's snapshot.
 /// Compares prices derived from snapshots in the hot cache. Uses PoolState for context.
-// FIX: Remove 'config' from skip list as parameter is '_config' (unused)
-#[instrument(skip(all_pool_states, all_pool_snapshots), level="debug", fields(pool=%updated_pool_snapshot.pool_address))]
+/// Routes stay within the first of `pairs` the updated pool holds (in either token order); pools holding
+/// none of them are skipped. Pools excluded by the config blocklist/allowlist never appear in a candidate.
+/// At most `config.max_routes_to_evaluate` candidates (best `score_route` first) are returned; 0 means no limit.
+#[instrument(skip(all_pool_states, all_pool_snapshots, fee_on_transfer_tokens, token_decimals, config, pairs), level="debug", fields(pool=%updated_pool_snapshot.pool_address))]
 pub fn find_top_routes(
     updated_pool_snapshot: &PoolSnapshot, // Triggering snapshot
     all_pool_states: &Arc<DashMap<Address, PoolState>>, // Source of detailed state context (incl. factory)
     all_pool_snapshots: &Arc<DashMap<Address, PoolSnapshot>>, // Map to iterate for comparison (hot cache)
-    _config: &Config, // Mark config as unused for now
-    // Target pair info (passed directly for clarity)
-    weth_address: Address,
-    usdc_address: Address,
-    weth_decimals: u8,
-    usdc_decimals: u8,
+    fee_on_transfer_tokens: &Arc<DashMap<Address, bool>>, // Tokens flagged by token_safety are never routed through
+    token_decimals: &DashMap<Address, TokenDecimals>, // Fetched decimals; assumed ones are skipped with `skip_unknown_decimals`
+    config: &Config, // Pool blocklist/allowlist
+    pairs: &[TradingPair], // Configured pairs (see `AppState::trading_pairs`)
 ) -> Vec<RouteCandidate> {
     trace!("Finding routes for updated pool snapshot");
 
     let mut candidates = Vec::new();
     let updated_pool_address = updated_pool_snapshot.pool_address;
+    if !config.is_pool_allowed(updated_pool_address) {
+        trace!(pool = %updated_pool_address, "Updated pool excluded by blocklist/allowlist. Skipping route search.");
+        return vec![];
+    }
+    if has_flagged_token(updated_pool_snapshot, fee_on_transfer_tokens) {
+        debug!(pool = %updated_pool_address, "Updated pool holds a fee-on-transfer token. Skipping route search.");
+        return vec![];
+    }
+    if config.skip_unknown_decimals && has_assumed_decimals(updated_pool_snapshot, token_decimals) {
+        debug!(pool = %updated_pool_address, "Updated pool holds a token with assumed decimals. Skipping route search.");
+        return vec![];
+    }
+    // A spot price far from its TWAP was likely pushed within the block; the discrepancy is not real
+    if spot_deviates_from_twap(updated_pool_snapshot, config.max_spot_twap_deviation_bps) {
+        debug!(pool = %updated_pool_address, deviation_bps = ?spot_twap_deviation_bps(updated_pool_snapshot), "Updated pool's spot price deviates from its TWAP. Skipping route search.");
+        return vec![];
+    }
+
+    // Routes stay within the updated pool's pair: quote variants (e.g. USDC.e and native USDC) are
+    // distinct tokens, so a pool of one cannot be sold into a pool of the other
+    let pair = match pairs.iter().find(|p| pool_matches_pair(updated_pool_snapshot, p.base, p.quote)) {
+        Some(pair) => *pair,
+        None => {
+            trace!(pool = %updated_pool_address, "Updated pool holds no configured trading pair. Skipping route search.");
+            return vec![];
+        }
+    };
 
     // --- Get Context for Updated Pool ---
     let updated_pool_state_entry = match all_pool_states.get(&updated_pool_address) {
@@ -67,7 +157,7 @@ pub fn find_top_routes(
     let updated_price = match calculate_cached_price(
         updated_pool_snapshot,
         &updated_pool_state_context,
-        weth_address, usdc_address, weth_decimals, usdc_decimals
+        &pair,
     ) {
         Ok(price) => {
             trace!(pool = %updated_pool_address, price = price, "Calculated price for updated pool from snapshot.");
@@ -86,13 +176,15 @@ pub fn find_top_routes(
         let other_pool_addr = *snapshot_entry.key();
 
         if other_pool_addr == updated_pool_address { continue; }
+        if !config.is_pool_allowed(other_pool_addr) { continue; }
+        if has_flagged_token(other_pool_snapshot, fee_on_transfer_tokens) { continue; }
+        if config.skip_unknown_decimals && has_assumed_decimals(other_pool_snapshot, token_decimals) { continue; }
+        if spot_deviates_from_twap(other_pool_snapshot, config.max_spot_twap_deviation_bps) {
+            debug!(pool = %other_pool_addr, deviation_bps = ?spot_twap_deviation_bps(other_pool_snapshot), "Comparison pool's spot price deviates from its TWAP. Skipping.");
+            continue;
+        }
 
-        let is_other_target = crate::state::is_target_pair_option(
-            other_pool_snapshot.token0,
-            other_pool_snapshot.token1,
-            Some((weth_address, usdc_address))
-        );
-        if !is_other_target { continue; }
+        if !pool_matches_pair(other_pool_snapshot, pair.base, pair.quote) { continue; }
 
         trace!(compare_pool = %other_pool_addr, "Comparing against snapshot.");
 
@@ -111,7 +203,7 @@ pub fn find_top_routes(
         let other_price = match calculate_cached_price(
             other_pool_snapshot,
             &other_pool_state_context,
-            weth_address, usdc_address, weth_decimals, usdc_decimals
+            &pair,
         ) {
             Ok(p) => p,
             Err(e) => {
@@ -162,7 +254,7 @@ pub fn find_top_routes(
                  "Potential arbitrage opportunity found!"
              );
 
-            let zero_for_one_a = determine_swap_direction(buy_state, weth_address);
+            let zero_for_one_a = determine_swap_direction(buy_state, pair.base);
             trace!(buy_pool = %buy_state.pool_address, zero_for_one_a, "Determined swap direction for Swap A");
 
             let candidate = RouteCandidate {
@@ -170,8 +262,8 @@ pub fn find_top_routes(
                 sell_pool_addr: sell_snapshot.pool_address,
                 buy_dex_type: buy_snapshot.dex_type,
                 sell_dex_type: sell_snapshot.dex_type,
-                token_in: weth_address,
-                token_out: usdc_address,
+                token_in: pair.base,
+                token_out: pair.quote,
                 buy_pool_fee: buy_state.uni_fee,
                 sell_pool_fee: sell_state.uni_fee,
                 buy_pool_stable: buy_state.velo_stable,
@@ -179,7 +271,8 @@ pub fn find_top_routes(
                 buy_pool_factory: buy_state.factory,
                 sell_pool_factory: sell_state.factory,
                 zero_for_one_a,
-                estimated_profit_usd: price_diff_percentage,
+                price_diff_pct: price_diff_percentage,
+                source_block: updated_pool_snapshot.last_update_block.unwrap_or_default(),
             };
 
             debug!(candidate = ?candidate, "Created RouteCandidate");
@@ -188,8 +281,21 @@ pub fn find_top_routes(
     } // End loop through snapshots
 
     if !candidates.is_empty() {
-        candidates.sort_by(|a, b| b.estimated_profit_usd.partial_cmp(&a.estimated_profit_usd).unwrap_or(std::cmp::Ordering::Equal));
-        debug!("Sorted {} candidates by estimated profit (desc).", candidates.len());
+        let mut scored: Vec<(f64, RouteCandidate)> = candidates.into_iter()
+            .map(|c| (score_route(&c, all_pool_states, all_pool_snapshots, config, pair.base_decimals), c))
+            .collect();
+        // Ties (DashMap iteration order is arbitrary) break on price spread, then (buy pool, sell pool) so runs are reproducible
+        scored.sort_by(|(score_a, a), (score_b, b)| {
+            score_b.partial_cmp(score_a).unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.price_diff_pct.partial_cmp(&a.price_diff_pct).unwrap_or(std::cmp::Ordering::Equal))
+                .then_with(|| (a.buy_pool_addr, a.sell_pool_addr).cmp(&(b.buy_pool_addr, b.sell_pool_addr)))
+        });
+        candidates = scored.into_iter().map(|(_, c)| c).collect();
+        debug!("Sorted {} candidates by route score (desc), then estimated profit and pool addresses.", candidates.len());
+        if config.max_routes_to_evaluate > 0 && candidates.len() > config.max_routes_to_evaluate {
+            debug!(found = candidates.len(), kept = config.max_routes_to_evaluate, "Truncating candidates to the most promising.");
+            candidates.truncate(config.max_routes_to_evaluate);
+        }
          if let Some(top_candidate) = candidates.first() {
               info!(?top_candidate, "Most promising candidate identified.");
          }
@@ -200,23 +306,84 @@ pub fn find_top_routes(
     candidates
 }
 
+/// Ranking score of a route (higher is better): the weighted sum of its price spread (%), log10 of the
+/// depth of its shallowest pool in whole loan tokens, and the maturity (0..1) of its youngest pool.
+/// Weights come from `config.route_score_*_weight`. A pool counts as fully mature once it is
+/// `config.route_score_mature_blocks` old at the route's source block; pools found by startup
+/// enumeration (no creation block) always do. Pools missing from the caches contribute zero depth.
+pub fn score_route(
+    candidate: &RouteCandidate,
+    all_pool_states: &DashMap<Address, PoolState>,
+    all_pool_snapshots: &DashMap<Address, PoolSnapshot>,
+    config: &Config,
+    loan_token_decimals: u8,
+) -> f64 {
+    let pools = [candidate.buy_pool_addr, candidate.sell_pool_addr];
+
+    let depth = pools.iter()
+        .map(|pool| all_pool_snapshots.get(pool).map_or(0.0, |s| loan_token_depth(&s, candidate.token_in, loan_token_decimals)))
+        .fold(f64::INFINITY, f64::min);
+    let maturity = pools.iter()
+        .map(|pool| all_pool_states.get(pool).map_or(0.0, |s| pool_maturity(&s, candidate.source_block, config.route_score_mature_blocks)))
+        .fold(1.0, f64::min);
+
+    let score = config.route_score_profit_weight * candidate.price_diff_pct
+        + config.route_score_liquidity_weight * (1.0 + depth).log10()
+        + config.route_score_age_weight * maturity;
+    trace!(route = %candidate.id(), depth, maturity, score, "Scored route");
+    score
+}
+
+/// Loan-token side of the pool in whole tokens: the reserve for Velo/Aero, the virtual reserve of the
+/// in-range liquidity (`L / sqrtP` or `L * sqrtP`) for UniV3. Zero when the snapshot lacks the data.
+pub(crate) fn loan_token_depth(snapshot: &PoolSnapshot, loan_token: Address, decimals: u8) -> f64 {
+    let raw = match snapshot.dex_type {
+        DexType::UniswapV3 => match (snapshot.sqrt_price_x96, snapshot.liquidity) {
+            (Some(sqrt_price_x96), Some(liquidity)) if !sqrt_price_x96.is_zero() => {
+                let sqrt_price = sqrt_price_x96.to_f64_lossy() / 2f64.powi(96);
+                if snapshot.token0 == loan_token { liquidity as f64 / sqrt_price } else { liquidity as f64 * sqrt_price }
+            }
+            _ => 0.0,
+        },
+        DexType::VelodromeV2 | DexType::Aerodrome => {
+            let reserve = if snapshot.token0 == loan_token { snapshot.reserve0 } else { snapshot.reserve1 };
+            reserve.map_or(0.0, |r| r.to_f64_lossy())
+        }
+        DexType::Unknown => 0.0,
+    };
+    raw / 10f64.powi(decimals as i32)
+}
+
+fn pool_maturity(pool_state: &PoolState, at_block: U64, mature_blocks: u64) -> f64 {
+    match pool_state.created_block {
+        None => 1.0,
+        Some(_) if mature_blocks == 0 => 1.0,
+        Some(created) => (at_block.saturating_sub(created).as_u64() as f64 / mature_blocks as f64).min(1.0),
+    }
+}
+
+/// Helper to check whether either pool token was flagged as fee-on-transfer/rebasing.
+fn has_flagged_token(snapshot: &PoolSnapshot, fee_on_transfer_tokens: &DashMap<Address, bool>) -> bool {
+    [snapshot.token0, snapshot.token1].iter().any(|t| fee_on_transfer_tokens.get(t).is_some_and(|flagged| *flagged))
+}
+
+fn has_assumed_decimals(snapshot: &PoolSnapshot, token_decimals: &DashMap<Address, TokenDecimals>) -> bool {
+    [snapshot.token0, snapshot.token1].iter().any(|t| token_decimals.get(t).is_some_and(|d| d.assumed))
+}
+
 /// Helper to determine swap direction (zeroForOne) for the first swap (Swap A) in the buy_pool.
 fn determine_swap_direction(buy_pool_state: &PoolState, loan_token: Address) -> bool {
     buy_pool_state.token0 == loan_token
 }
 
 
-/// Internal helper to calculate WETH/USDC price using snapshot data + state context.
-#[instrument(level="trace", skip(snapshot, state_context), fields(pool=%snapshot.pool_address, dex=?snapshot.dex_type))]
+/// Internal helper to calculate the pair's price (quote per base) using snapshot data + state context.
+#[instrument(level="trace", skip(snapshot, state_context, pair), fields(pool=%snapshot.pool_address, dex=?snapshot.dex_type))]
 fn calculate_cached_price(
     snapshot: &PoolSnapshot,
     state_context: &PoolState,
-    weth_address: Address,
-    _usdc_address: Address,
-    weth_decimals: u8,
-    usdc_decimals: u8,
+    pair: &TradingPair,
 ) -> Result<f64> {
-    // ... (implementation remains the same) ...
     if snapshot.pool_address != state_context.pool_address {
         return Err(eyre!("Snapshot/State address mismatch during price calculation for {}", snapshot.pool_address));
     }
@@ -224,24 +391,18 @@ fn calculate_cached_price(
         warn!(pool=%snapshot.pool_address, snap_dex=?snapshot.dex_type, state_dex=?state_context.dex_type, "Snapshot/State DEX type mismatch!");
     }
 
-    let t0_is_weth = match state_context.t0_is_weth {
-        Some(is_weth) => is_weth,
-        None => {
-            warn!(pool = %state_context.pool_address, "t0_is_weth flag missing in PoolState context, deriving from tokens.");
-            state_context.token0 == weth_address
-        }
-    };
+    // Oriented by the pair, not `t0_is_weth`, so pairs without WETH price correctly
+    let t0_is_base = state_context.token0 == pair.base;
+    let (dec0, dec1) = if t0_is_base { (pair.base_decimals, pair.quote_decimals) } else { (pair.quote_decimals, pair.base_decimals) };
 
     let price_t1_per_t0_result: Result<f64> = match snapshot.dex_type {
         DexType::UniswapV3 => {
             let sqrt_price = snapshot.sqrt_price_x96.ok_or_else(|| eyre!("Snapshot missing sqrtPriceX96 for UniV3 pool {}", snapshot.pool_address))?;
-            let (dec0, dec1) = if t0_is_weth { (weth_decimals, usdc_decimals) } else { (usdc_decimals, weth_decimals) };
             crate::utils::v3_price_from_sqrt(sqrt_price, dec0, dec1)
         }
         DexType::VelodromeV2 | DexType::Aerodrome => {
             let r0 = snapshot.reserve0.ok_or_else(|| eyre!("Snapshot missing reserve0 for V2 pool {}", snapshot.pool_address))?;
             let r1 = snapshot.reserve1.ok_or_else(|| eyre!("Snapshot missing reserve1 for V2 pool {}", snapshot.pool_address))?;
-            let (dec0, dec1) = if t0_is_weth { (weth_decimals, usdc_decimals) } else { (usdc_decimals, weth_decimals) };
             crate::utils::v2_price_from_reserves(r0, r1, dec0, dec1)
         }
         DexType::Unknown => Err(eyre!("Unknown DEX type in snapshot for pool {}", snapshot.pool_address)),
@@ -250,19 +411,19 @@ fn calculate_cached_price(
     let price_t1_per_t0 = price_t1_per_t0_result
         .wrap_err_with(|| format!("Base price calculation failed for pool {}", snapshot.pool_address))?;
 
-    let price_usdc_per_weth = if t0_is_weth {
+    let price_quote_per_base = if t0_is_base {
         price_t1_per_t0
     } else {
         if price_t1_per_t0.abs() < f64::EPSILON {
-            return Err(eyre!("Intermediate price (WETH/USDC) is zero for pool {}, cannot invert", snapshot.pool_address));
+            return Err(eyre!("Intermediate price (base/quote) is zero for pool {}, cannot invert", snapshot.pool_address));
         }
         1.0 / price_t1_per_t0
     };
 
-    if !price_usdc_per_weth.is_finite() {
-        return Err(eyre!("Calculated non-finite USDC/WETH price for pool {}", snapshot.pool_address));
+    if !price_quote_per_base.is_finite() {
+        return Err(eyre!("Calculated non-finite quote/base price for pool {}", snapshot.pool_address));
     }
 
-    trace!(price = price_usdc_per_weth, "Calculated USDC/WETH price from snapshot");
-    Ok(price_usdc_per_weth)
+    trace!(price = price_quote_per_base, "Calculated quote/base price from snapshot");
+    Ok(price_quote_per_base)
 }
\ No newline at end of file
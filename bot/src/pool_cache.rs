@@ -0,0 +1,105 @@
+// bot/src/pool_cache.rs
+//! On-disk cache of the monitored pools (`PoolState` with its latest `PoolSnapshot`), versioned.
+//!
+//! The file is a JSON object carrying `schema_version` next to the pools. The version is read before
+//! the pools are deserialized, so a cache written under another `PoolState`/`PoolSnapshot` layout is
+//! deleted instead of being misread, and its pools are fetched from chain as on a cold start. Bump
+//! `SCHEMA_VERSION` with any change to the fields of those structs (or of `DexType`).
+//!
+//! With `config.pool_cache_path` set, the cache is written on shutdown and loaded on the next start,
+//! where its pools are fetched before (and instead of re-fetching them during) discovery. Cached
+//! snapshots are never traded on; every pool is re-read from chain.
+
+use crate::discovery::DiscoveredPool;
+use crate::state::{AppState, PoolSnapshot, PoolState};
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Layout version of `PoolState`/`PoolSnapshot` written into every cache file.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One cached pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPool {
+    pub state: PoolState,
+    pub snapshot: PoolSnapshot,
+}
+
+#[derive(Deserialize)]
+struct CacheHeader {
+    schema_version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    schema_version: u32,
+    pools: Vec<CachedPool>,
+}
+
+impl CachedPool {
+    /// The pool as discovery would report it, so startup fetches it like a backfilled pool.
+    pub fn discovered(&self) -> DiscoveredPool {
+        DiscoveredPool {
+            pool: self.state.pool_address, dex_type: self.state.dex_type, factory: self.state.factory,
+            token0: self.state.token0, token1: self.state.token1, uni_fee: self.state.uni_fee,
+            velo_stable: self.state.velo_stable, created_block: self.state.created_block,
+        }
+    }
+}
+
+/// Outcome of `load_pool_cache`.
+#[derive(Debug)]
+pub enum PoolCacheLoad {
+    Loaded(Vec<CachedPool>),
+    /// No cache file at the path.
+    Missing,
+    /// The file had another schema version or could not be parsed (the reason). It was removed; the
+    /// pools must be re-fetched.
+    Discarded(String),
+}
+
+/// Writes every cached pool that has a snapshot to `path`, ordered by address, and returns the number
+/// written.
+pub fn write_pool_cache(app_state: &AppState, path: &str) -> Result<usize> {
+    let mut pools: Vec<CachedPool> = app_state.pool_states.iter()
+        .filter_map(|state| app_state.pool_snapshots.get(state.key()).map(|snapshot| CachedPool { state: state.value().clone(), snapshot: snapshot.value().clone() }))
+        .collect();
+    pools.sort_by_key(|p| p.state.pool_address);
+    let count = pools.len();
+    let contents = serde_json::to_string(&CacheFile { schema_version: SCHEMA_VERSION, pools })?;
+    std::fs::write(path, contents).wrap_err_with(|| format!("Failed to write pool cache to {}", path))?;
+    info!(path, pools = count, schema_version = SCHEMA_VERSION, "💾 Pool cache written.");
+    Ok(count)
+}
+
+/// Reads the pool cache at `path`. The schema version is checked before the pools are parsed; a
+/// mismatched or unparsable file is removed.
+pub fn load_pool_cache(path: &str) -> PoolCacheLoad {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return PoolCacheLoad::Missing,
+        Err(e) => return discard(path, format!("unreadable: {}", e)),
+    };
+    match serde_json::from_str::<CacheHeader>(&contents) {
+        Ok(header) if header.schema_version == SCHEMA_VERSION => {}
+        Ok(header) => return discard(path, format!("schema version {} (expected {})", header.schema_version, SCHEMA_VERSION)),
+        Err(e) => return discard(path, format!("no readable header: {}", e)),
+    }
+    match serde_json::from_str::<CacheFile>(&contents) {
+        Ok(file) => {
+            info!(path, pools = file.pools.len(), "Pool cache loaded.");
+            PoolCacheLoad::Loaded(file.pools)
+        }
+        Err(e) => discard(path, format!("malformed pools: {}", e)),
+    }
+}
+
+fn discard(path: &str, reason: String) -> PoolCacheLoad {
+    warn!(path, %reason, "Discarding pool cache; pools will be re-fetched.");
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!(path, error = ?e, "Could not remove the discarded pool cache.");
+    }
+    PoolCacheLoad::Discarded(reason)
+}
+// END OF FILE: bot/src/pool_cache.rs
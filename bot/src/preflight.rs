@@ -0,0 +1,75 @@
+// bot/src/preflight.rs
+//! Startup check that every configured contract answers a cheap view call as expected.
+//!
+//! A wrong factory or stale quoter address otherwise only shows up deep in the run, as empty pool
+//! lists or failing simulations. `Config::preflight_contracts` calls each configured contract once and
+//! fails with the full list of contracts that are unreachable or return unexpected results; `main`
+//! runs it before the event loop unless `SKIP_CONTRACT_PREFLIGHT` is set.
+
+use crate::bindings::{quoter_v2, IAerodromeFactory, IBalancerVaultViews, IUniswapV3Factory, IVelodromeFactory, QuoterV2};
+use crate::config::Config;
+use ethers::providers::Middleware;
+use ethers::types::{Address, U256};
+use eyre::{eyre, Result};
+use std::fmt::Display;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+/// Fee tier of the WETH/USDC pool used for the factory and quoter checks (exists on every supported chain).
+const PREFLIGHT_FEE_TIER: u32 = 500;
+
+/// `Ok` when the call succeeded and `expected` holds, otherwise why the contract failed the check.
+fn outcome<T, E: Display>(result: Result<T, E>, expected: impl FnOnce(&T) -> bool, describe: impl FnOnce(T) -> String) -> Result<(), String> {
+    match result {
+        Ok(value) if expected(&value) => Ok(()),
+        Ok(value) => Err(describe(value)),
+        Err(e) => Err(format!("call failed: {}", e)),
+    }
+}
+
+impl Config {
+    /// Makes one view call to each configured contract: the UniV3 factory (`getPool` of WETH/USDC),
+    /// the quoter (a tiny WETH -> USDC quote), the Velodrome/Aerodrome factories (`allPoolsLength`) and
+    /// the Balancer vault (`WETH()` must match `weth_address`). Returns an error listing every failure.
+    pub async fn preflight_contracts<M: Middleware + 'static>(&self, client: Arc<M>) -> Result<()> {
+        let mut failures = Vec::new();
+        let mut check = |contract: &str, address: Address, result: Result<(), String>| match result {
+            Ok(()) => debug!(contract, ?address, "Contract preflight passed."),
+            Err(reason) => {
+                error!(contract, ?address, %reason, "ALERT: Configured contract failed preflight.");
+                failures.push(format!("{} {:?}: {}", contract, address, reason));
+            }
+        };
+
+        let factory = IUniswapV3Factory::new(self.uniswap_v3_factory_addr, client.clone());
+        let pool = factory.get_pool(self.weth_address, self.usdc_address, PREFLIGHT_FEE_TIER).call().await;
+        check("UniswapV3 factory", self.uniswap_v3_factory_addr, outcome(pool, |pool| !pool.is_zero(), |_| "no WETH/USDC pool".to_string()));
+
+        // 1e-6 WETH: large enough to quote, small enough to stay inside the current tick
+        let amount_in = U256::exp10(self.weth_decimals.saturating_sub(6) as usize);
+        let params = quoter_v2::QuoteExactInputSingleParams {
+            token_in: self.weth_address, token_out: self.usdc_address, amount_in, fee: PREFLIGHT_FEE_TIER, sqrt_price_limit_x96: U256::zero(),
+        };
+        let quote = QuoterV2::new(self.quoter_v2_address, client.clone()).quote_exact_input_single(params).call().await;
+        check("QuoterV2", self.quoter_v2_address, outcome(quote, |quote| !quote.0.is_zero(), |_| "quoted zero output".to_string()));
+
+        if !self.velodrome_v2_factory_addr.is_zero() {
+            let len = IVelodromeFactory::new(self.velodrome_v2_factory_addr, client.clone()).all_pools_length().call().await;
+            check("Velodrome factory", self.velodrome_v2_factory_addr, outcome(len, |len| !len.is_zero(), |_| "no pools".to_string()));
+        }
+        if let Some(aero_factory) = self.aerodrome_factory_addr {
+            let len = IAerodromeFactory::new(aero_factory, client.clone()).all_pools_length().call().await;
+            check("Aerodrome factory", aero_factory, outcome(len, |len| !len.is_zero(), |_| "no pools".to_string()));
+        }
+
+        let vault_weth = IBalancerVaultViews::new(self.balancer_vault_address, client.clone()).weth().call().await;
+        check("Balancer vault", self.balancer_vault_address, outcome(vault_weth, |weth| *weth == self.weth_address, |weth| format!("WETH() is {:?}, expected {:?}", weth, self.weth_address)));
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(eyre!("{} configured contract(s) failed preflight (set SKIP_CONTRACT_PREFLIGHT to bypass): {}", failures.len(), failures.join("; ")))
+        }
+    }
+}
+// END OF FILE: bot/src/preflight.rs
@@ -0,0 +1,107 @@
+// bot/src/price_impact.rs
+//! Price impact of a route's swaps on its pools, bounded by `config.max_price_impact_bps`.
+//!
+//! The impact of a leg is the relative move of the pool's marginal price between its cached snapshot
+//! and the state after the swap. Velo/Aero legs follow the pool curve (`velo_math`, as the mempool
+//! prediction does). UniV3 legs assume the in-range liquidity holds for the whole swap, which
+//! understates the move of swaps that cross into thinner ticks.
+
+use crate::mempool::{predict_snapshot, PendingSwap};
+use crate::path_optimizer::RouteCandidate;
+use crate::state::{AppState, DexType, PoolSnapshot};
+use crate::univ3_math::{get_amount0_delta, get_amount1_delta, mul_div, next_sqrt_price_from_input};
+use crate::utils::ToF64Lossy;
+use ethers::types::{Address, U256};
+
+const UNIV3_FEE_DENOMINATOR: u32 = 1_000_000;
+/// Bisection steps of `max_loan_within_impact` (range / 2^40 is far below one gwei of WETH per ETH of range).
+const CAP_SEARCH_STEPS: usize = 40;
+
+/// Marginal price of token0 in token1 (raw units of each).
+pub(crate) fn marginal_price(state: &AppState, snapshot: &PoolSnapshot) -> Option<f64> {
+    match snapshot.dex_type {
+        DexType::UniswapV3 => {
+            let sqrt_price = snapshot.sqrt_price_x96?.to_f64_lossy() / 2f64.powi(96);
+            Some(sqrt_price * sqrt_price)
+        }
+        DexType::VelodromeV2 | DexType::Aerodrome => {
+            let (reserve0, reserve1) = (snapshot.reserve0?.to_f64_lossy(), snapshot.reserve1?.to_f64_lossy());
+            if state.pool_states.get(&snapshot.pool_address)?.velo_stable? {
+                // dy/dx of x³y + y³x = k on decimal-normalized reserves, scaled back to raw units
+                let (decimals0, decimals1) = (state.decimals_of(snapshot.token0)? as i32, state.decimals_of(snapshot.token1)? as i32);
+                let (x, y) = (reserve0 / 10f64.powi(decimals0), reserve1 / 10f64.powi(decimals1));
+                Some((3.0 * x * x * y + y * y * y) / (x * x * x + 3.0 * x * y * y) * 10f64.powi(decimals1 - decimals0))
+            } else {
+                Some(reserve1 / reserve0)
+            }
+        }
+        DexType::Unknown => None,
+    }
+}
+
+/// Snapshot after, and output of, swapping `amount_in` of `token_in` into `pool`.
+fn swap_leg(state: &AppState, pool: Address, token_in: Address, amount_in: U256) -> Option<(PoolSnapshot, U256)> {
+    let before = state.pool_snapshots.get(&pool)?.value().clone();
+    let zero_for_one = before.token0 == token_in;
+    match before.dex_type {
+        DexType::UniswapV3 => {
+            let (sqrt_price, liquidity) = (before.sqrt_price_x96?, before.liquidity?);
+            let fee = state.pool_states.get(&pool)?.uni_fee?;
+            let amount_less_fee = mul_div(amount_in, U256::from(UNIV3_FEE_DENOMINATOR - fee), U256::from(UNIV3_FEE_DENOMINATOR)).ok()?;
+            let next = next_sqrt_price_from_input(sqrt_price, liquidity, amount_less_fee, zero_for_one).ok()?;
+            let amount_out = if zero_for_one {
+                get_amount1_delta(next, sqrt_price, liquidity, false)
+            } else {
+                get_amount0_delta(sqrt_price, next, liquidity, false)
+            }.ok()?;
+            Some((PoolSnapshot { sqrt_price_x96: Some(next), ..before }, amount_out))
+        }
+        DexType::VelodromeV2 | DexType::Aerodrome => {
+            let after = predict_snapshot(&PendingSwap { pool, zero_for_one, amount_in }, state)?;
+            let amount_out = if zero_for_one { before.reserve1? - after.reserve1? } else { before.reserve0? - after.reserve0? };
+            Some((after, amount_out))
+        }
+        DexType::Unknown => None,
+    }
+}
+
+/// Largest price move (bps) `route` causes on either of its pools with a loan of `loan_amount`.
+/// None when a snapshot lacks the data to model a leg.
+pub fn route_price_impact_bps(state: &AppState, route: &RouteCandidate, loan_amount: U256) -> Option<u64> {
+    let leg_impact = |pool: Address, token_in: Address, amount_in: U256| -> Option<(u64, U256)> {
+        let before = state.pool_snapshots.get(&pool)?.value().clone();
+        let (after, amount_out) = swap_leg(state, pool, token_in, amount_in)?;
+        let (price_before, price_after) = (marginal_price(state, &before)?, marginal_price(state, &after)?);
+        if price_before.is_nan() || price_before <= 0.0 || !price_after.is_finite() {
+            return None;
+        }
+        Some((((price_after / price_before - 1.0).abs() * 10_000.0).round() as u64, amount_out))
+    };
+    let (buy_impact, intermediate) = leg_impact(route.buy_pool_addr, route.token_in, loan_amount)?;
+    let (sell_impact, _) = leg_impact(route.sell_pool_addr, route.token_out, intermediate)?;
+    Some(buy_impact.max(sell_impact))
+}
+
+/// Largest loan in `[min, max]` whose route impact stays within `max_impact_bps`, assuming impact grows
+/// with the loan. `max` itself when the impact cannot be modelled; None when even `min` exceeds the limit.
+pub fn max_loan_within_impact(state: &AppState, route: &RouteCandidate, min: U256, max: U256, max_impact_bps: u64) -> Option<U256> {
+    let within = |loan: U256| route_price_impact_bps(state, route, loan).map(|bps| bps <= max_impact_bps);
+    match within(max) {
+        None | Some(true) => return Some(max),
+        Some(false) => {}
+    }
+    if within(min) != Some(true) {
+        return None;
+    }
+    // Invariant: within(lo) && !within(hi)
+    let (mut lo, mut hi) = (min, max);
+    for _ in 0..CAP_SEARCH_STEPS {
+        if hi - lo <= U256::one() {
+            break;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if within(mid) == Some(true) { lo = mid; } else { hi = mid; }
+    }
+    Some(lo)
+}
+// END OF FILE: bot/src/price_impact.rs
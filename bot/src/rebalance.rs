@@ -0,0 +1,171 @@
+// bot/src/rebalance.rs
+//! Post-trade WETH rebalancing.
+//!
+//! USDC surplus left by manual sweeps slowly drains the wallet's WETH working balance, which seeds flash loan
+//! fees and follow-up transactions. A periodic check compares the wallet's WETH balance with
+//! `config.min_weth_working_balance` and, when it is below, sweeps each rebalance token from the executor and
+//! sells the wallet's whole balance of it for WETH on the configured Velodrome router (volatile pool,
+//! `max_slippage_bps` below the router quote), stopping once the floor is restored. With
+//! `config.auto_wrap_eth`, native ETH above the gas reserve covers what the sales could not. A zero floor
+//! disables rebalancing; nothing is swapped while paused.
+
+use crate::bindings::{velodrome_router, VelodromeRouter, IERC20, IWETH9};
+use crate::metrics::Metrics;
+use crate::state::AppState;
+use crate::transaction::{send_and_confirm, sweep_executor_token, NonceManager};
+use ethers::prelude::*;
+use eyre::{eyre, Result, WrapErr};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Validity window of a rebalance swap.
+const REBALANCE_SWAP_DEADLINE_SECS: u64 = 300;
+
+/// One token sold back into WETH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalanceSwap {
+    pub token: Address,
+    pub amount_in: U256,
+    pub tx_hash: TxHash,
+}
+
+/// Outcome of a rebalance that had to swap (the WETH balance was below the floor).
+#[derive(Debug, Clone)]
+pub struct RebalanceReport {
+    pub weth_before: U256,
+    pub weth_after: U256,
+    pub swaps: Vec<RebalanceSwap>,
+    pub wrap_tx_hash: Option<TxHash>,
+}
+
+/// Tokens sold back into WETH: the configured USDC. Never WETH itself.
+pub fn rebalance_tokens(app_state: &AppState) -> Vec<Address> {
+    let usdc = app_state.config.usdc_address;
+    if usdc == app_state.weth_address || usdc.is_zero() { vec![] } else { vec![usdc] }
+}
+
+/// `quote` less `max_slippage_bps`.
+pub fn min_output(quote: U256, max_slippage_bps: u64) -> U256 {
+    quote * U256::from(10_000 - max_slippage_bps.min(10_000)) / U256::from(10_000u64)
+}
+
+/// ETH to wrap so the wallet holds `required_weth`: the shortfall below it, or None when there is none or
+/// the native balance cannot cover it and still keep `gas_reserve_wei` for gas.
+pub fn wrap_shortfall(weth_balance: U256, eth_balance: U256, required_weth: U256, gas_reserve_wei: U256) -> Option<U256> {
+    let shortfall = required_weth.saturating_sub(weth_balance);
+    (!shortfall.is_zero() && eth_balance >= shortfall.saturating_add(gas_reserve_wei)).then_some(shortfall)
+}
+
+/// Runs one rebalance check for `wallet`. Returns None when disabled, paused or the balance is at or above
+/// the floor; otherwise the swaps made (possibly none, when there was nothing to sell) and any ETH wrap.
+pub async fn rebalance_weth<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    wallet: Address,
+) -> Result<Option<RebalanceReport>> where M::Error: 'static + Send + Sync {
+    let floor = app_state.config.min_weth_working_balance;
+    if floor.is_zero() || app_state.is_paused() {
+        return Ok(None);
+    }
+    let weth = IERC20::new(app_state.weth_address, client.clone());
+    let weth_before = weth.balance_of(wallet).call().await.wrap_err("Failed to read wallet WETH balance")?;
+    if weth_before >= floor {
+        debug!(balance = %weth_before, %floor, "WETH working balance above floor.");
+        return Ok(None);
+    }
+    info!(balance = %weth_before, %floor, "WETH working balance below floor. Rebalancing.");
+
+    let mut weth_after = weth_before;
+    let mut swaps = Vec::new();
+    for token in rebalance_tokens(app_state) {
+        if let Some((hash, amount)) = sweep_executor_token(client.clone(), app_state, nonce_manager, token, wallet).await? {
+            info!(%hash, ?token, %amount, "Swept executor token to wallet for rebalancing.");
+        }
+        let amount_in = IERC20::new(token, client.clone()).balance_of(wallet).call().await
+            .wrap_err_with(|| format!("Failed to read wallet balance of {:?}", token))?;
+        if amount_in.is_zero() {
+            continue;
+        }
+        let tx_hash = swap_to_weth(client.clone(), app_state, nonce_manager, wallet, token, amount_in).await?;
+        Metrics::inc(&app_state.metrics.weth_rebalance_swaps);
+        swaps.push(RebalanceSwap { token, amount_in, tx_hash });
+        weth_after = weth.balance_of(wallet).call().await.wrap_err("Failed to read wallet WETH balance")?;
+        if weth_after >= floor {
+            break;
+        }
+    }
+    let mut wrap_tx_hash = None;
+    if weth_after < floor && app_state.config.auto_wrap_eth {
+        wrap_tx_hash = wrap_eth_shortfall(client.clone(), app_state, nonce_manager, wallet, floor).await?;
+        if wrap_tx_hash.is_some() {
+            weth_after = weth.balance_of(wallet).call().await.wrap_err("Failed to read wallet WETH balance")?;
+        }
+    }
+    if weth_after < floor {
+        warn!(balance = %weth_after, %floor, swaps = swaps.len(), wrapped = wrap_tx_hash.is_some(), "WETH working balance still below floor after rebalancing; nothing left to sell or wrap.");
+    } else {
+        info!(before = %weth_before, after = %weth_after, swaps = swaps.len(), wrapped = wrap_tx_hash.is_some(), "⚖️ WETH working balance restored.");
+    }
+    Ok(Some(RebalanceReport { weth_before, weth_after, swaps, wrap_tx_hash }))
+}
+
+/// Tops the wallet's WETH up to `required_weth` by wrapping native ETH via `IWETH9::deposit`, keeping
+/// `config.min_gas_balance_wei` of ETH for gas. Returns the deposit tx hash, or None when nothing is missing
+/// or there is not enough ETH.
+async fn wrap_eth_shortfall<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    wallet: Address,
+    required_weth: U256,
+) -> Result<Option<TxHash>> where M::Error: 'static + Send + Sync {
+    let config = &app_state.config;
+    if required_weth.is_zero() {
+        return Ok(None);
+    }
+    let weth = IWETH9::new(app_state.weth_address, client.clone());
+    let weth_balance = weth.balance_of(wallet).call().await.wrap_err("Failed to read wallet WETH balance")?;
+    if weth_balance >= required_weth {
+        return Ok(None);
+    }
+    let eth_balance = client.get_balance(wallet, None).await.map_err(|e| eyre!("Failed to read wallet ETH balance: {}", e))?;
+    let Some(amount) = wrap_shortfall(weth_balance, eth_balance, required_weth, config.min_gas_balance_wei) else {
+        warn!(%weth_balance, %eth_balance, %required_weth, gas_reserve = %config.min_gas_balance_wei, "WETH short but not enough ETH to wrap the shortfall.");
+        return Ok(None);
+    };
+    let hash = send_and_confirm(client.clone(), app_state.tx_type, nonce_manager, weth.deposit().value(amount)).await.wrap_err("WETH wrap failed")?;
+    info!(%hash, %amount, %weth_balance, %required_weth, "💱 Wrapped ETH to cover the WETH shortfall.");
+    Ok(Some(hash))
+}
+
+/// Sells `amount_in` of `token` from `wallet` for WETH on the Velodrome router, approving it first if needed.
+async fn swap_to_weth<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    wallet: Address,
+    token: Address,
+    amount_in: U256,
+) -> Result<TxHash> where M::Error: 'static + Send + Sync {
+    let config = &app_state.config;
+    let router = VelodromeRouter::new(config.velo_router_addr, client.clone());
+    let routes = vec![velodrome_router::Route { from: token, to: app_state.weth_address, stable: false, factory: config.velodrome_v2_factory_addr }];
+    let quote = router.get_amounts_out(amount_in, routes.clone()).call().await
+        .wrap_err_with(|| format!("Failed to quote rebalance of {:?}", token))?
+        .last().copied().ok_or_else(|| eyre!("Empty getAmountsOut result for {:?}", token))?;
+    let amount_out_min = min_output(quote, config.max_slippage_bps);
+
+    let erc20 = IERC20::new(token, client.clone());
+    if erc20.allowance(wallet, config.velo_router_addr).call().await.wrap_err("Failed to read router allowance")? < amount_in {
+        send_and_confirm(client.clone(), app_state.tx_type, nonce_manager, erc20.approve(config.velo_router_addr, amount_in)).await
+            .wrap_err_with(|| format!("Router approval of {:?} failed", token))?;
+    }
+    let deadline = U256::from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + REBALANCE_SWAP_DEADLINE_SECS);
+    let swap = router.swap_exact_tokens_for_tokens(amount_in, amount_out_min, routes, wallet, deadline);
+    let hash = send_and_confirm(client, app_state.tx_type, nonce_manager, swap).await.wrap_err_with(|| format!("Rebalance swap of {:?} failed", token))?;
+    info!(%hash, ?token, %amount_in, %quote, %amount_out_min, "Sold token for WETH.");
+    Ok(hash)
+}
+// END OF FILE: bot/src/rebalance.rs
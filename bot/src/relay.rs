@@ -0,0 +1,132 @@
+// bot/src/relay.rs
+//! Private relay endpoints and request authentication.
+//!
+//! Relays that rank senders by reputation (Flashbots Protect and compatible builders) expect the
+//! JSON-RPC body to be signed by a stable searcher key. With `RelayAuthScheme::Flashbots` the body is
+//! signed with the wallet key as an EIP-191 personal message over the hex keccak256 of the body, and
+//! sent as `X-Flashbots-Signature: <address>:<signature>`.
+
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::TxHash;
+use ethers::utils::keccak256;
+use eyre::{eyre, Result, WrapErr};
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+pub const FLASHBOTS_SIGNATURE_HEADER: &str = "X-Flashbots-Signature";
+// A relay that has not answered by then is treated as failed, so the next one (sequential mode) still lands in time
+const RELAY_HTTP_TIMEOUT_SECS: u64 = 5;
+
+lazy_static! {
+    // One client (and connection pool) shared by all relay submissions
+    static ref RELAY_HTTP: reqwest::Client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(RELAY_HTTP_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_default();
+}
+
+/// How requests to a relay are authenticated. Parsed from `none` / `flashbots`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelayAuthScheme {
+    /// Plain JSON-RPC, no auth header.
+    #[default]
+    None,
+    /// `X-Flashbots-Signature` over the request body.
+    Flashbots,
+}
+
+impl FromStr for RelayAuthScheme {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(RelayAuthScheme::None),
+            "flashbots" => Ok(RelayAuthScheme::Flashbots),
+            other => Err(eyre!("Unknown relay auth scheme: {} (supported: none, flashbots)", other)),
+        }
+    }
+}
+
+/// A private relay submissions are tried against, in `Config::private_relays` order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayConfig {
+    pub url: String,
+    pub auth_scheme: RelayAuthScheme,
+}
+
+/// JSON-RPC body for a private transaction submission (Alchemy or Flashbots-style method by URL).
+pub fn private_tx_body(url: &str, rlp_hex: &str) -> Vec<u8> {
+    let (method, params) = if url.contains("alchemy") {
+        ("alchemy_sendPrivateTransaction", json!([{ "tx": rlp_hex }]))
+    } else {
+        ("eth_sendPrivateRawTransaction", json!([rlp_hex]))
+    };
+    json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string().into_bytes()
+}
+
+/// `<address>:<signature>` header value signing `body` with `wallet`.
+pub async fn flashbots_signature(wallet: &LocalWallet, body: &[u8]) -> Result<String> {
+    let digest = format!("0x{}", hex::encode(keccak256(body)));
+    let signature = wallet.sign_message(digest).await.wrap_err("Failed to sign relay request")?;
+    Ok(format!("{:?}:0x{}", wallet.address(), signature))
+}
+
+/// Builds the POST of `body` to `relay`, with the auth header its scheme requires.
+pub async fn build_relay_request(http: &reqwest::Client, relay: &RelayConfig, wallet: &LocalWallet, body: Vec<u8>) -> Result<reqwest::Request> {
+    let mut request = http.post(&relay.url).header(reqwest::header::CONTENT_TYPE, "application/json");
+    if relay.auth_scheme == RelayAuthScheme::Flashbots {
+        request = request.header(FLASHBOTS_SIGNATURE_HEADER, flashbots_signature(wallet, &body).await?);
+    }
+    request.body(body).build().wrap_err_with(|| format!("Invalid relay URL {}", relay.url))
+}
+
+/// Submits a signed raw transaction to `relay` and returns the hash it reports.
+pub async fn send_private_tx(relay: &RelayConfig, wallet: &LocalWallet, rlp_hex: &str) -> Result<TxHash> {
+    let request = build_relay_request(&RELAY_HTTP, relay, wallet, private_tx_body(&relay.url, rlp_hex)).await?;
+    debug!(relay = %relay.url, auth = ?relay.auth_scheme, "Posting private transaction.");
+    let response: Value = RELAY_HTTP.execute(request).await?.error_for_status()?.json().await?;
+    if let Some(error) = response.get("error") {
+        return Err(eyre!("Relay RPC error: {}", error));
+    }
+    let result = response.get("result").cloned().ok_or_else(|| eyre!("Relay response has no result: {}", response))?;
+    serde_json::from_value(result).wrap_err("Relay result is not a transaction hash")
+}
+
+/// Sends to every relay at once and returns the first relay to accept, with its result. Each send runs as
+/// its own task, so relays still in flight keep going after the first acceptance and every relay receives
+/// the transaction; their late results are only logged. Errors only when every relay fails (with the last failure).
+pub async fn first_accepted<T, F, Fut>(relays: Vec<RelayConfig>, send: F) -> Result<(RelayConfig, T)>
+where
+    F: Fn(RelayConfig) -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    for relay in relays {
+        let submission = send(relay.clone());
+        let results_tx = results_tx.clone();
+        tokio::spawn(async move {
+            let result = submission.await;
+            match &result {
+                Err(e) => warn!(error = ?e, relay = %relay.url, "Private Relay submission failed."),
+                Ok(_) if results_tx.is_closed() => debug!(relay = %relay.url, "Private Relay accepted after another relay."),
+                Ok(_) => {}
+            }
+            let _ = results_tx.send((relay, result));
+        });
+    }
+    drop(results_tx);
+    let mut last_error = None;
+    while let Some((relay, result)) = results.recv().await {
+        match result {
+            Ok(value) => return Ok((relay, value)),
+            Err(e) => last_error = Some(e.wrap_err(format!("Relay {} failed", relay.url))),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| eyre!("No private relays configured")))
+}
+// END OF FILE: bot/src/relay.rs
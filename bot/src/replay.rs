@@ -0,0 +1,128 @@
+// bot/src/replay.rs
+//! `ulp1_5 replay <block> <buy_pool>:<dex> <sell_pool>:<dex>`: was a route profitable at a past block?
+//!
+//! Post-mortem for a missed opportunity. The pools' static fields (tokens, fee, stability) are read at
+//! the latest block and their price/reserves with archive `eth_call`s at `block`. The loan search then
+//! runs as usual with every quote and gas estimate pinned to `block` (`AppState::replay_block`), charging
+//! gas at that block's base fee plus the configured priority fee. Needs an archive node, and the quoter,
+//! routers and executor deployed at `block`: a gas estimate against a missing executor reverts, which the
+//! search reports as unprofitable.
+
+use crate::config::Config;
+use crate::path_optimizer::{find_top_routes, RouteCandidate};
+use crate::simulation::find_optimal_loan_amount;
+use crate::state::{fetch_and_cache_pool_state, refresh_pool_snapshot, AppState, DexType};
+use crate::transaction::simulation_gas_price_gwei;
+use ethers::prelude::{Http, LocalWallet, Middleware, Provider, Signer, SignerMiddleware};
+use ethers::types::{Address, I256, U256, U64};
+use ethers::utils::parse_units;
+use eyre::{eyre, Result, WrapErr};
+use std::sync::Arc;
+use tracing::info;
+
+/// Outcome of a route's loan search as of a past block.
+#[derive(Debug, Clone)]
+pub struct ReplayReport {
+    pub block: U64,
+    pub route_id: String,
+    pub gas_price_gwei: f64,
+    /// Best loan and its net profit, None when no loan size was profitable.
+    pub best: Option<(U256, I256)>,
+}
+
+/// Parses `<pool>:<dex>` (e.g. `0xabc…:univ3`).
+pub fn parse_pool_arg(arg: &str) -> Result<(Address, DexType)> {
+    let (pool, dex) = arg.split_once(':').ok_or_else(|| eyre!("Expected <pool>:<dex>, got {}", arg))?;
+    Ok((pool.parse().map_err(|e| eyre!("Invalid pool address {}: {}", pool, e))?, dex.parse()?))
+}
+
+fn factory_for(config: &Config, dex_type: DexType) -> Result<Address> {
+    match dex_type {
+        DexType::UniswapV3 => Ok(config.uniswap_v3_factory_addr),
+        DexType::VelodromeV2 => Ok(config.velodrome_v2_factory_addr),
+        DexType::Aerodrome => config.aerodrome_factory_addr.ok_or_else(|| eyre!("AERODROME_FACTORY_ADDR not set")),
+        DexType::Unknown => Err(eyre!("Cannot replay a pool of unknown DEX type")),
+    }
+}
+
+/// An `AppState` holding `pools` as of `block`, with quotes and gas estimates pinned to it.
+pub async fn replay_state(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    config: &Config,
+    pools: &[(Address, DexType)],
+    block: U64,
+) -> Result<Arc<AppState>> {
+    // Reserves at `block` may be far from the latest ones; the jump filter would keep the latest
+    let mut state = AppState::new(Config { max_price_jump_bps: 0, ..config.clone() });
+    state.replay_block = Some(block);
+    let state = Arc::new(state);
+    let block_timestamp = state.block_timestamp(block, client.as_ref()).await;
+    for &(pool, dex_type) in pools {
+        fetch_and_cache_pool_state(pool, dex_type, factory_for(config, dex_type)?, None, client.clone(), state.clone()).await
+            .wrap_err_with(|| format!("Failed to fetch pool {:?}", pool))?;
+        if !state.pool_snapshots.contains_key(&pool) {
+            return Err(eyre!("Pool {:?} was not cached (blocklisted or not a target pair?)", pool));
+        }
+        refresh_pool_snapshot(pool, dex_type, block, block_timestamp, client.clone(), &state).await
+            .wrap_err_with(|| format!("Failed to read pool {:?} at block {} (archive node required)", pool, block))?;
+    }
+    Ok(state)
+}
+
+/// The `buy_pool` -> `sell_pool` route found in `app_state`, or an error when the pools' prices at the
+/// replayed block do not make that a route.
+pub fn replay_route(app_state: &AppState, buy_pool: Address, sell_pool: Address) -> Result<RouteCandidate> {
+    let snapshot = app_state.pool_snapshots.get(&buy_pool).map(|s| s.value().clone()).ok_or_else(|| eyre!("No snapshot for {:?}", buy_pool))?;
+    find_top_routes(
+        &snapshot, &app_state.pool_states, &app_state.pool_snapshots, &app_state.fee_on_transfer_tokens, &app_state.token_decimals,
+        &app_state.config, &app_state.trading_pairs(),
+    )
+    .into_iter()
+    .find(|route| route.buy_pool_addr == buy_pool && route.sell_pool_addr == sell_pool)
+    .ok_or_else(|| eyre!("No {:?} -> {:?} route: the pools' prices did not favour that direction", buy_pool, sell_pool))
+}
+
+/// Runs the loan search for `route` as of `block`. `app_state` must come from `replay_state` for that block.
+pub async fn simulate_at_block(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: Arc<AppState>,
+    route: &RouteCandidate,
+    block: U64,
+) -> Result<ReplayReport> {
+    if app_state.replay_block != Some(block) {
+        return Err(eyre!("State is pinned to {:?}, not block {}", app_state.replay_block, block));
+    }
+    let config = &app_state.config;
+    let base_fee = client.get_block(block).await.wrap_err("Failed to fetch the replayed block")?
+        .ok_or_else(|| eyre!("Block {} not found", block))?
+        .base_fee_per_gas;
+    let priority_fee: U256 = parse_units(config.max_priority_fee_per_gas_gwei, "gwei")?.into();
+    let gas_price_gwei = simulation_gas_price_gwei(base_fee.map(|fee| fee.saturating_add(priority_fee)), config);
+
+    let buy_snapshot = app_state.pool_snapshots.get(&route.buy_pool_addr).map(|s| s.value().clone());
+    let sell_snapshot = app_state.pool_snapshots.get(&route.sell_pool_addr).map(|s| s.value().clone());
+    let best = find_optimal_loan_amount(client, app_state.clone(), route, buy_snapshot.as_ref(), sell_snapshot.as_ref(), gas_price_gwei).await?
+        .map(|(loan_wei, net_profit_wei, _)| (loan_wei, net_profit_wei));
+    info!(%block, route = %route.id(), gas_price_gwei, ?best, "Replay finished.");
+    Ok(ReplayReport { block, route_id: route.id(), gas_price_gwei, best })
+}
+
+/// `replay` subcommand: `args` are `<block> <buy_pool>:<dex> <sell_pool>:<dex>`.
+pub async fn run_replay(config: &Config, args: &[String]) -> Result<ReplayReport> {
+    let [block, buy, sell] = args else {
+        return Err(eyre!("Usage: replay <block> <buy_pool>:<dex> <sell_pool>:<dex>"));
+    };
+    let block = U64::from(block.parse::<u64>().map_err(|e| eyre!("Invalid block {}: {}", block, e))?);
+    let (buy_pool, buy_dex) = parse_pool_arg(buy)?;
+    let (sell_pool, sell_dex) = parse_pool_arg(sell)?;
+
+    let provider = Provider::<Http>::try_from(config.read_endpoint()).wrap_err("HTTP provider creation failed")?;
+    let chain_id = match config.chain_id { Some(id) => id, None => provider.get_chainid().await?.as_u64() };
+    let wallet = config.local_private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let app_state = replay_state(client.clone(), config, &[(buy_pool, buy_dex), (sell_pool, sell_dex)], block).await?;
+    let route = replay_route(&app_state, buy_pool, sell_pool)?;
+    simulate_at_block(client, app_state, &route, block).await
+}
+// END OF FILE: bot/src/replay.rs
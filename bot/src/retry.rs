@@ -0,0 +1,43 @@
+// bot/src/retry.rs
+//! Retry with jittered exponential backoff for transient RPC failures.
+
+use eyre::Result;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// Upper bound of a single backoff delay, however many retries are configured.
+const MAX_BACKOFF_MS: u64 = 10_000;
+
+/// Runs `op` until it succeeds, retrying up to `max_retries` times. Retry `n` (1-based) waits a random
+/// delay in `[d/2, d]` with `d = base_delay_ms * 2^(n-1)` (capped at `MAX_BACKOFF_MS`), so concurrent
+/// fetches failing together do not retry in lockstep. Returns the last error when every attempt fails.
+pub async fn with_retries<T, F, Fut>(max_retries: u32, base_delay_ms: u64, context: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_retries => {
+                attempt += 1;
+                let delay = backoff_delay(base_delay_ms, attempt);
+                warn!(context, attempt, max_retries, delay_ms = delay.as_millis() as u64, error = ?e, "Attempt failed, retrying.");
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Jittered delay before retry `attempt` (1-based).
+pub fn backoff_delay(base_delay_ms: u64, attempt: u32) -> Duration {
+    let ceiling = base_delay_ms.saturating_mul(1u64 << attempt.saturating_sub(1).min(32)).min(MAX_BACKOFF_MS);
+    let jitter = RandomState::new().build_hasher().finish() % (ceiling / 2 + 1);
+    Duration::from_millis(ceiling - jitter)
+}
+// END OF FILE: bot/src/retry.rs
@@ -0,0 +1,72 @@
+// bot/src/revert.rs
+//! Human-readable revert reasons for failed flash-loan transactions.
+//!
+//! Reverted txs are replayed with `eth_call` against the state they executed on, and the returned revert
+//! data is decoded as a standard `Error(string)` or `Panic(uint256)`. The Huff executor bubbles up the revert
+//! data of a failed pool or token call, so a failed swap decodes as the callee's own error. Its own profit,
+//! salt, slippage floor and caller checks revert with empty data (its ABI declares no custom errors), so an
+//! empty payload is reported as one of those.
+
+use ethers::abi::{decode, ParamType, Token};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use tracing::debug;
+
+/// `Error(string)`
+pub const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// `Panic(uint256)`
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decodes raw revert data into a readable reason.
+pub fn decode_revert_data(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "empty revert data (executor check: unprofitable, reused salt, slippage floor or unauthorized caller)".to_string();
+    }
+    let (selector, payload) = data.split_at(data.len().min(4));
+    match selector {
+        s if s == ERROR_STRING_SELECTOR => match decode(&[ParamType::String], payload).ok().as_deref() {
+            Some([Token::String(reason)]) => format!("Error(\"{}\")", reason),
+            _ => format!("malformed Error(string) payload 0x{}", hex::encode(payload)),
+        },
+        s if s == PANIC_SELECTOR => match decode(&[ParamType::Uint(256)], payload).ok().as_deref() {
+            Some([Token::Uint(code)]) => format!("Panic(0x{:02x}): {}", code, panic_description(*code)),
+            _ => format!("malformed Panic(uint256) payload 0x{}", hex::encode(payload)),
+        },
+        s => format!("unknown error selector 0x{} (data 0x{})", hex::encode(s), hex::encode(data)),
+    }
+}
+
+fn panic_description(code: U256) -> &'static str {
+    match code.low_u64() {
+        _ if code > U256::from(u64::MAX) => "unknown panic code",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum conversion",
+        0x22 => "corrupt storage byte array",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to zero-initialized function",
+        _ => "unknown panic code",
+    }
+}
+
+/// The decoded revert reason carried by an RPC error, if the node returned revert data.
+pub fn revert_reason_from_error<E: MiddlewareError>(error: &E) -> Option<String> {
+    error.as_error_response().and_then(|resp| resp.as_revert_data()).map(|data| decode_revert_data(&data))
+}
+
+/// Replays `tx` with `eth_call` at `block` and returns the decoded revert reason. For a mined tx pass the
+/// parent of its block; earlier txs in the same block are not replayed, so a tx that only reverted because
+/// of in-block ordering replays successfully and is reported as such.
+pub async fn decode_revert_reason<M: Middleware>(client: &M, tx: &TypedTransaction, block: Option<BlockId>) -> String {
+    match client.call(tx, block).await {
+        Ok(output) => {
+            debug!(output = %output, "Revert replay succeeded.");
+            "replay did not revert (state changed within the block)".to_string()
+        }
+        Err(e) => revert_reason_from_error(&e).unwrap_or_else(|| format!("replay failed without revert data: {}", e)),
+    }
+}
+// END OF FILE: bot/src/revert.rs
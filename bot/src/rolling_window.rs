@@ -0,0 +1,49 @@
+// bot/src/rolling_window.rs
+//! Fixed-size rolling sample window behind the gas usage, RPC latency and profit accuracy trackers.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// The last `N` samples; recording into a full window drops the oldest.
+#[derive(Debug)]
+pub struct RollingWindow<T, const N: usize> {
+    samples: Mutex<VecDeque<T>>,
+}
+
+impl<T, const N: usize> Default for RollingWindow<T, N> {
+    fn default() -> Self {
+        Self { samples: Mutex::new(VecDeque::with_capacity(N)) }
+    }
+}
+
+impl<T: Copy, const N: usize> RollingWindow<T, N> {
+    pub fn record(&self, sample: T) {
+        self.record_then(sample, |_| ())
+    }
+
+    /// Records `sample` and applies `f` to the updated window under the same lock.
+    pub fn record_then<R>(&self, sample: T, f: impl FnOnce(&VecDeque<T>) -> R) -> R {
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if samples.len() == N {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+        f(&samples)
+    }
+
+    /// 95th percentile (nearest rank) of the window, or None below `min_samples` (or when it is 0).
+    pub fn p95(&self, min_samples: usize) -> Option<T>
+    where
+        T: Ord,
+    {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        if min_samples == 0 || samples.len() < min_samples {
+            return None;
+        }
+        let mut sorted: Vec<T> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+}
+// END OF FILE: bot/src/rolling_window.rs
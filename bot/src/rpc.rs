@@ -0,0 +1,43 @@
+// bot/src/rpc.rs
+//! Separate signer clients for reads and writes.
+//!
+//! Pool state fetches, snapshot refreshes and simulations go through `read` (`READ_RPC_URL`, e.g. a fast
+//! archive node); arb submissions go through `write` (`WRITE_RPC_URL`, e.g. a low-latency sender). Both
+//! default to `HTTP_RPC_URL`, in which case they are one client. Private relays are unaffected: the
+//! submission path still tries them before falling back to the write endpoint.
+
+use crate::config::Config;
+use ethers::prelude::{Http, LocalWallet, Provider, SignerMiddleware};
+use eyre::{Result, WrapErr};
+use std::sync::Arc;
+
+/// Read and write clients signing with the same wallet.
+#[derive(Debug, Clone)]
+pub struct RpcClients {
+    pub read: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    pub write: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+}
+
+impl RpcClients {
+    /// Connects to `config.read_endpoint()` and `config.write_endpoint()`.
+    pub fn new(config: &Config, wallet: LocalWallet) -> Result<Self> {
+        let connect = |url: &str| -> Result<Arc<SignerMiddleware<Provider<Http>, LocalWallet>>> {
+            let provider = Provider::<Http>::try_from(url).wrap_err_with(|| format!("Invalid RPC URL {}", url))?;
+            Ok(Arc::new(SignerMiddleware::new(provider, wallet.clone())))
+        };
+        let read = connect(config.read_endpoint())?;
+        let write = if config.write_endpoint() == config.read_endpoint() { read.clone() } else { connect(config.write_endpoint())? };
+        Ok(Self { read, write })
+    }
+
+    /// One client for both reads and writes (e.g. a local fork).
+    pub fn shared(client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>) -> Self {
+        Self { read: client.clone(), write: client }
+    }
+
+    /// Whether reads and writes go to different endpoints.
+    pub fn is_split(&self) -> bool {
+        !Arc::ptr_eq(&self.read, &self.write)
+    }
+}
+// END OF FILE: bot/src/rpc.rs
@@ -0,0 +1,59 @@
+// bot/src/seen_logs.rs
+//! Bounded set of recently processed logs, so logs re-delivered after a WS reconnect do not update
+//! snapshots or trigger arbitrage checks a second time.
+//!
+//! Logs are keyed by `(transaction_hash, log_index)`; logs missing either are never treated as seen.
+//! Once `config.seen_log_cache_size` keys are held, the one seen longest ago is forgotten.
+
+use ethers::types::{Log, H256, U256};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+type LogKey = (H256, U256);
+
+#[derive(Debug, Default)]
+struct Recent {
+    keys: HashSet<LogKey>,
+    order: VecDeque<LogKey>,
+}
+
+#[derive(Debug, Default)]
+pub struct SeenLogs {
+    capacity: usize,
+    recent: Mutex<Recent>,
+}
+
+impl SeenLogs {
+    /// A zero `capacity` disables deduplication.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, recent: Mutex::default() }
+    }
+
+    /// Records `log` and returns whether it had been seen already.
+    pub fn check_and_insert(&self, log: &Log) -> bool {
+        let key = match (log.transaction_hash, log.log_index) {
+            (Some(tx_hash), Some(log_index)) if self.capacity > 0 => (tx_hash, log_index),
+            _ => return false,
+        };
+        let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !recent.keys.insert(key) {
+            return true;
+        }
+        recent.order.push_back(key);
+        if recent.order.len() > self.capacity {
+            if let Some(oldest) = recent.order.pop_front() {
+                recent.keys.remove(&oldest);
+            }
+        }
+        false
+    }
+
+    pub fn len(&self) -> usize {
+        self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+// END OF FILE: bot/src/seen_logs.rs
@@ -0,0 +1,116 @@
+// bot/src/self_test.rs
+//! `ulp1_5 self-test`: end-to-end check of an operator setup on an Anvil fork.
+//!
+//! Forks `config.http_rpc_url` with a local `anvil` (or uses `SELF_TEST_RPC_URL`, an already running
+//! fork), deploys the executor from `config.executor_bytecode_path`, knocks the Velodrome WETH/USDC
+//! volatile pool off-price with an ETH->USDC router swap, then runs one detection pass over that pool
+//! and the UniV3 WETH/USDC 0.05% pool and submits the best route. The test passes when the arbitrage
+//! transaction confirms. It runs as the first Anvil dev account, so it never spends the configured key,
+//! and needs Optimism addresses (Velodrome V2) and a Cancun fork (the executor uses TLOAD/TSTORE).
+//! CI runs it on every push when the `OPTIMISM_FORK_RPC_URL` secret is set (`.github/workflows/self-test.yml`).
+
+use crate::bindings::{velodrome_router, IUniswapV3Factory, VelodromeRouter};
+use crate::config::{Config, EXECUTOR_BYTECODE_FILE};
+use crate::deadline::Deadline;
+use crate::deploy::{deploy_contract_from_bytecode, verify_transient_storage};
+use crate::event_handler::run_detection_pass;
+use crate::state::{fetch_and_cache_pool_state, AppState, DexType};
+use crate::transaction::{submit_arbitrage_transaction, NonceManager};
+use ethers::prelude::{Http, LocalWallet, Middleware, Provider, Signer, SignerMiddleware};
+use ethers::types::{Address, TxHash, I256, U256};
+use ethers::utils::{parse_ether, Anvil, AnvilInstance};
+use eyre::{eyre, Result, WrapErr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// First Anvil dev account (funded with 10,000 ETH on every fork).
+const ANVIL_DEV_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+/// ETH swapped into the Velodrome pool to create the price discrepancy.
+const DISCREPANCY_SWAP_ETH: &str = "200";
+const UNIV3_POOL_FEE: u32 = 500;
+const ANVIL_STARTUP_TIMEOUT_MS: u64 = 60_000;
+const SUBMISSION_TTL: Duration = Duration::from_secs(60); // Anvil round-trips are not latency-bound
+
+/// What a passing self-test did.
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    pub executor: Address,
+    pub route_id: String,
+    pub loan_amount_wei: U256,
+    pub simulated_net_profit_wei: I256,
+    pub tx_hash: TxHash,
+}
+
+/// Runs the self-test against a fork of `config`'s chain. Any failing step is returned as an error
+/// naming the step.
+pub async fn run_self_test(config: &Config) -> Result<SelfTestReport> {
+    // Kept alive until the test ends; dropping it stops anvil
+    let (_anvil, rpc_url) = match config.self_test_rpc_url.clone() {
+        Some(url) => (None, url),
+        None => {
+            let fork_url = config.http_rpc_url.clone();
+            info!(fork = %fork_url, "Starting anvil fork...");
+            let anvil: AnvilInstance = tokio::task::spawn_blocking(move || Anvil::new().fork(fork_url).timeout(ANVIL_STARTUP_TIMEOUT_MS).spawn())
+                .await
+                .map_err(|_| eyre!("Failed to start anvil (is it installed and on PATH?)"))?;
+            let url = anvil.endpoint();
+            (Some(anvil), url)
+        }
+    };
+    let provider = Provider::<Http>::try_from(rpc_url.as_str()).wrap_err("Invalid self-test RPC URL")?;
+    let chain_id = provider.get_chainid().await.wrap_err("Fork RPC is not reachable")?.as_u64();
+    let wallet = ANVIL_DEV_KEY.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let wallet_address = wallet.address();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    info!(%rpc_url, chain_id, signer = ?wallet_address, "Connected to fork.");
+
+    // A fork pinned to a pre-Cancun hardfork would only show up as a reverted arbitrage
+    verify_transient_storage(client.as_ref()).await.wrap_err("Fork cannot run the executor")?;
+    let bytecode_path = if config.executor_bytecode_path.is_empty() { config.build_path(EXECUTOR_BYTECODE_FILE).display().to_string() } else { config.executor_bytecode_path.clone() };
+    let executor = deploy_contract_from_bytecode(client.clone(), &bytecode_path).await.wrap_err("Executor deployment failed")?;
+    info!(?executor, "Executor deployed.");
+
+    let (weth, usdc) = (config.weth_address, config.usdc_address);
+    let uni_pool = IUniswapV3Factory::new(config.uniswap_v3_factory_addr, client.clone())
+        .get_pool(weth, usdc, UNIV3_POOL_FEE).call().await.wrap_err("UniV3 getPool failed")?;
+    let router = VelodromeRouter::new(config.velo_router_addr, client.clone());
+    let velo_pool = router.pool_for(weth, usdc, false, config.velodrome_v2_factory_addr).call().await.wrap_err("Velodrome poolFor failed")?;
+    if uni_pool.is_zero() || client.get_code(velo_pool, None).await?.is_empty() {
+        return Err(eyre!("WETH/USDC pools not found on the fork (UniV3: {:?}, Velodrome: {:?}); the self-test needs Optimism addresses", uni_pool, velo_pool));
+    }
+
+    // Price discrepancy: buy USDC with ETH on Velodrome only
+    let routes = vec![velodrome_router::Route { from: weth, to: usdc, stable: false, factory: config.velodrome_v2_factory_addr }];
+    let deadline = U256::from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 600);
+    let swap = router.swap_exact_eth_for_tokens(U256::zero(), routes, wallet_address, deadline).value(parse_ether(DISCREPANCY_SWAP_ETH)?);
+    let receipt = swap.send().await.wrap_err("Discrepancy swap failed")?.await?.ok_or_else(|| eyre!("Discrepancy swap dropped"))?;
+    info!(tx_hash = ?receipt.transaction_hash, eth = DISCREPANCY_SWAP_ETH, pool = ?velo_pool, "Velodrome pool pushed off-price.");
+
+    let test_config = Config {
+        http_rpc_url: rpc_url.clone(),
+        read_rpc_url: None, // Both sides on the fork
+        write_rpc_url: None,
+        arb_executor_address: Some(executor),
+        deploy_executor: false,
+        private_rpc_url: None, // Relays cannot see the fork
+        secondary_private_rpc_url: None,
+        ..config.clone()
+    };
+    let app_state = Arc::new(AppState::new(test_config));
+    fetch_and_cache_pool_state(uni_pool, DexType::UniswapV3, config.uniswap_v3_factory_addr, Some(UNIV3_POOL_FEE), client.clone(), app_state.clone()).await?;
+    fetch_and_cache_pool_state(velo_pool, DexType::VelodromeV2, config.velodrome_v2_factory_addr, None, client.clone(), app_state.clone()).await?;
+
+    let profitable = run_detection_pass(app_state.clone(), client.clone()).await.wrap_err("Detection pass failed")?;
+    let (route, loan_amount_wei, simulated_net_profit_wei) = profitable.into_iter().next()
+        .ok_or_else(|| eyre!("No profitable route detected after the discrepancy swap"))?;
+    info!(route = %route.id(), %loan_amount_wei, %simulated_net_profit_wei, "Profitable route detected, submitting.");
+
+    let route_id = route.id();
+    let nonce_manager = Arc::new(NonceManager::new(wallet_address));
+    let tx_hash = submit_arbitrage_transaction(client, app_state, route, loan_amount_wei, simulated_net_profit_wei, nonce_manager, Deadline::after(SUBMISSION_TTL))
+        .await
+        .wrap_err("Arbitrage submission did not confirm")?;
+    Ok(SelfTestReport { executor, route_id, loan_amount_wei, simulated_net_profit_wei, tx_hash })
+}
+// END OF FILE: bot/src/self_test.rs
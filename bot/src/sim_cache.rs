@@ -0,0 +1,124 @@
+// bot/src/sim_cache.rs
+//! Per-block cache of optimal loan searches, so several swap logs on the same pool in one block do not
+//! re-run identical `find_optimal_loan_amount` searches.
+//!
+//! Entries are keyed by route id, the route's source block and a fingerprint of the pool snapshots
+//! the search ran on: a later swap in the same block changes the snapshots and misses the cache. The
+//! gas price of the first search is reused for the rest of the block. Concurrent checks of the same
+//! key wait for the search in flight instead of starting their own; failed searches are not cached.
+//!
+//! `LegQuoteCache` keeps the per-leg outputs `calculate_net_profit` quoted for each loan amount it tried,
+//! so submission derives its slippage floors from the quotes the route was priced with instead of
+//! re-quoting both legs.
+
+use crate::metrics::Metrics;
+use crate::path_optimizer::RouteCandidate;
+use crate::state::PoolSnapshot;
+use dashmap::DashMap;
+use ethers::types::{I256, U256, U64};
+use eyre::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::trace;
+
+/// Optimal loan amount, its net profit and its price impact in bps (when modelled), or None when no
+/// amount is profitable.
+pub type LoanSearchResult = Option<(U256, I256, Option<u64>)>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationKey {
+    pub route_id: String,
+    pub block: U64,
+    pub snapshots_fingerprint: u64,
+}
+
+impl SimulationKey {
+    /// None when the route's source block is unknown (such searches are never cached).
+    pub fn new(route: &RouteCandidate, buy_snapshot: Option<&PoolSnapshot>, sell_snapshot: Option<&PoolSnapshot>) -> Option<Self> {
+        if route.source_block.is_zero() {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        for snapshot in [buy_snapshot, sell_snapshot] {
+            snapshot.map(|s| (s.reserve0, s.reserve1, s.sqrt_price_x96, s.tick, s.liquidity)).hash(&mut hasher);
+        }
+        Some(Self { route_id: route.id(), block: route.source_block, snapshots_fingerprint: hasher.finish() })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SimulationCache {
+    entries: DashMap<SimulationKey, Arc<OnceCell<LoanSearchResult>>>,
+}
+
+impl SimulationCache {
+    /// Returns the cached result for `key`, running `search` on a miss. Hits count in
+    /// `metrics.simulation_cache_hits`.
+    pub async fn get_or_search<F, Fut>(&self, key: SimulationKey, metrics: &Metrics, search: F) -> Result<LoanSearchResult>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<LoanSearchResult>>,
+    {
+        // Clone the cell out so no map shard lock is held across the search
+        let cell = self.entries.entry(key.clone()).or_default().clone();
+        let mut searched = false;
+        let result = cell.get_or_try_init(|| { searched = true; search() }).await.copied();
+        if !searched && result.is_ok() {
+            trace!(route = %key.route_id, block = %key.block, "Simulation cache hit.");
+            Metrics::inc(&metrics.simulation_cache_hits);
+        }
+        result
+    }
+
+    /// Drops entries for blocks before `block_number` (called on every new block).
+    pub fn prune(&self, block_number: U64) {
+        self.entries.retain(|key, _| key.block >= block_number);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Swap A output (intermediate token) and Swap B output (loan token) of one quoted loan amount.
+pub type LegQuotes = (U256, U256);
+
+#[derive(Debug, Default)]
+pub struct LegQuoteCache {
+    entries: DashMap<(String, U64, U256), LegQuotes>,
+}
+
+impl LegQuoteCache {
+    /// Records the legs quoted for `route` at `loan_amount`; the latest quote of a key wins. Routes with
+    /// an unknown source block are not recorded.
+    pub fn record(&self, route: &RouteCandidate, loan_amount: U256, quotes: LegQuotes) {
+        if !route.source_block.is_zero() {
+            self.entries.insert((route.id(), route.source_block, loan_amount), quotes);
+        }
+    }
+
+    pub fn get(&self, route: &RouteCandidate, loan_amount: U256) -> Option<LegQuotes> {
+        self.entries.get(&(route.id(), route.source_block, loan_amount)).map(|entry| *entry)
+    }
+
+    /// Drops entries for blocks before `block_number` (called on every new block).
+    pub fn prune(&self, block_number: U64) {
+        self.entries.retain(|(_, block, _), _| *block >= block_number);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+// END OF FILE: bot/src/sim_cache.rs
@@ -9,26 +9,44 @@ use crate::bindings::{
     VelodromeRouter,
 };
 use crate::config::Config;
+use crate::flash_loan::{execute_flash_loan_calldata, flash_loan_fee_wei};
 use crate::gas::estimate_flash_loan_gas;
+use crate::metrics::Metrics;
 use crate::state::{AppState, DexType, PoolSnapshot};
+use crate::univ3_math::quote_exact_input;
 use crate::path_optimizer::RouteCandidate;
-use crate::utils::{f64_to_wei, ToF64Lossy};
+use crate::price_impact::{max_loan_within_impact, route_price_impact_bps};
+use crate::sim_cache::LoanSearchResult;
+use crate::slippage::corrected_output;
+use crate::gas::{buffered_gas_limit, gas_cost_in_loan_token, gas_cost_wei};
+use crate::utils::{checked_mul_div, f64_to_wei, u256_to_i256, ToF64Lossy};
+use crate::encoding::{encode_user_data, route_swap_legs};
 use ethers::{
     // abi::AbiDecode, // Removed unused import
-    contract::ContractError, // For matching specific contract errors
-    prelude::{Http, LocalWallet, Provider, SignerMiddleware},
-    types::{Address, Bytes, I256, U256, Selector}, // Added Bytes for revert data compare
+    prelude::{Http, JsonRpcClient, LocalWallet, Middleware, Provider, SignerMiddleware},
+    providers::{spoof, RawCall},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, H256, I256, U256},
     utils::{format_units, parse_units},
 };
+#[cfg(feature = "local_simulation")]
+use ethers::{contract::ContractError, types::Selector}; // For matching the PairDoesNotExist revert in local sim
 use eyre::{eyre, Result, WrapErr};
-use hex; // Needed for hex decoding selector string
+use futures_util::{stream, StreamExt};
 use std::sync::Arc;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, instrument, trace, warn};
+#[cfg(feature = "local_simulation")]
 use std::str::FromStr; // Needed for Address::from_str
+#[cfg(feature = "local_simulation")]
+use crate::{mempool::velo_pool_curve, velo_math::get_amount_out as velo_get_amount_out};
 
 // Configuration Constants for Simulation
 const V2_RESERVE_PERCENTAGE_LIMIT: u64 = 5; // Max loan size as % of V2 pool reserve
+// Scratch address that hosts the flash loan probe code during the state-override eth_call.
+const FLASH_LOAN_PROBE_ADDRESS: Address = Address::repeat_byte(0xf1);
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+const TX_BASE_GAS: u64 = 21_000;
+const SHADOW_DIVERGENCE_WARN_BPS: u64 = 10; // Shadow-mode local quotes further off QuoterV2 than this are logged as divergent
 
 // Hardcoded Velodrome Router V2 Implementation address for local simulation workaround
 #[cfg(feature = "local_simulation")]
@@ -44,6 +62,7 @@ pub async fn simulate_swap(
     app_state: Arc<AppState>,
     client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
     dex_type: DexType,
+    pool: Address,
     token_in: Address,
     token_out: Address,
     amount_in_wei: U256,
@@ -59,27 +78,31 @@ pub async fn simulate_swap(
             let fee = uni_pool_fee.ok_or_else(|| eyre!("Missing UniV3 pool fee for simulation"))?;
             let params = quoter_v2_bindings::QuoteExactInputSingleParams { token_in, token_out, amount_in: amount_in_wei, fee, sqrt_price_limit_x96: U256::zero(), };
             trace!(?params, "Calling QuoterV2 quoteExactInputSingle");
-            let quote_result = quoter.quote_exact_input_single(params).call().await
+            let mut quote_call = quoter.quote_exact_input_single(params);
+            if let Some(block) = app_state.replay_block { quote_call = quote_call.block(block); }
+            let quote_result = quote_call.call().await
                 .wrap_err_with(|| format!("QuoterV2 simulation failed for pair {token_in:?} -> {token_out:?}"))?;
             debug!(amount_out = %quote_result.0, "QuoterV2 simulation successful");
             Ok(quote_result.0)
         }
         DexType::VelodromeV2 | DexType::Aerodrome => {
-            let mut router_address_to_use = if dex_type == DexType::VelodromeV2 {
+            let configured_router_address = if dex_type == DexType::VelodromeV2 {
                 app_state.config.velo_router_addr
             } else {
                 app_state.config.aerodrome_router_addr.ok_or_else(|| eyre!("Aerodrome router address missing for simulation"))?
             };
-            let mut attempted_impl_call = false;
 
             #[cfg(feature = "local_simulation")]
-            {
-                if dex_type == DexType::VelodromeV2 {
-                    warn!("LOCAL SIMULATION: Attempting VelodromeV2 simulate_swap with IMPL address ({}) due to Anvil proxy issues.", VELO_ROUTER_IMPL_ADDR_FOR_SIM);
-                    router_address_to_use = Address::from_str(VELO_ROUTER_IMPL_ADDR_FOR_SIM)?;
-                    attempted_impl_call = true;
-                }
-            }
+            let attempted_impl_call = dex_type == DexType::VelodromeV2;
+            #[cfg(feature = "local_simulation")]
+            let router_address_to_use = if attempted_impl_call {
+                warn!("LOCAL SIMULATION: Attempting VelodromeV2 simulate_swap with IMPL address ({}) due to Anvil proxy issues.", VELO_ROUTER_IMPL_ADDR_FOR_SIM);
+                Address::from_str(VELO_ROUTER_IMPL_ADDR_FOR_SIM)?
+            } else {
+                configured_router_address
+            };
+            #[cfg(not(feature = "local_simulation"))]
+            let router_address_to_use = configured_router_address;
 
             let factory_address_for_call = factory_addr.ok_or_else(|| eyre!("Missing Factory address for Velo/Aero route simulation"))?;
             let stable_for_call = is_stable_route.ok_or_else(|| eyre!("Missing stability flag for Velo/Aero simulation"))?;
@@ -93,10 +116,12 @@ pub async fn simulate_swap(
             }];
             trace!(?routes, amount_in = %amount_in_wei, "Calling VelodromeRouter ({}) getAmountsOut", router_address_to_use);
 
-            match router.get_amounts_out(amount_in_wei, routes.clone()).call().await {
+            let mut amounts_call = router.get_amounts_out(amount_in_wei, routes.clone());
+            if let Some(block) = app_state.replay_block { amounts_call = amounts_call.block(block); }
+            match amounts_call.call().await {
                 Ok(amounts) if amounts.len() >= 2 => {
                     debug!(amounts_out = ?amounts, "Velo/Aero getAmountsOut simulation successful on address {}", router_address_to_use);
-                    Ok(amounts[1])
+                    Ok(corrected_output(amounts[1], app_state.config.velo_slippage_correction_bps))
                 }
                 Ok(amounts) => Err(eyre!("Invalid amounts array length returned from getAmountsOut: {}", amounts.len())),
                 Err(e) => {
@@ -123,18 +148,10 @@ pub async fn simulate_swap(
                             return Err(eyre!(e).wrap_err(format!("Velo/Aero getAmountsOut RPC call FAILED UNEXPECTEDLY on IMPL address {}, factory {}, stable {}", router_address_to_use, factory_address_for_call, stable_for_call)));
                         }
 
-                        // Fallback estimation logic (same as before)
-                        let estimated_out = if stable_for_call {
-                             if token_in == app_state.usdc_address && token_out == app_state.weth_address { amount_in_wei / 2000u64 }
-                             else if token_in == app_state.weth_address && token_out == app_state.usdc_address { amount_in_wei * 2000u64 }
-                             else { amount_in_wei }
-                        } else {
-                             if token_in == app_state.weth_address && token_out == app_state.usdc_address { amount_in_wei * 2000u64 }
-                             else if token_in == app_state.usdc_address && token_out == app_state.weth_address { amount_in_wei / 2000u64 }
-                             else { amount_in_wei }
-                        };
-                        let simulated_out = estimated_out * 999u64 / 1000u64;
-                        warn!(amount_in = %amount_in_wei, simulated_out = %simulated_out, "Using ESTIMATED output for local sim due to IMPL call revert/failure.");
+                        // Fall back to the pool's own curve on the cached reserves
+                        let simulated_out = local_velo_quote(&app_state, pool, token_in, amount_in_wei)
+                            .ok_or_else(|| eyre!("No cached reserves to quote Velo/Aero pool {:?} locally", pool))?;
+                        warn!(%pool, amount_in = %amount_in_wei, simulated_out = %simulated_out, "Using the local Velo curve output for local sim due to IMPL call revert/failure.");
                         return Ok(simulated_out);
                     }
                     // If not local_simulation or not attempted_impl_call, propagate original error
@@ -146,6 +163,73 @@ pub async fn simulate_swap(
     }
 }
 
+/// Quotes a Velo/Aero exact-input swap locally from the pool's hot-cache reserves, as `Pool.getAmountOut`
+/// would at the default fee. Returns `None` when the reserves (or a stable pool's decimals) are not cached.
+#[cfg(feature = "local_simulation")]
+fn local_velo_quote(app_state: &AppState, pool: Address, token_in: Address, amount_in_wei: U256) -> Option<U256> {
+    let stable = app_state.pool_states.get(&pool)?.velo_stable?;
+    let snapshot = app_state.pool_snapshots.get(&pool)?;
+    let (reserves, fee_bps) = velo_pool_curve(app_state, &snapshot, stable)?;
+    let zero_for_one = token_in == snapshot.token0;
+    drop(snapshot);
+    velo_get_amount_out(&reserves, amount_in_wei, zero_for_one, fee_bps).ok()
+}
+
+/// Quotes a UniV3 exact-input swap locally from the pool's hot-cache snapshot, crossing the initialized
+/// ticks of its `univ3_ticks` window when one was read. Returns `None` when the snapshot lacks
+/// price/liquidity data or the swap would leave the known ticks (the window, else the current tick
+/// range), in which case the caller falls back to QuoterV2.
+fn local_univ3_quote(app_state: &AppState, pool: Address, token_in: Address, amount_in_wei: U256, fee: Option<u32>) -> Option<U256> {
+    let snapshot = app_state.pool_snapshots.get(&pool)?;
+    let (sqrt_price_x96, tick, liquidity) = (snapshot.sqrt_price_x96?, snapshot.tick?, snapshot.liquidity?);
+    let zero_for_one = token_in == snapshot.token0;
+    drop(snapshot);
+    let tick_spacing = app_state.pool_states.get(&pool).and_then(|state| state.tick_spacing);
+    let window = app_state.univ3_ticks.get(&pool);
+    match quote_exact_input(sqrt_price_x96, tick, liquidity, fee?, tick_spacing, window.as_deref(), zero_for_one, amount_in_wei) {
+        Ok(Some(amount_out)) => { trace!(%pool, %amount_in_wei, %amount_out, "UniV3 swap simulated locally."); Some(amount_out) }
+        Ok(None) => { trace!(%pool, %amount_in_wei, "UniV3 swap leaves the known ticks, using QuoterV2."); None }
+        Err(e) => { debug!(%pool, error = %e, "Local UniV3 math failed, using QuoterV2."); None }
+    }
+}
+
+/// Absolute difference of a local quote from the QuoterV2 quote, in bps of the QuoterV2 quote.
+pub fn quote_divergence_bps(local: U256, quoter: U256) -> u64 {
+    if quoter.is_zero() {
+        return if local.is_zero() { 0 } else { u64::MAX };
+    }
+    let diff = if local > quoter { local - quoter } else { quoter - local };
+    checked_mul_div(diff, U256::from(10_000), quoter, "quote divergence").map_or(u64::MAX, |bps| bps.min(U256::from(u64::MAX)).as_u64())
+}
+
+/// Records the divergence of a shadow-mode local quote (when the local math produced one) from QuoterV2.
+fn record_shadow_quote(app_state: &AppState, pool: Address, local: Option<U256>, quoter: U256) {
+    let Some(local) = local.filter(|_| app_state.config.pricing_shadow_mode) else { return };
+    let bps = quote_divergence_bps(local, quoter);
+    Metrics::inc(&app_state.metrics.shadow_quotes_compared);
+    Metrics::max(&app_state.metrics.shadow_quote_max_divergence_bps, bps);
+    if bps > SHADOW_DIVERGENCE_WARN_BPS {
+        Metrics::inc(&app_state.metrics.shadow_quotes_divergent);
+        warn!(%pool, %local, %quoter, divergence_bps = bps, "Local UniV3 quote diverges from QuoterV2.");
+    } else {
+        debug!(%pool, %local, %quoter, divergence_bps = bps, "Local UniV3 quote matches QuoterV2.");
+    }
+}
+
+/// Simulates both legs of `route` for `amount_in_wei` via the DEX quoters, returning
+/// `(intermediate_out, loan_token_out)`. Used to derive the per-swap slippage floors at submission.
+pub async fn simulate_route_legs(
+    app_state: Arc<AppState>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    route: &RouteCandidate,
+    amount_in_wei: U256,
+) -> Result<(U256, U256)> {
+    let out_a = simulate_swap( app_state.clone(), client.clone(), route.buy_dex_type, route.buy_pool_addr, route.token_in, route.token_out, amount_in_wei, route.buy_pool_stable, route.buy_pool_fee, Some(route.buy_pool_factory), ).await
+        .wrap_err("Swap A simulation failed")?;
+    let out_b = simulate_swap( app_state, client, route.sell_dex_type, route.sell_pool_addr, route.token_out, route.token_in, out_a, route.sell_pool_stable, route.sell_pool_fee, Some(route.sell_pool_factory), ).await
+        .wrap_err("Swap B simulation failed")?;
+    Ok((out_a, out_b))
+}
 
 /// Calculates the estimated net profit for a given route and loan amount.
 #[allow(clippy::too_many_arguments)]
@@ -158,36 +242,45 @@ pub async fn calculate_net_profit(
     gas_price_gwei: f64,
     gas_limit_buffer_percentage: u64,
     min_flashloan_gas_limit: u64,
+    use_local_univ3_math: bool, // Quote UniV3 legs from the snapshot when possible (QuoterV2 otherwise)
 ) -> Result<I256> {
     // ... (rest of the function remains the same) ...
     let config = &app_state.config;
     let loan_token = route.token_in; let intermediate_token = route.token_out;
     trace!("Calculating net profit for route: {:?} -> {:?}", route.buy_dex_type, route.sell_dex_type);
-    let amount_out_intermediate = match simulate_swap( app_state.clone(), client.clone(), route.buy_dex_type, loan_token, intermediate_token, amount_in_wei, route.buy_pool_stable, route.buy_pool_fee, Some(route.buy_pool_factory), ).await { Ok(amount) => amount, Err(e) => { warn!(error=?e, "Swap A simulation failed, assuming unprofitable."); return Ok(I256::min_value()); } };
+    let local_out_a = if use_local_univ3_math && route.buy_dex_type == DexType::UniswapV3 { local_univ3_quote(&app_state, route.buy_pool_addr, loan_token, amount_in_wei, route.buy_pool_fee) } else { None };
+    // Shadow mode: local quotes are only compared against QuoterV2, which prices the route
+    let shadow = config.pricing_shadow_mode && use_local_univ3_math;
+    let amount_out_intermediate = match local_out_a { Some(amount) if !shadow => amount, _ => match simulate_swap( app_state.clone(), client.clone(), route.buy_dex_type, route.buy_pool_addr, loan_token, intermediate_token, amount_in_wei, route.buy_pool_stable, route.buy_pool_fee, Some(route.buy_pool_factory), ).await { Ok(amount) => { record_shadow_quote(&app_state, route.buy_pool_addr, local_out_a, amount); amount }, Err(e) => { warn!(error=?e, "Swap A simulation failed, assuming unprofitable."); return Ok(I256::min_value()); } } };
     if amount_out_intermediate.is_zero() { debug!("Swap A simulation returned zero output. Route unprofitable."); return Ok(I256::min_value()); }
     trace!(amount_out_intermediate = %amount_out_intermediate, "Swap A simulation successful.");
-    let final_amount_out_loan_token = match simulate_swap( app_state.clone(), client.clone(), route.sell_dex_type, intermediate_token, loan_token, amount_out_intermediate, route.sell_pool_stable, route.sell_pool_fee, Some(route.sell_pool_factory), ).await { Ok(amount) => amount, Err(e) => { warn!(error=?e, "Swap B simulation failed, assuming unprofitable."); return Ok(I256::min_value()); } };
+    let local_out_b = if use_local_univ3_math && route.sell_dex_type == DexType::UniswapV3 { local_univ3_quote(&app_state, route.sell_pool_addr, intermediate_token, amount_out_intermediate, route.sell_pool_fee) } else { None };
+    let final_amount_out_loan_token = match local_out_b { Some(amount) if !shadow => amount, _ => match simulate_swap( app_state.clone(), client.clone(), route.sell_dex_type, route.sell_pool_addr, intermediate_token, loan_token, amount_out_intermediate, route.sell_pool_stable, route.sell_pool_fee, Some(route.sell_pool_factory), ).await { Ok(amount) => { record_shadow_quote(&app_state, route.sell_pool_addr, local_out_b, amount); amount }, Err(e) => { warn!(error=?e, "Swap B simulation failed, assuming unprofitable."); return Ok(I256::min_value()); } } };
     trace!(final_amount_out_loan_token = %final_amount_out_loan_token, "Swap B simulation successful.");
-    let gross_profit_wei = I256::from_raw(final_amount_out_loan_token) - I256::from_raw(amount_in_wei);
-    debug!(gross_profit_wei = %gross_profit_wei, "Gross profit calculated.");
+    app_state.leg_quotes.record(route, amount_in_wei, (amount_out_intermediate, final_amount_out_loan_token));
+    // The lender's fee is owed on top of the principal
+    let flash_loan_fee = flash_loan_fee_wei(&app_state, amount_in_wei)?;
+    let gross_profit_wei = u256_to_i256(final_amount_out_loan_token, "swap B output")?
+        .checked_sub(u256_to_i256(amount_in_wei.saturating_add(flash_loan_fee), "loan amount plus fee")?)
+        .ok_or_else(|| eyre!("Gross profit underflow (out {}, in {}, fee {})", final_amount_out_loan_token, amount_in_wei, flash_loan_fee))?;
+    debug!(gross_profit_wei = %gross_profit_wei, flash_loan_fee_wei = %flash_loan_fee, "Gross profit calculated.");
     if gross_profit_wei <= I256::zero() { return Ok(gross_profit_wei); }
     trace!("Estimating gas cost for net profit calculation...");
     let gas_price_wei_str = format!("{:.18}", gas_price_gwei); let gas_price_wei: U256 = parse_units(&gas_price_wei_str, "gwei")?.into();
     trace!(gas_price_gwei=%gas_price_gwei, gas_price_wei=%gas_price_wei, "Converted gas price");
-    let effective_router_addr = if route.buy_dex_type.is_velo_style() || route.sell_dex_type.is_velo_style() { if route.buy_dex_type == DexType::Aerodrome || route.sell_dex_type == DexType::Aerodrome { config.aerodrome_router_addr.ok_or_else(|| eyre!("Aero router needed for encoding"))? } else { config.velo_router_addr } } else { config.velo_router_addr };
-    use crate::encoding::encode_user_data;
-    let user_data_for_gas_est = encode_user_data( route.buy_pool_addr, route.sell_pool_addr, intermediate_token, route.zero_for_one_a, route.buy_dex_type.is_velo_style(), route.sell_dex_type.is_velo_style(), effective_router_addr, U256::zero(), U256::zero(), )?;
+    let (leg_a, leg_b) = route_swap_legs(&app_state, route)?;
+    let user_data_for_gas_est = encode_user_data( config.executor_abi_version, leg_a, leg_b, intermediate_token, U256::zero(), U256::zero(), U256::zero(), U256::zero(), )?;
     trace!("User data for gas estimate encoded.");
     let gas_est_timeout = Duration::from_secs(10);
     let gas_estimate_result = timeout(
         gas_est_timeout,
         estimate_flash_loan_gas(
             client.clone(),
-            config.balancer_vault_address,
             config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for gas estimate"))?,
             loan_token,
             amount_in_wei,
             user_data_for_gas_est,
+            app_state.replay_block,
         )
     ).await;
     let gas_estimate_units = match gas_estimate_result {
@@ -202,18 +295,346 @@ pub async fn calculate_net_profit(
         }
     };
     trace!(gas_estimate_units = %gas_estimate_units, "Initial gas estimate received.");
-    let buffered_gas_limit = gas_estimate_units * (100 + gas_limit_buffer_percentage) / 100; let final_gas_limit = std::cmp::max(buffered_gas_limit, U256::from(min_flashloan_gas_limit));
-    trace!(buffered_gas_limit = %buffered_gas_limit, min_flashloan_gas_limit = %min_flashloan_gas_limit, final_gas_limit = %final_gas_limit, "Calculated final gas limit");
-    let gas_cost_wei = gas_price_wei * final_gas_limit;
-    trace!(gas_cost_wei = %gas_cost_wei, "Total gas cost calculated.");
-    let net_profit_wei = gross_profit_wei - I256::from_raw(gas_cost_wei);
+    let final_gas_limit = buffered_gas_limit(gas_estimate_units, gas_limit_buffer_percentage, min_flashloan_gas_limit)?;
+    trace!(min_flashloan_gas_limit = %min_flashloan_gas_limit, final_gas_limit = %final_gas_limit, "Calculated final gas limit");
+    let gas_cost_wei = gas_cost_in_loan_token(gas_cost_wei(final_gas_limit, gas_price_wei)?, config.gas_token_price_in_loan_token, app_state.weth_decimals)?;
+    trace!(gas_cost_wei = %gas_cost_wei, "Total gas cost calculated (loan token wei).");
+    let mut net_profit_wei = gross_profit_wei.saturating_sub(u256_to_i256(gas_cost_wei, "gas cost")?);
+    if config.competition_priority_fee_bps_of_profit > 0 {
+        let swap_profit_wei = gross_profit_wei.saturating_add(u256_to_i256(flash_loan_fee, "flash loan fee")?);
+        let competitive_fee = competitive_priority_fee(swap_profit_wei, config.competition_priority_fee_bps_of_profit)?;
+        net_profit_wei = net_profit_wei.saturating_sub(u256_to_i256(competitive_fee, "competitive priority fee")?);
+        trace!(%competitive_fee, priority_fee_gwei = implied_priority_fee_gwei(config, app_state.weth_decimals, competitive_fee, final_gas_limit), "Competitive priority fee subtracted.");
+    }
     debug!(net_profit_wei = %net_profit_wei, "Net profit calculated.");
     Ok(net_profit_wei)
 }
 
 
+/// How a net profit figure was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationMethod {
+    /// One `eth_call` executing the real flash loan through the executor (state override).
+    FlashLoanBundle,
+    /// Two chained per-leg quotes plus a separate gas estimate (`calculate_net_profit`).
+    TwoQuoter,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NetProfitSimulation {
+    pub net_profit_wei: I256,
+    pub method: SimulationMethod,
+    /// Gas used by the flash loan including intrinsic cost (bundle method only).
+    pub gas_used: Option<U256>,
+    /// Priority fee per gas (gwei) of the competitive bid subtracted from the profit (bundle method with
+    /// `COMPETITION_PRIORITY_FEE_BPS_OF_PROFIT` only).
+    pub competitive_priority_fee_gwei: Option<f64>,
+}
+
+/// A flash loan transaction run through the probe by `simulate_via_executor_callstatic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutorCallStatic {
+    /// False when the flash loan reverted (e.g. a slippage floor or the on-chain min-profit check).
+    pub succeeded: bool,
+    /// Loan-token balance change of the executor.
+    pub gross_profit_wei: I256,
+    /// Gas used by the flash loan including intrinsic cost.
+    pub gas_used: U256,
+}
+
+/// Runtime code of the flash loan probe (no jumps, addresses embedded). Called with the flash loan
+/// transaction's calldata as its own calldata, it runs:
+/// `b0 = loanToken.balanceOf(executor); g0 = gas; ok = to.call(calldata); used = g0 - gas;
+///  b1 = loanToken.balanceOf(executor); return (ok, b1 - b0, used)`
+/// where `ok` is the AND of all call success flags. The executor keeps the profit, so `b1 - b0` is the gross profit.
+pub fn flash_loan_probe_bytecode(loan_token: Address, executor: Address, to: Address) -> Bytes {
+    const SUB: u8 = 0x03; const AND: u8 = 0x16; const SHL: u8 = 0x1b; const CALLDATASIZE: u8 = 0x36; const CALLDATACOPY: u8 = 0x37;
+    const MLOAD: u8 = 0x51; const MSTORE: u8 = 0x52; const GAS: u8 = 0x5a; const SWAP1: u8 = 0x90;
+    const PUSH1: u8 = 0x60; const PUSH2: u8 = 0x61; const PUSH4: u8 = 0x63; const PUSH20: u8 = 0x73;
+    const CALL: u8 = 0xf1; const RETURN: u8 = 0xf3; const STATICCALL: u8 = 0xfa;
+    // Memory: 0x00-0x24 call head, 0x80 b0, 0xc0 b1, 0xe0 g0, 0x100.. calldata copy, 0x200.. results (written after the call)
+    const CALLDATA_OFFSET: [u8; 2] = [0x01, 0x00];
+    const RESULT_OFFSET: [u8; 2] = [0x02, 0x00];
+
+    let mut code = Vec::with_capacity(256);
+    let push1 = |code: &mut Vec<u8>, v: u8| code.extend_from_slice(&[PUSH1, v]);
+    let push2 = |code: &mut Vec<u8>, v: [u8; 2]| { code.push(PUSH2); code.extend_from_slice(&v); };
+    let push20 = |code: &mut Vec<u8>, a: Address| { code.push(PUSH20); code.extend_from_slice(a.as_bytes()); };
+    // mstore(0, selector << 224); mstore(4, executor); staticcall(gas, loan_token, 0, 0x24, ret_offset, 0x20)
+    let balance_of = |code: &mut Vec<u8>, ret_offset: u8| {
+        code.push(PUSH4); code.extend_from_slice(&BALANCE_OF_SELECTOR);
+        push1(code, 0xe0); code.push(SHL);
+        push1(code, 0x00); code.push(MSTORE);
+        push20(code, executor); push1(code, 0x04); code.push(MSTORE);
+        push1(code, 0x20); push1(code, ret_offset); push1(code, 0x24); push1(code, 0x00);
+        push20(code, loan_token); code.push(GAS); code.push(STATICCALL);
+    };
+
+    balance_of(&mut code, 0x80); // [s1]
+    code.push(CALLDATASIZE); push1(&mut code, 0x00); push2(&mut code, CALLDATA_OFFSET); code.push(CALLDATACOPY);
+    code.push(GAS); push1(&mut code, 0xe0); code.push(MSTORE); // g0
+    // call(gas, to, 0, CALLDATA_OFFSET, calldatasize, 0, 0)
+    push1(&mut code, 0x00); push1(&mut code, 0x00); code.push(CALLDATASIZE); push2(&mut code, CALLDATA_OFFSET); push1(&mut code, 0x00);
+    push20(&mut code, to); code.push(GAS); code.push(CALL);
+    code.push(AND); // [s1 & s2]
+    push1(&mut code, 0xe0); code.push(MLOAD); code.push(GAS); code.push(SWAP1); code.push(SUB); // g0 - g1
+    push2(&mut code, [0x02, 0x40]); code.push(MSTORE);
+    balance_of(&mut code, 0xc0);
+    code.push(AND); // [ok]
+    push2(&mut code, RESULT_OFFSET); code.push(MSTORE);
+    push1(&mut code, 0x80); code.push(MLOAD);
+    push1(&mut code, 0xc0); code.push(MLOAD);
+    code.push(SUB); // b1 - b0
+    push2(&mut code, [0x02, 0x20]); code.push(MSTORE);
+    push1(&mut code, 0x60); push2(&mut code, RESULT_OFFSET); code.push(RETURN);
+    Bytes::from(code)
+}
+
+/// Intrinsic gas of a transaction carrying `data` (base cost plus calldata bytes).
+pub fn intrinsic_gas(data: &[u8]) -> u64 {
+    TX_BASE_GAS + data.iter().map(|b| if *b == 0 { 4 } else { 16 }).sum::<u64>()
+}
+
+/// Runs the flash loan transaction (`calldata` sent to `executor`) exactly as it would be submitted, in one `eth_call`
+/// through the flash loan probe (state override, with the probe as the executor's owner so
+/// `executeFlashLoan` accepts it), and reads back the executor's gross profit and the gas used. None
+/// when the provider rejects state overrides.
+pub async fn simulate_via_executor_callstatic<P: JsonRpcClient>(
+    provider: &Provider<P>,
+    loan_token: Address,
+    executor: Address,
+    calldata: &Bytes,
+) -> Result<Option<ExecutorCallStatic>> {
+    let probe_tx: TypedTransaction = TransactionRequest::new().to(FLASH_LOAN_PROBE_ADDRESS).data(calldata.clone()).into();
+    let mut state = spoof::code(FLASH_LOAN_PROBE_ADDRESS, flash_loan_probe_bytecode(loan_token, executor, executor));
+    state.account(executor).store(H256::zero(), H256::from(FLASH_LOAN_PROBE_ADDRESS)); // OWNER_SLOT
+    let out = match provider.call_raw(&probe_tx).state(&state).await {
+        Ok(out) if out.len() >= 96 => out,
+        Ok(out) => return Err(eyre!("Unexpected flash loan probe return data length {}", out.len())),
+        Err(e) => {
+            debug!(error = %e, "State-override eth_call unavailable.");
+            return Ok(None);
+        }
+    };
+    Ok(Some(ExecutorCallStatic {
+        succeeded: !U256::from_big_endian(&out[..32]).is_zero(),
+        gross_profit_wei: u256_to_i256(U256::from_big_endian(&out[32..64]), "executor gross profit")?,
+        gas_used: U256::from_big_endian(&out[64..96]).saturating_add(U256::from(intrinsic_gas(calldata))),
+    }))
+}
+
+/// `gross_profit_wei` less the cost of `gas_used` (buffered as a submission would be) at `gas_price_wei`,
+/// in loan-token wei.
+pub fn net_of_gas(config: &Config, weth_decimals: u8, gross_profit_wei: I256, gas_used: U256, gas_price_wei: U256) -> Result<I256> {
+    let gas_limit = buffered_gas_limit(gas_used, config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit)?;
+    let gas_cost = gas_cost_in_loan_token(gas_cost_wei(gas_limit, gas_price_wei)?, config.gas_token_price_in_loan_token, weth_decimals)?;
+    Ok(gross_profit_wei.saturating_sub(u256_to_i256(gas_cost, "gas cost")?))
+}
+
+/// Checks the two-quoter leg outputs before they are encoded: both must be non-zero, and when the
+/// executor eth_call succeeded, the final output it implies (`repay_wei` plus its gross profit) must be
+/// within `tolerance_bps` of the quoted `out_b` (0 disables the comparison). A stale or zero quote from
+/// either method fails here instead of turning into wrong slippage floors.
+pub fn validate_simulation_consistency(out_a: U256, out_b: U256, repay_wei: U256, callstatic: Option<&ExecutorCallStatic>, tolerance_bps: u64) -> Result<()> {
+    if out_a.is_zero() || out_b.is_zero() {
+        return Err(eyre!("Quoted leg output is zero (out_a {}, out_b {})", out_a, out_b));
+    }
+    let Some(outcome) = callstatic.filter(|outcome| outcome.succeeded && tolerance_bps > 0) else {
+        return Ok(());
+    };
+    let implied_out_b = u256_to_i256(repay_wei, "repay amount")?.saturating_add(outcome.gross_profit_wei);
+    let divergence_bps = if implied_out_b < I256::zero() { u64::MAX } else { quote_divergence_bps(implied_out_b.into_raw(), out_b) };
+    if divergence_bps > tolerance_bps {
+        return Err(eyre!(
+            "Executor eth_call output {} diverges {} bps from the quoted {} (tolerance {} bps)",
+            implied_out_b, divergence_bps, out_b, tolerance_bps
+        ));
+    }
+    Ok(())
+}
+
+/// Priority fee a competitive bid spends on a trade: `bps` of its swap profit (swap output less the
+/// principal, before the flash-loan fee and gas), in loan-token wei. Zero when the swaps do not profit.
+/// The lender's fee and gas are paid on every loan while only `1 - bps` of the swap profit is kept, so
+/// higher competition moves the optimal loan down whenever the lender charges a fee.
+pub fn competitive_priority_fee(swap_profit_wei: I256, bps: u64) -> Result<U256> {
+    if swap_profit_wei <= I256::zero() || bps == 0 {
+        return Ok(U256::zero());
+    }
+    Ok(checked_mul_div(swap_profit_wei.into_raw(), U256::from(bps.min(10_000)), U256::from(10_000u64), "competitive priority fee")?)
+}
+
+/// `fee_wei` (loan-token wei) as a priority fee per gas in gwei of the gas token over `gas_limit`.
+pub fn implied_priority_fee_gwei(config: &Config, weth_decimals: u8, fee_wei: U256, gas_limit: U256) -> f64 {
+    if gas_limit.is_zero() {
+        return 0.0;
+    }
+    let fee_native_wei = match config.gas_token_price_in_loan_token {
+        Some(price) if price > 0.0 => fee_wei.to_f64_lossy() / (price * 10f64.powi(weth_decimals as i32)) * 1e18,
+        _ => fee_wei.to_f64_lossy(),
+    };
+    fee_native_wei / gas_limit.to_f64_lossy() / 1e9
+}
+
+/// Simulates the whole flash loan (both swaps through the executor's own code path) in a single
+/// `eth_call` with a state override, and derives net profit from the executor's balance change and
+/// the measured gas. Falls back to the two-quoter `calculate_net_profit` when the provider rejects
+/// state overrides. A flash loan that reverts (e.g. repayment not covered) is reported as unprofitable.
+#[instrument(skip(app_state, client, route), level = "debug", fields( loan_amount_wei = %amount_in_wei ))]
+pub async fn simulate_flash_loan_bundle(
+    app_state: Arc<AppState>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    route: &RouteCandidate,
+    amount_in_wei: U256,
+    gas_price_gwei: f64,
+) -> Result<NetProfitSimulation> {
+    let config = &app_state.config;
+    let executor = config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for bundle simulation"))?;
+    let salt = U256::from(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_nanos());
+    let (leg_a, leg_b) = route_swap_legs(&app_state, route)?;
+    let user_data = encode_user_data(
+        config.executor_abi_version, leg_a, leg_b, route.token_out, U256::zero(), salt, U256::zero(), U256::zero(),
+    )?;
+    let calldata = execute_flash_loan_calldata(route.token_in, amount_in_wei, user_data);
+
+    let outcome = match simulate_via_executor_callstatic(client.provider(), route.token_in, executor, &calldata).await? {
+        Some(outcome) => outcome,
+        None => {
+            debug!("Falling back to two-quoter simulation.");
+            let net_profit_wei = calculate_net_profit(
+                app_state.clone(), client, route, amount_in_wei, gas_price_gwei,
+                config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit, false,
+            ).await?;
+            return Ok(NetProfitSimulation { net_profit_wei, method: SimulationMethod::TwoQuoter, gas_used: None, competitive_priority_fee_gwei: None });
+        }
+    };
+    if !outcome.succeeded {
+        debug!("Flash loan reverted in bundle simulation. Route unprofitable.");
+        return Ok(NetProfitSimulation { net_profit_wei: I256::min_value(), method: SimulationMethod::FlashLoanBundle, gas_used: None, competitive_priority_fee_gwei: None });
+    }
+    let gas_price_wei: U256 = parse_units(format!("{:.18}", gas_price_gwei), "gwei")?.into();
+    let mut net_profit_wei = net_of_gas(config, app_state.weth_decimals, outcome.gross_profit_wei, outcome.gas_used, gas_price_wei)?;
+    let mut competitive_priority_fee_gwei = None;
+    if config.competition_priority_fee_bps_of_profit > 0 {
+        // The executor's balance change is net of the lender's fee; the bid is on the swaps' profit
+        let swap_profit_wei = outcome.gross_profit_wei.saturating_add(u256_to_i256(flash_loan_fee_wei(&app_state, amount_in_wei)?, "flash loan fee")?);
+        let competitive_fee = competitive_priority_fee(swap_profit_wei, config.competition_priority_fee_bps_of_profit)?;
+        let gas_limit = buffered_gas_limit(outcome.gas_used, config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit)?;
+        net_profit_wei = net_profit_wei.saturating_sub(u256_to_i256(competitive_fee, "competitive priority fee")?);
+        competitive_priority_fee_gwei = Some(implied_priority_fee_gwei(config, app_state.weth_decimals, competitive_fee, gas_limit));
+    }
+    debug!(gross_profit_wei = %outcome.gross_profit_wei, gas_used = %outcome.gas_used, %net_profit_wei, ?competitive_priority_fee_gwei, "Flash loan bundle simulated.");
+    Ok(NetProfitSimulation { net_profit_wei, method: SimulationMethod::FlashLoanBundle, gas_used: Some(outcome.gas_used), competitive_priority_fee_gwei })
+}
+
+/// Where the best sample of a loan search lies in the searched range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchBoundary {
+    Interior,
+    /// Best at the smallest amount (`min_loan_wei`): the range may start too high.
+    Min,
+    /// Best at the largest amount (config or pool-depth max): the range may end too low.
+    Max,
+}
+
+/// Shape of the sampled profit curve, ordered by loan amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfitCurve {
+    Flat,
+    Increasing,
+    Decreasing,
+    /// Rises then falls: a single interior peak, what a well-placed search range looks like.
+    Unimodal,
+    /// More than one peak; the grid may be too coarse to locate the optimum.
+    Irregular,
+}
+
+/// Convergence summary of one optimal-loan search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoanSearchConvergence {
+    /// Simulations that produced a profit figure.
+    pub simulations: usize,
+    pub boundary: SearchBoundary,
+    pub curve: ProfitCurve,
+}
+
+/// Summarises the `(loan amount, net profit)` samples of a search (any order); None without samples.
+/// Ties for the best profit resolve to the smallest amount, as in the search itself.
+pub fn loan_search_convergence(samples: &[(U256, I256)]) -> Option<LoanSearchConvergence> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by_key(|(amount, _)| *amount);
+    let best = sorted.iter().enumerate().fold(None, |best: Option<(usize, I256)>, (i, (_, profit))| match best {
+        Some((_, best_profit)) if best_profit >= *profit => best,
+        _ => Some((i, *profit)),
+    })?.0;
+    let boundary = match best {
+        _ if sorted.len() < 2 => SearchBoundary::Interior,
+        0 => SearchBoundary::Min,
+        i if i == sorted.len() - 1 => SearchBoundary::Max,
+        _ => SearchBoundary::Interior,
+    };
+    // Direction changes of the curve, ignoring flat steps
+    let steps: Vec<bool> = sorted.windows(2).filter(|w| w[0].1 != w[1].1).map(|w| w[1].1 > w[0].1).collect();
+    let turns = steps.windows(2).filter(|w| w[0] != w[1]).count();
+    let curve = match (steps.first(), turns) {
+        (None, _) => ProfitCurve::Flat,
+        (Some(true), 0) => ProfitCurve::Increasing,
+        (Some(false), 0) => ProfitCurve::Decreasing,
+        (Some(true), 1) => ProfitCurve::Unimodal,
+        _ => ProfitCurve::Irregular,
+    };
+    Some(LoanSearchConvergence { simulations: sorted.len(), boundary, curve })
+}
+
+/// Logs a search's convergence and records it in the metrics. A profitable optimum pinned at a range
+/// boundary is warned about: the true optimum probably lies outside the searched range.
+fn record_loan_search_convergence(app_state: &AppState, samples: &[(U256, I256)], attempted: usize, min_loan_wei: U256, max_loan_wei: U256) {
+    Metrics::add(&app_state.metrics.loan_search_simulations, attempted as u64);
+    let Some(convergence) = loan_search_convergence(samples) else { return };
+    let best_profit = samples.iter().map(|(_, profit)| *profit).max().unwrap_or_else(I256::min_value);
+    debug!(simulations = convergence.simulations, failed = attempted - convergence.simulations, boundary = ?convergence.boundary, curve = ?convergence.curve, %best_profit, "Optimal loan search convergence.");
+    match convergence.boundary {
+        SearchBoundary::Min => Metrics::inc(&app_state.metrics.loan_searches_at_min),
+        SearchBoundary::Max => Metrics::inc(&app_state.metrics.loan_searches_at_max),
+        SearchBoundary::Interior => {}
+    }
+    if convergence.curve == ProfitCurve::Irregular {
+        Metrics::inc(&app_state.metrics.loan_searches_irregular);
+    }
+    if convergence.boundary != SearchBoundary::Interior && best_profit > I256::zero() {
+        warn!(boundary = ?convergence.boundary, %min_loan_wei, %max_loan_wei, "Optimal loan pinned at the edge of the search range; widen MIN_LOAN_AMOUNT_WETH/MAX_LOAN_AMOUNT_WETH.");
+    }
+}
+
+/// Runs `probe` for every loan amount, at most `concurrency` (at least one) at a time, and returns the
+/// results in the order of `amounts`. Probes are independent reads, so the order they finish in does not
+/// matter.
+pub async fn probe_loan_amounts<F, Fut>(amounts: Vec<U256>, concurrency: usize, probe: F) -> Vec<(U256, Result<I256>)>
+where
+    F: Fn(U256) -> Fut,
+    Fut: std::future::Future<Output = Result<I256>>,
+{
+    stream::iter(amounts)
+        .map(|amount| {
+            let profit = probe(amount);
+            async move { (amount, profit.await) }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// The most profitable `(loan amount, profit)` sample; ties resolve to the earliest sample.
+pub fn best_probe(samples: &[(U256, I256)]) -> Option<(U256, I256)> {
+    samples.iter().fold(None, |best: Option<(U256, I256)>, &sample| match best {
+        Some((_, best_profit)) if best_profit >= sample.1 => best,
+        _ => Some(sample),
+    })
+}
+
 /// Searches for the optimal flash loan amount for a given route candidate.
-// (Function remains unchanged)
+/// UniV3 legs are simulated locally during the search where possible; the winner is re-quoted on-chain.
+/// With `config.competition_priority_fee_bps_of_profit` the loan maximizes profit after the competitive bid.
 #[allow(clippy::too_many_arguments)]
 #[instrument(skip_all, level = "info", fields( route = ?route ))]
 pub async fn find_optimal_loan_amount(
@@ -223,7 +644,7 @@ pub async fn find_optimal_loan_amount(
     buy_pool_snapshot: Option<&PoolSnapshot>,
     sell_pool_snapshot: Option<&PoolSnapshot>,
     gas_price_gwei: f64,
-) -> Result<Option<(U256, I256)>> {
+) -> Result<LoanSearchResult> {
     info!("Searching optimal loan amount...");
     let config = &app_state.config; let mut best_loan_amount_wei = U256::zero(); let mut max_net_profit_wei = I256::min_value();
     let min_loan_weth = config.min_loan_amount_weth; let config_max_loan_weth = config.max_loan_amount_weth;
@@ -232,23 +653,85 @@ pub async fn find_optimal_loan_amount(
     let dynamic_max_loan_weth = dynamic_max_loan_wei.to_f64_lossy() / 10f64.powi(config.weth_decimals as i32);
     info!( config_max_weth = config_max_loan_weth, dynamic_max_weth = format!("{:.4}", dynamic_max_loan_weth), "Max loan amount limits (WETH)" );
     let effective_max_loan_wei = std::cmp::min(config_max_loan_wei, dynamic_max_loan_wei); let effective_max_loan_weth = dynamic_max_loan_weth.min(config_max_loan_weth);
+    let (effective_max_loan_wei, effective_max_loan_weth) = if config.max_price_impact_bps > 0 && min_loan_wei < effective_max_loan_wei {
+        match max_loan_within_impact(&app_state, route, min_loan_wei, effective_max_loan_wei, config.max_price_impact_bps) {
+            Some(capped_wei) if capped_wei < effective_max_loan_wei => { let capped_weth = capped_wei.to_f64_lossy() / 10f64.powi(config.weth_decimals as i32); info!( max_price_impact_bps = config.max_price_impact_bps, capped_max_weth = format!("{:.4}", capped_weth), "Max loan capped by price impact" ); (capped_wei, capped_weth) }
+            Some(_) => (effective_max_loan_wei, effective_max_loan_weth),
+            None => { info!( max_price_impact_bps = config.max_price_impact_bps, min_weth = min_loan_weth, "Minimum loan already exceeds the price impact limit. Skipping search." ); return Ok(None); }
+        }
+    } else { (effective_max_loan_wei, effective_max_loan_weth) };
     let search_min_weth = min_loan_weth; let search_max_weth = effective_max_loan_weth; let iterations = config.optimal_loan_search_iterations;
     if min_loan_wei >= effective_max_loan_wei || iterations < 1 || search_min_weth <= 0.0 || search_max_weth <= search_min_weth { warn!( min_weth = search_min_weth, eff_max_weth = search_max_weth, iterations, "Invalid or zero-width search range for optimal loan. Skipping search." ); return Ok(None); }
     info!( search_range_weth = format!("{:.4} - {:.4}", search_min_weth, search_max_weth), iterations, "Starting optimal loan search..." );
-    let mut simulation_tasks = vec![];
+    let mut probe_amounts = Vec::with_capacity(iterations as usize);
     for i in 0..iterations {
         let ratio = if iterations <= 1 { 0.5 } else { i as f64 / (iterations - 1) as f64 }; let current_loan_amount_weth = search_min_weth + (search_max_weth - search_min_weth) * ratio;
         let current_loan_amount_wei = match f64_to_wei(current_loan_amount_weth, config.weth_decimals as u32) { Ok(amount) => amount, Err(e) => { warn!(amount_f64=%current_loan_amount_weth, error=?e, "Failed f64_to_wei conversion, skipping amount"); continue; } };
         if current_loan_amount_wei < min_loan_wei || current_loan_amount_wei > effective_max_loan_wei || current_loan_amount_wei.is_zero() { trace!(%current_loan_amount_wei, "Skipping amount outside effective range."); continue; }
-        let task_client = client.clone(); let task_app_state = app_state.clone(); let task_route = route.clone(); let task_gas_limit_buffer = config.gas_limit_buffer_percentage; let task_min_gas_limit = config.min_flashloan_gas_limit;
-        simulation_tasks.push(tokio::spawn(async move { let profit_result = calculate_net_profit( task_app_state, task_client, &task_route, current_loan_amount_wei, gas_price_gwei, task_gas_limit_buffer, task_min_gas_limit, ).await; (current_loan_amount_wei, profit_result) }));
+        probe_amounts.push(current_loan_amount_wei);
     }
-    let results = futures_util::future::join_all(simulation_tasks).await; debug!("Collected {} simulation results.", results.len());
-    for join_result in results { match join_result { Ok((amount_wei, Ok(profit_wei))) => { trace!(loan_amount_wei=%amount_wei, net_profit_wei=%profit_wei, "Profit calculated for amount."); if profit_wei > max_net_profit_wei { max_net_profit_wei = profit_wei; best_loan_amount_wei = amount_wei; } } Ok((amount_wei, Err(e))) => { warn!(loan_amount_wei=%amount_wei, error=?e, "Error calculating profit for specific loan amount"); } Err(e) => { error!(error=?e, "Simulation task failed"); } } }
-    if max_net_profit_wei > I256::zero() { let best_loan_weth_str = format_units(best_loan_amount_wei, config.weth_decimals as i32)?; let profit_weth_str = format_units(max_net_profit_wei.into_raw(), config.weth_decimals as i32)?; info!( optimal_loan_weth = %best_loan_weth_str, max_net_profit_weth = %profit_weth_str, "🎉 Optimal loan amount found!" ); Ok(Some((best_loan_amount_wei, max_net_profit_wei))) }
+    let results = probe_loan_amounts(probe_amounts, config.sim_probe_concurrency, |amount_wei| calculate_net_profit( app_state.clone(), client.clone(), route, amount_wei, gas_price_gwei, config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit, true, )).await;
+    debug!(concurrency = config.sim_probe_concurrency, "Collected {} simulation results.", results.len());
+    let attempted = results.len(); let mut samples = Vec::with_capacity(attempted);
+    for (amount_wei, profit_result) in results { match profit_result { Ok(profit_wei) => { trace!(loan_amount_wei=%amount_wei, net_profit_wei=%profit_wei, "Profit calculated for amount."); samples.push((amount_wei, profit_wei)); } Err(e) => { warn!(loan_amount_wei=%amount_wei, error=?e, "Error calculating profit for specific loan amount"); } } }
+    if let Some((amount_wei, profit_wei)) = best_probe(&samples) { best_loan_amount_wei = amount_wei; max_net_profit_wei = profit_wei; }
+    record_loan_search_convergence(&app_state, &samples, attempted, min_loan_wei, effective_max_loan_wei);
+    // The search used per-leg quotes (and possibly local UniV3 math); confirm the chosen amount end-to-end
+    let mut competitive_priority_fee_gwei = None;
+    if max_net_profit_wei > I256::zero() {
+        let confirmed = simulate_flash_loan_bundle( app_state.clone(), client.clone(), route, best_loan_amount_wei, gas_price_gwei, ).await?;
+        debug!(search_profit_wei = %max_net_profit_wei, confirmed_profit_wei = %confirmed.net_profit_wei, method = ?confirmed.method, gas_used = ?confirmed.gas_used, "Optimal loan profit confirmed.");
+        max_net_profit_wei = confirmed.net_profit_wei;
+        competitive_priority_fee_gwei = confirmed.competitive_priority_fee_gwei;
+    }
+    if max_net_profit_wei > I256::zero() { let best_loan_weth_str = format_units(best_loan_amount_wei, config.weth_decimals as i32)?; let profit_weth_str = format_units(max_net_profit_wei.into_raw(), config.weth_decimals as i32)?; let price_impact_bps = route_price_impact_bps(&app_state, route, best_loan_amount_wei); info!( optimal_loan_weth = %best_loan_weth_str, max_net_profit_weth = %profit_weth_str, ?price_impact_bps, ?competitive_priority_fee_gwei, "🎉 Optimal loan amount found!" ); Ok(Some((best_loan_amount_wei, max_net_profit_wei, price_impact_bps))) }
     else { info!("No profitable loan amount found within the search range."); Ok(None) }
 }
 
+/// Smallest loan in `[min, max]` whose `profit` is positive, to within `tolerance`, or None when
+/// `profit(max)` is not positive. Bisects, so it assumes profit only increases with the loan up to `max`
+/// (true below the optimal loan of a single-peak curve).
+pub async fn break_even_search<F, Fut>(min: U256, max: U256, tolerance: U256, mut profit: F) -> Result<Option<U256>>
+where
+    F: FnMut(U256) -> Fut,
+    Fut: std::future::Future<Output = Result<I256>>,
+{
+    if min > max || profit(max).await? <= I256::zero() {
+        return Ok(None);
+    }
+    if profit(min).await? > I256::zero() {
+        return Ok(Some(min));
+    }
+    // Invariant: profit(lo) <= 0 < profit(hi)
+    let (mut lo, mut hi) = (min, max);
+    while hi - lo > tolerance.max(U256::one()) {
+        let mid = lo + (hi - lo) / 2;
+        if profit(mid).await? > I256::zero() { hi = mid; } else { lo = mid; }
+    }
+    Ok(Some(hi))
+}
+
+/// Smallest loan at which `route` nets a profit after gas, searched between `config.min_loan_amount_weth`
+/// and `max_loan_wei` (pass the optimal loan) to within 0.0001 WETH with the same `calculate_net_profit`
+/// as the optimal-loan search. None when the route is not profitable at `max_loan_wei`.
+#[instrument(skip_all, level = "debug", fields(route = %route.id()))]
+pub async fn find_break_even_loan(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: Arc<AppState>,
+    route: &RouteCandidate,
+    max_loan_wei: U256,
+    gas_price_gwei: f64,
+) -> Result<Option<U256>> {
+    let config = &app_state.config;
+    let min_loan_wei = f64_to_wei(config.min_loan_amount_weth, config.weth_decimals as u32)?;
+    let tolerance = U256::exp10((config.weth_decimals as usize).saturating_sub(4));
+    let break_even = break_even_search(min_loan_wei, max_loan_wei, tolerance, |amount| {
+        calculate_net_profit(app_state.clone(), client.clone(), route, amount, gas_price_gwei, config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit, true)
+    }).await?;
+    debug!(?break_even, %max_loan_wei, "Break-even loan search done.");
+    Ok(break_even)
+}
+
 /// Calculates a dynamic maximum loan amount based primarily on V2/Aero pool reserves.
 // (Function remains unchanged)
 #[instrument(level="debug", skip(buy_pool_snapshot))]
@@ -266,7 +749,7 @@ fn calculate_dynamic_max_loan(
             DexType::VelodromeV2 | DexType::Aerodrome => {
                 let reserve_option = if buy_snap.token0 == loan_token { buy_snap.reserve0 } else if buy_snap.token1 == loan_token { buy_snap.reserve1 } else { None };
                 if let Some(reserve) = reserve_option {
-                    if !reserve.is_zero() { let limit_wei = reserve * U256::from(V2_RESERVE_PERCENTAGE_LIMIT) / U256::from(100); dynamic_max_wei = std::cmp::min(dynamic_max_wei, limit_wei); trace!( pool = %buy_snap.pool_address, dex = %buy_snap.dex_type.to_string(), reserve = %reserve, limit_pct = V2_RESERVE_PERCENTAGE_LIMIT, limit_wei = %limit_wei, "Applied V2/Aero depth limit based on loan token reserve." ); }
+                    if !reserve.is_zero() { let limit_wei = checked_mul_div(reserve, U256::from(V2_RESERVE_PERCENTAGE_LIMIT), U256::from(100), "V2 reserve limit").unwrap_or(dynamic_max_wei); dynamic_max_wei = std::cmp::min(dynamic_max_wei, limit_wei); trace!( pool = %buy_snap.pool_address, dex = %buy_snap.dex_type.to_string(), reserve = %reserve, limit_pct = V2_RESERVE_PERCENTAGE_LIMIT, limit_wei = %limit_wei, "Applied V2/Aero depth limit based on loan token reserve." ); }
                     else { warn!(pool=%buy_snap.pool_address, dex=%buy_snap.dex_type.to_string(), "Loan token reserve is zero, cannot borrow."); dynamic_max_wei = U256::zero(); }
                 } else { error!(pool=%buy_snap.pool_address, %loan_token, token0=%buy_snap.token0, token1=%buy_snap.token1, "Loan token not found in V2 pool snapshot reserves!"); dynamic_max_wei = U256::zero(); }
             }
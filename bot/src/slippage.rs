@@ -0,0 +1,116 @@
+// bot/src/slippage.rs
+//! Realized vs quoted swap outputs of confirmed arbs, per DEX.
+//!
+//! The pools' `Swap` events are read back from the arb's receipt and each leg's actual output is compared
+//! with the quote it was submitted with (detection's leg quotes, see `sim_cache::LegQuoteCache`, so after any
+//! correction already applied).
+//! Errors are kept per DEX over the same rolling window as the profit bias (see `accuracy`), exported as
+//! metrics and written to the confirmed audit record. A persistent Velo/Aero shortfall is what
+//! `config.velo_slippage_correction_bps` is meant to absorb in `simulate_swap`.
+
+use crate::accuracy::{prediction_error_bps, ProfitAccuracy};
+use crate::bindings::{uniswap_v3_pool::SwapFilter as UniV3SwapFilter, velodrome_v2_pool::SwapFilter as VeloSwapFilter};
+use crate::metrics::Metrics;
+use crate::path_optimizer::RouteCandidate;
+use crate::state::{AppState, DexType};
+use crate::{UNI_V3_SWAP_TOPIC, VELO_AERO_SWAP_TOPIC};
+use dashmap::DashMap;
+use ethers::abi::RawLog;
+use ethers::contract::EthLogDecode;
+use ethers::types::{Address, TransactionReceipt, I256, U256};
+use tracing::{debug, info};
+
+/// Quoted and realized output of one swap leg of a confirmed arb.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegSlippage {
+    pub pool: Address,
+    pub dex_type: DexType,
+    pub quoted_out: U256,
+    pub realized_out: U256,
+}
+
+impl LegSlippage {
+    /// `(quoted - realized) / quoted` in bps; positive when the leg delivered less than quoted.
+    pub fn error_bps(&self) -> Option<i64> {
+        prediction_error_bps(I256::try_from(self.quoted_out).ok()?, I256::try_from(self.realized_out).ok()?)
+    }
+}
+
+/// Rolling output errors (bps) per DEX.
+#[derive(Debug, Default)]
+pub struct SlippageTracker {
+    windows: DashMap<DexType, ProfitAccuracy>,
+}
+
+impl SlippageTracker {
+    /// Adds one sample for `dex_type` and returns its window mean and sample count.
+    pub fn record(&self, dex_type: DexType, error_bps: i64) -> (i64, usize) {
+        self.windows.entry(dex_type).or_default().record(error_bps)
+    }
+}
+
+/// Output of `pool`'s swap in `receipt`, in its token0 when `out_is_token0` (else token1). None when the
+/// receipt has no decodable `Swap` log from the pool.
+pub fn realized_leg_output(receipt: &TransactionReceipt, pool: Address, dex_type: DexType, out_is_token0: bool) -> Option<U256> {
+    let log = receipt.logs.iter().find(|log| log.address == pool && log.topics.first().is_some_and(|topic| match dex_type {
+        DexType::UniswapV3 => *topic == *UNI_V3_SWAP_TOPIC,
+        DexType::VelodromeV2 | DexType::Aerodrome => *topic == *VELO_AERO_SWAP_TOPIC,
+        DexType::Unknown => false,
+    }))?;
+    let raw_log: RawLog = log.clone().into();
+    match dex_type {
+        // Pool deltas: the output leaves the pool, so it is the negative amount
+        DexType::UniswapV3 => {
+            let swap = <UniV3SwapFilter as EthLogDecode>::decode_log(&raw_log).ok()?;
+            let delta = if out_is_token0 { swap.amount_0 } else { swap.amount_1 };
+            (delta < I256::zero()).then(|| delta.unsigned_abs())
+        }
+        DexType::VelodromeV2 | DexType::Aerodrome => {
+            let swap = <VeloSwapFilter as EthLogDecode>::decode_log(&raw_log).ok()?;
+            Some(if out_is_token0 { swap.amount_0_out } else { swap.amount_1_out })
+        }
+        DexType::Unknown => None,
+    }
+}
+
+/// Compares both legs of a confirmed `route` with their quotes, records the errors per DEX (metrics and
+/// rolling window) and returns the legs that could be read from `receipt`, for the audit record.
+pub fn record_leg_slippage(app_state: &AppState, route: &RouteCandidate, receipt: &TransactionReceipt, quoted_out_a: U256, quoted_out_b: U256) -> Vec<LegSlippage> {
+    // Leg A sells the loan token for `token_out`, leg B sells it back
+    let legs = [
+        (route.buy_pool_addr, route.buy_dex_type, route.token_out, quoted_out_a),
+        (route.sell_pool_addr, route.sell_dex_type, route.token_in, quoted_out_b),
+    ];
+    let mut recorded = Vec::with_capacity(legs.len());
+    for (pool, dex_type, token_out, quoted_out) in legs {
+        let Some(out_is_token0) = app_state.pool_states.get(&pool).map(|state| state.token0 == token_out) else { continue };
+        let Some(realized_out) = realized_leg_output(receipt, pool, dex_type, out_is_token0) else {
+            debug!(%pool, ?dex_type, "No Swap log for the leg in the receipt; slippage not recorded.");
+            continue;
+        };
+        let leg = LegSlippage { pool, dex_type, quoted_out, realized_out };
+        if let Some(error_bps) = leg.error_bps() {
+            let (mean_bps, samples) = app_state.slippage_tracker.record(dex_type, error_bps);
+            let gauge = match dex_type {
+                DexType::UniswapV3 => &app_state.metrics.uni_v3_output_shortfall_bps,
+                DexType::VelodromeV2 => &app_state.metrics.velo_output_shortfall_bps,
+                DexType::Aerodrome => &app_state.metrics.aero_output_shortfall_bps,
+                DexType::Unknown => continue,
+            };
+            Metrics::set(gauge, mean_bps.max(0) as u64);
+            info!(%pool, ?dex_type, %quoted_out, %realized_out, error_bps, mean_bps, samples, "📐 Leg output vs quote.");
+        }
+        recorded.push(leg);
+    }
+    recorded
+}
+
+/// `quoted_out` reduced by `correction_bps` (capped at 10000), rounded down.
+pub fn corrected_output(quoted_out: U256, correction_bps: u64) -> U256 {
+    if correction_bps == 0 {
+        return quoted_out;
+    }
+    let keep = U256::from(10_000 - correction_bps.min(10_000));
+    quoted_out.checked_mul(keep).map_or_else(|| quoted_out / U256::from(10_000u64) * keep, |scaled| scaled / U256::from(10_000u64))
+}
+// END OF FILE: bot/src/slippage.rs
@@ -2,24 +2,52 @@
 
 // --- Imports ---
 use crate::bindings::{AerodromePool, UniswapV3Pool, VelodromeV2Pool};
+use crate::accuracy::ProfitAccuracy;
+use crate::audit::AuditLog;
 use crate::config::Config;
+use crate::cooldown::PoolCooldowns;
+use crate::gas::TxType;
+use crate::gas_learning::GasUsageStats;
+use crate::latency::{adaptive_timeout, EndpointLatencies};
+use crate::metrics::Metrics;
+use crate::path_optimizer::TradingPair;
+use crate::retry::with_retries;
+use crate::seen_logs::SeenLogs;
+use crate::sim_cache::{LegQuoteCache, SimulationCache};
+use crate::slippage::SlippageTracker;
+use crate::throttle::SubmissionThrottle;
+use crate::exposure::InflightExposure;
+use crate::token_safety::TokenDecimals;
+use crate::twap::fetch_twap_tick;
+use crate::univ3_math::{TickLiquidity, TickWindow};
+use crate::utils::{u256_to_i256, ToF64Lossy};
+use crate::warmup::SubmissionWarmup;
+use futures_util::future::{join_all, try_join_all};
+use serde::{Deserialize, Serialize};
 use dashmap::DashMap;
 use ethers::{
     prelude::*,
-    types::{Address, U256, U64},
+    types::{Address, I256, U256, U64},
 };
 use eyre::{eyre, Result, WrapErr};
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::{atomic::{AtomicU64, AtomicU8, Ordering}, Arc}};
+use tokio::sync::Semaphore;
 use tokio::time::{timeout, Duration};
-use tracing::{error, info, instrument, trace, warn};
+use tracing::{debug, error, info, instrument, trace, warn};
+
+// Number of recent blocks whose timestamps are kept in `AppState::block_timestamps`.
+const BLOCK_TIMESTAMP_CACHE_DEPTH: u64 = 64;
+// Maximum concurrent pool re-fetches during a full snapshot refresh sweep.
+const SNAPSHOT_REFRESH_CONCURRENCY: usize = 8;
 
 // --- Enums / Structs ---
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum DexType {
     UniswapV3,
     VelodromeV2,
     Aerodrome,
     #[allow(dead_code)] // Allow dead code for this variant as it's for robustness
+    #[default]
     Unknown,
 }
 impl DexType {
@@ -44,7 +72,7 @@ impl FromStr for DexType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolState {
     pub pool_address: Address,
     pub dex_type: DexType,
@@ -52,11 +80,13 @@ pub struct PoolState {
     #[allow(dead_code)] // Allow dead code for this field, kept for context/future use
     pub token1: Address,
     pub uni_fee: Option<u32>,
+    pub tick_spacing: Option<i32>, // UniV3 tickSpacing() (None for Velo-style pools)
     pub velo_stable: Option<bool>,
     pub t0_is_weth: Option<bool>, // Flag indicating if token0 is WETH
     pub factory: Address,
+    pub created_block: Option<U64>, // Block of the PoolCreated log when created while running (None for enumerated pools)
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PoolSnapshot {
     pub pool_address: Address,
     pub dex_type: DexType,
@@ -66,16 +96,116 @@ pub struct PoolSnapshot {
     pub reserve1: Option<U256>,
     pub sqrt_price_x96: Option<U256>,
     pub tick: Option<i32>,
+    pub liquidity: Option<u128>, // UniV3 in-range liquidity (enables local swap math)
+    pub twap_tick: Option<i32>, // UniV3 time-weighted average tick over `config.twap_window_secs` (see `twap`)
     pub last_update_block: Option<U64>,
+    pub last_update_timestamp: Option<u64>, // Block timestamp (unix secs) of `last_update_block`
+}
+
+/// Change from one snapshot of a pool to a later one (`newer - older`). A field is None when either
+/// snapshot lacks it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub sqrt_price_x96_delta: Option<I256>,
+    pub tick_delta: Option<i64>,
+    pub reserve0_delta: Option<I256>,
+    pub reserve1_delta: Option<I256>,
+    pub liquidity_delta: Option<i128>,
+    pub blocks_elapsed: Option<u64>,
+}
+
+impl SnapshotDiff {
+    /// True when no price, tick, reserve or liquidity field changed.
+    pub fn is_unchanged(&self) -> bool {
+        [self.sqrt_price_x96_delta, self.reserve0_delta, self.reserve1_delta].iter().all(|d| d.is_none_or(|d| d.is_zero()))
+            && self.tick_delta.is_none_or(|d| d == 0)
+            && self.liquidity_delta.is_none_or(|d| d == 0)
+    }
+}
+
+fn signed_delta(older: Option<U256>, newer: Option<U256>) -> Option<I256> {
+    let (older, newer) = (older?, newer?);
+    if newer >= older {
+        u256_to_i256(newer - older, "snapshot delta").ok()
+    } else {
+        u256_to_i256(older - newer, "snapshot delta").ok().map(|d| -d)
+    }
+}
+
+/// Debug-logs how a snapshot changed across an update (enabled by `config.log_snapshot_diffs`).
+pub fn log_snapshot_diff(before: &PoolSnapshot, after: &PoolSnapshot) {
+    let diff = before.diff(after);
+    debug!(pool = %after.pool_address, dex = ?after.dex_type, unchanged = diff.is_unchanged(), ?diff, "Snapshot diff.");
+}
+
+impl PoolSnapshot {
+    /// Deltas from `self` to the later snapshot `newer`.
+    pub fn diff(&self, newer: &Self) -> SnapshotDiff {
+        SnapshotDiff {
+            sqrt_price_x96_delta: signed_delta(self.sqrt_price_x96, newer.sqrt_price_x96),
+            tick_delta: self.tick.zip(newer.tick).map(|(a, b)| b as i64 - a as i64),
+            reserve0_delta: signed_delta(self.reserve0, newer.reserve0),
+            reserve1_delta: signed_delta(self.reserve1, newer.reserve1),
+            liquidity_delta: self.liquidity.zip(newer.liquidity).and_then(|(a, b)| i128::try_from(b).ok()?.checked_sub(i128::try_from(a).ok()?)),
+            blocks_elapsed: self.last_update_block.zip(newer.last_update_block).map(|(a, b)| b.as_u64().saturating_sub(a.as_u64())),
+        }
+    }
+}
+
+/// Why the circuit breaker is tripped. Each source sets and clears only its own reason, and the bot stays
+/// paused while any reason is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// `pause` from the admin interface, cleared by `resume`.
+    Admin,
+    /// Signer gas balance below `config.min_gas_balance_wei` (see `balance`).
+    LowGasBalance,
+    /// Submitted transactions are not landing (see `nonce_watch`).
+    NonceDivergence,
+}
+
+impl PauseReason {
+    pub const ALL: [PauseReason; 3] = [PauseReason::Admin, PauseReason::LowGasBalance, PauseReason::NonceDivergence];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
 }
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub config: Config,
     pub pool_states: Arc<DashMap<Address, PoolState>>, // Detailed, less frequently updated state
     pub pool_snapshots: Arc<DashMap<Address, PoolSnapshot>>, // Minimal, frequently updated state (hot-cache)
+    pub block_timestamps: Arc<DashMap<U64, u64>>, // Recent block number -> block timestamp (unix secs)
+    pub fee_on_transfer_tokens: Arc<DashMap<Address, bool>>, // Token -> flagged by the token_safety probe (checked once)
+    pub token_decimals: Arc<DashMap<Address, TokenDecimals>>, // Token -> decimals() (or the assumed default), fetched once
+    pub pending_reserve_jumps: Arc<DashMap<Address, (U256, U256)>>, // Pool -> last rejected reserve jump, accepted once a reading confirms it
+    pub submitted_salts: Arc<DashMap<U256, U64>>, // Salts submitted this session -> block at submission (pruned after `salt_expiry_blocks`)
+    pub weth_usd_prices: Arc<DashMap<U64, f64>>, // Block number -> Chainlink WETH/USD price (latest block only, see `oracle`)
+    pub pool_cooldowns: Arc<PoolCooldowns>, // Pools suppressed after reverted submissions (see `cooldown`)
+    pub simulation_cache: Arc<SimulationCache>, // Optimal loan searches of the current block (see `sim_cache`)
+    pub univ3_ticks: Arc<DashMap<Address, TickWindow>>, // UniV3 pool -> initialized ticks around its current tick, kept current by Mint/Burn (see `univ3_math`)
+    pub leg_quotes: Arc<LegQuoteCache>, // Per-leg outputs quoted by detection, reused for submission floors
+    pub seen_logs: Arc<SeenLogs>, // Recently processed (tx hash, log index) pairs, bounded by `seen_log_cache_size`
+    pub submission_throttle: Arc<SubmissionThrottle>, // Time of the last submission, globally and per pool (see `throttle`)
+    pub inflight_exposure: Arc<InflightExposure>, // Loan total of unresolved submissions (see `exposure`)
+    pub submission_warmup: Arc<SubmissionWarmup>, // Startup hold on submissions; its clock starts with the state (see `warmup`)
+    pub metrics: Arc<Metrics>,
+    pub rpc_latency: Arc<EndpointLatencies>, // Rolling fetch latency per RPC endpoint (drives the adaptive fetch timeout)
+    pub profit_accuracy: Arc<ProfitAccuracy>, // Predicted vs realized profit errors of recent confirmed arbs
+    pub gas_usage_stats: Arc<GasUsageStats>, // Receipt gas used per route shape, the learned gas limits (see `gas_learning`)
+    pub slippage_tracker: Arc<SlippageTracker>, // Quoted vs realized leg outputs of recent confirmed arbs, per DEX (see `slippage`)
+    pub paused: Arc<AtomicU8>, // Circuit breaker: bitset of active `PauseReason`s; while any is set, no new opportunities are evaluated or submitted
+    pub balancer_flash_loan_fee: Arc<AtomicU64>, // Vault flash loan fee as an 18-decimal fraction (see `flash_loan`); 0 until `main.rs` reads it
+    pub tx_type: TxType, // Submission envelope; EIP-1559 until `main.rs` sets the detected type
+    pub audit: AuditLog, // Opportunity audit trail; disabled until `main.rs` opens `config.audit_log_path`
+    pub replay_block: Option<U64>, // Block quotes and gas estimates are pinned to (`replay`); None for the latest block
+    pub pool_admission: Arc<std::sync::Mutex<()>>, // Serializes cap checks in `insert_pool` (not its depth search) so concurrent fetches cannot overshoot `max_monitored_pools`
     // Commonly used config values cached for quick access
     pub weth_address: Address,
     pub usdc_address: Address,
+    pub quote_tokens: Vec<Address>, // `usdc_address` followed by `config.usdc_addresses`, deduplicated
     pub weth_decimals: u8,
     pub usdc_decimals: u8,
     // FIX: Remove unused fields causing dead_code warnings
@@ -86,11 +216,24 @@ pub struct AppState {
 
 // --- AppState Impl ---
 impl AppState {
+    /// The single constructor, used by `main.rs` and all tests (`Config::default()` works offline).
+    /// Only `config` is required; token addresses/decimals are copied from it, every cache
+    /// (pool states, snapshots, block timestamps, fee-on-transfer flags, oracle prices, submitted salts, pool cooldowns, simulation results, seen logs, submission times) starts empty, the submission warmup starts now, metrics
+    /// start at zero, the circuit breaker starts un-paused, `tx_type` defaults to EIP-1559 and the audit log is disabled. RPC clients and the nonce manager
+    /// are not part of the state and are passed to handlers separately.
     pub fn new(config: Config) -> Self {
+        let seen_logs = Arc::new(SeenLogs::new(config.seen_log_cache_size));
+        let mut quote_tokens = vec![config.usdc_address];
+        for token in &config.usdc_addresses {
+            if !token.is_zero() && !quote_tokens.contains(token) {
+                quote_tokens.push(*token);
+            }
+        }
         Self {
             // Cache frequently accessed config values
             weth_address: config.weth_address,
             usdc_address: config.usdc_address,
+            quote_tokens,
             weth_decimals: config.weth_decimals,
             usdc_decimals: config.usdc_decimals,
             // FIX: Remove initialization of unused fields
@@ -102,9 +245,243 @@ impl AppState {
             // Initialize state maps
             pool_states: Default::default(),
             pool_snapshots: Default::default(),
+            block_timestamps: Default::default(),
+            fee_on_transfer_tokens: Default::default(),
+            token_decimals: Default::default(),
+            pending_reserve_jumps: Default::default(),
+            weth_usd_prices: Default::default(),
+            submitted_salts: Default::default(),
+            pool_cooldowns: Default::default(),
+            simulation_cache: Default::default(),
+            univ3_ticks: Default::default(),
+            leg_quotes: Default::default(),
+            seen_logs,
+            submission_throttle: Default::default(),
+            inflight_exposure: Default::default(),
+            submission_warmup: Default::default(),
+            metrics: Default::default(),
+            rpc_latency: Default::default(),
+            profit_accuracy: Default::default(),
+            gas_usage_stats: Default::default(),
+            slippage_tracker: Default::default(),
+            paused: Default::default(),
+            balancer_flash_loan_fee: Default::default(),
+            tx_type: TxType::default(),
+            audit: AuditLog::disabled(),
+            replay_block: None,
+            pool_admission: Default::default(),
         }
     }
 
+    /// Offline state for unit tests: `Config::default()` plus placeholder WETH (`0x…eeee`, 18 decimals) and
+    /// USDC (`0x…cccc`, 6 decimals) so `target_pair` resolves. Performs no I/O.
+    pub fn new_for_test() -> Self {
+        Self::new(Config {
+            weth_address: Address::from_low_u64_be(0xeeee),
+            usdc_address: Address::from_low_u64_be(0xcccc),
+            weth_decimals: 18,
+            usdc_decimals: 6,
+            ..Config::default()
+        })
+    }
+
+    /// Caches a fetched pool, enforcing `config.max_monitored_pools` (0 = unlimited). At the cap a new pool
+    /// replaces the cached pool with the least WETH depth if it is deeper, and is refused otherwise (pools
+    /// without WETH count as zero depth). Returns whether the pool was cached. Refreshes of cached pools
+    /// always go through. The shallowest pool is searched for before `pool_admission` is taken, so the lock
+    /// is only held for O(1) checks; a search overtaken by a concurrent insert is repeated.
+    pub fn insert_pool(&self, state: PoolState, snapshot: PoolSnapshot) -> bool {
+        let cap = self.config.max_monitored_pools;
+        let pool_addr = state.pool_address;
+        let at_cap = || cap > 0 && !self.pool_states.contains_key(&pool_addr) && self.pool_states.len() >= cap;
+        let depth = |sn: &PoolSnapshot| {
+            if sn.token0 == self.weth_address || sn.token1 == self.weth_address {
+                crate::path_optimizer::loan_token_depth(sn, self.weth_address, self.weth_decimals)
+            } else {
+                0.0
+            }
+        };
+        let new_depth = depth(&snapshot);
+        loop {
+            let shallowest = at_cap().then(|| {
+                self.pool_snapshots.iter()
+                    .map(|entry| (*entry.key(), depth(entry.value())))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+            });
+            let _admission = self.pool_admission.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if at_cap() {
+                // Reached the cap after the search was skipped: search again
+                let Some(shallowest) = shallowest else { continue };
+                if self.metrics.pools_evicted_at_cap.load(Ordering::Relaxed) + self.metrics.pools_refused_at_cap.load(Ordering::Relaxed) == 0 {
+                    warn!(cap, "MAX_MONITORED_POOLS reached; only pools deeper than the shallowest cached pool are added from now on.");
+                }
+                match shallowest {
+                    Some((evicted, _)) if !self.pool_states.contains_key(&evicted) => continue, // Evicted by a concurrent insert
+                    Some((evicted, evicted_depth)) if new_depth > evicted_depth => {
+                        self.pool_states.remove(&evicted);
+                        self.pool_snapshots.remove(&evicted);
+                        self.univ3_ticks.remove(&evicted);
+                        Metrics::inc(&self.metrics.pools_evicted_at_cap);
+                        debug!(pool = %pool_addr, depth = new_depth, %evicted, evicted_depth, "Pool cap reached, evicted the least liquid pool.");
+                    }
+                    _ => {
+                        Metrics::inc(&self.metrics.pools_refused_at_cap);
+                        debug!(pool = %pool_addr, depth = new_depth, "Pool cap reached, not caching a pool shallower than every cached pool.");
+                        return false;
+                    }
+                }
+            }
+            self.pool_states.insert(pool_addr, state);
+            self.pool_snapshots.insert(pool_addr, snapshot);
+            return true;
+        }
+    }
+
+    /// Per-call timeout for pool-state fetches on the read endpoint (see `latency`); also updates its gauge.
+    pub fn fetch_timeout(&self) -> Duration {
+        let config = &self.config;
+        let fetch_timeout = adaptive_timeout(
+            self.rpc_latency.p95(config.read_endpoint()),
+            Duration::from_secs(config.fetch_timeout_secs.unwrap_or(15)),
+            Duration::from_secs(config.min_fetch_timeout_secs),
+            Duration::from_secs(config.max_fetch_timeout_secs),
+        );
+        Metrics::set(&self.metrics.adaptive_fetch_timeout_ms, fetch_timeout.as_millis() as u64);
+        fetch_timeout
+    }
+
+    /// Runs a read-endpoint fetch under `fetch_timeout` and records its latency (a timeout records the full
+    /// timeout, so a slowing endpoint raises its own timeout).
+    pub async fn timed_fetch<F: std::future::Future>(&self, fetch: F) -> std::result::Result<F::Output, tokio::time::error::Elapsed> {
+        let timeout_dur = self.fetch_timeout();
+        let started = std::time::Instant::now();
+        let result = timeout(timeout_dur, fetch).await;
+        self.rpc_latency.record(self.config.read_endpoint(), started.elapsed().min(timeout_dur));
+        result
+    }
+
+    /// Records the timestamp of a new block and prunes entries older than the cache depth.
+    pub fn record_block_timestamp(&self, block_number: U64, timestamp: u64) {
+        self.block_timestamps.insert(block_number, timestamp);
+        let cutoff = block_number.as_u64().saturating_sub(BLOCK_TIMESTAMP_CACHE_DEPTH);
+        self.block_timestamps.retain(|n, _| n.as_u64() >= cutoff);
+    }
+
+    /// Reserves `salt` for a submission at `block_number`. Returns false when the salt was already submitted
+    /// this session (the executor's salt guard would revert it), so the caller can skip the attempt.
+    pub fn claim_salt(&self, salt: U256, block_number: U64) -> bool {
+        match self.submitted_salts.entry(salt) {
+            dashmap::mapref::entry::Entry::Occupied(_) => false,
+            dashmap::mapref::entry::Entry::Vacant(slot) => { slot.insert(block_number); true }
+        }
+    }
+
+    /// Forgets salts submitted more than `config.salt_expiry_blocks` before `block_number`.
+    pub fn prune_submitted_salts(&self, block_number: U64) {
+        let cutoff = block_number.as_u64().saturating_sub(self.config.salt_expiry_blocks);
+        self.submitted_salts.retain(|_, submitted_at| submitted_at.as_u64() >= cutoff);
+    }
+
+    /// Returns the timestamp of `block_number`, from the cache when possible.
+    /// On a miss the block is fetched once and cached, so every update in the same block shares one `get_block`.
+    pub async fn block_timestamp<M: Middleware>(&self, block_number: U64, client: &M) -> Option<u64> {
+        if let Some(ts) = self.block_timestamps.get(&block_number) {
+            return Some(*ts);
+        }
+        match client.get_block(block_number).await {
+            Ok(Some(block)) => {
+                let ts = block.timestamp.as_u64();
+                self.record_block_timestamp(block_number, ts);
+                Some(ts)
+            }
+            Ok(None) => { debug!(block = %block_number, "Block not found while fetching timestamp."); None }
+            Err(e) => { warn!(block = %block_number, error = %e, "Failed to fetch block timestamp."); None }
+        }
+    }
+
+    /// Returns the age in seconds of the stalest snapshot that carries a block timestamp, relative to `now_secs`.
+    pub fn max_snapshot_age_secs(&self, now_secs: u64) -> Option<u64> {
+        self.pool_snapshots
+            .iter()
+            .filter_map(|s| s.last_update_timestamp)
+            .min()
+            .map(|oldest| now_secs.saturating_sub(oldest))
+    }
+
+    /// Writes fresh Velo/Aero reserves into the pool's snapshot (the canonical
+    /// `reserve0`/`reserve1`/`last_update_block` fields). Returns false if the pool has no snapshot or the
+    /// reserves were rejected by `reserve_update_rejection` (the prior snapshot is kept).
+    pub fn update_pool_snapshot(&self, pool: Address, reserve0: U256, reserve1: U256, block_number: Option<U64>, block_timestamp: Option<u64>) -> bool {
+        match self.pool_snapshots.get_mut(&pool) {
+            Some(mut snapshot) => {
+                if self.reject_reserves(pool, &snapshot, reserve0, reserve1) {
+                    return false;
+                }
+                let before = self.config.log_snapshot_diffs.then(|| snapshot.clone());
+                snapshot.reserve0 = Some(reserve0);
+                snapshot.reserve1 = Some(reserve1);
+                snapshot.last_update_block = block_number;
+                snapshot.last_update_timestamp = block_timestamp;
+                if let Some(before) = before {
+                    log_snapshot_diff(&before, &snapshot);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether fresh reserves for `pool` fail `reserve_update_rejection` against `previous`. Rejections are
+    /// logged and counted; the caller keeps `previous`. A price jump is only held back until a second
+    /// reading agrees with it (within the same limit): the snapshot never moves on rejection, so judging
+    /// every reading against it alone would freeze a pool whose price really moved.
+    fn reject_reserves(&self, pool: Address, previous: &PoolSnapshot, reserve0: U256, reserve1: U256) -> bool {
+        let max_jump = self.config.max_price_jump_bps;
+        let Some(reason) = reserve_update_rejection(previous, reserve0, reserve1, max_jump) else {
+            self.pending_reserve_jumps.remove(&pool);
+            return false;
+        };
+        if !reserve0.is_zero() && !reserve1.is_zero() {
+            let pending = self.pending_reserve_jumps.insert(pool, (reserve0, reserve1));
+            let confirmed = pending.is_some_and(|(pending0, pending1)| {
+                let pending = PoolSnapshot { reserve0: Some(pending0), reserve1: Some(pending1), ..previous.clone() };
+                reserve_update_rejection(&pending, reserve0, reserve1, max_jump).is_none()
+            });
+            if confirmed {
+                self.pending_reserve_jumps.remove(&pool);
+                info!(%pool, %reason, old = ?(previous.reserve0, previous.reserve1), new = ?(reserve0, reserve1), "Reserve jump confirmed by a second reading, accepting it.");
+                return false;
+            }
+        }
+        Metrics::inc(&self.metrics.snapshot_updates_rejected);
+        warn!(%pool, %reason, old = ?(previous.reserve0, previous.reserve1), new = ?(reserve0, reserve1), "Implausible reserves rejected, keeping the prior snapshot.");
+        true
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed) != 0
+    }
+
+    /// The reasons the circuit breaker is currently tripped for.
+    pub fn pause_reasons(&self) -> Vec<PauseReason> {
+        let bits = self.paused.load(Ordering::Relaxed);
+        PauseReason::ALL.into_iter().filter(|reason| bits & reason.bit() != 0).collect()
+    }
+
+    /// Trips (`true`) or resets (`false`) the circuit breaker for `reason` only; it stays tripped while any
+    /// other reason is set. Returns whether `reason` was set before.
+    pub fn set_paused(&self, reason: PauseReason, paused: bool) -> bool {
+        let bit = reason.bit();
+        let previous = if paused { self.paused.fetch_or(bit, Ordering::Relaxed) } else { self.paused.fetch_and(!bit, Ordering::Relaxed) };
+        let current = if paused { previous | bit } else { previous & !bit };
+        if (previous != 0) != (current != 0) {
+            warn!(paused, ?reason, "Circuit breaker state changed.");
+        } else if previous != current {
+            info!(paused, ?reason, still_paused_for = ?self.pause_reasons(), "Circuit breaker reason changed.");
+        }
+        previous & bit != 0
+    }
+
     /// Returns the target token pair (WETH, USDC) sorted by address.
     /// Returns None if addresses are not configured (zero address).
     pub fn target_pair(&self) -> Option<(Address, Address)> {
@@ -120,61 +497,202 @@ impl AppState {
             }
         }
     }
+
+    /// WETH and the quote tokens it is paired with (see `is_target_pair_option`). None under the same
+    /// conditions as `target_pair`.
+    pub fn target_tokens(&self) -> Option<(Address, &[Address])> {
+        self.target_pair().map(|_| (self.weth_address, self.quote_tokens.as_slice()))
+    }
+
+    /// Pairs routes are searched within: WETH against each quote token, in `quote_tokens` order, each with
+    /// its own `decimals_of`. Empty under the same conditions as `target_pair`.
+    pub fn trading_pairs(&self) -> Vec<TradingPair> {
+        match self.target_pair() {
+            Some(_) => TradingPair::with_quotes(self.weth_address, self.quote_tokens.iter().copied(), self.weth_decimals, self.usdc_decimals)
+                .into_iter()
+                .filter_map(|pair| Some(TradingPair { base_decimals: self.decimals_of(pair.base)?, quote_decimals: self.decimals_of(pair.quote)?, ..pair }))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Decimals of `token` for price and amount conversions: its fetched `decimals()`, else the configured
+    /// WETH/quote decimals, else the default it was assumed with (`config.default_token_decimals`). None
+    /// for any other token whose decimals were never read.
+    pub fn decimals_of(&self, token: Address) -> Option<u8> {
+        let fetched = self.token_decimals.get(&token).map(|d| *d);
+        match fetched {
+            Some(d) if !d.assumed => Some(d.decimals),
+            _ if token == self.weth_address => Some(self.weth_decimals),
+            _ if self.quote_tokens.contains(&token) => Some(self.usdc_decimals),
+            _ => fetched.map(|d| d.decimals),
+        }
+    }
 }
 
 // --- Helper Functions ---
 
+/// Why fresh Velo/Aero reserves should not replace `previous`: a zero reserve, or a reserve price
+/// (reserve1 per reserve0) more than `max_price_jump_bps` away from the previous reserves' (0 disables
+/// the price check). Stale or forked `getReserves` answers otherwise turn into nonsensical routes.
+pub fn reserve_update_rejection(previous: &PoolSnapshot, reserve0: U256, reserve1: U256, max_price_jump_bps: u64) -> Option<String> {
+    if reserve0.is_zero() || reserve1.is_zero() {
+        return Some(format!("zero reserve ({}, {})", reserve0, reserve1));
+    }
+    let (Some(prev0), Some(prev1)) = (previous.reserve0, previous.reserve1) else { return None };
+    if max_price_jump_bps == 0 || prev0.is_zero() || prev1.is_zero() {
+        return None;
+    }
+    let price_ratio = (reserve1.to_f64_lossy() / reserve0.to_f64_lossy()) / (prev1.to_f64_lossy() / prev0.to_f64_lossy());
+    let jump_bps = (price_ratio - 1.0).abs() * 10_000.0;
+    (jump_bps > max_price_jump_bps as f64).then(|| format!("reserve price moved {:.0} bps (limit {})", jump_bps, max_price_jump_bps))
+}
+
+/// Records the block a pool was created in (from its PoolCreated log), used to rank routes through
+/// young pools lower. No-op when the pool was not cached (e.g. excluded by the blocklist).
+pub fn record_pool_creation(app_state: &AppState, pool_addr: Address, created_block: Option<U64>) {
+    if let Some(mut pool_state) = app_state.pool_states.get_mut(&pool_addr) {
+        pool_state.created_block = created_block;
+    }
+}
+
+/// Reads a UniV3 pool's static fields and current price/liquidity, plus its TWAP tick when
+/// `twap_window_secs` is set. `known_fee` (from the factory's `PoolCreated` event) replaces the `fee()` call.
+pub async fn fetch_univ3_pool_state<M: Middleware + 'static>(
+    pool_addr: Address,
+    factory_addr: Address,
+    known_fee: Option<u32>,
+    weth_addr: Address,
+    twap_window_secs: u32,
+    client: Arc<M>,
+) -> Result<(PoolState, PoolSnapshot)> {
+    let pool = UniswapV3Pool::new(pool_addr, client);
+    let slot0_call = pool.slot_0();
+    let token0_call = pool.token_0();
+    let token1_call = pool.token_1();
+    let fee_call = pool.fee();
+    let tick_spacing_call = pool.tick_spacing();
+    let liquidity_call = pool.liquidity();
+
+    let slot0_fut = slot0_call.call();
+    let token0_fut = token0_call.call();
+    let token1_fut = token1_call.call();
+    let fee_fut = async {
+        match known_fee {
+            Some(fee) => Ok(fee),
+            None => fee_call.call().await,
+        }
+    };
+    let tick_spacing_fut = tick_spacing_call.call();
+    let liquidity_fut = liquidity_call.call();
+
+    let (slot0_res, token0_res, token1_res, fee_res, tick_spacing_res, liquidity_res) = tokio::try_join!(
+        slot0_fut, token0_fut, token1_fut, fee_fut, tick_spacing_fut, liquidity_fut
+    )?;
+
+    let (sqrtp, tick, ..) = slot0_res;
+    let twap_tick = fetch_twap_tick(&pool, twap_window_secs, None).await;
+    let (t0, t1, f) = (token0_res, token1_res, fee_res);
+    let is_t0_weth = t0 == weth_addr;
+
+    let ps = PoolState {
+        pool_address: pool_addr, dex_type: DexType::UniswapV3, token0: t0, token1: t1,
+        uni_fee: Some(f), tick_spacing: Some(tick_spacing_res), velo_stable: None, t0_is_weth: Some(is_t0_weth),
+        factory: factory_addr, created_block: None,
+    };
+    let sn = PoolSnapshot {
+        pool_address: pool_addr, dex_type: DexType::UniswapV3, token0: t0, token1: t1,
+        reserve0: None, reserve1: None, sqrt_price_x96: Some(sqrtp),
+        tick: Some(tick), liquidity: Some(liquidity_res), twap_tick, last_update_block: None, last_update_timestamp: None,
+    };
+    Ok((ps, sn))
+}
+
+/// Reads the initialized ticks of the `words` tick bitmap words on each side of `tick`'s word (see
+/// `TickWindow`), at `block` or the latest block.
+pub async fn fetch_tick_window<M: Middleware + 'static>(
+    pool: &UniswapV3Pool<M>,
+    tick: i32,
+    tick_spacing: i32,
+    words: u32,
+    block: Option<U64>,
+) -> Result<TickWindow> {
+    if tick_spacing <= 0 {
+        return Err(eyre!("Invalid tick spacing {} for {}", tick_spacing, pool.address()));
+    }
+    let word = tick.div_euclid(tick_spacing).div_euclid(256);
+    let words = words.min(i16::MAX as u32) as i32;
+    let (word_lo, word_hi) = ((word - words).max(i16::MIN as i32) as i16, (word + words).min(i16::MAX as i32) as i16);
+
+    let bitmaps = try_join_all((word_lo..=word_hi).map(|word| {
+        let mut call = pool.tick_bitmap(word);
+        if let Some(block) = block {
+            call = call.block(block);
+        }
+        async move { call.call().await.map(|bitmap| (word, bitmap)) }
+    })).await?;
+    let initialized: Vec<i32> = bitmaps.iter()
+        .flat_map(|(word, bitmap)| (0..256).filter(|bit| bitmap.bit(*bit)).map(move |bit| (*word as i32 * 256 + bit as i32) * tick_spacing))
+        .collect();
+    let ticks = try_join_all(initialized.iter().map(|&tick| {
+        let mut call = pool.ticks(tick);
+        if let Some(block) = block {
+            call = call.block(block);
+        }
+        async move { call.call().await.map(|(liquidity_gross, liquidity_net, ..)| (tick, TickLiquidity { liquidity_gross, liquidity_net })) }
+    })).await?;
+    Ok(TickWindow { tick_spacing, word_lo, word_hi, ticks: ticks.into_iter().collect() })
+}
+
+/// Re-reads a cached UniV3 pool's `TickWindow` around its snapshot tick into `univ3_ticks`. A no-op when
+/// `config.univ3_tick_window_words` is 0; on failure the window is dropped, so quotes stop crossing ticks.
+pub(crate) async fn refresh_tick_window<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, pool_addr: Address, block: Option<U64>) {
+    let words = app_state.config.univ3_tick_window_words;
+    if words == 0 {
+        return;
+    }
+    let Some(tick_spacing) = app_state.pool_states.get(&pool_addr).and_then(|state| state.tick_spacing) else { return };
+    let Some(tick) = app_state.pool_snapshots.get(&pool_addr).and_then(|snapshot| snapshot.tick) else { return };
+    let pool = UniswapV3Pool::new(pool_addr, client);
+    match app_state.timed_fetch(fetch_tick_window(&pool, tick, tick_spacing, words, block)).await {
+        Ok(Ok(window)) => {
+            trace!(pool = %pool_addr, initialized = window.ticks.len(), "Tick window fetched.");
+            app_state.univ3_ticks.insert(pool_addr, window);
+        }
+        Ok(Err(e)) => {
+            debug!(pool = %pool_addr, error = ?e, "Tick window fetch failed; local quotes stay within the tick range.");
+            app_state.univ3_ticks.remove(&pool_addr);
+        }
+        Err(_) => {
+            debug!(pool = %pool_addr, "Tick window fetch timed out; local quotes stay within the tick range.");
+            app_state.univ3_ticks.remove(&pool_addr);
+        }
+    }
+}
+
 /// Fetches the detailed state for a given pool and caches it in `pool_states`.
 /// Also creates an initial snapshot and caches it in `pool_snapshots`.
-/// Handles different DEX types.
+/// Handles different DEX types. A UniV3 `uni_fee` already known (from the `PoolCreated` event) skips the `fee()` call.
 #[instrument(skip_all, fields(pool=%pool_addr, dex=?dex_type), level="info")]
 pub async fn fetch_and_cache_pool_state(
     pool_addr: Address,
     dex_type: DexType,
     factory_addr: Address, // Pass the factory address that created this pool
+    uni_fee: Option<u32>,
     client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
     app_state: Arc<AppState>,
 ) -> Result<()> {
+    if !app_state.config.is_pool_allowed(pool_addr) {
+        info!("Pool excluded by POOL_BLOCKLIST/POOL_ALLOWLIST, not caching.");
+        return Ok(());
+    }
     info!("Fetching state...");
     let weth_addr = app_state.weth_address; // Cache WETH address locally
-    let timeout_dur = Duration::from_secs(app_state.config.fetch_timeout_secs.unwrap_or(15));
 
-    // Define the async block that performs the fetches
-    let fetch_logic = async {
+    // Define the async block that performs the fetches (one attempt)
+    let fetch_logic = || async {
         match dex_type {
-            DexType::UniswapV3 => {
-                let pool = UniswapV3Pool::new(pool_addr, client.clone());
-                let slot0_call = pool.slot_0();
-                let token0_call = pool.token_0();
-                let token1_call = pool.token_1();
-                let fee_call = pool.fee();
-
-                let slot0_fut = slot0_call.call();
-                let token0_fut = token0_call.call();
-                let token1_fut = token1_call.call();
-                let fee_fut = fee_call.call();
-
-                let (slot0_res, token0_res, token1_res, fee_res) = tokio::try_join!(
-                    slot0_fut, token0_fut, token1_fut, fee_fut
-                )?;
-
-                let (sqrtp_u160, tick, ..) = slot0_res;
-                let (t0, t1, f) = (token0_res, token1_res, fee_res);
-                let is_t0_weth = t0 == weth_addr;
-                let sqrtp = U256::from(sqrtp_u160);
-
-                let ps = PoolState {
-                    pool_address: pool_addr, dex_type, token0: t0, token1: t1,
-                    uni_fee: Some(f), velo_stable: None, t0_is_weth: Some(is_t0_weth),
-                    factory: factory_addr,
-                };
-                let sn = PoolSnapshot {
-                    pool_address: pool_addr, dex_type, token0: t0, token1: t1,
-                    reserve0: None, reserve1: None, sqrt_price_x96: Some(sqrtp),
-                    tick: Some(tick), last_update_block: None,
-                };
-                Ok((ps, sn))
-            }
+            DexType::UniswapV3 => fetch_univ3_pool_state(pool_addr, factory_addr, uni_fee, weth_addr, app_state.config.twap_window_secs, client.clone()).await,
             DexType::VelodromeV2 | DexType::Aerodrome => {
                  let (reserves_call, token0_call, token1_call, stable_call) =
                     if dex_type == DexType::VelodromeV2 {
@@ -200,13 +718,13 @@ pub async fn fetch_and_cache_pool_state(
 
                 let ps = PoolState {
                     pool_address: pool_addr, dex_type, token0: t0, token1: t1,
-                    uni_fee: None, velo_stable: Some(s), t0_is_weth: Some(is_t0_weth),
-                    factory: factory_addr,
+                    uni_fee: None, tick_spacing: None, velo_stable: Some(s), t0_is_weth: Some(is_t0_weth),
+                    factory: factory_addr, created_block: None,
                 };
                 let sn = PoolSnapshot {
                     pool_address: pool_addr, dex_type, token0: t0, token1: t1,
                     reserve0: Some(r0), reserve1: Some(r1), sqrt_price_x96: None,
-                    tick: None, last_update_block: None,
+                    tick: None, liquidity: None, twap_tick: None, last_update_block: None, last_update_timestamp: None,
                 };
                 Ok((ps, sn))
             }
@@ -214,37 +732,161 @@ pub async fn fetch_and_cache_pool_state(
         }
     };
 
-    match timeout(timeout_dur, fetch_logic).await {
-        Ok(Ok((ps, sn))) => {
+    let config = &app_state.config;
+    let fetched = with_retries(config.fetch_max_retries, config.fetch_base_delay_ms, "pool state fetch", || async {
+        match app_state.timed_fetch(fetch_logic()).await {
+            Ok(result) => result,
+            Err(_) => Err(eyre!("Timeout fetching pool state for {} ({}ms)", pool_addr, app_state.fetch_timeout().as_millis())),
+        }
+    }).await;
+    match fetched {
+        Ok((ps, sn)) => {
             info!("State fetched successfully.");
             trace!(?ps, ?sn);
-            app_state.pool_states.insert(pool_addr, ps);
-            app_state.pool_snapshots.insert(pool_addr, sn);
+            let tokens = [ps.token0, ps.token1];
+            // A re-fetch must not replace a known-good snapshot with implausible reserves (new pools may be empty)
+            let previous = app_state.pool_snapshots.get(&pool_addr).map(|s| s.value().clone());
+            if let (Some(previous), Some(r0), Some(r1)) = (previous, sn.reserve0, sn.reserve1) {
+                if app_state.reject_reserves(pool_addr, &previous, r0, r1) {
+                    return Ok(());
+                }
+            }
+            if !app_state.insert_pool(ps, sn) {
+                return Ok(());
+            }
+            if dex_type == DexType::UniswapV3 {
+                refresh_tick_window(client.clone(), &app_state, pool_addr, None).await;
+            }
+            crate::token_safety::ensure_token_decimals(client.clone(), &app_state, tokens).await;
+            crate::token_safety::ensure_tokens_checked(client, &app_state, pool_addr, tokens).await;
             Ok(())
         }
-        Ok(Err(e)) => {
-            error!(pool = %pool_addr, error = ?e, "Fetch state failed");
+        Err(e) => {
+            error!(pool = %pool_addr, error = ?e, attempts = config.fetch_max_retries + 1, "Fetch state failed");
             Err(e).wrap_err("Pool state fetch logic failed")
         }
-        Err(_) => {
-            error!(pool = %pool_addr, timeout_secs = timeout_dur.as_secs(), "Fetch state timeout");
-            Err(eyre!(
-                "Timeout fetching pool state for {}",
-                pool_addr
-            ))
+    }
+}
+
+/// Safety net for missed swap events: re-fetches every cached pool at one block and overwrites drifted
+/// snapshots, at most `SNAPSHOT_REFRESH_CONCURRENCY` pools at a time. Snapshots that an event already
+/// updated at or after the sweep block are left untouched. Returns the number of snapshots that changed.
+#[instrument(skip_all, level = "info")]
+pub async fn refresh_all_snapshots(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: Arc<AppState>,
+) -> Result<usize> {
+    let block = client.get_block_number().await.wrap_err("Failed to fetch block number for snapshot refresh")?;
+    let block_timestamp = app_state.block_timestamp(block, client.as_ref()).await;
+    let pools: Vec<(Address, DexType)> = app_state.pool_states.iter().map(|e| (*e.key(), e.dex_type)).collect();
+    let semaphore = Arc::new(Semaphore::new(SNAPSHOT_REFRESH_CONCURRENCY));
+
+    let results = join_all(pools.iter().map(|&(pool_addr, dex_type)| {
+        let (client, app_state, semaphore) = (client.clone(), app_state.clone(), semaphore.clone());
+        async move {
+            let _permit = semaphore.acquire().await.ok()?;
+            match refresh_pool_snapshot(pool_addr, dex_type, block, block_timestamp, client, &app_state).await {
+                Ok(changed) => Some(changed),
+                Err(e) => { warn!(pool = %pool_addr, error = ?e, "Snapshot refresh failed."); None }
+            }
         }
+    })).await;
+
+    let refreshed = results.iter().filter(|r| r.is_some()).count();
+    let changed = results.iter().filter(|r| **r == Some(true)).count();
+    Metrics::set(&app_state.metrics.last_refresh_changed_snapshots, changed as u64);
+    if changed > 0 {
+        warn!(%block, pools = pools.len(), refreshed, changed, "Full refresh corrected drifted snapshots (missed events).");
+    } else {
+        info!(%block, pools = pools.len(), refreshed, "Full refresh: all snapshots in sync.");
     }
+    Ok(changed)
+}
+
+/// Re-fetches one pool's reserves/price at `block` and writes them into its snapshot. Returns whether anything changed.
+pub(crate) async fn refresh_pool_snapshot(
+    pool_addr: Address,
+    dex_type: DexType,
+    block: U64,
+    block_timestamp: Option<u64>,
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: &AppState,
+) -> Result<bool> {
+    let fetch_logic = async {
+        match dex_type {
+            DexType::UniswapV3 => {
+                let pool = UniswapV3Pool::new(pool_addr, client.clone());
+                let slot0_call = pool.slot_0().block(block);
+                let liquidity_call = pool.liquidity().block(block);
+                let ((sqrtp, tick, ..), liquidity) = tokio::try_join!(slot0_call.call(), liquidity_call.call())?;
+                let twap_tick = fetch_twap_tick(&pool, app_state.config.twap_window_secs, Some(block)).await;
+                Ok((None, None, Some(sqrtp), Some(tick), Some(liquidity), twap_tick))
+            }
+            DexType::VelodromeV2 | DexType::Aerodrome => {
+                let reserves_call = if dex_type == DexType::VelodromeV2 {
+                    VelodromeV2Pool::new(pool_addr, client.clone()).get_reserves()
+                } else {
+                    AerodromePool::new(pool_addr, client.clone()).get_reserves()
+                };
+                let (r0, r1, _block_timestamp_last): (U256, U256, U256) = reserves_call.block(block).call().await?;
+                Ok((Some(r0), Some(r1), None, None, None, None))
+            }
+            DexType::Unknown => Err(eyre!("Cannot refresh state for Unknown DEX type")),
+        }
+    };
+    let (reserve0, reserve1, sqrt_price_x96, tick, liquidity, twap_tick) = app_state.timed_fetch(fetch_logic).await
+        .map_err(|_| eyre!("Timeout refreshing pool state for {}", pool_addr))??;
+
+    let Some(mut snapshot) = app_state.pool_snapshots.get_mut(&pool_addr) else { return Ok(false) };
+    if snapshot.last_update_block.is_some_and(|b| b >= block) {
+        trace!(pool = %pool_addr, "Snapshot already updated by an event at/after the refresh block.");
+        return Ok(false);
+    }
+    if let (Some(r0), Some(r1)) = (reserve0, reserve1) {
+        if app_state.reject_reserves(pool_addr, &snapshot, r0, r1) {
+            return Ok(false);
+        }
+    }
+    let changed = snapshot.reserve0 != reserve0 || snapshot.reserve1 != reserve1
+        || snapshot.sqrt_price_x96 != sqrt_price_x96 || snapshot.tick != tick || snapshot.liquidity != liquidity;
+    if changed {
+        debug!(pool = %pool_addr, old = ?(snapshot.reserve0, snapshot.reserve1, snapshot.sqrt_price_x96, snapshot.tick), new = ?(reserve0, reserve1, sqrt_price_x96, tick), "Snapshot drift corrected.");
+    }
+    snapshot.reserve0 = reserve0;
+    snapshot.reserve1 = reserve1;
+    snapshot.sqrt_price_x96 = sqrt_price_x96;
+    snapshot.tick = tick;
+    snapshot.liquidity = liquidity;
+    snapshot.twap_tick = twap_tick;
+    snapshot.last_update_block = Some(block);
+    snapshot.last_update_timestamp = block_timestamp;
+    drop(snapshot);
+    // Drift means missed Mint/Burn events may have changed ticks too; a tick that left the window needs a re-centered one
+    let window_stale = match (tick, app_state.univ3_ticks.get(&pool_addr)) {
+        (Some(tick), Some(window)) => changed || !window.covers_tick(tick),
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    if window_stale {
+        refresh_tick_window(client, app_state, pool_addr, Some(block)).await;
+    }
+    Ok(changed)
+}
+
+/// Whether `pool` holds exactly `base` and `quote`, in either token order.
+pub fn pool_matches_pair(pool: &PoolSnapshot, base: Address, quote: Address) -> bool {
+    is_target_pair_option(pool.token0, pool.token1, Some((base, &[quote])))
 }
 
-/// Helper function to check if two token addresses match a target pair, ignoring order.
-/// If target is None, always returns true.
+/// Helper function to check if two token addresses pair the target base token (WETH) with one of the
+/// target quote tokens, ignoring order. If target is None, always returns true.
 pub fn is_target_pair_option(
     a0: Address,
     a1: Address,
-    target: Option<(Address, Address)>,
+    target: Option<(Address, &[Address])>,
 ) -> bool {
     match target {
-        Some((ta, tb)) => (a0 == ta && a1 == tb) || (a0 == tb && a1 == ta),
+        Some((base, quotes)) => (a0 == base && quotes.contains(&a1)) || (a1 == base && quotes.contains(&a0)),
         None => true,
     }
 }
\ No newline at end of file
@@ -0,0 +1,51 @@
+// bot/src/throttle.rs
+//! Minimum time between submissions (`config.min_submission_interval_ms`, optionally per pool with
+//! `config.min_pool_submission_interval_ms`), so volatile periods do not flood the mempool or get the
+//! bot rate-limited by relays.
+//!
+//! A submission attempt claims its slot right before it takes a nonce, after validation, simulation, the
+//! in-flight exposure cap and gas estimation, so attempts dropped by those checks leave the slot free.
+//! The claim itself is atomic, so concurrent opportunities cannot both pass; an attempt that fails after
+//! claiming (e.g. rejected by every relay) still counts. Throttled opportunities are dropped, not queued.
+
+use crate::cooldown::route_pools;
+use crate::path_optimizer::RouteCandidate;
+use dashmap::DashMap;
+use ethers::types::Address;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub struct SubmissionThrottle {
+    last_submission: Mutex<Option<Instant>>,
+    last_pool_submission: DashMap<Address, Instant>,
+}
+
+impl SubmissionThrottle {
+    /// Claims a submission slot for `route` at `now`. Returns the remaining wait instead when the last
+    /// submission (or the last one through any of the route's pools) is closer than its interval. Zero
+    /// intervals disable the respective check.
+    pub fn try_claim(&self, route: &RouteCandidate, global: Duration, per_pool: Duration, now: Instant) -> Result<(), Duration> {
+        let mut last = self.last_submission.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let remaining = |at: Instant, interval: Duration| interval.saturating_sub(now.saturating_duration_since(at));
+        let mut wait = last.map_or(Duration::ZERO, |at| remaining(at, global));
+        if !per_pool.is_zero() {
+            for pool in route_pools(route) {
+                if let Some(at) = self.last_pool_submission.get(&pool) {
+                    wait = wait.max(remaining(*at, per_pool));
+                }
+            }
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        *last = Some(now);
+        if !per_pool.is_zero() {
+            for pool in route_pools(route) {
+                self.last_pool_submission.insert(pool, now);
+            }
+        }
+        Ok(())
+    }
+}
+// END OF FILE: bot/src/throttle.rs
@@ -0,0 +1,198 @@
+// bot/src/token_safety.rs
+//! Detects fee-on-transfer / rebasing tokens before they are routed through.
+//!
+//! The profit math assumes swaps move exact amounts. To verify that, a small probe is run with
+//! `eth_call` and a state override that replaces the pool's code: acting as the pool (which holds
+//! the token), the probe transfers a slice of its balance to a scratch address and returns the
+//! receiver's balance delta. Any delta other than the amount sent flags the token.
+//! Results are cached in `AppState::fee_on_transfer_tokens`, so each token is probed once.
+//!
+//! Each token's `decimals()` is read once as well (`AppState::token_decimals`); price and amount
+//! conversions look it up through `AppState::decimals_of`. Tokens that do not implement it, or revert,
+//! get `config.default_token_decimals` and are marked as assumed, so `find_top_routes` can leave their
+//! pools alone (`config.skip_unknown_decimals`).
+
+use crate::bindings::IERC20;
+use crate::state::AppState;
+use ethers::{
+    contract::ContractError,
+    prelude::{Http, LocalWallet, Middleware, Provider, SignerMiddleware},
+    providers::{spoof, MiddlewareError, RawCall},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+};
+use eyre::{eyre, Result, WrapErr};
+use std::sync::Arc;
+use tracing::{debug, info, instrument, warn};
+
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+// Scratch receiver for the probe transfer (an address with no code or special token treatment).
+const PROBE_RECEIVER: Address = Address::repeat_byte(0x5a);
+// Probe transfers 1/PROBE_AMOUNT_DIVISOR of the pool's token balance.
+const PROBE_AMOUNT_DIVISOR: u64 = 1_000;
+
+/// Outcome of comparing the amount sent with the receiver's balance delta.
+pub fn is_fee_on_transfer(amount_sent: U256, amount_received: U256) -> bool {
+    amount_received != amount_sent
+}
+
+/// Builds the probe runtime code (no jumps, constants embedded):
+/// `b0 = token.balanceOf(R); ok = token.transfer(R, amount); b1 = token.balanceOf(R); return (ok, b1 - b0)`
+/// where `ok` is the AND of all three call success flags.
+pub fn transfer_probe_bytecode(token: Address, receiver: Address, amount: U256) -> Bytes {
+    const SUB: u8 = 0x03; const AND: u8 = 0x16; const SHL: u8 = 0x1b;
+    const MLOAD: u8 = 0x51; const MSTORE: u8 = 0x52; const GAS: u8 = 0x5a;
+    const PUSH1: u8 = 0x60; const PUSH4: u8 = 0x63; const PUSH20: u8 = 0x73; const PUSH32: u8 = 0x7f;
+    const CALL: u8 = 0xf1; const RETURN: u8 = 0xf3; const STATICCALL: u8 = 0xfa;
+
+    let mut code = Vec::with_capacity(256);
+    let push1 = |code: &mut Vec<u8>, v: u8| code.extend_from_slice(&[PUSH1, v]);
+    let push20 = |code: &mut Vec<u8>, a: Address| { code.push(PUSH20); code.extend_from_slice(a.as_bytes()); };
+    // mstore(0, selector << 224); mstore(4, receiver)
+    let write_call_head = |code: &mut Vec<u8>, selector: [u8; 4]| {
+        code.push(PUSH4); code.extend_from_slice(&selector);
+        push1(code, 0xe0); code.push(SHL);
+        push1(code, 0x00); code.push(MSTORE);
+        push20(code, receiver); push1(code, 0x04); code.push(MSTORE);
+    };
+    // staticcall(gas, token, 0, 0x24, ret_offset, 0x20)
+    let balance_of = |code: &mut Vec<u8>, ret_offset: u8| {
+        write_call_head(code, BALANCE_OF_SELECTOR);
+        push1(code, 0x20); push1(code, ret_offset); push1(code, 0x24); push1(code, 0x00);
+        push20(code, token); code.push(GAS); code.push(STATICCALL);
+    };
+
+    balance_of(&mut code, 0x80); // [s1], b0 at mem 0x80
+
+    write_call_head(&mut code, TRANSFER_SELECTOR);
+    code.push(PUSH32);
+    let mut amount_bytes = [0u8; 32];
+    amount.to_big_endian(&mut amount_bytes);
+    code.extend_from_slice(&amount_bytes);
+    push1(&mut code, 0x24); code.push(MSTORE);
+    // call(gas, token, 0, 0, 0x44, 0xa0, 0x20)
+    push1(&mut code, 0x20); push1(&mut code, 0xa0); push1(&mut code, 0x44); push1(&mut code, 0x00); push1(&mut code, 0x00);
+    push20(&mut code, token); code.push(GAS); code.push(CALL);
+    code.push(AND); // [s1 & s2]
+
+    balance_of(&mut code, 0xc0); // b1 at mem 0xc0
+    code.push(AND); // [ok]
+
+    push1(&mut code, 0x00); code.push(MSTORE); // mem[0] = ok
+    push1(&mut code, 0x80); code.push(MLOAD);
+    push1(&mut code, 0xc0); code.push(MLOAD);
+    code.push(SUB); // b1 - b0
+    push1(&mut code, 0x20); code.push(MSTORE);
+    push1(&mut code, 0x40); push1(&mut code, 0x00); code.push(RETURN);
+    Bytes::from(code)
+}
+
+/// A token's decimals, `assumed` when `decimals()` could not be read and the configured default is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenDecimals {
+    pub decimals: u8,
+    pub assumed: bool,
+}
+
+/// Reads `token.decimals()`, falling back to `default_decimals` (marked assumed) when the token does not
+/// implement it or it reverts. None on RPC failures, which say nothing about the token.
+pub async fn fetch_token_decimals<M: Middleware + 'static>(client: Arc<M>, token: Address, default_decimals: u8) -> Option<TokenDecimals> {
+    match IERC20::new(token, client).decimals().call().await {
+        Ok(decimals) => Some(TokenDecimals { decimals, assumed: false }),
+        Err(e) if is_non_standard_decimals(&e) => {
+            warn!(%token, default_decimals, error = ?e, "decimals() missing or reverted; assuming the default.");
+            Some(TokenDecimals { decimals: default_decimals, assumed: true })
+        }
+        Err(e) => {
+            warn!(%token, error = ?e, "decimals() call failed.");
+            None
+        }
+    }
+}
+
+/// A revert, or an answer that does not decode as `uint8` (no such function returns empty data).
+fn is_non_standard_decimals<M: Middleware>(error: &ContractError<M>) -> bool {
+    match error {
+        ContractError::Revert(_) | ContractError::DecodingError(_) | ContractError::AbiError(_) | ContractError::DetokenizationError(_) => true,
+        ContractError::MiddlewareError { e } => e.as_error_response().is_some_and(|response| response.is_revert()),
+        ContractError::ProviderError { e } => e.as_error_response().is_some_and(|response| response.is_revert()),
+        _ => false,
+    }
+}
+
+/// Reads the decimals of every token not yet in `AppState::token_decimals`. Failed reads are left
+/// uncached so a later fetch retries.
+pub async fn ensure_token_decimals<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, tokens: [Address; 2]) {
+    for token in tokens {
+        if app_state.token_decimals.contains_key(&token) {
+            continue;
+        }
+        if let Some(decimals) = fetch_token_decimals(client.clone(), token, app_state.config.default_token_decimals).await {
+            app_state.token_decimals.insert(token, decimals);
+        }
+    }
+}
+
+/// Probes every token not yet in the cache. Failures are logged and left uncached so a later fetch retries.
+pub async fn ensure_tokens_checked(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    app_state: &AppState,
+    holder_pool: Address,
+    tokens: [Address; 2],
+) {
+    for token in tokens {
+        if app_state.fee_on_transfer_tokens.contains_key(&token) {
+            continue;
+        }
+        match probe_token(client.clone(), token, holder_pool).await {
+            Ok(Some(flagged)) => {
+                if flagged {
+                    warn!(%token, pool = %holder_pool, "Fee-on-transfer/rebasing token detected. Routes through it will be skipped.");
+                }
+                app_state.fee_on_transfer_tokens.insert(token, flagged);
+            }
+            Ok(None) => debug!(%token, pool = %holder_pool, "Token safety probe inconclusive (pool holds no balance)."),
+            Err(e) => warn!(%token, pool = %holder_pool, error = ?e, "Token safety probe failed."),
+        }
+    }
+}
+
+/// Returns `Some(true)` for fee-on-transfer/rebasing tokens, `None` when `holder` has nothing to transfer.
+#[instrument(skip(client), level = "debug")]
+async fn probe_token(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    token: Address,
+    holder: Address,
+) -> Result<Option<bool>> {
+    let mut balance_call = BALANCE_OF_SELECTOR.to_vec();
+    balance_call.extend_from_slice(&[0u8; 12]);
+    balance_call.extend_from_slice(holder.as_bytes());
+    let balance_tx: TypedTransaction = TransactionRequest::new().to(token).data(balance_call).into();
+    let raw_balance = client.call(&balance_tx, None).await.wrap_err("balanceOf call failed")?;
+    if raw_balance.len() < 32 {
+        return Err(eyre!("Unexpected balanceOf return data: {}", raw_balance));
+    }
+    let amount = U256::from_big_endian(&raw_balance[..32]) / U256::from(PROBE_AMOUNT_DIVISOR);
+    if amount.is_zero() {
+        return Ok(None);
+    }
+
+    let probe_tx: TypedTransaction = TransactionRequest::new().to(holder).into();
+    let state = spoof::code(holder, transfer_probe_bytecode(token, PROBE_RECEIVER, amount));
+    let out = client.provider().call_raw(&probe_tx).state(&state).await
+        .wrap_err("Probe eth_call with state override failed (RPC may not support overrides)")?;
+    if out.len() < 64 {
+        return Err(eyre!("Unexpected probe return data length {}", out.len()));
+    }
+    if U256::from_big_endian(&out[..32]).is_zero() {
+        return Err(eyre!("Probe transfer reverted"));
+    }
+    let received = U256::from_big_endian(&out[32..64]);
+    if received.is_zero() {
+        // transfer() returned false (or the token blocks the receiver); not evidence of a fee
+        return Err(eyre!("Probe transfer moved no tokens"));
+    }
+    let flagged = is_fee_on_transfer(amount, received);
+    info!(%token, %amount, %received, flagged, "Token transfer probe complete.");
+    Ok(Some(flagged))
+}
@@ -1,23 +1,36 @@
 // bot/src/transaction.rs
 
-use crate::bindings::BalancerVault;
-use crate::config::Config;
-use crate::encoding::encode_user_data;
-use crate::gas::estimate_flash_loan_gas;
-use crate::state::{AppState, DexType};
+use crate::accuracy::{prediction_error_bps, ProfitAccuracy};
+use crate::alerts::{send_alert, AlertLevel};
+use crate::audit::{AuditEvent, AuditRecord, FailedSubmission};
+use crate::bindings::{ArbitrageExecutor, IERC20, IWETH9};
+use crate::config::{Config, PriorityFeeStrategy};
+use crate::deadline::{blocks_late, target_block, Deadline};
+use crate::encoding::{encode_user_data, min_amount_out, route_swap_legs};
+use crate::flash_loan::{execute_flash_loan_calldata, flash_loan_fee_wei};
+use crate::gas::{compute_priority_fee, gas_cost_in_loan_token, preset_gas_limit, submission_gas_limit, estimate_flash_loan_gas, TxType, FEE_HISTORY_BLOCKS};
+use crate::metrics::Metrics;
+use crate::oracle::{cached_weth_price_usd, net_profit_usd};
+use crate::state::AppState;
 use crate::path_optimizer::RouteCandidate;
+use crate::relay::{first_accepted, send_private_tx};
+use crate::revert::{decode_revert_reason, revert_reason_from_error};
+use crate::slippage::record_leg_slippage;
+use crate::simulation::{competitive_priority_fee, implied_priority_fee_gwei, net_of_gas, simulate_route_legs, simulate_via_executor_callstatic, validate_simulation_consistency};
+use crate::utils::{f64_to_wei, u256_to_i256, ArithmeticOverflow, ToF64Lossy};
+use crate::PROFIT_REALIZED_TOPIC;
 use ethers::{
     prelude::*,
     types::{
-        transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest, U256,
+        transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest, TransactionRequest, U256,
         U64, I256, TxHash,
     },
-    utils::format_units,
+    abi::{encode, Token},
+    utils::{format_units, keccak256},
 };
 use eyre::{eyre, Result, WrapErr};
-use serde::Serialize;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime};
 use std::str::FromStr;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, timeout}; // Import timeout
@@ -34,7 +47,6 @@ const GAS_ESTIMATION_TIMEOUT_SECS: u64 = 20; // Timeout for gas estimation step
 // --- Structs ---
 #[derive(Debug, Clone, Copy)] pub struct GasInfo { pub max_fee_per_gas: U256, pub max_priority_fee_per_gas: U256 }
 #[derive(Debug)] pub struct NonceManager { current_nonce: Mutex<Option<U256>>, wallet_address: Address }
-#[derive(Serialize, Debug)] #[serde(rename_all = "camelCase")] struct AlchemyPrivateTxParams<'a> { tx: &'a str }
 
 // --- NonceManager Impl ---
 // (remains unchanged)
@@ -63,6 +75,24 @@ impl NonceManager {
         Ok(next_nonce)
     }
 
+    /// Re-reads the pending nonce after a "nonce too low" rejection (the wallet was used elsewhere) and
+    /// assigns it. The lock is held across the fetch so concurrent submissions wait for the corrected
+    /// value, and the result never goes below the cache so a nonce already handed out is not reused.
+    #[instrument(skip(self, client), fields(wallet=%self.wallet_address))]
+    pub async fn resync_nonce<M: Middleware>(&self, client: &M) -> Result<U256> where M::Error: 'static+Send+Sync {
+        let mut guard = self.current_nonce.lock().await;
+        let pending = client.get_transaction_count(self.wallet_address, Some(BlockNumber::Pending.into()))
+            .await
+            .wrap_err("Failed to re-sync transaction count")?;
+        let next_nonce = match *guard {
+            Some(current) => pending.max(current + U256::one()),
+            None => pending,
+        };
+        warn!(cached_nonce=?*guard, %pending, %next_nonce, "Re-synced nonce from network (pending).");
+        *guard = Some(next_nonce);
+        Ok(next_nonce)
+    }
+
     #[instrument(skip(self), fields(wallet=%self.wallet_address))]
     pub async fn handle_nonce_error(&self) {
         let mut guard = self.current_nonce.lock().await;
@@ -70,36 +100,30 @@ impl NonceManager {
         *guard = None;
     }
 
-     #[instrument(skip(self), fields(wallet=%self.wallet_address, used_nonce=%used_nonce))]
+    /// Records that `used_nonce` was mined. The cache holds the last assigned nonce, so it only moves
+    /// when the confirmed nonce is ahead of it (or it was reset).
+    #[instrument(skip(self), fields(wallet=%self.wallet_address, used_nonce=%used_nonce))]
     pub async fn confirm_nonce_used(&self, used_nonce: U256) {
         let mut guard = self.current_nonce.lock().await;
         match *guard {
-            Some(current) if current == used_nonce => {
-                *guard = Some(used_nonce + U256::one());
-                 debug!(next_nonce=%(used_nonce + U256::one()), "Confirmed nonce used, incremented cache.");
-            }
-            Some(current) if current > used_nonce => {
-                warn!(current_cached_nonce=%current, "Confirmed nonce is lower than current cached nonce, cache might be ahead or reset. Not changing cache.");
+            Some(current) if current >= used_nonce => {
+                debug!(current_cached_nonce=%current, "Confirmed nonce used, cache already at or past it.");
             }
-            Some(current) if current < used_nonce => {
-                 warn!(current_cached_nonce=%current, "Confirmed nonce is higher than expected cached nonce, updating cache to confirmed + 1.");
-                *guard = Some(used_nonce + U256::one());
+            Some(current) => {
+                warn!(current_cached_nonce=%current, "Confirmed nonce is higher than the cached nonce, advancing cache to it.");
+                *guard = Some(used_nonce);
             }
             None => {
-                warn!("Confirmed nonce but manager cache was empty, setting next based on confirmed.");
-                *guard = Some(used_nonce + U256::one());
+                warn!("Confirmed nonce but manager cache was empty, continuing after the confirmed nonce.");
+                *guard = Some(used_nonce);
             }
-             Some(current) => {
-                 // This case was previously hitting a panic due to `current` being moved, fixed comparison logic
-                 if current != used_nonce { // Explicitly check inequality if not covered above
-                    error!(confirmed_nonce=%used_nonce, current_cached_nonce=%current, "Unexpected state in nonce confirmation logic! Check comparison.");
-                    // Decide recovery strategy, e.g., reset or use confirmed nonce
-                    *guard = Some(used_nonce + U256::one());
-                 }
-                 // If current == used_nonce, it's handled by the first arm
-             }
         }
     }
+
+    /// The last nonce handed out, None until the first assignment or after a reset.
+    pub async fn last_assigned_nonce(&self) -> Option<U256> {
+        *self.current_nonce.lock().await
+    }
 }
 
 
@@ -112,11 +136,17 @@ pub async fn fetch_gas_price(client: Arc<SignerMiddleware<Provider<Http>, LocalW
     let fallback_prio_gwei_str = config.fallback_gas_price_gwei.unwrap_or(config.max_priority_fee_per_gas_gwei).to_string();
     let max_prio_wei: U256 = ethers::utils::parse_units(&max_prio_gwei_str, "gwei")?.into();
     let fallback_prio_wei: U256 = ethers::utils::parse_units(&fallback_prio_gwei_str, "gwei")?.into();
+    if let Some(strategy) = &config.priority_fee_strategy {
+        match fetch_strategy_gas_price(client.clone(), strategy, max_prio_wei).await {
+            Ok(gas_info) => return Ok(gas_info),
+            Err(e) => warn!(error = ?e, ?strategy, "Priority fee strategy failed, falling back to node estimate."),
+        }
+    }
     match client.estimate_eip1559_fees(None).await {
          Ok((max_fee, max_priority_fee)) => {
             let final_max_priority_fee = max_priority_fee.min(max_prio_wei);
             let current_base_fee = client.get_gas_price().await.unwrap_or(max_fee);
-            let required_max_fee = current_base_fee + final_max_priority_fee;
+            let required_max_fee = current_base_fee.saturating_add(final_max_priority_fee);
             let final_max_fee = max_fee.max(required_max_fee);
             debug!(%final_max_fee, %final_max_priority_fee, "EIP-1559 fees estimated.");
             Ok(GasInfo { max_fee_per_gas: final_max_fee, max_priority_fee_per_gas: final_max_priority_fee })
@@ -126,14 +156,14 @@ pub async fn fetch_gas_price(client: Arc<SignerMiddleware<Provider<Http>, LocalW
              match client.get_gas_price().await {
                  Ok(legacy_price) => {
                      let final_max_priority_fee = fallback_prio_wei.min(max_prio_wei);
-                     let final_max_fee = legacy_price + final_max_priority_fee;
+                     let final_max_fee = legacy_price.saturating_add(final_max_priority_fee);
                      debug!(%final_max_fee, %final_max_priority_fee, "Using legacy price fallback gas prices.");
                      Ok(GasInfo { max_fee_per_gas: final_max_fee, max_priority_fee_per_gas: final_max_priority_fee })
                  }
                  Err(e_legacy) => {
                     error!(error_eip1559=?e, error_legacy=?e_legacy, "ALERT: Both EIP-1559 and legacy gas price fetch failed.");
                      let final_max_priority_fee = fallback_prio_wei.min(max_prio_wei);
-                     let final_max_fee = final_max_priority_fee * 2;
+                     let final_max_fee = final_max_priority_fee.saturating_mul(U256::from(2));
                      warn!(%final_max_fee, %final_max_priority_fee, "Using purely config-based fallback gas prices. Risk of underpricing.");
                      Ok(GasInfo { max_fee_per_gas: final_max_fee, max_priority_fee_per_gas: final_max_priority_fee })
                  }
@@ -143,6 +173,51 @@ pub async fn fetch_gas_price(client: Arc<SignerMiddleware<Provider<Http>, LocalW
 }
 
 
+/// Gas price (gwei) profit simulations charge gas at: the live `eth_gasPrice` (base fee plus the node's
+/// suggested tip) times `config.simulation_gas_price_multiplier`, or `config.fallback_gas_price_gwei`
+/// (else the priority fee cap) when no live price is available.
+pub fn simulation_gas_price_gwei(live_gas_price_wei: Option<U256>, config: &Config) -> f64 {
+    match live_gas_price_wei {
+        Some(price) => price.to_f64_lossy() / 1e9 * config.simulation_gas_price_multiplier.max(1.0),
+        None => config.fallback_gas_price_gwei.unwrap_or(config.max_priority_fee_per_gas_gwei),
+    }
+}
+
+/// `simulation_gas_price_gwei` with the live price fetched from `client`; a failed fetch falls back.
+pub async fn fetch_simulation_gas_price_gwei<M: Middleware>(client: &M, config: &Config) -> f64 {
+    let live = match client.get_gas_price().await {
+        Ok(price) => Some(price),
+        Err(e) => {
+            warn!(error = ?e, "eth_gasPrice failed, simulating at the configured fallback gas price.");
+            None
+        }
+    };
+    simulation_gas_price_gwei(live, config)
+}
+
+/// Builds EIP-1559 fees from the latest base fee and the configured priority fee strategy.
+async fn fetch_strategy_gas_price(
+    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    strategy: &PriorityFeeStrategy,
+    max_prio_wei: U256,
+) -> Result<GasInfo> {
+    let latest = client.get_block(BlockNumber::Latest).await?.ok_or_else(|| eyre!("Latest block not found"))?;
+    let base_fee = latest.base_fee_per_gas.ok_or_else(|| eyre!("Latest block has no base fee (non EIP-1559 chain?)"))?;
+    let fee_history = match strategy {
+        PriorityFeeStrategy::RecentBlocksPercentile(p) => Some(
+            client.fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[*p as f64]).await
+                .wrap_err("eth_feeHistory failed")?,
+        ),
+        _ => None,
+    };
+    let priority_fee = compute_priority_fee(strategy, base_fee, fee_history.as_ref())?.min(max_prio_wei);
+    // Standard headroom: allow the base fee to double before the tx becomes unincludable.
+    let max_fee = base_fee.saturating_mul(U256::from(2)).saturating_add(priority_fee);
+    debug!(?strategy, %base_fee, %priority_fee, %max_fee, "Strategy-based EIP-1559 fees computed.");
+    Ok(GasInfo { max_fee_per_gas: max_fee, max_priority_fee_per_gas: priority_fee })
+}
+
+
 /// Calculates the minimum profit required for the transaction to be considered successful on-chain.
 // (remains unchanged)
 #[instrument(level = "debug", skip(config))]
@@ -157,8 +232,10 @@ fn calculate_min_profit_threshold(
     }
     let abs_buffer_wei = U256::from_str(&config.min_profit_abs_buffer_wei_str)
         .wrap_err("Failed to parse MIN_PROFIT_ABS_BUFFER_WEI from config")?;
-    let abs_buffer = I256::from_raw(abs_buffer_wei);
-    let bps_buffer = (simulated_net_profit_wei * I256::from(config.min_profit_buffer_bps)) / I256::from(10000);
+    let abs_buffer = u256_to_i256(abs_buffer_wei, "min profit absolute buffer")?;
+    let bps_buffer = simulated_net_profit_wei.checked_mul(I256::from(config.min_profit_buffer_bps))
+        .ok_or(ArithmeticOverflow("min profit bps buffer"))?
+        / I256::from(10000);
     let effective_buffer = std::cmp::max(bps_buffer, abs_buffer);
     debug!(config_bps=config.min_profit_buffer_bps, config_abs_wei=%abs_buffer_wei, calculated_bps_buffer=%bps_buffer, effective_buffer=%effective_buffer);
     let final_buffer = if effective_buffer >= simulated_net_profit_wei {
@@ -167,7 +244,7 @@ fn calculate_min_profit_threshold(
          effective_buffer
     };
     debug!(final_buffer=%final_buffer);
-    let min_profit_wei_i256 = simulated_net_profit_wei - final_buffer;
+    let min_profit_wei_i256 = simulated_net_profit_wei.saturating_sub(final_buffer);
     let min_profit_wei_u256 = if min_profit_wei_i256 > I256::zero() {
         min_profit_wei_i256.into_raw()
     } else {
@@ -179,6 +256,49 @@ fn calculate_min_profit_threshold(
 }
 
 
+/// Executor salt of a submission: `keccak256(abi.encode(route id, source block, loan amount))`. The same
+/// opportunity re-detected in its block (another log, another task) gets the same salt, so `claim_salt`
+/// drops the duplicate and the executor's salt guard would revert it if it got through.
+pub fn submission_salt(route: &RouteCandidate, loan_amount_wei: U256) -> U256 {
+    let preimage = encode(&[Token::String(route.id()), Token::Uint(U256::from(route.source_block.as_u64())), Token::Uint(loan_amount_wei)]);
+    U256::from_big_endian(&keccak256(preimage))
+}
+
+/// Builds the signed-ready flash loan transaction in the chain's envelope.
+/// Legacy chains pay `gas_info.max_fee_per_gas` as the flat gas price.
+pub fn build_submission_tx(
+    tx_type: TxType,
+    to: Address,
+    calldata: Bytes,
+    gas_limit: U256,
+    gas_info: &GasInfo,
+    nonce: U256,
+    chain_id: u64,
+) -> TypedTransaction {
+    match tx_type {
+        TxType::Eip1559 => Eip1559TransactionRequest::new()
+            .to(to).value(U256::zero()).data(calldata).gas(gas_limit)
+            .max_fee_per_gas(gas_info.max_fee_per_gas).max_priority_fee_per_gas(gas_info.max_priority_fee_per_gas)
+            .nonce(nonce).chain_id(chain_id)
+            .into(),
+        TxType::Legacy => TransactionRequest::new()
+            .to(to).value(U256::zero()).data(calldata).gas(gas_limit)
+            .gas_price(gas_info.max_fee_per_gas)
+            .nonce(nonce).chain_id(chain_id)
+            .into(),
+    }
+}
+
+/// `gas_info` with its priority fee raised to at least `priority_fee_per_gas`. The max fee rises by the
+/// same amount, keeping the base-fee headroom (and the flat price on legacy chains covers the bid too).
+pub fn with_priority_fee_floor(gas_info: GasInfo, priority_fee_per_gas: U256) -> GasInfo {
+    let raise = priority_fee_per_gas.saturating_sub(gas_info.max_priority_fee_per_gas);
+    GasInfo {
+        max_fee_per_gas: gas_info.max_fee_per_gas.saturating_add(raise),
+        max_priority_fee_per_gas: gas_info.max_priority_fee_per_gas.saturating_add(raise),
+    }
+}
+
 /// Constructs, submits, and monitors the arbitrage transaction using polling.
 #[instrument(skip_all, level = "info", fields(
     buy_pool = %route.buy_pool_addr,
@@ -194,96 +314,252 @@ pub async fn submit_arbitrage_transaction(
     loan_amount_wei: U256,
     simulated_net_profit_wei: I256,
     nonce_manager: Arc<NonceManager>,
+    deadline: Deadline,
 ) -> Result<TxHash> {
     info!("Attempting submission & monitoring");
     let config = &app_state.config;
-    // FIX: Prefix unused variable
-    let _start_time = SystemTime::now();
+    let audit_record = |event| AuditRecord::new(event, &route).loan_amount(loan_amount_wei).net_profit(simulated_net_profit_wei);
+    if let Err(expired) = deadline.check("pre-submission") {
+        Metrics::inc(&app_state.metrics.deadline_expired_opportunities);
+        app_state.audit.record(|| audit_record(AuditEvent::Expired).reason("pre-submission"));
+        return Err(expired.into());
+    }
+    // Held until this submission resolves; released on every return path
+    let _inflight_loan = match app_state.inflight_exposure.try_reserve(loan_amount_wei, config.max_total_inflight_loan_wei) {
+        Ok(reservation) => reservation,
+        Err(inflight_wei) => {
+            Metrics::inc(&app_state.metrics.inflight_exposure_rejections);
+            info!(%inflight_wei, cap_wei = %config.max_total_inflight_loan_wei, "In-flight loan cap reached, dropping opportunity.");
+            app_state.audit.record(|| audit_record(AuditEvent::Expired).reason(format!("in-flight loan cap ({} wei already in flight)", inflight_wei)));
+            return Err(eyre!("In-flight loan cap reached ({} wei in flight, cap {} wei)", inflight_wei, config.max_total_inflight_loan_wei));
+        }
+    };
+
+    // Latest head seen by the block stream (RPC fallback before the first block arrives)
+    let price_block = match app_state.block_timestamps.iter().map(|e| *e.key()).max() {
+        Some(b) => Some(b),
+        None => client.get_block_number().await.ok(),
+    };
+    if let Some(head) = price_block {
+        let late = blocks_late(route.source_block, head);
+        if late > config.max_blocks_late {
+            Metrics::inc(&app_state.metrics.late_block_opportunities);
+            warn!(source_block = %route.source_block, target_block = %target_block(route.source_block), %head, max_blocks_late = config.max_blocks_late, "Head is past the opportunity's target block. Dropping.");
+            app_state.audit.record(|| audit_record(AuditEvent::Expired).reason(format!("head {} is {} blocks past target {}", head, late, target_block(route.source_block))));
+            return Err(eyre!("Opportunity from block {} is {} blocks late (head {})", route.source_block, late, head));
+        }
+    }
 
     // --- Prepare Tx Data ---
     trace!("Step 1: Fetching gas price...");
     let gas_info = fetch_gas_price(client.clone(), config).await.wrap_err("ALERT: Failed gas price fetch pre-submission")?;
     trace!("Step 2: Calculating min profit threshold...");
-    let min_profit_wei_u256 = calculate_min_profit_threshold(simulated_net_profit_wei, config)
-        .wrap_err("ALERT: Failed to calculate minimum profit threshold")?;
+    // Forks/tests only: unprofitable routes go through the whole path with no on-chain profit floor
+    let allow_zero_profit = config.zero_profit_submission_allowed(client.signer().chain_id());
+    let min_profit_wei_u256 = if allow_zero_profit && simulated_net_profit_wei <= I256::zero() {
+        warn!(%simulated_net_profit_wei, "ALLOW_SUBMISSION_ZERO_PROFIT: submitting an unprofitable route with no min profit.");
+        U256::zero()
+    } else {
+        calculate_min_profit_threshold(simulated_net_profit_wei, config)
+            .wrap_err("ALERT: Failed to calculate minimum profit threshold")?
+    };
+    // Logging only: the price detection already cached (or the pool-derived one), never an oracle read here
+    let weth_usd = cached_weth_price_usd(&app_state, price_block.unwrap_or_default());
+    let to_usd = |wei: I256| net_profit_usd(&app_state, &route, wei, weth_usd);
+    let profit_usd = to_usd(simulated_net_profit_wei.max(I256::zero()));
+    let threshold_usd = u256_to_i256(min_profit_wei_u256, "min profit").ok().and_then(to_usd);
+    info!(weth_usd = ?weth_usd, profit_usd = ?profit_usd, ?threshold_usd, "Profit and on-chain threshold in USD.");
     trace!("Step 3: Generating salt...");
-    let salt = U256::from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos());
-    trace!("Step 4: Determining effective router address...");
-    let effective_router_addr = {
-         if route.buy_dex_type.is_velo_style() || route.sell_dex_type.is_velo_style() {
-            if route.buy_dex_type == DexType::Aerodrome || route.sell_dex_type == DexType::Aerodrome {
-                config.aerodrome_router_addr.ok_or_else(|| eyre!("Aerodrome Router address missing from config but needed for route"))?
-            } else {
-                config.velo_router_addr
-            }
-        } else {
-             // Should not happen in 2-swap arb, but use Velo as default
-             config.velo_router_addr
+    let salt = submission_salt(&route, loan_amount_wei);
+    trace!("Step 4: Validating swap directions and encoding user data...");
+    {
+        let buy_state = app_state.pool_states.get(&route.buy_pool_addr).ok_or_else(|| eyre!("No pool state for buy pool {:?}", route.buy_pool_addr))?;
+        let sell_state = app_state.pool_states.get(&route.sell_pool_addr).ok_or_else(|| eyre!("No pool state for sell pool {:?}", route.sell_pool_addr))?;
+        route.validate_directions(&buy_state, &sell_state).wrap_err("ALERT: Route swap directions do not match pool state")?;
+    }
+    let (leg_a, leg_b) = route_swap_legs(&app_state, &route)?;
+    // Floors come from the quotes detection priced the route with; only a loan amount detection never quoted
+    // (e.g. sized by the bundle simulation) is quoted here
+    let (simulated_out_a, simulated_out_b) = match app_state.leg_quotes.get(&route, loan_amount_wei) {
+        Some(quotes) => quotes,
+        None => {
+            debug!(route = %route.id(), %loan_amount_wei, "No detection quotes for this loan amount; quoting legs for slippage floors.");
+            simulate_route_legs(app_state.clone(), client.clone(), &route, loan_amount_wei).await
+                .wrap_err("ALERT: Failed to quote route legs for slippage floors")?
         }
     };
-    trace!("Step 5: Encoding user data...");
-    let user_data = encode_user_data( route.buy_pool_addr, route.sell_pool_addr, app_state.usdc_address, route.zero_for_one_a, route.buy_dex_type.is_velo_style(), route.sell_dex_type.is_velo_style(), effective_router_addr, min_profit_wei_u256, salt )?;
-
-    // --- Step 6: Estimate Gas with Timeout ---
-    trace!("Step 6: Estimating gas limit (timeout: {}s)...", GAS_ESTIMATION_TIMEOUT_SECS);
-    let gas_est_timeout = Duration::from_secs(GAS_ESTIMATION_TIMEOUT_SECS);
-    let gas_estimate_result = timeout(
-        gas_est_timeout,
-        estimate_flash_loan_gas(
-            client.clone(),
-            config.balancer_vault_address,
-            config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for gas estimate"))?,
-            app_state.weth_address, // Use loan token (WETH) from app_state
-            loan_amount_wei,
-            user_data.clone(),
-        )
-    ).await;
-
-    let estimated_gas_limit = match gas_estimate_result {
-        Ok(Ok(est)) => {
-            trace!("Gas estimation successful: {}", est);
-            est
+    let repay_wei = loan_amount_wei.saturating_add(flash_loan_fee_wei(&app_state, loan_amount_wei)?);
+    let reject_inconsistent = |e: eyre::Report| {
+        Metrics::inc(&app_state.metrics.simulation_divergence_rejections);
+        app_state.audit.record(|| audit_record(AuditEvent::SimulationFailed).reason(e.to_string()));
+        e.wrap_err("Submission aborted: inconsistent simulation")
+    };
+    validate_simulation_consistency(simulated_out_a, simulated_out_b, repay_wei, None, config.sim_divergence_tolerance_bps).map_err(reject_inconsistent)?;
+    let min_out_a = min_amount_out(simulated_out_a, config.max_slippage_bps);
+    let min_out_b = min_amount_out(simulated_out_b, config.max_slippage_bps);
+    debug!(%simulated_out_a, %min_out_a, %simulated_out_b, %min_out_b, slippage_bps = config.max_slippage_bps, "Per-swap slippage floors.");
+    let user_data = encode_user_data( config.executor_abi_version, leg_a, leg_b, route.token_out, min_profit_wei_u256, salt, min_out_a, min_out_b )?;
+
+    trace!("Step 4b: Preparing contract call...");
+    let executor_address = config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for flash loan target"))?;
+    let calldata = execute_flash_loan_calldata(app_state.weth_address, loan_amount_wei, user_data.clone());
+    // Replayable dump of the exact transaction, only with `log_failed_calldata` (the signer key never enters it)
+    let calldata_dump = |stage: &str, gas_info: &GasInfo, gas_limit: Option<U256>, nonce: Option<U256>| -> Option<FailedSubmission> {
+        if !config.log_failed_calldata {
+            return None;
         }
-        Ok(Err(e)) => {
-             // Propagate error if gas estimation fails within timeout
-            error!(error = ?e, "ALERT: Gas estimation failed pre-submission");
-            return Err(e.wrap_err("ALERT: Gas estimation failed pre-submission"));
+        error!(stage, to = ?executor_address, calldata = %calldata, %salt, gas_limit = ?gas_limit, max_fee = %gas_info.max_fee_per_gas, max_prio = %gas_info.max_priority_fee_per_gas, nonce = ?nonce, route = ?route, "Failed submission calldata.");
+        Some(FailedSubmission {
+            stage: stage.to_string(), to: executor_address, calldata: calldata.clone(), salt: salt.to_string(), gas_limit: gas_limit.map(|g| g.to_string()),
+            max_fee_per_gas: gas_info.max_fee_per_gas.to_string(), max_priority_fee_per_gas: gas_info.max_priority_fee_per_gas.to_string(),
+            nonce: nonce.map(|n| n.to_string()), route: format!("{:?}", route),
+        })
+    };
+
+    // --- Step 4c: Confirm through the executor (the real contract path, with floors and min profit) ---
+    if config.executor_callstatic_check {
+        trace!("Step 4c: Confirming profit with an eth_call of the submission...");
+        match simulate_via_executor_callstatic(client.provider(), app_state.weth_address, executor_address, &calldata).await? {
+            Some(outcome) => {
+                let confirmed_net_profit_wei = if outcome.succeeded {
+                    net_of_gas(config, app_state.weth_decimals, outcome.gross_profit_wei, outcome.gas_used, gas_info.max_fee_per_gas)?
+                } else {
+                    I256::min_value()
+                };
+                info!(modeled_profit_wei = %simulated_net_profit_wei, confirmed_profit_wei = %confirmed_net_profit_wei, succeeded = outcome.succeeded, gas_used = %outcome.gas_used, "Executor eth_call confirmation.");
+                validate_simulation_consistency(simulated_out_a, simulated_out_b, repay_wei, Some(&outcome), config.sim_divergence_tolerance_bps).map_err(reject_inconsistent)?;
+                if confirmed_net_profit_wei <= I256::zero() && allow_zero_profit {
+                    warn!(confirmed_profit_wei = %confirmed_net_profit_wei, "ALLOW_SUBMISSION_ZERO_PROFIT: submitting despite the executor eth_call.");
+                } else if confirmed_net_profit_wei <= I256::zero() {
+                    Metrics::inc(&app_state.metrics.callstatic_rejections);
+                    let reason = if outcome.succeeded { format!("executor eth_call nets {} wei", confirmed_net_profit_wei) } else { "executor eth_call reverted".to_string() };
+                    let dump = calldata_dump("callstatic", &gas_info, None, None);
+                    app_state.audit.record(|| audit_record(AuditEvent::SimulationFailed).reason(reason.clone()).failed_submission(dump));
+                    return Err(eyre!("Submission not confirmed: {}", reason));
+                }
+            }
+            None => warn!("State-override eth_call unavailable. Submitting without executor confirmation."),
         }
-        Err(_) => {
-            // Handle timeout specifically
-             error!(timeout_secs = gas_est_timeout.as_secs(), "ALERT: Gas estimation timed out pre-submission");
-             return Err(eyre!("ALERT: Gas estimation timed out after {}s", gas_est_timeout.as_secs()));
+    }
+
+    // --- Step 6: Learned or Profiled Gas Limit, or Estimate Gas with Timeout ---
+    let gas_shape = (route.buy_dex_type, route.sell_dex_type);
+    let learned_gas_limit = app_state.gas_usage_stats.learned_limit(&gas_shape, config.learned_gas_min_samples);
+    let preset_limit = preset_gas_limit(config, learned_gas_limit, &gas_shape);
+    let estimated_gas_limit = if let Some(preset) = preset_limit {
+        debug!(%preset, learned = learned_gas_limit.is_some(), shape = ?gas_shape, "Using learned or profiled gas limit; skipping estimation.");
+        None
+    } else {
+        trace!("Step 6: Estimating gas limit (timeout: {}s)...", GAS_ESTIMATION_TIMEOUT_SECS);
+        let gas_est_timeout = Duration::from_secs(GAS_ESTIMATION_TIMEOUT_SECS);
+        let gas_estimate_result = timeout(
+            gas_est_timeout,
+            estimate_flash_loan_gas(
+                client.clone(),
+                executor_address,
+                app_state.weth_address, // Use loan token (WETH) from app_state
+                loan_amount_wei,
+                user_data,
+                None, // Submissions always estimate against the latest block
+            )
+        ).await;
+
+        match gas_estimate_result {
+            Ok(Ok(est)) => {
+                trace!("Gas estimation successful: {}", est);
+                Some(est)
+            }
+            Ok(Err(e)) => {
+                let revert_reason = e.downcast_ref::<ethers::middleware::signer::SignerMiddlewareError<Provider<Http>, LocalWallet>>().and_then(revert_reason_from_error);
+                let Some(revert_reason) = revert_reason else {
+                    // No revert data: the RPC failed rather than the call, so the fallback limit would be a blind guess
+                    error!(error = ?e, "ALERT: Gas estimation failed pre-submission without a revert. Dropping.");
+                    let dump = calldata_dump("gas_estimation", &gas_info, None, None);
+                    app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).reason(format!("gas estimation failed: {:#}", e)).failed_submission(dump));
+                    return Err(e.wrap_err("ALERT: Gas estimation failed pre-submission"));
+                };
+                // Estimation reverted: fall back to the configured limit and let the on-chain min-profit check protect us
+                warn!(error = ?e, %revert_reason, fallback_gas_limit = config.submission_gas_limit_default, "Gas estimation reverted pre-submission. Using configured gas limit.");
+                if let Some(dump) = calldata_dump("gas_estimation", &gas_info, None, None) {
+                    app_state.audit.record(|| audit_record(AuditEvent::SimulationFailed).reason(format!("gas estimation reverted: {}", revert_reason)).failed_submission(Some(dump)));
+                }
+                None
+            }
+            Err(_) => {
+                // Handle timeout specifically
+                 error!(timeout_secs = gas_est_timeout.as_secs(), "ALERT: Gas estimation timed out pre-submission");
+                 let dump = calldata_dump("gas_estimation", &gas_info, None, None);
+                 app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).reason("gas estimation timed out").failed_submission(dump));
+                 return Err(eyre!("ALERT: Gas estimation timed out after {}s", gas_est_timeout.as_secs()));
+            }
         }
     };
 
     trace!("Step 7: Calculating final gas limit...");
-    let final_gas_limit = std::cmp::max(estimated_gas_limit * (100 + config.gas_limit_buffer_percentage) / 100, U256::from(config.min_flashloan_gas_limit));
+    let final_gas_limit = match preset_limit {
+        Some(preset) => preset,
+        None => submission_gas_limit(
+            estimated_gas_limit,
+            config.gas_limit_buffer_percentage,
+            config.submission_gas_limit_default,
+            config.min_flashloan_gas_limit,
+            config.max_gas_limit,
+        )?,
+    };
+    debug!(estimate = ?estimated_gas_limit, %final_gas_limit, "Final gas limit.");
+    // Last check before a nonce is consumed; gas estimation above is the final slow RPC.
+    if let Err(expired) = deadline.check("post gas estimation") {
+        Metrics::inc(&app_state.metrics.deadline_expired_opportunities);
+        warn!(elapsed_ms = expired.elapsed.as_millis() as u64, "Opportunity expired before submission. Dropping.");
+        app_state.audit.record(|| audit_record(AuditEvent::Expired).gas_limit(final_gas_limit).reason("post gas estimation"));
+        return Err(expired.into());
+    }
+    // Claimed only now, so attempts dropped by the checks above do not use up the slot
+    let global_interval = Duration::from_millis(config.min_submission_interval_ms);
+    let pool_interval = Duration::from_millis(config.min_pool_submission_interval_ms);
+    if let Err(wait) = app_state.submission_throttle.try_claim(&route, global_interval, pool_interval, Instant::now()) {
+        Metrics::inc(&app_state.metrics.throttled_submissions);
+        info!(wait_ms = wait.as_millis() as u64, "Submission throttled, dropping opportunity.");
+        app_state.audit.record(|| audit_record(AuditEvent::Expired).gas_limit(final_gas_limit).reason(format!("throttled ({}ms until next submission slot)", wait.as_millis())));
+        return Err(eyre!("Submission throttled for another {}ms", wait.as_millis()));
+    }
+    // The competitive bid simulation subtracted from profit is actually paid, as priority fee over the gas limit
+    let gas_info = if config.competition_priority_fee_bps_of_profit > 0 {
+        let swap_profit_wei = u256_to_i256(simulated_out_b, "swap output")?.saturating_sub(u256_to_i256(loan_amount_wei, "loan amount")?);
+        let bid_wei = competitive_priority_fee(swap_profit_wei, config.competition_priority_fee_bps_of_profit)?;
+        let bid_per_gas = f64_to_wei(implied_priority_fee_gwei(config, app_state.weth_decimals, bid_wei, final_gas_limit), 9).wrap_err("Invalid competitive priority fee")?;
+        debug!(%bid_wei, %bid_per_gas, strategy_priority_fee = %gas_info.max_priority_fee_per_gas, "Competitive priority fee bid.");
+        with_priority_fee_floor(gas_info, bid_per_gas)
+    } else {
+        gas_info
+    };
+    // Also claimed last, so a salt is only used up by an attempt that goes on to submit
+    if !app_state.claim_salt(salt, price_block.unwrap_or_default()) {
+        warn!(%salt, route = ?route, "Salt already submitted this session, skipping duplicate attempt.");
+        app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).reason(format!("duplicate salt {}", salt)));
+        return Err(eyre!("Duplicate salt {}", salt));
+    }
     trace!("Step 8: Getting next nonce...");
     let nonce = nonce_manager.get_next_nonce(client.clone()).await.wrap_err("ALERT: Nonce fetch failed pre-submission")?;
-    trace!("Step 9: Preparing contract call...");
-    let balancer_contract = BalancerVault::new(config.balancer_vault_address, client.clone());
-    let executor_address = config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for flash loan target"))?;
-    let calldata = balancer_contract.flash_loan( executor_address, vec![app_state.weth_address], vec![loan_amount_wei], user_data, ).calldata().ok_or_else(|| eyre!("ALERT: Calldata generation failed"))?;
-    trace!("Step 10: Constructing transaction request...");
-    let tx_request = Eip1559TransactionRequest::new().to(config.balancer_vault_address).value(U256::zero()).data(calldata).gas(final_gas_limit).max_fee_per_gas(gas_info.max_fee_per_gas).max_priority_fee_per_gas(gas_info.max_priority_fee_per_gas).nonce(nonce).chain_id(client.signer().chain_id());
-    info!(nonce = %nonce, gas_limit = %final_gas_limit, max_fee = %gas_info.max_fee_per_gas, max_prio = %gas_info.max_priority_fee_per_gas, min_profit_req_wei = %min_profit_wei_u256, "Constructed Tx Request");
-    let typed_tx: TypedTransaction = tx_request.clone().into();
-    trace!("Step 11: Signing transaction...");
-    let signature = client.signer().sign_transaction(&typed_tx).await.wrap_err("ALERT: Signing failed pre-submission")?;
-    let rlp_signed = typed_tx.rlp_signed(&signature);
-    let rlp_hex = format!("0x{}", hex::encode(rlp_signed.as_ref()));
-    trace!("Transaction signed. RLP Hex: {}", rlp_hex); // Be careful logging this if sensitive
+    trace!("Step 9: Constructing transaction request...");
+    let mut typed_tx = build_submission_tx(app_state.tx_type, executor_address, calldata.clone(), final_gas_limit, &gas_info, nonce, client.signer().chain_id());
+    info!(tx_type = ?app_state.tx_type, nonce = %nonce, gas_limit = %final_gas_limit, max_fee = %gas_info.max_fee_per_gas, max_prio = %gas_info.max_priority_fee_per_gas, min_profit_req_wei = %min_profit_wei_u256, "Constructed Tx Request");
 
-    // --- Step 12: Attempt Submissions Sequentially ---
-    trace!("Step 12: Attempting sequential submission...");
-    let submitted_tx_hash = match timeout(Duration::from_secs(TX_SUBMISSION_TIMEOUT_SECS), submit_sequentially( &config, client.provider(), client.clone(), &rlp_hex, &rlp_signed )).await {
+    // --- Step 10: Sign and Attempt Submissions Sequentially ---
+    trace!("Step 10: Signing and attempting sequential submission...");
+    let submission = timeout(Duration::from_secs(TX_SUBMISSION_TIMEOUT_SECS), submit_sequentially(config, client.as_ref(), &nonce_manager, &mut typed_tx)).await;
+    let nonce = typed_tx.nonce().copied().unwrap_or(nonce); // Changes when a "nonce too low" rejection was re-synced
+    let submitted_tx_hash = match submission {
         Ok(Ok(hash)) => {
             tracing::Span::current().record("tx_hash", tracing::field::debug(hash));
             info!(%hash, "Transaction submitted successfully.");
+            app_state.audit.record(|| audit_record(AuditEvent::Submitted).gas_limit(final_gas_limit).tx_hash(hash));
             hash
         },
         Ok(Err(submission_error)) => {
             error!(error = ?submission_error, route = ?route, "ALERT: All transaction submission attempts failed.");
+            let dump = calldata_dump("submission", &gas_info, Some(final_gas_limit), Some(nonce));
+            app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).gas_limit(final_gas_limit).reason(format!("{:#}", submission_error)).failed_submission(dump));
             if submission_error.to_string().to_lowercase().contains("nonce") || submission_error.to_string().to_lowercase().contains("known transaction") {
                 warn!("Submission error likely due to nonce, resetting manager state.");
                 nonce_manager.handle_nonce_error().await;
@@ -292,14 +568,16 @@ pub async fn submit_arbitrage_transaction(
         }
         Err(_) => {
              error!(timeout_secs = TX_SUBMISSION_TIMEOUT_SECS, route = ?route, "ALERT: Timeout during transaction submission attempt.");
+             let dump = calldata_dump("submission", &gas_info, Some(final_gas_limit), Some(nonce));
+             app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).gas_limit(final_gas_limit).reason("submission timed out").failed_submission(dump));
              warn!("Submission timeout, resetting nonce manager state.");
              nonce_manager.handle_nonce_error().await;
              return Err(eyre!("Timeout submitting transaction"));
         }
     };
 
-    // --- Step 13: Monitor Submitted Transaction via Polling ---
-    // (Monitoring logic remains unchanged)
+    // --- Step 11: Monitor Submitted Transaction via Polling ---
+    // A receipt only counts once `config.confirmations_required` blocks deep (see `receipt_at_depth`)
     info!(%submitted_tx_hash, "Monitoring transaction confirmation (Polling every {}ms, Timeout: {}s)...", TX_POLLING_INTERVAL_MS, TX_CONFIRMATION_TIMEOUT_SECS);
     let confirmation_start_time = SystemTime::now();
     let mut poll_count = 0;
@@ -307,6 +585,7 @@ pub async fn submit_arbitrage_transaction(
     loop {
         if confirmation_start_time.elapsed()? > Duration::from_secs(TX_CONFIRMATION_TIMEOUT_SECS) {
             warn!(%submitted_tx_hash, timeout_secs = TX_CONFIRMATION_TIMEOUT_SECS, route = ?route, "ALERT: Timeout waiting for transaction confirmation via polling.");
+            app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).gas_limit(final_gas_limit).tx_hash(submitted_tx_hash).reason("confirmation timed out"));
             nonce_manager.handle_nonce_error().await;
             return Err(eyre!("Timeout confirming tx {}", submitted_tx_hash));
         }
@@ -314,26 +593,89 @@ pub async fn submit_arbitrage_transaction(
         poll_count += 1;
         trace!(%submitted_tx_hash, poll_attempt = poll_count, "Polling for transaction receipt...");
 
-        match client.get_transaction_receipt(submitted_tx_hash).await {
+        match receipt_at_depth(client.as_ref(), submitted_tx_hash, config.confirmations_required).await {
             Ok(Some(receipt)) => {
                 let gas_used = receipt.gas_used.unwrap_or_default();
                 let effective_gas_price = receipt.effective_gas_price.unwrap_or_default();
-                let gas_cost_eth = format_units(gas_used * effective_gas_price, "ether").unwrap_or_default();
+                let gas_cost_wei = gas_used.saturating_mul(effective_gas_price);
+                let gas_cost_eth = format_units(gas_cost_wei, "ether").unwrap_or_default();
+                // Profit is denominated in the loan token; gas is paid in the native token
+                let gas_cost_loan_wei = gas_cost_in_loan_token(gas_cost_wei, config.gas_token_price_in_loan_token, app_state.weth_decimals).unwrap_or(gas_cost_wei);
 
                 if receipt.status == Some(TX_SUCCESS_STATUS) {
                      info!(tx_hash = %receipt.transaction_hash, block = %receipt.block_number.unwrap_or_default(), gas_used = %gas_used, gas_cost_eth = %gas_cost_eth, route = ?route, "ALERT: ✅✅✅ Tx Confirmed & Succeeded!");
                      nonce_manager.confirm_nonce_used(nonce).await;
+                     app_state.gas_usage_stats.record(gas_shape, gas_used);
+                     app_state.pool_cooldowns.record_success(&route);
+                     Metrics::set(&app_state.metrics.pools_in_cooldown, app_state.pool_cooldowns.active(Instant::now()) as u64);
+                     let realized_gross = match realized_gross_profit(client.clone(), &app_state, &receipt).await {
+                         Ok(realized) => Some(realized),
+                         Err(e) => { warn!(error = ?e, "Failed to read realized profit; accuracy check and profit share skipped."); None }
+                     };
+                     let realized_net_profit = realized_gross.and_then(|realized| {
+                         let realized_net = u256_to_i256(gas_cost_loan_wei, "gas cost").ok().map(|g| realized.saturating_sub(g));
+                         if let Some(realized_net) = realized_net { check_profit_accuracy(&app_state, simulated_net_profit_wei, realized_net); }
+                         realized_net
+                     });
+                     if config.executor_residual_check {
+                         match assert_executor_clean(client.clone(), &app_state, route.token_out).await {
+                             Ok(Some(_)) => if let Some(recipient) = config.sweep_recipient {
+                                 match sweep_executor_token(client.clone(), &app_state, &nonce_manager, route.token_out, recipient).await {
+                                     Ok(Some((sweep_hash, amount))) => info!(%sweep_hash, %amount, token = ?route.token_out, ?recipient, "Swept residual intermediate token from executor."),
+                                     Ok(None) => {}
+                                     Err(e) => warn!(error = ?e, "ALERT: Residual intermediate-token sweep failed."),
+                                 }
+                             },
+                             Ok(None) => {}
+                             Err(e) => warn!(error = ?e, "Executor residual check failed."),
+                         }
+                     }
+                     let leg_slippage = record_leg_slippage(&app_state, &route, &receipt, simulated_out_a, simulated_out_b);
+                     app_state.audit.record(|| {
+                         let record = audit_record(AuditEvent::Confirmed).gas_limit(final_gas_limit).gas_cost(gas_cost_wei).tx_hash(submitted_tx_hash).leg_slippage(&leg_slippage);
+                         match realized_net_profit { Some(realized) => record.realized_profit(realized), None => record }
+                     });
+                     // Realized net profit when it could be read, else the simulated one
+                     let (profit_kind, alert_profit_wei) = match realized_net_profit { Some(realized) => ("realized", realized), None => ("simulated", simulated_net_profit_wei) };
+                     if u256_to_i256(config.alert_profit_threshold_wei, "alert threshold").is_ok_and(|threshold| alert_profit_wei >= threshold) {
+                         let profit_weth = format_units(alert_profit_wei, app_state.weth_decimals as u32).unwrap_or_default();
+                         let usd = to_usd(alert_profit_wei).map(|v| format!(" ≈ ${:.2}", v)).unwrap_or_default();
+                         send_alert(config, AlertLevel::Info, format!("Arbitrage succeeded: tx {:?}, {} profit {} WETH{} (gas {} ETH)", submitted_tx_hash, profit_kind, profit_weth, usd, gas_cost_eth));
+                     }
+                     if let Some(realized) = realized_gross.filter(|_| config.profit_sharing_bps_for_devs > 0) {
+                         match pay_profit_share(client.clone(), &app_state, &nonce_manager, client.address(), realized.max(I256::zero()).into_raw(), gas_cost_loan_wei).await {
+                             Ok(Some((share_hash, share))) => app_state.audit.record(|| audit_record(AuditEvent::ProfitShared).tx_hash(share_hash)
+                                 .reason(format!("{} wei ({} bps) to {:?}", share, config.profit_sharing_bps_for_devs, config.profit_share_recipient.unwrap_or_default()))),
+                             Ok(None) => {}
+                             Err(e) => warn!(error = ?e, "ALERT: Profit share transfer failed."),
+                         }
+                     }
+                     if config.auto_unwrap_profit {
+                         match unwrap_weth_profit(client.clone(), &app_state, &nonce_manager, client.address()).await {
+                             Ok(Some(unwrap_hash)) => app_state.audit.record(|| audit_record(AuditEvent::Unwrapped).tx_hash(unwrap_hash)),
+                             Ok(None) => {}
+                             Err(e) => warn!(error = ?e, "ALERT: Profit unwrap failed; WETH left in executor/wallet."),
+                         }
+                     }
                      return Ok(submitted_tx_hash);
                 } else {
-                     error!(tx_hash = %submitted_tx_hash, status = ?receipt.status, block = %receipt.block_number.unwrap_or_default(), gas_used = %gas_used, gas_cost_eth = %gas_cost_eth, route = ?route, "ALERT: ❌ Tx Confirmed but REVERTED on-chain!");
                      nonce_manager.confirm_nonce_used(nonce).await;
-                     // Consider logging revert reason if available from trace
-                     // let trace = client.trace_transaction(submitted_tx_hash).await; etc.
-                     return Err(eyre!("Transaction reverted on-chain: {}", submitted_tx_hash));
+                     // Replay against the parent block's state to recover the revert data
+                     let mut replay_tx = typed_tx.clone();
+                     replay_tx.set_from(client.address());
+                     let parent_block = receipt.block_number.map(|b| BlockId::from(b.saturating_sub(U64::one())));
+                     let revert_reason = decode_revert_reason(client.as_ref(), &replay_tx, parent_block).await;
+                     error!(tx_hash = %submitted_tx_hash, status = ?receipt.status, block = %receipt.block_number.unwrap_or_default(), gas_used = %gas_used, gas_cost_eth = %gas_cost_eth, %revert_reason, route = ?route, "ALERT: ❌ Tx Confirmed but REVERTED on-chain!");
+                     let dump = calldata_dump("on_chain", &gas_info, Some(final_gas_limit), Some(nonce));
+                     app_state.audit.record(|| audit_record(AuditEvent::Reverted).gas_limit(final_gas_limit).gas_cost(gas_cost_wei).tx_hash(submitted_tx_hash)
+                         .reason(format!("status {:?} in block {}: {}", receipt.status, receipt.block_number.unwrap_or_default(), revert_reason)).failed_submission(dump));
+                     app_state.pool_cooldowns.record_failure(&route, Duration::from_secs(config.pool_failure_cooldown_secs), Instant::now());
+                     Metrics::set(&app_state.metrics.pools_in_cooldown, app_state.pool_cooldowns.active(Instant::now()) as u64);
+                     return Err(eyre!("Transaction reverted on-chain: {}: {}", submitted_tx_hash, revert_reason));
                 }
             }
             Ok(None) => {
-                 trace!(%submitted_tx_hash, "Transaction still pending...");
+                 trace!(%submitted_tx_hash, confirmations_required = config.confirmations_required, "Transaction still pending or not yet final...");
                  if poll_count == TX_STALLED_POLL_COUNT {
                      warn!(%submitted_tx_hash, polls = poll_count, "Transaction has not confirmed after {} polls (~{}s). Might be stalled or mempool is busy.", TX_STALLED_POLL_COUNT, (TX_STALLED_POLL_COUNT as u64 * TX_POLLING_INTERVAL_MS) / 1000);
                  }
@@ -344,6 +686,7 @@ pub async fn submit_arbitrage_transaction(
                  warn!(%submitted_tx_hash, error = ?provider_err, "Error fetching transaction receipt. Retrying polling...");
                  if provider_err.to_string().contains("transaction not found") {
                      error!(%submitted_tx_hash, "ALERT: Transaction likely dropped or replaced (not found by provider). Resetting nonce.");
+                     app_state.audit.record(|| audit_record(AuditEvent::SubmissionFailed).gas_limit(final_gas_limit).tx_hash(submitted_tx_hash).reason("dropped or replaced"));
                      nonce_manager.handle_nonce_error().await;
                      return Err(eyre!("Transaction likely dropped/replaced: {}", submitted_tx_hash));
                  }
@@ -354,75 +697,296 @@ pub async fn submit_arbitrage_transaction(
     }
 }
 
-// --- Helper functions (send_alchemy_private_tx, send_flashbots_private_tx, submit_sequentially) ---
-// (remain unchanged)
-async fn send_alchemy_private_tx( provider: &Provider<Http>, rlp_hex: &str ) -> Result<TxHash> {
-    let method="alchemy_sendPrivateTransaction";
-    let params=AlchemyPrivateTxParams{tx:rlp_hex};
-    provider.request(method,[serde_json::to_value(params)?]).await
-        .map_err(|e|eyre!("Alchemy RPC error: {}", e.to_string()))
+/// The receipt of `tx_hash` once it is `confirmations` blocks deep (the inclusion block counts as one).
+/// None while the transaction is pending or shallower; a reorg that drops it reads as pending again.
+pub async fn receipt_at_depth<M: Middleware>(client: &M, tx_hash: TxHash, confirmations: u64) -> Result<Option<TransactionReceipt>>
+where
+    M::Error: 'static + Send + Sync,
+{
+    let Some(receipt) = client.get_transaction_receipt(tx_hash).await? else { return Ok(None) };
+    if confirmations <= 1 {
+        return Ok(Some(receipt));
+    }
+    let Some(included) = receipt.block_number else { return Ok(None) };
+    let head = client.get_block_number().await?;
+    let depth = head.as_u64().saturating_add(1).saturating_sub(included.as_u64());
+    if depth < confirmations {
+        trace!(%tx_hash, %included, %head, depth, confirmations, "Receipt not yet final.");
+        return Ok(None);
+    }
+    Ok(Some(receipt))
 }
-async fn send_flashbots_private_tx( provider: &Provider<Http>, rlp_hex: &str ) -> Result<TxHash> {
-    let method="eth_sendPrivateRawTransaction";
-    let params=[rlp_hex];
-    provider.request(method,params).await
-        .map_err(|e|eyre!("Flashbots RPC error: {}", e.to_string()))
+
+/// Realizes WETH profit as native ETH: sweeps any WETH held by the executor to `wallet` (owner-only
+/// `withdrawToken`), then unwraps the wallet's WETH above `config.min_weth_working_balance` via
+/// `IWETH9::withdraw`. Both txs take their nonce from the nonce manager. Returns the unwrap tx hash, or
+/// None when the wallet holds no WETH beyond its working balance.
+pub async fn unwrap_weth_profit<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    wallet: Address,
+) -> Result<Option<TxHash>> where M::Error: 'static + Send + Sync {
+    let weth = IWETH9::new(app_state.weth_address, client.clone());
+    sweep_executor_weth(client.clone(), app_state, nonce_manager, wallet).await?;
+    let balance = weth.balance_of(wallet).call().await.wrap_err("Failed to read wallet WETH balance")?;
+    let working_balance = app_state.config.min_weth_working_balance;
+    let amount = balance.saturating_sub(working_balance);
+    if amount.is_zero() {
+        debug!(%balance, %working_balance, "No WETH above the working balance to unwrap.");
+        return Ok(None);
+    }
+    let hash = send_and_confirm(client.clone(), app_state.tx_type, nonce_manager, weth.withdraw(amount)).await.wrap_err("WETH unwrap failed")?;
+    info!(%hash, %amount, %working_balance, "💱 Unwrapped WETH profit to ETH.");
+    Ok(Some(hash))
 }
-#[instrument(level="debug", skip(config, _provider, client, rlp_hex, rlp_signed))]
-async fn submit_sequentially(
-    config: &Config,
-    _provider: &Provider<Http>,
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    rlp_hex: &str,
-    rlp_signed: &Bytes
-) -> Result<TxHash> {
-    async fn try_relay(url: &str, rlp_hex: &str) -> Result<TxHash> {
-        debug!("Attempting submission via relay: {}", url);
-        let relay_provider = Provider::<Http>::try_from(url.to_string())
-             .wrap_err_with(|| format!("Failed to create provider for relay: {}", url))?;
-        let result = if url.contains("alchemy") {
-            send_alchemy_private_tx(&relay_provider, rlp_hex).await
-        } else {
-            send_flashbots_private_tx(&relay_provider, rlp_hex).await
-        };
-        match result {
-            Ok(tx_hash) => {
-                info!(%tx_hash, relay = url, "Submitted via Private Relay.");
-                Ok(tx_hash)
-            }
-            Err(e) => {
-                warn!(error = ?e, relay = url, "Private Relay submission failed.");
-                Err(e)
+
+/// The profit share for `bps` basis points of the realized net profit (realized WETH gain minus gas).
+/// Zero when `bps` is zero or the trade did not net a profit; never exceeds the realized gain.
+pub fn profit_share_amount(realized_profit_wei: U256, gas_cost_wei: U256, bps: u64) -> U256 {
+    let net_profit = realized_profit_wei.saturating_sub(gas_cost_wei);
+    let share = net_profit.full_mul(U256::from(bps.min(10_000))) / U256::from(10_000u64);
+    U256::try_from(share).unwrap_or(U256::zero()).min(realized_profit_wei)
+}
+
+/// Pays `config.profit_sharing_bps_for_devs` of a confirmed arb's realized net profit to
+/// `config.profit_share_recipient`. `realized` is the arb's gross profit (see `realized_gross_profit`); the
+/// executor's WETH is swept to `wallet` first and the share is transferred from there. `gas_cost_wei` is in
+/// loan-token wei (see `gas_cost_in_loan_token`). Returns the transfer hash and amount, or None when there is
+/// nothing to share.
+pub async fn pay_profit_share<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    wallet: Address,
+    realized: U256,
+    gas_cost_wei: U256,
+) -> Result<Option<(TxHash, U256)>> where M::Error: 'static + Send + Sync {
+    let config = &app_state.config;
+    let (bps, Some(recipient)) = (config.profit_sharing_bps_for_devs, config.profit_share_recipient) else { return Ok(None) };
+    if bps == 0 {
+        return Ok(None);
+    }
+    let weth = IERC20::new(app_state.weth_address, client.clone());
+    let share = profit_share_amount(realized, gas_cost_wei, bps);
+    if share.is_zero() {
+        debug!(%realized, %gas_cost_wei, "No net profit to share.");
+        return Ok(None);
+    }
+    sweep_executor_weth(client.clone(), app_state, nonce_manager, wallet).await?;
+    let hash = send_and_confirm(client.clone(), app_state.tx_type, nonce_manager, weth.transfer(recipient, share)).await.wrap_err("Profit share transfer failed")?;
+    info!(%hash, %share, %realized, bps, ?recipient, "🤝 Profit share paid.");
+    Ok(Some((hash, share)))
+}
+
+/// The profit reported by `executor`'s `ProfitRealized(int256)` log in `receipt` (final loan-token balance
+/// less loan and fee, gross of gas). None when there is no such log (executor older than 2.4.1) or its data
+/// is not a single word.
+pub fn realized_profit_from_receipt(receipt: &TransactionReceipt, executor: Address) -> Option<I256> {
+    receipt.logs.iter()
+        .filter(|log| log.address == executor && log.topics.first() == Some(&*PROFIT_REALIZED_TOPIC))
+        .find_map(|log| (log.data.len() == 32).then(|| I256::from_raw(U256::from_big_endian(&log.data))))
+}
+
+/// The executor's WETH gain across the block of `receipt` (gross of gas).
+pub async fn realized_executor_profit<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, receipt: &TransactionReceipt) -> Result<U256>
+where M::Error: 'static + Send + Sync {
+    let executor = app_state.config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for realized profit"))?;
+    let block = receipt.block_number.ok_or_else(|| eyre!("Receipt has no block number"))?;
+    let weth = IERC20::new(app_state.weth_address, client);
+    let before = weth.balance_of(executor).block(block.saturating_sub(U64::one())).call().await.wrap_err("Failed to read pre-arb executor WETH balance")?;
+    let after = weth.balance_of(executor).block(block).call().await.wrap_err("Failed to read post-arb executor WETH balance")?;
+    Ok(after.saturating_sub(before))
+}
+
+/// The executor's gross profit on the arb in `receipt`: its `ProfitRealized` log (executors from 2.4.1),
+/// else its WETH gain across the block (`realized_executor_profit`).
+pub async fn realized_gross_profit<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, receipt: &TransactionReceipt) -> Result<I256>
+where M::Error: 'static + Send + Sync {
+    if let Some(profit) = app_state.config.arb_executor_address.and_then(|executor| realized_profit_from_receipt(receipt, executor)) {
+        Metrics::inc(&app_state.metrics.profit_realized_events);
+        return Ok(profit);
+    }
+    let realized = realized_executor_profit(client, app_state, receipt).await?;
+    Ok(u256_to_i256(realized, "realized profit")?)
+}
+
+/// Records the predicted-vs-realized profit error of a confirmed arb in the rolling bias metrics and
+/// warns when the bias persists beyond `config.profit_bias_warn_bps`. Returns the window mean (bps).
+pub fn check_profit_accuracy(app_state: &AppState, predicted_wei: I256, realized_wei: I256) -> Option<i64> {
+    let error_bps = prediction_error_bps(predicted_wei, realized_wei)?;
+    let (mean_bps, samples) = app_state.profit_accuracy.record(error_bps);
+    Metrics::set(&app_state.metrics.profit_overestimate_bps, mean_bps.max(0) as u64);
+    Metrics::set(&app_state.metrics.profit_underestimate_bps, mean_bps.min(0).unsigned_abs());
+    info!(%predicted_wei, %realized_wei, error_bps, mean_bps, samples, "🎯 Simulation accuracy check.");
+    if let Some(bias) = ProfitAccuracy::persistent_bias(mean_bps, samples, app_state.config.profit_bias_warn_bps) {
+        warn!(mean_bps = bias, samples, threshold_bps = app_state.config.profit_bias_warn_bps,
+            "ALERT: Simulation profit bias persists ({}); recalibrate fee/gas assumptions.", if bias > 0 { "overestimating" } else { "underestimating" });
+    }
+    Some(mean_bps)
+}
+
+/// Moves any WETH held by the executor to `wallet` via the owner-only `withdrawToken`.
+async fn sweep_executor_weth<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, nonce_manager: &NonceManager, wallet: Address) -> Result<()>
+where M::Error: 'static + Send + Sync {
+    if let Some((hash, amount)) = sweep_executor_token(client, app_state, nonce_manager, app_state.weth_address, wallet).await? {
+        info!(%hash, %amount, "Swept executor WETH to wallet.");
+    }
+    Ok(())
+}
+
+/// Moves the executor's whole balance of `token` to `recipient` via the owner-only `withdrawToken`.
+/// Returns the sweep tx hash and amount, or None when the executor holds none (or is not configured).
+pub async fn sweep_executor_token<M: Middleware + 'static>(
+    client: Arc<M>,
+    app_state: &AppState,
+    nonce_manager: &NonceManager,
+    token: Address,
+    recipient: Address,
+) -> Result<Option<(TxHash, U256)>> where M::Error: 'static + Send + Sync {
+    let Some(executor) = app_state.config.arb_executor_address else { return Ok(None) };
+    let held = IERC20::new(token, client.clone()).balance_of(executor).call().await.wrap_err_with(|| format!("Failed to read executor balance of {:?}", token))?;
+    if held.is_zero() {
+        return Ok(None);
+    }
+    let sweep = ArbitrageExecutor::new(executor, client.clone()).withdraw_token(token, recipient);
+    let hash = send_and_confirm(client, app_state.tx_type, nonce_manager, sweep).await.wrap_err_with(|| format!("Executor sweep of {:?} failed", token))?;
+    Ok(Some((hash, held)))
+}
+
+/// Checks that a confirmed arb left the executor without `token` (the route's intermediate token): a
+/// correct arb swaps all of it back into the loan token. A balance above `config.executor_dust_threshold`
+/// points at a bug or stuck funds, so it is warned about, alerted and counted. Returns the residual when
+/// above the threshold.
+pub async fn assert_executor_clean<M: Middleware + 'static>(client: Arc<M>, app_state: &AppState, token: Address) -> Result<Option<U256>>
+where M::Error: 'static + Send + Sync {
+    let config = &app_state.config;
+    let executor = config.arb_executor_address.ok_or_else(|| eyre!("Executor address missing for residual check"))?;
+    let held = IERC20::new(token, client).balance_of(executor).call().await.wrap_err("Failed to read executor intermediate-token balance")?;
+    if held <= config.executor_dust_threshold {
+        trace!(?token, %held, "Executor holds no residual intermediate token.");
+        return Ok(None);
+    }
+    Metrics::inc(&app_state.metrics.executor_residual_detections);
+    warn!(?executor, ?token, residual = %held, dust_threshold = %config.executor_dust_threshold, "ALERT: Executor holds a residual intermediate-token balance after an arb.");
+    send_alert(config, AlertLevel::Warning, format!("Executor {:?} holds {} (raw units) of {:?} after an arb; check for a bug or stuck funds", executor, held, token));
+    Ok(Some(held))
+}
+
+/// Sends a follow-up contract call with a managed nonce and waits for a successful receipt.
+pub(crate) async fn send_and_confirm<M: Middleware + 'static, D: ethers::abi::Detokenize>(
+    client: Arc<M>,
+    tx_type: TxType,
+    nonce_manager: &NonceManager,
+    call: ContractCall<M, D>,
+) -> Result<TxHash> where M::Error: 'static + Send + Sync {
+    let nonce = nonce_manager.get_next_nonce(client).await?;
+    let call = match tx_type { TxType::Legacy => call.legacy(), TxType::Eip1559 => call }.nonce(nonce);
+    let pending = match call.send().await {
+        Ok(p) => p,
+        Err(e) => {
+            nonce_manager.handle_nonce_error().await;
+            return Err(eyre!("Send failed: {}", e));
+        }
+    };
+    let hash = pending.tx_hash();
+    match timeout(Duration::from_secs(TX_CONFIRMATION_TIMEOUT_SECS), pending).await {
+        Ok(Ok(Some(receipt))) => {
+            nonce_manager.confirm_nonce_used(nonce).await;
+            if receipt.status != Some(TX_SUCCESS_STATUS) {
+                return Err(eyre!("Tx {:?} reverted on-chain", hash));
             }
+            Ok(hash)
         }
+        Ok(Ok(None)) => { nonce_manager.handle_nonce_error().await; Err(eyre!("Tx {:?} dropped", hash)) }
+        Ok(Err(e)) => { nonce_manager.handle_nonce_error().await; Err(eyre!("Tx {:?} confirmation failed: {}", hash, e)) }
+        Err(_) => { nonce_manager.handle_nonce_error().await; Err(eyre!("Timeout confirming tx {:?}", hash)) }
     }
-    if let Some(url) = &config.private_rpc_url {
-        if !url.is_empty() {
-             match try_relay(url, rlp_hex).await {
-                 Ok(hash) => return Ok(hash),
-                 Err(_) => {} // Ignore error, try next
-             }
+}
+
+// --- Helper functions (submit_sequentially) ---
+/// Signs `tx` and submits it to each private relay in turn, then the public RPC. When the final
+/// rejection is "nonce too low" (the wallet sent a transaction outside the bot) the nonce is re-synced
+/// from the network and `tx` re-signed with it, up to `config.nonce_resync_retries` times; `tx` is left
+/// holding the nonce that was submitted.
+#[instrument(level="debug", skip_all)]
+pub async fn submit_sequentially<M: Middleware + 'static>(
+    config: &Config,
+    client: &SignerMiddleware<M, LocalWallet>,
+    nonce_manager: &NonceManager,
+    tx: &mut TypedTransaction,
+) -> Result<TxHash>
+where
+    M::Error: 'static + Send + Sync,
+{
+    let mut resyncs = 0;
+    loop {
+        match submit_signed(config, client, tx).await {
+            Err(e) if resyncs < config.nonce_resync_retries && is_nonce_too_low(&format!("{:#}", e)) => {
+                resyncs += 1;
+                let stale_nonce = tx.nonce().copied();
+                let nonce = nonce_manager.resync_nonce(client).await.wrap_err("Nonce re-sync after \"nonce too low\" failed")?;
+                warn!(?stale_nonce, %nonce, attempt = resyncs, "Nonce too low (wallet used outside the bot?), retrying with re-synced nonce.");
+                tx.set_nonce(nonce);
+            }
+            result => return result,
         }
     }
-    if let Some(url) = &config.secondary_private_rpc_url {
-         if !url.is_empty() {
-            match try_relay(url, rlp_hex).await {
-                Ok(hash) => return Ok(hash),
-                Err(_) => {} // Ignore error, try next
+}
+
+/// Whether a submission error is a stale-nonce rejection (geth/erigon wording, Nethermind's `OldNonce`).
+pub fn is_nonce_too_low(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("nonce too low") || error.contains("oldnonce")
+}
+
+/// One signed submission attempt over every relay, then the public RPC.
+async fn submit_signed<M: Middleware + 'static>(config: &Config, client: &SignerMiddleware<M, LocalWallet>, tx: &TypedTransaction) -> Result<TxHash>
+where
+    M::Error: 'static + Send + Sync,
+{
+    let signature = client.signer().sign_transaction(tx).await.wrap_err("ALERT: Signing failed pre-submission")?;
+    let rlp_signed = tx.rlp_signed(&signature);
+    let rlp_hex = format!("0x{}", hex::encode(rlp_signed.as_ref()));
+    trace!("Transaction signed. RLP Hex: {}", rlp_hex); // Be careful logging this if sensitive
+    let relays = config.private_relays();
+    if config.concurrent_relay_submission && relays.len() > 1 {
+        // Every relay gets the same signed transaction, so at most one copy can land
+        debug!(relays = relays.len(), "Attempting submission via all relays concurrently.");
+        let (wallet, rlp_hex) = (client.signer().clone(), Arc::new(rlp_hex.clone()));
+        match first_accepted(relays, move |relay| {
+            let (wallet, rlp_hex) = (wallet.clone(), rlp_hex.clone());
+            async move { send_private_tx(&relay, &wallet, &rlp_hex).await }
+        }).await {
+            Ok((relay, tx_hash)) => {
+                info!(%tx_hash, relay = %relay.url, "Submitted via Private Relay (first to accept).");
+                return Ok(tx_hash);
+            }
+            Err(e) => warn!(error = ?e, "Every Private Relay rejected the submission."),
+        }
+    } else {
+        for relay in relays {
+            debug!("Attempting submission via relay: {}", relay.url);
+            match send_private_tx(&relay, client.signer(), &rlp_hex).await {
+                Ok(tx_hash) => {
+                    info!(%tx_hash, relay = %relay.url, "Submitted via Private Relay.");
+                    return Ok(tx_hash);
+                }
+                Err(e) => warn!(error = ?e, relay = %relay.url, "Private Relay submission failed."), // Try next
             }
-         }
+        }
     }
     info!("Attempting submission via Public RPC...");
-    match client.send_raw_transaction(rlp_signed.clone()).await {
+    match client.send_raw_transaction(rlp_signed).await {
         Ok(pending_tx) => {
             let tx_hash = pending_tx.tx_hash();
             info!(%tx_hash, "Submitted via Public RPC.");
-            return Ok(tx_hash);
+            Ok(tx_hash)
         }
         Err(provider_error) => {
             let error_string = provider_error.to_string();
             error!(error = error_string, "Public RPC submission failed.");
-            return Err(eyre!(provider_error).wrap_err(format!("Public RPC submission failed: {}", error_string)));
+            Err(eyre!(provider_error).wrap_err(format!("Public RPC submission failed: {}", error_string)))
         }
     }
-}
\ No newline at end of file
+}
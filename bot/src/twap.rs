@@ -0,0 +1,86 @@
+// bot/src/twap.rs
+//! Short-window UniV3 TWAP, a manipulation-resistant reference for the `slot0` spot price.
+//!
+//! With `config.twap_window_secs` set, each UniV3 pool fetch and full refresh also reads
+//! `observe([window, 0])` and stores the time-weighted average tick on the snapshot. While the deviation
+//! check is on, every UniV3 Swap event re-reads it at the swap's block too (`refresh_twap_tick`), so the
+//! TWAP moves with the spot tick the event updates instead of lagging until the next full refresh. `find_top_routes`
+//! then skips pools whose spot tick is more than `config.max_spot_twap_deviation_bps` away from it: a
+//! price pushed within the block (e.g. by a sandwich or a flash-loaned swap) shows up as a discrepancy
+//! the TWAP does not share. Pools whose observation buffer is too short for the window have no TWAP
+//! and are not filtered.
+
+use crate::bindings::UniswapV3Pool;
+use crate::state::{AppState, PoolSnapshot};
+use ethers::providers::Middleware;
+use ethers::types::{Address, U64};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Average tick between the two `observe` tick cumulatives taken `window_secs` apart, rounded towards
+/// negative infinity like Uniswap's `OracleLibrary.consult`. None for a zero window or malformed input.
+pub fn twap_tick(tick_cumulatives: &[i64], window_secs: u32) -> Option<i32> {
+    let [older, newer] = tick_cumulatives else { return None };
+    if window_secs == 0 {
+        return None;
+    }
+    let delta = newer.checked_sub(*older)?;
+    let window = i64::from(window_secs);
+    let mut tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        tick -= 1;
+    }
+    i32::try_from(tick).ok()
+}
+
+/// Reads the pool's TWAP tick over the last `window_secs` (at `block` when given). None when disabled
+/// or when `observe` reverts, e.g. because the pool's observations do not reach back that far.
+pub async fn fetch_twap_tick<M: Middleware + 'static>(pool: &UniswapV3Pool<M>, window_secs: u32, block: Option<U64>) -> Option<i32> {
+    if window_secs == 0 {
+        return None;
+    }
+    let mut call = pool.observe(vec![window_secs, 0]);
+    if let Some(block) = block {
+        call = call.block(block);
+    }
+    match call.call().await {
+        Ok((tick_cumulatives, _)) => twap_tick(&tick_cumulatives, window_secs),
+        Err(e) => {
+            debug!(pool = %pool.address(), window_secs, error = ?e, "observe() failed; no TWAP for the pool.");
+            None
+        }
+    }
+}
+
+/// Re-reads `pool`'s TWAP at `block` into its snapshot. A no-op unless both the window and
+/// `config.max_spot_twap_deviation_bps` are set; a snapshot already updated past `block` is left alone.
+pub async fn refresh_twap_tick<M: Middleware + 'static>(client: Arc<M>, state: &AppState, pool: Address, block: Option<U64>) {
+    let window_secs = state.config.twap_window_secs;
+    if window_secs == 0 || state.config.max_spot_twap_deviation_bps == 0 {
+        return;
+    }
+    let Ok(twap_tick) = state.timed_fetch(fetch_twap_tick(&UniswapV3Pool::new(pool, client), window_secs, block)).await else {
+        debug!(%pool, "observe() timed out; keeping the previous TWAP.");
+        return;
+    };
+    if let Some(mut snapshot) = state.pool_snapshots.get_mut(&pool) {
+        if block.is_none() || snapshot.last_update_block <= block {
+            snapshot.twap_tick = twap_tick;
+        }
+    }
+}
+
+/// Distance between the snapshot's spot price and its TWAP price in bps (`|1.0001^(tick - twap) - 1|`).
+/// None unless the snapshot has both ticks.
+pub fn spot_twap_deviation_bps(snapshot: &PoolSnapshot) -> Option<u64> {
+    let tick_delta = snapshot.tick? - snapshot.twap_tick?;
+    let ratio = 1.0001f64.powi(tick_delta);
+    Some(((ratio - 1.0).abs() * 10_000.0).round() as u64)
+}
+
+/// Whether the snapshot's spot price is further than `max_deviation_bps` from its TWAP. Never true when
+/// the check is disabled (0) or the pool has no TWAP.
+pub fn spot_deviates_from_twap(snapshot: &PoolSnapshot, max_deviation_bps: u64) -> bool {
+    max_deviation_bps > 0 && spot_twap_deviation_bps(snapshot).is_some_and(|bps| bps > max_deviation_bps)
+}
+// END OF FILE: bot/src/twap.rs
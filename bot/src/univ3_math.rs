@@ -0,0 +1,422 @@
+// bot/src/univ3_math.rs
+//! Off-chain port of the Uniswap V3 swap math (TickMath, SqrtPriceMath, SwapMath) used to
+//! simulate exact-input swaps from a pool snapshot without a QuoterV2 round-trip.
+//!
+//! Without the pool's tick bitmap, liquidity is only known to be constant up to the next
+//! tick-spacing boundary (the nearest tick that could be initialized). Swaps that stay inside
+//! that range are exact; swaps that would reach it return `None` and must go to the quoter.
+//! The boundary comes from the pool's own `tickSpacing()` when known (`PoolState.tick_spacing`),
+//! otherwise from the standard spacing of its fee tier.
+//!
+//! With a `TickWindow` (the initialized ticks of the bitmap words around the current tick, see
+//! `univ3_ticks`), `quote_exact_input_across_ticks` steps across initialized ticks like
+//! `UniswapV3Pool.swap`, and returns `None` only once the swap would leave the words that were read.
+
+use crate::utils::ArithmeticOverflow;
+use ethers::types::{U256, U512};
+use std::collections::BTreeMap;
+
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+type MathResult<T> = std::result::Result<T, ArithmeticOverflow>;
+
+fn q96() -> U256 {
+    U256::one() << 96
+}
+
+/// Tick spacing for the standard UniV3 fee tiers.
+pub fn tick_spacing_for_fee(fee: u32) -> Option<i32> {
+    match fee {
+        100 => Some(1),
+        500 => Some(10),
+        3000 => Some(60),
+        10000 => Some(200),
+        _ => None,
+    }
+}
+
+// --- FullMath ---
+
+/// `floor(a * b / denominator)` with a 512-bit intermediate product.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> MathResult<U256> {
+    if denominator.is_zero() {
+        return Err(ArithmeticOverflow("mul_div denominator"));
+    }
+    let result = a.full_mul(b) / U512::from(denominator);
+    U256::try_from(result).map_err(|_| ArithmeticOverflow("mul_div result"))
+}
+
+/// `ceil(a * b / denominator)` with a 512-bit intermediate product.
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> MathResult<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if (a.full_mul(b) % U512::from(denominator)).is_zero() {
+        Ok(result)
+    } else {
+        result.checked_add(U256::one()).ok_or(ArithmeticOverflow("mul_div_rounding_up"))
+    }
+}
+
+fn div_rounding_up(a: U256, b: U256) -> MathResult<U256> {
+    if b.is_zero() {
+        return Err(ArithmeticOverflow("div_rounding_up denominator"));
+    }
+    let q = a / b;
+    Ok(if (a % b).is_zero() { q } else { q + U256::one() })
+}
+
+// --- TickMath ---
+
+/// sqrt(1.0001^tick) * 2^96, bit-for-bit identical to `TickMath.getSqrtRatioAtTick`.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> MathResult<U256> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(ArithmeticOverflow("tick out of range"));
+    }
+    const MAGIC: [(u32, &str); 19] = [
+        (0x2, "fff97272373d413259a46990580e213a"),
+        (0x4, "fff2e50f5f656932ef12357cf3c7fdcc"),
+        (0x8, "ffe5caca7e10e4e61c3624eaa0941cd0"),
+        (0x10, "ffcb9843d60f6159c9db58835c926644"),
+        (0x20, "ff973b41fa98c081472e6896dfb254c0"),
+        (0x40, "ff2ea16466c96a3843ec78b326b52861"),
+        (0x80, "fe5dee046a99a2a811c461f1969c3053"),
+        (0x100, "fcbe86c7900a88aedcffc83b479aa3a4"),
+        (0x200, "f987a7253ac413176f2b074cf7815e54"),
+        (0x400, "f3392b0822b70005940c7a398e4b70f3"),
+        (0x800, "e7159475a2c29b7443b29c7fa6e889d9"),
+        (0x1000, "d097f3bdfd2022b8845ad8f792aa5825"),
+        (0x2000, "a9f746462d870fdf8a65dc1f90e061e5"),
+        (0x4000, "70d869a156d2a1b890bb3df62baf32f7"),
+        (0x8000, "31be135f97d08fd981231505542fcfa6"),
+        (0x10000, "9aa508b5b7a84e1c677de54f3e99bc9"),
+        (0x20000, "5d6af8dedb81196699c329225ee604"),
+        (0x40000, "2216e584f5fa1ea926041bedfe98"),
+        (0x80000, "48a170391f7dc42444e8fa2"),
+    ];
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from_str_radix("fffcb933bd6fad37aa2d162d1a594001", 16).expect("valid constant")
+    } else {
+        U256::one() << 128
+    };
+    for (bit, magic) in MAGIC {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * U256::from_str_radix(magic, 16).expect("valid constant")) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Q128.128 -> Q64.96, rounding up so getTickAtSqrtRatio round-trips
+    let rounding = if (ratio & U256::from(u32::MAX)).is_zero() { U256::zero() } else { U256::one() };
+    Ok((ratio >> 32) + rounding)
+}
+
+// --- SqrtPriceMath ---
+
+/// Amount of token0 between two prices for the given liquidity.
+pub fn get_amount0_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> MathResult<U256> {
+    let (lower, upper) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+    if lower.is_zero() {
+        return Err(ArithmeticOverflow("zero sqrt price"));
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = upper - lower;
+    if round_up {
+        div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower)
+    } else {
+        Ok(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// Amount of token1 between two prices for the given liquidity.
+pub fn get_amount1_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> MathResult<U256> {
+    let (lower, upper) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), upper - lower, q96())
+    } else {
+        mul_div(U256::from(liquidity), upper - lower, q96())
+    }
+}
+
+fn next_sqrt_price_from_amount0_rounding_up(sqrt_price: U256, liquidity: u128, amount: U256) -> MathResult<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price);
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    if let Some(product) = amount.checked_mul(sqrt_price) {
+        if let Some(denominator) = numerator1.checked_add(product) {
+            return mul_div_rounding_up(numerator1, sqrt_price, denominator);
+        }
+    }
+    let denominator = (numerator1 / sqrt_price).checked_add(amount).ok_or(ArithmeticOverflow("next sqrt price (token0)"))?;
+    div_rounding_up(numerator1, denominator)
+}
+
+fn next_sqrt_price_from_amount1_rounding_down(sqrt_price: U256, liquidity: u128, amount: U256) -> MathResult<U256> {
+    let quotient = if amount < (U256::one() << 160) {
+        (amount << 96) / U256::from(liquidity)
+    } else {
+        mul_div(amount, q96(), U256::from(liquidity))?
+    };
+    sqrt_price.checked_add(quotient).ok_or(ArithmeticOverflow("next sqrt price (token1)"))
+}
+
+/// Price after adding `amount_in` (fee already deducted) at constant `liquidity`, ignoring tick boundaries.
+pub fn next_sqrt_price_from_input(sqrt_price: U256, liquidity: u128, amount_in: U256, zero_for_one: bool) -> MathResult<U256> {
+    if sqrt_price.is_zero() || liquidity == 0 {
+        return Err(ArithmeticOverflow("zero price or liquidity"));
+    }
+    if zero_for_one {
+        next_sqrt_price_from_amount0_rounding_up(sqrt_price, liquidity, amount_in)
+    } else {
+        next_sqrt_price_from_amount1_rounding_down(sqrt_price, liquidity, amount_in)
+    }
+}
+
+// --- SwapMath ---
+
+/// Result of a single exact-input swap step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// Port of `SwapMath.computeSwapStep` for exact-input swaps (`fee_pips` in hundredths of a bip). A fee of
+/// 100% or more (`fee_pips >= 1_000_000`, e.g. from a corrupt pool read) is an error, not an underflow.
+pub fn compute_swap_step(
+    sqrt_price_current_x96: U256,
+    sqrt_price_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_pips: u32,
+) -> MathResult<SwapStep> {
+    let zero_for_one = sqrt_price_current_x96 >= sqrt_price_target_x96;
+    let fee_complement = FEE_DENOMINATOR.checked_sub(fee_pips).filter(|complement| *complement > 0)
+        .map(U256::from).ok_or(ArithmeticOverflow("swap fee complement (fee_pips must be below 1_000_000)"))?;
+    let amount_remaining_less_fee = mul_div(amount_remaining, fee_complement, U256::from(FEE_DENOMINATOR))?;
+
+    let max_amount_in = if zero_for_one {
+        get_amount0_delta(sqrt_price_target_x96, sqrt_price_current_x96, liquidity, true)?
+    } else {
+        get_amount1_delta(sqrt_price_current_x96, sqrt_price_target_x96, liquidity, true)?
+    };
+    let sqrt_price_next_x96 = if amount_remaining_less_fee >= max_amount_in {
+        sqrt_price_target_x96
+    } else {
+        next_sqrt_price_from_input(sqrt_price_current_x96, liquidity, amount_remaining_less_fee, zero_for_one)?
+    };
+    let reached_target = sqrt_price_next_x96 == sqrt_price_target_x96;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        (
+            if reached_target { max_amount_in } else { get_amount0_delta(sqrt_price_next_x96, sqrt_price_current_x96, liquidity, true)? },
+            get_amount1_delta(sqrt_price_next_x96, sqrt_price_current_x96, liquidity, false)?,
+        )
+    } else {
+        (
+            if reached_target { max_amount_in } else { get_amount1_delta(sqrt_price_current_x96, sqrt_price_next_x96, liquidity, true)? },
+            get_amount0_delta(sqrt_price_current_x96, sqrt_price_next_x96, liquidity, false)?,
+        )
+    };
+    let fee_amount = if !reached_target {
+        amount_remaining - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, U256::from(fee_pips), fee_complement)?
+    };
+    Ok(SwapStep { sqrt_price_next_x96, amount_in, amount_out, fee_amount })
+}
+
+/// Simulates an exact-input swap that stays within the current tick-spacing range.
+/// Returns `Ok(None)` when the swap would reach the next possibly-initialized tick, where
+/// liquidity may change and the quoter is required, or when the spacing is unknown (no
+/// `tick_spacing` and a non-standard fee tier).
+pub fn quote_exact_input_within_tick_range(
+    sqrt_price_x96: U256,
+    tick: i32,
+    liquidity: u128,
+    fee: u32,
+    tick_spacing: Option<i32>,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> MathResult<Option<U256>> {
+    Ok(swap_within_tick_range(sqrt_price_x96, tick, liquidity, fee, tick_spacing, zero_for_one, amount_in)?.map(|step| step.amount_out))
+}
+
+/// The full swap step (including the resulting price) of an exact-input swap that stays within the
+/// current tick-spacing range; `Ok(None)` under the same conditions as `quote_exact_input_within_tick_range`.
+pub fn swap_within_tick_range(
+    sqrt_price_x96: U256,
+    tick: i32,
+    liquidity: u128,
+    fee: u32,
+    tick_spacing: Option<i32>,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> MathResult<Option<SwapStep>> {
+    let tick_spacing = match tick_spacing.filter(|s| *s > 0).or_else(|| tick_spacing_for_fee(fee)) {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    if liquidity == 0 || amount_in.is_zero() {
+        return Ok(None);
+    }
+    let compressed = tick.div_euclid(tick_spacing);
+    let boundary_tick = if zero_for_one { compressed * tick_spacing } else { (compressed + 1) * tick_spacing };
+    let sqrt_price_target = get_sqrt_ratio_at_tick(boundary_tick.clamp(MIN_TICK, MAX_TICK))?;
+    if (zero_for_one && sqrt_price_target >= sqrt_price_x96) || (!zero_for_one && sqrt_price_target <= sqrt_price_x96) {
+        return Ok(None); // Price sits on the boundary; the next step crosses it
+    }
+    let step = compute_swap_step(sqrt_price_x96, sqrt_price_target, liquidity, amount_in, fee)?;
+    if step.sqrt_price_next_x96 == sqrt_price_target {
+        return Ok(None);
+    }
+    Ok(Some(step))
+}
+
+// --- Tick crossing ---
+
+/// Liquidity of one initialized tick (`UniswapV3Pool.ticks`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickLiquidity {
+    pub liquidity_gross: u128,
+    pub liquidity_net: i128,
+}
+
+/// The initialized ticks of a UniV3 pool within tick bitmap words `word_lo..=word_hi`. Ticks outside
+/// those words are unknown, so swaps can only be simulated while they stay inside them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickWindow {
+    pub tick_spacing: i32,
+    pub word_lo: i16,
+    pub word_hi: i16,
+    pub ticks: BTreeMap<i32, TickLiquidity>,
+}
+
+impl TickWindow {
+    fn covers_word(&self, compressed: i32) -> bool {
+        let word = compressed.div_euclid(256);
+        word >= self.word_lo as i32 && word <= self.word_hi as i32
+    }
+
+    /// Whether `tick` lies in one of the words that were read.
+    pub fn covers_tick(&self, tick: i32) -> bool {
+        self.tick_spacing > 0 && self.covers_word(tick.div_euclid(self.tick_spacing))
+    }
+
+    /// Port of `TickBitmap.nextInitializedTickWithinOneWord`: the next initialized tick at or below `tick`
+    /// (`lte`) or above it within one bitmap word, else that word's last tick in the swap direction.
+    /// `None` when that word was not read.
+    pub fn next_initialized_tick_within_one_word(&self, tick: i32, lte: bool) -> Option<(i32, bool)> {
+        let spacing = self.tick_spacing;
+        let compressed = tick.div_euclid(spacing);
+        if lte {
+            if !self.covers_word(compressed) {
+                return None;
+            }
+            let word_start = (compressed - compressed.rem_euclid(256)) * spacing;
+            Some(match self.ticks.range(word_start..=compressed * spacing).next_back() {
+                Some((next, _)) => (*next, true),
+                None => (word_start, false),
+            })
+        } else {
+            let compressed = compressed + 1;
+            if !self.covers_word(compressed) {
+                return None;
+            }
+            let word_end = (compressed - compressed.rem_euclid(256) + 255) * spacing;
+            Some(match self.ticks.range(compressed * spacing..=word_end).next() {
+                Some((next, _)) => (*next, true),
+                None => (word_end, false),
+            })
+        }
+    }
+
+    /// Applies a position change (`Mint`: positive `delta`, `Burn`: negative) to its two ticks like
+    /// `UniswapV3Pool._updatePosition`. Ticks in words outside the window stay unknown; a tick left
+    /// without gross liquidity is uninitialized and removed.
+    pub fn apply_position_change(&mut self, tick_lower: i32, tick_upper: i32, delta: i128) {
+        for (tick, net_delta) in [(tick_lower, delta), (tick_upper, delta.saturating_neg())] {
+            if self.tick_spacing <= 0 || tick % self.tick_spacing != 0 || !self.covers_word(tick / self.tick_spacing) {
+                continue;
+            }
+            let entry = self.ticks.entry(tick).or_default();
+            entry.liquidity_gross = if delta >= 0 {
+                entry.liquidity_gross.saturating_add(delta.unsigned_abs())
+            } else {
+                entry.liquidity_gross.saturating_sub(delta.unsigned_abs())
+            };
+            entry.liquidity_net = entry.liquidity_net.saturating_add(net_delta);
+            if entry.liquidity_gross == 0 {
+                self.ticks.remove(&tick);
+            }
+        }
+    }
+}
+
+/// Simulates an exact-input swap across the initialized ticks of `window`, step by step as
+/// `UniswapV3Pool.swap` does (so rounding matches QuoterV2). Returns `Ok(None)` when the swap would
+/// continue past the bitmap words that were read or reach `MIN_TICK`/`MAX_TICK`.
+pub fn quote_exact_input_across_ticks(
+    sqrt_price_x96: U256,
+    tick: i32,
+    liquidity: u128,
+    fee: u32,
+    window: &TickWindow,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> MathResult<Option<U256>> {
+    if window.tick_spacing <= 0 || amount_in.is_zero() {
+        return Ok(None);
+    }
+    let (mut sqrt_price, mut tick, mut liquidity) = (sqrt_price_x96, tick, liquidity);
+    let (mut remaining, mut amount_out) = (amount_in, U256::zero());
+    while !remaining.is_zero() {
+        let Some((next_tick, initialized)) = window.next_initialized_tick_within_one_word(tick, zero_for_one) else {
+            return Ok(None);
+        };
+        let next_tick = next_tick.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_tick = get_sqrt_ratio_at_tick(next_tick)?;
+        let step = compute_swap_step(sqrt_price, sqrt_price_next_tick, liquidity, remaining, fee)?;
+        remaining = remaining.checked_sub(step.amount_in + step.fee_amount).ok_or(ArithmeticOverflow("swap amount remaining"))?;
+        amount_out = amount_out.checked_add(step.amount_out).ok_or(ArithmeticOverflow("swap amount out"))?;
+        sqrt_price = step.sqrt_price_next_x96;
+        if sqrt_price != sqrt_price_next_tick {
+            break;
+        }
+        if next_tick == MIN_TICK || next_tick == MAX_TICK {
+            return Ok(None);
+        }
+        if initialized {
+            let net = window.ticks.get(&next_tick).map_or(0, |t| t.liquidity_net);
+            let net = if zero_for_one { net.checked_neg().ok_or(ArithmeticOverflow("tick liquidity net"))? } else { net };
+            liquidity = if net >= 0 { liquidity.checked_add(net.unsigned_abs()) } else { liquidity.checked_sub(net.unsigned_abs()) }
+                .ok_or(ArithmeticOverflow("liquidity after crossing a tick"))?;
+        }
+        tick = if zero_for_one { next_tick - 1 } else { next_tick };
+    }
+    Ok(Some(amount_out))
+}
+
+/// `quote_exact_input_across_ticks` when the pool's tick `window` is known, else
+/// `quote_exact_input_within_tick_range`.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_exact_input(
+    sqrt_price_x96: U256,
+    tick: i32,
+    liquidity: u128,
+    fee: u32,
+    tick_spacing: Option<i32>,
+    window: Option<&TickWindow>,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> MathResult<Option<U256>> {
+    match window {
+        Some(window) => quote_exact_input_across_ticks(sqrt_price_x96, tick, liquidity, fee, window, zero_for_one, amount_in),
+        None => quote_exact_input_within_tick_range(sqrt_price_x96, tick, liquidity, fee, tick_spacing, zero_for_one, amount_in),
+    }
+}
@@ -1,7 +1,7 @@
 // src/utils.rs
 
 // --- Imports ---
-use ethers::types::U256;
+use ethers::types::{I256, U256};
 // FIX Warning: Remove unused eyre import
 use eyre::{Result, WrapErr};
 use std::str::FromStr;
@@ -108,4 +108,32 @@ pub fn f64_to_wei(amount_f64: f64, decimals: u32) -> Result<U256> {
 
     U256::from_dec_str(wei_str_cleaned)
          .wrap_err_with(|| format!("Failed to parse f64 '{}' (wei string '{}', cleaned '{}', decimals {}) to U256", amount_f64, wei_str, wei_str_cleaned, decimals))
-}
\ No newline at end of file
+}
+// --- Checked Arithmetic Helpers ---
+
+/// Returned when profit/gas math would overflow, instead of panicking (debug) or wrapping (release).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArithmeticOverflow(pub &'static str);
+
+impl std::fmt::Display for ArithmeticOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Arithmetic overflow in {}", self.0)
+    }
+}
+
+impl std::error::Error for ArithmeticOverflow {}
+
+/// Computes `value * numerator / denominator` without overflowing the intermediate product.
+pub fn checked_mul_div(value: U256, numerator: U256, denominator: U256, context: &'static str) -> std::result::Result<U256, ArithmeticOverflow> {
+    value.checked_mul(numerator)
+        .and_then(|product| product.checked_div(denominator))
+        .ok_or(ArithmeticOverflow(context))
+}
+
+/// Converts an unsigned amount to `I256`, failing (rather than silently going negative) above `I256::MAX`.
+pub fn u256_to_i256(value: U256, context: &'static str) -> std::result::Result<I256, ArithmeticOverflow> {
+    if value > I256::MAX.into_raw() {
+        return Err(ArithmeticOverflow(context));
+    }
+    Ok(I256::from_raw(value))
+}
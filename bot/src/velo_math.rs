@@ -0,0 +1,145 @@
+// bot/src/velo_math.rs
+//! Off-chain port of Velodrome V2 `Pool.getAmountOut`, so Velo/Aero swaps can be simulated from a
+//! snapshot's reserves without a router round-trip.
+//!
+//! Volatile pools are constant-product. Stable pools use the Solidly invariant `x³y + y³x = k` on
+//! reserves normalized to 18 decimals, solved for the output reserve with the contract's Newton
+//! iteration (`_get_y`). Every step mirrors the Solidity rounding (floor division, checked
+//! intermediates), so the result matches the pool to the wei.
+//!
+//! The swap fee is a factory setting and is not part of the snapshot; callers pass it in basis
+//! points (the factory's `getFee(pool, stable)`).
+
+use crate::utils::{checked_mul_div, ArithmeticOverflow};
+use ethers::types::U256;
+
+const FEE_DENOMINATOR: u64 = 10_000;
+/// `_get_y` gives up (the contract reverts with `!y`) after this many Newton steps.
+const MAX_NEWTON_ITERATIONS: usize = 255;
+
+type MathResult<T> = std::result::Result<T, ArithmeticOverflow>;
+
+fn e18() -> U256 {
+    U256::exp10(18)
+}
+
+/// Reserves and token scales of a Velodrome V2 pool, in pool (token0/token1) order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VeloPoolReserves {
+    pub reserve0: U256,
+    pub reserve1: U256,
+    /// `10^decimals` of token0, as returned by the pool's `metadata()`.
+    pub decimals0: U256,
+    /// `10^decimals` of token1.
+    pub decimals1: U256,
+    pub stable: bool,
+}
+
+impl VeloPoolReserves {
+    /// Builds the reserves from token decimal counts (e.g. 6 for USDC).
+    pub fn new(reserve0: U256, reserve1: U256, token0_decimals: u8, token1_decimals: u8, stable: bool) -> Self {
+        Self {
+            reserve0,
+            reserve1,
+            decimals0: U256::exp10(token0_decimals as usize),
+            decimals1: U256::exp10(token1_decimals as usize),
+            stable,
+        }
+    }
+}
+
+/// Output of an exact-input swap of `amount_in`, as `Pool.getAmountOut` computes it.
+/// `zero_for_one` is true when token0 is sold; `fee_bps` is deducted from the input first.
+pub fn get_amount_out(pool: &VeloPoolReserves, amount_in: U256, zero_for_one: bool, fee_bps: u32) -> MathResult<U256> {
+    let fee = checked_mul_div(amount_in, U256::from(fee_bps), U256::from(FEE_DENOMINATOR), "velo fee")?;
+    let amount_in = amount_in.checked_sub(fee).ok_or(ArithmeticOverflow("velo fee exceeds input"))?;
+
+    if !pool.stable {
+        let (reserve_a, reserve_b) = if zero_for_one { (pool.reserve0, pool.reserve1) } else { (pool.reserve1, pool.reserve0) };
+        let denominator = reserve_a.checked_add(amount_in).ok_or(ArithmeticOverflow("velo volatile denominator"))?;
+        return checked_mul_div(amount_in, reserve_b, denominator, "velo volatile amount out");
+    }
+
+    let xy = k(pool, pool.reserve0, pool.reserve1)?;
+    let reserve0 = checked_mul_div(pool.reserve0, e18(), pool.decimals0, "velo reserve0 normalization")?;
+    let reserve1 = checked_mul_div(pool.reserve1, e18(), pool.decimals1, "velo reserve1 normalization")?;
+    let (reserve_a, reserve_b, decimals_in, decimals_out) = if zero_for_one {
+        (reserve0, reserve1, pool.decimals0, pool.decimals1)
+    } else {
+        (reserve1, reserve0, pool.decimals1, pool.decimals0)
+    };
+    let amount_in = checked_mul_div(amount_in, e18(), decimals_in, "velo amount normalization")?;
+    let x0 = amount_in.checked_add(reserve_a).ok_or(ArithmeticOverflow("velo x0"))?;
+    let y = reserve_b.checked_sub(get_y(pool, x0, xy, reserve_b)?).ok_or(ArithmeticOverflow("velo stable amount out"))?;
+    checked_mul_div(y, decimals_out, e18(), "velo amount denormalization")
+}
+
+/// The pool invariant on raw reserves: `x * y` (volatile) or `x³y + y³x` on 18-decimal values (stable).
+pub fn k(pool: &VeloPoolReserves, x: U256, y: U256) -> MathResult<U256> {
+    if !pool.stable {
+        return x.checked_mul(y).ok_or(ArithmeticOverflow("velo k"));
+    }
+    let x = checked_mul_div(x, e18(), pool.decimals0, "velo k x")?;
+    let y = checked_mul_div(y, e18(), pool.decimals1, "velo k y")?;
+    f(x, y)
+}
+
+/// `x0³y + y³x0` on 18-decimal values.
+fn f(x0: U256, y: U256) -> MathResult<U256> {
+    let a = checked_mul_div(x0, y, e18(), "velo f a")?;
+    let b = checked_mul_div(x0, x0, e18(), "velo f x²")?
+        .checked_add(checked_mul_div(y, y, e18(), "velo f y²")?)
+        .ok_or(ArithmeticOverflow("velo f b"))?;
+    checked_mul_div(a, b, e18(), "velo f")
+}
+
+/// `∂f/∂y = 3x0y² + x0³` on 18-decimal values.
+fn d(x0: U256, y: U256) -> MathResult<U256> {
+    let y2 = checked_mul_div(y, y, e18(), "velo d y²")?;
+    let term_a = U256::from(3u8).checked_mul(x0)
+        .and_then(|x0_3| x0_3.checked_mul(y2))
+        .ok_or(ArithmeticOverflow("velo d 3x0y²"))? / e18();
+    let x0_2 = checked_mul_div(x0, x0, e18(), "velo d x0²")?;
+    let term_b = checked_mul_div(x0_2, x0, e18(), "velo d x0³")?;
+    term_a.checked_add(term_b).ok_or(ArithmeticOverflow("velo d"))
+}
+
+/// Newton iteration for the `y` with `f(x0, y) = xy`, starting from `y`.
+///
+/// Ported as-is, including the contract's use of `_k` (which rescales by the pool decimals) on the
+/// already-normalized `y + 1` candidate; that quirk decides the final wei for non-18-decimal pools.
+fn get_y(pool: &VeloPoolReserves, x0: U256, xy: U256, mut y: U256) -> MathResult<U256> {
+    for _ in 0..MAX_NEWTON_ITERATIONS {
+        let current = f(x0, y)?;
+        let derivative = d(x0, y)?;
+        if current < xy {
+            let mut dy = checked_mul_div(xy - current, e18(), derivative, "velo get_y dy")?;
+            if dy.is_zero() {
+                if current == xy {
+                    return Ok(y);
+                }
+                let y_next = y.checked_add(U256::one()).ok_or(ArithmeticOverflow("velo get_y y+1"))?;
+                if k(pool, x0, y_next)? > xy {
+                    return Ok(y_next);
+                }
+                dy = U256::one();
+            }
+            y = y.checked_add(dy).ok_or(ArithmeticOverflow("velo get_y step up"))?;
+        } else {
+            let mut dy = checked_mul_div(current - xy, e18(), derivative, "velo get_y dy")?;
+            if dy.is_zero() {
+                if current == xy {
+                    return Ok(y);
+                }
+                let y_prev = y.checked_sub(U256::one()).ok_or(ArithmeticOverflow("velo get_y y-1"))?;
+                if f(x0, y_prev)? < xy {
+                    return Ok(y);
+                }
+                dy = U256::one();
+            }
+            y = y.checked_sub(dy).ok_or(ArithmeticOverflow("velo get_y step down"))?;
+        }
+    }
+    Err(ArithmeticOverflow("velo get_y did not converge"))
+}
+// END OF FILE: bot/src/velo_math.rs
@@ -0,0 +1,70 @@
+// bot/src/warmup.rs
+//! Startup warmup before the first submission (`config.submission_warmup_secs`,
+//! `config.min_pools_before_trading`).
+//!
+//! Right after startup the snapshot cache may be partially loaded, so a route's spread can be judged
+//! against an incomplete view. Detection and simulation run as usual (logs, metrics, audit), but
+//! profitable opportunities are dropped until the warmup has elapsed and enough pools are cached. Once
+//! both hold, submissions stay enabled for the rest of the session even if evictions later shrink the
+//! cache. Zero settings disable the respective check.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Why submissions are still held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmupPending {
+    /// The warmup period has this long left to run.
+    Time(Duration),
+    /// Fewer pools are cached than required.
+    Pools { loaded: usize, required: usize },
+}
+
+impl fmt::Display for WarmupPending {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarmupPending::Time(left) => write!(f, "warmup ({}s left)", left.as_secs()),
+            WarmupPending::Pools { loaded, required } => write!(f, "warmup ({} of {} pools loaded)", loaded, required),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SubmissionWarmup {
+    started: Instant,
+    complete: AtomicBool,
+}
+
+impl Default for SubmissionWarmup {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl SubmissionWarmup {
+    pub fn new(started: Instant) -> Self {
+        Self { started, complete: AtomicBool::new(false) }
+    }
+
+    /// Whether a submission may go out at `now` with `pools_loaded` pools cached. The first success
+    /// completes the warmup for good.
+    pub fn check(&self, now: Instant, pools_loaded: usize, warmup: Duration, min_pools: usize) -> Result<(), WarmupPending> {
+        if self.complete.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let left = warmup.saturating_sub(now.saturating_duration_since(self.started));
+        if !left.is_zero() {
+            return Err(WarmupPending::Time(left));
+        }
+        if pools_loaded < min_pools {
+            return Err(WarmupPending::Pools { loaded: pools_loaded, required: min_pools });
+        }
+        if !self.complete.swap(true, Ordering::Relaxed) {
+            info!(pools_loaded, warmup_secs = warmup.as_secs(), "Warmup complete. Submissions enabled.");
+        }
+        Ok(())
+    }
+}
+// END OF FILE: bot/src/warmup.rs
@@ -0,0 +1,119 @@
+// tests/admin_test.rs
+// Offline tests for the admin command interface (no Anvil required).
+
+use ethers::types::{Address, U256, U64};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use ulp1_5::admin::{handle_command, run_admin_server, MAX_COMMAND_BYTES};
+use ulp1_5::config::Config;
+use ulp1_5::dump::CSV_HEADER;
+use ulp1_5::state::{AppState, DexType, PauseReason, PoolSnapshot, PoolState};
+
+fn app_state_with_pool(pool: Address) -> AppState {
+    let app_state = AppState::new(Config::default());
+    app_state.pool_states.insert(pool, PoolState {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        uni_fee: Some(500), tick_spacing: Some(10), velo_stable: None, t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: None, reserve1: None, sqrt_price_x96: Some(U256::one() << 96), tick: Some(0), liquidity: Some(1_000),
+        last_update_block: Some(U64::from(42)), last_update_timestamp: Some(1_700_000_000),
+        ..Default::default()
+    });
+    app_state
+}
+
+#[test]
+fn test_admin_pools_and_snapshot() {
+    let pool = Address::from_low_u64_be(0xabc);
+    let app_state = app_state_with_pool(pool);
+
+    let pools = handle_command("pools", &app_state);
+    assert_eq!(pools["count"], 1);
+    assert_eq!(pools["pools"][0]["fee"], 500);
+
+    let snapshot = handle_command(&format!("snapshot {:?}", pool), &app_state);
+    assert_eq!(snapshot["ok"], true);
+    assert_eq!(snapshot["sqrt_price_x96"], (U256::one() << 96).to_string());
+    assert_eq!(snapshot["last_update_block"], 42);
+
+    assert_eq!(handle_command("snapshot 0x0000000000000000000000000000000000000001", &app_state)["ok"], false);
+    assert_eq!(handle_command("snapshot nope", &app_state)["ok"], false);
+    assert_eq!(handle_command("frobnicate", &app_state)["ok"], false);
+}
+
+#[test]
+fn test_admin_pause_resume_toggles_circuit_breaker() {
+    let app_state = app_state_with_pool(Address::from_low_u64_be(0xabc));
+    assert!(!app_state.is_paused());
+
+    assert_eq!(handle_command("pause", &app_state)["paused"], true);
+    assert!(app_state.is_paused());
+    assert_eq!(handle_command("stats", &app_state)["paused"], true);
+
+    handle_command("RESUME", &app_state);
+    assert!(!app_state.is_paused());
+
+    // Resume lifts only the admin pause
+    app_state.set_paused(PauseReason::LowGasBalance, true);
+    handle_command("pause", &app_state);
+    let reply = handle_command("resume", &app_state);
+    assert_eq!(reply["paused"], true);
+    assert_eq!(reply["pause_reasons"], serde_json::json!(["LowGasBalance"]));
+    app_state.set_paused(PauseReason::LowGasBalance, false);
+    let stats = handle_command("stats", &app_state);
+    assert_eq!(stats["pool_snapshots"], 1);
+    assert!(stats["metrics"].get("deadline_expired_opportunities").is_some());
+}
+
+#[tokio::test]
+async fn test_admin_server_rejects_oversized_commands() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let state = Arc::new(app_state_with_pool(Address::from_low_u64_be(0xabc)));
+    tokio::spawn({ let addr = addr.clone(); async move { run_admin_server(&addr, state).await } });
+    let stream = loop {
+        match TcpStream::connect(&addr).await {
+            Ok(stream) => break stream,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+        }
+    };
+    let (reader, mut writer) = stream.into_split();
+    let mut replies = BufReader::new(reader).lines();
+
+    writer.write_all(b"stats\n").await.unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&replies.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(stats["pool_states"], 1);
+
+    writer.write_all(format!("{}\nstats\n", "a".repeat(MAX_COMMAND_BYTES + 1)).as_bytes()).await.unwrap();
+    let rejected: serde_json::Value = serde_json::from_str(&replies.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(rejected["ok"], false);
+    assert!(rejected["error"].as_str().unwrap().contains("longer than"), "{}", rejected);
+    assert!(!matches!(replies.next_line().await, Ok(Some(_))), "The connection is closed after an oversized line");
+}
+
+#[test]
+fn test_admin_dump_writes_snapshot_csv() {
+    let pool = Address::from_low_u64_be(0xabc);
+    let app_state = app_state_with_pool(pool);
+    let path = std::env::temp_dir().join(format!("ulp_snapshot_dump_test_{}.csv", std::process::id()));
+
+    let reply = handle_command(&format!("dump {}", path.display()), &app_state);
+    assert_eq!(reply["ok"], true, "{}", reply);
+    assert_eq!(reply["rows"], 1);
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some(CSV_HEADER));
+    let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(row.len(), CSV_HEADER.split(',').count());
+    assert_eq!(row[0], format!("{:?}", pool));
+    assert_eq!((row[1], row[4], row[5]), ("UniswapV3", "500", "10"));
+    assert_eq!(row[8], "1", "sqrtPriceX96 of 2^96 is a raw price of 1");
+    assert_eq!(row[14], "42");
+    assert!(lines.next().is_none());
+
+    assert_eq!(handle_command("dump", &app_state)["ok"], false, "No path given and SNAPSHOT_DUMP_PATH unset");
+    assert_eq!(handle_command(&format!("dump {} parquet", path.display()), &app_state)["ok"], false);
+}
@@ -0,0 +1,83 @@
+// tests/audit_test.rs
+// Offline tests for the JSONL opportunity audit trail.
+
+mod common;
+
+use ethers::types::{Address, Bytes, TxHash, I256, U256};
+use ulp1_5::audit::{AuditEvent, AuditLog, AuditRecord, FailedSubmission};
+use ulp1_5::state::DexType;
+
+#[tokio::test]
+async fn test_audit_log_appends_jsonl() -> eyre::Result<()> {
+    let path = std::env::temp_dir().join(format!("ulp_audit_test_{}.jsonl", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let (audit, writer) = AuditLog::open(path_str).await?;
+    let route = common::route(Address::from_low_u64_be(1), DexType::UniswapV3, Address::from_low_u64_be(2), DexType::VelodromeV2);
+    audit.record(|| AuditRecord::new(AuditEvent::Detected, &route));
+    audit.record(|| AuditRecord::new(AuditEvent::Reverted, &route)
+        .loan_amount(U256::exp10(18)).net_profit(I256::from(-5)).gas_limit(U256::from(450_000))
+        .tx_hash(TxHash::repeat_byte(0xab)).reason("status 0"));
+    drop(audit);
+    writer.await?;
+
+    let contents = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    let lines: Vec<serde_json::Value> = contents.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["event"], "detected");
+    assert_eq!(lines[0]["route_id"], route.id());
+    assert!(lines[0]["tx_hash"].is_null());
+    assert_eq!(lines[1]["event"], "reverted");
+    assert_eq!(lines[1]["loan_amount_wei"], "1000000000000000000");
+    assert_eq!(lines[1]["net_profit_wei"], "-5");
+    assert_eq!(lines[1]["reason"], "status 0");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_drains_queue_while_handles_are_alive() -> eyre::Result<()> {
+    let path = std::env::temp_dir().join(format!("ulp_audit_close_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let (audit, writer) = AuditLog::open(path.to_str().unwrap()).await?;
+    let held_by_task = audit.clone();
+    let route = common::route(Address::from_low_u64_be(1), DexType::UniswapV3, Address::from_low_u64_be(2), DexType::VelodromeV2);
+    audit.record(|| AuditRecord::new(AuditEvent::Detected, &route));
+    audit.record(|| AuditRecord::new(AuditEvent::Submitted, &route));
+    audit.close();
+    tokio::time::timeout(std::time::Duration::from_secs(5), writer).await.expect("Writer should stop once closed")?;
+    held_by_task.record(|| AuditRecord::new(AuditEvent::Confirmed, &route));
+
+    let contents = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(contents.lines().count(), 2, "Queued records are written; later ones dropped");
+    Ok(())
+}
+
+#[test]
+fn test_disabled_audit_log_never_builds_records() {
+    AuditLog::disabled().record(|| panic!("Record built while the audit log is disabled"));
+}
+
+#[test]
+fn test_failed_submission_dump_serializes_calldata_and_salt() -> eyre::Result<()> {
+    let route = common::route(Address::from_low_u64_be(1), DexType::UniswapV3, Address::from_low_u64_be(2), DexType::VelodromeV2);
+    let dump = FailedSubmission {
+        stage: "on_chain".to_string(), to: Address::from_low_u64_be(0xba), calldata: Bytes::from(vec![0x5c, 0x38, 0x44, 0x9e, 0x01]),
+        salt: "42".to_string(), gas_limit: Some("450000".to_string()), max_fee_per_gas: "1000".to_string(),
+        max_priority_fee_per_gas: "10".to_string(), nonce: Some("7".to_string()), route: format!("{:?}", route),
+    };
+    let plain = serde_json::to_value(AuditRecord::new(AuditEvent::Reverted, &route))?;
+    assert!(plain.get("failed_submission").is_none(), "Dump omitted when not captured");
+
+    let line = serde_json::to_value(AuditRecord::new(AuditEvent::Reverted, &route).failed_submission(Some(dump)))?;
+    let dump = &line["failed_submission"];
+    assert_eq!(dump["stage"], "on_chain");
+    assert_eq!(dump["calldata"], "0x5c38449e01");
+    assert_eq!(dump["salt"], "42");
+    assert_eq!(dump["nonce"], "7");
+    assert!(dump["route"].as_str().unwrap().contains("buy_pool_addr"));
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// tests/balance_test.rs
+// Offline tests for the signer gas balance monitor (mocked provider, no Anvil required).
+
+use ethers::providers::Provider;
+use ethers::types::{Address, U256};
+use ulp1_5::balance::{apply_gas_balance, check_gas_balance, GasBalanceTransition};
+use ulp1_5::config::Config;
+use ulp1_5::state::{AppState, PauseReason};
+
+fn app_state_with_min(min_wei: u64) -> AppState {
+    AppState::new(Config { min_gas_balance_wei: U256::from(min_wei), ..Default::default() })
+}
+
+#[test]
+fn test_low_balance_trips_and_refill_clears_breaker() {
+    let app_state = app_state_with_min(1_000_000_000_000);
+    assert_eq!(apply_gas_balance(&app_state, U256::from(2_000_000_000_000u64)), None);
+    assert!(!app_state.is_paused());
+
+    assert_eq!(apply_gas_balance(&app_state, U256::from(999_999_999_999u64)), Some(GasBalanceTransition::Low));
+    assert!(app_state.is_paused());
+    assert_eq!(apply_gas_balance(&app_state, U256::from(5u64)), None, "Alert only on the crossing");
+    assert_eq!(app_state.metrics.values().iter().find(|(n, _)| *n == "signer_balance_gwei").unwrap().1, 0);
+
+    assert_eq!(apply_gas_balance(&app_state, U256::from(1_000_000_000_000u64)), Some(GasBalanceTransition::Refilled));
+    assert!(!app_state.is_paused());
+    assert_eq!(app_state.metrics.values().iter().find(|(n, _)| *n == "signer_balance_gwei").unwrap().1, 1_000);
+}
+
+#[test]
+fn test_refill_does_not_clear_manual_pause() {
+    let app_state = app_state_with_min(1_000);
+    app_state.set_paused(PauseReason::Admin, true);
+    assert_eq!(apply_gas_balance(&app_state, U256::from(10_000u64)), None);
+    assert!(app_state.is_paused());
+
+    // Low balance, then an admin pause on top: the refill only lifts its own reason
+    let app_state = app_state_with_min(1_000);
+    assert_eq!(apply_gas_balance(&app_state, U256::from(10u64)), Some(GasBalanceTransition::Low));
+    app_state.set_paused(PauseReason::Admin, true);
+    assert_eq!(app_state.pause_reasons(), vec![PauseReason::Admin, PauseReason::LowGasBalance]);
+    assert_eq!(apply_gas_balance(&app_state, U256::from(10_000u64)), Some(GasBalanceTransition::Refilled));
+    assert!(app_state.is_paused(), "Admin pause survives the refill");
+    assert_eq!(app_state.pause_reasons(), vec![PauseReason::Admin]);
+    assert_eq!(apply_gas_balance(&app_state, U256::from(10_000u64)), None, "Healthy readings leave the breaker alone");
+    assert!(app_state.is_paused());
+    // Zero minimum disables the breaker entirely
+    let disabled = app_state_with_min(0);
+    assert_eq!(apply_gas_balance(&disabled, U256::zero()), None);
+    assert!(!disabled.is_paused());
+}
+
+#[tokio::test]
+async fn test_check_gas_balance_reads_signer_balance() {
+    let (provider, mock) = Provider::mocked();
+    mock.push::<U256, _>(U256::from(10u64)).unwrap();
+    let app_state = app_state_with_min(100);
+    let balance = check_gas_balance(&provider, &app_state, Address::from_low_u64_be(0xbeef)).await.unwrap();
+    assert_eq!(balance, U256::from(10u64));
+    assert!(app_state.is_paused());
+}
@@ -0,0 +1,35 @@
+// tests/chain_presets_test.rs
+// Built-in per-chain address presets.
+
+use ethers::types::Address;
+use ulp1_5::chain_presets::ChainPreset;
+
+#[test]
+fn test_presets_have_checksummed_nonzero_addresses() {
+    // Building a preset asserts that every literal is EIP-55 checksummed.
+    for (preset, chain_id) in [(ChainPreset::Mainnet, 1), (ChainPreset::Optimism, 10), (ChainPreset::Base, 8453)] {
+        let config = preset.config();
+        assert_eq!(config.chain_id, Some(chain_id));
+        assert_eq!((config.weth_decimals, config.usdc_decimals), (18, 6));
+        for address in [config.uniswap_v3_factory_addr, config.quoter_v2_address, config.balancer_vault_address, config.weth_address, config.usdc_address] {
+            assert_ne!(address, Address::zero(), "{:?} preset is missing a core address", preset);
+        }
+    }
+
+    let optimism = ChainPreset::optimism();
+    assert!(!optimism.velodrome_v2_factory_addr.is_zero() && !optimism.velo_router_addr.is_zero());
+    let base = ChainPreset::base();
+    assert!(base.aerodrome_factory_addr.is_some() && base.aerodrome_router_addr.is_some());
+    assert!(base.velodrome_v2_factory_addr.is_zero(), "Velodrome is not deployed on Base");
+    let mainnet = ChainPreset::mainnet();
+    assert_ne!(mainnet.weth_address, optimism.weth_address);
+}
+
+#[test]
+fn test_preset_names_parse() {
+    assert_eq!("Optimism".parse::<ChainPreset>().unwrap(), ChainPreset::Optimism);
+    assert_eq!("ethereum".parse::<ChainPreset>().unwrap(), ChainPreset::Mainnet);
+    assert_eq!(ChainPreset::Base.config().chain_preset.as_deref(), Some("base"));
+    assert!("arbitrum".parse::<ChainPreset>().is_err());
+}
+// END OF FILE: tests/chain_presets_test.rs
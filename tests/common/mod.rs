@@ -0,0 +1,26 @@
+// tests/common/mod.rs
+// Fixtures shared by the test crates (each crate uses a subset).
+#![allow(dead_code)]
+
+use ethers::types::Address;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::state::DexType;
+
+pub fn weth() -> Address { Address::from_low_u64_be(0xeeee) }
+pub fn usdc() -> Address { Address::from_low_u64_be(0xcccc) }
+
+/// WETH -> USDC route buying on `buy` and selling on `sell`, swap A selling token0. Each leg gets what its
+/// DEX needs to be quoted: the 0.05% tier on UniV3, the volatile curve on Velo/Aero.
+pub fn route(buy: Address, buy_dex_type: DexType, sell: Address, sell_dex_type: DexType) -> RouteCandidate {
+    let fee = |dex: DexType| (dex == DexType::UniswapV3).then_some(500);
+    let stable = |dex: DexType| matches!(dex, DexType::VelodromeV2 | DexType::Aerodrome).then_some(false);
+    RouteCandidate {
+        buy_pool_addr: buy, sell_pool_addr: sell, buy_dex_type, sell_dex_type,
+        token_in: weth(), token_out: usdc(),
+        buy_pool_fee: fee(buy_dex_type), sell_pool_fee: fee(sell_dex_type),
+        buy_pool_stable: stable(buy_dex_type), sell_pool_stable: stable(sell_dex_type),
+        zero_for_one_a: true,
+        ..Default::default()
+    }
+}
+// END OF FILE: tests/common/mod.rs
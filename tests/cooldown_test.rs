@@ -0,0 +1,48 @@
+// tests/cooldown_test.rs
+// Per-pool cooldown after reverted submissions.
+
+mod common;
+
+use ethers::types::Address;
+use std::time::{Duration, Instant};
+use ulp1_5::cooldown::PoolCooldowns;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::state::DexType;
+
+fn route(buy: u64, sell: u64) -> RouteCandidate {
+    common::route(Address::from_low_u64_be(buy), DexType::VelodromeV2, Address::from_low_u64_be(sell), DexType::UniswapV3)
+}
+
+#[test]
+fn test_candidate_after_revert_skipped_until_cooldown_elapses() {
+    let cooldowns = PoolCooldowns::default();
+    let base = Duration::from_secs(300);
+    let now = Instant::now();
+    cooldowns.record_failure(&route(1, 2), base, now);
+
+    let through_reverted_pool = route(3, 2);
+    assert!(cooldowns.route_cooling_down(&through_reverted_pool, now + Duration::from_secs(299)));
+    assert!(!cooldowns.route_cooling_down(&through_reverted_pool, now + base), "Cooldown elapsed");
+    assert!(!cooldowns.route_cooling_down(&route(3, 4), now), "Unrelated pools are unaffected");
+    assert_eq!(cooldowns.active(now), 2);
+}
+
+#[test]
+fn test_repeat_reverts_lengthen_cooldown_and_success_decays() {
+    let cooldowns = PoolCooldowns::default();
+    let base = Duration::from_secs(60);
+    let now = Instant::now();
+    let pool = Address::from_low_u64_be(2);
+    cooldowns.record_failure(&route(1, 2), base, now);
+    cooldowns.record_failure(&route(1, 2), base, now);
+    assert_eq!(cooldowns.remaining(pool, now), Some(Duration::from_secs(120)), "Second strike doubles the cooldown");
+
+    cooldowns.record_success(&route(1, 2));
+    assert!(cooldowns.remaining(pool, now).is_some(), "One strike left");
+    cooldowns.record_success(&route(1, 2));
+    assert!(cooldowns.remaining(pool, now).is_none(), "Strikes decayed away");
+
+    cooldowns.record_failure(&route(1, 2), Duration::ZERO, now);
+    assert_eq!(cooldowns.active(now), 0, "A zero cooldown disables the feature");
+}
+// END OF FILE: tests/cooldown_test.rs
@@ -0,0 +1,44 @@
+// tests/deadline_test.rs
+// Opportunity deadline: stage checks before and after expiry, and block lateness against the target block.
+
+use ethers::types::U64;
+use std::time::Duration;
+use ulp1_5::deadline::{blocks_late, target_block, Deadline, DeadlineExpired};
+
+#[test]
+fn test_check_passes_before_expiry() {
+    let deadline = Deadline::from_ttl_ms(60_000);
+    assert!(!deadline.is_expired());
+    assert_eq!(deadline.check("simulation"), Ok(()));
+    assert!(deadline.remaining() > Duration::from_secs(59), "Budget barely used");
+}
+
+#[test]
+fn test_check_fails_with_stage_once_expired() {
+    let deadline = Deadline::after(Duration::ZERO);
+    assert!(deadline.is_expired(), "A zero budget is expired at once");
+    assert!(matches!(deadline.check("gas estimation"), Err(DeadlineExpired { stage: "gas estimation", .. })));
+    assert_eq!(deadline.remaining(), Duration::ZERO);
+
+    // Not yet expired, then expired after the budget runs out
+    let deadline = Deadline::from_ttl_ms(20);
+    assert!(deadline.check("detection").is_ok());
+    std::thread::sleep(Duration::from_millis(30));
+    let expired = deadline.check("submission").unwrap_err();
+    assert_eq!(expired.stage, "submission");
+    assert!(expired.elapsed >= Duration::from_millis(20), "Elapsed time covers the whole budget");
+    assert!(expired.to_string().contains("'submission'"));
+}
+
+#[test]
+fn test_blocks_late_counts_blocks_past_the_target() {
+    let source = U64::from(1_000);
+    assert_eq!(target_block(source), U64::from(1_001));
+    assert_eq!(blocks_late(source, U64::from(999)), 0, "Head behind the source block is not late");
+    assert_eq!(blocks_late(source, U64::from(1_000)), 0);
+    assert_eq!(blocks_late(source, U64::from(1_001)), 0, "Head at the target block is on time");
+    assert_eq!(blocks_late(source, U64::from(1_002)), 1);
+    assert_eq!(blocks_late(source, U64::from(1_010)), 9);
+    assert_eq!(blocks_late(U64::zero(), U64::from(1_010)), 0, "Unknown source block is never late");
+    assert_eq!(blocks_late(U64::MAX, U64::MAX), 0, "Target block saturates instead of overflowing");
+}
@@ -0,0 +1,118 @@
+// tests/deploy_test.rs
+// Offline tests for the startup executor ABI check (crafted bytecode, mocked provider).
+
+use ethers::providers::{JsonRpcError, MockResponse, Provider};
+use ethers::types::{Address, Bytes};
+use std::path::Path;
+use ulp1_5::config::{Config, ExecutorAbiVersion, EXECUTOR_BYTECODE_FILE};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{Bytes as EvmBytes, SpecId, TransactTo};
+use revm::Evm;
+use ulp1_5::deploy::{missing_selectors, required_executor_selectors, verify_executor_selectors, verify_transient_storage, TRANSIENT_STORAGE_PROBE};
+
+/// Dispatcher-shaped code: `PUSH4 <selector> EQ` for each selector.
+fn dispatcher(selectors: &[[u8; 4]]) -> Vec<u8> {
+    let mut code = vec![0x5f, 0x35, 0x60, 0xe0, 0x1c]; // PUSH0 CALLDATALOAD PUSH1 0xe0 SHR
+    for selector in selectors {
+        code.push(0x63);
+        code.extend_from_slice(selector);
+        code.push(0x14);
+    }
+    code
+}
+
+#[test]
+fn test_missing_selectors_scans_push_immediates() {
+    let required = required_executor_selectors(ExecutorAbiVersion::V2_4);
+    let all: Vec<[u8; 4]> = required.iter().map(|(_, s)| *s).collect();
+    assert!(missing_selectors(&dispatcher(&all), &required).is_empty());
+
+    let missing = missing_selectors(&dispatcher(&all[1..]), &required);
+    assert_eq!(missing, vec!["receiveFlashLoan(address[],uint256[],uint256[],bytes)"]);
+
+    // The selector bytes inside a PUSH32 immediate are data, not a dispatcher constant
+    let mut hidden = vec![0x7f];
+    hidden.extend_from_slice(&[0u8; 28]);
+    hidden.extend_from_slice(&all[0]);
+    assert_eq!(missing_selectors(&hidden, &required[..1]).len(), 1);
+
+    // Leading zero bytes may be dropped by the compiler (PUSH3 for 0x00xxxxxx)
+    assert!(missing_selectors(&[0x62, 0xab, 0xcd, 0xef], &[("f()", [0x00, 0xab, 0xcd, 0xef])]).is_empty());
+}
+
+#[test]
+fn test_required_selectors_follow_abi_version() {
+    let names = |version| required_executor_selectors(version).into_iter().map(|(sig, _)| sig).collect::<Vec<_>>();
+    let v2_3 = names(ExecutorAbiVersion::V2_3);
+    assert_eq!(v2_3, vec![
+        "receiveFlashLoan(address[],uint256[],uint256[],bytes)",
+        "uniswapV3SwapCallback(int256,int256,bytes)",
+        "withdrawToken(address,address)",
+    ]);
+
+    // 2.4 loans are started through the owner-only entry point, which 2.3 executors lack
+    let v2_4 = names(ExecutorAbiVersion::V2_4);
+    assert_eq!(v2_4[..3], v2_3[..]);
+    assert_eq!(v2_4[3..], ["executeFlashLoan(address,uint256,bytes)"]);
+}
+
+#[tokio::test]
+async fn test_verify_executor_selectors_fails_fast() {
+    let config = Config::default();
+    let executor = Address::from_low_u64_be(0xe8ec);
+    let (provider, mock) = Provider::mocked();
+
+    mock.push::<Bytes, _>(Bytes::from(dispatcher(&[[0xde, 0xad, 0xbe, 0xef]]))).unwrap();
+    let err = verify_executor_selectors(&provider, executor, &config).await.unwrap_err().to_string();
+    assert!(err.contains("withdrawToken(address,address)"), "{}", err);
+
+    mock.push::<Bytes, _>(Bytes::new()).unwrap();
+    assert!(verify_executor_selectors(&provider, executor, &config).await.is_err(), "No code at the address");
+
+    let all: Vec<[u8; 4]> = required_executor_selectors(config.executor_abi_version).iter().map(|(_, s)| *s).collect();
+    mock.push::<Bytes, _>(Bytes::from(dispatcher(&all))).unwrap();
+    verify_executor_selectors(&provider, executor, &config).await.unwrap();
+
+    // The same 2.3-style dispatcher fails the 2.4 check but passes the 2.3 one
+    let v2_3 = Config { executor_abi_version: ExecutorAbiVersion::V2_3, ..Config::default() };
+    mock.push::<Bytes, _>(Bytes::from(dispatcher(&all[..3]))).unwrap();
+    let err = verify_executor_selectors(&provider, executor, &config).await.unwrap_err().to_string();
+    assert!(err.contains("executeFlashLoan(address,uint256,bytes)"), "{}", err);
+    mock.push::<Bytes, _>(Bytes::from(dispatcher(&all[..3]))).unwrap();
+    verify_executor_selectors(&provider, executor, &v2_3).await.unwrap();
+}
+
+#[test]
+fn test_transient_storage_probe_needs_cancun() {
+    let probe_succeeds = |spec: SpecId| {
+        let mut evm = Evm::builder().with_db(CacheDB::new(EmptyDB::default())).with_spec_id(spec).build();
+        let tx = evm.tx_mut();
+        tx.transact_to = TransactTo::create();
+        tx.data = EvmBytes::from(TRANSIENT_STORAGE_PROBE.to_vec());
+        tx.gas_limit = 100_000;
+        evm.transact().unwrap().result.is_success()
+    };
+    assert!(probe_succeeds(SpecId::CANCUN));
+    assert!(!probe_succeeds(SpecId::SHANGHAI), "TLOAD is an undefined opcode before Cancun");
+}
+
+#[tokio::test]
+async fn test_verify_transient_storage_reports_pre_cancun_chain() {
+    let (provider, mock) = Provider::mocked();
+    mock.push::<Bytes, _>(Bytes::new()).unwrap();
+    verify_transient_storage(&provider).await.unwrap();
+
+    mock.push_response(MockResponse::Error(JsonRpcError { code: -32000, message: "invalid opcode: opcode 0x5c not defined".into(), data: None }));
+    let err = verify_transient_storage(&provider).await.unwrap_err().to_string();
+    assert!(err.contains("Cancun") && err.contains("opcode 0x5c"), "{}", err);
+}
+
+#[test]
+fn test_artifact_paths_honor_configured_build_dir() {
+    let config = Config { build_dir: "/opt/ulp/out".to_string(), ..Config::default() };
+    assert_eq!(config.build_path(EXECUTOR_BYTECODE_FILE), Path::new("/opt/ulp/out/ArbitrageExecutor.bin"));
+
+    let defaults = Config::default();
+    assert_eq!(defaults.build_path(EXECUTOR_BYTECODE_FILE), Path::new("./build/ArbitrageExecutor.bin"), "An unset dir keeps the old relative path");
+}
+// END OF FILE: tests/deploy_test.rs
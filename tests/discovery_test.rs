@@ -0,0 +1,174 @@
+// tests/discovery_test.rs
+// PoolCreated backfill: block chunking, event decoding and a mocked eth_getLogs pass.
+
+mod common;
+
+use common::{usdc, weth};
+use ethers::abi::{encode, Token};
+use ethers::providers::Provider;
+use ethers::types::{Address, Bytes, Log, H256, U256, U64};
+use ulp1_5::config::Config;
+use ulp1_5::discovery::{backfill_pool_created, block_ranges, decode_pool_created, univ3_pool_keys, DiscoveredPool};
+use ulp1_5::state::DexType;
+use ulp1_5::{UNI_V3_POOL_CREATED_TOPIC, VELO_AERO_POOL_CREATED_TOPIC};
+
+fn uni_factory() -> Address { Address::from_low_u64_be(0xf1) }
+fn velo_factory() -> Address { Address::from_low_u64_be(0xf2) }
+fn aero_factory() -> Address { Address::from_low_u64_be(0xf3) }
+
+fn config() -> Config {
+    Config {
+        uniswap_v3_factory_addr: uni_factory(), velodrome_v2_factory_addr: velo_factory(), aerodrome_factory_addr: Some(aero_factory()),
+        initial_block_history_to_scan: 100, max_block_range_per_query: 1_000, enumeration_concurrency: 2,
+        ..Config::default()
+    }
+}
+
+fn topic(address: Address) -> H256 { H256::from(address) }
+
+fn uni_log(pool: Address, fee: u32, block: u64) -> Log {
+    Log {
+        address: uni_factory(),
+        topics: vec![*UNI_V3_POOL_CREATED_TOPIC, topic(usdc()), topic(weth()), H256::from_low_u64_be(fee as u64)],
+        data: Bytes::from(encode(&[Token::Int(U256::from(10)), Token::Address(pool)])),
+        block_number: Some(U64::from(block)),
+        ..Log::default()
+    }
+}
+
+/// `PoolCreated(address indexed token0, address indexed token1, bool indexed stable, address pool, uint256)`,
+/// emitted by both the Velodrome V2 and Aerodrome factories
+fn velo_log(factory: Address, pool: Address, stable: bool, block: u64) -> Log {
+    Log {
+        address: factory,
+        topics: vec![*VELO_AERO_POOL_CREATED_TOPIC, topic(usdc()), topic(weth()), H256::from_low_u64_be(stable as u64)],
+        data: Bytes::from(encode(&[Token::Address(pool), Token::Uint(U256::from(7))])),
+        block_number: Some(U64::from(block)),
+        ..Log::default()
+    }
+}
+
+#[test]
+fn test_block_ranges_chunks_inclusively() {
+    assert_eq!(block_ranges(0, 24, 10), vec![(0, 9), (10, 19), (20, 24)]);
+    assert_eq!(block_ranges(5, 5, 10), vec![(5, 5)]);
+    assert_eq!(block_ranges(10, 9, 10), vec![]);
+    // A zero range is treated as one block per query
+    assert_eq!(block_ranges(1, 3, 0), vec![(1, 1), (2, 2), (3, 3)]);
+    assert_eq!(block_ranges(u64::MAX - 1, u64::MAX, 10), vec![(u64::MAX - 1, u64::MAX)]);
+}
+
+#[test]
+fn test_decode_pool_created_per_factory() {
+    let config = config();
+    let pool = Address::from_low_u64_be(0x9001);
+
+    let uni = decode_pool_created(&uni_log(pool, 500, 42), &config).expect("UniV3 log decodes");
+    assert_eq!(uni, DiscoveredPool {
+        pool, dex_type: DexType::UniswapV3, factory: uni_factory(), token0: usdc(), token1: weth(),
+        uni_fee: Some(500), velo_stable: None, created_block: Some(U64::from(42)),
+    });
+
+    let velo = decode_pool_created(&velo_log(velo_factory(), pool, true, 43), &config).expect("Velodrome log decodes");
+    assert_eq!((velo.dex_type, velo.factory, velo.velo_stable, velo.uni_fee), (DexType::VelodromeV2, velo_factory(), Some(true), None));
+    let aero = decode_pool_created(&velo_log(aero_factory(), pool, false, 44), &config).expect("Aerodrome log decodes");
+    assert_eq!((aero.dex_type, aero.factory, aero.velo_stable), (DexType::Aerodrome, aero_factory(), Some(false)));
+    // `stable` is a topic; a log carrying it in the data is malformed
+    let unindexed = Log {
+        topics: vec![*VELO_AERO_POOL_CREATED_TOPIC, topic(usdc()), topic(weth())],
+        data: Bytes::from(encode(&[Token::Bool(true), Token::Address(pool), Token::Uint(U256::from(7))])),
+        ..velo_log(velo_factory(), pool, true, 44)
+    };
+    assert_eq!(decode_pool_created(&unindexed, &config), None);
+
+    // Same events from other contracts are ignored
+    assert_eq!(decode_pool_created(&velo_log(Address::from_low_u64_be(0xbad), pool, false, 45), &config), None);
+    assert_eq!(decode_pool_created(&Log { address: velo_factory(), ..uni_log(pool, 500, 46) }, &config), None);
+}
+
+#[test]
+fn test_decode_real_aerodrome_pool_created() {
+    // Base: Aerodrome factory creating the WETH/USDC volatile pool
+    let factory: Address = "0x420DD381b31aEf6683db6B902084cB0FFECe40Da".parse().unwrap();
+    let (weth, usdc): (Address, Address) = ("0x4200000000000000000000000000000000000006".parse().unwrap(), "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".parse().unwrap());
+    let pool: Address = "0xcDAC0d6c6C59727a65F871236188350531885C43".parse().unwrap();
+    let log = Log {
+        address: factory,
+        topics: vec![
+            "0x2128d88d14c80cb081c1252a5acff7a264671bf199ce226b53788fb26065005e".parse().unwrap(),
+            "0x0000000000000000000000004200000000000000000000000000000000000006".parse().unwrap(),
+            "0x000000000000000000000000833589fcd6edb6e08f4c7c32d4f71b54bda02913".parse().unwrap(),
+            H256::zero(),
+        ],
+        data: "0x000000000000000000000000cdac0d6c6c59727a65f871236188350531885c430000000000000000000000000000000000000000000000000000000000000006".parse().unwrap(),
+        block_number: Some(U64::from(3_200_601)),
+        ..Log::default()
+    };
+    assert_eq!(log.topics[0], *VELO_AERO_POOL_CREATED_TOPIC);
+
+    let config = Config { aerodrome_factory_addr: Some(factory), ..Config::default() };
+    assert_eq!(decode_pool_created(&log, &config), Some(DiscoveredPool {
+        pool, dex_type: DexType::Aerodrome, factory, token0: weth, token1: usdc,
+        uni_fee: None, velo_stable: Some(false), created_block: Some(U64::from(3_200_601)),
+    }));
+}
+
+#[test]
+fn test_decode_real_velodrome_pool_created() {
+    // Optimism: Velodrome V2 factory creating the WETH/USDC.e volatile pool, laid out as the factory emits
+    // it (tokens and `stable` in the topics, pool and pool count in the data). Addresses are mainnet's;
+    // the block and pool count are placeholders until the log is re-captured from an RPC.
+    let factory: Address = "0xF1046053aa5682b4F9a81b5481394DA16BE5FF5a".parse().unwrap();
+    let (weth, usdc): (Address, Address) = ("0x4200000000000000000000000000000000000006".parse().unwrap(), "0x7F5c764cBc14f9669B88837ca1490cCa17c31607".parse().unwrap());
+    let pool: Address = "0x0493Bf8b6DBB159Ce2Db2E0E8403E753Abd1235b".parse().unwrap();
+    let log = Log {
+        address: factory,
+        topics: vec![
+            "0x2128d88d14c80cb081c1252a5acff7a264671bf199ce226b53788fb26065005e".parse().unwrap(),
+            "0x0000000000000000000000004200000000000000000000000000000000000006".parse().unwrap(),
+            "0x0000000000000000000000007f5c764cbc14f9669b88837ca1490cca17c31607".parse().unwrap(),
+            H256::zero(),
+        ],
+        data: "0x0000000000000000000000000493bf8b6dbb159ce2db2e0e8403e753abd1235b0000000000000000000000000000000000000000000000000000000000000003".parse().unwrap(),
+        block_number: Some(U64::from(105_896_851)),
+        ..Log::default()
+    };
+    assert_eq!(log.topics[0], *VELO_AERO_POOL_CREATED_TOPIC);
+
+    let config = Config { velodrome_v2_factory_addr: factory, ..Config::default() };
+    assert_eq!(decode_pool_created(&log, &config), Some(DiscoveredPool {
+        pool, dex_type: DexType::VelodromeV2, factory, token0: weth, token1: usdc,
+        uni_fee: None, velo_stable: Some(false), created_block: Some(U64::from(105_896_851)),
+    }));
+}
+
+#[tokio::test]
+async fn test_backfill_decodes_logs_of_the_scanned_range() {
+    let (provider, mock) = Provider::mocked();
+    let pool_a = Address::from_low_u64_be(0xa);
+    let pool_b = Address::from_low_u64_be(0xb);
+    let unrelated = Log { address: Address::from_low_u64_be(0xbad), ..velo_log(velo_factory(), pool_b, false, 990) };
+    mock.push::<Vec<Log>, _>(vec![uni_log(pool_a, 3000, 950), unrelated, velo_log(velo_factory(), pool_b, false, 999)]).unwrap();
+
+    // 100 blocks back from 1000 fit in one query
+    let pools = backfill_pool_created(&provider, &config(), 1_000).await.unwrap();
+    assert_eq!(pools.iter().map(|p| (p.pool, p.dex_type)).collect::<Vec<_>>(), vec![(pool_a, DexType::UniswapV3), (pool_b, DexType::VelodromeV2)]);
+    assert_eq!(pools[0].uni_fee, Some(3000));
+}
+
+#[tokio::test]
+async fn test_only_configured_fee_tiers_are_monitored() {
+    let native_usdc = Address::from_low_u64_be(0xffff);
+    assert_eq!(univ3_pool_keys(weth(), &[usdc(), native_usdc], &[500, 3000]), vec![
+        (usdc(), weth(), 500), (usdc(), weth(), 3000), (weth(), native_usdc, 500), (weth(), native_usdc, 3000),
+    ]);
+    assert_eq!(Config::default().enabled_univ3_fee_tiers(), &[100, 500, 3000, 10000], "Unset means the standard tiers");
+
+    let (provider, mock) = Provider::mocked();
+    let (pool_a, pool_b) = (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb));
+    mock.push::<Vec<Log>, _>(vec![uni_log(pool_a, 10_000, 950), uni_log(pool_b, 500, 960)]).unwrap();
+    let config = Config { univ3_fee_tiers: vec![500, 3000], ..config() };
+    let pools = backfill_pool_created(&provider, &config, 1_000).await.unwrap();
+    assert_eq!(pools.iter().map(|p| p.pool).collect::<Vec<_>>(), vec![pool_b], "The 1% pool is outside the configured tiers");
+}
+// END OF FILE: tests/discovery_test.rs
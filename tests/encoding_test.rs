@@ -0,0 +1,96 @@
+// tests/encoding_test.rs
+// Offline tests for the executor userData layout and slippage floors.
+
+use ethers::types::{Address, U256};
+use ulp1_5::config::ExecutorAbiVersion;
+use ulp1_5::state::DexType;
+use ulp1_5::encoding::{encode_user_data, encode_user_data_v2_3, encode_user_data_v2_4, min_amount_out, SwapLeg};
+
+fn word(data: &[u8], offset: usize) -> U256 { U256::from_big_endian(&data[offset..offset + 32]) }
+
+#[test]
+fn test_min_amount_out_applies_slippage_bps() {
+    let simulated_out = U256::from(1_234_567_890u64);
+    assert_eq!(min_amount_out(simulated_out, 50), simulated_out * U256::from(9_950u64) / U256::from(10_000u64));
+    assert_eq!(min_amount_out(simulated_out, 0), simulated_out);
+    assert_eq!(min_amount_out(simulated_out, 10_000), U256::zero());
+    assert_eq!(min_amount_out(simulated_out, 20_000), U256::zero());
+    // No overflow near U256::MAX
+    assert_eq!(min_amount_out(U256::MAX, 0), U256::MAX);
+}
+
+#[test]
+fn test_user_data_encodes_min_outs_after_salt() {
+    let (simulated_a, simulated_b) = (U256::from(3_000_000_000u64), U256::exp10(18) + U256::from(7u64));
+    let bps = 30;
+    let (min_a, min_b) = (min_amount_out(simulated_a, bps), min_amount_out(simulated_b, bps));
+    let user_data = encode_user_data_v2_4(
+        Address::from_low_u64_be(1), Address::from_low_u64_be(2), Address::from_low_u64_be(3), true, false, true, U256::from(5u64), U256::from(6u64), min_a, min_b,
+    ).unwrap();
+    assert_eq!(user_data.len(), 11 * 32);
+    assert_eq!(word(&user_data, 0x100), U256::from(6u64));
+    assert_eq!(word(&user_data, 0x120), simulated_a * U256::from(9_970u64) / U256::from(10_000u64));
+    assert_eq!(word(&user_data, 0x140), simulated_b * U256::from(9_970u64) / U256::from(10_000u64));
+}
+
+#[test]
+fn test_v2_3_layout_is_nine_words_with_salt_last() {
+    let user_data = encode_user_data_v2_3(
+        Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb), Address::from_low_u64_be(0xc), false, true, false,
+        U256::from(77u64), U256::from(88u64),
+    ).unwrap();
+    assert_eq!(user_data.len(), 9 * 32);
+    let expected = [0xa, 0xb, 0xc, 0, 1, 0, 0, 77, 88]; // Router word unused, always zero
+    for (i, value) in expected.iter().enumerate() {
+        assert_eq!(word(&user_data, i * 32), U256::from(*value as u64), "word at 0x{:x}", i * 32);
+    }
+}
+
+#[test]
+fn test_encode_user_data_dispatches_on_abi_version() {
+    let leg_a = SwapLeg { pool: Address::from_low_u64_be(1), dex_type: DexType::VelodromeV2, zero_for_one: true };
+    let leg_b = SwapLeg { pool: Address::from_low_u64_be(2), dex_type: DexType::UniswapV3, zero_for_one: false };
+    let encode = |version| encode_user_data(
+        version, leg_a, leg_b, Address::from_low_u64_be(3), U256::from(5u64), U256::from(6u64), U256::from(7u64), U256::from(8u64),
+    ).unwrap();
+    let (v2_3, v2_4) = (encode(ExecutorAbiVersion::V2_3), encode(ExecutorAbiVersion::V2_4));
+    assert_eq!(v2_3.len(), 9 * 32);
+    assert_eq!(v2_4.len(), 11 * 32);
+    assert_eq!(v2_3[..], v2_4[..9 * 32], "v2.4 only appends words");
+
+    assert_eq!("v2.4.3".parse::<ExecutorAbiVersion>().unwrap(), ExecutorAbiVersion::V2_4);
+    assert_eq!(ExecutorAbiVersion::default(), ExecutorAbiVersion::V2_4);
+    assert!("2.2".parse::<ExecutorAbiVersion>().is_err());
+    // Executors that cannot complete a flash loan or lack executeFlashLoan are refused at config load
+    for old in ["2.3", "2.3.0", "2.4.2"] {
+        let err = old.parse::<ExecutorAbiVersion>().unwrap_err().to_string();
+        assert!(err.contains("no longer supported"), "{}", err);
+    }
+}
+
+#[test]
+fn test_univ3_to_velo_legs_round_trip() {
+    // Sells token0 into the UniV3 pool, then token1 into the Velodrome pool
+    let leg_a = SwapLeg { pool: Address::from_low_u64_be(0xa), dex_type: DexType::UniswapV3, zero_for_one: true };
+    let leg_b = SwapLeg { pool: Address::from_low_u64_be(0xb), dex_type: DexType::VelodromeV2, zero_for_one: false };
+    let user_data = encode_user_data(
+        ExecutorAbiVersion::V2_4, leg_a, leg_b, Address::from_low_u64_be(0xc),
+        U256::from(77u64), U256::from(88u64), U256::from(99u64), U256::from(111u64),
+    ).unwrap();
+    let expected = [0xa, 0xb, 0xc, 1, 0, 1, 0, 77, 88, 99, 111];
+    for (i, value) in expected.iter().enumerate() {
+        assert_eq!(word(&user_data, i * 32), U256::from(*value as u64), "word at 0x{:x}", i * 32);
+    }
+
+    // The executor runs Swap B opposite to Swap A, so a same-direction Swap B cannot be encoded
+    let same_direction = SwapLeg { zero_for_one: true, ..leg_b };
+    let err = encode_user_data(
+        ExecutorAbiVersion::V2_4, leg_a, same_direction, Address::from_low_u64_be(0xc),
+        U256::zero(), U256::zero(), U256::zero(), U256::zero(),
+    ).unwrap_err();
+    assert!(err.to_string().contains("not the inverse"), "{}", err);
+    let unknown = SwapLeg { dex_type: DexType::Unknown, ..leg_b };
+    assert!(encode_user_data(
+        ExecutorAbiVersion::V2_3, leg_a, unknown, Address::zero(), U256::zero(), U256::zero(), U256::zero(), U256::zero(),
+    ).is_err());
+}
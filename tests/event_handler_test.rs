@@ -0,0 +1,154 @@
+// tests/event_handler_test.rs
+// Offline tests for log handling (no RPC is reached: the handled paths only read cached state).
+
+use ethers::abi::{encode, Token};
+use ethers::prelude::{Http, LocalWallet, Provider, SignerMiddleware};
+use ethers::types::{Address, Bytes, Log, H256, I256, U256, U64};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use ulp1_5::config::{Config, MonitoredEvent};
+use ulp1_5::event_handler::{handle_log_event, monitored_topics};
+use ulp1_5::rpc::RpcClients;
+use ulp1_5::state::{AppState, DexType, PoolSnapshot};
+use ulp1_5::{NonceManager, UNI_V3_BURN_TOPIC, UNI_V3_MINT_TOPIC, UNI_V3_SWAP_TOPIC, VELO_AERO_SWAP_TOPIC};
+
+fn univ3_swap_log(pool: Address, tick: i32) -> Log {
+    let data = encode(&[
+        Token::Int(I256::from(-1_000).into_raw()),
+        Token::Int(I256::from(2_000).into_raw()),
+        Token::Uint(U256::one() << 96),
+        Token::Uint(U256::from(5_000)),
+        Token::Int(I256::from(tick).into_raw()),
+    ]);
+    Log {
+        address: pool,
+        topics: vec![*UNI_V3_SWAP_TOPIC, H256::zero(), H256::zero()],
+        data: Bytes::from(data),
+        block_number: Some(U64::from(100)),
+        transaction_hash: Some(H256::repeat_byte(0xab)),
+        log_index: Some(U256::from(3)),
+        ..Default::default()
+    }
+}
+
+/// Waits for the spawned arbitrage checks to run.
+async fn arbitrage_checks_reach(app_state: &AppState, count: u64) {
+    for _ in 0..100 {
+        if app_state.metrics.arbitrage_checks.load(Ordering::Relaxed) >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Fewer than {count} arbitrage checks ran");
+}
+
+#[tokio::test]
+async fn test_redelivered_log_is_processed_once() {
+    let pool = Address::from_low_u64_be(0x7e10);
+    let app_state = Arc::new(AppState::new(Config { seen_log_cache_size: 16, ..Config::default() }));
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: None, reserve1: None, sqrt_price_x96: None, tick: Some(0), liquidity: None,
+        last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    app_state.record_block_timestamp(U64::from(100), 1_700_000_000);
+    let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+    let clients = RpcClients::shared(Arc::new(SignerMiddleware::new(provider, "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse::<LocalWallet>().unwrap())));
+    let nonce_manager = Arc::new(NonceManager::new(Address::zero()));
+    let log = univ3_swap_log(pool, 42);
+
+    handle_log_event(log.clone(), app_state.clone(), clients.clone(), nonce_manager.clone()).await.unwrap();
+    assert_eq!(app_state.pool_snapshots.get(&pool).unwrap().tick, Some(42));
+    arbitrage_checks_reach(&app_state, 1).await;
+
+    // Reset the snapshot: a second update would show up as tick 42 again
+    app_state.pool_snapshots.get_mut(&pool).unwrap().tick = Some(7);
+    handle_log_event(log.clone(), app_state.clone(), clients.clone(), nonce_manager.clone()).await.unwrap();
+    assert_eq!(app_state.pool_snapshots.get(&pool).unwrap().tick, Some(7), "Re-delivered log must not update the snapshot");
+    assert_eq!(app_state.metrics.duplicate_logs_skipped.load(Ordering::Relaxed), 1);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(app_state.metrics.arbitrage_checks.load(Ordering::Relaxed), 1, "Re-delivered log must not trigger another arbitrage check");
+
+    // A different log of the same transaction is processed
+    let next = Log { log_index: Some(U256::from(4)), ..univ3_swap_log(pool, 43) };
+    handle_log_event(next, app_state.clone(), clients, nonce_manager).await.unwrap();
+    assert_eq!(app_state.pool_snapshots.get(&pool).unwrap().tick, Some(43));
+}
+
+#[test]
+fn test_seen_log_cache_is_bounded() {
+    let seen = ulp1_5::seen_logs::SeenLogs::new(2);
+    let logs: Vec<Log> = (0..3).map(|i| Log { transaction_hash: Some(H256::repeat_byte(1)), log_index: Some(U256::from(i)), ..Default::default() }).collect();
+    assert!(logs.iter().all(|log| !seen.check_and_insert(log)));
+    assert_eq!(seen.len(), 2);
+    assert!(!seen.check_and_insert(&logs[0]), "Oldest key was evicted");
+    assert!(seen.check_and_insert(&logs[2]));
+    assert!(!seen.check_and_insert(&Log::default()), "Logs without tx hash/index are never deduplicated");
+    assert!(!seen.check_and_insert(&Log::default()));
+}
+
+fn indexed_tick(tick: i32) -> H256 {
+    let mut word = [0u8; 32];
+    I256::from(tick).into_raw().to_big_endian(&mut word);
+    H256(word)
+}
+
+/// UniV3 `Mint(sender, owner indexed, tickLower indexed, tickUpper indexed, amount, amount0, amount1)` or
+/// `Burn(owner indexed, tickLower indexed, tickUpper indexed, amount, amount0, amount1)`.
+fn univ3_liquidity_log(pool: Address, mint: bool, tick_lower: i32, tick_upper: i32, amount: u64, log_index: u64) -> Log {
+    let mut data = vec![Token::Uint(U256::from(amount)), Token::Uint(U256::from(1_000)), Token::Uint(U256::from(2_000))];
+    if mint {
+        data.insert(0, Token::Address(Address::from_low_u64_be(0x5e)));
+    }
+    Log {
+        address: pool,
+        topics: vec![if mint { *UNI_V3_MINT_TOPIC } else { *UNI_V3_BURN_TOPIC }, H256::zero(), indexed_tick(tick_lower), indexed_tick(tick_upper)],
+        data: Bytes::from(encode(&data)),
+        block_number: Some(U64::from(101)),
+        transaction_hash: Some(H256::repeat_byte(0xcd)),
+        log_index: Some(U256::from(log_index)),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_mint_and_burn_update_active_liquidity() {
+    let pool = Address::from_low_u64_be(0x7e11);
+    let app_state = Arc::new(AppState::new(Config { seen_log_cache_size: 16, ..Config::default() }));
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: None, reserve1: None, sqrt_price_x96: Some(U256::one() << 96), tick: Some(0), liquidity: Some(5_000),
+        last_update_block: Some(U64::from(100)), last_update_timestamp: None,
+        ..Default::default()
+    });
+    let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+    let clients = RpcClients::shared(Arc::new(SignerMiddleware::new(provider, "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse::<LocalWallet>().unwrap())));
+    let nonce_manager = Arc::new(NonceManager::new(Address::zero()));
+    let liquidity = || app_state.pool_snapshots.get(&pool).unwrap().liquidity;
+
+    handle_log_event(univ3_liquidity_log(pool, true, -60, 60, 1_000, 0), app_state.clone(), clients.clone(), nonce_manager.clone()).await.unwrap();
+    assert_eq!(liquidity(), Some(6_000), "In-range Mint adds liquidity");
+    assert_eq!(app_state.pool_snapshots.get(&pool).unwrap().last_update_block, Some(U64::from(101)));
+
+    handle_log_event(univ3_liquidity_log(pool, false, 0, 60, 2_500, 1), app_state.clone(), clients.clone(), nonce_manager.clone()).await.unwrap();
+    assert_eq!(liquidity(), Some(3_500), "Burn of a range starting at the current tick removes liquidity");
+
+    // The upper tick is exclusive, and out-of-range positions do not touch the active liquidity
+    handle_log_event(univ3_liquidity_log(pool, true, -60, 0, 1_000, 2), app_state.clone(), clients.clone(), nonce_manager.clone()).await.unwrap();
+    handle_log_event(univ3_liquidity_log(pool, false, 60, 120, 1_000, 3), app_state.clone(), clients, nonce_manager).await.unwrap();
+    assert_eq!(liquidity(), Some(3_500));
+}
+
+#[test]
+fn test_monitored_topics_follow_config() {
+    let default_topics = monitored_topics(Config::default().enabled_monitored_events());
+    assert_eq!(default_topics.len(), 4);
+    assert!(!default_topics.contains(&UNI_V3_MINT_TOPIC) && !default_topics.contains(&UNI_V3_BURN_TOPIC));
+
+    let events: Vec<MonitoredEvent> = "swap, mint,burn".split(',').map(|e| e.parse().unwrap()).collect();
+    assert_eq!(monitored_topics(&events), vec![*UNI_V3_SWAP_TOPIC, *VELO_AERO_SWAP_TOPIC, *UNI_V3_MINT_TOPIC, *UNI_V3_BURN_TOPIC]);
+    assert!("sync".parse::<MonitoredEvent>().is_err());
+}
+// END OF FILE: tests/event_handler_test.rs
@@ -0,0 +1,368 @@
+// tests/executor_evm_test.rs
+// Runs the shipped ArbitrageExecutor artifact (build/ArbitrageExecutor.bin) in an in-process EVM (revm)
+// against Huff mocks of an ERC20, a UniV3 pool, a Velodrome pool and the Balancer vault (installed at its
+// real address), so the flash loan path is exercised without an Anvil fork.
+
+use ethers::abi::{encode, Token};
+use ethers::types::{Address, Log as EthLog, TransactionReceipt, H256, I256, U256};
+use ethers::utils::{hex, id, parse_ether};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{AccountInfo, Bytecode, Bytes as EvmBytes, ExecutionResult, Log, Output, SpecId, TransactTo, U256 as EvmU256};
+use revm::Evm;
+use ulp1_5::config::{DEFAULT_BUILD_DIR, EXECUTOR_BYTECODE_FILE};
+use ulp1_5::encoding::encode_user_data_v2_4;
+use ulp1_5::transaction::realized_profit_from_receipt;
+
+const BALANCER_VAULT: &str = "0xBA12222222228d8Ba445958a75a0704d566BF2C9";
+const PROFIT_REALIZED: &str = "ProfitRealized(int256)";
+
+/// Runtime bytecode of the Huff mocks in tests/mocks (an ERC20, the vault, a UniV3 pool and a Velodrome pool).
+const MOCK_TOKEN: &str = include_str!("mocks/token.bin");
+const MOCK_VAULT: &str = include_str!("mocks/vault.bin");
+const MOCK_UNIV3_POOL: &str = include_str!("mocks/univ3_pool.bin");
+const MOCK_VELO_POOL: &str = include_str!("mocks/velo_pool.bin");
+
+fn evm_address(address: Address) -> revm::primitives::Address {
+    revm::primitives::Address::from_slice(address.as_bytes())
+}
+
+fn evm_u256(value: U256) -> EvmU256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    EvmU256::from_be_bytes(bytes)
+}
+
+fn runtime(mock: &str) -> Vec<u8> {
+    hex::decode(mock.trim()).unwrap()
+}
+
+/// Creation code of the shipped executor artifact.
+fn executor_bytecode() -> Vec<u8> {
+    let artifact = std::fs::read_to_string(format!("{}/{}", DEFAULT_BUILD_DIR, EXECUTOR_BYTECODE_FILE)).unwrap();
+    hex::decode(artifact.trim().trim_start_matches("0x")).unwrap()
+}
+
+struct Chain {
+    evm: Evm<'static, (), CacheDB<EmptyDB>>,
+}
+
+impl Chain {
+    fn new() -> Self {
+        Self { evm: Evm::builder().with_db(CacheDB::new(EmptyDB::default())).with_spec_id(SpecId::CANCUN).build() }
+    }
+
+    fn install(&mut self, at: Address, code: Vec<u8>, storage: &[(U256, U256)]) {
+        let code = Bytecode::new_raw(EvmBytes::from(code));
+        let db = self.evm.db_mut();
+        db.insert_account_info(evm_address(at), AccountInfo { code_hash: code.hash_slow(), code: Some(code), ..Default::default() });
+        for (slot, value) in storage {
+            db.insert_account_storage(evm_address(at), evm_u256(*slot), evm_u256(*value)).unwrap();
+        }
+    }
+
+    fn transact(&mut self, from: Address, to: TransactTo, data: Vec<u8>) -> ExecutionResult {
+        let tx = self.evm.tx_mut();
+        tx.caller = evm_address(from);
+        tx.transact_to = to;
+        tx.data = EvmBytes::from(data);
+        tx.gas_limit = 10_000_000;
+        tx.nonce = None;
+        self.evm.transact_commit().unwrap()
+    }
+
+    fn call(&mut self, from: Address, to: Address, data: Vec<u8>) -> ExecutionResult {
+        self.transact(from, TransactTo::Call(evm_address(to)), data)
+    }
+
+    fn balance_of(&mut self, token: Address, holder: Address) -> U256 {
+        let data = [&id("balanceOf(address)")[..], &encode(&[Token::Address(holder)])].concat();
+        match self.call(Address::from_low_u64_be(0xea0a), token, data) {
+            ExecutionResult::Success { output: Output::Call(out), .. } => U256::from_big_endian(&out),
+            other => panic!("balanceOf failed: {:?}", other),
+        }
+    }
+}
+
+/// Executor deployed by `owner`, tokens (t0 < t1), a UniV3 pool selling t1 at 2000 per t0 and buying it
+/// back at 2000, and a Velodrome pool selling t1 at 1990 per t0 and buying it back at 1990. Buying t1 on
+/// UniV3 and selling it on Velodrome is profitable, the other way round is not.
+struct Setup {
+    chain: Chain,
+    owner: Address,
+    executor: Address,
+    vault: Address,
+    t0: Address,
+    t1: Address,
+    uni: Address,
+    velo: Address,
+}
+
+fn rate(numerator: u64, denominator: u64) -> U256 {
+    parse_ether(1).unwrap() * U256::from(numerator) / U256::from(denominator)
+}
+
+fn setup() -> Setup {
+    let mut chain = Chain::new();
+    let owner = Address::from_low_u64_be(0x0a11ce);
+    let vault: Address = BALANCER_VAULT.parse().unwrap();
+    let (t0, t1) = (Address::from_low_u64_be(0x1000), Address::from_low_u64_be(0x2000));
+    let (uni, velo) = (Address::from_low_u64_be(0x3000), Address::from_low_u64_be(0x4000));
+    let slot = |a: Address| U256::from_big_endian(a.as_bytes());
+    let liquidity = parse_ether(1_000_000).unwrap();
+
+    chain.install(t0, runtime(MOCK_TOKEN), &[(slot(vault), parse_ether(100).unwrap()), (slot(uni), liquidity), (slot(velo), liquidity)]);
+    chain.install(t1, runtime(MOCK_TOKEN), &[(slot(uni), liquidity), (slot(velo), liquidity)]);
+    chain.install(vault, runtime(MOCK_VAULT), &[]);
+    let pool_storage = |rate01: U256, rate10: U256| vec![
+        (U256::zero(), slot(t0)), (U256::one(), slot(t1)), (U256::from(2), rate01), (U256::from(3), rate10),
+        (U256::from(4), liquidity), (U256::from(5), liquidity),
+    ];
+    chain.install(uni, runtime(MOCK_UNIV3_POOL), &pool_storage(rate(2000, 1), rate(1, 2000)));
+    chain.install(velo, runtime(MOCK_VELO_POOL), &pool_storage(rate(1990, 1), rate(1, 1990)));
+
+    let bytecode = executor_bytecode();
+    let executor = match chain.transact(owner, TransactTo::create(), bytecode) {
+        ExecutionResult::Success { output: Output::Create(_, Some(address)), .. } => Address::from_slice(address.as_slice()),
+        other => panic!("Executor deployment failed: {:?}", other),
+    };
+    Setup { chain, owner, executor, vault, t0, t1, uni, velo }
+}
+
+/// One flash loan route through the mock vault.
+struct Route {
+    loan_token: Address,
+    intermediate: Address,
+    pool_a: (Address, bool),
+    pool_b: (Address, bool),
+    zero_for_one_a: bool,
+    loan: U256,
+    fee: U256,
+    min_profit: U256,
+    salt: U256,
+    min_out_a: U256,
+    min_out_b: U256,
+}
+
+impl Setup {
+    fn route(&self) -> Route {
+        Route {
+            loan_token: self.t0, intermediate: self.t1, pool_a: (self.uni, false), pool_b: (self.velo, true), zero_for_one_a: true,
+            loan: parse_ether(1).unwrap(), fee: U256::zero(), min_profit: U256::zero(), salt: U256::one(),
+            min_out_a: U256::zero(), min_out_b: U256::zero(),
+        }
+    }
+
+    fn user_data(route: &Route) -> Vec<u8> {
+        encode_user_data_v2_4(
+            route.pool_a.0, route.pool_b.0, route.intermediate, route.zero_for_one_a, route.pool_a.1, route.pool_b.1,
+            route.min_profit, route.salt, route.min_out_a, route.min_out_b,
+        ).unwrap().to_vec()
+    }
+
+    /// The owner's `executeFlashLoan`, with the vault charging `route.fee`.
+    fn flash_loan(&mut self, route: &Route) -> ExecutionResult {
+        self.chain.evm.db_mut().insert_account_storage(evm_address(self.vault), EvmU256::ZERO, evm_u256(route.fee)).unwrap();
+        let data = [&id("executeFlashLoan(address,uint256,bytes)")[..], &encode(&[
+            Token::Address(route.loan_token), Token::Uint(route.loan), Token::Bytes(Self::user_data(route)),
+        ])].concat();
+        let (owner, executor) = (self.owner, self.executor);
+        self.chain.call(owner, executor, data)
+    }
+
+    /// `vault.flashLoan(executor, ...)` sent by `from` directly, bypassing `executeFlashLoan`.
+    fn vault_flash_loan(&mut self, from: Address, route: &Route) -> ExecutionResult {
+        let data = [&id("flashLoan(address,address[],uint256[],bytes)")[..], &encode(&[
+            Token::Address(self.executor), Token::Array(vec![Token::Address(route.loan_token)]),
+            Token::Array(vec![Token::Uint(route.loan)]), Token::Bytes(Self::user_data(route)),
+        ])].concat();
+        let vault = self.vault;
+        self.chain.call(from, vault, data)
+    }
+}
+
+fn profit_realized(logs: &[Log], executor: Address) -> Vec<U256> {
+    let topic = ethers::utils::keccak256(PROFIT_REALIZED);
+    logs.iter()
+        .filter(|log| log.address.as_slice() == executor.as_bytes() && log.data.topics().first().map(|t| t.0) == Some(topic))
+        .map(|log| U256::from_big_endian(&log.data.data))
+        .collect()
+}
+
+#[test]
+fn test_executor_deploys_with_owner_and_runtime() {
+    let Setup { chain, owner, executor, .. } = setup();
+    let account = chain.evm.db().accounts.get(&evm_address(executor)).unwrap();
+    let code = account.info.code.as_ref().unwrap().original_bytes();
+    assert!(!code.is_empty() && executor_bytecode().ends_with(code.as_ref()), "Constructor returns the runtime appended to it");
+    assert_eq!(account.storage.get(&EvmU256::ZERO).copied(), Some(evm_u256(U256::from_big_endian(owner.as_bytes()))));
+}
+
+#[test]
+fn test_flash_loan_arbitrage_repays_vault_and_emits_profit() {
+    let mut s = setup();
+    let vault_before = s.chain.balance_of(s.t0, s.vault);
+    let mut route = s.route();
+    route.fee = parse_ether(0.001).unwrap();
+    route.min_profit = parse_ether(0.004).unwrap();
+    let logs = match s.flash_loan(&route) {
+        ExecutionResult::Success { logs, .. } => logs,
+        other => panic!("Arbitrage failed: {:?}", other),
+    };
+    // 1 t0 -> 2000 t1 on UniV3 -> 2000/1990 t0 on Velodrome, less the loan and fee
+    let returned = parse_ether(2000).unwrap() * rate(1, 1990) / parse_ether(1).unwrap();
+    let profit = returned - route.loan - route.fee;
+    assert_eq!(profit_realized(&logs, s.executor), vec![profit]);
+    assert_eq!(s.chain.balance_of(s.t0, s.executor), profit);
+    assert_eq!(s.chain.balance_of(s.t0, s.vault), vault_before + route.fee, "Loan and fee repaid");
+    assert!(s.chain.balance_of(s.t1, s.executor).is_zero(), "All of Swap A's output sold in Swap B");
+}
+
+#[test]
+fn test_profit_realized_decodes_from_receipt_logs() {
+    let mut s = setup();
+    let logs = match s.flash_loan(&s.route()) {
+        ExecutionResult::Success { logs, .. } => logs,
+        other => panic!("Arbitrage failed: {:?}", other),
+    };
+    // The EVM's logs as a node returns them in the transaction receipt
+    let receipt = TransactionReceipt {
+        logs: logs.iter().map(|log| EthLog {
+            address: Address::from_slice(log.address.as_slice()),
+            topics: log.data.topics().iter().map(|topic| H256::from_slice(topic.as_slice())).collect(),
+            data: log.data.data.to_vec().into(),
+            ..Default::default()
+        }).collect(),
+        ..Default::default()
+    };
+    let profit = s.chain.balance_of(s.t0, s.executor);
+    assert_eq!(realized_profit_from_receipt(&receipt, s.executor), Some(I256::from_raw(profit)));
+}
+
+#[test]
+fn test_velo_buy_univ3_sell_with_loan_in_token1() {
+    let mut s = setup();
+    // Fund the vault with t1: buy t0 on Velodrome (1990 t1 per t0), sell it on UniV3 (2000 t1 per t0)
+    let slot = U256::from_big_endian(s.vault.as_bytes());
+    let t1 = s.t1;
+    s.chain.install(t1, runtime(MOCK_TOKEN), &[(slot, parse_ether(10_000).unwrap())]);
+    let liquidity = parse_ether(1_000_000).unwrap();
+    for pool in [s.uni, s.velo] {
+        s.chain.evm.db_mut().insert_account_storage(evm_address(t1), evm_u256(U256::from_big_endian(pool.as_bytes())), evm_u256(liquidity)).unwrap();
+    }
+    let route = Route { loan_token: s.t1, intermediate: s.t0, pool_a: (s.velo, true), pool_b: (s.uni, false), zero_for_one_a: false, loan: parse_ether(1990).unwrap(), ..s.route() };
+    let logs = match s.flash_loan(&route) {
+        ExecutionResult::Success { logs, .. } => logs,
+        other => panic!("Arbitrage failed: {:?}", other),
+    };
+    let profits = profit_realized(&logs, s.executor);
+    assert_eq!(profits.len(), 1);
+    assert!(profits[0] > parse_ether(9).unwrap() && profits[0] <= parse_ether(10).unwrap(), "~10 t1 profit, got {}", profits[0]);
+}
+
+#[test]
+fn test_flash_loan_reverts_without_profit() {
+    let mut s = setup();
+    // Buy on Velodrome, sell on UniV3: 1 t0 -> 1990 t1 -> 0.995 t0
+    let route = Route { pool_a: (s.velo, true), pool_b: (s.uni, false), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }));
+    let route = Route { min_profit: parse_ether(1).unwrap(), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Below minProfitWei");
+    assert!(s.chain.balance_of(s.t0, s.executor).is_zero());
+}
+
+#[test]
+fn test_flash_loan_enforces_slippage_floors() {
+    let mut s = setup();
+    let route = Route { min_out_a: parse_ether(2001).unwrap(), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Swap A below minOutA");
+    let route = Route { min_out_b: parse_ether(1.006).unwrap(), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Swap B below minOutB");
+    let route = Route { min_out_a: parse_ether(2000).unwrap(), min_out_b: parse_ether(1.005).unwrap(), ..s.route() };
+    assert!(s.flash_loan(&route).is_success(), "Floors at the exact outputs pass");
+}
+
+#[test]
+fn test_floors_ignore_balances_already_held() {
+    let mut s = setup();
+    // Unwithdrawn profit in both tokens must not be sold, nor count towards the floors or minProfit
+    let (held0, held1) = (parse_ether(5).unwrap(), parse_ether(100).unwrap());
+    let holder = evm_u256(U256::from_big_endian(s.executor.as_bytes()));
+    let (t0, t1) = (s.t0, s.t1);
+    s.chain.evm.db_mut().insert_account_storage(evm_address(t0), holder, evm_u256(held0)).unwrap();
+    s.chain.evm.db_mut().insert_account_storage(evm_address(t1), holder, evm_u256(held1)).unwrap();
+
+    let route = Route { pool_a: (s.velo, true), pool_b: (s.uni, false), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Losing route covered by held t0");
+    let route = Route { min_out_a: parse_ether(2001).unwrap(), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "minOutA met only with held t1");
+    let route = Route { min_out_b: parse_ether(1.006).unwrap(), ..s.route() };
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "minOutB met only with held t0");
+
+    let logs = match s.flash_loan(&s.route()) {
+        ExecutionResult::Success { logs, .. } => logs,
+        other => panic!("Arbitrage failed: {:?}", other),
+    };
+    let profit = parse_ether(2000).unwrap() * rate(1, 1990) / parse_ether(1).unwrap() - parse_ether(1).unwrap();
+    assert_eq!(profit_realized(&logs, s.executor), vec![profit]);
+    assert_eq!(s.chain.balance_of(t0, s.executor), held0 + profit);
+    assert_eq!(s.chain.balance_of(t1, s.executor), held1, "Held t1 not sold in Swap B");
+}
+
+#[test]
+fn test_flash_loan_salt_is_single_use() {
+    let mut s = setup();
+    let route = s.route();
+    assert!(s.flash_loan(&route).is_success());
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Replayed salt");
+    assert!(s.flash_loan(&Route { salt: U256::from(2), ..route }).is_success());
+}
+
+#[test]
+fn test_entry_points_reject_unauthorized_callers() {
+    let mut s = setup();
+    let stranger = Address::from_low_u64_be(0xbad);
+    let executor = s.executor;
+    let receive = [&id("receiveFlashLoan(address[],uint256[],uint256[],bytes)")[..], &encode(&[
+        Token::Array(vec![Token::Address(s.t0)]), Token::Array(vec![Token::Uint(U256::one())]), Token::Array(vec![Token::Uint(U256::zero())]), Token::Bytes(vec![0; 0x160]),
+    ])].concat();
+    assert!(matches!(s.chain.call(stranger, executor, receive), ExecutionResult::Revert { .. }), "receiveFlashLoan from outside the vault");
+    let route = s.route();
+    let execute = [&id("executeFlashLoan(address,uint256,bytes)")[..], &encode(&[Token::Address(s.t0), Token::Uint(route.loan), Token::Bytes(Setup::user_data(&route))])].concat();
+    assert!(matches!(s.chain.call(stranger, executor, execute), ExecutionResult::Revert { .. }), "executeFlashLoan by a non-owner");
+
+    // A callback outside a swap would pay the caller from the executor's balance
+    let t0_slot = U256::from_big_endian(executor.as_bytes());
+    let t0 = s.t0;
+    s.chain.evm.db_mut().insert_account_storage(evm_address(t0), evm_u256(t0_slot), evm_u256(parse_ether(5).unwrap())).unwrap();
+    let callback = [&id("uniswapV3SwapCallback(int256,int256,bytes)")[..], &encode(&[Token::Int(parse_ether(5).unwrap()), Token::Int(U256::zero()), Token::Bytes(vec![])])].concat();
+    assert!(matches!(s.chain.call(stranger, executor, callback), ExecutionResult::Revert { .. }));
+    assert!(matches!(s.chain.call(stranger, executor, id("unknown()").to_vec()), ExecutionResult::Revert { .. }));
+
+    let withdraw = |recipient: Address| [&id("withdrawToken(address,address)")[..], &encode(&[Token::Address(t0), Token::Address(recipient)])].concat();
+    assert!(matches!(s.chain.call(stranger, executor, withdraw(stranger)), ExecutionResult::Revert { .. }), "withdrawToken by a non-owner");
+    let owner = s.owner;
+    assert!(s.chain.call(owner, executor, withdraw(owner)).is_success());
+    assert_eq!(s.chain.balance_of(t0, owner), parse_ether(5).unwrap());
+    assert!(s.chain.balance_of(t0, executor).is_zero());
+}
+
+#[test]
+fn test_stranger_cannot_drain_through_vault_and_malicious_pool() {
+    let mut s = setup();
+    // Held profit, and a UniV3 pool whose callback asks for 5 t0 on top of the 1 t0 swapped
+    let held = parse_ether(5).unwrap();
+    let (t0, uni, executor) = (s.t0, s.uni, s.executor);
+    s.chain.evm.db_mut().insert_account_storage(evm_address(t0), evm_u256(U256::from_big_endian(executor.as_bytes())), evm_u256(held)).unwrap();
+    s.chain.evm.db_mut().insert_account_storage(evm_address(uni), evm_u256(U256::from(6)), evm_u256(held)).unwrap();
+    let route = s.route();
+
+    let stranger = Address::from_low_u64_be(0xbad);
+    assert!(matches!(s.vault_flash_loan(stranger, &route), ExecutionResult::Revert { .. }), "Loan started outside executeFlashLoan");
+    assert!(matches!(s.flash_loan(&route), ExecutionResult::Revert { .. }), "Callback owed more than amountIn");
+    assert_eq!(s.chain.balance_of(t0, executor), held);
+
+    s.chain.evm.db_mut().insert_account_storage(evm_address(uni), evm_u256(U256::from(6)), EvmU256::ZERO).unwrap();
+    assert!(matches!(s.vault_flash_loan(stranger, &route), ExecutionResult::Revert { .. }), "Even through honest pools");
+    assert!(s.flash_loan(&route).is_success());
+}
+// END OF FILE: tests/executor_evm_test.rs
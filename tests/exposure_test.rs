@@ -0,0 +1,57 @@
+// tests/exposure_test.rs
+// In-flight loan cap: reservations, and a concurrent submission dropped while the first is unresolved.
+
+mod common;
+
+use ethers::prelude::{Http, LocalWallet, Provider, SignerMiddleware};
+use ethers::types::{Address, I256, U256};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use ulp1_5::config::Config;
+use ulp1_5::deadline::Deadline;
+use ulp1_5::exposure::InflightExposure;
+use ulp1_5::state::{AppState, DexType};
+use ulp1_5::transaction::{submit_arbitrage_transaction, NonceManager};
+
+fn eth(n: u64) -> U256 { U256::from(n) * U256::exp10(18) }
+
+#[test]
+fn test_reservations_released_on_drop() {
+    let exposure = Arc::new(InflightExposure::default());
+    let first = exposure.try_reserve(eth(60), eth(100)).unwrap();
+    assert_eq!(exposure.try_reserve(eth(60), eth(100)).unwrap_err(), eth(60), "Would take the total to 120");
+    let small = exposure.try_reserve(eth(40), eth(100)).unwrap();
+    assert_eq!(exposure.total(), eth(100));
+    drop(first);
+    let second = exposure.try_reserve(eth(60), eth(100)).unwrap();
+    drop((small, second));
+    assert!(exposure.total().is_zero());
+    // A zero cap only tracks
+    let _a = exposure.try_reserve(eth(1_000), U256::zero()).unwrap();
+    assert_eq!(exposure.try_reserve(eth(1_000), U256::zero()).map(|_| ()), Ok(()));
+}
+
+#[tokio::test]
+async fn test_second_large_submission_blocked_until_first_resolves() {
+    let app_state = Arc::new(AppState::new(Config { max_total_inflight_loan_wei: eth(100), ..Config::default() }));
+    // Unreachable endpoint: a submission past the cap check fails at its first RPC call
+    let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+    let client = Arc::new(SignerMiddleware::new(provider, "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse::<LocalWallet>().unwrap()));
+    let nonce_manager = Arc::new(NonceManager::new(Address::zero()));
+    let route = common::route(Address::from_low_u64_be(1), DexType::UniswapV3, Address::from_low_u64_be(2), DexType::VelodromeV2);
+    let submit = || submit_arbitrage_transaction(client.clone(), app_state.clone(), route.clone(), eth(80), I256::exp10(15), nonce_manager.clone(), Deadline::after(Duration::from_secs(60)));
+
+    // The first 80 ETH loan is still waiting for its receipt
+    let first = app_state.inflight_exposure.try_reserve(eth(80), app_state.config.max_total_inflight_loan_wei).unwrap();
+    let blocked = submit().await.unwrap_err();
+    assert!(blocked.to_string().contains("In-flight loan cap"), "{:#}", blocked);
+    assert_eq!(app_state.metrics.inflight_exposure_rejections.load(Ordering::Relaxed), 1);
+
+    drop(first);
+    let attempted = submit().await.unwrap_err();
+    assert!(!attempted.to_string().contains("In-flight loan cap"), "{:#}", attempted);
+    assert_eq!(app_state.metrics.inflight_exposure_rejections.load(Ordering::Relaxed), 1);
+    assert!(app_state.inflight_exposure.total().is_zero(), "The failed attempt released its reservation");
+}
+// END OF FILE: tests/exposure_test.rs
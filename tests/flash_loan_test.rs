@@ -0,0 +1,104 @@
+// tests/flash_loan_test.rs
+// Offline tests for flash loan calldata and fees (no RPC calls).
+
+mod common;
+
+use ethers::abi::{decode, encode, ParamType, Token};
+use ethers::prelude::{Http, LocalWallet, Provider, SignerMiddleware};
+use ethers::types::{Address, Bytes, I256, U256};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use ulp1_5::config::Config;
+use ulp1_5::flash_loan::{balancer_flash_loan_fee, execute_flash_loan_calldata, flash_loan_fee_wei, refresh_balancer_flash_loan_fee};
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::simulation::calculate_net_profit;
+use ulp1_5::state::{AppState, DexType, PoolSnapshot, PoolState};
+use ulp1_5::univ3_math::get_sqrt_ratio_at_tick;
+
+fn mainnet_config() -> Config {
+    Config {
+        balancer_vault_address: Address::from_low_u64_be(0xba1),
+        weth_address: Address::from_low_u64_be(0xe7),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_flash_loan_calldata_targets_executor_entry_point() {
+    let config = mainnet_config();
+    let (amount, data) = (U256::exp10(18), Bytes::from(vec![0x01]));
+
+    let calldata = execute_flash_loan_calldata(config.weth_address, amount, data.clone());
+    assert_eq!(&calldata[..4], &[0xf3, 0xdf, 0x12, 0x53], "executeFlashLoan(address,uint256,bytes)");
+    let args = decode(&[ParamType::Address, ParamType::Uint(256), ParamType::Bytes], &calldata[4..]).unwrap();
+    assert_eq!(args, vec![Token::Address(config.weth_address), Token::Uint(amount), Token::Bytes(data.to_vec())]);
+}
+
+#[test]
+fn test_balancer_flash_loan_fee_rounds_up() {
+    let fee_pct = U256::exp10(15); // 0.1%
+    assert_eq!(balancer_flash_loan_fee(U256::exp10(18), fee_pct).unwrap(), U256::exp10(15));
+    assert_eq!(balancer_flash_loan_fee(U256::one(), fee_pct).unwrap(), U256::one(), "Rounded up, as the Vault does");
+    assert_eq!(balancer_flash_loan_fee(U256::exp10(18), U256::zero()).unwrap(), U256::zero());
+
+    let state = AppState::new(mainnet_config());
+    state.balancer_flash_loan_fee.store(fee_pct.as_u64(), Ordering::Relaxed);
+    assert_eq!(flash_loan_fee_wei(&state, U256::exp10(18)).unwrap(), U256::exp10(15));
+}
+
+/// WETH/USDC UniV3 0.05% pool at ~2000 USDC/WETH, deep enough that a 1 WETH swap stays in range.
+fn insert_univ3_pool(state: &AppState, pool: Address) {
+    let (weth, usdc) = (state.weth_address, state.usdc_address);
+    state.pool_states.insert(pool, PoolState {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: weth, token1: usdc,
+        uni_fee: Some(500), tick_spacing: Some(10), velo_stable: None, t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::UniswapV3, token0: weth, token1: usdc, reserve0: None, reserve1: None,
+        sqrt_price_x96: Some(get_sqrt_ratio_at_tick(-200_995).unwrap()), tick: Some(-200_995),
+        liquidity: Some(10u128.pow(21)), last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+}
+
+#[tokio::test]
+async fn test_balancer_fee_reduces_net_profit() {
+    let (buy, sell) = (Address::from_low_u64_be(0x1), Address::from_low_u64_be(0x2));
+    let state = Arc::new(AppState::new_for_test());
+    insert_univ3_pool(&state, buy);
+    insert_univ3_pool(&state, sell);
+    let route = RouteCandidate { token_in: state.weth_address, token_out: state.usdc_address, ..common::route(buy, DexType::UniswapV3, sell, DexType::UniswapV3) };
+    // Both legs are quoted locally and the route loses the pool fees, so no RPC is reached
+    let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+    let wallet: LocalWallet = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse().unwrap();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    let loan = U256::exp10(18);
+    let net_profit = || calculate_net_profit(state.clone(), client.clone(), &route, loan, 0.0, 0, 0, true);
+
+    let without_fee = net_profit().await.unwrap();
+    assert!(without_fee < I256::zero() && without_fee > I256::min_value());
+    state.balancer_flash_loan_fee.store(U256::exp10(15).as_u64(), Ordering::Relaxed); // 0.1%
+    let with_fee = net_profit().await.unwrap();
+    assert_eq!(with_fee, without_fee - I256::exp10(15), "The fee on 1 WETH is 0.001 WETH");
+}
+
+#[tokio::test]
+async fn test_balancer_fee_refresh_tracks_the_fee_without_pausing() {
+    let (provider, mock) = Provider::mocked();
+    let client = Arc::new(provider);
+    let state = AppState::new_for_test();
+    // Each refresh reads the fees collector, then its fee; the mock answers last-pushed first
+    let push_fee = |fee: U256| {
+        mock.push::<Bytes, Bytes>(encode(&[Token::Uint(fee)]).into()).unwrap();
+        mock.push::<Bytes, Bytes>(encode(&[Token::Address(Address::from_low_u64_be(0xfee))]).into()).unwrap();
+    };
+
+    push_fee(U256::exp10(15));
+    assert_eq!(refresh_balancer_flash_loan_fee(client.clone(), &state).await.unwrap(), U256::exp10(15));
+    assert_eq!(state.balancer_flash_loan_fee.load(Ordering::Relaxed), U256::exp10(15).as_u64());
+    assert!(state.pause_reasons().is_empty(), "The executor repays the fee, so trading continues");
+
+    push_fee(U256::zero());
+    assert!(refresh_balancer_flash_loan_fee(client.clone(), &state).await.unwrap().is_zero());
+    assert_eq!(state.balancer_flash_loan_fee.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,52 @@
+// tests/gas_learning_test.rs
+// Gas limits learned per route shape from receipt gas usage.
+
+use ethers::types::U256;
+use ulp1_5::config::Config;
+use ulp1_5::gas_learning::{execution_gas_upper_bound, GasUsageStats, GAS_USAGE_WINDOW};
+use ulp1_5::state::{AppState, DexType};
+
+const SHAPE: (DexType, DexType) = (DexType::UniswapV3, DexType::VelodromeV2);
+
+#[test]
+fn test_learned_limit_used_after_min_samples() {
+    let state = AppState::new(Config { learned_gas_min_samples: 20, ..Config::default() });
+    let stats = &state.gas_usage_stats;
+    for gas_used in 300_001..=300_019u64 {
+        stats.record(SHAPE, U256::from(gas_used));
+    }
+    assert_eq!(stats.learned_limit(&SHAPE, state.config.learned_gas_min_samples), None, "19 receipts fall back to estimation");
+
+    stats.record(SHAPE, U256::from(300_020u64));
+    // p95 of 300_001..=300_020 is the 19th sample, before refunds, plus the 10% buffer
+    let expected = execution_gas_upper_bound(300_019) * 110 / 100;
+    assert_eq!(stats.learned_limit(&SHAPE, state.config.learned_gas_min_samples), Some(U256::from(expected)));
+
+    // Other shapes are unseen; a minimum of 0 disables learning
+    assert_eq!(stats.learned_limit(&(DexType::VelodromeV2, DexType::UniswapV3), 20), None);
+    assert_eq!(stats.learned_limit(&SHAPE, 0), None);
+}
+
+#[test]
+fn test_window_forgets_old_receipts() {
+    let stats = GasUsageStats::default();
+    for _ in 0..GAS_USAGE_WINDOW {
+        stats.record(SHAPE, U256::from(900_000u64));
+    }
+    // A cheaper executor deployment replaces every old sample
+    for _ in 0..GAS_USAGE_WINDOW {
+        stats.record(SHAPE, U256::from(200_000u64));
+    }
+    assert_eq!(stats.learned_limit(&SHAPE, 1), Some(U256::from(250_000u64 * 110 / 100)));
+}
+
+#[test]
+fn test_learned_limit_covers_refunded_execution_gas() {
+    // 500k of execution gas with the maximum EIP-3529 refund (a fifth) leaves 400k in the receipt
+    assert_eq!(execution_gas_upper_bound(400_000), 500_000);
+    let stats = GasUsageStats::default();
+    stats.record(SHAPE, U256::from(400_000u64));
+    let limit = stats.learned_limit(&SHAPE, 1).unwrap();
+    assert!(limit >= U256::from(500_000u64), "a limit below execution gas runs out of gas: {limit}");
+}
+// END OF FILE: tests/gas_learning_test.rs
@@ -0,0 +1,158 @@
+// tests/gas_test.rs
+// Offline tests for gas/priority-fee computation (no Anvil required).
+
+use ethers::providers::Provider;
+use ethers::types::{Block, FeeHistory, TxHash, U256, U64};
+use eyre::Result;
+use ulp1_5::config::{parse_gas_profiles, Config, PriorityFeeStrategy, DEFAULT_GAS_PROFILES};
+use ulp1_5::gas::{compute_priority_fee, detect_tx_type, preset_gas_limit, TxType};
+use ulp1_5::state::DexType;
+
+fn gwei(n: u64) -> U256 {
+    U256::from(n) * U256::exp10(9)
+}
+
+/// Mock `eth_feeHistory` response with one reward column (the requested percentile) per block.
+fn mock_fee_history(rewards_gwei: &[u64]) -> FeeHistory {
+    FeeHistory {
+        base_fee_per_gas: vec![gwei(10); rewards_gwei.len() + 1],
+        gas_used_ratio: vec![0.5; rewards_gwei.len()],
+        oldest_block: U256::from(100),
+        reward: rewards_gwei.iter().map(|r| vec![gwei(*r)]).collect(),
+    }
+}
+
+#[test]
+fn test_priority_fee_strategies_ordering() -> Result<()> {
+    let base_fee = gwei(10);
+    let history = mock_fee_history(&[8, 0, 9, 12, 7]); // 0 = empty block, ignored
+
+    let fixed = compute_priority_fee(&PriorityFeeStrategy::Fixed(1.0), base_fee, None)?;
+    let multiplier = compute_priority_fee(&PriorityFeeStrategy::BaseFeeMultiplier(0.5), base_fee, None)?;
+    let percentile = compute_priority_fee(&PriorityFeeStrategy::RecentBlocksPercentile(50), base_fee, Some(&history))?;
+
+    assert_eq!(fixed, gwei(1));
+    assert_eq!(multiplier, gwei(5));
+    assert_eq!(percentile, gwei(9), "Median of non-empty block rewards");
+    assert!(fixed < multiplier && multiplier < percentile);
+    Ok(())
+}
+
+#[test]
+fn test_percentile_strategy_tracks_competition() -> Result<()> {
+    let strategy = PriorityFeeStrategy::RecentBlocksPercentile(90);
+    let calm = compute_priority_fee(&strategy, gwei(10), Some(&mock_fee_history(&[1, 2, 2, 3])))?;
+    let busy = compute_priority_fee(&strategy, gwei(10), Some(&mock_fee_history(&[20, 25, 30, 40])))?;
+    assert!(busy > calm);
+
+    // Without a history (or with only empty blocks) the percentile strategy cannot produce a fee
+    assert!(compute_priority_fee(&strategy, gwei(10), None).is_err());
+    assert!(compute_priority_fee(&strategy, gwei(10), Some(&mock_fee_history(&[0, 0]))).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_priority_fee_strategy_parsing() -> Result<()> {
+    assert_eq!("fixed:0.5".parse::<PriorityFeeStrategy>()?, PriorityFeeStrategy::Fixed(0.5));
+    assert_eq!("multiplier:1.25".parse::<PriorityFeeStrategy>()?, PriorityFeeStrategy::BaseFeeMultiplier(1.25));
+    assert_eq!("percentile:75".parse::<PriorityFeeStrategy>()?, PriorityFeeStrategy::RecentBlocksPercentile(75));
+    assert!("percentile:101".parse::<PriorityFeeStrategy>().is_err());
+    assert!("bogus:1".parse::<PriorityFeeStrategy>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_gas_math_overflow_returns_error() {
+    use ulp1_5::gas::{buffered_gas_limit, gas_cost_wei};
+
+    assert_eq!(buffered_gas_limit(U256::from(200_000), 20, 300_000).unwrap(), U256::from(300_000));
+    assert_eq!(buffered_gas_limit(U256::from(400_000), 25, 300_000).unwrap(), U256::from(500_000));
+    assert!(buffered_gas_limit(U256::MAX, 20, 300_000).is_err());
+    assert!(buffered_gas_limit(U256::MAX / 200, 100, 300_000).is_ok(), "Largest estimate whose buffered value still fits");
+
+    assert_eq!(gas_cost_wei(U256::from(500_000), gwei(2)).unwrap(), U256::from(500_000) * gwei(2));
+    assert!(gas_cost_wei(U256::MAX - 1, gwei(1)).is_err());
+}
+
+#[test]
+fn test_checked_conversions_near_max() {
+    use ethers::types::I256;
+    use ulp1_5::utils::{checked_mul_div, u256_to_i256};
+
+    assert_eq!(checked_mul_div(U256::from(1000), U256::from(999), U256::from(1000), "test").unwrap(), U256::from(999));
+    assert!(checked_mul_div(U256::MAX, U256::from(999), U256::from(1000), "test").is_err());
+    assert!(checked_mul_div(U256::one(), U256::one(), U256::zero(), "test").is_err(), "Division by zero is an error, not a panic");
+
+    assert_eq!(u256_to_i256(I256::MAX.into_raw(), "test").unwrap(), I256::MAX);
+    assert!(u256_to_i256(U256::MAX, "test").is_err(), "Values above I256::MAX must not wrap negative");
+}
+
+#[tokio::test]
+async fn test_detect_tx_type_from_latest_block() -> Result<()> {
+    let (provider, mock) = Provider::mocked();
+
+    let london_block = Block::<TxHash> { number: Some(U64::from(1)), base_fee_per_gas: Some(gwei(1)), ..Default::default() };
+    mock.push(london_block)?;
+    assert_eq!(detect_tx_type(&provider).await?, TxType::Eip1559);
+
+    let legacy_block = Block::<TxHash> { number: Some(U64::from(2)), base_fee_per_gas: None, ..Default::default() };
+    mock.push(legacy_block)?;
+    assert_eq!(detect_tx_type(&provider).await?, TxType::Legacy);
+    Ok(())
+}
+
+#[test]
+fn test_submission_gas_limit_buffer_fallback_and_clamp() {
+    use ulp1_5::gas::submission_gas_limit;
+    let (fallback, min, max) = (1_500_000, 400_000, 3_000_000);
+
+    // Estimate + 25% buffer inside the bounds
+    assert_eq!(submission_gas_limit(Some(U256::from(800_000)), 25, fallback, min, max).unwrap(), U256::from(1_000_000));
+    // Clamped to the minimum and to the maximum
+    assert_eq!(submission_gas_limit(Some(U256::from(100_000)), 25, fallback, min, max).unwrap(), U256::from(min));
+    assert_eq!(submission_gas_limit(Some(U256::from(2_800_000)), 25, fallback, min, max).unwrap(), U256::from(max));
+    // Reverted estimate -> configured default, still clamped
+    assert_eq!(submission_gas_limit(None, 25, fallback, min, max).unwrap(), U256::from(fallback));
+    assert_eq!(submission_gas_limit(None, 25, fallback, min, 1_000_000).unwrap(), U256::from(1_000_000));
+    assert!(submission_gas_limit(Some(U256::MAX), 25, fallback, min, max).is_err());
+}
+
+#[test]
+fn test_gas_cost_converted_to_loan_token() {
+    use ulp1_5::gas::gas_cost_in_loan_token;
+    let cost = U256::from(300_000u64) * gwei(1); // 0.0003 native
+    // ETH-native chain: unchanged
+    assert_eq!(gas_cost_in_loan_token(cost, None, 18).unwrap(), cost);
+    assert_eq!(gas_cost_in_loan_token(cost, Some(1.0), 18).unwrap(), cost);
+    // Gas token worth 0.0004 WETH
+    assert_eq!(gas_cost_in_loan_token(cost, Some(0.0004), 18).unwrap(), U256::from(120_000_000_000u64));
+    // 6-decimal loan token priced at 2500 per gas token: 0.0003 * 2500 = 0.75 => 750_000
+    assert_eq!(gas_cost_in_loan_token(cost, Some(2500.0), 6).unwrap(), U256::from(750_000u64));
+    assert!(gas_cost_in_loan_token(cost, Some(f64::NAN), 18).is_err());
+}
+
+#[test]
+fn test_gas_profile_used_for_mapped_route_and_estimation_otherwise() -> Result<()> {
+    let config = Config {
+        gas_profiles: parse_gas_profiles("univ3:velov2=520000, velov2:velov2=0, aero:univ3=100000, univ3:univ3=480000")?,
+        min_flashloan_gas_limit: 400_000,
+        max_gas_limit: 3_000_000,
+        ..Config::default()
+    };
+    assert_eq!(preset_gas_limit(&config, None, &(DexType::UniswapV3, DexType::VelodromeV2)), Some(U256::from(520_000u64)), "Mapped shape skips estimation");
+    assert_eq!(preset_gas_limit(&config, None, &(DexType::UniswapV3, DexType::UniswapV3)), Some(U256::from(480_000u64)), "Same-DEX pair");
+    assert_eq!(preset_gas_limit(&config, None, &(DexType::VelodromeV2, DexType::UniswapV3)), None, "Unmapped pair (direction matters) estimates");
+    assert_eq!(preset_gas_limit(&config, None, &(DexType::VelodromeV2, DexType::VelodromeV2)), None, "0 leaves the pair to estimation");
+    assert_eq!(preset_gas_limit(&config, None, &(DexType::Aerodrome, DexType::UniswapV3)), Some(U256::from(400_000u64)), "Clamped to the minimum");
+    assert_eq!(preset_gas_limit(&config, Some(U256::from(610_000u64)), &(DexType::UniswapV3, DexType::VelodromeV2)), Some(U256::from(610_000u64)), "Learned limit wins");
+    assert_eq!(preset_gas_limit(&Config::default(), None, &(DexType::UniswapV3, DexType::UniswapV3)), None, "No profiles: always estimate");
+
+    assert!(parse_gas_profiles("univ3:velov2").is_err());
+    assert!(parse_gas_profiles("univ3:curve=1").is_err());
+    assert!(parse_gas_profiles("univ3:velov2:balancer=1").is_err(), "Loans always come from the Balancer Vault");
+    assert!(parse_gas_profiles("")?.is_empty());
+    let defaults = parse_gas_profiles("default")?;
+    assert_eq!(defaults.len(), DEFAULT_GAS_PROFILES.len(), "Every known buy/sell pair is seeded");
+    assert!(defaults[&(DexType::UniswapV3, DexType::UniswapV3)] < defaults[&(DexType::VelodromeV2, DexType::VelodromeV2)]);
+    Ok(())
+}
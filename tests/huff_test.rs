@@ -0,0 +1,103 @@
+// tests/huff_test.rs
+// Tests for the shipped executor artifact (build/ArbitrageExecutor.bin) against its Huff source.
+
+use ethers::utils::{hex, id};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{Bytes as EvmBytes, ExecutionResult, Output, TransactTo};
+use revm::Evm;
+use std::collections::HashSet;
+use std::process::Command;
+use ulp1_5::config::{ExecutorAbiVersion, DEFAULT_BUILD_DIR, EXECUTOR_BYTECODE_FILE, EXECUTOR_HUFF_SOURCE};
+use ulp1_5::deploy::{missing_selectors, required_executor_selectors};
+
+fn shipped_bytecode() -> Vec<u8> {
+    let artifact = std::fs::read_to_string(format!("{}/{}", DEFAULT_BUILD_DIR, EXECUTOR_BYTECODE_FILE)).unwrap();
+    hex::decode(artifact.trim().trim_start_matches("0x")).unwrap()
+}
+
+/// Runtime code the shipped creation code deploys.
+fn shipped_runtime() -> Vec<u8> {
+    let mut evm = Evm::builder().with_db(CacheDB::new(EmptyDB::default())).build();
+    let tx = evm.tx_mut();
+    tx.transact_to = TransactTo::create();
+    tx.data = EvmBytes::from(shipped_bytecode());
+    tx.gas_limit = 10_000_000;
+    match evm.transact().unwrap().result {
+        ExecutionResult::Success { output: Output::Create(code, _), .. } => code.to_vec(),
+        other => panic!("Executor deployment failed: {:?}", other),
+    }
+}
+
+/// Value of `#define constant <name> = 0x…` in the executor source.
+fn huff_constant(source: &str, name: &str) -> Vec<u8> {
+    let prefix = format!("#define constant {} =", name);
+    let value = source.lines()
+        .find_map(|line| line.trim().strip_prefix(&prefix))
+        .unwrap_or_else(|| panic!("{} is not defined", name))
+        .split("//").next().unwrap().trim().trim_start_matches("0x");
+    hex::decode(format!("{:0>8}", value)).unwrap()
+}
+
+/// `DUP1 PUSH4 <selector> EQ PUSH2 <dest> JUMPI` entries of a dispatcher, with their jump targets.
+fn dispatcher_entries(runtime: &[u8]) -> Vec<([u8; 4], usize)> {
+    runtime.windows(12)
+        .filter(|w| w[0] == 0x80 && w[1] == 0x63 && w[6] == 0x14 && w[7] == 0x61 && w[10] == 0x57)
+        .map(|w| ([w[2], w[3], w[4], w[5]], u16::from_be_bytes([w[8], w[9]]) as usize))
+        .collect()
+}
+
+/// Run by the `huff` CI workflow, which installs huffc: `cargo test --test huff_test -- --ignored`.
+#[test]
+#[ignore = "needs huffc on PATH"]
+fn test_executor_artifact_matches_huffc() {
+    let output = Command::new("huffc").args([EXECUTOR_HUFF_SOURCE, "-b"]).output().expect("huffc not on PATH");
+    assert!(output.status.success(), "huffc failed: {}", String::from_utf8_lossy(&output.stderr));
+    let compiled = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        hex::decode(compiled.trim().trim_start_matches("0x")).unwrap() == shipped_bytecode(),
+        "build/ArbitrageExecutor.bin is stale; rebuild it with `huffc ./contracts/ArbitrageExecutor.huff -b`"
+    );
+}
+
+#[test]
+fn test_executor_dispatcher_matches_huff_selectors() {
+    let source = std::fs::read_to_string(EXECUTOR_HUFF_SOURCE).unwrap();
+    let runtime = shipped_runtime();
+    let entries = dispatcher_entries(&runtime);
+
+    let mut expected = HashSet::new();
+    for (name, signature) in [
+        ("RECEIVE_FLASH_LOAN_SELECTOR", "receiveFlashLoan(address[],uint256[],uint256[],bytes)"),
+        ("UNISWAP_V3_SWAP_CALLBACK_SELECTOR", "uniswapV3SwapCallback(int256,int256,bytes)"),
+        ("WITHDRAW_TOKEN_SELECTOR", "withdrawToken(address,address)"),
+        ("EXECUTE_FLASH_LOAN_SELECTOR", "executeFlashLoan(address,uint256,bytes)"),
+    ] {
+        let constant: [u8; 4] = huff_constant(&source, name).try_into().unwrap();
+        assert_eq!(constant, id(signature), "{} is not the selector of {}", name, signature);
+        expected.insert(constant);
+    }
+    let dispatched: HashSet<[u8; 4]> = entries.iter().map(|(selector, _)| *selector).collect();
+    assert_eq!(dispatched, expected, "build/ArbitrageExecutor.bin does not dispatch the source's selectors");
+    for (selector, target) in entries {
+        assert_eq!(runtime[target], 0x5b, "Dispatch of 0x{} does not land on a JUMPDEST", hex::encode(selector));
+    }
+    assert!(missing_selectors(&runtime, &required_executor_selectors(ExecutorAbiVersion::V2_4)).is_empty());
+}
+
+#[test]
+fn test_executor_artifact_pushes_source_constants() {
+    // A .huff edit shipped without rebuilding the .bin leaves the old constants (or none) in the runtime
+    let source = std::fs::read_to_string(EXECUTOR_HUFF_SOURCE).unwrap();
+    assert_eq!(huff_constant(&source, "PROFIT_REALIZED_TOPIC"), ethers::utils::keccak256("ProfitRealized(int256)"));
+    assert_eq!(huff_constant(&source, "VAULT_FLASH_LOAN_SELECTOR"), id("flashLoan(address,address[],uint256[],bytes)"));
+    let runtime = shipped_runtime();
+    for name in [
+        "TRANSFER_SELECTOR", "BALANCE_OF_SELECTOR", "UNISWAP_V3_SWAP_SELECTOR", "VELO_PAIR_SWAP_SELECTOR",
+        "VELO_GET_AMOUNT_OUT_SELECTOR", "VAULT_FLASH_LOAN_SELECTOR", "PROFIT_REALIZED_TOPIC",
+    ] {
+        let value = huff_constant(&source, name);
+        let push = [&[0x5f + value.len() as u8][..], &value].concat();
+        assert!(runtime.windows(push.len()).any(|w| w == &push[..]), "build/ArbitrageExecutor.bin never pushes {}", name);
+    }
+}
+// END OF FILE: tests/huff_test.rs
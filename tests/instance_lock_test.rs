@@ -0,0 +1,51 @@
+// tests/instance_lock_test.rs
+// Single-instance lock file: held locks refuse a second instance, released and stale locks do not.
+
+use ethers::types::Address;
+use std::path::PathBuf;
+use ulp1_5::config::Config;
+use ulp1_5::instance_lock::InstanceLock;
+
+fn lock_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ulp1_5-test-{}-{}.lock", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn signer() -> Address { Address::from_low_u64_be(0x5167) }
+
+#[test]
+fn test_second_acquisition_fails_while_held() {
+    let path = lock_path("held");
+    let lock = InstanceLock::acquire(&path, signer()).unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().contains(&format!("pid={}", std::process::id())));
+
+    let err = InstanceLock::acquire(&path, signer()).unwrap_err();
+    assert!(err.to_string().contains("refusing to start"), "{}", err);
+    assert!(path.exists(), "The failed attempt leaves the holder's lock in place");
+
+    // Released on drop (graceful shutdown), after which a new instance may start
+    drop(lock);
+    assert!(!path.exists());
+    let again = InstanceLock::acquire(&path, signer()).unwrap();
+    assert_eq!(again.path(), path.as_path());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_stale_lock_of_dead_process_is_taken_over() {
+    let path = lock_path("stale");
+    // Above the kernel's pid_max limit (2^22), so never a running process
+    std::fs::write(&path, format!("pid={}\nsigner={:?}\n", 1u32 << 23, signer())).unwrap();
+    let lock = InstanceLock::acquire(&path, signer()).unwrap();
+    assert!(std::fs::read_to_string(lock.path()).unwrap().contains(&format!("pid={}", std::process::id())));
+}
+
+#[test]
+fn test_default_lock_path_is_per_signer() {
+    let config = Config::default();
+    assert_ne!(InstanceLock::path_for(&config, signer()), InstanceLock::path_for(&config, Address::from_low_u64_be(1)));
+    let configured = Config { lock_file_path: Some("/var/run/ulp.lock".to_string()), ..Config::default() };
+    assert_eq!(InstanceLock::path_for(&configured, signer()), PathBuf::from("/var/run/ulp.lock"));
+}
+// END OF FILE: tests/instance_lock_test.rs
@@ -1,5 +1,6 @@
 // tests/integration_test.rs
 #![cfg(feature = "local_simulation")] // Only compile when the feature is enabled
+#![forbid(unsafe_code)] // Tests run in parallel: shared state goes through Once/OnceCell, never `static mut`
 
 // Use ulp1_5:: prefix now that this is an external integration test
 use ulp1_5::local_simulator::{setup_simulation_environment, trigger_v3_swap, trigger_v2_swap};
@@ -219,6 +220,8 @@ async fn test_full_arbitrage_cycle_simulation() -> Result<()> {
     use ulp1_5::simulation::find_optimal_loan_amount;
     use ulp1_5::transaction::{fetch_gas_price, submit_arbitrage_transaction, NonceManager};
     use ulp1_5::utils::ToF64Lossy;
+    use ulp1_5::deadline::Deadline;
+    use std::time::Duration;
     let sim_env = setup_simulation_environment().await?;
     let client = sim_env.http_client.clone();
     let executor_addr = sim_env.executor_address.expect("Executor must be deployed for this test");
@@ -239,13 +242,12 @@ async fn test_full_arbitrage_cycle_simulation() -> Result<()> {
         token_in: weth_addr,
         token_out: usdc_addr,
         buy_pool_fee: Some(500),
-        sell_pool_fee: None,
-        buy_pool_stable: None,
         sell_pool_stable: Some(true), // Correct for the stable pool target
         buy_pool_factory: config.uniswap_v3_factory_addr,
         sell_pool_factory: config.velodrome_v2_factory_addr,
         zero_for_one_a: true,
-        estimated_profit_usd: 0.1,
+        price_diff_pct: 0.1,
+        ..Default::default()
     };
     info!("Constructed Manual Route Candidate: {:?}", route);
     let app_state = Arc::new(AppState::new(config.clone()));
@@ -263,11 +265,11 @@ async fn test_full_arbitrage_cycle_simulation() -> Result<()> {
         gas_price_gwei,
     ).await?;
     let (loan_amount_wei, simulated_net_profit_wei) = match optimal_loan_result {
-        Some((amount, profit)) if profit > I256::zero() => {
+        Some((amount, profit, _)) if profit > I256::zero() => {
             info!("✅ Optimal loan found: amount={}, profit={}", amount, profit);
             (amount, profit)
         },
-        Some((_, profit)) => {
+        Some((_, profit, _)) => {
              warn!("Simulation found optimal loan, but profit is not positive ({}). Test may not execute tx.", profit);
              (U256::zero(), I256::zero())
         }
@@ -293,6 +295,7 @@ async fn test_full_arbitrage_cycle_simulation() -> Result<()> {
         loan_amount_wei,
         simulated_net_profit_wei,
         nonce_manager.clone(),
+        Deadline::after(Duration::from_secs(60)), // Anvil round-trips are not latency-bound
     ).await;
     match submission_result {
         Ok(tx_hash) => {
@@ -316,6 +319,282 @@ async fn test_full_arbitrage_cycle_simulation() -> Result<()> {
 }
 
 
+/// Test: UniV3 -> UniV3 arbitrage across two fee tiers of the same WETH/USDC pair.
+/// Unlike a same-pool route, a cross-fee route can genuinely be profitable, so this exercises
+/// the per-leg fee plumbing. Either outcome is acceptable as long as it is coherent:
+/// a positive optimum within the configured loan range, or a clean `None`.
+#[tokio::test]
+#[ignore]
+async fn test_univ3_cross_fee_arbitrage_simulation() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_univ3_cross_fee_arbitrage_simulation ---");
+    use ulp1_5::bindings::IUniswapV3Factory;
+    use ulp1_5::config::load_config;
+    use ulp1_5::path_optimizer::RouteCandidate;
+    use ulp1_5::simulation::{find_optimal_loan_amount, simulate_flash_loan_bundle, SimulationMethod};
+    use ulp1_5::state::{self, AppState, DexType};
+    use ulp1_5::transaction::fetch_gas_price;
+    use ulp1_5::utils::{f64_to_wei, ToF64Lossy};
+
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let executor_addr = sim_env.executor_address.expect("Executor must be deployed for this test");
+
+    let mut config = load_config()?;
+    config.arb_executor_address = Some(executor_addr);
+    let weth_addr = config.weth_address;
+    let usdc_addr = config.usdc_address;
+
+    // Resolve the two fee-tier pools from the factory on the fork
+    let factory = IUniswapV3Factory::new(config.uniswap_v3_factory_addr, client.clone());
+    let (fee_a, fee_b) = (500u32, 3000u32);
+    let pool_a_addr = factory.get_pool(weth_addr, usdc_addr, fee_a).call().await?;
+    let pool_b_addr = factory.get_pool(weth_addr, usdc_addr, fee_b).call().await?;
+    if pool_a_addr == Address::zero() || pool_b_addr == Address::zero() {
+        warn!("WETH/USDC pool missing for fee {} or {} on this fork. Skipping.", fee_a, fee_b);
+        return Ok(());
+    }
+    assert_ne!(pool_a_addr, pool_b_addr, "Different fee tiers must resolve to different pools");
+    info!("Using UniV3 pools: A (fee {})={}, B (fee {})={}", fee_a, pool_a_addr, fee_b, pool_b_addr);
+
+    // Load both pools into state so snapshots/fees come from chain rather than being hand-written
+    let app_state = Arc::new(AppState::new(config.clone()));
+    state::fetch_and_cache_pool_state(pool_a_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    state::fetch_and_cache_pool_state(pool_b_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    let pool_a_state = app_state.pool_states.get(&pool_a_addr).map(|r| r.value().clone()).ok_or_else(|| eyre!("Pool A state missing"))?;
+    let pool_b_state = app_state.pool_states.get(&pool_b_addr).map(|r| r.value().clone()).ok_or_else(|| eyre!("Pool B state missing"))?;
+    assert_eq!(pool_a_state.uni_fee, Some(fee_a));
+    assert_eq!(pool_b_state.uni_fee, Some(fee_b));
+
+    let route = RouteCandidate {
+        buy_pool_addr: pool_a_addr,
+        sell_pool_addr: pool_b_addr,
+        buy_dex_type: DexType::UniswapV3,
+        sell_dex_type: DexType::UniswapV3,
+        token_in: weth_addr,
+        token_out: usdc_addr,
+        buy_pool_fee: pool_a_state.uni_fee,
+        sell_pool_fee: pool_b_state.uni_fee,
+        buy_pool_factory: config.uniswap_v3_factory_addr,
+        sell_pool_factory: config.uniswap_v3_factory_addr,
+        zero_for_one_a: pool_a_state.token0 == weth_addr,
+        ..Default::default()
+    };
+    info!("Constructed cross-fee Route Candidate: {:?}", route);
+    route.validate_directions(&pool_a_state, &pool_b_state)?;
+
+    let gas_info = fetch_gas_price(client.clone(), &config).await?;
+    let gas_price_gwei = ToF64Lossy::to_f64_lossy(&gas_info.max_priority_fee_per_gas) / 1e9;
+    let buy_snapshot = app_state.pool_snapshots.get(&pool_a_addr).map(|r| r.value().clone());
+    let sell_snapshot = app_state.pool_snapshots.get(&pool_b_addr).map(|r| r.value().clone());
+
+    let result = find_optimal_loan_amount(
+        client.clone(),
+        app_state.clone(),
+        &route,
+        buy_snapshot.as_ref(),
+        sell_snapshot.as_ref(),
+        gas_price_gwei,
+    ).await?;
+
+    // Anvil supports state overrides, so the end-to-end check must run as a single bundle eth_call
+    let probe_loan_wei = f64_to_wei(config.min_loan_amount_weth, config.weth_decimals as u32)?;
+    let bundle = simulate_flash_loan_bundle(app_state.clone(), client.clone(), &route, probe_loan_wei, gas_price_gwei).await?;
+    info!(?bundle, "Bundle simulation at the minimum loan.");
+    assert_eq!(bundle.method, SimulationMethod::FlashLoanBundle);
+
+    match result {
+        Some((loan_amount_wei, net_profit_wei, _)) => {
+            info!("✅ Cross-fee optimum found: loan={}, profit={}", loan_amount_wei, net_profit_wei);
+            let min_loan_wei = f64_to_wei(config.min_loan_amount_weth, config.weth_decimals as u32)?;
+            let max_loan_wei = f64_to_wei(config.max_loan_amount_weth, config.weth_decimals as u32)?;
+            assert!(net_profit_wei > I256::zero(), "Returned optimum must be profitable");
+            assert!(loan_amount_wei >= min_loan_wei && loan_amount_wei <= max_loan_wei, "Optimal loan must lie within the configured range");
+        }
+        None => info!("✅ No profitable cross-fee loan on this fork (clean None)."),
+    }
+    Ok(())
+}
+
+
+/// Test: the executor eth_call confirmation (the exact flash loan transaction, through the probe)
+/// against the two-quoter model, on the cross-fee UniV3 WETH/USDC route at the minimum loan.
+/// A reverting flash loan must correspond to a modelled loss; a confirmed one must be within 5% of the model.
+#[tokio::test]
+#[ignore]
+async fn test_executor_callstatic_matches_model() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_executor_callstatic_matches_model ---");
+    use ulp1_5::bindings::IUniswapV3Factory;
+    use ulp1_5::config::load_config;
+    use ulp1_5::encoding::{encode_user_data, route_swap_legs};
+    use ulp1_5::flash_loan::execute_flash_loan_calldata;
+    use ulp1_5::simulation::{calculate_net_profit, net_of_gas, simulate_via_executor_callstatic};
+    use ulp1_5::state::{self, AppState, DexType};
+    use ulp1_5::transaction::fetch_gas_price;
+    use ulp1_5::utils::f64_to_wei;
+
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let executor_addr = sim_env.executor_address.expect("Executor must be deployed for this test");
+    let mut config = load_config()?;
+    config.arb_executor_address = Some(executor_addr);
+    let (weth_addr, usdc_addr) = (config.weth_address, config.usdc_address);
+
+    let factory = IUniswapV3Factory::new(config.uniswap_v3_factory_addr, client.clone());
+    let pool_a_addr = factory.get_pool(weth_addr, usdc_addr, 500).call().await?;
+    let pool_b_addr = factory.get_pool(weth_addr, usdc_addr, 3000).call().await?;
+    if pool_a_addr == Address::zero() || pool_b_addr == Address::zero() {
+        warn!("WETH/USDC 0.05%/0.3% pools missing on this fork. Skipping.");
+        return Ok(());
+    }
+    let app_state = Arc::new(AppState::new(config.clone()));
+    state::fetch_and_cache_pool_state(pool_a_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    state::fetch_and_cache_pool_state(pool_b_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    let pool_a_state = app_state.pool_states.get(&pool_a_addr).map(|r| r.value().clone()).ok_or_else(|| eyre!("Pool A state missing"))?;
+    let pool_b_state = app_state.pool_states.get(&pool_b_addr).map(|r| r.value().clone()).ok_or_else(|| eyre!("Pool B state missing"))?;
+    let route = ulp1_5::path_optimizer::RouteCandidate {
+        buy_pool_addr: pool_a_addr, sell_pool_addr: pool_b_addr,
+        buy_dex_type: DexType::UniswapV3, sell_dex_type: DexType::UniswapV3,
+        token_in: weth_addr, token_out: usdc_addr,
+        buy_pool_fee: pool_a_state.uni_fee, sell_pool_fee: pool_b_state.uni_fee,
+        buy_pool_factory: config.uniswap_v3_factory_addr, sell_pool_factory: config.uniswap_v3_factory_addr,
+        zero_for_one_a: pool_a_state.token0 == weth_addr,
+        ..Default::default()
+    };
+    route.validate_directions(&pool_a_state, &pool_b_state)?;
+
+    let gas_info = fetch_gas_price(client.clone(), &config).await?;
+    let gas_price_gwei = gas_info.max_fee_per_gas.as_u128() as f64 / 1e9;
+    let loan_wei = f64_to_wei(config.min_loan_amount_weth, config.weth_decimals as u32)?;
+    let modeled = calculate_net_profit(app_state.clone(), client.clone(), &route, loan_wei, gas_price_gwei, config.gas_limit_buffer_percentage, config.min_flashloan_gas_limit, false).await?;
+
+    // No floors and no on-chain min profit, so only an unrepayable loan reverts
+    let (leg_a, leg_b) = route_swap_legs(&app_state, &route)?;
+    let user_data = encode_user_data(
+        config.executor_abi_version, leg_a, leg_b, usdc_addr, U256::zero(), U256::from(1u64), U256::zero(), U256::zero(),
+    )?;
+    let calldata = execute_flash_loan_calldata(weth_addr, loan_wei, user_data);
+    let outcome = simulate_via_executor_callstatic(client.provider(), weth_addr, executor_addr, &calldata).await?
+        .ok_or_else(|| eyre!("Anvil must support state overrides"))?;
+    info!(?outcome, %modeled, "Executor eth_call vs two-quoter model.");
+    if outcome.succeeded {
+        let confirmed = net_of_gas(&config, config.weth_decimals, outcome.gross_profit_wei, outcome.gas_used, gas_info.max_fee_per_gas)?;
+        let tolerance = modeled.abs() / I256::from(20);
+        assert!((confirmed - modeled).abs() <= tolerance, "eth_call profit {} is not within 5% of the modelled {}", confirmed, modeled);
+    } else {
+        assert!(modeled <= I256::zero(), "The model calls a loan profitable ({}) that the executor cannot repay", modeled);
+    }
+    Ok(())
+}
+
+
+/// Test: Local UniV3 swap math (univ3_math) against QuoterV2 on a forked WETH/USDC pool.
+/// Every amount the local math answers must match the quoter to the wei, including at least one swap
+/// that crosses initialized ticks in the tick window. Swaps that leave the window defer to the quoter
+/// (None) rather than return an inexact amount.
+#[tokio::test]
+#[ignore]
+async fn test_univ3_local_math_matches_quoter() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_univ3_local_math_matches_quoter ---");
+    use ulp1_5::bindings::{quoter_v2, IUniswapV3Factory};
+    use ulp1_5::config::load_config;
+    use ulp1_5::state::{self, AppState, DexType};
+    use ulp1_5::univ3_math::quote_exact_input;
+
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let config = load_config()?;
+    let (weth_addr, usdc_addr, fee) = (config.weth_address, config.usdc_address, 500u32);
+
+    let factory = IUniswapV3Factory::new(config.uniswap_v3_factory_addr, client.clone());
+    let pool_addr = factory.get_pool(weth_addr, usdc_addr, fee).call().await?;
+    if pool_addr == Address::zero() {
+        warn!("WETH/USDC {} pool missing on this fork. Skipping.", fee);
+        return Ok(());
+    }
+    let app_state = Arc::new(AppState::new(config.clone()));
+    state::fetch_and_cache_pool_state(pool_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    let snapshot = app_state.pool_snapshots.get(&pool_addr).map(|r| r.value().clone()).ok_or_else(|| eyre!("Snapshot missing"))?;
+    let (sqrt_price, tick, liquidity) = (
+        snapshot.sqrt_price_x96.ok_or_else(|| eyre!("No sqrtPriceX96"))?,
+        snapshot.tick.ok_or_else(|| eyre!("No tick"))?,
+        snapshot.liquidity.ok_or_else(|| eyre!("No liquidity"))?,
+    );
+    let zero_for_one = snapshot.token0 == weth_addr;
+    let tick_spacing = app_state.pool_states.get(&pool_addr).and_then(|s| s.tick_spacing);
+    assert_eq!(tick_spacing, Some(10), "tickSpacing() of a 0.05% pool");
+    let window = app_state.univ3_ticks.get(&pool_addr).map(|w| w.value().clone());
+    assert!(window.is_some() || config.univ3_tick_window_words == 0, "Tick window read with the pool");
+    let quoter = QuoterV2::new(config.quoter_v2_address, client.clone());
+
+    let (mut compared, mut compared_crossing) = (0, 0);
+    for amount_weth in ["0.0001", "0.001", "0.01", "0.1", "1", "10", "100", "1000"] {
+        let amount_in: U256 = parse_ether(amount_weth)?;
+        let local = quote_exact_input(sqrt_price, tick, liquidity, fee, tick_spacing, window.as_ref(), zero_for_one, amount_in)?;
+        let params = quoter_v2::QuoteExactInputSingleParams { token_in: weth_addr, token_out: usdc_addr, amount_in, fee, sqrt_price_limit_x96: U256::zero() };
+        let (quoted, _, ticks_crossed, _) = quoter.quote_exact_input_single(params).call().await?;
+        match local {
+            Some(local_out) => {
+                info!(amount_weth, %local_out, %quoted, ticks_crossed, "Local vs quoter");
+                let diff = if local_out > quoted { local_out - quoted } else { quoted - local_out };
+                assert!(diff <= U256::one(), "Local UniV3 math off by {} wei for {} WETH", diff, amount_weth);
+                compared += 1;
+                if ticks_crossed > 0 {
+                    compared_crossing += 1;
+                }
+            }
+            None => info!(amount_weth, %quoted, ticks_crossed, "Local math deferred to quoter (swap leaves the known ticks)"),
+        }
+    }
+    assert!(compared > 0, "Local math deferred every amount to the quoter");
+    assert!(compared_crossing > 0, "No compared amount crossed an initialized tick");
+    info!("✅ Compared {} amounts, {} crossing initialized ticks within the window.", compared, compared_crossing);
+    Ok(())
+}
+
+/// Test: velo_math stable-pool output matches the pool's own getAmountOut on the fork
+#[tokio::test]
+#[ignore]
+async fn test_velo_stable_math_matches_pool() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_velo_stable_math_matches_pool ---");
+    use ulp1_5::config::load_config;
+    use ulp1_5::velo_math::{get_amount_out, VeloPoolReserves};
+
+    // Native USDC on Optimism; paired with USDC.e (config.usdc_address) in a Velodrome V2 stable pool.
+    const NATIVE_USDC: &str = "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85";
+    // Velodrome V2 default stable fee; the factory can override it per pool.
+    const VELO_STABLE_FEE_BPS: u32 = 5;
+
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let config = load_config()?;
+    let router = VelodromeRouter::new(Address::from_str(VELO_ROUTER_IMPL_ADDR_FOR_TEST)?, client.clone());
+    let pool_addr = router.pool_for(config.usdc_address, Address::from_str(NATIVE_USDC)?, true, config.velodrome_v2_factory_addr).call().await?;
+    if client.get_code(pool_addr, None).await?.is_empty() {
+        warn!("USDC/USDC.e stable pool {:?} missing on this fork. Skipping.", pool_addr);
+        return Ok(());
+    }
+    let pool = VelodromeV2Pool::new(pool_addr, client.clone());
+    let (dec0, dec1, r0, r1, stable, t0, _t1) = pool.metadata().call().await?;
+    assert!(stable, "poolFor(stable = true) returned a volatile pool");
+    let reserves = VeloPoolReserves { reserve0: r0, reserve1: r1, decimals0: dec0, decimals1: dec1, stable };
+
+    for amount in ["1", "1000", "100000", "1000000"] {
+        let amount_in: U256 = parse_units(amount, 6)?.into();
+        let local = get_amount_out(&reserves, amount_in, true, VELO_STABLE_FEE_BPS)?;
+        let on_chain = pool.get_amount_out(amount_in, t0).call().await?;
+        info!(amount, %local, %on_chain, "Local vs pool");
+        let diff = if local > on_chain { local - on_chain } else { on_chain - local };
+        assert!(diff <= U256::one(), "Local Velo stable math off by {} for {} token0 in", diff, amount);
+    }
+    info!("✅ Local stable-pool math matches Pool.getAmountOut.");
+    Ok(())
+}
+
+
 // --- test_huff_direct_call remains unchanged ---
 /// Placeholder: Test direct interaction with Huff contract functions (e.g., withdraw)
 #[tokio::test]
@@ -355,4 +634,253 @@ async fn test_huff_direct_call() -> Result<()> {
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Test: profit unwrap. Wraps ETH, parks part of it in the executor as a stand-in for arb profit (a real
+/// arb on the fork is not reliably profitable), then checks `unwrap_weth_profit` sweeps the executor and
+/// leaves the wallet with only its WETH working balance and more native ETH.
+#[tokio::test]
+#[ignore]
+async fn test_unwrap_weth_profit() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_unwrap_weth_profit ---");
+    use ulp1_5::bindings::{IERC20, IWETH9};
+    use ulp1_5::config::load_config;
+    use ulp1_5::state::AppState;
+    use ulp1_5::transaction::{unwrap_weth_profit, NonceManager};
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let executor_addr = sim_env.executor_address.expect("Executor must be deployed for this test");
+    let wallet = sim_env.wallet_address;
+    let mut config = load_config()?;
+    config.arb_executor_address = Some(executor_addr);
+    config.auto_unwrap_profit = true;
+    let weth = IWETH9::new(config.weth_address, client.clone());
+
+    let wrapped = parse_ether(1)?;
+    let weth_before = weth.balance_of(wallet).call().await?;
+    config.min_weth_working_balance = weth_before + wrapped / 4;
+    weth.deposit().value(wrapped).send().await?.await?.ok_or_else(|| eyre!("WETH deposit dropped"))?;
+    IERC20::new(config.weth_address, client.clone()).transfer(executor_addr, wrapped / 2).send().await?.await?
+        .ok_or_else(|| eyre!("WETH transfer to executor dropped"))?;
+    let eth_before = client.get_balance(wallet, None).await?;
+    info!(%eth_before, "Wrapped 1 WETH, half parked in executor.");
+
+    let app_state = AppState::new(config);
+    let nonce_manager = NonceManager::new(wallet);
+    let unwrap_hash = unwrap_weth_profit(client.clone(), &app_state, &nonce_manager, wallet).await?;
+    assert!(unwrap_hash.is_some(), "Expected an unwrap transaction");
+    let receipt = client.get_transaction_receipt(unwrap_hash.unwrap()).await?.ok_or_else(|| eyre!("Unwrap receipt missing"))?;
+    assert_eq!(receipt.status, Some(U64::one()), "Unwrap tx reverted");
+
+    assert!(weth.balance_of(executor_addr).call().await?.is_zero(), "Executor still holds WETH");
+    assert_eq!(weth.balance_of(wallet).call().await?, weth_before + wrapped / 4, "Wallet working balance was unwrapped too");
+    let eth_after = client.get_balance(wallet, None).await?;
+    assert!(eth_after > eth_before, "Native ETH did not increase ({} -> {})", eth_before, eth_after);
+    info!(%eth_after, "✅ Unwrap landed.");
+    Ok(())
+}
+
+/// Test: `self-test` passes against the running Anvil fork (same fork the other tests use)
+#[tokio::test]
+#[ignore]
+async fn test_self_test_round_trip_on_fork() -> Result<()> {
+    setup_tracing();
+    use ulp1_5::config::load_config;
+    use ulp1_5::local_simulator::SIMULATION_CONFIG;
+    use ulp1_5::self_test::run_self_test;
+    let mut config = load_config().expect("Failed to load test config from .env");
+    config.self_test_rpc_url = Some(SIMULATION_CONFIG.anvil_http_url.to_string());
+    let report = run_self_test(&config).await?;
+    assert!(report.simulated_net_profit_wei > I256::zero());
+    info!(?report, "✅ Self-test passed.");
+    Ok(())
+}
+
+/// Test: a replay at a past block reads that block's price, not the one a later swap left behind
+#[tokio::test]
+#[ignore]
+async fn test_replay_reads_historical_pool_state() -> Result<()> {
+    setup_tracing();
+    use ulp1_5::config::load_config;
+    use ulp1_5::replay::{replay_route, replay_state, simulate_at_block};
+    use ulp1_5::state::DexType;
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let config = load_config().expect("Failed to load test config from .env");
+    let uni_pool_addr: Address = "0x851492574065EDE975391E141377067943aA08eF".parse()?;
+    let velo_pool_addr: Address = "0x207addb05c548f262219f6b50eadff8640ed6488".parse()?;
+    let uni_pool = UniswapV3Pool::new(uni_pool_addr, client.clone());
+
+    let head = client.get_block_number().await?;
+    let (sqrt_price_at_head, ..) = uni_pool.slot_0().call().await?;
+    trigger_v3_swap(&sim_env, uni_pool_addr, &uni_pool, sim_env.wallet_address, true, I256::from_raw(parse_ether("5")?), U256::zero(), Bytes::new()).await?;
+    let (sqrt_price_now, ..) = uni_pool.slot_0().call().await?;
+    assert_ne!(sqrt_price_now, sqrt_price_at_head, "Swap did not move the price");
+
+    let pools = [(uni_pool_addr, DexType::UniswapV3), (velo_pool_addr, DexType::VelodromeV2)];
+    let app_state = replay_state(client.clone(), &config, &pools, head).await?;
+    let replayed = app_state.pool_snapshots.get(&uni_pool_addr).map(|s| s.value().clone()).ok_or_else(|| eyre!("UniV3 pool not replayed"))?;
+    assert_eq!(replayed.sqrt_price_x96, Some(sqrt_price_at_head), "Replay read the post-swap price");
+
+    // Which direction is a route depends on the fork's prices; only check the search is pinned
+    for (buy, sell) in [(uni_pool_addr, velo_pool_addr), (velo_pool_addr, uni_pool_addr)] {
+        if let Ok(route) = replay_route(&app_state, buy, sell) {
+            let report = simulate_at_block(client.clone(), app_state.clone(), &route, head).await?;
+            assert_eq!(report.block, head);
+            info!(?report, "✅ Replayed route.");
+        }
+    }
+    Ok(())
+}
+
+/// Test: WETH rebalance. Buys USDC with ETH on Velodrome as stand-in profit, raises the WETH floor above
+/// the wallet's balance, then checks `rebalance_weth` sells the USDC and restores the floor.
+#[tokio::test]
+#[ignore]
+async fn test_rebalance_restores_weth_working_balance() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_rebalance_restores_weth_working_balance ---");
+    use ulp1_5::bindings::{velodrome_router, IERC20};
+    use ulp1_5::config::load_config;
+    use ulp1_5::rebalance::rebalance_weth;
+    use ulp1_5::state::AppState;
+    use ulp1_5::transaction::NonceManager;
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let wallet = sim_env.wallet_address;
+    let mut config = load_config()?;
+    config.arb_executor_address = None; // Profit sits in the wallet; nothing to sweep
+    let weth = IERC20::new(config.weth_address, client.clone());
+    let usdc = IERC20::new(config.usdc_address, client.clone());
+
+    let router = VelodromeRouter::new(config.velo_router_addr, client.clone());
+    let routes = vec![velodrome_router::Route { from: config.weth_address, to: config.usdc_address, stable: false, factory: config.velodrome_v2_factory_addr }];
+    router.swap_exact_eth_for_tokens(U256::zero(), routes, wallet, U256::from(u64::MAX)).value(parse_ether("0.05")?).send().await?.await?
+        .ok_or_else(|| eyre!("USDC purchase dropped"))?;
+    let usdc_profit = usdc.balance_of(wallet).call().await?;
+    assert!(!usdc_profit.is_zero(), "Expected USDC in the wallet");
+
+    let weth_before = weth.balance_of(wallet).call().await?;
+    config.min_weth_working_balance = weth_before + parse_ether("0.01")?;
+    let floor = config.min_weth_working_balance;
+    let app_state = AppState::new(config);
+    let nonce_manager = NonceManager::new(wallet);
+    let report = rebalance_weth(client.clone(), &app_state, &nonce_manager, wallet).await?.ok_or_else(|| eyre!("Balance was below the floor"))?;
+    info!(?report, "Rebalance report.");
+
+    assert_eq!(report.swaps.len(), 1);
+    assert_eq!((report.swaps[0].token, report.swaps[0].amount_in), (app_state.config.usdc_address, usdc_profit));
+    assert!(usdc.balance_of(wallet).call().await?.is_zero(), "USDC not sold");
+    let weth_after = weth.balance_of(wallet).call().await?;
+    assert_eq!(weth_after, report.weth_after);
+    assert!(weth_after >= floor, "WETH {} still below floor {}", weth_after, floor);
+    Ok(())
+}
+
+/// Test: auto-wrap. Unwraps any WETH so the wallet starts with only ETH, then sets a WETH floor no
+/// leftover USDC could cover and checks `rebalance_weth` wraps native ETH to restore it.
+#[tokio::test]
+#[ignore]
+async fn test_rebalance_wraps_eth_shortfall() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_rebalance_wraps_eth_shortfall ---");
+    use ulp1_5::bindings::IWETH9;
+    use ulp1_5::config::load_config;
+    use ulp1_5::rebalance::rebalance_weth;
+    use ulp1_5::state::AppState;
+    use ulp1_5::transaction::NonceManager;
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let wallet = sim_env.wallet_address;
+    let mut config = load_config()?;
+    config.arb_executor_address = None;
+    config.auto_wrap_eth = true;
+    config.min_weth_working_balance = parse_ether("100")?;
+    let weth = IWETH9::new(config.weth_address, client.clone());
+
+    let held = weth.balance_of(wallet).call().await?;
+    if !held.is_zero() {
+        weth.withdraw(held).send().await?.await?.ok_or_else(|| eyre!("WETH withdraw dropped"))?;
+    }
+    assert!(weth.balance_of(wallet).call().await?.is_zero(), "Wallet should start with only ETH");
+
+    let floor = config.min_weth_working_balance;
+    let app_state = AppState::new(config);
+    let nonce_manager = NonceManager::new(wallet);
+    let report = rebalance_weth(client.clone(), &app_state, &nonce_manager, wallet).await?.ok_or_else(|| eyre!("Balance was below the floor"))?;
+    info!(?report, "Rebalance report.");
+
+    assert!(report.wrap_tx_hash.is_some(), "No wrap recorded: {:?}", report);
+    let weth_after = weth.balance_of(wallet).call().await?;
+    assert_eq!(weth_after, report.weth_after);
+    assert!(weth_after >= floor, "WETH {} still below floor {}", weth_after, floor);
+    Ok(())
+}
+
+/// Test: ProfitRealized from a real receipt. Dumps WETH into the Velodrome WETH/USDC pool so WETH is cheap
+/// there, flash-borrows WETH from the Balancer vault, sells it on UniV3 and buys it back on Velodrome, then
+/// decodes the executor's `ProfitRealized` log from the mined receipt and checks it against the executor's
+/// WETH balance change.
+#[tokio::test]
+#[ignore]
+async fn test_profit_realized_decoded_from_receipt() -> Result<()> {
+    setup_tracing();
+    info!("--- Running Test: test_profit_realized_decoded_from_receipt ---");
+    use ulp1_5::bindings::{IERC20, IWETH9};
+    use ulp1_5::config::load_config;
+    use ulp1_5::encoding::{encode_user_data, route_swap_legs};
+    use ulp1_5::flash_loan::execute_flash_loan_calldata;
+    use ulp1_5::path_optimizer::RouteCandidate;
+    use ulp1_5::state::{self, AppState, DexType};
+    use ulp1_5::transaction::realized_profit_from_receipt;
+    let sim_env = setup_simulation_environment().await?;
+    let client = sim_env.http_client.clone();
+    let executor_addr = sim_env.executor_address.expect("Executor must be deployed for this test");
+    let mut config = load_config()?;
+    config.arb_executor_address = Some(executor_addr);
+    let uni_pool_addr: Address = "0x851492574065EDE975391E141377067943aA08eF".parse()?;
+    let velo_pool_addr: Address = "0x207addb05c548f262219f6b50eadff8640ed6488".parse()?;
+
+    // Sell 20 WETH into the Velodrome pool (transfer, then swap out the quoted USDC)
+    let dump = parse_ether(20)?;
+    IWETH9::new(config.weth_address, client.clone()).deposit().value(dump).send().await?.await?.ok_or_else(|| eyre!("WETH deposit dropped"))?;
+    let velo_pool = VelodromeV2Pool::new(velo_pool_addr, client.clone());
+    let usdc_out = velo_pool.get_amount_out(dump, config.weth_address).call().await?;
+    IERC20::new(config.weth_address, client.clone()).transfer(velo_pool_addr, dump).send().await?.await?.ok_or_else(|| eyre!("WETH transfer dropped"))?;
+    let (amount0_out, amount1_out) = if velo_pool.token_0().call().await? == config.usdc_address { (usdc_out, U256::zero()) } else { (U256::zero(), usdc_out) };
+    let dump_hash = trigger_v2_swap(&sim_env, velo_pool_addr, &velo_pool, amount0_out, amount1_out, sim_env.wallet_address, Bytes::new()).await?;
+    client.get_transaction_receipt(dump_hash).await?.filter(|r| r.status == Some(U64::one())).ok_or_else(|| eyre!("Velodrome dump swap failed"))?;
+
+    let app_state = Arc::new(AppState::new(config.clone()));
+    state::fetch_and_cache_pool_state(uni_pool_addr, DexType::UniswapV3, config.uniswap_v3_factory_addr, None, client.clone(), app_state.clone()).await?;
+    state::fetch_and_cache_pool_state(velo_pool_addr, DexType::VelodromeV2, config.velodrome_v2_factory_addr, None, client.clone(), app_state.clone()).await?;
+    let uni_token0 = app_state.pool_states.get(&uni_pool_addr).map(|s| s.token0).ok_or_else(|| eyre!("UniV3 pool state missing"))?;
+    let route = RouteCandidate {
+        buy_pool_addr: uni_pool_addr, sell_pool_addr: velo_pool_addr,
+        buy_dex_type: DexType::UniswapV3, sell_dex_type: DexType::VelodromeV2,
+        token_in: config.weth_address, token_out: config.usdc_address,
+        buy_pool_fee: Some(500), sell_pool_stable: Some(false),
+        buy_pool_factory: config.uniswap_v3_factory_addr, sell_pool_factory: config.velodrome_v2_factory_addr,
+        zero_for_one_a: uni_token0 == config.weth_address,
+        ..Default::default()
+    };
+    let (leg_a, leg_b) = route_swap_legs(&app_state, &route)?;
+    let salt = U256::from(client.get_block_number().await?.as_u64());
+    let user_data = encode_user_data(config.executor_abi_version, leg_a, leg_b, config.usdc_address, U256::zero(), salt, U256::zero(), U256::zero())?;
+    let loan_wei = parse_ether(1)?;
+    let calldata = execute_flash_loan_calldata(config.weth_address, loan_wei, user_data);
+
+    let weth = IERC20::new(config.weth_address, client.clone());
+    let before = weth.balance_of(executor_addr).call().await?;
+    let tx = TransactionRequest::new().to(executor_addr).data(calldata).gas(1_500_000u64);
+    let receipt = client.send_transaction(tx, None).await?.await?.ok_or_else(|| eyre!("Flash loan receipt missing"))?;
+    assert_eq!(receipt.status, Some(U64::one()), "Flash loan reverted; the dump did not open a profitable spread");
+    let after = weth.balance_of(executor_addr).call().await?;
+
+    let profit = realized_profit_from_receipt(&receipt, executor_addr).ok_or_else(|| eyre!("No ProfitRealized log in the receipt"))?;
+    info!(%profit, tx_hash = ?receipt.transaction_hash, "✅ ProfitRealized decoded from the receipt.");
+    assert!(profit > I256::zero());
+    assert_eq!(profit, I256::from_raw(after - before), "Event disagrees with the executor's WETH balance change");
+    Ok(())
+}
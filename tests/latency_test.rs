@@ -0,0 +1,48 @@
+// tests/latency_test.rs
+// Offline tests for the rolling RPC latency tracker and adaptive fetch timeout.
+
+use std::time::Duration;
+use ulp1_5::config::Config;
+use ulp1_5::latency::{adaptive_timeout, LatencyTracker, MIN_LATENCY_SAMPLES};
+use ulp1_5::state::AppState;
+
+#[test]
+fn test_p95_needs_min_samples() {
+    let tracker = LatencyTracker::default();
+    for ms in 1..MIN_LATENCY_SAMPLES as u64 {
+        tracker.record(Duration::from_millis(ms));
+    }
+    assert_eq!(tracker.p95(MIN_LATENCY_SAMPLES), None);
+    // 1..=100 ms: p95 (nearest rank) is 95 ms
+    for ms in MIN_LATENCY_SAMPLES as u64..=100 {
+        tracker.record(Duration::from_millis(ms));
+    }
+    assert_eq!(tracker.p95(MIN_LATENCY_SAMPLES), Some(Duration::from_millis(95)));
+}
+
+#[test]
+fn test_adaptive_timeout_is_bounded_multiple_of_p95() {
+    let (fallback, min, max) = (Duration::from_secs(15), Duration::from_secs(2), Duration::from_secs(30));
+    assert_eq!(adaptive_timeout(None, fallback, min, max), fallback);
+    assert_eq!(adaptive_timeout(Some(Duration::from_secs(4)), fallback, min, max), Duration::from_secs(12));
+    assert_eq!(adaptive_timeout(Some(Duration::from_millis(100)), fallback, min, max), min);
+    assert_eq!(adaptive_timeout(Some(Duration::from_secs(20)), fallback, min, max), max);
+    assert_eq!(adaptive_timeout(Some(Duration::from_secs(4)), fallback, min, Duration::ZERO), fallback, "0 max disables adaptation");
+}
+
+#[tokio::test]
+async fn test_timed_fetch_feeds_fetch_timeout_metric() {
+    let app_state = AppState::new(Config {
+        http_rpc_url: "http://rpc.test".into(), fetch_timeout_secs: Some(15), min_fetch_timeout_secs: 1, max_fetch_timeout_secs: 30,
+        ..Config::default()
+    });
+    let metric = || app_state.metrics.values().into_iter().find(|(n, _)| *n == "adaptive_fetch_timeout_ms").unwrap().1;
+    assert_eq!(app_state.fetch_timeout(), Duration::from_secs(15));
+    assert_eq!(metric(), 15_000);
+    for _ in 0..MIN_LATENCY_SAMPLES {
+        assert_eq!(app_state.timed_fetch(async { 7 }).await.unwrap(), 7);
+    }
+    // Instant fetches: 3 × p95 is far below the floor
+    assert_eq!(app_state.fetch_timeout(), Duration::from_secs(1));
+    assert_eq!(metric(), 1_000);
+}
@@ -0,0 +1,98 @@
+// tests/mempool_test.rs
+// Decoding of pending swaps and post-swap snapshot prediction, on offline state.
+
+mod common;
+
+use common::{usdc, weth};
+use ethers::abi::AbiEncode;
+use ethers::types::{Address, Transaction, I256, U256};
+use ulp1_5::bindings::{uniswap_v3_pool, velodrome_router};
+use ulp1_5::config::Config;
+use ulp1_5::mempool::{decode_pending_swap, predict_snapshot, PendingSwap, VELO_DEFAULT_VOLATILE_FEE_BPS};
+use ulp1_5::state::{AppState, DexType, PoolSnapshot, PoolState};
+use ulp1_5::velo_math::{get_amount_out, VeloPoolReserves};
+
+fn router() -> Address { Address::from_low_u64_be(0x7007) }
+const UNI_POOL: u64 = 0x1;
+const VELO_POOL: u64 = 0x2;
+
+fn state() -> AppState {
+    let state = AppState::new(Config {
+        weth_address: weth(), usdc_address: usdc(), weth_decimals: 18, usdc_decimals: 6, velo_router_addr: router(),
+        ..Config::default()
+    });
+    let uni = Address::from_low_u64_be(UNI_POOL);
+    state.pool_states.insert(uni, PoolState {
+        pool_address: uni, dex_type: DexType::UniswapV3, token0: weth(), token1: usdc(),
+        uni_fee: Some(500), tick_spacing: Some(10), velo_stable: None, t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    state.pool_snapshots.insert(uni, PoolSnapshot {
+        pool_address: uni, dex_type: DexType::UniswapV3, token0: weth(), token1: usdc(), reserve0: None, reserve1: None,
+        // Mid-range of tick -201_000..-200_990 (~2000 USDC/WETH)
+        sqrt_price_x96: Some(ulp1_5::univ3_math::get_sqrt_ratio_at_tick(-200_995).unwrap()), tick: Some(-200_995),
+        liquidity: Some(10u128.pow(21)), last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    let velo = Address::from_low_u64_be(VELO_POOL);
+    state.pool_states.insert(velo, PoolState {
+        pool_address: velo, dex_type: DexType::VelodromeV2, token0: weth(), token1: usdc(),
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    state.pool_snapshots.insert(velo, PoolSnapshot {
+        pool_address: velo, dex_type: DexType::VelodromeV2, token0: weth(), token1: usdc(),
+        reserve0: Some(U256::exp10(20)), reserve1: Some(U256::from(200_000u64) * U256::exp10(6)),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    state
+}
+
+fn tx(to: Address, input: Vec<u8>) -> Transaction {
+    Transaction { to: Some(to), input: input.into(), ..Default::default() }
+}
+
+fn uni_swap(amount_specified: I256) -> Vec<u8> {
+    uniswap_v3_pool::SwapCall {
+        recipient: Address::zero(), zero_for_one: true, amount_specified,
+        sqrt_price_limit_x96: U256::zero(), data: Default::default(),
+    }.encode()
+}
+
+#[test]
+fn test_pending_univ3_swap_moves_predicted_price() {
+    let state = state();
+    let pool = Address::from_low_u64_be(UNI_POOL);
+    let amount_in = U256::exp10(15); // 0.001 WETH, well inside the tick range
+    let swap = decode_pending_swap(&tx(pool, uni_swap(I256::from_raw(amount_in))), &state).unwrap();
+    assert_eq!(swap, PendingSwap { pool, zero_for_one: true, amount_in });
+
+    let before = state.pool_snapshots.get(&pool).unwrap().sqrt_price_x96.unwrap();
+    let predicted = predict_snapshot(&swap, &state).unwrap();
+    assert!(predicted.sqrt_price_x96.unwrap() < before, "Selling token0 lowers the price");
+    assert_eq!(state.pool_snapshots.get(&pool).unwrap().sqrt_price_x96.unwrap(), before, "Cache is untouched");
+
+    assert!(decode_pending_swap(&tx(pool, uni_swap(I256::from(-1_000))), &state).is_none(), "Exact output is not decoded");
+}
+
+#[test]
+fn test_pending_router_swap_predicts_velo_reserves() {
+    let state = state();
+    let amount_in = U256::from(1_000u64) * U256::exp10(6); // 1000 USDC
+    let call = velodrome_router::SwapExactTokensForTokensCall {
+        amount_in, amount_out_min: U256::zero(),
+        routes: vec![velodrome_router::Route { from: usdc(), to: weth(), stable: false, factory: Address::zero() }],
+        to: Address::zero(), deadline: U256::MAX,
+    }.encode();
+    assert!(decode_pending_swap(&tx(Address::from_low_u64_be(0xdead), call.clone()), &state).is_none(), "Unknown target");
+
+    let swap = decode_pending_swap(&tx(router(), call), &state).unwrap();
+    let pool = Address::from_low_u64_be(VELO_POOL);
+    assert_eq!(swap, PendingSwap { pool, zero_for_one: false, amount_in });
+
+    let (r0, r1) = (U256::exp10(20), U256::from(200_000u64) * U256::exp10(6));
+    let out = get_amount_out(&VeloPoolReserves::new(r0, r1, 18, 6, false), amount_in, false, VELO_DEFAULT_VOLATILE_FEE_BPS).unwrap();
+    let predicted = predict_snapshot(&swap, &state).unwrap();
+    assert_eq!(predicted.reserve0, Some(r0 - out));
+    assert_eq!(predicted.reserve1, Some(r1 + amount_in - amount_in * 30 / 10_000), "Fee leaves the reserves");
+}
+// END OF FILE: tests/mempool_test.rs
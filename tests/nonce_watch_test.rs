@@ -0,0 +1,95 @@
+// tests/nonce_watch_test.rs
+// Nonce divergence dead man's switch: local nonce advancing while the on-chain count stays flat.
+
+use ethers::providers::Provider;
+use ethers::types::{Address, U256, U64};
+use std::sync::Arc;
+use ulp1_5::config::Config;
+use ulp1_5::nonce_watch::{check_nonce_divergence, NonceDivergenceTransition, NonceWatch};
+use ulp1_5::state::{AppState, PauseReason};
+use ulp1_5::transaction::NonceManager;
+
+fn app_state(max_blocks: u64) -> AppState {
+    AppState::new(Config { nonce_divergence_blocks: max_blocks, ..Config::default() })
+}
+
+#[test]
+fn test_flat_onchain_count_trips_breaker() {
+    let state = app_state(10);
+    let watch = NonceWatch::default();
+    let onchain = U256::from(5);
+    assert_eq!(watch.observe(&state, U64::from(100), None, onchain), None, "Nothing submitted yet");
+    assert_eq!(watch.observe(&state, U64::from(100), Some(U256::from(4)), onchain), None, "Caught up");
+
+    // Nonces 5 and 6 submitted, the count never moves
+    assert_eq!(watch.observe(&state, U64::from(101), Some(U256::from(5)), onchain), None);
+    assert_eq!(watch.observe(&state, U64::from(105), Some(U256::from(6)), onchain), None);
+    assert!(!state.is_paused());
+    assert_eq!(
+        watch.observe(&state, U64::from(111), Some(U256::from(6)), onchain),
+        Some(NonceDivergenceTransition::Diverged { local_next: U256::from(7), onchain, blocks: 10 })
+    );
+    assert!(state.is_paused());
+    assert_eq!(state.metrics.values().iter().find(|(n, _)| *n == "unmined_nonces").unwrap().1, 2);
+    assert_eq!(watch.observe(&state, U64::from(120), Some(U256::from(6)), onchain), None, "Alert only on the crossing");
+
+    assert_eq!(watch.observe(&state, U64::from(121), Some(U256::from(6)), U256::from(7)), Some(NonceDivergenceTransition::Recovered));
+    assert!(!state.is_paused());
+}
+
+#[test]
+fn test_landing_transactions_restart_the_clock() {
+    let state = app_state(10);
+    let watch = NonceWatch::default();
+    // Always one transaction in flight, but the count keeps advancing
+    for (block, onchain) in [(100u64, 5u64), (108, 6), (116, 7), (124, 8)] {
+        assert_eq!(watch.observe(&state, U64::from(block), Some(U256::from(onchain)), U256::from(onchain)), None);
+    }
+    assert!(!state.is_paused());
+
+    // A manual pause is not lifted by the check, and 0 disables it
+    let disabled = app_state(0);
+    disabled.set_paused(PauseReason::Admin, true);
+    assert_eq!(NonceWatch::default().observe(&disabled, U64::from(1_000), Some(U256::from(9)), U256::zero()), None);
+    assert!(disabled.is_paused());
+}
+
+#[test]
+fn test_only_the_nonce_reason_is_set_and_cleared() {
+    let state = AppState::new(Config { nonce_divergence_blocks: 1, min_gas_balance_wei: U256::from(1_000u64), ..Config::default() });
+    let watch = NonceWatch::default();
+    ulp1_5::balance::apply_gas_balance(&state, U256::from(10u64));
+    assert!(watch.observe(&state, U64::from(10), Some(U256::from(5)), U256::from(5)).is_none());
+    assert!(matches!(watch.observe(&state, U64::from(11), Some(U256::from(5)), U256::from(5)), Some(NonceDivergenceTransition::Diverged { .. })));
+
+    // An admin resume does not undo the divergence pause
+    state.set_paused(PauseReason::Admin, true);
+    state.set_paused(PauseReason::Admin, false);
+    assert_eq!(state.pause_reasons(), vec![PauseReason::LowGasBalance, PauseReason::NonceDivergence]);
+
+    // Recovery clears the divergence only; the low balance still holds the breaker
+    assert_eq!(watch.observe(&state, U64::from(12), Some(U256::from(5)), U256::from(6)), Some(NonceDivergenceTransition::Recovered));
+    assert_eq!(state.pause_reasons(), vec![PauseReason::LowGasBalance]);
+    assert!(state.is_paused());
+}
+
+#[tokio::test]
+async fn test_check_reads_latest_count() -> eyre::Result<()> {
+    let state = app_state(1);
+    let watch = NonceWatch::default();
+    let nonce_manager = NonceManager::new(Address::from_low_u64_be(0xbeef));
+    let (provider, mock) = Provider::mocked();
+    // Nonce 3 handed out from the pending count
+    mock.push(U256::from(3))?;
+    assert_eq!(nonce_manager.get_next_nonce(Arc::new(provider.clone())).await?, U256::from(3));
+
+    // Responses are served last-in first-out: block number, then transaction count
+    for block in [10u64, 12] {
+        mock.push(U256::from(3))?;
+        mock.push(U64::from(block))?;
+        check_nonce_divergence(&provider, &state, &nonce_manager, &watch, Address::from_low_u64_be(0xbeef)).await?;
+    }
+    assert!(state.is_paused());
+    Ok(())
+}
+// END OF FILE: tests/nonce_watch_test.rs
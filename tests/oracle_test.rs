@@ -0,0 +1,122 @@
+// tests/oracle_test.rs
+// Offline tests for the WETH/USD price source (no feed configured, no RPC calls).
+
+mod common;
+
+use ethers::types::{Address, I256, U256};
+use ulp1_5::config::Config;
+use ulp1_5::oracle::{chainlink_answer_to_usd, convert_at_price, convert_via_pool, net_profit_usd, pool_derived_weth_price_usd};
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::state::{AppState, DexType, PoolSnapshot, PoolState};
+use ulp1_5::token_safety::TokenDecimals;
+
+#[test]
+fn test_chainlink_answer_scaling() {
+    let price = chainlink_answer_to_usd(I256::from(351_234_000_000i64), 8).unwrap();
+    assert!((price - 3512.34).abs() < 1e-9);
+    assert!(chainlink_answer_to_usd(I256::zero(), 8).is_none());
+    assert!(chainlink_answer_to_usd(I256::from(-1), 8).is_none());
+}
+
+#[test]
+fn test_pool_derived_price_uses_weth_usdc_snapshots() {
+    // WETH (18 decimals) sorts after USDC (6 decimals), so WETH is token1 here
+    let usdc = Address::from_low_u64_be(1);
+    let weth = Address::from_low_u64_be(2);
+    let config = Config { weth_address: weth, usdc_address: usdc, weth_decimals: 18, usdc_decimals: 6, ..Default::default() };
+    let app_state = AppState::new(config);
+    assert!(pool_derived_weth_price_usd(&app_state).is_none());
+
+    let pool = Address::from_low_u64_be(0x7e10);
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: usdc, token1: weth,
+        reserve0: Some(U256::from(3_000_000u64) * U256::exp10(6)), reserve1: Some(U256::from(1_000u64) * U256::exp10(18)),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    let price = pool_derived_weth_price_usd(&app_state).unwrap();
+    assert!((price - 3000.0).abs() < 1e-6, "got {}", price);
+
+    // Pools outside the WETH/USDC pair are ignored
+    let other = Address::from_low_u64_be(0x0123);
+    app_state.pool_snapshots.insert(other, PoolSnapshot {
+        pool_address: other, dex_type: DexType::VelodromeV2, token0: weth, token1: Address::from_low_u64_be(3),
+        reserve0: Some(U256::one()), reserve1: Some(U256::one()),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    assert!((pool_derived_weth_price_usd(&app_state).unwrap() - 3000.0).abs() < 1e-6);
+}
+
+fn route(token_in: Address, token_out: Address, buy_pool: Address) -> RouteCandidate {
+    RouteCandidate { token_in, token_out, ..common::route(buy_pool, DexType::VelodromeV2, Address::from_low_u64_be(0x5e11), DexType::VelodromeV2) }
+}
+
+#[test]
+fn test_net_profit_usd_compares_across_loan_tokens() {
+    let usdc = Address::from_low_u64_be(1);
+    let weth = Address::from_low_u64_be(2);
+    let op = Address::from_low_u64_be(3);
+    let app_state = AppState::new(Config { weth_address: weth, usdc_address: usdc, weth_decimals: 18, usdc_decimals: 6, ..Default::default() });
+
+    // 0.01 WETH at $3000 and 30 USDC are both $30
+    let weth_profit = net_profit_usd(&app_state, &route(weth, usdc, Address::zero()), I256::exp10(16), Some(3000.0)).unwrap();
+    let usdc_profit = net_profit_usd(&app_state, &route(usdc, weth, Address::zero()), I256::from(30_000_000), Some(3000.0)).unwrap();
+    assert!((weth_profit - 30.0).abs() < 1e-9, "{}", weth_profit);
+    assert!((usdc_profit - 30.0).abs() < 1e-9, "{}", usdc_profit);
+    assert!((net_profit_usd(&app_state, &route(weth, usdc, Address::zero()), -I256::exp10(16), Some(3000.0)).unwrap() + 30.0).abs() < 1e-9, "Losses stay negative");
+    assert_eq!(net_profit_usd(&app_state, &route(weth, usdc, Address::zero()), I256::exp10(16), None), None, "No WETH price");
+
+    // 20 OP through an OP/USDC pool at $1.50
+    let pool = Address::from_low_u64_be(0x0901);
+    app_state.pool_states.insert(pool, PoolState {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: op, token1: usdc,
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(false), factory: Address::zero(), created_block: None,
+    });
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: op, token1: usdc,
+        reserve0: Some(U256::exp10(24)), reserve1: Some(U256::from(1_500_000u64) * U256::exp10(6)),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+    let op_profit = net_profit_usd(&app_state, &route(op, usdc, pool), I256::from(20) * I256::exp10(18), None).unwrap();
+    assert!((op_profit - 30.0).abs() < 1e-6, "{}", op_profit);
+    assert_eq!(convert_via_pool(&app_state, pool, op, weth, U256::exp10(18)), None, "The pool does not pair OP with WETH");
+}
+
+#[test]
+fn test_convert_at_price_both_directions() {
+    // 1 WETH (18 decimals) at 2000 USDC (6 decimals): raw price 2000e6 / 1e18
+    let price = 2e-9;
+    let close = |actual: U256, expected: U256| assert!(actual.max(expected) - actual.min(expected) <= U256::one(), "{} != {}", actual, expected);
+    close(convert_at_price(U256::exp10(18), price, true).unwrap(), U256::from(2_000_000_000u64));
+    close(convert_at_price(U256::from(2_000_000_000u64), price, false).unwrap(), U256::exp10(18));
+    assert_eq!(convert_at_price(U256::exp10(18), 0.0, true), None);
+    assert_eq!(convert_at_price(U256::exp10(18), f64::NAN, true), None);
+}
+
+#[test]
+fn test_conversions_use_fetched_token_decimals() {
+    let usdc = Address::from_low_u64_be(1);
+    let weth = Address::from_low_u64_be(2);
+    let dai = Address::from_low_u64_be(3);
+    let odd = Address::from_low_u64_be(4);
+    let app_state = AppState::new(Config {
+        weth_address: weth, usdc_address: usdc, usdc_addresses: vec![dai], weth_decimals: 18, usdc_decimals: 6, ..Default::default()
+    });
+    assert_eq!(app_state.decimals_of(dai), Some(6), "Unfetched quote tokens use the configured decimals");
+    assert_eq!(app_state.decimals_of(odd), None);
+
+    app_state.token_decimals.insert(dai, TokenDecimals { decimals: 18, assumed: false });
+    app_state.token_decimals.insert(odd, TokenDecimals { decimals: 18, assumed: true });
+    app_state.token_decimals.insert(usdc, TokenDecimals { decimals: 18, assumed: true });
+    assert_eq!(app_state.decimals_of(dai), Some(18));
+    assert_eq!(app_state.decimals_of(odd), Some(18), "The assumed default applies when nothing else is known");
+    assert_eq!(app_state.decimals_of(usdc), Some(6), "Configured decimals beat an assumed default");
+
+    // 30 DAI is $30 at DAI's own 18 decimals, not USDC's 6
+    let dai_profit = net_profit_usd(&app_state, &route(dai, weth, Address::zero()), I256::from(30) * I256::exp10(18), None).unwrap();
+    assert!((dai_profit - 30.0).abs() < 1e-9, "{}", dai_profit);
+    let quote_decimals: Vec<(Address, u8)> = app_state.trading_pairs().iter().map(|p| (p.quote, p.quote_decimals)).collect();
+    assert_eq!(quote_decimals, vec![(usdc, 6), (dai, 18)]);
+}
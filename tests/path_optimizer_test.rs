@@ -0,0 +1,443 @@
+// tests/path_optimizer_test.rs
+// Offline tests for route discovery over in-memory pool caches (no Anvil required).
+
+mod common;
+
+use common::{usdc, weth};
+use dashmap::DashMap;
+use ethers::types::{Address, U256, U64};
+use std::sync::Arc;
+use ulp1_5::config::Config;
+use ulp1_5::path_optimizer::{find_top_routes, score_route, RouteCandidate, TradingPair};
+use ulp1_5::state::{DexType, PoolSnapshot, PoolState};
+
+const WETH_DECIMALS: u8 = 18;
+const USDC_DECIMALS: u8 = 6;
+
+fn pool(n: u64) -> Address { Address::from_low_u64_be(n) }
+
+/// WETH against USDC and `config.usdc_addresses`, as `AppState::trading_pairs` builds them.
+fn pairs(config: &Config) -> Vec<TradingPair> {
+    TradingPair::with_quotes(weth(), std::iter::once(usdc()).chain(config.usdc_addresses.iter().copied()), WETH_DECIMALS, USDC_DECIMALS)
+}
+
+/// Caches a WETH/USDC Velodrome pool quoting `usdc_per_weth` (100 WETH of depth).
+fn add_v2_pool(
+    states: &DashMap<Address, PoolState>,
+    snapshots: &DashMap<Address, PoolSnapshot>,
+    addr: Address,
+    usdc_per_weth: u64,
+) {
+    add_v2_pool_quoted(states, snapshots, addr, usdc(), usdc_per_weth);
+}
+
+/// `add_v2_pool` against the quote token `quote` (a USDC variant).
+fn add_v2_pool_quoted(
+    states: &DashMap<Address, PoolState>,
+    snapshots: &DashMap<Address, PoolSnapshot>,
+    addr: Address,
+    quote: Address,
+    usdc_per_weth: u64,
+) {
+    states.insert(addr, PoolState {
+        pool_address: addr, dex_type: DexType::VelodromeV2, token0: weth(), token1: quote,
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    snapshots.insert(addr, PoolSnapshot {
+        pool_address: addr, dex_type: DexType::VelodromeV2, token0: weth(), token1: quote,
+        reserve0: Some(U256::from(100u64) * U256::exp10(WETH_DECIMALS as usize)),
+        reserve1: Some(U256::from(100u64 * usdc_per_weth) * U256::exp10(USDC_DECIMALS as usize)),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+}
+
+/// Three pools priced 2000 / 2050 / 2500 USDC per WETH; returns the routes found for the first one.
+fn routes_with(config: &Config) -> Vec<(Address, Address)> {
+    routes_with_flags(config, &Arc::new(DashMap::new()))
+}
+
+fn routes_with_flags(config: &Config, fee_on_transfer_tokens: &Arc<DashMap<Address, bool>>) -> Vec<(Address, Address)> {
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2050);
+    add_v2_pool(&states, &snapshots, pool(3), 2500);
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+    find_top_routes(&updated, &states, &snapshots, fee_on_transfer_tokens, &DashMap::new(), config, &pairs(config))
+        .into_iter()
+        .map(|r| (r.buy_pool_addr, r.sell_pool_addr))
+        .collect()
+}
+
+#[test]
+fn test_blocklisted_pool_never_in_routes() {
+    let unrestricted = routes_with(&Config::default());
+    assert_eq!(unrestricted.len(), 2);
+    assert_eq!(unrestricted[0], (pool(1), pool(3)), "Widest spread ranks first");
+
+    let config = Config { pool_blocklist: vec![pool(3)], ..Config::default() };
+    let routes = routes_with(&config);
+    assert_eq!(routes, vec![(pool(1), pool(2))]);
+    assert!(routes.iter().all(|(buy, sell)| *buy != pool(3) && *sell != pool(3)));
+
+    // A blocklisted triggering pool yields no routes at all
+    let config = Config { pool_blocklist: vec![pool(1)], ..Config::default() };
+    assert!(routes_with(&config).is_empty());
+}
+
+#[test]
+fn test_allowlist_restricts_routes() {
+    let config = Config { pool_allowlist: Some(vec![pool(1), pool(3)]), ..Config::default() };
+    assert_eq!(routes_with(&config), vec![(pool(1), pool(3))]);
+
+    // Blocklist wins over allowlist
+    let config = Config { pool_allowlist: Some(vec![pool(1), pool(3)]), pool_blocklist: vec![pool(3)], ..Config::default() };
+    assert!(routes_with(&config).is_empty());
+}
+
+#[test]
+fn test_fee_on_transfer_token_excludes_routes() {
+    let flags = Arc::new(DashMap::new());
+    flags.insert(usdc(), false);
+    assert_eq!(routes_with_flags(&Config::default(), &flags).len(), 2, "Probed-clean tokens are routable");
+
+    flags.insert(usdc(), true);
+    assert!(routes_with_flags(&Config::default(), &flags).is_empty());
+}
+
+#[test]
+fn test_transfer_probe_classification() {
+    use ulp1_5::token_safety::{is_fee_on_transfer, transfer_probe_bytecode};
+
+    assert!(!is_fee_on_transfer(U256::from(1000), U256::from(1000)));
+    assert!(is_fee_on_transfer(U256::from(1000), U256::from(990)), "Fee taken on transfer");
+    assert!(is_fee_on_transfer(U256::from(1000), U256::from(1001)), "Rebasing/reflection token");
+
+    let code = transfer_probe_bytecode(usdc(), pool(9), U256::from(1000));
+    assert_eq!(*code.last().unwrap(), 0xf3, "Probe ends in RETURN");
+    assert!(code.windows(20).any(|w| w == usdc().as_bytes()));
+}
+
+fn route_between(buy: &PoolState, sell: &PoolState, zero_for_one_a: bool) -> RouteCandidate {
+    RouteCandidate {
+        buy_pool_addr: buy.pool_address, sell_pool_addr: sell.pool_address,
+        buy_dex_type: buy.dex_type, sell_dex_type: sell.dex_type,
+        token_in: weth(), token_out: usdc(),
+        buy_pool_fee: buy.uni_fee, sell_pool_fee: sell.uni_fee,
+        buy_pool_stable: buy.velo_stable, sell_pool_stable: sell.velo_stable,
+        buy_pool_factory: buy.factory, sell_pool_factory: sell.factory,
+        zero_for_one_a,
+        ..Default::default()
+    }
+}
+
+fn pool_state(addr: Address, token0: Address, token1: Address) -> PoolState {
+    PoolState {
+        pool_address: addr, dex_type: DexType::VelodromeV2, token0, token1,
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(token0 == weth()), factory: Address::zero(), created_block: None,
+    }
+}
+
+#[test]
+fn test_validate_directions_weth_token0() {
+    let buy = pool_state(pool(1), weth(), usdc());
+    let sell = pool_state(pool(2), usdc(), weth());
+    assert!(route_between(&buy, &sell, true).validate_directions(&buy, &sell).is_ok());
+    assert!(route_between(&buy, &sell, false).validate_directions(&buy, &sell).is_err(), "WETH is token0: Swap A must be zeroForOne");
+    assert!(route_between(&buy, &sell, true).validate_directions(&sell, &buy).is_err(), "Pool states swapped");
+}
+
+#[test]
+fn test_validate_directions_weth_token1() {
+    let buy = pool_state(pool(1), usdc(), weth());
+    let sell = pool_state(pool(2), weth(), usdc());
+    assert!(route_between(&buy, &sell, false).validate_directions(&buy, &sell).is_ok());
+    assert!(route_between(&buy, &sell, true).validate_directions(&buy, &sell).is_err(), "WETH is token1: Swap A must be oneForZero");
+
+    let foreign = pool_state(pool(2), weth(), Address::from_low_u64_be(0xdddd));
+    assert!(route_between(&buy, &foreign, false).validate_directions(&buy, &foreign).is_err(), "Sell pool without USDC");
+}
+
+#[test]
+fn test_route_count_capped_at_max_routes_to_evaluate() {
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    // One cheap pool and 20 progressively more expensive ones: every pair with pool 1 clears the threshold
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    for n in 2..=21 {
+        add_v2_pool(&states, &snapshots, pool(n), 2100 + 10 * n);
+    }
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+    let find = |config: &Config| find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), config, &pairs(config));
+
+    assert_eq!(find(&Config::default()).len(), 20, "0 means unlimited");
+    let routes = find(&Config { max_routes_to_evaluate: 4, ..Config::default() });
+    assert_eq!(routes.len(), 4);
+    let sells: Vec<Address> = routes.iter().map(|r| r.sell_pool_addr).collect();
+    assert_eq!(sells, vec![pool(21), pool(20), pool(19), pool(18)], "Keeps the widest spreads");
+}
+
+#[test]
+fn test_equal_profit_routes_order_by_pool_addresses() {
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    // Identical prices on every sell pool, inserted out of order: all candidates tie on estimated profit
+    add_v2_pool(&states, &snapshots, pool(0x10), 2000);
+    for n in [0x35, 0x12, 0x50, 0x21, 0x44] {
+        add_v2_pool(&states, &snapshots, pool(n), 2500);
+    }
+    let updated = snapshots.get(&pool(0x10)).unwrap().clone();
+    for _ in 0..3 {
+        let routes: Vec<(Address, Address)> = find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), &Config::default(), &pairs(&Config::default()))
+            .into_iter().map(|r| (r.buy_pool_addr, r.sell_pool_addr)).collect();
+        assert_eq!(routes, vec![
+            (pool(0x10), pool(0x12)), (pool(0x10), pool(0x21)), (pool(0x10), pool(0x35)), (pool(0x10), pool(0x44)), (pool(0x10), pool(0x50)),
+        ]);
+    }
+}
+
+#[test]
+fn test_route_pinned_to_source_block() {
+    use ulp1_5::deadline::{blocks_late, target_block};
+
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2500);
+    snapshots.get_mut(&pool(1)).unwrap().last_update_block = Some(U64::from(100));
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+    let routes = find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), &Config::default(), &pairs(&Config::default()));
+    assert_eq!(routes[0].source_block, U64::from(100), "Source block comes from the triggering pool's snapshot");
+
+    assert_eq!(target_block(U64::from(100)), U64::from(101));
+    assert_eq!(blocks_late(U64::from(100), U64::from(100)), 0);
+    assert_eq!(blocks_late(U64::from(100), U64::from(101)), 0, "Head at the target block is on time");
+    assert_eq!(blocks_late(U64::from(100), U64::from(103)), 2);
+    assert_eq!(blocks_late(U64::zero(), U64::from(103)), 0, "Unknown source block is never late");
+}
+
+/// Rescales a cached pool's reserves to `weth_depth` WETH, keeping its price.
+fn set_depth(snapshots: &DashMap<Address, PoolSnapshot>, addr: Address, weth_depth: u64) {
+    let mut snapshot = snapshots.get_mut(&addr).unwrap();
+    snapshot.reserve1 = Some(snapshot.reserve1.unwrap() / 100 * weth_depth);
+    snapshot.reserve0 = Some(U256::from(weth_depth) * U256::exp10(WETH_DECIMALS as usize));
+}
+
+#[test]
+fn test_deep_route_outranks_marginally_more_profitable_shallow_one() {
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2050); // 2.5% spread, deep
+    add_v2_pool(&states, &snapshots, pool(3), 2060); // 3.0% spread, 1 WETH deep
+    set_depth(&snapshots, pool(2), 100_000);
+    set_depth(&snapshots, pool(3), 1);
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+    let sell_pools = |config: &Config| -> Vec<Address> {
+        find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), config, &pairs(config))
+            .into_iter().map(|r| r.sell_pool_addr).collect()
+    };
+
+    assert_eq!(sell_pools(&Config::default()), vec![pool(3), pool(2)], "Without weights the larger spread wins");
+    let weighted = Config { route_score_profit_weight: 1.0, route_score_liquidity_weight: 1.0, ..Config::default() };
+    assert_eq!(sell_pools(&weighted), vec![pool(2), pool(3)], "Depth outweighs a 0.5% spread advantage");
+}
+
+#[test]
+fn test_young_pool_scores_lower() {
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2050);
+    let config = Config { route_score_age_weight: 1.0, route_score_mature_blocks: 1_000, ..Config::default() };
+    let mut route = route_between(&states.get(&pool(1)).unwrap(), &states.get(&pool(2)).unwrap(), true);
+    route.source_block = U64::from(10_000);
+
+    assert_eq!(score_route(&route, &states, &snapshots, &config, WETH_DECIMALS), 1.0, "Enumerated pools count as established");
+    states.get_mut(&pool(2)).unwrap().created_block = Some(U64::from(9_750));
+    assert_eq!(score_route(&route, &states, &snapshots, &config, WETH_DECIMALS), 0.25, "250 of 1000 blocks old");
+    states.get_mut(&pool(2)).unwrap().created_block = Some(U64::from(1_000));
+    assert_eq!(score_route(&route, &states, &snapshots, &config, WETH_DECIMALS), 1.0);
+}
+
+#[test]
+fn test_usdc_variants_are_never_cross_routed() {
+    use ulp1_5::state::{is_target_pair_option, AppState};
+
+    let native_usdc = Address::from_low_u64_be(0xdddd);
+    let config = Config { usdc_addresses: vec![native_usdc], ..Config::default() };
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2500);
+    add_v2_pool_quoted(&states, &snapshots, pool(3), native_usdc, 2100);
+    add_v2_pool_quoted(&states, &snapshots, pool(4), native_usdc, 2600);
+    let routes_from = |p: Address, config: &Config| -> Vec<RouteCandidate> {
+        let updated = snapshots.get(&p).unwrap().clone();
+        find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), config, &pairs(config))
+    };
+
+    let bridged = routes_from(pool(1), &config);
+    assert_eq!(bridged.iter().map(|r| (r.buy_pool_addr, r.sell_pool_addr)).collect::<Vec<_>>(), vec![(pool(1), pool(2))]);
+    assert_eq!(bridged[0].token_out, usdc());
+    let native = routes_from(pool(3), &config);
+    assert_eq!(native.iter().map(|r| (r.buy_pool_addr, r.sell_pool_addr)).collect::<Vec<_>>(), vec![(pool(3), pool(4))]);
+    assert_eq!(native[0].token_out, native_usdc, "The route carries the pool's own USDC variant");
+    assert!(routes_from(pool(3), &Config::default()).is_empty(), "Unconfigured variants are not quote tokens");
+
+    let state = AppState::new(Config { usdc_addresses: vec![native_usdc, usdc()], ..AppState::new_for_test().config.clone() });
+    assert_eq!(state.quote_tokens, vec![usdc(), native_usdc]);
+    assert!(is_target_pair_option(native_usdc, weth(), state.target_tokens()));
+    assert!(is_target_pair_option(weth(), usdc(), state.target_tokens()));
+    assert!(!is_target_pair_option(usdc(), native_usdc, state.target_tokens()), "USDC variants do not pair with each other");
+}
+
+#[test]
+fn test_routes_found_for_non_weth_pair() {
+    use ulp1_5::state::AppState;
+
+    let op = Address::from_low_u64_be(0x0f);
+    let op_usdc = TradingPair { base: op, quote: usdc(), base_decimals: 18, quote_decimals: USDC_DECIMALS };
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    // OP/USDC pools in both token orders: 1.50 and 1.60 USDC per OP
+    for (addr, token0, token1, reserve0, reserve1) in [
+        (pool(1), op, usdc(), U256::exp10(24), U256::from(1_500_000u64) * U256::exp10(6)),
+        (pool(2), usdc(), op, U256::from(1_600_000u64) * U256::exp10(6), U256::exp10(24)),
+    ] {
+        states.insert(addr, PoolState {
+            pool_address: addr, dex_type: DexType::VelodromeV2, token0, token1,
+            uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(false), factory: Address::zero(), created_block: None,
+        });
+        snapshots.insert(addr, PoolSnapshot {
+            pool_address: addr, dex_type: DexType::VelodromeV2, token0, token1, reserve0: Some(reserve0), reserve1: Some(reserve1),
+            sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+            ..Default::default()
+        });
+    }
+    add_v2_pool(&states, &snapshots, pool(3), 2000);
+    let config = Config::default();
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+
+    let routes = find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), &config, &[op_usdc]);
+    assert_eq!(routes.iter().map(|r| (r.buy_pool_addr, r.sell_pool_addr)).collect::<Vec<_>>(), vec![(pool(1), pool(2))], "The WETH/USDC pool is not part of the pair");
+    assert_eq!((routes[0].token_in, routes[0].token_out), (op, usdc()));
+    assert!(routes[0].zero_for_one_a, "OP is token0 of the buy pool");
+    assert!(find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), &config, &pairs(&config)).is_empty(), "OP/USDC is not configured");
+
+    let state = AppState::new_for_test();
+    assert_eq!(state.trading_pairs(), vec![TradingPair { base: state.weth_address, quote: state.usdc_address, base_decimals: 18, quote_decimals: 6 }]);
+}
+
+#[test]
+fn test_spot_price_far_from_twap_suppresses_route() {
+    use ulp1_5::twap::{spot_twap_deviation_bps, twap_tick};
+    use ulp1_5::univ3_math::get_sqrt_ratio_at_tick;
+
+    // ~2000 USDC per WETH is tick -200_311; the spot was pushed to ~2500 (tick -198_080) within the block
+    let (stable_tick, spot_tick) = (-200_311, -198_080);
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    states.insert(pool(2), PoolState {
+        pool_address: pool(2), dex_type: DexType::UniswapV3, token0: weth(), token1: usdc(),
+        uni_fee: Some(500), tick_spacing: Some(10), velo_stable: None, t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    snapshots.insert(pool(2), PoolSnapshot {
+        pool_address: pool(2), dex_type: DexType::UniswapV3, token0: weth(), token1: usdc(), reserve0: None, reserve1: None,
+        sqrt_price_x96: Some(get_sqrt_ratio_at_tick(spot_tick).unwrap()), tick: Some(spot_tick), liquidity: Some(10u128.pow(21)),
+        twap_tick: Some(stable_tick), last_update_block: None, last_update_timestamp: None,
+    });
+    assert!(spot_twap_deviation_bps(&snapshots.get(&pool(2)).unwrap()).unwrap() > 2_000);
+
+    let routes = |config: &Config, updated: Address| {
+        let updated = snapshots.get(&updated).unwrap().clone();
+        find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &DashMap::new(), config, &pairs(config))
+    };
+    assert_eq!(routes(&Config::default(), pool(1)).len(), 1, "The check is off by default");
+    let config = Config { max_spot_twap_deviation_bps: 100, ..Config::default() };
+    assert!(routes(&config, pool(1)).is_empty(), "The manipulated pool is not a comparison pool");
+    assert!(routes(&config, pool(2)).is_empty(), "The manipulated pool's own update is not acted on");
+
+    // Once the spot is back at the TWAP the discrepancy is real again
+    snapshots.get_mut(&pool(2)).unwrap().twap_tick = Some(spot_tick);
+    assert_eq!(routes(&config, pool(1)).len(), 1);
+
+    // Average tick of observe([60, 0]) rounds towards negative infinity
+    assert_eq!(twap_tick(&[0, 600], 60), Some(10));
+    assert_eq!(twap_tick(&[0, -601], 60), Some(-11));
+    assert_eq!(twap_tick(&[0, -600], 60), Some(-10));
+    assert_eq!(twap_tick(&[0, 600], 0), None);
+    assert_eq!(twap_tick(&[600], 60), None);
+}
+
+#[tokio::test]
+async fn test_swap_refreshes_twap_tick() {
+    use ethers::abi::{encode, Token};
+    use ethers::providers::Provider;
+    use ethers::types::{Bytes, I256};
+    use ulp1_5::state::AppState;
+    use ulp1_5::twap::refresh_twap_tick;
+
+    let snapshot = || PoolSnapshot {
+        pool_address: pool(2), dex_type: DexType::UniswapV3, token0: weth(), token1: usdc(), twap_tick: Some(-5), last_update_block: Some(U64::from(10)),
+        ..Default::default()
+    };
+    // observe([60, 0]) returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s)
+    let observed = |newer: i64| -> Bytes {
+        let ticks = vec![Token::Int(U256::zero()), Token::Int(I256::from(newer).into_raw())];
+        encode(&[Token::Array(ticks), Token::Array(vec![Token::Uint(U256::zero()); 2])]).into()
+    };
+    let (provider, mock) = Provider::mocked();
+    let provider = Arc::new(provider);
+
+    let state = AppState::new(Config { twap_window_secs: 60, max_spot_twap_deviation_bps: 100, ..Config::default() });
+    state.pool_snapshots.insert(pool(2), snapshot());
+    mock.push::<Bytes, Bytes>(observed(600)).unwrap();
+    refresh_twap_tick(provider.clone(), &state, pool(2), Some(U64::from(10))).await;
+    assert_eq!(state.pool_snapshots.get(&pool(2)).unwrap().twap_tick, Some(10), "Read at the swap's block");
+
+    mock.push::<Bytes, Bytes>(observed(1_200)).unwrap();
+    refresh_twap_tick(provider.clone(), &state, pool(2), Some(U64::from(9))).await;
+    assert_eq!(state.pool_snapshots.get(&pool(2)).unwrap().twap_tick, Some(10), "A read older than the snapshot is dropped");
+
+    // With the deviation check off nothing is read (an empty mock would fail the call and clear the TWAP)
+    let state = AppState::new(Config { twap_window_secs: 60, ..Config::default() });
+    state.pool_snapshots.insert(pool(2), snapshot());
+    refresh_twap_tick(provider.clone(), &state, pool(2), Some(U64::from(11))).await;
+    assert_eq!(state.pool_snapshots.get(&pool(2)).unwrap().twap_tick, Some(-5));
+}
+
+#[tokio::test]
+async fn test_reverting_decimals_assumed_and_optionally_skipped() {
+    use ethers::abi::{encode, Token};
+    use ethers::providers::{JsonRpcError, MockResponse, Provider};
+    use ethers::types::Bytes;
+    use ulp1_5::state::AppState;
+    use ulp1_5::token_safety::{ensure_token_decimals, fetch_token_decimals, TokenDecimals};
+
+    let reverted = || MockResponse::Error(JsonRpcError { code: 3, message: "execution reverted".into(), data: None });
+    let state = AppState::new(Config { default_token_decimals: 18, ..Config::default() });
+    let (provider, mock) = Provider::mocked();
+    let provider = Arc::new(provider);
+    // Answered last-in first-out: WETH's decimals() returns 18, the USDC stand-in reverts
+    mock.push_response(reverted());
+    mock.push::<Bytes, Bytes>(encode(&[Token::Uint(U256::from(18u64))]).into()).unwrap();
+    ensure_token_decimals(provider.clone(), &state, [weth(), usdc()]).await;
+    assert_eq!(*state.token_decimals.get(&weth()).unwrap(), TokenDecimals { decimals: 18, assumed: false });
+    assert_eq!(*state.token_decimals.get(&usdc()).unwrap(), TokenDecimals { decimals: 18, assumed: true });
+
+    // An RPC failure says nothing about the token and is not cached as assumed
+    mock.push_response(MockResponse::Error(JsonRpcError { code: -32603, message: "upstream timeout".into(), data: None }));
+    assert_eq!(fetch_token_decimals(provider.clone(), pool(9), 18).await, None);
+
+    let states = Arc::new(DashMap::new());
+    let snapshots = Arc::new(DashMap::new());
+    add_v2_pool(&states, &snapshots, pool(1), 2000);
+    add_v2_pool(&states, &snapshots, pool(2), 2050);
+    let updated = snapshots.get(&pool(1)).unwrap().clone();
+    let routes = |config: &Config| find_top_routes(&updated, &states, &snapshots, &Arc::new(DashMap::new()), &state.token_decimals, config, &pairs(config));
+    assert_eq!(routes(&Config::default()).len(), 1, "Assumed decimals are routed unless configured otherwise");
+    assert!(routes(&Config { skip_unknown_decimals: true, ..Config::default() }).is_empty());
+}
@@ -0,0 +1,74 @@
+// tests/pool_cache_test.rs
+// Versioned pool cache files: round trip, and discarding of caches written under another schema.
+
+use ethers::types::{Address, U256, U64};
+use std::path::PathBuf;
+use ulp1_5::pool_cache::{load_pool_cache, write_pool_cache, PoolCacheLoad, SCHEMA_VERSION};
+use ulp1_5::state::{AppState, DexType, PoolSnapshot, PoolState};
+
+fn cache_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ulp1_5-test-{}-{}.json", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn state_with_pool(pool: Address) -> AppState {
+    let state = AppState::new_for_test();
+    let (weth, usdc) = (state.weth_address, state.usdc_address);
+    state.insert_pool(
+        PoolState {
+            pool_address: pool, dex_type: DexType::VelodromeV2, token0: weth, token1: usdc,
+            uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true), factory: Address::zero(), created_block: Some(U64::from(7)),
+        },
+        PoolSnapshot {
+            pool_address: pool, dex_type: DexType::VelodromeV2, token0: weth, token1: usdc,
+            reserve0: Some(U256::exp10(20)), reserve1: Some(U256::from(200_000_000_000u64)),
+            sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: Some(U64::from(9)), last_update_timestamp: Some(1_700_000_000),
+            ..Default::default()
+        },
+    );
+    state
+}
+
+#[test]
+fn test_cache_round_trip() {
+    let path = cache_path("round-trip");
+    let pool = Address::from_low_u64_be(0x1);
+    assert_eq!(write_pool_cache(&state_with_pool(pool), path.to_str().unwrap()).unwrap(), 1);
+
+    let PoolCacheLoad::Loaded(pools) = load_pool_cache(path.to_str().unwrap()) else { panic!("Current-version cache must load") };
+    assert_eq!(pools.len(), 1);
+    assert_eq!((pools[0].state.pool_address, pools[0].state.created_block), (pool, Some(U64::from(7))));
+    assert_eq!((pools[0].snapshot.reserve0, pools[0].snapshot.last_update_block), (Some(U256::exp10(20)), Some(U64::from(9))));
+
+    // Startup re-fetches cached pools like backfilled ones
+    let discovered = pools[0].discovered();
+    assert_eq!((discovered.pool, discovered.dex_type, discovered.velo_stable, discovered.created_block), (pool, DexType::VelodromeV2, Some(false), Some(U64::from(7))));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_older_schema_is_discarded() {
+    let path = cache_path("old-schema");
+    write_pool_cache(&state_with_pool(Address::from_low_u64_be(0x1)), path.to_str().unwrap()).unwrap();
+    // Same file as an older release would have labelled it
+    let current = format!("\"schema_version\":{}", SCHEMA_VERSION);
+    let old = std::fs::read_to_string(&path).unwrap().replace(&current, &format!("\"schema_version\":{}", SCHEMA_VERSION - 1));
+    std::fs::write(&path, old).unwrap();
+
+    match load_pool_cache(path.to_str().unwrap()) {
+        PoolCacheLoad::Discarded(reason) => assert!(reason.contains("schema version"), "{}", reason),
+        other => panic!("Expected the old cache to be discarded, got {:?}", other),
+    }
+    assert!(!path.exists(), "The stale cache is removed");
+    assert!(matches!(load_pool_cache(path.to_str().unwrap()), PoolCacheLoad::Missing), "Next start fetches from chain");
+}
+
+#[test]
+fn test_garbage_is_discarded() {
+    let path = cache_path("garbage");
+    std::fs::write(&path, "{\"pools\": 3}").unwrap();
+    assert!(matches!(load_pool_cache(path.to_str().unwrap()), PoolCacheLoad::Discarded(_)));
+    assert!(!path.exists());
+}
+// END OF FILE: tests/pool_cache_test.rs
@@ -0,0 +1,77 @@
+// tests/preflight_test.rs
+// Offline tests for the startup contract preflight (mocked provider).
+
+use ethers::abi::{encode, Token};
+use ethers::providers::{MockProvider, Provider};
+use ethers::types::{Address, Bytes, U256};
+use std::sync::Arc;
+use ulp1_5::config::Config;
+
+fn config() -> Config {
+    Config {
+        uniswap_v3_factory_addr: Address::from_low_u64_be(0xf1),
+        quoter_v2_address: Address::from_low_u64_be(0xf2),
+        velodrome_v2_factory_addr: Address::from_low_u64_be(0xf3),
+        balancer_vault_address: Address::from_low_u64_be(0xf4),
+        weth_address: Address::from_low_u64_be(0xeeee),
+        usdc_address: Address::from_low_u64_be(0xcccc),
+        weth_decimals: 18,
+        ..Config::default()
+    }
+}
+
+fn word(token: Token) -> Bytes {
+    Bytes::from(encode(&[token]))
+}
+
+/// Queues `eth_call` results in call order (the mock pops the most recently pushed response first).
+fn push_calls(mock: &MockProvider, results: Vec<Bytes>) {
+    for result in results.into_iter().rev() {
+        mock.push::<Bytes, _>(result).unwrap();
+    }
+}
+
+fn healthy_results(config: &Config) -> Vec<Bytes> {
+    let quote = Bytes::from(encode(&[Token::Uint(U256::from(3_000)), Token::Uint(U256::one() << 96), Token::Uint(U256::zero()), Token::Uint(U256::from(80_000))]));
+    vec![
+        word(Token::Address(Address::from_low_u64_be(0x9001))), // getPool
+        quote,
+        word(Token::Uint(U256::from(250))), // allPoolsLength
+        word(Token::Address(config.weth_address)), // vault WETH()
+    ]
+}
+
+#[tokio::test]
+async fn test_preflight_passes_when_every_contract_answers() {
+    let config = config();
+    let (provider, mock) = Provider::mocked();
+    push_calls(&mock, healthy_results(&config));
+    config.preflight_contracts(Arc::new(provider)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_preflight_flags_bad_addresses() {
+    let config = config();
+    let (provider, mock) = Provider::mocked();
+    let mut results = healthy_results(&config);
+    results[1] = Bytes::new(); // No contract at the quoter address: empty return data
+    results[3] = word(Token::Address(Address::from_low_u64_be(0xbad))); // Vault of another chain
+    push_calls(&mock, results);
+
+    let err = config.preflight_contracts(Arc::new(provider)).await.unwrap_err().to_string();
+    assert!(err.starts_with("2 configured contract(s) failed preflight"), "{}", err);
+    assert!(err.contains(&format!("QuoterV2 {:?}", config.quoter_v2_address)), "{}", err);
+    assert!(err.contains(&format!("Balancer vault {:?}", config.balancer_vault_address)), "{}", err);
+    assert!(!err.contains("UniswapV3 factory") && !err.contains("Velodrome factory"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_preflight_skips_unconfigured_velodrome_factory() {
+    let config = Config { velodrome_v2_factory_addr: Address::zero(), ..config() };
+    let (provider, mock) = Provider::mocked();
+    let mut results = healthy_results(&config);
+    results.remove(2);
+    push_calls(&mock, results);
+    config.preflight_contracts(Arc::new(provider)).await.unwrap();
+}
+// END OF FILE: tests/preflight_test.rs
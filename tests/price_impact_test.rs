@@ -0,0 +1,83 @@
+// tests/price_impact_test.rs
+// Price impact of route legs and the loan cap it implies, on offline Velodrome state.
+
+mod common;
+
+use common::{usdc, weth};
+use ethers::types::{Address, I256, U256};
+use ulp1_5::config::Config;
+use ulp1_5::mempool::VELO_DEFAULT_VOLATILE_FEE_BPS;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::price_impact::{max_loan_within_impact, route_price_impact_bps};
+use ulp1_5::state::{AppState, DexType, PoolSnapshot, PoolState};
+use ulp1_5::velo_math::{get_amount_out, VeloPoolReserves};
+
+const SHALLOW_POOL: u64 = 0x1;
+const DEEP_POOL: u64 = 0x2;
+const MAX_IMPACT_BPS: u64 = 100;
+
+fn usdc_units(amount: u64) -> U256 { U256::from(amount) * U256::exp10(6) }
+
+fn insert_velo_pool(state: &AppState, pool: u64, reserve_weth: U256, reserve_usdc: U256) {
+    let pool = Address::from_low_u64_be(pool);
+    state.pool_states.insert(pool, PoolState {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: weth(), token1: usdc(),
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    });
+    state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: weth(), token1: usdc(),
+        reserve0: Some(reserve_weth), reserve1: Some(reserve_usdc),
+        sqrt_price_x96: None, tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+}
+
+/// 10 WETH at 2100 USDC (shallow, buy side) against 1000 WETH at 2000 USDC (deep, sell side).
+fn state() -> AppState {
+    let state = AppState::new(Config { weth_address: weth(), usdc_address: usdc(), weth_decimals: 18, usdc_decimals: 6, ..Config::default() });
+    insert_velo_pool(&state, SHALLOW_POOL, U256::exp10(19), usdc_units(21_000));
+    insert_velo_pool(&state, DEEP_POOL, U256::exp10(21), usdc_units(2_000_000));
+    state
+}
+
+fn route() -> RouteCandidate {
+    common::route(Address::from_low_u64_be(SHALLOW_POOL), DexType::VelodromeV2, Address::from_low_u64_be(DEEP_POOL), DexType::VelodromeV2)
+}
+
+/// Gross WETH profit of the route for `loan`, from the pool curves alone (no gas).
+fn local_profit(loan: U256) -> I256 {
+    let buy = VeloPoolReserves::new(U256::exp10(19), usdc_units(21_000), 18, 6, false);
+    let sell = VeloPoolReserves::new(U256::exp10(21), usdc_units(2_000_000), 18, 6, false);
+    let usdc_out = get_amount_out(&buy, loan, true, VELO_DEFAULT_VOLATILE_FEE_BPS).unwrap();
+    let weth_back = get_amount_out(&sell, usdc_out, false, VELO_DEFAULT_VOLATILE_FEE_BPS).unwrap();
+    I256::from_raw(weth_back) - I256::from_raw(loan)
+}
+
+#[test]
+fn test_shallow_pool_caps_loan_by_impact_before_profit() {
+    let (state, route) = (state(), route());
+    let (min, max) = (U256::exp10(15), U256::exp10(19));
+    assert!(route_price_impact_bps(&state, &route, max).unwrap() > MAX_IMPACT_BPS);
+
+    let cap = max_loan_within_impact(&state, &route, min, max, MAX_IMPACT_BPS).expect("min is within the limit");
+    assert!(cap > min && cap < max);
+    assert!(route_price_impact_bps(&state, &route, cap).unwrap() <= MAX_IMPACT_BPS);
+    assert!(route_price_impact_bps(&state, &route, cap + U256::exp10(14)).unwrap() > MAX_IMPACT_BPS);
+    // The 5% price gap still pays more for a larger loan: impact, not profit, is what binds
+    assert!(local_profit(cap) > I256::zero());
+    assert!(local_profit(cap * 2) > local_profit(cap));
+}
+
+#[test]
+fn test_impact_cap_bounds() {
+    let (state, route) = (state(), route());
+    // A loan range within the limit is left as is
+    assert_eq!(max_loan_within_impact(&state, &route, U256::exp10(14), U256::exp10(15), MAX_IMPACT_BPS), Some(U256::exp10(15)));
+    // Even the minimum moves the shallow pool by more than the limit
+    assert_eq!(max_loan_within_impact(&state, &route, U256::exp10(18), U256::exp10(19), MAX_IMPACT_BPS), None);
+    // Unmodelled pools leave the range unbounded
+    let unknown = RouteCandidate { buy_pool_addr: Address::from_low_u64_be(0x99), ..route };
+    assert_eq!(route_price_impact_bps(&state, &unknown, U256::exp10(18)), None);
+    assert_eq!(max_loan_within_impact(&state, &unknown, U256::exp10(18), U256::exp10(19), MAX_IMPACT_BPS), Some(U256::exp10(19)));
+}
+// END OF FILE: tests/price_impact_test.rs
@@ -0,0 +1,58 @@
+// tests/rebalance_test.rs
+// Offline tests for the WETH rebalance gating (mocked provider; the swap path runs on Anvil in integration_test).
+
+mod common;
+
+use common::{usdc, weth};
+use ethers::abi::{encode, Token};
+use ethers::providers::Provider;
+use ethers::types::{Address, Bytes, U256};
+use std::sync::Arc;
+use ulp1_5::config::Config;
+use ulp1_5::rebalance::{min_output, rebalance_tokens, rebalance_weth, wrap_shortfall};
+use ulp1_5::state::{AppState, PauseReason};
+use ulp1_5::transaction::NonceManager;
+
+fn app_state(floor: u64) -> AppState {
+    AppState::new(Config { weth_address: weth(), usdc_address: usdc(), min_weth_working_balance: U256::from(floor), ..Config::default() })
+}
+
+#[tokio::test]
+async fn test_rebalance_skipped_when_disabled_paused_or_above_floor() -> eyre::Result<()> {
+    let wallet = Address::from_low_u64_be(0xa11ce);
+    let nonce_manager = NonceManager::new(wallet);
+    let (provider, mock) = Provider::mocked();
+    let client = Arc::new(provider);
+
+    // Neither a zero floor nor a paused bot reads the balance (no response is queued)
+    assert!(rebalance_weth(client.clone(), &app_state(0), &nonce_manager, wallet).await?.is_none());
+    let paused = app_state(1_000);
+    paused.set_paused(PauseReason::Admin, true);
+    assert!(rebalance_weth(client.clone(), &paused, &nonce_manager, wallet).await?.is_none());
+
+    // At the floor: one balance read, no swap
+    mock.push::<Bytes, Bytes>(encode(&[Token::Uint(U256::from(1_000u64))]).into())?;
+    assert!(rebalance_weth(client, &app_state(1_000), &nonce_manager, wallet).await?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_rebalance_tokens_and_min_output() {
+    assert_eq!(rebalance_tokens(&app_state(1)), vec![usdc()]);
+    let weth_quote = AppState::new(Config { usdc_address: weth(), ..app_state(1).config.clone() });
+    assert!(rebalance_tokens(&weth_quote).is_empty(), "WETH is never sold for WETH");
+
+    assert_eq!(min_output(U256::from(10_000u64), 50), U256::from(9_950u64));
+    assert_eq!(min_output(U256::from(10_000u64), 20_000), U256::zero());
+}
+
+#[test]
+fn test_wrap_shortfall_keeps_gas_reserve() {
+    let eth = |milli: u64| U256::from(milli) * U256::exp10(15);
+    assert_eq!(wrap_shortfall(U256::zero(), eth(1_000), eth(10), eth(2)), Some(eth(10)), "Only ETH: wrap the whole requirement");
+    assert_eq!(wrap_shortfall(eth(4), eth(1_000), eth(10), eth(2)), Some(eth(6)), "Only the shortfall is wrapped");
+    assert_eq!(wrap_shortfall(eth(10), eth(1_000), eth(10), eth(2)), None, "Nothing missing");
+    assert_eq!(wrap_shortfall(U256::zero(), eth(11), eth(10), eth(2)), None, "Wrapping would eat into the gas reserve");
+    assert_eq!(wrap_shortfall(U256::zero(), eth(12), eth(10), eth(2)), Some(eth(10)));
+}
+// END OF FILE: tests/rebalance_test.rs
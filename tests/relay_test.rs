@@ -0,0 +1,96 @@
+// tests/relay_test.rs
+// Private relay request signing and concurrent submission (no network: requests are built, not sent).
+
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature, TxHash};
+use ethers::utils::keccak256;
+use eyre::eyre;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ulp1_5::config::Config;
+use ulp1_5::relay::{build_relay_request, first_accepted, private_tx_body, RelayAuthScheme, RelayConfig, FLASHBOTS_SIGNATURE_HEADER};
+
+// Well-known test key (first Anvil account)
+const KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+fn relay(auth_scheme: RelayAuthScheme) -> RelayConfig {
+    RelayConfig { url: "https://relay.example/".into(), auth_scheme }
+}
+
+#[tokio::test]
+async fn test_flashbots_signature_header_is_verifiable() {
+    let wallet: LocalWallet = KEY.parse().unwrap();
+    let body = private_tx_body("https://relay.example/", "0x02f8");
+    assert_eq!(String::from_utf8(body.clone()).unwrap(), r#"{"id":1,"jsonrpc":"2.0","method":"eth_sendPrivateRawTransaction","params":["0x02f8"]}"#);
+
+    let http = reqwest::Client::new();
+    let request = build_relay_request(&http, &relay(RelayAuthScheme::Flashbots), &wallet, body.clone()).await.unwrap();
+    let header = request.headers().get(FLASHBOTS_SIGNATURE_HEADER).expect("signature header").to_str().unwrap();
+    let (address, signature) = header.split_once(':').unwrap();
+    assert_eq!(Address::from_str(address).unwrap(), wallet.address());
+
+    // The signer is recovered from the EIP-191 message "0x" + hex(keccak256(body))
+    let signature = Signature::from_str(signature).unwrap();
+    let message = format!("0x{}", hex::encode(keccak256(&body)));
+    assert_eq!(signature.recover(message.as_str()).unwrap(), wallet.address());
+    assert!(signature.recover("0xdeadbeef").unwrap() != wallet.address(), "Bound to this body");
+    assert_eq!(request.body().and_then(|b| b.as_bytes()), Some(body.as_slice()), "Signed bytes are the sent bytes");
+
+    let plain = build_relay_request(&http, &relay(RelayAuthScheme::None), &wallet, body).await.unwrap();
+    assert!(plain.headers().get(FLASHBOTS_SIGNATURE_HEADER).is_none());
+}
+
+#[test]
+fn test_private_relays_keep_order_and_per_relay_scheme() {
+    let config = Config {
+        private_rpc_url: Some("https://rpc.flashbots.net".into()), private_rpc_auth: RelayAuthScheme::Flashbots,
+        secondary_private_rpc_url: Some(String::new()),
+        ..Config::default()
+    };
+    assert_eq!(config.private_relays(), vec![RelayConfig { url: "https://rpc.flashbots.net".into(), auth_scheme: RelayAuthScheme::Flashbots }]);
+    assert_eq!("Flashbots".parse::<RelayAuthScheme>().unwrap(), RelayAuthScheme::Flashbots);
+    assert!("eip712".parse::<RelayAuthScheme>().is_err());
+    assert!(private_tx_body("https://eth-mainnet.g.alchemy.com/v2/x", "0x02").starts_with(br#"{"id":1,"jsonrpc":"2.0","method":"alchemy_sendPrivateTransaction""#));
+}
+
+fn relay_at(url: &str) -> RelayConfig {
+    RelayConfig { url: url.into(), auth_scheme: RelayAuthScheme::None }
+}
+
+#[tokio::test]
+async fn test_concurrent_relays_return_the_first_acceptance() {
+    let relays = vec![relay_at("https://slow.example/"), relay_at("https://fast.example/")];
+    let received = Arc::new(Mutex::new(Vec::new()));
+    // The second relay answers first; the slow one still gets its send through
+    let log = received.clone();
+    let (relay, tx_hash) = first_accepted(relays.clone(), move |relay| {
+        let log = log.clone();
+        async move {
+            if relay.url.contains("slow") {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            log.lock().unwrap().push(relay.url.clone());
+            Ok(TxHash::from_low_u64_be(relay.url.len() as u64))
+        }
+    }).await.unwrap();
+    assert_eq!(relay.url, "https://fast.example/");
+    assert_eq!(tx_hash, TxHash::from_low_u64_be("https://fast.example/".len() as u64));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(*received.lock().unwrap(), vec!["https://fast.example/".to_string(), "https://slow.example/".to_string()], "Every relay received the transaction");
+
+    // A fast rejection does not end the race
+    let (relay, _) = first_accepted(relays.clone(), |relay| async move {
+        if relay.url.contains("fast") {
+            return Err(eyre!("rejected"));
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(TxHash::zero())
+    }).await.unwrap();
+    assert_eq!(relay.url, "https://slow.example/");
+
+    let err = first_accepted(relays, |_| async { Err::<TxHash, _>(eyre!("rejected")) }).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("rejected"));
+    assert!(first_accepted(vec![], |_| async { Ok(TxHash::zero()) }).await.is_err());
+}
+// END OF FILE: tests/relay_test.rs
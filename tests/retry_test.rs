@@ -0,0 +1,48 @@
+// tests/retry_test.rs
+// Jittered exponential backoff shared by the pool state fetches.
+
+use eyre::eyre;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use ulp1_5::retry::{backoff_delay, with_retries};
+
+/// Fails its first `failures` calls, then returns the attempt number.
+struct FlakyRpc {
+    calls: AtomicU32,
+    failures: u32,
+}
+
+impl FlakyRpc {
+    async fn call(&self) -> eyre::Result<u32> {
+        let attempt = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if attempt <= self.failures { Err(eyre!("connection reset (attempt {})", attempt)) } else { Ok(attempt) }
+    }
+}
+
+#[tokio::test]
+async fn test_flaky_fetch_succeeds_on_third_attempt() {
+    let rpc = FlakyRpc { calls: AtomicU32::new(0), failures: 2 };
+    assert_eq!(with_retries(3, 1, "test", || rpc.call()).await.unwrap(), 3);
+    assert_eq!(rpc.calls.load(Ordering::SeqCst), 3);
+
+    let rpc = FlakyRpc { calls: AtomicU32::new(0), failures: 2 };
+    let err = with_retries(1, 1, "test", || rpc.call()).await.unwrap_err();
+    assert!(err.to_string().contains("attempt 2"), "Last error is returned: {}", err);
+    assert_eq!(rpc.calls.load(Ordering::SeqCst), 2, "One try plus one retry");
+
+    let rpc = FlakyRpc { calls: AtomicU32::new(0), failures: 1 };
+    assert!(with_retries(0, 1, "test", || rpc.call()).await.is_err(), "Zero retries means a single attempt");
+}
+
+#[test]
+fn test_backoff_doubles_with_jitter_and_caps() {
+    for attempt in 1..=4u32 {
+        let ceiling = 200 * 2u64.pow(attempt - 1);
+        for _ in 0..20 {
+            let delay = backoff_delay(200, attempt);
+            assert!(delay >= Duration::from_millis(ceiling / 2) && delay <= Duration::from_millis(ceiling), "{:?} for attempt {}", delay, attempt);
+        }
+    }
+    assert!(backoff_delay(200, 40) <= Duration::from_secs(10), "Delay is capped");
+    assert_eq!(backoff_delay(0, 3), Duration::ZERO);
+}
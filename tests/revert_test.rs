@@ -0,0 +1,41 @@
+// tests/revert_test.rs
+// Offline tests for revert reason decoding (mocked provider, no Anvil required).
+
+use ethers::abi::{encode, Token};
+use ethers::providers::{JsonRpcError, MockResponse, Provider};
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256};
+use serde_json::json;
+use ulp1_5::revert::{decode_revert_data, decode_revert_reason, ERROR_STRING_SELECTOR, PANIC_SELECTOR};
+
+fn with_selector(selector: [u8; 4], tokens: &[Token]) -> Vec<u8> {
+    [selector.to_vec(), encode(tokens)].concat()
+}
+
+#[test]
+fn test_decode_revert_data_standard_errors() {
+    let error = with_selector(ERROR_STRING_SELECTOR, &[Token::String("BAL#102".into())]);
+    assert_eq!(decode_revert_data(&error), "Error(\"BAL#102\")");
+
+    let panic = with_selector(PANIC_SELECTOR, &[Token::Uint(U256::from(0x11))]);
+    assert_eq!(decode_revert_data(&panic), "Panic(0x11): arithmetic overflow or underflow");
+
+    assert!(decode_revert_data(&[]).starts_with("empty revert data"), "Executor reverts carry no data");
+    assert!(decode_revert_data(&[0xde, 0xad, 0xbe, 0xef]).starts_with("unknown error selector 0xdeadbeef"));
+}
+
+#[tokio::test]
+async fn test_decode_revert_reason_replays_via_eth_call() -> eyre::Result<()> {
+    let (provider, mock) = Provider::mocked();
+    let data = with_selector(ERROR_STRING_SELECTOR, &[Token::String("BAL#528".into())]);
+    mock.push_response(MockResponse::Error(JsonRpcError {
+        code: 3,
+        message: "execution reverted: BAL#528".into(),
+        data: Some(json!(format!("0x{}", hex::encode(&data)))),
+    }));
+    let tx: TypedTransaction = TransactionRequest::new().to(Address::from_low_u64_be(0xba1)).into();
+    assert_eq!(decode_revert_reason(&provider, &tx, None).await, "Error(\"BAL#528\")");
+
+    mock.push::<Bytes, _>(Bytes::new())?;
+    assert!(decode_revert_reason(&provider, &tx, None).await.starts_with("replay did not revert"));
+    Ok(())
+}
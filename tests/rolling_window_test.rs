@@ -0,0 +1,17 @@
+// tests/rolling_window_test.rs
+// Offline tests for the shared rolling sample window.
+
+use ulp1_5::rolling_window::RollingWindow;
+
+#[test]
+fn test_full_window_drops_oldest_sample() {
+    let window = RollingWindow::<i64, 3>::default();
+    for sample in 1..=3 {
+        window.record(sample);
+    }
+    let samples = window.record_then(4, |samples| samples.iter().copied().collect::<Vec<_>>());
+    assert_eq!(samples, vec![2, 3, 4]);
+    assert_eq!(window.p95(3), Some(4));
+    assert_eq!(window.p95(4), None, "Only 3 samples fit");
+    assert_eq!(window.p95(0), None, "0 disables the percentile");
+}
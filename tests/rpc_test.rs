@@ -0,0 +1,48 @@
+// tests/rpc_test.rs
+// Read/write endpoint split: which URL each client talks to, and which endpoint state fetches are timed against.
+
+use ethers::prelude::{LocalWallet, Middleware};
+use std::time::Duration;
+use ulp1_5::config::Config;
+use ulp1_5::latency::MIN_LATENCY_SAMPLES;
+use ulp1_5::rpc::RpcClients;
+use ulp1_5::state::AppState;
+
+const HTTP_URL: &str = "http://127.0.0.1:8545/";
+const READ_URL: &str = "http://127.0.0.1:8546/";
+const WRITE_URL: &str = "http://127.0.0.1:8547/";
+
+fn wallet() -> LocalWallet {
+    "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse().unwrap()
+}
+
+#[test]
+fn test_endpoints_default_to_http_rpc_url() {
+    let config = Config { http_rpc_url: HTTP_URL.to_string(), ..Config::default() };
+    assert_eq!((config.read_endpoint(), config.write_endpoint()), (HTTP_URL, HTTP_URL));
+    let clients = RpcClients::new(&config, wallet()).unwrap();
+    assert!(!clients.is_split(), "One endpoint, one client");
+    assert_eq!(clients.write.provider().url().as_str(), HTTP_URL);
+}
+
+#[tokio::test]
+async fn test_submission_uses_write_endpoint_and_fetches_use_read_endpoint() {
+    let config = Config {
+        http_rpc_url: HTTP_URL.to_string(), read_rpc_url: Some(READ_URL.to_string()), write_rpc_url: Some(WRITE_URL.to_string()),
+        ..Config::default()
+    };
+    let clients = RpcClients::new(&config, wallet()).unwrap();
+    assert!(clients.is_split());
+    assert_eq!(clients.read.provider().url().as_str(), READ_URL);
+    assert_eq!(clients.write.provider().url().as_str(), WRITE_URL);
+
+    // Pool-state fetches are timed (and their timeout adapted) against the read endpoint only
+    let state = AppState::new(config);
+    for _ in 0..MIN_LATENCY_SAMPLES {
+        state.timed_fetch(tokio::time::sleep(Duration::from_millis(1))).await.unwrap();
+    }
+    assert!(state.rpc_latency.p95(READ_URL).is_some());
+    assert!(state.rpc_latency.p95(WRITE_URL).is_none());
+    assert!(state.rpc_latency.p95(HTTP_URL).is_none());
+}
+// END OF FILE: tests/rpc_test.rs
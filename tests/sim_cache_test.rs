@@ -0,0 +1,89 @@
+// tests/sim_cache_test.rs
+// Per-block reuse of optimal loan searches (the search itself is stubbed).
+
+mod common;
+
+use ethers::types::{Address, I256, U256, U64};
+use std::sync::atomic::{AtomicU32, Ordering};
+use ulp1_5::metrics::Metrics;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::sim_cache::{LegQuoteCache, LoanSearchResult, SimulationCache, SimulationKey};
+use ulp1_5::state::{DexType, PoolSnapshot};
+
+fn route(source_block: u64) -> RouteCandidate {
+    RouteCandidate { source_block: U64::from(source_block), ..common::route(Address::from_low_u64_be(1), DexType::VelodromeV2, Address::from_low_u64_be(2), DexType::VelodromeV2) }
+}
+
+fn snapshot(reserve0: u64) -> PoolSnapshot {
+    PoolSnapshot {
+        pool_address: Address::from_low_u64_be(1), dex_type: DexType::VelodromeV2,
+        token0: Address::from_low_u64_be(0xeeee), token1: Address::from_low_u64_be(0xcccc),
+        reserve0: Some(U256::from(reserve0)), reserve1: Some(U256::from(1_000u64)), sqrt_price_x96: None, tick: None,
+        liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    }
+}
+
+async fn search(cache: &SimulationCache, metrics: &Metrics, key: SimulationKey, runs: &AtomicU32) -> LoanSearchResult {
+    cache.get_or_search(key, metrics, || async {
+        runs.fetch_add(1, Ordering::SeqCst);
+        tokio::task::yield_now().await;
+        Ok(Some((U256::exp10(18), I256::from(1_000), None)))
+    }).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_identical_checks_in_one_block_search_once() {
+    let (cache, metrics, runs) = (SimulationCache::default(), Metrics::default(), AtomicU32::new(0));
+    let (buy, sell) = (snapshot(100), snapshot(200));
+    let key = || SimulationKey::new(&route(50), Some(&buy), Some(&sell)).unwrap();
+
+    let first = search(&cache, &metrics, key(), &runs).await;
+    let second = search(&cache, &metrics, key(), &runs).await;
+    assert_eq!(first, second);
+    assert_eq!(runs.load(Ordering::SeqCst), 1, "Second check reuses the search");
+    assert_eq!(metrics.simulation_cache_hits.load(Ordering::Relaxed), 1);
+
+    // Concurrent checks wait for the search in flight
+    let concurrent_key = || SimulationKey::new(&route(51), Some(&buy), Some(&sell)).unwrap();
+    tokio::join!(search(&cache, &metrics, concurrent_key(), &runs), search(&cache, &metrics, concurrent_key(), &runs));
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+    // A later swap in the same block changes the snapshot, so it searches again
+    let moved = snapshot(101);
+    search(&cache, &metrics, SimulationKey::new(&route(50), Some(&moved), Some(&sell)).unwrap(), &runs).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_new_block_invalidates_and_unknown_block_is_not_cached() {
+    let (cache, metrics, runs) = (SimulationCache::default(), Metrics::default(), AtomicU32::new(0));
+    let buy = snapshot(100);
+    search(&cache, &metrics, SimulationKey::new(&route(50), Some(&buy), None).unwrap(), &runs).await;
+    search(&cache, &metrics, SimulationKey::new(&route(51), Some(&buy), None).unwrap(), &runs).await;
+    assert_eq!(cache.len(), 2);
+
+    cache.prune(U64::from(51));
+    assert_eq!(cache.len(), 1, "Block 50 entry dropped");
+    search(&cache, &metrics, SimulationKey::new(&route(50), Some(&buy), None).unwrap(), &runs).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    assert!(SimulationKey::new(&route(0), Some(&buy), None).is_none(), "No source block, no caching");
+}
+
+#[test]
+fn test_leg_quotes_are_kept_per_loan_amount_for_the_block() {
+    let quotes = LegQuoteCache::default();
+    let loan = U256::exp10(18);
+    quotes.record(&route(50), loan, (U256::from(2_000u64), U256::from(1_005u64)));
+    quotes.record(&route(50), loan * 2, (U256::from(3_900u64), U256::from(1_990u64)));
+    assert_eq!(quotes.get(&route(50), loan), Some((U256::from(2_000u64), U256::from(1_005u64))));
+    assert_eq!(quotes.get(&route(50), loan * 3), None, "Loan amount never quoted");
+    assert_eq!(quotes.get(&route(51), loan), None, "Route detected in another block");
+
+    quotes.record(&route(0), loan, (U256::one(), U256::one()));
+    assert_eq!(quotes.len(), 2, "Unknown source block is not recorded");
+    quotes.prune(U64::from(51));
+    assert!(quotes.is_empty());
+}
+// END OF FILE: tests/sim_cache_test.rs
@@ -0,0 +1,200 @@
+// tests/simulation_test.rs
+// Offline checks for the flash loan bundle simulation helpers.
+
+use ethers::providers::{JsonRpcError, MockResponse, Provider};
+use ethers::types::{Address, Bytes, I256, U256};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use ulp1_5::config::Config;
+use ulp1_5::flash_loan::balancer_flash_loan_fee;
+use ulp1_5::mempool::VELO_DEFAULT_VOLATILE_FEE_BPS;
+use ulp1_5::simulation::{
+    best_probe, break_even_search, competitive_priority_fee, flash_loan_probe_bytecode, implied_priority_fee_gwei, intrinsic_gas, net_of_gas, probe_loan_amounts, quote_divergence_bps, loan_search_convergence, simulate_via_executor_callstatic, validate_simulation_consistency,
+    ExecutorCallStatic, LoanSearchConvergence, ProfitCurve, SearchBoundary,
+};
+use ulp1_5::velo_math::{get_amount_out, VeloPoolReserves};
+
+#[test]
+fn test_flash_loan_probe_layout_and_intrinsic_gas() {
+    let (weth, executor, to) = (Address::repeat_byte(0xee), Address::repeat_byte(0xe1), Address::repeat_byte(0xba));
+    let code = flash_loan_probe_bytecode(weth, executor, to);
+    assert_eq!(*code.last().unwrap(), 0xf3, "Probe ends in RETURN");
+    let count = |a: Address| code.windows(20).filter(|w| *w == a.as_bytes()).count();
+    assert_eq!(count(weth), 2, "Loan token balance read before and after");
+    assert_eq!(count(executor), 2);
+    assert_eq!(count(to), 1, "Single flash loan call");
+
+    assert_eq!(intrinsic_gas(&[]), 21_000);
+    assert_eq!(intrinsic_gas(&[0x00, 0x01, 0xff]), 21_000 + 4 + 16 + 16);
+}
+
+#[test]
+fn test_loan_search_convergence_flags_boundary_and_curve_shape() {
+    let curve = |profits: &[i64]| -> Vec<(U256, I256)> {
+        // Reversed input order: the analysis must sort by amount
+        profits.iter().enumerate().rev().map(|(i, p)| (U256::from(i + 1), I256::from(*p))).collect()
+    };
+    let shape = |profits: &[i64]| loan_search_convergence(&curve(profits)).unwrap();
+
+    assert_eq!(shape(&[-5, 3, 9, 4, -2]), LoanSearchConvergence { simulations: 5, boundary: SearchBoundary::Interior, curve: ProfitCurve::Unimodal });
+    assert_eq!(shape(&[1, 2, 2, 7]).boundary, SearchBoundary::Max, "Still rising at the max: range too narrow");
+    assert_eq!(shape(&[1, 2, 2, 7]).curve, ProfitCurve::Increasing);
+    assert_eq!(shape(&[8, 3, -1]).boundary, SearchBoundary::Min);
+    assert_eq!(shape(&[8, 3, -1]).curve, ProfitCurve::Decreasing);
+    assert_eq!(shape(&[1, 5, 2, 6, 3]).curve, ProfitCurve::Irregular);
+    assert_eq!(shape(&[4, 4, 4]), LoanSearchConvergence { simulations: 3, boundary: SearchBoundary::Min, curve: ProfitCurve::Flat });
+    assert_eq!(shape(&[4]).boundary, SearchBoundary::Interior, "A single sample has no range to be pinned to");
+    assert!(loan_search_convergence(&[]).is_none());
+}
+
+#[tokio::test]
+async fn test_break_even_search_finds_profit_crossing() {
+    // Profit = loan - 3.7 ETH of fixed costs: positive from 3.7 ETH + 1 wei
+    let eth = U256::exp10(18);
+    let costs = eth * 37 / 10;
+    let profit = |loan: U256| async move { Ok(I256::from_raw(loan) - I256::from_raw(costs)) };
+    let tolerance = U256::exp10(14);
+
+    let break_even = break_even_search(eth, eth * 10, tolerance, profit).await.unwrap().unwrap();
+    assert!(break_even > costs && break_even - costs <= tolerance, "break-even {} not within tolerance above {}", break_even, costs);
+
+    assert_eq!(break_even_search(eth * 4, eth * 10, tolerance, profit).await.unwrap(), Some(eth * 4), "Profitable from the range minimum");
+    assert_eq!(break_even_search(eth, eth * 3, tolerance, profit).await.unwrap(), None, "Never profitable in range");
+}
+
+#[test]
+fn test_quote_divergence_bps() {
+    let quoter = U256::from(1_000_000u64);
+    assert_eq!(quote_divergence_bps(quoter, quoter), 0);
+    assert_eq!(quote_divergence_bps(U256::from(1_001_000u64), quoter), 10, "Local above QuoterV2");
+    assert_eq!(quote_divergence_bps(U256::from(990_000u64), quoter), 100, "Local below QuoterV2");
+    assert_eq!(quote_divergence_bps(U256::one(), U256::zero()), u64::MAX);
+    assert_eq!(quote_divergence_bps(U256::zero(), U256::zero()), 0);
+}
+
+/// Probe return data: `(ok, gross profit, gas used)` as three words.
+fn probe_output(ok: bool, gross_profit: U256, gas_used: u64) -> Bytes {
+    let mut out = vec![0u8; 96];
+    U256::from(ok as u8).to_big_endian(&mut out[..32]);
+    gross_profit.to_big_endian(&mut out[32..64]);
+    U256::from(gas_used).to_big_endian(&mut out[64..96]);
+    out.into()
+}
+
+#[tokio::test]
+async fn test_executor_callstatic_decodes_probe_result() {
+    let (provider, mock) = Provider::mocked();
+    let (weth, executor) = (Address::repeat_byte(0xee), Address::repeat_byte(0xe1));
+    let calldata = Bytes::from(vec![0x01; 4]);
+    let call = || simulate_via_executor_callstatic(&provider, weth, executor, &calldata);
+
+    mock.push::<Bytes, _>(probe_output(true, U256::exp10(16), 180_000)).unwrap();
+    let outcome = call().await.unwrap().unwrap();
+    assert_eq!(outcome, ExecutorCallStatic { succeeded: true, gross_profit_wei: I256::exp10(16), gas_used: U256::from(180_000 + intrinsic_gas(&calldata)) });
+
+    mock.push::<Bytes, _>(probe_output(false, U256::zero(), 90_000)).unwrap();
+    assert!(!call().await.unwrap().unwrap().succeeded, "A reverted flash loan is reported, not an error");
+
+    mock.push_response(MockResponse::Error(JsonRpcError { code: -32602, message: "state override unsupported".into(), data: None }));
+    assert_eq!(call().await.unwrap(), None);
+
+    // 200k gas (no buffer) at 10 gwei = 0.002 ETH
+    let config = Config { gas_limit_buffer_percentage: 0, ..Config::default() };
+    let net = net_of_gas(&config, 18, I256::exp10(16), U256::from(200_000), U256::from(10_000_000_000u64)).unwrap();
+    assert_eq!(net, I256::exp10(16) - I256::from(2) * I256::exp10(15));
+}
+
+#[tokio::test]
+async fn test_divergent_callstatic_aborts_before_encoding() {
+    let (provider, mock) = Provider::mocked();
+    let (weth, executor) = (Address::repeat_byte(0xee), Address::repeat_byte(0xe1));
+    let calldata = Bytes::from(vec![0x01; 4]);
+    // 10 WETH loan repaying 10.005 WETH; the quoters expect 10.025 WETH back (0.02 WETH gross)
+    let (out_a, out_b, repay) = (U256::from(20_000_000_000u64), U256::from(10_025u64) * U256::exp10(15), U256::from(10_005u64) * U256::exp10(15));
+
+    // The executor agrees to within 1 bps of the final output
+    mock.push::<Bytes, _>(probe_output(true, U256::from(19_500u64) * U256::exp10(12), 180_000)).unwrap();
+    let agreeing = simulate_via_executor_callstatic(&provider, weth, executor, &calldata).await.unwrap().unwrap();
+    validate_simulation_consistency(out_a, out_b, repay, Some(&agreeing), 10).unwrap();
+
+    // A stale quote: the executor only makes 0.0005 WETH, 0.19% less output than quoted
+    mock.push::<Bytes, _>(probe_output(true, U256::from(5u64) * U256::exp10(14), 180_000)).unwrap();
+    let divergent = simulate_via_executor_callstatic(&provider, weth, executor, &calldata).await.unwrap().unwrap();
+    let err = validate_simulation_consistency(out_a, out_b, repay, Some(&divergent), 10).unwrap_err();
+    assert!(err.to_string().contains("diverges 19 bps"), "{}", err);
+    assert!(validate_simulation_consistency(out_a, out_b, repay, Some(&divergent), 0).is_ok(), "Zero tolerance disables the comparison");
+    assert!(validate_simulation_consistency(out_a, out_b, repay, Some(&ExecutorCallStatic { succeeded: false, ..divergent }), 10).is_ok(), "Reverts are rejected by the profit check");
+
+    // A zero leg output aborts with or without an executor result
+    assert!(validate_simulation_consistency(U256::zero(), out_b, repay, None, 0).is_err());
+    assert!(validate_simulation_consistency(out_a, U256::zero(), repay, Some(&agreeing), 10).is_err());
+}
+
+/// Net profit of buying USDC on a shallow 2100 pool and selling on a deep 2000 pool with a 0.5% flash-loan
+/// fee and a fixed 0.001 WETH gas cost, after bidding `bps` of the swap profit as priority fee.
+fn competitive_net_profit(loan: U256, bps: u64) -> I256 {
+    let buy = VeloPoolReserves::new(U256::exp10(20), U256::from(210_000u64) * U256::exp10(6), 18, 6, false);
+    let sell = VeloPoolReserves::new(U256::exp10(22), U256::from(20_000_000u64) * U256::exp10(6), 18, 6, false);
+    let usdc_out = get_amount_out(&buy, loan, true, VELO_DEFAULT_VOLATILE_FEE_BPS).unwrap();
+    let weth_back = get_amount_out(&sell, usdc_out, false, VELO_DEFAULT_VOLATILE_FEE_BPS).unwrap();
+    let swap_profit = I256::from_raw(weth_back) - I256::from_raw(loan);
+    let flash_fee = balancer_flash_loan_fee(loan, U256::exp10(15) * 5).unwrap();
+    let bid = competitive_priority_fee(swap_profit, bps).unwrap();
+    swap_profit - I256::from_raw(flash_fee) - I256::exp10(15) - I256::from_raw(bid)
+}
+
+/// The loan a search over 0.05..5 WETH (0.05 WETH steps) selects, and its profit.
+fn best_loan(bps: u64) -> (U256, I256) {
+    (1..=100u64).map(|i| U256::from(i) * U256::exp10(16) * 5)
+        .map(|loan| (loan, competitive_net_profit(loan, bps)))
+        .fold((U256::zero(), I256::min_value()), |best, sample| if sample.1 > best.1 { sample } else { best })
+}
+
+#[test]
+fn test_competition_shrinks_selected_loan_and_profit() {
+    assert_eq!(competitive_priority_fee(I256::exp10(16), 2_500).unwrap(), U256::exp10(15) * 25 / 10);
+    assert_eq!(competitive_priority_fee(-I256::exp10(16), 2_500).unwrap(), U256::zero(), "No bid on a losing trade");
+
+    let (loan_free, profit_free) = best_loan(0);
+    let (loan_contested, profit_contested) = best_loan(5_000);
+    assert!(profit_free > I256::zero() && profit_contested > I256::zero());
+    assert!(loan_contested < loan_free, "{} !< {}", loan_contested, loan_free);
+    assert!(profit_contested < profit_free);
+
+    // 0.002 WETH over a 200k gas limit is a 10 gwei priority fee
+    let gwei = implied_priority_fee_gwei(&Config::default(), 18, U256::exp10(15) * 2, U256::from(200_000u64));
+    assert!((gwei - 10.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_concurrent_probes_find_the_sequential_optimum() {
+    let in_flight = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    // Later probes answer sooner, so concurrent completion order differs from the probe order
+    let probe = |loan: U256| {
+        let (in_flight, peak) = (&in_flight, &peak);
+        async move {
+            peak.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20 - loan.as_u64() / U256::exp10(17).as_u64())).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            if loan == U256::exp10(17) * 13 { return Err(eyre::eyre!("probe RPC failed")); }
+            Ok(competitive_net_profit(loan, 0))
+        }
+    };
+    let amounts: Vec<U256> = (1..=20u64).map(|i| U256::exp10(17) * i).collect();
+
+    let sequential = probe_loan_amounts(amounts.clone(), 1, probe).await;
+    assert_eq!(peak.swap(0, Ordering::SeqCst), 1);
+    let concurrent = probe_loan_amounts(amounts.clone(), 4, probe).await;
+    assert_eq!(peak.load(Ordering::SeqCst), 4, "Bounded by the configured concurrency");
+
+    assert_eq!(concurrent.iter().map(|(amount, _)| *amount).collect::<Vec<_>>(), amounts, "Results keep probe order");
+    let ok = |results: &[(U256, eyre::Result<I256>)]| results.iter().filter_map(|(a, p)| p.as_ref().ok().map(|p| (*a, *p))).collect::<Vec<_>>();
+    let (sequential, concurrent) = (ok(&sequential), ok(&concurrent));
+    assert_eq!(concurrent.len(), 19, "The failed probe is dropped");
+    assert_eq!(sequential, concurrent);
+    let best = best_probe(&concurrent).unwrap();
+    assert_eq!(Some(best), best_probe(&sequential));
+    assert!(best.1 > I256::zero() && best.0 > U256::exp10(17) && best.0 < U256::exp10(18) * 2, "Interior optimum {:?}", best);
+    assert_eq!(best_probe(&[(U256::one(), I256::one()), (U256::from(2), I256::one())]), Some((U256::one(), I256::one())), "Ties keep the smaller loan");
+}
@@ -0,0 +1,100 @@
+// tests/slippage_test.rs
+// Realized leg outputs parsed from a synthetic arb receipt and compared with their quotes per DEX.
+
+mod common;
+
+use common::{usdc, weth};
+use ethers::abi::{encode, Token};
+use ethers::types::{Address, Log, TransactionReceipt, H256, I256, U256};
+use std::sync::atomic::Ordering;
+use ulp1_5::config::Config;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::slippage::{corrected_output, realized_leg_output, record_leg_slippage, LegSlippage};
+use ulp1_5::state::{AppState, DexType, PoolState};
+use ulp1_5::{UNI_V3_SWAP_TOPIC, VELO_AERO_SWAP_TOPIC};
+
+fn uni_pool() -> Address { Address::from_low_u64_be(0x1) }
+fn velo_pool() -> Address { Address::from_low_u64_be(0x2) }
+fn executor() -> H256 { H256::from(Address::from_low_u64_be(0xe1)) }
+
+/// UniV3 `Swap(sender, recipient, int256 amount0, int256 amount1, uint160, uint128, int24)`.
+fn uni_swap_log(amount0: I256, amount1: I256) -> Log {
+    Log {
+        address: uni_pool(),
+        topics: vec![*UNI_V3_SWAP_TOPIC, executor(), executor()],
+        data: encode(&[Token::Int(amount0.into_raw()), Token::Int(amount1.into_raw()), Token::Uint(U256::one() << 96), Token::Uint(U256::exp10(18)), Token::Int(U256::zero())]).into(),
+        ..Log::default()
+    }
+}
+
+/// Velodrome `Swap(sender, to, amount0In, amount1In, amount0Out, amount1Out)`.
+fn velo_swap_log(amounts: [u64; 4]) -> Log {
+    Log {
+        address: velo_pool(),
+        topics: vec![*VELO_AERO_SWAP_TOPIC, executor(), executor()],
+        data: encode(&amounts.map(|a| Token::Uint(U256::from(a)))).into(),
+        ..Log::default()
+    }
+}
+
+fn pool_state(pool: Address, dex_type: DexType) -> PoolState {
+    PoolState {
+        pool_address: pool, dex_type, token0: weth(), token1: usdc(),
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true), factory: Address::zero(), created_block: None,
+    }
+}
+
+/// Buys 2000 USDC with 1 WETH on the UniV3 pool, sells them for WETH on the Velodrome pool.
+fn route() -> RouteCandidate {
+    common::route(uni_pool(), DexType::UniswapV3, velo_pool(), DexType::VelodromeV2)
+}
+
+fn receipt() -> TransactionReceipt {
+    TransactionReceipt {
+        logs: vec![
+            // 1 WETH in, 1990 USDC out
+            uni_swap_log(I256::exp10(18), -I256::from(1_990_000_000u64)),
+            // 1990 USDC in, 1.0015 WETH out
+            velo_swap_log([0, 1_990_000_000, 1_001_500_000_000_000_000, 0]),
+        ],
+        ..TransactionReceipt::default()
+    }
+}
+
+#[test]
+fn test_realized_outputs_parsed_from_receipt() {
+    let receipt = receipt();
+    assert_eq!(realized_leg_output(&receipt, uni_pool(), DexType::UniswapV3, false), Some(U256::from(1_990_000_000u64)));
+    assert_eq!(realized_leg_output(&receipt, uni_pool(), DexType::UniswapV3, true), None, "Token0 went into the pool");
+    assert_eq!(realized_leg_output(&receipt, velo_pool(), DexType::VelodromeV2, true), Some(U256::from(1_001_500_000_000_000_000u64)));
+    // Another pool's Swap, or the other DEX's layout, is not read
+    assert_eq!(realized_leg_output(&receipt, Address::from_low_u64_be(0x3), DexType::VelodromeV2, true), None);
+    assert_eq!(realized_leg_output(&receipt, uni_pool(), DexType::VelodromeV2, true), None);
+}
+
+#[test]
+fn test_leg_errors_recorded_per_dex() {
+    let state = AppState::new(Config { weth_address: weth(), usdc_address: usdc(), ..Config::default() });
+    state.pool_states.insert(uni_pool(), pool_state(uni_pool(), DexType::UniswapV3));
+    state.pool_states.insert(velo_pool(), pool_state(velo_pool(), DexType::VelodromeV2));
+
+    // Quoted 2000 USDC and 1.0005 WETH
+    let legs = record_leg_slippage(&state, &route(), &receipt(), U256::from(2_000_000_000u64), U256::from(1_000_500_000_000_000_000u64));
+    assert_eq!(legs, vec![
+        LegSlippage { pool: uni_pool(), dex_type: DexType::UniswapV3, quoted_out: U256::from(2_000_000_000u64), realized_out: U256::from(1_990_000_000u64) },
+        LegSlippage { pool: velo_pool(), dex_type: DexType::VelodromeV2, quoted_out: U256::from(1_000_500_000_000_000_000u64), realized_out: U256::from(1_001_500_000_000_000_000u64) },
+    ]);
+    assert_eq!(legs[0].error_bps(), Some(50), "UniV3 delivered 0.5% less than quoted");
+    assert_eq!(legs[1].error_bps(), Some(-9), "Velodrome delivered more than quoted");
+    assert_eq!(state.metrics.uni_v3_output_shortfall_bps.load(Ordering::Relaxed), 50);
+    assert_eq!(state.metrics.velo_output_shortfall_bps.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn test_velo_correction_reduces_quotes() {
+    assert_eq!(corrected_output(U256::from(1_000_000u64), 0), U256::from(1_000_000u64));
+    assert_eq!(corrected_output(U256::from(1_000_000u64), 25), U256::from(997_500u64));
+    assert_eq!(corrected_output(U256::from(1_000_000u64), 20_000), U256::zero(), "Capped at the whole output");
+    assert_eq!(corrected_output(U256::MAX, 1), U256::MAX / U256::from(10_000u64) * U256::from(9_999u64));
+}
+// END OF FILE: tests/slippage_test.rs
@@ -0,0 +1,241 @@
+// tests/state_test.rs
+// Offline tests for AppState cache updates (no Anvil required).
+
+use ethers::abi::{encode, Token};
+use ethers::providers::{MockProvider, Provider};
+use ethers::types::{Address, Bytes, I256, U256, U64};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use ulp1_5::config::Config;
+use ulp1_5::state::{fetch_univ3_pool_state, reserve_update_rejection, AppState, DexType, PoolSnapshot, PoolState};
+
+#[test]
+fn test_update_velo_snapshot_reserves() {
+    let app_state = AppState::new(Config::default());
+    let pool = Address::from_low_u64_be(0x7e10);
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: None, reserve1: None, sqrt_price_x96: None, tick: None, liquidity: None,
+        last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    });
+
+    assert!(app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(2_000), Some(U64::from(7)), Some(1_700_000_000)));
+    let snapshot = app_state.pool_snapshots.get(&pool).unwrap().clone();
+    assert_eq!(snapshot.reserve0, Some(U256::from(1_000)));
+    assert_eq!(snapshot.reserve1, Some(U256::from(2_000)));
+    assert_eq!(snapshot.last_update_block, Some(U64::from(7)));
+    assert_eq!(snapshot.last_update_timestamp, Some(1_700_000_000));
+
+    assert!(!app_state.update_pool_snapshot(Address::from_low_u64_be(0xdead), U256::one(), U256::one(), None, None), "Unknown pool is not inserted");
+    assert_eq!(app_state.pool_snapshots.len(), 1);
+}
+
+#[test]
+fn test_implausible_reserve_updates_keep_prior_snapshot() {
+    let app_state = AppState::new(Config { max_price_jump_bps: 2_000, ..Config::default() });
+    let pool = Address::from_low_u64_be(0x7e10);
+    let prior = PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: Some(U256::from(1_000)), reserve1: Some(U256::from(2_000)), sqrt_price_x96: None, tick: None, liquidity: None,
+        last_update_block: Some(U64::from(7)), last_update_timestamp: None,
+        ..Default::default()
+    };
+    app_state.pool_snapshots.insert(pool, prior.clone());
+
+    assert!(!app_state.update_pool_snapshot(pool, U256::zero(), U256::from(2_000), Some(U64::from(8)), None), "Zero reserve rejected");
+    let kept = app_state.pool_snapshots.get(&pool).unwrap().clone();
+    assert_eq!((kept.reserve0, kept.reserve1, kept.last_update_block), (prior.reserve0, prior.reserve1, prior.last_update_block), "Prior snapshot retained");
+    // Price 2.0 -> 3.0 is a 50% jump, over the 20% limit
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(3_000), Some(U64::from(8)), None));
+    assert_eq!(app_state.metrics.snapshot_updates_rejected.load(Ordering::Relaxed), 2);
+
+    // 2.0 -> 2.2 is within the limit
+    assert!(app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(2_200), Some(U64::from(9)), None));
+    assert_eq!(app_state.pool_snapshots.get(&pool).unwrap().reserve1, Some(U256::from(2_200)));
+
+    // Without a limit only zero reserves are rejected; without prior reserves there is no price to compare
+    assert_eq!(reserve_update_rejection(&prior, U256::from(1_000), U256::from(9_000), 0), None);
+    assert!(reserve_update_rejection(&prior, U256::from(1_000), U256::zero(), 0).is_some());
+    let empty = PoolSnapshot { reserve0: None, reserve1: None, ..prior };
+    assert_eq!(reserve_update_rejection(&empty, U256::from(1), U256::from(9_000), 2_000), None);
+}
+
+#[test]
+fn test_confirmed_reserve_jump_is_accepted() {
+    let app_state = AppState::new(Config { max_price_jump_bps: 2_000, ..Config::default() });
+    let pool = Address::from_low_u64_be(0x7e10);
+    app_state.pool_snapshots.insert(pool, PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: Some(U256::from(1_000)), reserve1: Some(U256::from(2_000)), sqrt_price_x96: None, tick: None, liquidity: None,
+        last_update_block: Some(U64::from(7)), last_update_timestamp: None,
+        ..Default::default()
+    });
+    let reserve1 = |app_state: &AppState| app_state.pool_snapshots.get(&pool).unwrap().reserve1;
+
+    // The price really moved 2.0 -> 3.0: the first reading is held back, the second one confirms it
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(3_000), Some(U64::from(8)), None));
+    assert!(app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(3_050), Some(U64::from(9)), None), "Pool must not freeze");
+    assert_eq!(reserve1(&app_state), Some(U256::from(3_050)));
+
+    // Readings that disagree with each other keep being rejected until two agree
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(6_000), Some(U64::from(10)), None));
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(12_000), Some(U64::from(11)), None));
+    assert_eq!(reserve1(&app_state), Some(U256::from(3_050)));
+    assert!(app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(12_000), Some(U64::from(12)), None));
+
+    // A plausible reading in between drops the pending jump
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(30_000), Some(U64::from(13)), None));
+    assert!(app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(12_100), Some(U64::from(14)), None));
+    assert!(!app_state.update_pool_snapshot(pool, U256::from(1_000), U256::from(30_000), Some(U64::from(15)), None), "Needs a fresh confirmation");
+    assert_eq!(app_state.metrics.snapshot_updates_rejected.load(Ordering::Relaxed), 5);
+}
+
+#[test]
+fn test_snapshot_diff_reports_deltas() {
+    let older = PoolSnapshot {
+        pool_address: Address::from_low_u64_be(0x7e10), dex_type: DexType::UniswapV3, token0: Address::from_low_u64_be(1), token1: Address::from_low_u64_be(2),
+        reserve0: None, reserve1: None, sqrt_price_x96: Some(U256::from(1_000_000u64)), tick: Some(-10), liquidity: Some(500),
+        last_update_block: Some(U64::from(100)), last_update_timestamp: None,
+        ..Default::default()
+    };
+    let newer = PoolSnapshot { sqrt_price_x96: Some(U256::from(999_000u64)), tick: Some(5), liquidity: Some(800), last_update_block: Some(U64::from(103)), ..older.clone() };
+
+    let diff = older.diff(&newer);
+    assert_eq!(diff.sqrt_price_x96_delta, Some(I256::from(-1_000)));
+    assert_eq!(diff.tick_delta, Some(15));
+    assert_eq!(diff.liquidity_delta, Some(300));
+    assert_eq!(diff.blocks_elapsed, Some(3));
+    assert_eq!(diff.reserve0_delta, None, "V3 snapshots carry no reserves");
+    assert!(!diff.is_unchanged());
+    assert!(older.diff(&older).is_unchanged());
+
+    let v2_old = PoolSnapshot { reserve0: Some(U256::from(1_000)), reserve1: Some(U256::from(2_000)), sqrt_price_x96: None, tick: None, liquidity: None, ..older.clone() };
+    let v2_new = PoolSnapshot { reserve0: Some(U256::from(1_100)), reserve1: Some(U256::from(1_820)), ..v2_old.clone() };
+    let diff = v2_old.diff(&v2_new);
+    assert_eq!((diff.reserve0_delta, diff.reserve1_delta), (Some(I256::from(100)), Some(I256::from(-180))));
+}
+
+#[test]
+fn test_new_for_test_is_offline_and_empty() {
+    let app_state = AppState::new_for_test();
+    assert!(app_state.pool_states.is_empty() && app_state.pool_snapshots.is_empty() && app_state.block_timestamps.is_empty());
+    assert!(!app_state.is_paused());
+    assert_eq!(app_state.target_pair(), Some((Address::from_low_u64_be(0xcccc), Address::from_low_u64_be(0xeeee))));
+    assert_eq!((app_state.weth_decimals, app_state.usdc_decimals), (18, 6));
+}
+
+#[test]
+fn test_submitted_salts_dedup_and_expire() {
+    let app_state = AppState::new(Config { salt_expiry_blocks: 10, ..Config::default() });
+    let salt = U256::from(0x5a17u64);
+    assert!(app_state.claim_salt(salt, U64::from(100)));
+    assert!(!app_state.claim_salt(salt, U64::from(100)), "Same salt in the same block is a duplicate");
+    assert!(app_state.claim_salt(U256::from(0x5a18u64), U64::from(105)));
+
+    app_state.prune_submitted_salts(U64::from(110));
+    assert!(!app_state.claim_salt(salt, U64::from(110)), "Still within the expiry window");
+    app_state.prune_submitted_salts(U64::from(111));
+    assert!(app_state.claim_salt(salt, U64::from(111)), "Expired salts can be reused");
+    assert_eq!(app_state.submitted_salts.len(), 2);
+}
+
+/// Queues UniV3 pool `eth_call` results in call order (slot0, token0, token1, [fee], tickSpacing, liquidity).
+fn push_univ3_pool_calls(mock: &MockProvider, fee: Option<u32>) {
+    let word = |token: Token| Bytes::from(encode(&[token]));
+    let slot0 = Bytes::from(encode(&[
+        Token::Uint(U256::one() << 96), Token::Int(U256::from(5)), Token::Uint(U256::zero()), Token::Uint(U256::one()),
+        Token::Uint(U256::one()), Token::Uint(U256::zero()), Token::Bool(true),
+    ]));
+    let mut results = vec![slot0, word(Token::Address(Address::from_low_u64_be(0xeeee))), word(Token::Address(Address::from_low_u64_be(0xcccc)))];
+    results.extend(fee.map(|f| word(Token::Uint(U256::from(f)))));
+    results.extend([word(Token::Int(U256::from(10))), word(Token::Uint(U256::exp10(20)))]);
+    // The mock answers the most recently pushed response first
+    for result in results.into_iter().rev() {
+        mock.push::<Bytes, _>(result).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_univ3_fetch_skips_fee_call_when_fee_is_known() -> eyre::Result<()> {
+    let (pool, factory, weth) = (Address::from_low_u64_be(0x9001), Address::from_low_u64_be(0xf1), Address::from_low_u64_be(0xeeee));
+
+    let (provider, mock) = Provider::mocked();
+    push_univ3_pool_calls(&mock, Some(3000));
+    let (state, snapshot) = fetch_univ3_pool_state(pool, factory, None, weth, 0, Arc::new(provider)).await?;
+    assert_eq!(state.uni_fee, Some(3000), "Fee read from the pool");
+    assert_eq!(state.tick_spacing, Some(10));
+    assert_eq!(snapshot.liquidity, Some(10u128.pow(20)));
+
+    // Fee from the PoolCreated event: one call fewer, so the queued responses only line up without fee()
+    let (provider, mock) = Provider::mocked();
+    push_univ3_pool_calls(&mock, None);
+    let (state, snapshot) = fetch_univ3_pool_state(pool, factory, Some(500), weth, 0, Arc::new(provider)).await?;
+    assert_eq!(state.uni_fee, Some(500));
+    assert_eq!((state.token0, state.t0_is_weth), (weth, Some(true)));
+    assert_eq!((snapshot.tick, snapshot.liquidity), (Some(5), Some(10u128.pow(20))));
+    Ok(())
+}
+
+fn velo_weth_pool(app_state: &AppState, low: u64, weth_reserve: u64) -> (PoolState, PoolSnapshot) {
+    let (pool, other) = (Address::from_low_u64_be(low), Address::from_low_u64_be(0x70c0));
+    let state = PoolState {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: app_state.weth_address, token1: other,
+        uni_fee: None, tick_spacing: None, velo_stable: Some(false), t0_is_weth: Some(true),
+        factory: Address::zero(), created_block: None,
+    };
+    let snapshot = PoolSnapshot {
+        pool_address: pool, dex_type: DexType::VelodromeV2, token0: app_state.weth_address, token1: other,
+        reserve0: Some(U256::exp10(18) * weth_reserve), reserve1: Some(U256::exp10(18)), sqrt_price_x96: None,
+        tick: None, liquidity: None, last_update_block: None, last_update_timestamp: None,
+        ..Default::default()
+    };
+    (state, snapshot)
+}
+
+#[test]
+fn test_insert_pool_past_cap_evicts_least_liquid() {
+    let mut app_state = AppState::new_for_test();
+    app_state.config.max_monitored_pools = 2;
+    for (low, depth) in [(0x1, 50), (0x2, 10)] {
+        let (state, snapshot) = velo_weth_pool(&app_state, low, depth);
+        assert!(app_state.insert_pool(state, snapshot));
+    }
+
+    let (state, snapshot) = velo_weth_pool(&app_state, 0x3, 30);
+    assert!(app_state.insert_pool(state, snapshot), "Deeper pool replaces the shallowest");
+    assert_eq!(app_state.pool_states.len(), 2);
+    assert!(!app_state.pool_states.contains_key(&Address::from_low_u64_be(0x2)));
+    assert!(!app_state.pool_snapshots.contains_key(&Address::from_low_u64_be(0x2)));
+
+    let (state, snapshot) = velo_weth_pool(&app_state, 0x4, 5);
+    assert!(!app_state.insert_pool(state, snapshot), "Shallower pool is refused");
+    assert_eq!(app_state.pool_states.len(), 2);
+    assert_eq!(app_state.pool_snapshots.len(), 2);
+
+    let (state, snapshot) = velo_weth_pool(&app_state, 0x1, 1);
+    assert!(app_state.insert_pool(state, snapshot), "Refresh of a cached pool is not subject to the cap");
+    assert_eq!(app_state.metrics.pools_evicted_at_cap.load(Ordering::Relaxed), 1);
+    assert_eq!(app_state.metrics.pools_refused_at_cap.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_concurrent_inserts_never_overshoot_cap() {
+    let mut app_state = AppState::new_for_test();
+    app_state.config.max_monitored_pools = 8;
+    std::thread::scope(|scope| {
+        for thread in 0..4u64 {
+            let app_state = &app_state;
+            scope.spawn(move || {
+                for n in 1..=50u64 {
+                    let (state, snapshot) = velo_weth_pool(app_state, thread * 100 + n, n);
+                    app_state.insert_pool(state, snapshot);
+                    assert!(app_state.pool_states.len() <= 8);
+                }
+            });
+        }
+    });
+    assert_eq!(app_state.pool_states.len(), 8);
+    assert_eq!(app_state.pool_snapshots.len(), 8);
+}
+
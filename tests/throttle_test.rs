@@ -0,0 +1,42 @@
+// tests/throttle_test.rs
+// Minimum interval between submissions, globally and per pool.
+
+mod common;
+
+use ethers::types::Address;
+use std::time::{Duration, Instant};
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::state::DexType;
+use ulp1_5::throttle::SubmissionThrottle;
+
+fn route(buy: u64, sell: u64) -> RouteCandidate {
+    common::route(Address::from_low_u64_be(buy), DexType::VelodromeV2, Address::from_low_u64_be(sell), DexType::UniswapV3)
+}
+
+#[test]
+fn test_rapid_submissions_throttled_to_one_per_window() {
+    let throttle = SubmissionThrottle::default();
+    let interval = Duration::from_millis(500);
+    let now = Instant::now();
+
+    assert!(throttle.try_claim(&route(1, 2), interval, Duration::ZERO, now).is_ok());
+    assert_eq!(throttle.try_claim(&route(3, 4), interval, Duration::ZERO, now + Duration::from_millis(100)), Err(Duration::from_millis(400)));
+    assert!(throttle.try_claim(&route(3, 4), interval, Duration::ZERO, now + interval).is_ok(), "Window elapsed");
+
+    let unthrottled = SubmissionThrottle::default();
+    assert!(unthrottled.try_claim(&route(1, 2), Duration::ZERO, Duration::ZERO, now).is_ok());
+    assert!(unthrottled.try_claim(&route(1, 2), Duration::ZERO, Duration::ZERO, now).is_ok(), "Zero interval disables the throttle");
+}
+
+#[test]
+fn test_per_pool_interval_only_throttles_shared_pools() {
+    let throttle = SubmissionThrottle::default();
+    let per_pool = Duration::from_secs(5);
+    let now = Instant::now();
+
+    assert!(throttle.try_claim(&route(1, 2), Duration::ZERO, per_pool, now).is_ok());
+    assert!(throttle.try_claim(&route(3, 4), Duration::ZERO, per_pool, now).is_ok(), "Disjoint pools are not throttled");
+    assert_eq!(throttle.try_claim(&route(5, 2), Duration::ZERO, per_pool, now + Duration::from_secs(1)), Err(Duration::from_secs(4)));
+    assert!(throttle.try_claim(&route(5, 2), Duration::ZERO, per_pool, now + per_pool).is_ok());
+}
+// END OF FILE: tests/throttle_test.rs
@@ -0,0 +1,326 @@
+// tests/transaction_test.rs
+// Offline tests for submission transaction construction (no Anvil required).
+
+mod common;
+
+use ethers::middleware::SignerMiddleware;
+use ethers::abi::{encode, Token};
+use ethers::providers::{JsonRpcError, MockProvider, MockResponse, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, Bytes, Log, TransactionReceipt, TxHash, I256, U256, U64};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use ulp1_5::accuracy::{prediction_error_bps, PROFIT_BIAS_MIN_SAMPLES};
+use ulp1_5::config::Config;
+use ulp1_5::gas::TxType;
+use ulp1_5::path_optimizer::RouteCandidate;
+use ulp1_5::state::{AppState, DexType};
+use ulp1_5::simulation::{competitive_priority_fee, implied_priority_fee_gwei, net_of_gas};
+use ulp1_5::utils::f64_to_wei;
+use ulp1_5::PROFIT_REALIZED_TOPIC;
+use ulp1_5::transaction::{assert_executor_clean, build_submission_tx, check_profit_accuracy, fetch_simulation_gas_price_gwei, is_nonce_too_low, profit_share_amount, realized_gross_profit, realized_profit_from_receipt, receipt_at_depth, submission_salt, submit_sequentially, with_priority_fee_floor, GasInfo, NonceManager};
+
+const CHAIN_ID: u64 = 10;
+
+fn gas_info() -> GasInfo {
+    GasInfo { max_fee_per_gas: U256::from(2_000_000_000u64), max_priority_fee_per_gas: U256::from(1_000_000u64) }
+}
+
+#[test]
+fn test_build_submission_tx_eip1559() {
+    let vault = Address::from_low_u64_be(0xba1);
+    let tx = build_submission_tx(TxType::Eip1559, vault, Bytes::from(vec![0xab]), U256::from(500_000), &gas_info(), U256::from(7), CHAIN_ID);
+    match tx {
+        TypedTransaction::Eip1559(req) => {
+            assert_eq!(req.max_fee_per_gas, Some(gas_info().max_fee_per_gas));
+            assert_eq!(req.max_priority_fee_per_gas, Some(gas_info().max_priority_fee_per_gas));
+            assert_eq!(req.gas, Some(U256::from(500_000)));
+            assert_eq!(req.nonce, Some(U256::from(7)));
+            assert_eq!(req.chain_id.map(|c| c.as_u64()), Some(CHAIN_ID));
+        }
+        other => panic!("Expected an EIP-1559 transaction, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_build_submission_tx_legacy() {
+    let vault = Address::from_low_u64_be(0xba1);
+    let tx = build_submission_tx(TxType::Legacy, vault, Bytes::from(vec![0xab]), U256::from(500_000), &gas_info(), U256::from(7), CHAIN_ID);
+    match tx {
+        TypedTransaction::Legacy(req) => {
+            assert_eq!(req.gas_price, Some(gas_info().max_fee_per_gas), "Legacy pays the max fee as a flat price");
+            assert_eq!(req.gas, Some(U256::from(500_000)));
+            assert_eq!(req.nonce, Some(U256::from(7)));
+            assert_eq!(req.chain_id.map(|c| c.as_u64()), Some(CHAIN_ID));
+            assert_eq!(req.data, Some(Bytes::from(vec![0xab])));
+        }
+        other => panic!("Expected a legacy transaction, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_competitive_bid_raises_submitted_priority_fee() {
+    // 10% of a 0.05 WETH swap profit over a 500k gas limit is 10 gwei per gas
+    let config = Config::default();
+    let bid_wei = competitive_priority_fee(I256::exp10(16) * I256::from(5), 1_000).unwrap();
+    let bid_per_gas = f64_to_wei(implied_priority_fee_gwei(&config, 18, bid_wei, U256::from(500_000)), 9).unwrap();
+    assert_eq!(bid_per_gas, U256::from(10_000_000_000u64));
+
+    let bid = with_priority_fee_floor(gas_info(), bid_per_gas);
+    assert_eq!(bid.max_priority_fee_per_gas, bid_per_gas);
+    assert_eq!(bid.max_fee_per_gas, gas_info().max_fee_per_gas + bid_per_gas - gas_info().max_priority_fee_per_gas, "Base-fee headroom is kept");
+    let tx = build_submission_tx(TxType::Eip1559, Address::from_low_u64_be(0xba1), Bytes::new(), U256::from(500_000), &bid, U256::zero(), CHAIN_ID);
+    match tx {
+        TypedTransaction::Eip1559(req) => assert_eq!(req.max_priority_fee_per_gas, Some(bid_per_gas)),
+        other => panic!("Expected an EIP-1559 transaction, got {:?}", other),
+    }
+
+    // A bid below the strategy's tip leaves the fees alone
+    let low = with_priority_fee_floor(gas_info(), U256::from(1_000u64));
+    assert_eq!((low.max_fee_per_gas, low.max_priority_fee_per_gas), (gas_info().max_fee_per_gas, gas_info().max_priority_fee_per_gas));
+}
+
+#[test]
+fn test_profit_share_amount_matches_bps_of_net_profit() {
+    // Simulated successful arb: executor gained 0.05 WETH, gas cost 0.001 ETH -> net 0.049
+    let realized = U256::exp10(16) * 5;
+    let gas_cost = U256::exp10(15);
+    let net = realized - gas_cost;
+    assert_eq!(profit_share_amount(realized, gas_cost, 500), net * 500 / 10_000);
+    assert_eq!(profit_share_amount(realized, gas_cost, 500), U256::from(2_450_000_000_000_000u64));
+
+    assert!(profit_share_amount(realized, gas_cost, 0).is_zero(), "Zero bps is a no-op");
+    assert!(profit_share_amount(gas_cost, realized, 500).is_zero(), "No share when gas exceeds the gain");
+    // Out-of-range bps is clamped so the share never exceeds what was realized
+    assert_eq!(profit_share_amount(realized, U256::zero(), 20_000), realized);
+}
+
+#[test]
+fn test_profit_accuracy_tracks_rolling_bias() {
+    assert_eq!(prediction_error_bps(I256::from(1_000), I256::from(800)), Some(2_000));
+    assert_eq!(prediction_error_bps(I256::from(1_000), I256::from(1_100)), Some(-1_000));
+    assert_eq!(prediction_error_bps(I256::zero(), I256::from(5)), None);
+
+    let app_state = AppState::new(Config { profit_bias_warn_bps: 1_000, ..Default::default() });
+    let metric = |name: &str| app_state.metrics.values().into_iter().find(|(n, _)| *n == name).unwrap().1;
+    // Simulation consistently predicts 25% more than realized
+    for _ in 0..PROFIT_BIAS_MIN_SAMPLES {
+        assert_eq!(check_profit_accuracy(&app_state, I256::from(4_000), I256::from(3_000)), Some(2_500));
+    }
+    assert_eq!(metric("profit_overestimate_bps"), 2_500);
+    assert_eq!(metric("profit_underestimate_bps"), 0);
+    // Underestimates pull the mean down and then below zero
+    for _ in 0..2 * PROFIT_BIAS_MIN_SAMPLES {
+        check_profit_accuracy(&app_state, I256::from(4_000), I256::from(6_000));
+    }
+    assert_eq!(metric("profit_overestimate_bps"), 0);
+    assert!(metric("profit_underestimate_bps") > 0);
+    assert_eq!(check_profit_accuracy(&app_state, I256::from(-1), I256::zero()), None, "No ratio without a positive prediction");
+}
+
+fn stale_nonce_rejection() -> MockResponse {
+    MockResponse::Error(JsonRpcError { code: -32000, message: "nonce too low: next nonce 9, tx nonce 7".into(), data: None })
+}
+
+fn signer_client(provider: Provider<MockProvider>) -> SignerMiddleware<Provider<MockProvider>, LocalWallet> {
+    let wallet: LocalWallet = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d".parse::<LocalWallet>().unwrap().with_chain_id(CHAIN_ID);
+    SignerMiddleware::new(provider, wallet)
+}
+
+#[test]
+fn test_is_nonce_too_low_matches_client_wordings() {
+    assert!(is_nonce_too_low("Public RPC submission failed: (code: -32000, message: nonce too low, data: None)"));
+    assert!(is_nonce_too_low("OldNonce, Current nonce: 9, nonce of rejected tx: 7"));
+    assert!(!is_nonce_too_low("replacement transaction underpriced"));
+    assert!(!is_nonce_too_low("insufficient funds for gas * price + value"));
+}
+
+#[tokio::test]
+async fn test_submission_recovers_from_external_nonce_bump() -> eyre::Result<()> {
+    let config = Config { nonce_resync_retries: 1, ..Config::default() };
+    let (provider, mock) = Provider::mocked();
+    let client = Arc::new(signer_client(provider));
+    let nonce_manager = NonceManager::new(client.address());
+    // The bot cached nonce 7 while another sender on the same wallet used 7 and 8
+    mock.push(U256::from(7))?;
+    assert_eq!(nonce_manager.get_next_nonce(client.clone()).await?, U256::from(7));
+
+    // Responses pop last-pushed first: rejection, then the pending count, then the accepted retry
+    let accepted = TxHash::repeat_byte(0x42);
+    mock.push(accepted)?;
+    mock.push(U256::from(9))?;
+    mock.push_response(stale_nonce_rejection());
+    let mut tx = build_submission_tx(TxType::Eip1559, Address::from_low_u64_be(0xba1), Bytes::from(vec![0xab]), U256::from(500_000), &gas_info(), U256::from(7), CHAIN_ID);
+
+    assert_eq!(submit_sequentially(&config, client.as_ref(), &nonce_manager, &mut tx).await?, accepted);
+    assert_eq!(tx.nonce(), Some(&U256::from(9)), "Retry was signed with the re-synced nonce");
+    assert_eq!(nonce_manager.get_next_nonce(client.clone()).await?, U256::from(10), "Cache continues after the re-synced nonce");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submission_gives_up_after_nonce_resync_budget() -> eyre::Result<()> {
+    let (provider, mock) = Provider::mocked();
+    let client = signer_client(provider);
+    let nonce_manager = NonceManager::new(client.address());
+    let tx = || build_submission_tx(TxType::Legacy, Address::from_low_u64_be(0xba1), Bytes::new(), U256::from(500_000), &gas_info(), U256::from(7), CHAIN_ID);
+
+    // Disabled: the rejection is returned as is
+    mock.push_response(stale_nonce_rejection());
+    let err = submit_sequentially(&Config::default(), &client, &nonce_manager, &mut tx()).await.unwrap_err();
+    assert!(is_nonce_too_low(&format!("{:#}", err)), "{:#}", err);
+
+    // One re-sync allowed: a second rejection ends the attempt
+    let config = Config { nonce_resync_retries: 1, ..Config::default() };
+    mock.push_response(stale_nonce_rejection());
+    mock.push(U256::from(9))?;
+    mock.push_response(stale_nonce_rejection());
+    let mut retried = tx();
+    assert!(submit_sequentially(&config, &client, &nonce_manager, &mut retried).await.is_err());
+    assert_eq!(retried.nonce(), Some(&U256::from(9)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assert_executor_clean_flags_residual_above_dust() -> eyre::Result<()> {
+    let (provider, mock) = Provider::mocked();
+    let client = Arc::new(provider);
+    let usdc = Address::from_low_u64_be(0xcccc);
+    let app_state = AppState::new(Config {
+        arb_executor_address: Some(Address::from_low_u64_be(0xe1)), executor_dust_threshold: U256::from(1_000u64), ..Config::default()
+    });
+
+    // Dust is tolerated
+    mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Uint(U256::from(1_000u64))])))?;
+    assert_eq!(assert_executor_clean(client.clone(), &app_state, usdc).await?, None);
+    assert_eq!(app_state.metrics.executor_residual_detections.load(Ordering::Relaxed), 0);
+
+    // 25 USDC left behind by a broken route
+    mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Uint(U256::from(25_000_000u64))])))?;
+    assert_eq!(assert_executor_clean(client, &app_state, usdc).await?, Some(U256::from(25_000_000u64)));
+    assert_eq!(app_state.metrics.executor_residual_detections.load(Ordering::Relaxed), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_receipt_reported_only_at_required_depth() -> eyre::Result<()> {
+    let (provider, mock) = Provider::mocked();
+    let hash = TxHash::repeat_byte(0x42);
+    let receipt = TransactionReceipt { transaction_hash: hash, block_number: Some(U64::from(100)), status: Some(U64::one()), ..TransactionReceipt::default() };
+
+    mock.push::<Option<TransactionReceipt>, _>(None)?;
+    assert_eq!(receipt_at_depth(&provider, hash, 3).await?, None, "Pending");
+
+    // Responses pop last-pushed first: the receipt, then the head
+    mock.push(U64::from(101))?;
+    mock.push(Some(receipt.clone()))?;
+    assert_eq!(receipt_at_depth(&provider, hash, 3).await?, None, "Two blocks deep is not final at 3 confirmations");
+
+    mock.push(U64::from(102))?;
+    mock.push(Some(receipt.clone()))?;
+    assert_eq!(receipt_at_depth(&provider, hash, 3).await?, Some(receipt.clone()));
+
+    // A single confirmation needs no head lookup
+    mock.push(Some(receipt.clone()))?;
+    assert_eq!(receipt_at_depth(&provider, hash, 1).await?, Some(receipt));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_live_gas_price_flips_marginal_route() -> eyre::Result<()> {
+    let config = Config { max_priority_fee_per_gas_gwei: 0.01, simulation_gas_price_multiplier: 1.5, ..Config::default() };
+    let net_at = |gwei: f64| -> eyre::Result<I256> {
+        let gas_price_wei: U256 = ethers::utils::parse_units(format!("{:.18}", gwei), "gwei")?.into();
+        net_of_gas(&config, 18, I256::exp10(14), U256::from(200_000u64), gas_price_wei)
+    };
+
+    // No live price: charged at the priority fee cap, 0.01 gwei
+    let (provider, _mock) = Provider::mocked();
+    let fallback_gwei = fetch_simulation_gas_price_gwei(&provider, &config).await;
+    assert_eq!(fallback_gwei, 0.01);
+    assert!(net_at(fallback_gwei)? > I256::zero());
+
+    // 1 gwei live, with the 1.5x margin, costs 3e14 wei against 1e14 of gross profit
+    let (provider, mock) = Provider::mocked();
+    mock.push(U256::exp10(9))?;
+    let live_gwei = fetch_simulation_gas_price_gwei(&provider, &config).await;
+    assert!((live_gwei - 1.5).abs() < 1e-9, "{}", live_gwei);
+    assert!(net_at(live_gwei)? < I256::zero());
+    Ok(())
+}
+
+#[test]
+fn test_zero_profit_submission_only_outside_production() {
+    let config = Config { allow_submission_zero_profit: true, ..Config::default() };
+    assert!(config.zero_profit_submission_allowed(31337), "Honored on an Anvil chain id");
+    assert!(config.zero_profit_submission_allowed(1337), "Honored on a Ganache chain id");
+    for mainnet in [1, CHAIN_ID, 8453, 137, 42161] {
+        assert!(!config.zero_profit_submission_allowed(mainnet), "Ignored on chain {}", mainnet);
+    }
+    let production = Config { production: true, ..config.clone() };
+    assert!(!production.zero_profit_submission_allowed(31337), "Ignored with PRODUCTION set");
+    assert!(!Config::default().zero_profit_submission_allowed(31337), "Off unless set");
+}
+
+#[test]
+fn test_realized_profit_decoded_from_executor_event() {
+    let executor = Address::from_low_u64_be(0xe0);
+    let profit_log = |address: Address, profit: I256| Log {
+        address,
+        topics: vec![*PROFIT_REALIZED_TOPIC],
+        data: encode(&[Token::Int(profit.into_raw())]).into(),
+        ..Log::default()
+    };
+    let receipt = |logs: Vec<Log>| TransactionReceipt { logs, ..TransactionReceipt::default() };
+
+    let gain = I256::from(12_345_678_900_000u64);
+    let unrelated = Log { address: executor, topics: vec![TxHash::repeat_byte(0xdd)], data: encode(&[Token::Uint(U256::one())]).into(), ..Log::default() };
+    assert_eq!(realized_profit_from_receipt(&receipt(vec![unrelated, profit_log(executor, gain)]), executor), Some(gain));
+    assert_eq!(realized_profit_from_receipt(&receipt(vec![profit_log(executor, I256::from(-42))]), executor), Some(I256::from(-42)), "Negative int256 round-trips");
+
+    assert_eq!(realized_profit_from_receipt(&receipt(vec![profit_log(Address::from_low_u64_be(0xbad), gain)]), executor), None, "Other emitters ignored");
+    let truncated = Log { data: Bytes::from(vec![0x01; 31]), ..profit_log(executor, gain) };
+    assert_eq!(realized_profit_from_receipt(&receipt(vec![truncated]), executor), None, "Malformed data ignored");
+    assert_eq!(realized_profit_from_receipt(&receipt(vec![]), executor), None, "Pre-2.4.1 executor emits nothing");
+}
+
+#[tokio::test]
+async fn test_realized_gross_profit_prefers_the_event_over_the_balance_delta() {
+    let executor = Address::from_low_u64_be(0xe0);
+    let state = AppState::new(Config { arb_executor_address: Some(executor), ..Config::default() });
+    let (provider, mock) = Provider::mocked();
+    let client = Arc::new(provider);
+    let gain = I256::from(-7);
+    let logged = TransactionReceipt {
+        block_number: Some(U64::from(100)),
+        logs: vec![Log { address: executor, topics: vec![*PROFIT_REALIZED_TOPIC], data: encode(&[Token::Int(gain.into_raw())]).into(), ..Log::default() }],
+        ..TransactionReceipt::default()
+    };
+    assert_eq!(realized_gross_profit(client.clone(), &state, &logged).await.unwrap(), gain, "A loss is reported as one, with no RPC read");
+
+    // Pre-2.4.1 executor: WETH balance after, then before (the mock answers last-pushed first)
+    mock.push::<Bytes, Bytes>(encode(&[Token::Uint(U256::from(1_500u64))]).into()).unwrap();
+    mock.push::<Bytes, Bytes>(encode(&[Token::Uint(U256::from(1_000u64))]).into()).unwrap();
+    let silent = TransactionReceipt { logs: vec![], ..logged };
+    assert_eq!(realized_gross_profit(client, &state, &silent).await.unwrap(), I256::from(500));
+}
+
+#[test]
+fn test_submission_salt_is_deterministic_per_opportunity() {
+    let route = RouteCandidate {
+        token_in: Address::from_low_u64_be(0xe7), token_out: Address::from_low_u64_be(0xc0), source_block: U64::from(100),
+        ..common::route(Address::from_low_u64_be(1), DexType::UniswapV3, Address::from_low_u64_be(2), DexType::VelodromeV2)
+    };
+    let loan = U256::exp10(18);
+    let salt = submission_salt(&route, loan);
+    assert_eq!(salt, submission_salt(&route.clone(), loan), "Re-detection in the same block");
+    assert_ne!(salt, submission_salt(&route, loan * 2));
+    assert_ne!(salt, submission_salt(&RouteCandidate { source_block: U64::from(101), ..route.clone() }, loan));
+    assert_ne!(salt, submission_salt(&RouteCandidate { sell_pool_addr: Address::from_low_u64_be(3), ..route.clone() }, loan));
+
+    // A duplicate submission of the same opportunity is refused
+    let app_state = AppState::new(Config::default());
+    assert!(app_state.claim_salt(salt, U64::from(100)));
+    assert!(!app_state.claim_salt(submission_salt(&route, loan), U64::from(100)));
+}
+
@@ -0,0 +1,163 @@
+// tests/univ3_math_test.rs
+// Offline checks of the UniV3 math port against vectors from the Uniswap v3-core test suite.
+
+use ethers::types::U256;
+use std::collections::BTreeMap;
+use ulp1_5::univ3_math::{
+    compute_swap_step, get_sqrt_ratio_at_tick, quote_exact_input, quote_exact_input_across_ticks,
+    quote_exact_input_within_tick_range, TickLiquidity, TickWindow, MAX_TICK, MIN_TICK,
+};
+use ulp1_5::utils::ToF64Lossy;
+
+fn u(s: &str) -> U256 {
+    U256::from_dec_str(s).unwrap()
+}
+
+#[test]
+fn test_sqrt_ratio_at_tick_matches_tick_math() {
+    assert_eq!(get_sqrt_ratio_at_tick(0).unwrap(), U256::one() << 96);
+    assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK).unwrap(), u("4295128739"));
+    assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK + 1).unwrap(), u("4295343490"));
+    assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK - 1).unwrap(), u("1461373636630004318706518188784493106690254656249"));
+    assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK).unwrap(), u("1461446703485210103287273052203988822378723970342"));
+    assert!(get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+
+    for tick in [-500_000, -50_000, -4_000, -60, -1, 1, 10, 200, 3_000, 50_000, 150_000, 500_000] {
+        let expected = 1.0001f64.powf(tick as f64 / 2.0) * 2f64.powi(96);
+        let actual = get_sqrt_ratio_at_tick(tick).unwrap().to_f64_lossy();
+        assert!(((actual - expected) / expected).abs() < 1e-9, "tick {} -> {} vs {}", tick, actual, expected);
+    }
+}
+
+#[test]
+fn test_compute_swap_step_vectors() {
+    let price_1_1 = U256::one() << 96; // encodePriceSqrt(1, 1)
+    let liquidity = 2_000_000_000_000_000_000u128;
+    let amount = u("1000000000000000000");
+
+    // Exact amount in that gets capped at the price target (one for zero)
+    let target = u("79623317895830914510639640423"); // encodePriceSqrt(101, 100)
+    let step = compute_swap_step(price_1_1, target, liquidity, amount, 600).unwrap();
+    assert_eq!(step.amount_in, u("9975124224178055"));
+    assert_eq!(step.fee_amount, u("5988667735148"));
+    assert_eq!(step.amount_out, u("9925619580021728"));
+    assert_eq!(step.sqrt_price_next_x96, target);
+
+    // Exact amount in that is fully spent (one for zero)
+    let target = u("250541448375047931186413801569"); // encodePriceSqrt(1000, 100)
+    let step = compute_swap_step(price_1_1, target, liquidity, amount, 600).unwrap();
+    assert_eq!(step.amount_in, u("999400000000000000"));
+    assert_eq!(step.fee_amount, u("600000000000000"));
+    assert_eq!(step.amount_out, u("666399946655997866"));
+    assert!(step.sqrt_price_next_x96 < target);
+    assert_eq!(step.amount_in + step.fee_amount, amount);
+
+    // Fees of 100% and above are rejected instead of underflowing
+    assert!(compute_swap_step(price_1_1, target, liquidity, amount, 1_000_000).is_err());
+    assert!(compute_swap_step(price_1_1, target, liquidity, amount, u32::MAX).is_err());
+    assert!(compute_swap_step(price_1_1, target, liquidity, amount, 999_999).is_ok());
+}
+
+#[test]
+fn test_quote_within_tick_range_defers_to_quoter_on_crossing() {
+    // Mid-range price of a 0.05% pool (spacing 10): tick 5 sits between boundaries 0 and 10
+    let sqrt_price = get_sqrt_ratio_at_tick(5).unwrap();
+    let liquidity = 10u128.pow(24);
+
+    let small = quote_exact_input_within_tick_range(sqrt_price, 5, liquidity, 500, None, true, u("1000000000000000")).unwrap();
+    let out = small.expect("Small swap stays within the tick range");
+    assert!(out > U256::zero() && out < u("1000000000000000"), "Price ~1 minus fee");
+
+    let huge = quote_exact_input_within_tick_range(sqrt_price, 5, liquidity, 500, None, true, U256::exp10(27)).unwrap();
+    assert!(huge.is_none(), "Swap reaching the next tick boundary must fall back to the quoter");
+
+    // Unknown fee tier -> unknown spacing -> quoter
+    assert!(quote_exact_input_within_tick_range(sqrt_price, 5, liquidity, 1234, None, true, U256::one()).unwrap().is_none());
+}
+
+#[test]
+fn test_quote_within_tick_range_uses_pool_tick_spacing() {
+    // Tick 5 with spacing 50 (a non-standard tier): the next boundary below is tick 0
+    let sqrt_price = get_sqrt_ratio_at_tick(5).unwrap();
+    let liquidity = 10u128.pow(24);
+    let amount = u("1000000000000000");
+    let with_spacing = quote_exact_input_within_tick_range(sqrt_price, 5, liquidity, 2500, Some(50), true, amount).unwrap();
+    assert!(with_spacing.is_some(), "Known spacing simulates a non-standard fee tier locally");
+
+    // The pool's spacing wins over the fee tier's: with spacing 1 a price just below tick 6 is one step
+    // from a boundary, with the 0.05% tier's spacing of 10 it is not
+    let on_boundary = get_sqrt_ratio_at_tick(6).unwrap() - U256::one();
+    assert!(quote_exact_input_within_tick_range(on_boundary, 5, liquidity, 500, Some(1), false, amount).unwrap().is_none());
+    assert!(quote_exact_input_within_tick_range(on_boundary, 5, liquidity, 500, None, false, amount).unwrap().is_some());
+}
+
+/// Words -1 and 0 of a spacing-10 pool, with initialized ticks 0 and 10 whose net liquidity is `net`.
+fn window(net: i128) -> TickWindow {
+    let tick = |liquidity_net: i128| TickLiquidity { liquidity_gross: net.unsigned_abs(), liquidity_net };
+    TickWindow { tick_spacing: 10, word_lo: -1, word_hi: 0, ticks: BTreeMap::from([(0, tick(net)), (10, tick(-net))]) }
+}
+
+#[test]
+fn test_quote_across_ticks_matches_stepwise_crossing() {
+    // Tick 5 inside a position [0, 10) holding 1e18 of the pool's 2e18 liquidity
+    let sqrt_price = get_sqrt_ratio_at_tick(5).unwrap();
+    let (liquidity, net) = (2 * 10u128.pow(18), 10i128.pow(18));
+    let window = window(net);
+    let amount = u("10000000000000000");
+    assert!(quote_exact_input_within_tick_range(sqrt_price, 5, liquidity, 500, Some(10), true, amount).unwrap().is_none());
+
+    // zeroForOne: down to tick 0, drop the position's liquidity, then on to the start of word -1
+    let first = compute_swap_step(sqrt_price, get_sqrt_ratio_at_tick(0).unwrap(), liquidity, amount, 500).unwrap();
+    let remaining = amount - first.amount_in - first.fee_amount;
+    let second = compute_swap_step(first.sqrt_price_next_x96, get_sqrt_ratio_at_tick(-2560).unwrap(), liquidity - net as u128, remaining, 500).unwrap();
+    assert!(second.sqrt_price_next_x96 > get_sqrt_ratio_at_tick(-2560).unwrap(), "Swap ends inside word -1");
+    let crossed = quote_exact_input_across_ticks(sqrt_price, 5, liquidity, 500, &window, true, amount).unwrap();
+    assert_eq!(crossed, Some(first.amount_out + second.amount_out));
+    assert_eq!(quote_exact_input(sqrt_price, 5, liquidity, 500, Some(10), Some(&window), true, amount).unwrap(), crossed);
+
+    // oneForZero: up to tick 10, where the position's liquidity leaves, then on to the end of word 0
+    let first = compute_swap_step(sqrt_price, get_sqrt_ratio_at_tick(10).unwrap(), liquidity, amount, 500).unwrap();
+    let remaining = amount - first.amount_in - first.fee_amount;
+    let second = compute_swap_step(first.sqrt_price_next_x96, get_sqrt_ratio_at_tick(2550).unwrap(), liquidity - net as u128, remaining, 500).unwrap();
+    let crossed = quote_exact_input_across_ticks(sqrt_price, 5, liquidity, 500, &window, false, amount).unwrap();
+    assert_eq!(crossed, Some(first.amount_out + second.amount_out));
+}
+
+#[test]
+fn test_quote_across_ticks_defers_beyond_window() {
+    let sqrt_price = get_sqrt_ratio_at_tick(5).unwrap();
+    let (liquidity, net) = (2 * 10u128.pow(18), 10i128.pow(18));
+    // Enough to push the price past tick -2560 into word -2, which was not read
+    let amount = U256::exp10(21);
+    assert!(quote_exact_input_across_ticks(sqrt_price, 5, liquidity, 500, &window(net), true, amount).unwrap().is_none());
+    // ... and past the end of word 0 upwards
+    assert!(quote_exact_input_across_ticks(sqrt_price, 5, liquidity, 500, &window(net), false, amount).unwrap().is_none());
+    // No window: the within-range quote decides
+    assert!(quote_exact_input(sqrt_price, 5, liquidity, 500, Some(10), None, true, u("1000")).unwrap().is_some());
+}
+
+#[test]
+fn test_tick_window_tracks_position_changes() {
+    let mut window = TickWindow { tick_spacing: 10, word_lo: -1, word_hi: 0, ticks: BTreeMap::new() };
+    assert_eq!(window.next_initialized_tick_within_one_word(5, true), Some((0, false)));
+    assert_eq!(window.next_initialized_tick_within_one_word(5, false), Some((2550, false)));
+    assert_eq!(window.next_initialized_tick_within_one_word(-1, true), Some((-2560, false)));
+    assert_eq!(window.next_initialized_tick_within_one_word(-2561, true), None, "Word -2 was not read");
+    assert_eq!(window.next_initialized_tick_within_one_word(2550, false), None, "Word 1 was not read");
+
+    // Mint [-100, 200) and [200, 5000): the upper tick of the second lies in word 1 and stays unknown
+    window.apply_position_change(-100, 200, 5);
+    window.apply_position_change(200, 5000, 3);
+    assert_eq!(window.ticks.get(&-100), Some(&TickLiquidity { liquidity_gross: 5, liquidity_net: 5 }));
+    assert_eq!(window.ticks.get(&200), Some(&TickLiquidity { liquidity_gross: 8, liquidity_net: -2 }));
+    assert!(!window.ticks.contains_key(&5000));
+    // Like TickBitmap, the search stops at the word boundary: -100 is only found from word -1
+    assert_eq!(window.next_initialized_tick_within_one_word(5, true), Some((0, false)));
+    assert_eq!(window.next_initialized_tick_within_one_word(-1, true), Some((-100, true)));
+    assert_eq!(window.next_initialized_tick_within_one_word(5, false), Some((200, true)));
+
+    // Burning a position uninitializes the ticks no other position references
+    window.apply_position_change(-100, 200, -5);
+    assert!(!window.ticks.contains_key(&-100));
+    assert_eq!(window.ticks.get(&200), Some(&TickLiquidity { liquidity_gross: 3, liquidity_net: 3 }));
+}
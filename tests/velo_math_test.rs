@@ -0,0 +1,59 @@
+// tests/velo_math_test.rs
+// Offline checks of the Velodrome V2 pool math port. The vectors come from an independent
+// integer port of Pool.sol `getAmountOut`; the on-chain comparison lives in integration_test.rs.
+
+use ethers::types::U256;
+use ulp1_5::velo_math::{get_amount_out, k, VeloPoolReserves};
+
+fn units(amount: u64, decimals: usize) -> U256 {
+    U256::from(amount) * U256::exp10(decimals)
+}
+
+#[test]
+fn test_volatile_pool_is_constant_product_after_fee() {
+    let pool = VeloPoolReserves::new(units(100, 18), units(200_000, 6), 18, 6, false);
+    let out = get_amount_out(&pool, units(1, 18), true, 30).unwrap();
+    assert_eq!(out, U256::from(1_974_316_068u64));
+
+    // amountIn * reserveOut / (reserveIn + amountIn) with the fee taken off the input.
+    let net_in = units(1, 18) - units(1, 18) * 30 / 10_000;
+    assert_eq!(out, net_in * pool.reserve1 / (pool.reserve0 + net_in));
+}
+
+#[test]
+fn test_stable_pool_matches_reference_vectors() {
+    let usdc_pair = VeloPoolReserves::new(units(5_000_000, 6), units(4_800_000, 6), 6, 6, true);
+    assert_eq!(get_amount_out(&usdc_pair, units(10_000, 6), true, 5).unwrap(), U256::from(9_994_802_876u64));
+    assert_eq!(get_amount_out(&usdc_pair, units(10_000, 6), false, 5).unwrap(), U256::from(9_995_146_094u64));
+
+    // Mixed decimals (18-decimal token0, 6-decimal token1).
+    let mixed = VeloPoolReserves::new(units(2_000_000, 18), units(2_100_000, 6), 18, 6, true);
+    assert_eq!(get_amount_out(&mixed, units(50_000, 18), true, 5).unwrap(), U256::from(49_975_362_734u64));
+    assert_eq!(get_amount_out(&mixed, units(50_000, 6), false, 5).unwrap(), U256::from_dec_str("49969565430520808353262").unwrap());
+}
+
+#[test]
+fn test_stable_swap_preserves_invariant_and_beats_constant_product() {
+    let stable = VeloPoolReserves::new(units(1_000_000, 18), units(1_000_000, 18), 18, 18, true);
+    let volatile = VeloPoolReserves { stable: false, ..stable };
+    let amount_in = units(20_000, 18);
+
+    let out = get_amount_out(&stable, amount_in, true, 0).unwrap();
+    let k_before = k(&stable, stable.reserve0, stable.reserve1).unwrap();
+    let k_after = k(&stable, stable.reserve0 + amount_in, stable.reserve1 - out).unwrap();
+    assert!(k_after >= k_before, "swap must not decrease the invariant");
+
+    // Near the peg the stable curve is much flatter than x*y.
+    assert!(out < amount_in);
+    assert!(out > get_amount_out(&volatile, amount_in, true, 0).unwrap());
+    assert!(amount_in - out < amount_in / 1_000, "balanced stable swap slipped more than 0.1%");
+}
+
+#[test]
+fn test_empty_pool_and_oversized_fee_do_not_panic() {
+    let pool = VeloPoolReserves::new(U256::zero(), U256::zero(), 18, 18, true);
+    assert_eq!(get_amount_out(&pool, units(1, 18), true, 5).unwrap(), U256::zero());
+    let pool = VeloPoolReserves::new(units(1, 18), units(1, 18), 18, 18, false);
+    assert!(get_amount_out(&pool, units(1, 18), true, 20_000).is_err());
+}
+// END OF FILE: tests/velo_math_test.rs
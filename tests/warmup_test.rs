@@ -0,0 +1,32 @@
+// tests/warmup_test.rs
+// Offline tests for the startup submission warmup.
+
+use std::time::{Duration, Instant};
+use ulp1_5::warmup::{SubmissionWarmup, WarmupPending};
+
+const WARMUP: Duration = Duration::from_secs(30);
+
+#[test]
+fn test_submissions_suppressed_during_warmup_then_enabled() {
+    let start = Instant::now();
+    let warmup = SubmissionWarmup::new(start);
+
+    assert_eq!(warmup.check(start + Duration::from_secs(5), 10, WARMUP, 3), Err(WarmupPending::Time(Duration::from_secs(25))));
+    assert_eq!(warmup.check(start + WARMUP, 2, WARMUP, 3), Err(WarmupPending::Pools { loaded: 2, required: 3 }), "Elapsed but too few pools");
+    assert_eq!(warmup.check(start + WARMUP, 3, WARMUP, 3), Ok(()));
+    assert_eq!(warmup.check(start + WARMUP, 0, WARMUP, 3), Ok(()), "Stays enabled once complete");
+}
+
+#[test]
+fn test_zero_settings_disable_warmup() {
+    let start = Instant::now();
+    assert_eq!(SubmissionWarmup::new(start).check(start, 0, Duration::ZERO, 0), Ok(()));
+    assert_eq!(SubmissionWarmup::new(start).check(start, 0, Duration::ZERO, 1), Err(WarmupPending::Pools { loaded: 0, required: 1 }));
+}
+
+#[test]
+fn test_warmup_reason_for_audit() {
+    assert_eq!(WarmupPending::Time(Duration::from_millis(12_900)).to_string(), "warmup (12s left)");
+    assert_eq!(WarmupPending::Pools { loaded: 1, required: 4 }.to_string(), "warmup (1 of 4 pools loaded)");
+}
+// END OF FILE: tests/warmup_test.rs